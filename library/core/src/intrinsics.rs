@@ -945,6 +945,7 @@ extern "rust-intrinsic" {
     /// own, or if it does not enable any significant optimizations.
     ///
     /// This intrinsic does not have a stable counterpart.
+    #[cfg_attr(not(bootstrap), lang = "assume")]
     pub fn assume(b: bool);
 
     /// Hints to the compiler that branch condition is likely to be true.
@@ -1961,6 +1962,13 @@ extern "rust-intrinsic" {
         end_byte_pos: u32,
     );
 
+    /// Internal placeholder for the profiling hook called at the entry of a function when the
+    /// "instrument-mcount" option is enabled. Code generation replaces it with a call to the
+    /// target's `mcount` function.
+    #[cfg(not(bootstrap))]
+    #[lang = "instrument_function_entry"]
+    pub fn instrument_function_entry();
+
     /// Internal marker for code coverage expressions, injected into the MIR when the
     /// "instrument-coverage" option is enabled. This intrinsic is not converted into a
     /// backend intrinsic call, but its arguments are extracted during the production of a
@@ -2002,6 +2010,32 @@ extern "rust-intrinsic" {
     /// See documentation of `<*const T>::guaranteed_ne` for details.
     #[rustc_const_unstable(feature = "const_raw_ptr_comparison", issue = "53020")]
     pub fn ptr_guaranteed_ne<T>(ptr: *const T, other: *const T) -> bool;
+
+    /// Allocates a block of memory at compile time.
+    /// At runtime, just returns a null pointer.
+    ///
+    /// # Safety
+    ///
+    /// - The `align` argument must be a power of two.
+    ///    - At compile time, a compile error occurs if this constraint is violated.
+    ///    - At runtime, it is not checked.
+    ///
+    /// The memory can only end up in the final value of a constant or static behind a
+    /// reference, which makes it immutable.
+    #[rustc_const_unstable(feature = "const_heap", issue = "none")]
+    pub fn const_allocate(size: usize, align: usize) -> *mut u8;
+
+    /// Deallocates memory that was allocated by `intrinsics::const_allocate` at compile time.
+    /// At runtime, does nothing.
+    ///
+    /// # Safety
+    ///
+    /// - `ptr` must have been returned by `intrinsics::const_allocate` for the same `size` and
+    ///   `align`.
+    ///    - At compile time, a compile error occurs if this constraint is violated.
+    ///    - At runtime, it is not checked.
+    #[rustc_const_unstable(feature = "const_heap", issue = "none")]
+    pub fn const_deallocate(ptr: *mut u8, size: usize, align: usize);
 }
 
 // Some functions are defined here because they accidentally got made
@@ -2115,6 +2149,7 @@ pub(crate) fn is_nonoverlapping<T>(src: *const T, dst: *const T, count: usize) -
 #[inline]
 pub unsafe fn copy_nonoverlapping<T>(src: *const T, dst: *mut T, count: usize) {
     extern "rust-intrinsic" {
+        #[cfg_attr(not(bootstrap), lang = "copy_nonoverlapping")]
         fn copy_nonoverlapping<T>(src: *const T, dst: *mut T, count: usize);
     }
 
@@ -2276,6 +2311,7 @@ pub unsafe fn copy<T>(src: *const T, dst: *mut T, count: usize) {
 #[inline]
 pub unsafe fn write_bytes<T>(dst: *mut T, val: u8, count: usize) {
     extern "rust-intrinsic" {
+        #[cfg_attr(not(bootstrap), lang = "write_bytes")]
         fn write_bytes<T>(dst: *mut T, val: u8, count: usize);
     }
 
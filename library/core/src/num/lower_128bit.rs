@@ -0,0 +1,189 @@
+//! Targets of the MIR `Lower128Bit` pass.
+//!
+//! When a target sets `i128_lowering` (or `-Z lower-128bit-ops` is passed), the compiler
+//! replaces 128-bit arithmetic, comparison and checked operations with calls to the lang items
+//! below, so that codegen backends without native 128-bit integers only have to deal with a
+//! handful of functions. The arithmetic is forwarded to the routines compiler-builtins already
+//! provides for exactly this purpose.
+//!
+//! The compiler never lowers the bodies of these functions, but they must still not use the
+//! operators on 128-bit integers directly, since that is what a backend can't handle.
+
+#![allow(improper_ctypes)]
+
+use crate::mem;
+
+extern "C" {
+    fn __rust_i128_add(a: i128, b: i128) -> i128;
+    fn __rust_u128_add(a: u128, b: u128) -> u128;
+    fn __rust_i128_sub(a: i128, b: i128) -> i128;
+    fn __rust_u128_sub(a: u128, b: u128) -> u128;
+    fn __multi3(a: i128, b: i128) -> i128;
+    fn __divti3(a: i128, b: i128) -> i128;
+    fn __udivti3(a: u128, b: u128) -> u128;
+    fn __modti3(a: i128, b: i128) -> i128;
+    fn __umodti3(a: u128, b: u128) -> u128;
+    fn __ashlti3(a: u128, b: u32) -> u128;
+    fn __ashrti3(a: i128, b: u32) -> i128;
+    fn __lshrti3(a: u128, b: u32) -> u128;
+    fn __rust_i128_addo(a: i128, b: i128) -> (i128, bool);
+    fn __rust_u128_addo(a: u128, b: u128) -> (u128, bool);
+    fn __rust_i128_subo(a: i128, b: i128) -> (i128, bool);
+    fn __rust_u128_subo(a: u128, b: u128) -> (u128, bool);
+    fn __rust_i128_mulo(a: i128, b: i128) -> (i128, bool);
+    fn __rust_u128_mulo(a: u128, b: u128) -> (u128, bool);
+}
+
+// SAFETY (for all the functions below): the compiler-builtins routines are plain arithmetic
+// without preconditions beyond the ones MIR already checked (division by zero and overflow are
+// guarded by `Assert` terminators before the operator is reached).
+
+#[lang = "i128_add"]
+fn i128_add(a: i128, b: i128) -> i128 {
+    unsafe { __rust_i128_add(a, b) }
+}
+
+#[lang = "u128_add"]
+fn u128_add(a: u128, b: u128) -> u128 {
+    unsafe { __rust_u128_add(a, b) }
+}
+
+#[lang = "i128_sub"]
+fn i128_sub(a: i128, b: i128) -> i128 {
+    unsafe { __rust_i128_sub(a, b) }
+}
+
+#[lang = "u128_sub"]
+fn u128_sub(a: u128, b: u128) -> u128 {
+    unsafe { __rust_u128_sub(a, b) }
+}
+
+#[lang = "i128_mul"]
+fn i128_mul(a: i128, b: i128) -> i128 {
+    unsafe { __multi3(a, b) }
+}
+
+#[lang = "u128_mul"]
+fn u128_mul(a: u128, b: u128) -> u128 {
+    // The low 128 bits of a product do not depend on signedness.
+    unsafe { __multi3(a as i128, b as i128) as u128 }
+}
+
+#[lang = "i128_div"]
+fn i128_div(a: i128, b: i128) -> i128 {
+    unsafe { __divti3(a, b) }
+}
+
+#[lang = "u128_div"]
+fn u128_div(a: u128, b: u128) -> u128 {
+    unsafe { __udivti3(a, b) }
+}
+
+#[lang = "i128_rem"]
+fn i128_rem(a: i128, b: i128) -> i128 {
+    unsafe { __modti3(a, b) }
+}
+
+#[lang = "u128_rem"]
+fn u128_rem(a: u128, b: u128) -> u128 {
+    unsafe { __umodti3(a, b) }
+}
+
+// Unchecked shifts behave like the backend's shift with the amount masked to the bit width.
+
+#[lang = "i128_shl"]
+fn i128_shl(a: i128, b: u32) -> i128 {
+    unsafe { __ashlti3(a as u128, b & 127) as i128 }
+}
+
+#[lang = "u128_shl"]
+fn u128_shl(a: u128, b: u32) -> u128 {
+    unsafe { __ashlti3(a, b & 127) }
+}
+
+#[lang = "i128_shr"]
+fn i128_shr(a: i128, b: u32) -> i128 {
+    unsafe { __ashrti3(a, b & 127) }
+}
+
+#[lang = "u128_shr"]
+fn u128_shr(a: u128, b: u32) -> u128 {
+    unsafe { __lshrti3(a, b & 127) }
+}
+
+#[lang = "i128_addo"]
+fn i128_addo(a: i128, b: i128) -> (i128, bool) {
+    unsafe { __rust_i128_addo(a, b) }
+}
+
+#[lang = "u128_addo"]
+fn u128_addo(a: u128, b: u128) -> (u128, bool) {
+    unsafe { __rust_u128_addo(a, b) }
+}
+
+#[lang = "i128_subo"]
+fn i128_subo(a: i128, b: i128) -> (i128, bool) {
+    unsafe { __rust_i128_subo(a, b) }
+}
+
+#[lang = "u128_subo"]
+fn u128_subo(a: u128, b: u128) -> (u128, bool) {
+    unsafe { __rust_u128_subo(a, b) }
+}
+
+#[lang = "i128_mulo"]
+fn i128_mulo(a: i128, b: i128) -> (i128, bool) {
+    unsafe { __rust_i128_mulo(a, b) }
+}
+
+#[lang = "u128_mulo"]
+fn u128_mulo(a: u128, b: u128) -> (u128, bool) {
+    unsafe { __rust_u128_mulo(a, b) }
+}
+
+// Checked shifts get the whole shift amount (sign-extended to 128 bits for signed amounts, which
+// makes negative amounts overflow as they should).
+
+#[lang = "i128_shlo"]
+fn i128_shlo(a: i128, b: u128) -> (i128, bool) {
+    let (b_high, b_low) = halves(b);
+    (i128_shl(a, b_low as u32), b_high != 0 || b_low >= 128)
+}
+
+#[lang = "u128_shlo"]
+fn u128_shlo(a: u128, b: u128) -> (u128, bool) {
+    let (b_high, b_low) = halves(b);
+    (u128_shl(a, b_low as u32), b_high != 0 || b_low >= 128)
+}
+
+#[lang = "i128_shro"]
+fn i128_shro(a: i128, b: u128) -> (i128, bool) {
+    let (b_high, b_low) = halves(b);
+    (i128_shr(a, b_low as u32), b_high != 0 || b_low >= 128)
+}
+
+#[lang = "u128_shro"]
+fn u128_shro(a: u128, b: u128) -> (u128, bool) {
+    let (b_high, b_low) = halves(b);
+    (u128_shr(a, b_low as u32), b_high != 0 || b_low >= 128)
+}
+
+#[lang = "i128_cmp"]
+fn i128_cmp(a: i128, b: i128) -> i8 {
+    let (a_high, a_low) = halves(a as u128);
+    let (b_high, b_low) = halves(b as u128);
+    (a_high as i64, a_low).cmp(&(b_high as i64, b_low)) as i8
+}
+
+#[lang = "u128_cmp"]
+fn u128_cmp(a: u128, b: u128) -> i8 {
+    halves(a).cmp(&halves(b)) as i8
+}
+
+/// Splits `a` into its high and low halves without any 128-bit arithmetic.
+#[inline]
+fn halves(a: u128) -> (u64, u64) {
+    // SAFETY: `u128` and `[u64; 2]` have the same size and no invalid bit patterns.
+    let [first, second]: [u64; 2] = unsafe { mem::transmute(a) };
+    if cfg!(target_endian = "little") { (second, first) } else { (first, second) }
+}
@@ -291,6 +291,9 @@ pub mod flt2dec;
 
 mod wrapping;
 
+#[cfg(not(bootstrap))]
+mod lower_128bit;
+
 macro_rules! usize_isize_to_xe_bytes_doc {
     () => {
         "
@@ -1,3 +1,14 @@
+//! Besides the `compiler-rt` profiler, this crate provides the runtime of `-Z profile`.
+//!
+//! The `InstrumentBlockCounters` MIR pass makes every basic block of an instrumented function
+//! call `profile_count_block` with the file the counts of its crate go to, the name of the
+//! function, a hash of that name and the index of the block. The counters live in a fixed-size
+//! table, so this works the same with every codegen backend. The first call registers `flush`
+//! with `atexit`, which merges the counters into the files: each file has one
+//! `<function> <block> <count>` line per executed block, and the counts of the lines already in
+//! the file are added to. A generic function instantiated in several crates can get several lines
+//! for the same block, which are meant to be added up.
+
 #![no_std]
 #![feature(profiler_runtime)]
 #![profiler_runtime]
@@ -7,5 +18,331 @@
     issue = "none"
 )]
 #![allow(unused_features)]
+#![feature(const_in_array_repeat_expressions)]
+#![feature(lang_items)]
 #![feature(nll)]
 #![feature(staged_api)]
+
+use core::ffi::c_void;
+use core::fmt::{self, Write};
+use core::sync::atomic::{self, AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use core::{cmp, ptr, slice, str};
+
+const TABLE_SIZE: usize = 1 << 14;
+
+struct Counter {
+    /// `0` while the slot is free.
+    key: AtomicU64,
+    /// Set once the fields below are written by the thread that claimed the slot.
+    ready: AtomicBool,
+    path: AtomicUsize,
+    path_len: AtomicUsize,
+    function: AtomicUsize,
+    function_len: AtomicUsize,
+    block: AtomicU32,
+    count: AtomicU64,
+    /// Set once the count is written to its file.
+    flushed: AtomicBool,
+}
+
+const EMPTY: Counter = Counter {
+    key: AtomicU64::new(0),
+    ready: AtomicBool::new(false),
+    path: AtomicUsize::new(0),
+    path_len: AtomicUsize::new(0),
+    function: AtomicUsize::new(0),
+    function_len: AtomicUsize::new(0),
+    block: AtomicU32::new(0),
+    count: AtomicU64::new(0),
+    flushed: AtomicBool::new(false),
+};
+
+static COUNTERS: [Counter; TABLE_SIZE] = [EMPTY; TABLE_SIZE];
+
+/// Executions of blocks that didn't fit into the table anymore.
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Whether `flush` is registered with `atexit`.
+static REGISTERED: AtomicBool = AtomicBool::new(false);
+
+impl Counter {
+    fn claim(&self, path: &'static str, function: &'static str, block: u32) {
+        self.path.store(path.as_ptr() as usize, Ordering::Relaxed);
+        self.path_len.store(path.len(), Ordering::Relaxed);
+        self.function.store(function.as_ptr() as usize, Ordering::Relaxed);
+        self.function_len.store(function.len(), Ordering::Relaxed);
+        self.block.store(block, Ordering::Relaxed);
+        self.ready.store(true, Ordering::Release);
+    }
+
+    fn path(&self) -> &'static str {
+        load_str(&self.path, &self.path_len)
+    }
+
+    fn function(&self) -> &'static str {
+        load_str(&self.function, &self.function_len)
+    }
+
+    /// Whether this claimed slot counts `block` of `function`. The key only selects the slot, so
+    /// different blocks can have the same one.
+    fn counts(&self, path: &str, function: &str, block: u32) -> bool {
+        while !self.ready.load(Ordering::Acquire) {
+            atomic::spin_loop_hint();
+        }
+        self.block.load(Ordering::Relaxed) == block
+            && same_str(self.function(), function)
+            && same_str(self.path(), path)
+    }
+}
+
+fn load_str(ptr: &AtomicUsize, len: &AtomicUsize) -> &'static str {
+    let ptr = ptr.load(Ordering::Relaxed) as *const u8;
+    let len = len.load(Ordering::Relaxed);
+    // SAFETY: the pointer and length were taken from a `&'static str` before `ready` was set.
+    unsafe { str::from_utf8_unchecked(slice::from_raw_parts(ptr, len)) }
+}
+
+fn same_str(a: &str, b: &str) -> bool {
+    // Every call from a block passes the same constants, so comparing addresses is usually enough.
+    (a.as_ptr() == b.as_ptr() && a.len() == b.len()) || a == b
+}
+
+/// The 64-bit FNV-1a hash of a function name, as computed by the compiler.
+fn hash_function_name(function: &str) -> u64 {
+    function.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn key(function_hash: u64, block: u32) -> u64 {
+    // Spread the blocks of a function over the table.
+    let key = function_hash ^ (block as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    if key == 0 { 1 } else { key }
+}
+
+fn start(key: u64) -> usize {
+    (key >> 16) as usize % TABLE_SIZE
+}
+
+#[lang = "profile_count_block"]
+pub fn profile_count_block(
+    path: &'static str,
+    function: &'static str,
+    function_hash: u64,
+    block: u32,
+) {
+    if !REGISTERED.load(Ordering::Relaxed) && !REGISTERED.swap(true, Ordering::Relaxed) {
+        // SAFETY: `flush` only touches the table and the files, which stay valid until exit.
+        unsafe {
+            atexit(flush);
+        }
+    }
+
+    let key = key(function_hash, block);
+    let start = start(key);
+    for i in 0..TABLE_SIZE {
+        let counter = &COUNTERS[(start + i) % TABLE_SIZE];
+        match counter.key.compare_exchange(0, key, Ordering::Relaxed, Ordering::Relaxed) {
+            Ok(_) => counter.claim(path, function, block),
+            Err(existing) if existing == key && counter.counts(path, function, block) => {}
+            Err(_) => continue,
+        }
+        counter.count.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+    DROPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Looks up the counter of `block` of `function` among those going to `path`.
+fn find(path: &str, function: &str, block: u32) -> Option<&'static Counter> {
+    let key = key(hash_function_name(function), block);
+    let start = start(key);
+    for i in 0..TABLE_SIZE {
+        let counter = &COUNTERS[(start + i) % TABLE_SIZE];
+        match counter.key.load(Ordering::Relaxed) {
+            // Slots are never freed, so the counter would have been claimed before this one.
+            0 => return None,
+            existing if existing == key && counter.counts(path, function, block) => {
+                return Some(counter);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+extern "C" {
+    fn atexit(callback: extern "C" fn()) -> i32;
+    fn fopen(path: *const u8, mode: *const u8) -> *mut c_void;
+    fn fread(ptr: *mut c_void, size: usize, count: usize, file: *mut c_void) -> usize;
+    fn fwrite(ptr: *const c_void, size: usize, count: usize, file: *mut c_void) -> usize;
+    fn fclose(file: *mut c_void) -> i32;
+    fn realloc(ptr: *mut c_void, size: usize) -> *mut c_void;
+    fn free(ptr: *mut c_void);
+}
+
+struct File(*mut c_void);
+
+impl File {
+    fn write_bytes(&mut self, bytes: &[u8]) -> fmt::Result {
+        // SAFETY: `self.0` is an open file and `bytes` is valid for `bytes.len()` bytes.
+        let written = unsafe { fwrite(bytes.as_ptr().cast(), 1, bytes.len(), self.0) };
+        if written == bytes.len() { Ok(()) } else { Err(fmt::Error) }
+    }
+}
+
+impl Write for File {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_bytes(s.as_bytes())
+    }
+}
+
+impl Drop for File {
+    fn drop(&mut self) {
+        // SAFETY: `self.0` is an open file, which is not used afterwards.
+        unsafe {
+            fclose(self.0);
+        }
+    }
+}
+
+/// The contents of a file, allocated with `realloc`.
+struct Buffer {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl Buffer {
+    fn bytes(&self) -> &[u8] {
+        if self.ptr.is_null() {
+            return &[];
+        }
+        // SAFETY: the first `len` bytes of the allocation were written by `fread`.
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` is null or was allocated with `realloc`.
+        unsafe {
+            free(self.ptr.cast());
+        }
+    }
+}
+
+/// Reads the NUL-terminated `path`, which is empty if it can't be opened. Returns `None` if the
+/// contents don't fit into memory, in which case the file must not be overwritten.
+fn read_file(path: &str) -> Option<Buffer> {
+    let mut buffer = Buffer { ptr: ptr::null_mut(), len: 0 };
+    // SAFETY: both strings are NUL-terminated.
+    let file = unsafe { fopen(path.as_ptr(), b"rb\0".as_ptr()) };
+    if file.is_null() {
+        return Some(buffer);
+    }
+
+    let file = File(file);
+    let mut capacity = 0;
+    loop {
+        if buffer.len == capacity {
+            capacity = cmp::max(capacity * 2, 4096);
+            // SAFETY: `buffer.ptr` is null or was allocated with `realloc`.
+            let ptr = unsafe { realloc(buffer.ptr.cast(), capacity) };
+            if ptr.is_null() {
+                return None;
+            }
+            buffer.ptr = ptr.cast();
+        }
+        // SAFETY: the allocation has room for `capacity` bytes, `file.0` is open.
+        let read =
+            unsafe { fread(buffer.ptr.add(buffer.len).cast(), 1, capacity - buffer.len, file.0) };
+        if read == 0 {
+            return Some(buffer);
+        }
+        buffer.len += read;
+    }
+}
+
+/// Splits a `<function> <block> <count>` line. Function names can contain spaces.
+fn parse_line(line: &str) -> Option<(&str, u32, u64)> {
+    let mut fields = line.rsplitn(3, ' ');
+    let count = fields.next()?.parse().ok()?;
+    let block = fields.next()?.parse().ok()?;
+    let function = fields.next()?;
+    Some((function, block, count))
+}
+
+/// Merges the counters into their files, when the process exits.
+extern "C" fn flush() {
+    for counter in COUNTERS.iter() {
+        if counter.key.load(Ordering::Relaxed) != 0
+            && counter.ready.load(Ordering::Acquire)
+            && !counter.flushed.load(Ordering::Relaxed)
+        {
+            let _ = flush_file(counter.path());
+        }
+    }
+}
+
+/// Merges the counters going to the NUL-terminated `path` into it, and marks them as flushed.
+fn flush_file(path: &'static str) -> fmt::Result {
+    let old = read_file(path);
+    // SAFETY: both strings are NUL-terminated.
+    let file = match old {
+        Some(_) => unsafe { fopen(path.as_ptr(), b"wb\0".as_ptr()) },
+        None => ptr::null_mut(),
+    };
+    if file.is_null() {
+        // Don't try again for every other counter of the file.
+        for counter in COUNTERS.iter() {
+            if counter.ready.load(Ordering::Acquire) && same_str(counter.path(), path) {
+                counter.flushed.store(true, Ordering::Relaxed);
+            }
+        }
+        return Err(fmt::Error);
+    }
+
+    let mut file = File(file);
+    let old = old.as_ref().map_or(&[][..], Buffer::bytes);
+    for line in old.split(|&byte| byte == b'\n').filter(|line| !line.is_empty()) {
+        // The number of dropped executions is only about the last run.
+        if line[0] == b'#' {
+            continue;
+        }
+        let parsed = str::from_utf8(line).ok().and_then(parse_line);
+        let (function, block, count) = match parsed {
+            Some(parsed) => parsed,
+            None => {
+                file.write_bytes(line)?;
+                file.write_bytes(b"\n")?;
+                continue;
+            }
+        };
+        let count = match find(path, function, block) {
+            Some(counter) if !counter.flushed.swap(true, Ordering::Relaxed) => {
+                count.wrapping_add(counter.count.load(Ordering::Relaxed))
+            }
+            _ => count,
+        };
+        writeln!(file, "{} {} {}", function, block, count)?;
+    }
+
+    for counter in COUNTERS.iter() {
+        if counter.key.load(Ordering::Relaxed) == 0
+            || !counter.ready.load(Ordering::Acquire)
+            || !same_str(counter.path(), path)
+            || counter.flushed.swap(true, Ordering::Relaxed)
+        {
+            continue;
+        }
+        let block = counter.block.load(Ordering::Relaxed);
+        let count = counter.count.load(Ordering::Relaxed);
+        writeln!(file, "{} {} {}", counter.function(), block, count)?;
+    }
+
+    let dropped = DROPPED.load(Ordering::Relaxed);
+    if dropped != 0 {
+        writeln!(file, "# {} block executions did not fit into the table", dropped)?;
+    }
+    Ok(())
+}
@@ -1893,6 +1893,7 @@ bitflags::bitflags! {
         const NORETURN = 1 << 4;
         const NOSTACK = 1 << 5;
         const ATT_SYNTAX = 1 << 6;
+        const MAY_UNWIND = 1 << 7;
     }
 }
 
@@ -1968,6 +1969,9 @@ pub enum InlineAsmOperand {
     Sym {
         expr: P<Expr>,
     },
+    Label {
+        block: P<Block>,
+    },
 }
 
 /// Inline assembly.
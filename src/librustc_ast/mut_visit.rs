@@ -1227,6 +1227,7 @@ pub fn noop_visit_expr<T: MutVisitor>(
                             vis.visit_expr(out_expr);
                         }
                     }
+                    InlineAsmOperand::Label { block } => vis.visit_block(block),
                 }
             }
         }
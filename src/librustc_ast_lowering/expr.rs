@@ -1061,6 +1061,9 @@ impl<'hir> LoweringContext<'_, 'hir> {
                     InlineAsmOperand::Sym { ref expr } => {
                         hir::InlineAsmOperand::Sym { expr: self.lower_expr_mut(expr) }
                     }
+                    InlineAsmOperand::Label { ref block } => {
+                        hir::InlineAsmOperand::Label { block: self.lower_block(block, false) }
+                    }
                 };
                 Some(op)
             })
@@ -1133,6 +1136,15 @@ impl<'hir> LoweringContext<'_, 'hir> {
                         err.span_label(op_sp, "argument");
                         err.emit();
                     }
+                    hir::InlineAsmOperand::Label { .. } => {
+                        let mut err = sess.struct_span_err(
+                            placeholder_span,
+                            "asm template modifiers are not allowed for `label` arguments",
+                        );
+                        err.span_label(placeholder_span, "template modifier");
+                        err.span_label(op_sp, "argument");
+                        err.emit();
+                    }
                 }
             }
         }
@@ -1190,9 +1202,9 @@ impl<'hir> LoweringContext<'_, 'hir> {
                         hir::InlineAsmOperand::Out { late, .. } => (!late, true),
                         hir::InlineAsmOperand::InOut { .. }
                         | hir::InlineAsmOperand::SplitInOut { .. } => (true, true),
-                        hir::InlineAsmOperand::Const { .. } | hir::InlineAsmOperand::Sym { .. } => {
-                            unreachable!()
-                        }
+                        hir::InlineAsmOperand::Const { .. }
+                        | hir::InlineAsmOperand::Sym { .. }
+                        | hir::InlineAsmOperand::Label { .. } => unreachable!(),
                     };
 
                     // Flag to output the error only once per operand
@@ -2123,6 +2123,11 @@ impl<'a> State<'a> {
                                 s.space();
                                 s.print_expr(expr);
                             }
+                            InlineAsmOperand::Label { block } => {
+                                s.word("label");
+                                s.space();
+                                s.print_block(block);
+                            }
                         }
                     }
                     AsmArg::Options(opts) => {
@@ -2150,6 +2155,9 @@ impl<'a> State<'a> {
                         if opts.contains(InlineAsmOptions::ATT_SYNTAX) {
                             options.push("att_syntax");
                         }
+                        if opts.contains(InlineAsmOptions::MAY_UNWIND) {
+                            options.push("may_unwind");
+                        }
                         s.commasep(Inconsistent, &options, |s, &opt| {
                             s.word(opt);
                         });
@@ -147,6 +147,9 @@ fn parse_args<'a>(
                 }
             }
             ast::InlineAsmOperand::Sym { expr }
+        } else if p.eat(&token::Ident(sym::label, false)) {
+            let block = p.parse_block()?;
+            ast::InlineAsmOperand::Label { block }
         } else if allow_templates {
             let template = p.parse_expr()?;
             // If it can't possibly expand to a string, provide diagnostics here to include other
@@ -251,6 +254,7 @@ fn parse_args<'a>(
 
     let mut have_real_output = false;
     let mut outputs_sp = vec![];
+    let mut labels_sp = vec![];
     for (op, op_sp) in &args.operands {
         match op {
             ast::InlineAsmOperand::Out { expr, .. }
@@ -262,6 +266,7 @@ fn parse_args<'a>(
                 outputs_sp.push(*op_sp);
                 have_real_output = true;
             }
+            ast::InlineAsmOperand::Label { .. } => labels_sp.push(*op_sp),
             _ => {}
         }
     }
@@ -279,6 +284,15 @@ fn parse_args<'a>(
         // Bail out now since this is likely to confuse MIR
         return Err(err);
     }
+    if !labels_sp.is_empty() && !outputs_sp.is_empty() {
+        let err = ecx.struct_span_err(outputs_sp, "asm outputs are not allowed with labels");
+        return Err(err);
+    }
+    if args.options.contains(ast::InlineAsmOptions::MAY_UNWIND) && !labels_sp.is_empty() {
+        ecx.struct_span_err(labels_sp, "asm labels are not allowed with the `may_unwind` option")
+            .span_labels(args.options_spans.clone(), "options")
+            .emit();
+    }
 
     Ok(args)
 }
@@ -345,6 +359,8 @@ fn parse_options<'a>(p: &mut Parser<'a>, args: &mut AsmArgs) -> Result<(), Diagn
             try_set_option(p, args, sym::noreturn, ast::InlineAsmOptions::NORETURN);
         } else if p.eat(&token::Ident(sym::nostack, false)) {
             try_set_option(p, args, sym::nostack, ast::InlineAsmOptions::NOSTACK);
+        } else if p.eat(&token::Ident(sym::may_unwind, false)) {
+            try_set_option(p, args, sym::may_unwind, ast::InlineAsmOptions::MAY_UNWIND);
         } else {
             p.expect(&token::Ident(sym::att_syntax, false))?;
             try_set_option(p, args, sym::att_syntax, ast::InlineAsmOptions::ATT_SYNTAX);
@@ -1,13 +1,15 @@
+use crate::attributes;
 use crate::builder::Builder;
+use crate::common::Funclet;
 use crate::context::CodegenCx;
-use crate::llvm;
+use crate::llvm::{self, BasicBlock};
 use crate::type_::Type;
 use crate::type_of::LayoutLlvmExt;
 use crate::value::Value;
 
 use rustc_ast::ast::LlvmAsmDialect;
 use rustc_ast::ast::{InlineAsmOptions, InlineAsmTemplatePiece};
-use rustc_codegen_ssa::mir::operand::OperandValue;
+use rustc_codegen_ssa::mir::operand::{OperandRef, OperandValue};
 use rustc_codegen_ssa::mir::place::PlaceRef;
 use rustc_codegen_ssa::traits::*;
 use rustc_data_structures::fx::FxHashMap;
@@ -98,6 +100,7 @@ impl AsmBuilderMethods<'tcx> for Builder<'a, 'll, 'tcx> {
             ia.alignstack,
             ia.dialect,
             &[span],
+            None,
         );
         if r.is_none() {
             return false;
@@ -120,7 +123,16 @@ impl AsmBuilderMethods<'tcx> for Builder<'a, 'll, 'tcx> {
         operands: &[InlineAsmOperandRef<'tcx, Self>],
         options: InlineAsmOptions,
         line_spans: &[Span],
+        dest: Option<&'ll BasicBlock>,
+        catch_funclet: Option<(&'ll BasicBlock, Option<&Funclet<'ll>>)>,
     ) {
+        if let Some((catch, funclet)) = catch_funclet {
+            let dest = dest.unwrap_or_else(|| span_bug!(line_spans[0], "invoked asm without dest"));
+            let (llfn, args) = unwinding_asm_fn(self, template, operands, options, line_spans);
+            self.invoke(llfn, &args, dest, catch, funclet);
+            return;
+        }
+
         let asm_arch = self.tcx.sess.asm_arch.unwrap();
 
         // Collect the types of output operands
@@ -162,6 +174,7 @@ impl AsmBuilderMethods<'tcx> for Builder<'a, 'll, 'tcx> {
 
         // Collect input operands
         let mut inputs = vec![];
+        let mut labels = vec![];
         for (idx, op) in operands.iter().enumerate() {
             match *op {
                 InlineAsmOperandRef::In { reg, value } => {
@@ -191,6 +204,14 @@ impl AsmBuilderMethods<'tcx> for Builder<'a, 'll, 'tcx> {
                     op_idx.insert(idx, constraints.len());
                     constraints.push("s".to_string());
                 }
+                InlineAsmOperandRef::Label { label } => {
+                    // `callbr` expects the address of each of its indirect destinations
+                    // as an argument.
+                    inputs.push(unsafe { llvm::LLVMBlockAddress(self.llfn(), label) });
+                    labels.push(label);
+                    op_idx.insert(idx, constraints.len());
+                    constraints.push("X".to_string());
+                }
                 _ => {}
             }
         }
@@ -236,6 +257,10 @@ impl AsmBuilderMethods<'tcx> for Builder<'a, 'll, 'tcx> {
                             // Only emit the raw symbol name
                             template_str.push_str(&format!("${{{}:c}}", op_idx[&operand_idx]));
                         }
+                        InlineAsmOperandRef::Label { .. } => {
+                            // Emit the label of the block
+                            template_str.push_str(&format!("${{{}:l}}", op_idx[&operand_idx]));
+                        }
                     }
                 }
             }
@@ -289,6 +314,7 @@ impl AsmBuilderMethods<'tcx> for Builder<'a, 'll, 'tcx> {
             alignstack,
             dialect,
             line_spans,
+            if labels.is_empty() { None } else { dest.map(|dest| (dest, &labels[..])) },
         )
         .unwrap_or_else(|| span_bug!(line_spans[0], "LLVM asm constraint validation failed"));
 
@@ -321,7 +347,100 @@ impl AsmBuilderMethods<'tcx> for Builder<'a, 'll, 'tcx> {
                 OperandValue::Immediate(value).store(self, place);
             }
         }
+
+        // Without labels the asm is an ordinary call, so the block still has to be terminated.
+        if labels.is_empty() {
+            if let Some(dest) = dest {
+                self.br(dest);
+            }
+        }
+    }
+}
+
+/// Moves inline assembly that may unwind into a function of its own, since LLVM can `invoke`
+/// functions but not inline assembly. The values of the input operands and the places of the
+/// output operands are passed as arguments, so the function writes the outputs itself. Returns
+/// the function and the arguments to invoke it with.
+fn unwinding_asm_fn(
+    bx: &mut Builder<'a, 'll, 'tcx>,
+    template: &[InlineAsmTemplatePiece],
+    operands: &[InlineAsmOperandRef<'tcx, Builder<'a, 'll, 'tcx>>],
+    options: InlineAsmOptions,
+    line_spans: &[Span],
+) -> (&'ll Value, Vec<&'ll Value>) {
+    let mut args = vec![];
+    for op in operands {
+        match *op {
+            InlineAsmOperandRef::In { value, .. } => args.push(value.immediate()),
+            InlineAsmOperandRef::Out { place, .. } => args.extend(place.map(|place| place.llval)),
+            InlineAsmOperandRef::InOut { in_value, out_place, .. } => {
+                args.push(in_value.immediate());
+                args.extend(out_place.map(|place| place.llval));
+            }
+            InlineAsmOperandRef::Const { .. }
+            | InlineAsmOperandRef::SymFn { .. }
+            | InlineAsmOperandRef::SymStatic { .. } => {}
+            InlineAsmOperandRef::Label { .. } => {
+                span_bug!(line_spans[0], "asm labels are not allowed with `may_unwind`")
+            }
+        }
+    }
+
+    let cx = bx.cx;
+    let arg_tys: Vec<_> = args.iter().map(|&arg| cx.val_ty(arg)).collect();
+    let name = cx.generate_local_symbol_name("asm_unwind");
+    let llfn = cx.declare_cfn(&name, cx.type_func(&arg_tys, cx.type_void()));
+    unsafe { llvm::LLVMRustSetLinkage(llfn, llvm::Linkage::InternalLinkage) };
+    // Inlined into its caller, the assembly would be called rather than invoked again.
+    llvm::Attribute::NoInline.apply_llfn(llvm::AttributePlace::Function, llfn);
+    attributes::emit_uwtable(llfn, true);
+    cx.set_frame_pointer_elimination(llfn);
+    cx.apply_target_cpu_attr(llfn);
+
+    let mut params = (0..args.len()).map(|i| llvm::get_param(llfn, i as c_uint));
+    let mut param_operand = |layout| OperandRef {
+        val: OperandValue::Immediate(params.next().unwrap()),
+        layout,
+    };
+    let operands: Vec<_> = operands
+        .iter()
+        .map(|op| match *op {
+            InlineAsmOperandRef::In { reg, value } => {
+                InlineAsmOperandRef::In { reg, value: param_operand(value.layout) }
+            }
+            InlineAsmOperandRef::Out { reg, late, place } => InlineAsmOperandRef::Out {
+                reg,
+                late,
+                place: place.map(|place| {
+                    let llval = param_operand(place.layout).immediate();
+                    PlaceRef::new_sized_aligned(llval, place.layout, place.align)
+                }),
+            },
+            InlineAsmOperandRef::InOut { reg, late, in_value, out_place } => {
+                let in_value = param_operand(in_value.layout);
+                let out_place = out_place.map(|place| {
+                    let llval = param_operand(place.layout).immediate();
+                    PlaceRef::new_sized_aligned(llval, place.layout, place.align)
+                });
+                InlineAsmOperandRef::InOut { reg, late, in_value, out_place }
+            }
+            InlineAsmOperandRef::Const { ref string } => {
+                InlineAsmOperandRef::Const { string: string.clone() }
+            }
+            InlineAsmOperandRef::SymFn { instance } => InlineAsmOperandRef::SymFn { instance },
+            InlineAsmOperandRef::SymStatic { def_id } => InlineAsmOperandRef::SymStatic { def_id },
+            InlineAsmOperandRef::Label { .. } => unreachable!(),
+        })
+        .collect();
+
+    let mut fn_bx = Builder::new_block(cx, llfn, "start");
+    fn_bx.codegen_inline_asm(template, &operands, options, line_spans, None, None);
+    if options.contains(InlineAsmOptions::NORETURN) {
+        fn_bx.unreachable();
+    } else {
+        fn_bx.ret_void();
     }
+    (llfn, args)
 }
 
 impl AsmMethods for CodegenCx<'ll, 'tcx> {
@@ -343,6 +462,7 @@ fn inline_asm_call(
     alignstack: bool,
     dia: LlvmAsmDialect,
     line_spans: &[Span],
+    dest_labels: Option<(&'ll BasicBlock, &[&'ll BasicBlock])>,
 ) -> Option<&'ll Value> {
     let volatile = if volatile { llvm::True } else { llvm::False };
     let alignstack = if alignstack { llvm::True } else { llvm::False };
@@ -372,7 +492,11 @@ fn inline_asm_call(
                 alignstack,
                 llvm::AsmDialect::from_generic(dia),
             );
-            let call = bx.call(v, inputs, None);
+            let call = if let Some((dest, labels)) = dest_labels {
+                bx.callbr(v, inputs, dest, labels, None)
+            } else {
+                bx.call(v, inputs, None)
+            };
 
             // Store mark in a metadata node so we can map LLVM errors
             // back to source locations.  See #17552.
@@ -83,7 +83,7 @@ pub fn set_frame_pointer_elimination(cx: &CodegenCx<'ll, '_>, llfn: &'ll Value)
 
 /// Tell LLVM what instrument function to insert.
 #[inline]
-fn set_instrument_function(cx: &CodegenCx<'ll, '_>, llfn: &'ll Value) {
+pub fn set_instrument_function(cx: &CodegenCx<'ll, '_>, llfn: &'ll Value) {
     if cx.sess().instrument_mcount() {
         // Similar to `clang -pg` behavior. Handled by the
         // `post-inline-ee-instrument` LLVM pass.
@@ -128,11 +128,6 @@ fn set_probestack(cx: &CodegenCx<'ll, '_>, llfn: &'ll Value) {
         return;
     }
 
-    // probestack doesn't play nice either with gcov profiling.
-    if cx.sess().opts.debugging_opts.profile {
-        return;
-    }
-
     // FIXME(richkadel): Make sure probestack plays nice with `-Z instrument-coverage`
     // or disable it if not, similar to above early exits.
 
@@ -250,7 +245,15 @@ pub fn from_fn_attrs(cx: &CodegenCx<'ll, 'tcx>, llfn: &'ll Value, instance: ty::
         inline(cx, llfn, attributes::InlineAttr::Hint);
     }
 
-    inline(cx, llfn, codegen_fn_attrs.inline.clone());
+    // Blocks were outlined from their parent to keep them out of its way, so they keep none
+    // of its inlining hints and are never merged back into it.
+    let is_outlined = matches!(instance.def, ty::InstanceDef::Outlined(..));
+    if is_outlined {
+        inline(cx, llfn, attributes::InlineAttr::Never);
+        Attribute::Cold.apply_llfn(Function, llfn);
+    } else {
+        inline(cx, llfn, codegen_fn_attrs.inline.clone());
+    }
 
     // The `uwtable` attribute according to LLVM is:
     //
@@ -273,7 +276,6 @@ pub fn from_fn_attrs(cx: &CodegenCx<'ll, 'tcx>, llfn: &'ll Value, instance: ty::
     }
 
     set_frame_pointer_elimination(cx, llfn);
-    set_instrument_function(cx, llfn);
     set_probestack(cx, llfn);
 
     if codegen_fn_attrs.flags.contains(CodegenFnAttrFlags::COLD) {
@@ -291,7 +293,7 @@ pub fn from_fn_attrs(cx: &CodegenCx<'ll, 'tcx>, llfn: &'ll Value, instance: ty::
     if codegen_fn_attrs.flags.contains(CodegenFnAttrFlags::NAKED) {
         naked(llfn, true);
     }
-    if codegen_fn_attrs.flags.contains(CodegenFnAttrFlags::ALLOCATOR) {
+    if codegen_fn_attrs.flags.contains(CodegenFnAttrFlags::ALLOCATOR) && !is_outlined {
         Attribute::NoAlias.apply_llfn(llvm::AttributePlace::ReturnValue, llfn);
     }
     sanitize(cx, codegen_fn_attrs.no_sanitize, llfn);
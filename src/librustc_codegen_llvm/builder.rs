@@ -190,6 +190,27 @@ impl BuilderMethods<'a, 'tcx> for Builder<'a, 'll, 'tcx> {
         }
     }
 
+    fn branch_weights_metadata(&mut self, weights: &[u32]) {
+        unsafe {
+            let branch = match llvm::LLVMGetBasicBlockTerminator(self.llbb()) {
+                Some(branch) => branch,
+                None => return,
+            };
+            let kind = "branch_weights";
+            let mut node = vec![llvm::LLVMMDStringInContext(
+                self.cx.llcx,
+                kind.as_ptr().cast(),
+                kind.len() as c_uint,
+            )];
+            node.extend(weights.iter().map(|&weight| self.cx.const_u32(weight)));
+            llvm::LLVMSetMetadata(
+                branch,
+                llvm::MD_prof as c_uint,
+                llvm::LLVMMDNodeInContext(self.cx.llcx, node.as_ptr(), node.len() as c_uint),
+            );
+        }
+    }
+
     fn invoke(
         &mut self,
         llfn: &'ll Value,
@@ -1239,6 +1260,35 @@ impl Builder<'a, 'll, 'tcx> {
         ret.expect("LLVM does not have support for catchret")
     }
 
+    pub fn callbr(
+        &mut self,
+        llfn: &'ll Value,
+        args: &[&'ll Value],
+        default_dest: &'ll BasicBlock,
+        indirect_dests: &[&'ll BasicBlock],
+        funclet: Option<&Funclet<'ll>>,
+    ) -> &'ll Value {
+        debug!("callbr {:?} with args ({:?})", llfn, args);
+
+        let args = self.check_call("callbr", llfn, args);
+        let bundle = funclet.map(|funclet| funclet.bundle());
+        let bundle = bundle.as_ref().map(|b| &*b.raw);
+
+        unsafe {
+            llvm::LLVMRustBuildCallBr(
+                self.llbuilder,
+                llfn,
+                default_dest,
+                indirect_dests.as_ptr(),
+                indirect_dests.len() as c_uint,
+                args.as_ptr(),
+                args.len() as c_uint,
+                bundle,
+                UNNAMED,
+            )
+        }
+    }
+
     fn check_store(&mut self, val: &'ll Value, ptr: &'ll Value) -> &'ll Value {
         let dest_ptr_ty = self.cx.val_ty(ptr);
         let stored_ty = self.cx.val_ty(val);
@@ -4,10 +4,14 @@ use crate::llvm;
 
 use llvm::coverageinfo::CounterMappingRegion;
 use log::debug;
-use rustc_codegen_ssa::coverageinfo::map::{Counter, CounterExpression, Region};
-use rustc_codegen_ssa::traits::{BaseTypeMethods, ConstMethods};
-use rustc_data_structures::fx::FxHashMap;
+use rustc_codegen_ssa::coverageinfo::map::{Counter, CounterExpression, FunctionCoverage, Region};
+use rustc_codegen_ssa::traits::{BaseTypeMethods, ConstMethods, DerivedTypeMethods};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_hir::def_id::{DefId, LOCAL_CRATE};
 use rustc_llvm::RustString;
+use rustc_middle::mir::mono::MonoItem;
+use rustc_middle::mir::BodyKind;
+use rustc_middle::ty::Instance;
 
 use std::ffi::CString;
 
@@ -25,17 +29,23 @@ use std::ffi::CString;
 /// replicated for Rust's Coverage Map.
 pub fn finalize<'ll, 'tcx>(cx: &CodegenCx<'ll, 'tcx>) {
     let function_coverage_map = cx.coverage_context().take_function_coverage_map();
-    if function_coverage_map.is_empty() {
+    let unused_functions = unused_function_coverage(cx);
+    if function_coverage_map.is_empty() && unused_functions.is_empty() {
         // This module has no functions with coverage instrumentation
         return;
     }
+    let unused_function_names =
+        unused_functions.iter().map(|(instance, _)| cx.tcx.symbol_name(*instance).to_string());
+    let unused_function_names = unused_function_names.collect::<Vec<_>>();
 
     let mut mapgen = CoverageMapGenerator::new();
 
     // Encode coverage mappings and generate function records
     let mut function_records = Vec::<&'ll llvm::Value>::new();
     let coverage_mappings_buffer = llvm::build_byte_buffer(|coverage_mappings_buffer| {
-        for (instance, function_coverage) in function_coverage_map.into_iter() {
+        for (instance, function_coverage) in
+            function_coverage_map.into_iter().chain(unused_functions)
+        {
             debug!("Generate coverage map for: {:?}", instance);
 
             let mangled_function_name = cx.tcx.symbol_name(instance).to_string();
@@ -73,6 +83,76 @@ pub fn finalize<'ll, 'tcx>(cx: &CodegenCx<'ll, 'tcx>) {
         filenames_buffer,
         coverage_mappings_buffer,
     );
+
+    if !unused_function_names.is_empty() {
+        save_unused_function_names(cx, &unused_function_names);
+    }
+}
+
+/// Returns the coverage of the non-generic functions of the local crate that have code regions,
+/// but no counters in the generated code: their code was either never generated, or removed by
+/// MIR optimizations. Without this, coverage reports would show them as not instrumented, rather
+/// than as never executed. Only one codegen unit reports them, so that they get a single record.
+fn unused_function_coverage<'tcx>(
+    cx: &CodegenCx<'_, 'tcx>,
+) -> Vec<(Instance<'tcx>, FunctionCoverage<'tcx>)> {
+    let tcx = cx.tcx;
+    let (_, codegen_units) = tcx.collect_and_partition_mono_items(LOCAL_CRATE);
+    if codegen_units.iter().map(|cgu| cgu.name()).min() != Some(cx.codegen_unit.name()) {
+        return Vec::new();
+    }
+
+    let codegenned_functions: FxHashSet<DefId> = codegen_units
+        .iter()
+        .flat_map(|cgu| cgu.items().keys())
+        .filter_map(|item| match *item {
+            MonoItem::Fn(instance) => Some(instance.def_id()),
+            MonoItem::Static(_) | MonoItem::GlobalAsm(_) => None,
+        })
+        .collect();
+
+    let mut unused_functions = Vec::new();
+    for &def_id in tcx.mir_keys(LOCAL_CRATE).iter() {
+        if tcx.mir_body_kind(def_id).kind != BodyKind::Fn {
+            continue;
+        }
+        let def_id = def_id.to_def_id();
+        if tcx.generics_of(def_id).requires_monomorphization(tcx)
+            || tcx.optimized_mir(def_id).coverage_regions.is_empty()
+            || (codegenned_functions.contains(&def_id) && tcx.coverageinfo(def_id).num_counters > 0)
+        {
+            continue;
+        }
+        debug!("Adding unused function to coverage map: {:?}", def_id);
+        unused_functions.push((Instance::mono(tcx, def_id), FunctionCoverage::unused(tcx, def_id)));
+    }
+    unused_functions.sort_by_cached_key(|(instance, _)| tcx.symbol_name(*instance).to_string());
+    unused_functions
+}
+
+/// Saves the names of the functions that have a coverage record but no profile counters, like
+/// Clang does for unused functions. LLVM adds them to the profile names, so that the coverage
+/// tools can match their records and report their regions as never executed.
+fn save_unused_function_names<'ll, 'tcx>(cx: &CodegenCx<'ll, 'tcx>, names: &[String]) {
+    let i8p_type = cx.type_i8p();
+    let name_ptrs = names
+        .iter()
+        .map(|name| {
+            let name_val = cx.const_bytes(name.as_bytes());
+            let name_global =
+                llvm::add_global(cx.llmod, cx.val_ty(name_val), &format!("__profn_{}", name));
+            llvm::set_initializer(name_global, name_val);
+            llvm::set_global_constant(name_global, true);
+            llvm::set_linkage(name_global, llvm::Linkage::PrivateLinkage);
+            cx.const_ptrcast(name_global, i8p_type)
+        })
+        .collect::<Vec<_>>();
+
+    let names_val = cx.const_array(i8p_type, &name_ptrs);
+    let names_global = llvm::add_global(cx.llmod, cx.val_ty(names_val), "__llvm_coverage_names");
+    llvm::set_initializer(names_global, names_val);
+    llvm::set_global_constant(names_global, true);
+    llvm::set_linkage(names_global, llvm::Linkage::InternalLinkage);
 }
 
 struct CoverageMapGenerator {
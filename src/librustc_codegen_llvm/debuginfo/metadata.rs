@@ -25,7 +25,6 @@ use rustc_data_structures::const_cstr;
 use rustc_data_structures::fingerprint::Fingerprint;
 use rustc_data_structures::fx::FxHashMap;
 use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
-use rustc_fs_util::path_to_c_string;
 use rustc_hir::def::CtorKind;
 use rustc_hir::def_id::{CrateNum, DefId, LOCAL_CRATE};
 use rustc_index::vec::{Idx, IndexVec};
@@ -49,7 +48,7 @@ use std::collections::hash_map::Entry;
 use std::fmt::{self, Write};
 use std::hash::{Hash, Hasher};
 use std::iter;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::ptr;
 
 impl PartialEq for llvm::Metadata {
@@ -1045,35 +1044,6 @@ pub fn compile_unit_metadata(
             kind,
         );
 
-        if tcx.sess.opts.debugging_opts.profile {
-            let cu_desc_metadata =
-                llvm::LLVMRustMetadataAsValue(debug_context.llcontext, unit_metadata);
-            let default_gcda_path = &tcx.output_filenames(LOCAL_CRATE).with_extension("gcda");
-            let gcda_path =
-                tcx.sess.opts.debugging_opts.profile_emit.as_ref().unwrap_or(default_gcda_path);
-
-            let gcov_cu_info = [
-                path_to_mdstring(
-                    debug_context.llcontext,
-                    &tcx.output_filenames(LOCAL_CRATE).with_extension("gcno"),
-                ),
-                path_to_mdstring(debug_context.llcontext, &gcda_path),
-                cu_desc_metadata,
-            ];
-            let gcov_metadata = llvm::LLVMMDNodeInContext(
-                debug_context.llcontext,
-                gcov_cu_info.as_ptr(),
-                gcov_cu_info.len() as c_uint,
-            );
-
-            let llvm_gcov_ident = const_cstr!("llvm.gcov");
-            llvm::LLVMAddNamedMetadataOperand(
-                debug_context.llmod,
-                llvm_gcov_ident.as_ptr(),
-                gcov_metadata,
-            );
-        }
-
         // Insert `llvm.ident` metadata on the wasm32 targets since that will
         // get hooked up to the "producer" sections `processed-by` information.
         if tcx.sess.opts.target_triple.triple().starts_with("wasm32") {
@@ -1091,17 +1061,6 @@ pub fn compile_unit_metadata(
 
         return unit_metadata;
     };
-
-    fn path_to_mdstring(llcx: &'ll llvm::Context, path: &Path) -> &'ll Value {
-        let path_str = path_to_c_string(path);
-        unsafe {
-            llvm::LLVMMDStringInContext(
-                llcx,
-                path_str.as_ptr(),
-                path_str.as_bytes().len() as c_uint,
-            )
-        }
-    }
 }
 
 struct MetadataCreationResult<'ll> {
@@ -1,4 +1,5 @@
 use crate::abi::{Abi, FnAbi, LlvmType, PassMode};
+use crate::attributes;
 use crate::builder::Builder;
 use crate::context::CodegenCx;
 use crate::llvm;
@@ -214,6 +215,12 @@ impl IntrinsicCallMethods<'tcx> for Builder<'a, 'll, 'tcx> {
                 let llfn = self.get_intrinsic(&("llvm.debugtrap"));
                 self.call(llfn, &[], None)
             }
+            sym::instrument_function_entry => {
+                // `mcount` finds its caller from the return address, so LLVM has to insert the
+                // call itself, once it has inlined into the function what it is going to.
+                attributes::set_instrument_function(self.cx, self.llfn());
+                return;
+            }
             sym::count_code_region => {
                 // FIXME(richkadel): The current implementation assumes the MIR for the given
                 // caller_instance represents a single function. Validate and/or correct if inlining
@@ -893,6 +900,17 @@ impl IntrinsicCallMethods<'tcx> for Builder<'a, 'll, 'tcx> {
                 self.exactsdiv(d, pointee_size)
             }
 
+            sym::const_allocate => {
+                // The const heap only exists during const evaluation, at runtime allocating from
+                // it always fails.
+                self.const_null(self.type_i8p())
+            }
+
+            sym::const_deallocate => {
+                // Nothing was allocated at runtime, so there is nothing to free either.
+                return;
+            }
+
             _ => bug!("unknown intrinsic '{}'", name),
         };
 
@@ -1027,6 +1027,7 @@ extern "C" {
     pub fn LLVMConstIntToPtr(ConstantVal: &'a Value, ToType: &'a Type) -> &'a Value;
     pub fn LLVMConstBitCast(ConstantVal: &'a Value, ToType: &'a Type) -> &'a Value;
     pub fn LLVMConstPointerCast(ConstantVal: &'a Value, ToType: &'a Type) -> &'a Value;
+    pub fn LLVMBlockAddress(F: &'a Value, BB: &'a BasicBlock) -> &'a Value;
     pub fn LLVMConstExtractValue(
         AggConstant: &Value,
         IdxList: *const c_uint,
@@ -1105,6 +1106,7 @@ extern "C" {
         Name: *const c_char,
     ) -> &'a BasicBlock;
     pub fn LLVMDeleteBasicBlock(BB: &BasicBlock);
+    pub fn LLVMGetBasicBlockTerminator(BB: &BasicBlock) -> Option<&Value>;
 
     // Operations on instructions
     pub fn LLVMIsAInstruction(Val: &Value) -> Option<&Value>;
@@ -1164,6 +1166,17 @@ extern "C" {
         Bundle: Option<&OperandBundleDef<'a>>,
         Name: *const c_char,
     ) -> &'a Value;
+    pub fn LLVMRustBuildCallBr(
+        B: &Builder<'a>,
+        Fn: &'a Value,
+        DefaultDest: &'a BasicBlock,
+        IndirectDests: *const &'a BasicBlock,
+        NumIndirectDests: c_uint,
+        Args: *const &'a Value,
+        NumArgs: c_uint,
+        Bundle: Option<&OperandBundleDef<'a>>,
+        Name: *const c_char,
+    ) -> &'a Value;
     pub fn LLVMBuildLandingPad(
         B: &Builder<'a>,
         Ty: &'a Type,
@@ -1,5 +1,6 @@
 pub use super::ffi::*;
 
+use rustc_hir::def_id::DefId;
 use rustc_index::vec::IndexVec;
 use rustc_middle::ty::Instance;
 use rustc_middle::ty::TyCtxt;
@@ -182,12 +183,40 @@ pub struct FunctionCoverage<'a> {
 impl<'a> FunctionCoverage<'a> {
     pub fn new<'tcx: 'a>(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>) -> Self {
         let coverageinfo = tcx.coverageinfo(instance.def_id());
+        let source_map = tcx.sess.source_map();
+        // The regions whose counters were optimized away are never executed.
+        let unreachable_regions = coverageinfo
+            .unreachable_regions
+            .iter()
+            .map(|region| Region::new(source_map, region.start_byte_pos, region.end_byte_pos))
+            .collect();
         Self {
-            source_map: tcx.sess.source_map(),
+            source_map,
             source_hash: 0, // will be set with the first `add_counter()`
             counters: IndexVec::from_elem_n(None, coverageinfo.num_counters as usize),
             expressions: IndexVec::from_elem_n(None, coverageinfo.num_expressions as usize),
-            unreachable_regions: Vec::new(),
+            unreachable_regions,
+        }
+    }
+
+    /// Creates the coverage of a function without any counters in the generated code, because
+    /// it was never code generated, or because MIR optimizations removed all of its code. All of
+    /// its code regions are unreachable.
+    pub fn unused<'tcx: 'a>(tcx: TyCtxt<'tcx>, def_id: DefId) -> Self {
+        let coverageinfo = tcx.coverageinfo(def_id);
+        let source_map = tcx.sess.source_map();
+        let unreachable_regions = tcx
+            .optimized_mir(def_id)
+            .coverage_regions
+            .iter()
+            .map(|region| Region::new(source_map, region.start_byte_pos, region.end_byte_pos))
+            .collect();
+        Self {
+            source_map,
+            source_hash: coverageinfo.function_source_hash,
+            counters: IndexVec::new(),
+            expressions: IndexVec::new(),
+            unreachable_regions,
         }
     }
 
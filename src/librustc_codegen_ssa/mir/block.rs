@@ -24,6 +24,7 @@ use rustc_target::abi::{self, LayoutOf};
 use rustc_target::spec::abi::Abi;
 
 use std::borrow::Cow;
+use std::iter;
 
 /// Used by `FunctionCx::codegen_terminator` for emitting common patterns
 /// e.g., creating a basic block, calling a function, etc.
@@ -203,6 +204,12 @@ impl<'a, 'tcx, Bx: BuilderMethods<'a, 'tcx>> FunctionCx<'a, 'tcx, Bx> {
         let discr = self.codegen_operand(&mut bx, &discr);
         // `switch_ty` is redundant, sanity-check that.
         assert_eq!(discr.layout.ty, switch_ty);
+        // The weights of the MIR targets, in their order, if the `BranchWeights` pass found any.
+        let mir = self.mir;
+        let weights = match mir.branch_weights {
+            Some(ref weights) => &weights[helper.bb][..],
+            None => &[],
+        };
         if targets.len() == 2 {
             // If there are two targets, emit br instead of switch
             let lltrue = helper.llblock(self, targets[0]);
@@ -212,9 +219,15 @@ impl<'a, 'tcx, Bx: BuilderMethods<'a, 'tcx>> FunctionCx<'a, 'tcx, Bx> {
                 // Don't generate trivial icmps when switching on bool
                 if let [0] = values[..] {
                     bx.cond_br(discr.immediate(), llfalse, lltrue);
+                    if let [weight_true, weight_false] = *weights {
+                        bx.branch_weights_metadata(&[weight_false, weight_true]);
+                    }
                 } else {
                     assert_eq!(&values[..], &[1]);
                     bx.cond_br(discr.immediate(), lltrue, llfalse);
+                    if !weights.is_empty() {
+                        bx.branch_weights_metadata(weights);
+                    }
                 }
             } else {
                 let switch_llty = bx.immediate_backend_type(bx.layout_of(switch_ty));
@@ -222,6 +235,9 @@ impl<'a, 'tcx, Bx: BuilderMethods<'a, 'tcx>> FunctionCx<'a, 'tcx, Bx> {
                 let cmp = bx.icmp(IntPredicate::IntEQ, discr.immediate(), llval);
                 helper.maybe_sideeffect(self.mir, &mut bx, targets.as_slice());
                 bx.cond_br(cmp, lltrue, llfalse);
+                if !weights.is_empty() {
+                    bx.branch_weights_metadata(weights);
+                }
             }
         } else {
             helper.maybe_sideeffect(self.mir, &mut bx, targets.as_slice());
@@ -234,6 +250,11 @@ impl<'a, 'tcx, Bx: BuilderMethods<'a, 'tcx>> FunctionCx<'a, 'tcx, Bx> {
                     .zip(targets)
                     .map(|(&value, target)| (value, helper.llblock(self, *target))),
             );
+            if let Some((&weight_otherwise, weights)) = weights.split_last() {
+                let weights: Vec<u32> =
+                    iter::once(weight_otherwise).chain(weights.iter().copied()).collect();
+                bx.branch_weights_metadata(&weights);
+            }
         }
     }
 
@@ -849,6 +870,8 @@ impl<'a, 'tcx, Bx: BuilderMethods<'a, 'tcx>> FunctionCx<'a, 'tcx, Bx> {
         options: ast::InlineAsmOptions,
         line_spans: &[Span],
         destination: Option<mir::BasicBlock>,
+        labels: &[mir::BasicBlock],
+        cleanup: Option<mir::BasicBlock>,
     ) {
         let span = terminator.source_info.span;
 
@@ -940,10 +963,34 @@ impl<'a, 'tcx, Bx: BuilderMethods<'a, 'tcx>> FunctionCx<'a, 'tcx, Bx> {
                 mir::InlineAsmOperand::SymStatic { def_id } => {
                     InlineAsmOperandRef::SymStatic { def_id }
                 }
+                mir::InlineAsmOperand::Label { target_index } => {
+                    let label = helper.llblock(self, labels[target_index]);
+                    InlineAsmOperandRef::Label { label }
+                }
             })
             .collect();
 
-        bx.codegen_inline_asm(template, &operands, options, line_spans);
+        // With labels or an unwind edge the asm itself terminates the block.
+        if cleanup.is_some() || !labels.is_empty() {
+            let ret_llbb = if let Some(target) = destination {
+                self.blocks[target]
+            } else {
+                self.unreachable_block()
+            };
+            let catch_funclet =
+                cleanup.map(|cleanup| (helper.llblock(self, cleanup), helper.funclet(self)));
+            bx.codegen_inline_asm(
+                template,
+                &operands,
+                options,
+                line_spans,
+                Some(ret_llbb),
+                catch_funclet,
+            );
+            return;
+        }
+
+        bx.codegen_inline_asm(template, &operands, options, line_spans, None, None);
 
         if let Some(target) = destination {
             helper.funclet_br(self, &mut bx, target);
@@ -1054,6 +1101,8 @@ impl<'a, 'tcx, Bx: BuilderMethods<'a, 'tcx>> FunctionCx<'a, 'tcx, Bx> {
                 options,
                 line_spans,
                 destination,
+                ref labels,
+                cleanup,
             } => {
                 self.codegen_asm_terminator(
                     helper,
@@ -1064,6 +1113,8 @@ impl<'a, 'tcx, Bx: BuilderMethods<'a, 'tcx>> FunctionCx<'a, 'tcx, Bx> {
                     options,
                     line_spans,
                     destination,
+                    labels,
+                    cleanup,
                 );
             }
         }
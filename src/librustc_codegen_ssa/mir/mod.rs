@@ -1,5 +1,6 @@
 use crate::base;
 use crate::traits::*;
+use rustc_data_structures::fx::FxHashMap;
 use rustc_errors::ErrorReported;
 use rustc_middle::mir;
 use rustc_middle::mir::interpret::ErrorHandled;
@@ -210,6 +211,10 @@ pub fn codegen_mir<'a, 'tcx, Bx: BuilderMethods<'a, 'tcx>>(
     fx.locals = {
         let args = arg_local_refs(&mut bx, &mut fx, &memory_locals);
 
+        // Locals that `-Z mir-stack-slot-hints` put in the same slot are never storage-live at
+        // the same time and have the same type, so they can share an alloca.
+        let mut slot_allocas = FxHashMap::default();
+
         let mut allocate_local = |local| {
             let decl = &mir.local_decls[local];
             let layout = bx.layout_of(fx.monomorphize(&decl.ty));
@@ -225,6 +230,12 @@ pub fn codegen_mir<'a, 'tcx, Bx: BuilderMethods<'a, 'tcx>>(
                 debug!("alloc: {:?} -> place", local);
                 if layout.is_unsized() {
                     LocalRef::UnsizedPlace(PlaceRef::alloca_unsized_indirect(&mut bx, layout))
+                } else if let Some(slot) = mir.stack_slot_hints.as_ref().and_then(|h| h[local]) {
+                    debug!("alloc: {:?} -> {:?}", local, slot);
+                    let place = *slot_allocas
+                        .entry(slot)
+                        .or_insert_with(|| PlaceRef::alloca(&mut bx, layout));
+                    LocalRef::Place(place)
                 } else {
                     LocalRef::Place(PlaceRef::alloca(&mut bx, layout))
                 }
@@ -34,6 +34,9 @@ pub enum InlineAsmOperandRef<'tcx, B: BackendTypes + ?Sized> {
     SymStatic {
         def_id: DefId,
     },
+    Label {
+        label: B::BasicBlock,
+    },
 }
 
 pub trait AsmBuilderMethods<'tcx>: BackendTypes {
@@ -47,12 +50,18 @@ pub trait AsmBuilderMethods<'tcx>: BackendTypes {
     ) -> bool;
 
     /// Take an inline assembly expression and splat it out via LLVM
+    ///
+    /// With `dest`, the assembly terminates the block: it continues at `dest` when it falls
+    /// through, at its `Label` operands when it jumps to them and, with `catch_funclet`, at the
+    /// catch block when it unwinds. The builder is then left without a block to add to.
     fn codegen_inline_asm(
         &mut self,
         template: &[InlineAsmTemplatePiece],
         operands: &[InlineAsmOperandRef<'tcx, Self>],
         options: InlineAsmOptions,
         line_spans: &[Span],
+        dest: Option<Self::BasicBlock>,
+        catch_funclet: Option<(Self::BasicBlock, Option<&Self::Funclet>)>,
     );
 }
 
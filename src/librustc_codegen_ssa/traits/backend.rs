@@ -51,6 +51,11 @@ pub trait CodegenBackend {
     fn target_features(&self, _sess: &Session) -> Vec<Symbol> {
         vec![]
     }
+    /// Whether aggregate assignments like `_1 = Foo { 0: _2, 1: _3 }` in MIR lower to better code
+    /// than the equivalent stores to every field, which the MIR optimizations produce.
+    fn prefers_aggregate_mir(&self, _sess: &Session) -> bool {
+        false
+    }
     fn print_passes(&self) {}
     fn print_version(&self) {}
 
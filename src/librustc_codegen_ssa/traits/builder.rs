@@ -62,6 +62,10 @@ pub trait BuilderMethods<'a, 'tcx>:
         else_llbb: Self::BasicBlock,
         cases: impl ExactSizeIterator<Item = (u128, Self::BasicBlock)> + TrustedLen,
     );
+    /// Weights the targets of the branch or switch that ends the current block, in the order
+    /// LLVM's `branch_weights` metadata lists them: the `then` and `else` targets of a branch,
+    /// the default target and then the cases of a switch.
+    fn branch_weights_metadata(&mut self, weights: &[u32]);
     fn invoke(
         &mut self,
         llfn: Self::Value,
@@ -94,7 +94,7 @@ use std::process;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use measureme::{EventId, EventIdBuilder, SerializableString, StringId};
+use measureme::{EventId, EventIdBuilder, SerializableString, StringComponent, StringId};
 use parking_lot::RwLock;
 
 cfg_if! {
@@ -143,6 +143,10 @@ bitflags::bitflags! {
     }
 }
 
+// The separator measureme puts between the label and the arguments of an event, see
+// `EventIdBuilder::from_label_and_arg`.
+const EVENT_ARG_SEPARATOR: &str = "\x1E";
+
 // keep this in sync with the `-Z self-profile-events` help message in librustc_session/options.rs
 const EVENT_FILTERS_BY_NAME: &[(&str, EventFilter)] = &[
     ("none", EventFilter::empty()),
@@ -297,6 +301,30 @@ impl SelfProfilerRef {
         })
     }
 
+    /// Start profiling a generic activity that has several arguments. Profiling
+    /// continues until the TimingGuard returned from this call is dropped.
+    #[inline(always)]
+    pub fn generic_activity_with_args(
+        &self,
+        event_label: &'static str,
+        event_args: &[&str],
+    ) -> TimingGuard<'_> {
+        self.exec(EventFilter::GENERIC_ACTIVITIES, |profiler| {
+            let event_label = profiler.get_or_alloc_cached_string(event_label);
+            let event_id = if profiler.event_filter_mask.contains(EventFilter::FUNCTION_ARGS) {
+                let mut components = vec![StringComponent::Ref(event_label)];
+                for &event_arg in event_args {
+                    components.push(StringComponent::Value(EVENT_ARG_SEPARATOR));
+                    components.push(StringComponent::Value(event_arg));
+                }
+                EventId::from_label(profiler.profiler.alloc_string(&components[..]))
+            } else {
+                EventId::from_label(event_label)
+            };
+            TimingGuard::start(profiler, profiler.generic_activity_event_kind, event_id)
+        })
+    }
+
     /// Start profiling a query provider. Profiling continues until the
     /// TimingGuard returned from this call is dropped.
     #[inline(always)]
@@ -607,9 +635,12 @@ pub fn duration_to_secs_str(dur: std::time::Duration) -> String {
 }
 
 // Memory reporting
+//
+// Returns the resident set size of the current process in bytes, if the
+// platform lets us find out.
 cfg_if! {
     if #[cfg(windows)] {
-        fn get_resident() -> Option<usize> {
+        pub fn get_resident() -> Option<usize> {
             use std::mem::{self, MaybeUninit};
             use winapi::shared::minwindef::DWORD;
             use winapi::um::processthreadsapi::GetCurrentProcess;
@@ -627,7 +658,7 @@ cfg_if! {
             }
         }
     } else if #[cfg(unix)] {
-        fn get_resident() -> Option<usize> {
+        pub fn get_resident() -> Option<usize> {
             let field = 1;
             let contents = fs::read("/proc/self/statm").ok()?;
             let contents = String::from_utf8(contents).ok()?;
@@ -636,7 +667,7 @@ cfg_if! {
             Some(npages * 4096)
         }
     } else {
-        fn get_resident() -> Option<usize> {
+        pub fn get_resident() -> Option<usize> {
             None
         }
     }
@@ -29,6 +29,7 @@ use rustc_lint::LintStore;
 use rustc_metadata::locator;
 use rustc_middle::middle::cstore::MetadataLoader;
 use rustc_middle::ty::TyCtxt;
+use rustc_mir::transform::{dump_mir, instrument_coverage, mir_stats, pass_pipeline, stack_usage};
 use rustc_save_analysis as save;
 use rustc_save_analysis::DumpHandler;
 use rustc_serialize::json::{self, ToJson};
@@ -176,6 +177,7 @@ pub fn run_compiler(
             lint_caps: Default::default(),
             register_lints: None,
             override_queries: None,
+            register_mir_passes: None,
             registry: diagnostics_registry(),
         };
         callbacks.config(&mut config);
@@ -252,6 +254,7 @@ pub fn run_compiler(
         lint_caps: Default::default(),
         register_lints: None,
         override_queries: None,
+        register_mir_passes: None,
         registry: diagnostics_registry(),
     };
 
@@ -355,6 +358,21 @@ pub fn run_compiler(
                 return early_exit();
             }
 
+            if sess.opts.debugging_opts.print_mir_pass_pipeline {
+                queries.global_ctxt()?.peek_mut().enter(pass_pipeline::print_pass_pipeline);
+                return early_exit();
+            }
+
+            let mir_emit_phase = sess.opts.debugging_opts.mir_emit_phase;
+            if mir_emit_phase.is_some() {
+                queries.global_ctxt()?.peek_mut().enter(|tcx| {
+                    if let Err(e) = dump_mir::start_emit_mir_phase(tcx) {
+                        sess.err(&format!("could not emit MIR: {}", e));
+                    }
+                });
+                sess.abort_if_errors();
+            }
+
             if sess.opts.debugging_opts.save_analysis {
                 let crate_name = queries.crate_name()?.peek().clone();
                 queries.global_ctxt()?.peek_mut().enter(|tcx| {
@@ -379,6 +397,14 @@ pub fn run_compiler(
 
             queries.global_ctxt()?.peek_mut().enter(|tcx| tcx.analysis(LOCAL_CRATE))?;
 
+            if let Some(phase) = mir_emit_phase {
+                queries
+                    .global_ctxt()?
+                    .peek_mut()
+                    .enter(|tcx| dump_mir::finish_emit_mir_phase(tcx, phase));
+                return early_exit();
+            }
+
             if callbacks.after_analysis(compiler, queries) == Compilation::Stop {
                 return early_exit();
             }
@@ -389,6 +415,21 @@ pub fn run_compiler(
                 sess.code_stats.print_type_sizes();
             }
 
+            if sess.opts.debugging_opts.print_mir_stack_usage {
+                queries.global_ctxt()?.peek_mut().enter(stack_usage::print_stack_usage);
+            }
+
+            if sess.opts.debugging_opts.mir_stats {
+                queries.global_ctxt()?.peek_mut().enter(mir_stats::print_mir_stats);
+            }
+
+            if sess.opts.debugging_opts.print_coverage_summary {
+                queries
+                    .global_ctxt()?
+                    .peek_mut()
+                    .enter(instrument_coverage::print_coverage_summary);
+            }
+
             let linker = queries.linker()?;
             Ok(Some(linker))
         })?;
@@ -402,12 +443,30 @@ pub fn run_compiler(
             sess.print_perf_stats();
         }
 
+        if sess.opts.debugging_opts.time_mir_passes {
+            sess.print_mir_pass_times();
+        }
+
         if sess.print_fuel_crate.is_some() {
             eprintln!(
                 "Fuel used by {}: {}",
                 sess.print_fuel_crate.as_ref().unwrap(),
                 sess.print_fuel.load(SeqCst)
             );
+            let by_mir_pass = sess.print_fuel_by_mir_pass.lock();
+            let mut by_mir_pass: Vec<_> = by_mir_pass.iter().collect();
+            by_mir_pass.sort();
+            for (pass_name, fuel) in by_mir_pass {
+                eprintln!("  of which MIR pass {}: {}", pass_name, fuel);
+            }
+        }
+
+        // With a limit higher than the number of runs, this tells how many there are.
+        if sess.opts.debugging_opts.mir_opt_bisect_limit.is_some() {
+            match &sess.mir_opt_bisect.lock().last_run {
+                Some((index, run)) => eprintln!("MIR opt bisect: last run ({}) {}", index, run),
+                None => eprintln!("MIR opt bisect: no run within the limit"),
+            }
         }
 
         Ok(())
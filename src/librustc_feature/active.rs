@@ -579,6 +579,14 @@ declare_features! (
     /// Alloc calling `transmute` in const fn
     (active, const_fn_transmute, "1.46.0", Some(53605), None),
 
+    /// Allows calling function pointers in const contexts when they are known to point to a
+    /// `const fn`.
+    (active, const_fn_ptr_call, "1.46.0", Some(57563), None),
+
+    /// Allows the use of `#[no_instrument_function]` to exclude a function from
+    /// `-Z instrument-mcount`.
+    (active, no_instrument_function, "1.46.0", None, None),
+
     // -------------------------------------------------------------------------
     // feature-group-end: actual feature gates
     // -------------------------------------------------------------------------
@@ -34,4 +34,8 @@ fake_lang_items! {
     CountCodeRegionFnLangItem,         count_code_region,         count_code_region_fn;
     CoverageCounterAddFnLangItem,      coverage_counter_add,      coverage_counter_add_fn;
     CoverageCounterSubtractFnLangItem, coverage_counter_subtract, coverage_counter_subtract_fn;
+    AssumeFnLangItem,                  assume,                    assume_fn;
+    InstrumentFunctionEntryFnLangItem, instrument_function_entry, instrument_function_entry_fn;
+    CopyNonOverlappingFnLangItem,      copy_nonoverlapping,       copy_nonoverlapping_fn;
+    WriteBytesFnLangItem,              write_bytes,               write_bytes_fn;
 }
@@ -1303,7 +1303,7 @@ impl BodyOwnerKind {
 }
 
 /// The kind of an item that requires const-checking.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, RustcEncodable, RustcDecodable, HashStable_Generic)]
 pub enum ConstContext {
     /// A `const fn`.
     ConstFn,
@@ -2091,6 +2091,9 @@ pub enum InlineAsmOperand<'hir> {
     Sym {
         expr: Expr<'hir>,
     },
+    Label {
+        block: &'hir Block<'hir>,
+    },
 }
 
 impl<'hir> InlineAsmOperand<'hir> {
@@ -2100,7 +2103,7 @@ impl<'hir> InlineAsmOperand<'hir> {
             | Self::Out { reg, .. }
             | Self::InOut { reg, .. }
             | Self::SplitInOut { reg, .. } => Some(reg),
-            Self::Const { .. } | Self::Sym { .. } => None,
+            Self::Const { .. } | Self::Sym { .. } | Self::Label { .. } => None,
         }
     }
 }
@@ -262,11 +262,13 @@ language_item_table! {
     // lang item, but do not have it defined.
     PanicFnLangItem,               sym::panic,              panic_fn,                Target::Fn;
     PanicBoundsCheckFnLangItem,    sym::panic_bounds_check, panic_bounds_check_fn,   Target::Fn;
+    PanicFmtFnLangItem,            sym::panic_fmt,          panic_fmt_fn,            Target::Fn;
     PanicInfoLangItem,             sym::panic_info,         panic_info,              Target::Struct;
     PanicLocationLangItem,         sym::panic_location,     panic_location,          Target::Struct;
     PanicImplLangItem,             sym::panic_impl,         panic_impl,              Target::Fn;
     // Libstd panic entry point. Necessary for const eval to be able to catch it
     BeginPanicFnLangItem,          sym::begin_panic,        begin_panic_fn,          Target::Fn;
+    BeginPanicFmtFnLangItem,       sym::begin_panic_fmt,    begin_panic_fmt_fn,      Target::Fn;
 
     ExchangeMallocFnLangItem,      sym::exchange_malloc,    exchange_malloc_fn,      Target::Fn;
     BoxFreeFnLangItem,             sym::box_free,           box_free_fn,             Target::Fn;
@@ -294,8 +296,52 @@ language_item_table! {
 
     TryTraitLangItem,              kw::Try,                 try_trait,               Target::Trait;
 
+    // Functions that 128-bit integer operations are lowered to for backends without native
+    // support (see `-Z lower-128bit-ops` and the `i128_lowering` target option). The
+    // overflowing `*o` variants return `(T, bool)` like `CheckedBinaryOp`, and the `cmp`
+    // variants return an `i8` that is negative, zero or positive.
+    I128AddFnLangItem,             sym::i128_add,           i128_add_fn,             Target::Fn;
+    U128AddFnLangItem,             sym::u128_add,           u128_add_fn,             Target::Fn;
+    I128SubFnLangItem,             sym::i128_sub,           i128_sub_fn,             Target::Fn;
+    U128SubFnLangItem,             sym::u128_sub,           u128_sub_fn,             Target::Fn;
+    I128MulFnLangItem,             sym::i128_mul,           i128_mul_fn,             Target::Fn;
+    U128MulFnLangItem,             sym::u128_mul,           u128_mul_fn,             Target::Fn;
+    I128DivFnLangItem,             sym::i128_div,           i128_div_fn,             Target::Fn;
+    U128DivFnLangItem,             sym::u128_div,           u128_div_fn,             Target::Fn;
+    I128RemFnLangItem,             sym::i128_rem,           i128_rem_fn,             Target::Fn;
+    U128RemFnLangItem,             sym::u128_rem,           u128_rem_fn,             Target::Fn;
+    I128ShlFnLangItem,             sym::i128_shl,           i128_shl_fn,             Target::Fn;
+    U128ShlFnLangItem,             sym::u128_shl,           u128_shl_fn,             Target::Fn;
+    I128ShrFnLangItem,             sym::i128_shr,           i128_shr_fn,             Target::Fn;
+    U128ShrFnLangItem,             sym::u128_shr,           u128_shr_fn,             Target::Fn;
+    I128AddoFnLangItem,            sym::i128_addo,          i128_addo_fn,            Target::Fn;
+    U128AddoFnLangItem,            sym::u128_addo,          u128_addo_fn,            Target::Fn;
+    I128SuboFnLangItem,            sym::i128_subo,          i128_subo_fn,            Target::Fn;
+    U128SuboFnLangItem,            sym::u128_subo,          u128_subo_fn,            Target::Fn;
+    I128MuloFnLangItem,            sym::i128_mulo,          i128_mulo_fn,            Target::Fn;
+    U128MuloFnLangItem,            sym::u128_mulo,          u128_mulo_fn,            Target::Fn;
+    I128ShloFnLangItem,            sym::i128_shlo,          i128_shlo_fn,            Target::Fn;
+    U128ShloFnLangItem,            sym::u128_shlo,          u128_shlo_fn,            Target::Fn;
+    I128ShroFnLangItem,            sym::i128_shro,          i128_shro_fn,            Target::Fn;
+    U128ShroFnLangItem,            sym::u128_shro,          u128_shro_fn,            Target::Fn;
+    I128CmpFnLangItem,             sym::i128_cmp,           i128_cmp_fn,             Target::Fn;
+    U128CmpFnLangItem,             sym::u128_cmp,           u128_cmp_fn,             Target::Fn;
+
     // language items related to source code coverage instrumentation (-Zinstrument-coverage)
     CountCodeRegionFnLangItem,         sym::count_code_region,         count_code_region_fn,         Target::Fn;
     CoverageCounterAddFnLangItem,      sym::coverage_counter_add,      coverage_counter_add_fn,      Target::Fn;
     CoverageCounterSubtractFnLangItem, sym::coverage_counter_subtract, coverage_counter_subtract_fn, Target::Fn;
+
+    // The intrinsic `-Z mir-insert-assumes` calls to pass the facts MIR proved on to codegen.
+    AssumeFnLangItem,              sym::assume,             assume_fn,               Target::Fn;
+
+    // The marker `-Z instrument-mcount` calls at the entry of the functions it instruments.
+    InstrumentFunctionEntryFnLangItem, sym::instrument_function_entry, instrument_function_entry_fn, Target::Fn;
+
+    // The intrinsics that loops filling or copying slices element by element are replaced with.
+    CopyNonOverlappingFnLangItem,  sym::copy_nonoverlapping, copy_nonoverlapping_fn,  Target::Fn;
+    WriteBytesFnLangItem,          sym::write_bytes,        write_bytes_fn,          Target::Fn;
+
+    // The runtime of `-Z profile`, provided by `profiler_builtins`.
+    ProfileCountBlockFnLangItem,   sym::profile_count_block, profile_count_block_fn,  Target::Fn;
 }
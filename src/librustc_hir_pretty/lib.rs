@@ -1519,6 +1519,11 @@ impl<'a> State<'a> {
                             s.space();
                             s.print_expr(expr);
                         }
+                        hir::InlineAsmOperand::Label { block } => {
+                            s.word("label");
+                            s.space();
+                            s.print_block(block);
+                        }
                     },
                     AsmArg::Options(opts) => {
                         s.word("options");
@@ -1545,6 +1550,9 @@ impl<'a> State<'a> {
                         if opts.contains(ast::InlineAsmOptions::ATT_SYNTAX) {
                             options.push("att_syntax");
                         }
+                        if opts.contains(ast::InlineAsmOptions::MAY_UNWIND) {
+                            options.push("may_unwind");
+                        }
                         s.commasep(Inconsistent, &options, |s, &opt| {
                             s.word(opt);
                         });
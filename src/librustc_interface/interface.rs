@@ -11,6 +11,7 @@ use rustc_errors::registry::Registry;
 use rustc_errors::ErrorReported;
 use rustc_lint::LintStore;
 use rustc_middle::ty;
+use rustc_mir::transform::plugin::PluginRegistrarFn;
 use rustc_parse::new_parser_from_source_str;
 use rustc_session::config::{self, ErrorOutputType, Input, OutputFilenames};
 use rustc_session::early_error;
@@ -155,6 +156,10 @@ pub struct Config {
     pub override_queries:
         Option<fn(&Session, &mut ty::query::Providers, &mut ty::query::Providers)>,
 
+    /// This is a callback from the driver that returns MIR passes to add to the pipeline, like
+    /// a `-Z mir-pass-plugin` library does. See `rustc_mir::transform::plugin`.
+    pub register_mir_passes: Option<PluginRegistrarFn>,
+
     /// Registry of diagnostics codes.
     pub registry: Registry,
 }
@@ -170,6 +175,7 @@ pub fn create_compiler_and_run<R>(config: Config, f: impl FnOnce(&Compiler) -> R
         config.lint_caps,
         registry.clone(),
     );
+    util::load_mir_pass_plugins(&sess, config.register_mir_passes);
 
     let compiler = Compiler {
         sess,
@@ -898,6 +898,10 @@ fn analysis(tcx: TyCtxt<'_>, cnum: CrateNum) -> Result<()> {
         }
     });
 
+    if sess.opts.debugging_opts.check_ctfe_bodies {
+        sess.time("check_ctfe_bodies", || mir::const_eval::check_ctfe_bodies(tcx));
+    }
+
     sess.time("layout_testing", || layout_test::test_layout(tcx));
 
     // Avoid overwhelming user with errors if borrow checking failed.
@@ -7,7 +7,7 @@ use rustc_session::config::{build_configuration, build_session_options, to_crate
 use rustc_session::config::{rustc_optgroups, ErrorOutputType, ExternLocation, Options, Passes};
 use rustc_session::config::{CFGuard, ExternEntry, LinkerPluginLto, LtoCli, SwitchWithOptPath};
 use rustc_session::config::{
-    Externs, OutputType, OutputTypes, SanitizerSet, SymbolManglingVersion,
+    Externs, MirEmitPhase, OutputType, OutputTypes, SanitizerSet, SymbolManglingVersion,
 };
 use rustc_session::lint::Level;
 use rustc_session::search_paths::SearchPath;
@@ -466,6 +466,7 @@ fn test_debugging_options_tracking_hash() {
     untracked!(ast_json_noexpand, true);
     untracked!(borrowck, String::from("other"));
     untracked!(borrowck_stats, true);
+    untracked!(check_ctfe_bodies, true);
     untracked!(deduplicate_diagnostics, true);
     untracked!(dep_tasks, true);
     untracked!(dont_buffer_diagnostics, true);
@@ -488,6 +489,8 @@ fn test_debugging_options_tracking_hash() {
     untracked!(ls, true);
     untracked!(macro_backtrace, true);
     untracked!(meta_stats, true);
+    untracked!(mir_pass_memory_threshold, Some(64));
+    untracked!(mir_stats, true);
     untracked!(nll_facts, true);
     untracked!(no_analysis, true);
     untracked!(no_interleave_lints, true);
@@ -498,12 +501,16 @@ fn test_debugging_options_tracking_hash() {
     untracked!(polonius, true);
     // `pre_link_arg` is omitted because it just forwards to `pre_link_args`.
     untracked!(pre_link_args, vec![String::from("abc"), String::from("def")]);
+    untracked!(print_coverage_summary, true);
     untracked!(print_link_args, true);
     untracked!(print_llvm_passes, true);
     untracked!(print_mono_items, Some(String::from("abc")));
+    untracked!(print_mir_pass_pipeline, true);
+    untracked!(print_mir_stack_usage, true);
     untracked!(print_type_sizes, true);
     untracked!(query_dep_graph, true);
     untracked!(query_stats, true);
+    untracked!(remark_mir, Passes::Some(vec![String::from("pass1")]));
     untracked!(save_analysis, true);
     untracked!(self_profile, SwitchWithOptPath::Enabled(None));
     untracked!(self_profile_events, Some(vec![String::new()]));
@@ -514,6 +521,7 @@ fn test_debugging_options_tracking_hash() {
     untracked!(threads, 99);
     untracked!(time, true);
     untracked!(time_llvm_passes, true);
+    untracked!(time_mir_passes, true);
     untracked!(time_passes, true);
     untracked!(trace_macros, true);
     untracked!(ui_testing, true);
@@ -548,13 +556,35 @@ fn test_debugging_options_tracking_hash() {
     tracked!(fuel, Some(("abc".to_string(), 99)));
     tracked!(human_readable_cgu_names, true);
     tracked!(inline_in_all_cgus, Some(true));
+    tracked!(inline_mir_hint_threshold, Some(123));
+    tracked!(inline_mir_relaxed, true);
+    tracked!(inline_mir_threshold, Some(123));
     tracked!(insert_sideeffect, true);
     tracked!(instrument_coverage, true);
     tracked!(instrument_mcount, true);
     tracked!(link_only, true);
+    tracked!(lower_128bit_ops, Some(true));
     tracked!(merge_functions, Some(MergeFunctions::Disabled));
+    tracked!(mir_branch_weights, true);
+    tracked!(mir_emit_phase, Some(MirEmitPhase::Validated));
     tracked!(mir_emit_retag, true);
+    tracked!(mir_enable_passes, vec![(String::from("Inline"), false)]);
+    tracked!(mir_fixpoint_iterations, 5);
+    tracked!(mir_insert_assumes, true);
+    tracked!(mir_max_block_statements, Some(1000));
+    tracked!(mir_opt_bisect_limit, Some(10));
     tracked!(mir_opt_level, 3);
+    tracked!(mir_opt_stop_after, Some(String::from("ConstProp")));
+    tracked!(mir_outline_cold_blocks, true);
+    tracked!(mir_pass_growth_limit, 100);
+    tracked!(mir_pass_growth_strict, true);
+    tracked!(mir_pass_plugin, vec![PathBuf::from("plugin.so")]);
+    tracked!(mir_provenance, true);
+    tracked!(mir_reaggregate, Some(true));
+    tracked!(mir_reorder_blocks, true);
+    tracked!(mir_shuffle_passes, Some(7));
+    tracked!(mir_stack_slot_hints, true);
+    tracked!(mir_strip_debuginfo, Some(false));
     tracked!(mutable_noalias, true);
     tracked!(new_llvm_pass_manager, true);
     tracked!(no_codegen, true);
@@ -13,6 +13,7 @@ use rustc_data_structures::stable_hasher::StableHasher;
 use rustc_data_structures::sync::Lrc;
 use rustc_errors::registry::Registry;
 use rustc_metadata::dynamic_lib::DynamicLibrary;
+use rustc_mir::transform::plugin::{PluginPasses, PluginRegistrarFn};
 use rustc_resolve::{self, Resolver};
 use rustc_session as session;
 use rustc_session::config::{self, CrateType};
@@ -48,6 +49,7 @@ pub fn add_configuration(
 
     let target_features = codegen_backend.target_features(sess);
     sess.target_features.extend(target_features.iter().cloned());
+    sess.backend_prefers_aggregates = codegen_backend.prefers_aggregate_mir(sess);
 
     cfg.extend(target_features.into_iter().map(|feat| (tf, Some(feat))));
 
@@ -225,6 +227,54 @@ fn load_backend_from_dylib(path: &Path) -> fn() -> Box<dyn CodegenBackend> {
     }
 }
 
+/// Keeps the MIR passes that `register_mir_passes` and the `-Z mir-pass-plugin` libraries return
+/// on the session.
+pub fn load_mir_pass_plugins(sess: &Session, register_mir_passes: Option<PluginRegistrarFn>) {
+    let mut passes = vec![];
+    if let Some(registrar) = register_mir_passes {
+        passes.extend(registrar());
+    }
+    for path in &sess.opts.debugging_opts.mir_pass_plugin {
+        // Make sure the path contains a / or the linker will search for it.
+        let path = match env::current_dir() {
+            Ok(dir) => dir.join(path),
+            Err(err) => {
+                let err = format!("couldn't load MIR pass plugin {}: {}", path.display(), err);
+                sess.err(&err);
+                continue;
+            }
+        };
+        let lib = match DynamicLibrary::open(&path) {
+            Ok(lib) => lib,
+            Err(err) => {
+                let err = format!("couldn't load MIR pass plugin {}: {}", path.display(), err);
+                sess.err(&err);
+                continue;
+            }
+        };
+        let registrar = unsafe {
+            match lib.symbol("__rustc_mir_pass_plugin") {
+                Ok(registrar) => mem::transmute::<*mut u8, PluginRegistrarFn>(registrar),
+                Err(err) => {
+                    let err = format!(
+                        "MIR pass plugin {} doesn't export `__rustc_mir_pass_plugin`: {}",
+                        path.display(),
+                        err
+                    );
+                    sess.err(&err);
+                    continue;
+                }
+            }
+        };
+        // The passes are code from the library, so it can never be unloaded.
+        mem::forget(lib);
+        passes.extend(registrar());
+    }
+    if !passes.is_empty() {
+        let _ = sess.mir_pass_plugins.set(Box::new(PluginPasses(passes)));
+    }
+}
+
 pub fn get_codegen_backend(sess: &Session) -> Box<dyn CodegenBackend> {
     static INIT: Once = Once::new();
 
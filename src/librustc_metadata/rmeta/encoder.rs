@@ -1112,7 +1112,11 @@ impl EncodeContext<'a, 'tcx> {
                     && !self.metadata_output_only();
                 let is_const_fn = sig.header.constness == hir::Constness::Const;
                 let always_encode_mir = self.tcx.sess.opts.debugging_opts.always_encode_mir;
-                needs_inline || is_const_fn || always_encode_mir
+                needs_inline
+                    || is_const_fn
+                    || always_encode_mir
+                    || (!self.metadata_output_only()
+                        && tcx.cross_crate_inlinable(def_id.expect_local()))
             }
             hir::ImplItemKind::TyAlias(..) => false,
         };
@@ -1407,7 +1411,11 @@ impl EncodeContext<'a, 'tcx> {
                     || tcx.codegen_fn_attrs(def_id).requests_inline())
                     && !self.metadata_output_only();
                 let always_encode_mir = self.tcx.sess.opts.debugging_opts.always_encode_mir;
-                needs_inline || sig.header.constness == hir::Constness::Const || always_encode_mir
+                needs_inline
+                    || sig.header.constness == hir::Constness::Const
+                    || always_encode_mir
+                    || (!self.metadata_output_only()
+                        && tcx.cross_crate_inlinable(def_id.expect_local()))
             }
             _ => false,
         };
@@ -1898,7 +1906,10 @@ impl<'tcx, 'v> ParItemLikeVisitor<'v> for PrefetchVisitor<'tcx> {
                 let generics = tcx.generics_of(def_id.to_def_id());
                 let needs_inline = generics.requires_monomorphization(tcx)
                     || tcx.codegen_fn_attrs(def_id.to_def_id()).requests_inline();
-                if needs_inline || sig.header.constness == hir::Constness::Const {
+                if needs_inline
+                    || sig.header.constness == hir::Constness::Const
+                    || tcx.cross_crate_inlinable(def_id)
+                {
                     self.prefetch_mir(def_id)
                 }
             }
@@ -1924,7 +1935,7 @@ impl<'tcx, 'v> ParItemLikeVisitor<'v> for PrefetchVisitor<'tcx> {
                 let needs_inline = generics.requires_monomorphization(tcx)
                     || tcx.codegen_fn_attrs(def_id.to_def_id()).requests_inline();
                 let is_const_fn = sig.header.constness == hir::Constness::Const;
-                if needs_inline || is_const_fn {
+                if needs_inline || is_const_fn || tcx.cross_crate_inlinable(def_id) {
                     self.prefetch_mir(def_id)
                 }
             }
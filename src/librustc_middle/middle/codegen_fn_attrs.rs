@@ -79,6 +79,9 @@ bitflags! {
         /// #[ffi_const]: applies clang's `const` attribute to a foreign function
         /// declaration.
         const FFI_CONST                 = 1 << 13;
+        /// `#[no_instrument_function]`: an indicator that `-Z instrument-mcount`
+        /// should not instrument the function.
+        const NO_INSTRUMENT_FUNCTION    = 1 << 14;
     }
 }
 
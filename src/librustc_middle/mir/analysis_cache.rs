@@ -0,0 +1,86 @@
+//! Lazily compute analyses of a body that several passes need, so that they can share the
+//! results instead of each computing them again.
+//!
+//! The analyses are thrown away along with the predecessor cache, whenever the blocks may have
+//! changed. Since passes can also change `local_decls` directly, `run_passes` throws them away
+//! after every pass that reports a change as well.
+
+use rustc_data_structures::graph::dominators::{dominators, Dominators};
+use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
+use rustc_data_structures::sync::OnceCell;
+use rustc_index::bit_set::BitSet;
+use rustc_index::vec::IndexVec;
+use rustc_serialize as serialize;
+
+use crate::mir::{traversal, BasicBlock, Body, Local};
+
+#[derive(Clone, Debug)]
+pub(super) struct AnalysisCache {
+    dominators: OnceCell<Dominators<BasicBlock>>,
+    postorder: OnceCell<Vec<BasicBlock>>,
+    live_locals_on_entry: OnceCell<IndexVec<BasicBlock, BitSet<Local>>>,
+}
+
+impl AnalysisCache {
+    #[inline]
+    pub(super) fn new() -> Self {
+        AnalysisCache {
+            dominators: OnceCell::new(),
+            postorder: OnceCell::new(),
+            live_locals_on_entry: OnceCell::new(),
+        }
+    }
+
+    /// Invalidates every analysis.
+    #[inline]
+    pub(super) fn invalidate(&mut self) {
+        *self = AnalysisCache::new();
+    }
+
+    /// Returns the dominators of the blocks of `body`.
+    #[inline]
+    pub(super) fn dominators(&self, body: &Body<'_>) -> &Dominators<BasicBlock> {
+        self.dominators.get_or_init(|| dominators(body))
+    }
+
+    /// Returns the blocks of `body` that are reachable from the start block, in postorder.
+    #[inline]
+    pub(super) fn postorder(&self, body: &Body<'_>) -> &[BasicBlock] {
+        self.postorder.get_or_init(|| traversal::postorder(body).map(|(bb, _)| bb).collect())
+    }
+
+    /// Returns the locals that are live on entry to each block, computing them with `compute`
+    /// if needed.
+    #[inline]
+    pub(super) fn live_locals_on_entry(
+        &self,
+        compute: impl FnOnce() -> IndexVec<BasicBlock, BitSet<Local>>,
+    ) -> &IndexVec<BasicBlock, BitSet<Local>> {
+        self.live_locals_on_entry.get_or_init(compute)
+    }
+}
+
+impl serialize::Encodable for AnalysisCache {
+    #[inline]
+    fn encode<S: serialize::Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        serialize::Encodable::encode(&(), s)
+    }
+}
+
+impl serialize::Decodable for AnalysisCache {
+    #[inline]
+    fn decode<D: serialize::Decoder>(d: &mut D) -> Result<Self, D::Error> {
+        serialize::Decodable::decode(d).map(|_v: ()| Self::new())
+    }
+}
+
+impl<CTX> HashStable<CTX> for AnalysisCache {
+    #[inline]
+    fn hash_stable(&self, _: &mut CTX, _: &mut StableHasher) {
+        // do nothing
+    }
+}
+
+CloneTypeFoldableAndLiftImpls! {
+    AnalysisCache,
+}
@@ -23,3 +23,11 @@ pub mod coverage_unreachable_args {
     pub const START_BYTE_POS: usize = 0;
     pub const END_BYTE_POS: usize = 1;
 }
+
+/// A source code region that `InstrumentCoverage` injected a counter or counter expression for,
+/// as a range of byte positions in the `SourceMap`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, RustcEncodable, RustcDecodable, HashStable)]
+pub struct CodeRegion {
+    pub start_byte_pos: u32,
+    pub end_byte_pos: u32,
+}
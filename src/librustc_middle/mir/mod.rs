@@ -20,9 +20,9 @@ use rustc_target::abi::VariantIdx;
 use polonius_engine::Atom;
 pub use rustc_ast::ast::Mutability;
 use rustc_data_structures::fx::FxHashSet;
-use rustc_data_structures::graph::dominators::{dominators, Dominators};
+use rustc_data_structures::graph::dominators::Dominators;
 use rustc_data_structures::graph::{self, GraphSuccessors};
-use rustc_index::bit_set::BitMatrix;
+use rustc_index::bit_set::{BitMatrix, BitSet};
 use rustc_index::vec::{Idx, IndexVec};
 use rustc_macros::HashStable;
 use rustc_serialize::{Decodable, Encodable};
@@ -36,14 +36,18 @@ use std::ops::{Index, IndexMut};
 use std::slice;
 use std::{iter, mem, option};
 
+use self::analysis_cache::AnalysisCache;
 use self::predecessors::{PredecessorCache, Predecessors};
+use self::switch_sources::{SwitchSourceCache, SwitchSources};
 pub use self::query::*;
 
+mod analysis_cache;
 pub mod coverage;
 pub mod interpret;
 pub mod mono;
 mod predecessors;
 mod query;
+mod switch_sources;
 pub mod tcx;
 pub mod terminator;
 pub use terminator::*;
@@ -80,7 +84,9 @@ pub enum MirPhase {
     Const = 1,
     Validated = 2,
     DropElab = 3,
-    Optimized = 4,
+    /// The optimizations up to and including inlining have run.
+    Inlined = 4,
+    Optimized = 5,
 }
 
 impl MirPhase {
@@ -165,7 +171,36 @@ pub struct Body<'tcx> {
     /// FIXME(oli-obk): rewrite the promoted during promotion to eliminate the cell components.
     pub ignore_interior_mut_in_const_validation: bool,
 
+    /// A suggested assignment of locals to stack slots, computed by the `StackSlotHints` pass.
+    /// Locals that are assigned the same slot are never storage-live at the same time, so
+    /// codegen may back them with a single allocation. Locals without a slot (arguments, the
+    /// return place and locals without storage annotations) must get their own allocation.
+    pub stack_slot_hints: Option<IndexVec<Local, Option<StackSlot>>>,
+
+    /// The relative likelihood of the targets of the `switchInt` terminating each block, in the
+    /// order of its targets, computed by the `BranchWeights` pass. Codegen passes them on as
+    /// branch weights. The weights of blocks with other terminators, or whose targets are all
+    /// equally likely, are empty.
+    pub branch_weights: Option<IndexVec<BasicBlock, Vec<u32>>>,
+
+    /// The code regions `InstrumentCoverage` injected counters and counter expressions for.
+    /// Optimizations may remove the calls to the coverage intrinsics, but not these regions, so
+    /// that code which was optimized away is still reported as never executed.
+    pub coverage_regions: Vec<coverage::CodeRegion>,
+
+    /// With `-Z mir-provenance`, the locations of the statements of the MIR before optimizations
+    /// that every statement of the optimized MIR derives from, indexed by block and statement
+    /// index. Statements that were synthesized by a pass or inlined from another body don't
+    /// derive from any statement.
+    pub statement_provenance: Option<IndexVec<BasicBlock, Vec<Vec<Location>>>>,
+
+    /// The bodies of the functions that `OutlineColdBlocks` moved cold blocks of this body into,
+    /// indexed by `InstanceDef::Outlined`. Only calls in this body reach them.
+    pub outlined: Vec<Body<'tcx>>,
+
     predecessor_cache: PredecessorCache,
+    switch_source_cache: SwitchSourceCache,
+    analysis_cache: AnalysisCache,
 }
 
 impl<'tcx> Body<'tcx> {
@@ -203,7 +238,14 @@ impl<'tcx> Body<'tcx> {
             span,
             required_consts: Vec::new(),
             ignore_interior_mut_in_const_validation: false,
+            stack_slot_hints: None,
+            branch_weights: None,
+            coverage_regions: Vec::new(),
+            statement_provenance: None,
+            outlined: Vec::new(),
             predecessor_cache: PredecessorCache::new(),
+            switch_source_cache: SwitchSourceCache::new(),
+            analysis_cache: AnalysisCache::new(),
         }
     }
 
@@ -229,7 +271,14 @@ impl<'tcx> Body<'tcx> {
             generator_kind: None,
             var_debug_info: Vec::new(),
             ignore_interior_mut_in_const_validation: false,
+            stack_slot_hints: None,
+            branch_weights: None,
+            coverage_regions: Vec::new(),
+            statement_provenance: None,
+            outlined: Vec::new(),
             predecessor_cache: PredecessorCache::new(),
+            switch_source_cache: SwitchSourceCache::new(),
+            analysis_cache: AnalysisCache::new(),
         }
     }
 
@@ -241,19 +290,33 @@ impl<'tcx> Body<'tcx> {
     #[inline]
     pub fn basic_blocks_mut(&mut self) -> &mut IndexVec<BasicBlock, BasicBlockData<'tcx>> {
         // Because the user could mutate basic block terminators via this reference, we need to
-        // invalidate the predecessor cache.
+        // invalidate the caches derived from the control-flow graph.
         //
         // FIXME: Use a finer-grained API for this, so only transformations that alter terminators
-        // invalidate the predecessor cache.
-        self.predecessor_cache.invalidate();
+        // invalidate the caches.
+        self.invalidate_cfg_cache();
         &mut self.basic_blocks
     }
 
+    #[inline]
+    fn invalidate_cfg_cache(&mut self) {
+        self.predecessor_cache.invalidate();
+        self.switch_source_cache.invalidate();
+        self.analysis_cache.invalidate();
+    }
+
+    /// Throws away the analyses cached for this body, for when it changed in a way they may
+    /// depend on without going through `basic_blocks_mut`, e.g. by adding locals.
+    #[inline]
+    pub fn invalidate_analyses(&mut self) {
+        self.analysis_cache.invalidate();
+    }
+
     #[inline]
     pub fn basic_blocks_and_local_decls_mut(
         &mut self,
     ) -> (&mut IndexVec<BasicBlock, BasicBlockData<'tcx>>, &mut LocalDecls<'tcx>) {
-        self.predecessor_cache.invalidate();
+        self.invalidate_cfg_cache();
         (&mut self.basic_blocks, &mut self.local_decls)
     }
 
@@ -265,7 +328,7 @@ impl<'tcx> Body<'tcx> {
         &mut LocalDecls<'tcx>,
         &mut Vec<VarDebugInfo<'tcx>>,
     ) {
-        self.predecessor_cache.invalidate();
+        self.invalidate_cfg_cache();
         (&mut self.basic_blocks, &mut self.local_decls, &mut self.var_debug_info)
     }
 
@@ -407,9 +470,33 @@ impl<'tcx> Body<'tcx> {
         self.predecessor_cache.compute(&self.basic_blocks)
     }
 
+    /// Returns, for each edge from a block ending in `SwitchInt` to one of its targets, the
+    /// values of the discriminant that take that edge.
+    #[inline]
+    pub fn switch_sources(&self) -> &SwitchSources {
+        self.switch_source_cache.compute(&self.basic_blocks)
+    }
+
     #[inline]
-    pub fn dominators(&self) -> Dominators<BasicBlock> {
-        dominators(self)
+    pub fn dominators(&self) -> &Dominators<BasicBlock> {
+        self.analysis_cache.dominators(self)
+    }
+
+    /// Returns the blocks that are reachable from `START_BLOCK`, in postorder.
+    #[inline]
+    pub fn postorder(&self) -> &[BasicBlock] {
+        self.analysis_cache.postorder(self)
+    }
+
+    /// Returns the locals that are live on entry to each block, computing them with `compute`
+    /// if this body has no up to date result. The liveness analysis itself lives in
+    /// `rustc_mir::util::liveness`, which passes should call instead.
+    #[inline]
+    pub fn live_locals_on_entry(
+        &self,
+        compute: impl FnOnce() -> IndexVec<BasicBlock, BitSet<Local>>,
+    ) -> &IndexVec<BasicBlock, BitSet<Local>> {
+        self.analysis_cache.live_locals_on_entry(compute)
     }
 }
 
@@ -619,6 +706,14 @@ impl Atom for Local {
     }
 }
 
+rustc_index::newtype_index! {
+    /// A stack slot that may be shared by several locals. See `Body::stack_slot_hints`.
+    pub struct StackSlot {
+        derive [HashStable]
+        DEBUG_FORMAT = "slot{}",
+    }
+}
+
 /// Classifies locals into categories. See `Body::local_kind`.
 #[derive(PartialEq, Eq, Debug, HashStable)]
 pub enum LocalKind {
@@ -1098,15 +1193,23 @@ pub enum InlineAsmOperand<'tcx> {
     SymStatic {
         def_id: DefId,
     },
+    Label {
+        /// Index into the `labels` of the `InlineAsm` terminator.
+        target_index: usize,
+    },
 }
 
 /// Type for MIR `Assert` terminator error messages.
 pub type AssertMessage<'tcx> = AssertKind<Operand<'tcx>>;
 
-pub type Successors<'a> =
-    iter::Chain<option::IntoIter<&'a BasicBlock>, slice::Iter<'a, BasicBlock>>;
-pub type SuccessorsMut<'a> =
-    iter::Chain<option::IntoIter<&'a mut BasicBlock>, slice::IterMut<'a, BasicBlock>>;
+pub type Successors<'a> = iter::Chain<
+    iter::Chain<option::IntoIter<&'a BasicBlock>, slice::Iter<'a, BasicBlock>>,
+    option::IntoIter<&'a BasicBlock>,
+>;
+pub type SuccessorsMut<'a> = iter::Chain<
+    iter::Chain<option::IntoIter<&'a mut BasicBlock>, slice::IterMut<'a, BasicBlock>>,
+    option::IntoIter<&'a mut BasicBlock>,
+>;
 
 impl<'tcx> BasicBlockData<'tcx> {
     pub fn new(terminator: Option<Terminator<'tcx>>) -> BasicBlockData<'tcx> {
@@ -1931,7 +2034,7 @@ pub enum AggregateKind<'tcx> {
     Generator(DefId, SubstsRef<'tcx>, hir::Movability),
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, RustcEncodable, RustcDecodable, HashStable)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, RustcEncodable, RustcDecodable, HashStable)]
 pub enum BinOp {
     /// The `+` operator (addition)
     Add,
@@ -1977,6 +2080,16 @@ impl BinOp {
             _ => false,
         }
     }
+
+    /// Whether the result does not depend on the order of the operands. For the checked
+    /// operations, this includes the overflow flag.
+    pub fn is_commutative(self) -> bool {
+        use self::BinOp::*;
+        match self {
+            Add | Mul | BitXor | BitAnd | BitOr | Eq | Ne => true,
+            Sub | Div | Rem | Shl | Shr | Lt | Le | Ge | Gt | Offset => false,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, RustcEncodable, RustcDecodable, HashStable)]
@@ -1987,7 +2100,7 @@ pub enum NullOp {
     Box,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, RustcEncodable, RustcDecodable, HashStable)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, RustcEncodable, RustcDecodable, HashStable)]
 pub enum UnOp {
     /// The `!` operator for logical inversion
     Not,
@@ -2450,7 +2563,8 @@ impl graph::WithPredecessors for Body<'tcx> {
 /// `Location` represents the position of the start of the statement; or, if
 /// `statement_index` equals the number of statements, then the start of the
 /// terminator.
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd, HashStable)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd, RustcEncodable, RustcDecodable)]
+#[derive(HashStable)]
 pub struct Location {
     /// The block that the location is within.
     pub block: BasicBlock,
@@ -90,6 +90,12 @@ impl<'tcx> MonoItem<'tcx> {
 
         match *self {
             MonoItem::Fn(ref instance) => {
+                // Every crate that instantiates the MIR of a function whose blocks were outlined,
+                // which other crates only do for `#[inline]` functions, needs its own copy of them.
+                if let InstanceDef::Outlined(def_id, _) = instance.def {
+                    return InstantiationMode::GloballyShared { may_conflict: !def_id.is_local() };
+                }
+
                 let entry_def_id = tcx.entry_fn(LOCAL_CRATE).map(|(id, _)| id);
                 // If this function isn't inlined or otherwise has an extern
                 // indicator, then we'll be creating a globally shared version.
@@ -356,7 +362,8 @@ impl<'tcx> CodegenUnit<'tcx> {
                             | InstanceDef::Virtual(..)
                             | InstanceDef::ClosureOnceShim { .. }
                             | InstanceDef::DropGlue(..)
-                            | InstanceDef::CloneShim(..) => None,
+                            | InstanceDef::CloneShim(..)
+                            | InstanceDef::Outlined(..) => None,
                         }
                     }
                     MonoItem::Static(def_id) => {
@@ -14,6 +14,7 @@ use smallvec::SmallVec;
 use std::cell::Cell;
 use std::fmt::{self, Debug};
 
+use super::coverage::CodeRegion;
 use super::{Field, SourceInfo};
 
 #[derive(Copy, Clone, PartialEq, RustcEncodable, RustcDecodable, HashStable)]
@@ -242,6 +243,48 @@ pub struct ConstQualifs {
     pub custom_eq: bool,
 }
 
+/// An estimate of the stack frame of a body, computed from its optimized MIR before the backend
+/// gets to allocate anything. See the `mir_stack_usage` query.
+#[derive(Clone, Copy, Debug, Default, RustcEncodable, RustcDecodable, HashStable)]
+pub struct StackUsage {
+    /// Bytes needed by the locals, each padded to its alignment. Locals that share a slot
+    /// according to `Body::stack_slot_hints` are only counted once.
+    pub locals_size: u64,
+    /// The largest alignment required by any local.
+    pub max_align: u64,
+    /// The number of calls made by the body, each of which needs some stack of its own for
+    /// the return address and spilled arguments, depending on the target.
+    pub call_sites: u64,
+    /// Whether the layout of some local depends on generic parameters, making `locals_size`
+    /// a lower bound.
+    pub is_lower_bound: bool,
+}
+
+/// The kind of a body that has MIR, i.e. of an entry of `mir_keys`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, RustcEncodable, RustcDecodable, HashStable)]
+pub enum BodyKind {
+    /// A function or method, including `const fn`s.
+    Fn,
+    Closure,
+    /// A generator, including the ones that `async` blocks and functions desugar to.
+    Generator,
+    /// A `const`, an associated `const` or an anonymous constant like an array length.
+    Const,
+    /// A `static` or `static mut`.
+    Static,
+    /// The constructor of a tuple struct or tuple-like enum variant.
+    Ctor,
+}
+
+/// What kind of body a `mir_keys` entry is. See the `mir_body_kind` query.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, RustcEncodable, RustcDecodable, HashStable)]
+pub struct BodyClassification {
+    pub kind: BodyKind,
+    /// The const context of the body, if it is const-checked. Closures are never const-checked,
+    /// even if they are defined in a const context.
+    pub const_context: Option<hir::ConstContext>,
+}
+
 /// After we borrow check a closure, we are left with various
 /// requirements that we have inferred between the free regions that
 /// appear in the closure's signature or on its field types. These
@@ -405,6 +448,18 @@ pub struct CoverageInfo {
 
     /// The total number of coverage region counter expressions added to the MIR `Body`.
     pub num_expressions: u32,
+
+    /// The number of distinct code regions that were instrumented, including the unreachable
+    /// ones.
+    pub num_regions: u32,
+
+    /// The code regions whose counters and expressions were all removed by MIR optimizations,
+    /// which means they are never executed.
+    pub unreachable_regions: Vec<CodeRegion>,
+
+    /// The hash of the function's source, as passed to the injected counters, or 0 if none of
+    /// them is left and the function is not local.
+    pub function_source_hash: u64,
 }
 
 impl<'tcx> TyCtxt<'tcx> {
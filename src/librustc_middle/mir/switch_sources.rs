@@ -0,0 +1,83 @@
+//! Lazily compute, for each edge out of a `SwitchInt`, the values of the discriminant that take
+//! it.
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
+use rustc_data_structures::sync::OnceCell;
+use rustc_index::vec::IndexVec;
+use rustc_serialize as serialize;
+use smallvec::SmallVec;
+
+use crate::mir::{BasicBlock, BasicBlockData, TerminatorKind};
+
+/// Maps `(target, switch)` to the values of the discriminant of the `SwitchInt` terminating
+/// `switch` that lead to `target`. `None` stands for the "otherwise" edge.
+pub type SwitchSources = FxHashMap<(BasicBlock, BasicBlock), SmallVec<[Option<u128>; 1]>>;
+
+#[derive(Clone, Debug)]
+pub(super) struct SwitchSourceCache {
+    cache: OnceCell<SwitchSources>,
+}
+
+impl SwitchSourceCache {
+    #[inline]
+    pub(super) fn new() -> Self {
+        SwitchSourceCache { cache: OnceCell::new() }
+    }
+
+    /// Invalidates the switch source cache. This has the same preconditions as invalidating the
+    /// predecessor cache, and is done at the same time.
+    #[inline]
+    pub(super) fn invalidate(&mut self) {
+        self.cache = OnceCell::new();
+    }
+
+    /// Returns the switch sources for this MIR.
+    #[inline]
+    pub(super) fn compute(
+        &self,
+        basic_blocks: &IndexVec<BasicBlock, BasicBlockData<'_>>,
+    ) -> &SwitchSources {
+        self.cache.get_or_init(|| {
+            let mut switch_sources = SwitchSources::default();
+            for (bb, data) in basic_blocks.iter_enumerated() {
+                if let Some(TerminatorKind::SwitchInt { values, targets, .. }) =
+                    data.terminator.as_ref().map(|terminator| &terminator.kind)
+                {
+                    // The last target is the "otherwise" edge, which has no value.
+                    let values = values.iter().copied().map(Some).chain(Some(None));
+                    for (&target, value) in targets.iter().zip(values) {
+                        switch_sources.entry((target, bb)).or_default().push(value);
+                    }
+                }
+            }
+
+            switch_sources
+        })
+    }
+}
+
+impl serialize::Encodable for SwitchSourceCache {
+    #[inline]
+    fn encode<S: serialize::Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        serialize::Encodable::encode(&(), s)
+    }
+}
+
+impl serialize::Decodable for SwitchSourceCache {
+    #[inline]
+    fn decode<D: serialize::Decoder>(d: &mut D) -> Result<Self, D::Error> {
+        serialize::Decodable::decode(d).map(|_v: ()| Self::new())
+    }
+}
+
+impl<CTX> HashStable<CTX> for SwitchSourceCache {
+    #[inline]
+    fn hash_stable(&self, _: &mut CTX, _: &mut StableHasher) {
+        // do nothing
+    }
+}
+
+CloneTypeFoldableAndLiftImpls! {
+    SwitchSourceCache,
+}
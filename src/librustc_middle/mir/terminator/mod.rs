@@ -192,6 +192,14 @@ pub enum TerminatorKind<'tcx> {
         /// Destination block after the inline assembly returns, unless it is
         /// diverging (InlineAsmOptions::NORETURN).
         destination: Option<BasicBlock>,
+
+        /// Blocks the inline assembly may jump to instead of falling through to
+        /// `destination` (asm goto), indexed by `InlineAsmOperand::Label`.
+        labels: Vec<BasicBlock>,
+
+        /// Cleanups to be done if the inline assembly unwinds, which it may only do with
+        /// `InlineAsmOptions::MAY_UNWIND`.
+        cleanup: Option<BasicBlock>,
     },
 }
 #[derive(Clone, Debug, RustcEncodable, RustcDecodable, HashStable)]
@@ -236,14 +244,16 @@ impl<'tcx> TerminatorKind<'tcx> {
 
     pub fn successors(&self) -> Successors<'_> {
         use self::TerminatorKind::*;
-        match *self {
+        // Inline assembly is the only terminator with optional successors around a slice of
+        // them, so its unwind edge is chained on separately.
+        let mut unwind = None;
+        let successors = match *self {
             Resume
             | Abort
             | GeneratorDrop
             | Return
             | Unreachable
-            | Call { destination: None, cleanup: None, .. }
-            | InlineAsm { destination: None, .. } => None.into_iter().chain(&[]),
+            | Call { destination: None, cleanup: None, .. } => None.into_iter().chain(&[]),
             Goto { target: ref t }
             | Call { destination: None, cleanup: Some(ref t), .. }
             | Call { destination: Some((_, ref t)), cleanup: None, .. }
@@ -251,8 +261,7 @@ impl<'tcx> TerminatorKind<'tcx> {
             | DropAndReplace { target: ref t, unwind: None, .. }
             | Drop { target: ref t, unwind: None, .. }
             | Assert { target: ref t, cleanup: None, .. }
-            | FalseUnwind { real_target: ref t, unwind: None }
-            | InlineAsm { destination: Some(ref t), .. } => Some(t).into_iter().chain(&[]),
+            | FalseUnwind { real_target: ref t, unwind: None } => Some(t).into_iter().chain(&[]),
             Call { destination: Some((_, ref t)), cleanup: Some(ref u), .. }
             | Yield { resume: ref t, drop: Some(ref u), .. }
             | DropAndReplace { target: ref t, unwind: Some(ref u), .. }
@@ -265,19 +274,24 @@ impl<'tcx> TerminatorKind<'tcx> {
             FalseEdge { ref real_target, ref imaginary_target } => {
                 Some(real_target).into_iter().chain(slice::from_ref(imaginary_target))
             }
-        }
+            InlineAsm { ref destination, ref labels, ref cleanup, .. } => {
+                unwind = cleanup.as_ref();
+                destination.as_ref().into_iter().chain(&labels[..])
+            }
+        };
+        successors.chain(unwind)
     }
 
     pub fn successors_mut(&mut self) -> SuccessorsMut<'_> {
         use self::TerminatorKind::*;
-        match *self {
+        let mut unwind = None;
+        let successors = match *self {
             Resume
             | Abort
             | GeneratorDrop
             | Return
             | Unreachable
-            | Call { destination: None, cleanup: None, .. }
-            | InlineAsm { destination: None, .. } => None.into_iter().chain(&mut []),
+            | Call { destination: None, cleanup: None, .. } => None.into_iter().chain(&mut []),
             Goto { target: ref mut t }
             | Call { destination: None, cleanup: Some(ref mut t), .. }
             | Call { destination: Some((_, ref mut t)), cleanup: None, .. }
@@ -285,8 +299,9 @@ impl<'tcx> TerminatorKind<'tcx> {
             | DropAndReplace { target: ref mut t, unwind: None, .. }
             | Drop { target: ref mut t, unwind: None, .. }
             | Assert { target: ref mut t, cleanup: None, .. }
-            | FalseUnwind { real_target: ref mut t, unwind: None }
-            | InlineAsm { destination: Some(ref mut t), .. } => Some(t).into_iter().chain(&mut []),
+            | FalseUnwind { real_target: ref mut t, unwind: None } => {
+                Some(t).into_iter().chain(&mut [])
+            }
             Call { destination: Some((_, ref mut t)), cleanup: Some(ref mut u), .. }
             | Yield { resume: ref mut t, drop: Some(ref mut u), .. }
             | DropAndReplace { target: ref mut t, unwind: Some(ref mut u), .. }
@@ -299,7 +314,12 @@ impl<'tcx> TerminatorKind<'tcx> {
             FalseEdge { ref mut real_target, ref mut imaginary_target } => {
                 Some(real_target).into_iter().chain(slice::from_mut(imaginary_target))
             }
-        }
+            InlineAsm { ref mut destination, ref mut labels, ref mut cleanup, .. } => {
+                unwind = cleanup.as_mut();
+                destination.as_mut().into_iter().chain(&mut labels[..])
+            }
+        };
+        successors.chain(unwind)
     }
 
     pub fn unwind(&self) -> Option<&Option<BasicBlock>> {
@@ -312,10 +332,10 @@ impl<'tcx> TerminatorKind<'tcx> {
             | TerminatorKind::GeneratorDrop
             | TerminatorKind::Yield { .. }
             | TerminatorKind::SwitchInt { .. }
-            | TerminatorKind::FalseEdge { .. }
-            | TerminatorKind::InlineAsm { .. } => None,
+            | TerminatorKind::FalseEdge { .. } => None,
             TerminatorKind::Call { cleanup: ref unwind, .. }
             | TerminatorKind::Assert { cleanup: ref unwind, .. }
+            | TerminatorKind::InlineAsm { cleanup: ref unwind, .. }
             | TerminatorKind::DropAndReplace { ref unwind, .. }
             | TerminatorKind::Drop { ref unwind, .. }
             | TerminatorKind::FalseUnwind { ref unwind, .. } => Some(unwind),
@@ -332,10 +352,10 @@ impl<'tcx> TerminatorKind<'tcx> {
             | TerminatorKind::GeneratorDrop
             | TerminatorKind::Yield { .. }
             | TerminatorKind::SwitchInt { .. }
-            | TerminatorKind::FalseEdge { .. }
-            | TerminatorKind::InlineAsm { .. } => None,
+            | TerminatorKind::FalseEdge { .. } => None,
             TerminatorKind::Call { cleanup: ref mut unwind, .. }
             | TerminatorKind::Assert { cleanup: ref mut unwind, .. }
+            | TerminatorKind::InlineAsm { cleanup: ref mut unwind, .. }
             | TerminatorKind::DropAndReplace { ref mut unwind, .. }
             | TerminatorKind::Drop { ref mut unwind, .. }
             | TerminatorKind::FalseUnwind { ref mut unwind, .. } => Some(unwind),
@@ -454,6 +474,9 @@ impl<'tcx> TerminatorKind<'tcx> {
                         InlineAsmOperand::SymStatic { def_id } => {
                             write!(fmt, "sym_static {:?}", def_id)?;
                         }
+                        InlineAsmOperand::Label { target_index } => {
+                            write!(fmt, "label{}", target_index)?;
+                        }
                     }
                 }
                 write!(fmt, ", options({:?}))", options)
@@ -500,8 +523,22 @@ impl<'tcx> TerminatorKind<'tcx> {
             FalseEdge { .. } => vec!["real".into(), "imaginary".into()],
             FalseUnwind { unwind: Some(_), .. } => vec!["real".into(), "cleanup".into()],
             FalseUnwind { unwind: None, .. } => vec!["real".into()],
-            InlineAsm { destination: Some(_), .. } => vec!["".into()],
-            InlineAsm { destination: None, .. } => vec![],
+            InlineAsm { destination: Some(_), ref labels, cleanup: None, .. }
+                if labels.is_empty() =>
+            {
+                vec!["".into()]
+            }
+            InlineAsm { destination, ref labels, cleanup, .. } => {
+                let mut successors = Vec::with_capacity(labels.len() + 2);
+                if destination.is_some() {
+                    successors.push("return".into());
+                }
+                successors.extend((0..labels.len()).map(|i| format!("label{}", i).into()));
+                if cleanup.is_some() {
+                    successors.push("unwind".into());
+                }
+                successors
+            }
         }
     }
 }
@@ -5,9 +5,11 @@ use crate::ty;
 
 CloneTypeFoldableAndLiftImpls! {
     BlockTailInfo,
+    coverage::CodeRegion,
     MirPhase,
     SourceInfo,
     FakeReadCause,
+    Location,
     RetagKind,
     SourceScope,
     SourceScopeData,
@@ -78,12 +80,22 @@ impl<'tcx> TypeFoldable<'tcx> for Terminator<'tcx> {
                 FalseEdge { real_target, imaginary_target }
             }
             FalseUnwind { real_target, unwind } => FalseUnwind { real_target, unwind },
-            InlineAsm { template, ref operands, options, line_spans, destination } => InlineAsm {
+            InlineAsm {
+                template,
+                ref operands,
+                options,
+                line_spans,
+                destination,
+                ref labels,
+                cleanup,
+            } => InlineAsm {
                 template,
                 operands: operands.fold_with(folder),
                 options,
                 line_spans,
                 destination,
+                labels: labels.clone(),
+                cleanup,
             },
         };
         Terminator { source_info: self.source_info, kind }
@@ -526,6 +526,8 @@ macro_rules! make_mir_visitor {
                         options: _,
                         line_spans: _,
                         destination: _,
+                        labels: _,
+                        cleanup: _,
                     } => {
                         for op in operands {
                             match op {
@@ -556,6 +558,7 @@ macro_rules! make_mir_visitor {
                                     self.visit_constant(value, location);
                                 }
                                 InlineAsmOperand::SymStatic { def_id: _ } => {}
+                                InlineAsmOperand::Label { target_index: _ } => {}
                             }
                         }
                     }
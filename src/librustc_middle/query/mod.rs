@@ -205,6 +205,11 @@ rustc_queries! {
             desc { "getting a list of all mir_keys" }
         }
 
+        /// Classifies an entry of `mir_keys` by the kind of its body and its const context.
+        query mir_body_kind(key: LocalDefId) -> mir::BodyClassification {
+            desc { |tcx| "classifying the body of `{}`", tcx.def_path_str(key.to_def_id()) }
+        }
+
         /// Maps DefId's that have an associated `mir::Body` to the result
         /// of the MIR const-checking pass. This is the set of qualifs in
         /// the final value of a `const`.
@@ -260,6 +265,13 @@ rustc_queries! {
             }
         }
 
+        /// MIR after the optimization passes up to and including inlining have run. It is
+        /// stolen by `optimized_mir`, which runs the remaining passes.
+        query mir_inlined(key: ty::WithOptConstParam<LocalDefId>) -> &'tcx Steal<mir::Body<'tcx>> {
+            no_hash
+            desc { |tcx| "inlining MIR for `{}`", tcx.def_path_str(key.did.to_def_id()) }
+        }
+
         /// MIR after our optimization passes have run. This is MIR that is ready
         /// for codegen. This is also the only query that can fetch non-local MIR, at present.
         query optimized_mir(key: DefId) -> &'tcx mir::Body<'tcx> {
@@ -293,6 +305,27 @@ rustc_queries! {
                 tcx.def_path_str(key.0.to_def_id()),
             }
         }
+
+        /// Estimates the worst-case stack frame of a body from its optimized MIR.
+        query mir_stack_usage(key: LocalDefId) -> mir::StackUsage {
+            desc { |tcx| "estimating the stack usage of `{}`", tcx.def_path_str(key.to_def_id()) }
+        }
+
+        /// Whether the optimized MIR of a function that isn't generic or `#[inline]` is small and
+        /// self-contained enough to be exported to other crates, for them to inline.
+        query cross_crate_inlinable(key: LocalDefId) -> bool {
+            desc {
+                |tcx| "checking whether `{}` can be inlined into other crates",
+                tcx.def_path_str(key.to_def_id()),
+            }
+        }
+
+        /// The constant that a function returns on every path, if it does nothing else that could
+        /// be observed, from its optimized MIR. Calls of such functions are folded into the
+        /// constant.
+        query mir_constant_return(key: DefId) -> Option<&'tcx ty::Const<'tcx>> {
+            desc { |tcx| "finding the constant returned by `{}`", tcx.def_path_str(key) }
+        }
     }
 
     TypeChecking {
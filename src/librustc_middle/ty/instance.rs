@@ -104,6 +104,13 @@ pub enum InstanceDef<'tcx> {
     /// problems with the MIR shim bodies. `Instance::resolve` enforces this.
     // FIXME(#69925) support polymorphic MIR shim bodies properly instead.
     CloneShim(DefId, Ty<'tcx>),
+
+    /// Blocks moved out of the body of a `fn` item by `-Z mir-outline-cold-blocks`.
+    ///
+    /// The `DefId` is for the `fn` item, the `u32` indexes `Body::outlined` of its optimized MIR.
+    /// The blocks are only reached through a `fn` pointer in that MIR, so this instance has no
+    /// `fn` item type of its own, and its signature is the one of its MIR.
+    Outlined(DefId, u32),
 }
 
 impl<'tcx> Instance<'tcx> {
@@ -159,7 +166,8 @@ impl<'tcx> InstanceDef<'tcx> {
             | InstanceDef::Intrinsic(def_id)
             | InstanceDef::ClosureOnceShim { call_once: def_id }
             | InstanceDef::DropGlue(def_id, _)
-            | InstanceDef::CloneShim(def_id, _) => def_id,
+            | InstanceDef::CloneShim(def_id, _)
+            | InstanceDef::Outlined(def_id, _) => def_id,
         }
     }
 
@@ -174,7 +182,8 @@ impl<'tcx> InstanceDef<'tcx> {
             | InstanceDef::Intrinsic(def_id)
             | InstanceDef::ClosureOnceShim { call_once: def_id }
             | InstanceDef::DropGlue(def_id, _)
-            | InstanceDef::CloneShim(def_id, _) => ty::WithOptConstParam::unknown(def_id),
+            | InstanceDef::CloneShim(def_id, _)
+            | InstanceDef::Outlined(def_id, _) => ty::WithOptConstParam::unknown(def_id),
         }
     }
 
@@ -193,6 +202,8 @@ impl<'tcx> InstanceDef<'tcx> {
         let def_id = match *self {
             ty::InstanceDef::Item(def) => def.did,
             ty::InstanceDef::DropGlue(_, Some(_)) => return false,
+            // Outlined blocks are cold, so inlining them back is the last thing wanted.
+            ty::InstanceDef::Outlined(..) => return false,
             _ => return true,
         };
         match tcx.def_key(def_id).disambiguated_data.data {
@@ -212,6 +223,10 @@ impl<'tcx> InstanceDef<'tcx> {
         if self.requires_inline(tcx) {
             return true;
         }
+        if let ty::InstanceDef::Outlined(..) = *self {
+            // The `#[inline]` of the function the blocks came from is not theirs.
+            return false;
+        }
         if let ty::InstanceDef::DropGlue(.., Some(ty)) = *self {
             // Drop glue generally wants to be instantiated at every codegen
             // unit, but without an #[inline] hint. We should make this
@@ -264,6 +279,7 @@ impl<'tcx> fmt::Display for Instance<'tcx> {
             InstanceDef::ClosureOnceShim { .. } => write!(f, " - shim"),
             InstanceDef::DropGlue(_, ty) => write!(f, " - shim({:?})", ty),
             InstanceDef::CloneShim(_, ty) => write!(f, " - shim({:?})", ty),
+            InstanceDef::Outlined(_, index) => write!(f, " - outlined#{}", index),
         }
     }
 }
@@ -461,7 +477,8 @@ impl<'tcx> Instance<'tcx> {
             | InstanceDef::Intrinsic(..)
             | InstanceDef::ReifyShim(..)
             | InstanceDef::Virtual(..)
-            | InstanceDef::VtableShim(..) => Some(self.substs),
+            | InstanceDef::VtableShim(..)
+            | InstanceDef::Outlined(..) => Some(self.substs),
         }
     }
 
@@ -2306,6 +2306,19 @@ impl<'tcx> ty::Instance<'tcx> {
     // or should go through `FnAbi` instead, to avoid losing any
     // adjustments `FnAbi::of_instance` might be performing.
     fn fn_sig_for_fn_abi(&self, tcx: TyCtxt<'tcx>) -> ty::PolyFnSig<'tcx> {
+        if let ty::InstanceDef::Outlined(..) = self.def {
+            // Outlined blocks have no `fn` item type, their MIR defines their signature.
+            let body = tcx.instance_mir(self.def);
+            let inputs = body.args_iter().map(|local| body.local_decls[local].ty);
+            return ty::Binder::dummy(tcx.mk_fn_sig(
+                inputs,
+                body.return_ty(),
+                false,
+                hir::Unsafety::Normal,
+                rustc_target::spec::abi::Abi::Rust,
+            ));
+        }
+
         // FIXME(davidtwco,eddyb): A `ParamEnv` should be passed through to this function.
         let ty = self.ty(tcx, ty::ParamEnv::reveal_all());
         match ty.kind {
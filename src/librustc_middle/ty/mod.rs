@@ -3003,6 +3003,9 @@ impl<'tcx> TyCtxt<'tcx> {
             | ty::InstanceDef::ClosureOnceShim { .. }
             | ty::InstanceDef::DropGlue(..)
             | ty::InstanceDef::CloneShim(..) => self.mir_shims(instance),
+            ty::InstanceDef::Outlined(def_id, index) => {
+                &self.optimized_mir(def_id).outlined[index as usize]
+            }
         }
     }
 
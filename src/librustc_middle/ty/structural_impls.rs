@@ -270,6 +270,7 @@ CloneTypeFoldableAndLiftImpls! {
     (),
     bool,
     usize,
+    u32,
     ::rustc_target::abi::VariantIdx,
     u64,
     String,
@@ -290,6 +291,7 @@ CloneTypeFoldableAndLiftImpls! {
     ::rustc_target::spec::abi::Abi,
     crate::mir::Local,
     crate::mir::Promoted,
+    crate::mir::StackSlot,
     crate::traits::Reveal,
     crate::ty::adjustment::AutoBorrowMutability,
     crate::ty::AdtKind,
@@ -701,6 +703,9 @@ impl<'a, 'tcx> Lift<'tcx> for ty::InstanceDef<'a> {
             ty::InstanceDef::CloneShim(def_id, ref ty) => {
                 Some(ty::InstanceDef::CloneShim(def_id, tcx.lift(ty)?))
             }
+            ty::InstanceDef::Outlined(def_id, index) => {
+                Some(ty::InstanceDef::Outlined(def_id, index))
+            }
         }
     }
 }
@@ -879,6 +884,7 @@ impl<'tcx> TypeFoldable<'tcx> for ty::instance::Instance<'tcx> {
                 }
                 DropGlue(did, ty) => DropGlue(did.fold_with(folder), ty.fold_with(folder)),
                 CloneShim(did, ty) => CloneShim(did.fold_with(folder), ty.fold_with(folder)),
+                Outlined(did, index) => Outlined(did.fold_with(folder), index),
             },
         }
     }
@@ -888,9 +894,11 @@ impl<'tcx> TypeFoldable<'tcx> for ty::instance::Instance<'tcx> {
         self.substs.visit_with(visitor)
             || match self.def {
                 Item(def) => def.visit_with(visitor),
-                VtableShim(did) | ReifyShim(did) | Intrinsic(did) | Virtual(did, _) => {
-                    did.visit_with(visitor)
-                }
+                VtableShim(did)
+                | ReifyShim(did)
+                | Intrinsic(did)
+                | Virtual(did, _)
+                | Outlined(did, _) => did.visit_with(visitor),
                 FnPtrShim(did, ty) | CloneShim(did, ty) => {
                     did.visit_with(visitor) || ty.visit_with(visitor)
                 }
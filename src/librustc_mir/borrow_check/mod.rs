@@ -237,7 +237,11 @@ fn do_mir_borrowck<'a, 'tcx>(
     // write unit-tests, as well as helping with debugging.
     nll::dump_mir_results(
         infcx,
-        MirSource { instance: InstanceDef::Item(def.to_global()), promoted: None },
+        MirSource {
+            instance: InstanceDef::Item(def.to_global()),
+            promoted: None,
+            promoted_span: None,
+        },
         &body,
         &regioncx,
         &opt_closure_req,
@@ -282,7 +286,7 @@ fn do_mir_borrowck<'a, 'tcx>(
 
     for (idx, move_data_results) in promoted_errors {
         let promoted_body = &promoted[idx];
-        let dominators = promoted_body.dominators();
+        let dominators = promoted_body.dominators().clone();
 
         if let Err((move_data, move_errors)) = move_data_results {
             let mut promoted_mbcx = MirBorrowckCtxt {
@@ -316,7 +320,7 @@ fn do_mir_borrowck<'a, 'tcx>(
         };
     }
 
-    let dominators = body.dominators();
+    let dominators = body.dominators().clone();
 
     let mut mbcx = MirBorrowckCtxt {
         infcx,
@@ -750,6 +754,8 @@ impl<'cx, 'tcx> dataflow::ResultsVisitor<'cx, 'tcx> for MirBorrowckCtxt<'cx, 'tc
                 options: _,
                 line_spans: _,
                 destination: _,
+                labels: _,
+                cleanup: _,
             } => {
                 for op in operands {
                     match *op {
@@ -781,7 +787,8 @@ impl<'cx, 'tcx> dataflow::ResultsVisitor<'cx, 'tcx> for MirBorrowckCtxt<'cx, 'tc
                             }
                         }
                         InlineAsmOperand::SymFn { value: _ }
-                        | InlineAsmOperand::SymStatic { def_id: _ } => {}
+                        | InlineAsmOperand::SymStatic { def_id: _ }
+                        | InlineAsmOperand::Label { target_index: _ } => {}
                     }
                 }
             }
@@ -0,0 +1,56 @@
+//! `-Z check-ctfe-bodies`: evaluates the bodies of the local crate that can be evaluated at
+//! compile time on their own, so that CTFE errors in them are reported when the crate is built
+//! instead of when a downstream crate first uses them in a const context.
+
+use rustc_hir as hir;
+use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
+use rustc_middle::mir::interpret::GlobalId;
+use rustc_middle::mir::BodyKind;
+use rustc_middle::ty::{self, Instance, TyCtxt};
+
+pub fn check_ctfe_bodies(tcx: TyCtxt<'_>) {
+    for def_id in tcx.body_owners() {
+        if tcx.generics_of(def_id).requires_monomorphization(tcx) {
+            continue;
+        }
+
+        let classification = tcx.mir_body_kind(def_id);
+        match (classification.kind, classification.const_context) {
+            (BodyKind::Const, Some(_)) => {
+                // Errors are reported by the query.
+                let _ = tcx.const_eval_poly(def_id.to_def_id());
+            }
+            (BodyKind::Fn, Some(hir::ConstContext::ConstFn)) => {
+                let param_env = ty::ParamEnv::reveal_all();
+                let instance = Instance::mono(tcx, def_id.to_def_id());
+                if !can_evaluate_without_inputs(tcx, param_env, instance) {
+                    continue;
+                }
+                let gid = GlobalId { instance, promoted: None };
+                let _ = tcx.const_eval_raw(param_env.and(gid));
+            }
+            // Statics are evaluated anyway, and nothing else is const-evaluable on its own.
+            _ => {}
+        }
+    }
+}
+
+/// Whether `instance` is a const fn that can be evaluated like a constant, i.e. the same calls
+/// `try_eval_const_fn_call` memoizes: no arguments other than ZSTs, no implicit caller location
+/// and a return value.
+fn can_evaluate_without_inputs<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    instance: Instance<'tcx>,
+) -> bool {
+    if tcx.codegen_fn_attrs(instance.def_id()).flags.contains(CodegenFnAttrFlags::TRACK_CALLER) {
+        return false;
+    }
+
+    let sig = tcx.normalize_erasing_late_bound_regions(param_env, &tcx.fn_sig(instance.def_id()));
+    !sig.output().is_never()
+        && sig.inputs().iter().all(|&input| match tcx.layout_of(param_env.and(input)) {
+            Ok(layout) => layout.is_zst(),
+            Err(_) => false,
+        })
+}
@@ -1,22 +1,27 @@
 use rustc_middle::mir;
 use rustc_middle::ty::layout::HasTyCtxt;
+use rustc_middle::ty::subst::Subst;
 use rustc_middle::ty::{self, Ty};
 use std::borrow::Borrow;
 use std::collections::hash_map::Entry;
+use std::fmt;
 use std::hash::Hash;
 
 use rustc_data_structures::fx::FxHashMap;
 
 use rustc_ast::ast::Mutability;
 use rustc_hir::def_id::DefId;
+use rustc_index::vec::Idx;
 use rustc_middle::mir::AssertMessage;
 use rustc_session::Limit;
-use rustc_span::symbol::Symbol;
+use rustc_span::symbol::{sym, Symbol};
+use rustc_target::abi::{Align, Size, VariantIdx};
 
 use crate::interpret::{
-    self, compile_time_machine, AllocId, Allocation, Frame, GlobalId, ImmTy, InterpCx,
-    InterpResult, Memory, OpTy, PlaceTy, Pointer, Scalar,
+    self, compile_time_machine, sign_extend, AllocId, Allocation, FnVal, Frame, GlobalId, ImmTy,
+    InterpCx, InterpResult, MPlaceTy, Memory, OpTy, PlaceTy, Pointer, Scalar,
 };
+use crate::transform::lower_128bit::{lowered_op, LoweredOp};
 
 use super::error::*;
 
@@ -60,6 +65,43 @@ impl<'mir, 'tcx> InterpCx<'mir, 'tcx, CompileTimeInterpreter<'mir, 'tcx>> {
         Ok(true)
     }
 
+    /// Evaluates a call to one of the lang items that `Lower128Bit` replaced the 128-bit
+    /// operators of a `const fn` with. They aren't `const fn`s themselves.
+    ///
+    /// Returns `true` if the call has been evaluated.
+    fn hook_lowered_128bit_fn(
+        &mut self,
+        instance: ty::Instance<'tcx>,
+        args: &[OpTy<'tcx>],
+        ret: Option<(PlaceTy<'tcx>, mir::BasicBlock)>,
+    ) -> InterpResult<'tcx, bool> {
+        let (op, (dest, target)) = match (lowered_op(*self.tcx, instance.def_id()), ret) {
+            (Some(op), Some(ret)) => (op, ret),
+            _ => return Ok(false),
+        };
+        assert!(args.len() == 2);
+        let left = self.read_immediate(args[0])?;
+        let right = self.read_immediate(args[1])?;
+        match op {
+            LoweredOp::Direct(bin_op) => self.binop_ignore_overflow(bin_op, left, right, dest)?,
+            LoweredOp::Checked(bin_op) => self.binop_with_overflow(bin_op, left, right, dest)?,
+            LoweredOp::Compare => {
+                let less = self.binary_op(mir::BinOp::Lt, left, right)?.to_scalar()?.to_bool()?;
+                let equal = self.binary_op(mir::BinOp::Eq, left, right)?.to_scalar()?.to_bool()?;
+                let ordering = if less {
+                    -1
+                } else if equal {
+                    0
+                } else {
+                    1
+                };
+                self.write_scalar(Scalar::from_i8(ordering), dest)?;
+            }
+        }
+        self.return_to_block(Some(target))?;
+        Ok(true)
+    }
+
     /// "Intercept" a function call to a panic-related function
     /// because we have something special to do for it.
     /// If this returns successfully (`Ok`), the function should just be evaluated normally.
@@ -69,20 +111,115 @@ impl<'mir, 'tcx> InterpCx<'mir, 'tcx, CompileTimeInterpreter<'mir, 'tcx>> {
         args: &[OpTy<'tcx>],
     ) -> InterpResult<'tcx> {
         let def_id = instance.def_id();
-        if Some(def_id) == self.tcx.lang_items().panic_fn()
-            || Some(def_id) == self.tcx.lang_items().begin_panic_fn()
+        let lang_items = self.tcx.lang_items();
+        let msg = if Some(def_id) == lang_items.panic_fn()
+            || Some(def_id) == lang_items.begin_panic_fn()
         {
             // &'static str
             assert!(args.len() == 1);
 
             let msg_place = self.deref_operand(args[0])?;
-            let msg = Symbol::intern(self.read_str(msg_place)?);
-            let span = self.find_closest_untracked_caller_location();
-            let (file, line, col) = self.location_triple_for_span(span);
-            Err(ConstEvalErrKind::Panic { msg, file, line, col }.into())
+            Symbol::intern(self.read_str(msg_place)?)
+        } else if Some(def_id) == lang_items.panic_fmt_fn() {
+            // fmt::Arguments<'_>
+            assert!(args.len() == 1);
+
+            Symbol::intern(&self.format_panic_args(args[0])?)
+        } else if Some(def_id) == lang_items.begin_panic_fmt_fn() {
+            // &fmt::Arguments<'_>
+            assert!(args.len() == 1);
+
+            let args_place = self.deref_operand(args[0])?;
+            Symbol::intern(&self.format_panic_args(args_place.into())?)
         } else {
-            Ok(())
+            return Ok(());
+        };
+        let span = self.find_closest_untracked_caller_location();
+        let (file, line, col) = self.location_triple_for_span(span);
+        Err(ConstEvalErrKind::Panic { msg, file, line, col }.into())
+    }
+
+    /// Renders the `fmt::Arguments` of a formatted panic.
+    ///
+    /// The formatting impls are not `const`, so instead of calling them the message is rendered
+    /// here. This only supports `{}` and `{:?}` without formatting parameters, for arguments
+    /// that are integers, `bool`, `char` or `str`, or references to those.
+    fn format_panic_args(&self, arguments: OpTy<'tcx>) -> InterpResult<'tcx, String> {
+        // `fmt::Arguments { pieces, fmt, args }`
+        let pieces = self.deref_operand(self.operand_field(arguments, 0)?)?;
+        let (_, fmt) = self.read_discriminant(self.operand_field(arguments, 1)?)?;
+        let args = self.deref_operand(self.operand_field(arguments, 2)?)?;
+        if fmt != VariantIdx::new(0) {
+            throw_unsup_format!("formatting parameters are not supported in const panics");
+        }
+
+        // Every argument is preceded by a piece.
+        let mut msg = String::new();
+        for i in 0..pieces.len(self)? {
+            let piece = self.deref_operand(self.mplace_index(pieces, i)?.into())?;
+            msg.push_str(self.read_str(piece)?);
+            if i < args.len(self)? {
+                msg.push_str(&self.format_panic_arg(self.mplace_index(args, i)?)?);
+            }
         }
+        Ok(msg)
+    }
+
+    fn format_panic_arg(&self, arg: MPlaceTy<'tcx>) -> InterpResult<'tcx, String> {
+        // `fmt::ArgumentV1 { value, formatter }`
+        let value = self.read_scalar(self.mplace_field(arg, 0)?.into())?.check_init()?;
+        let formatter = self.read_scalar(self.mplace_field(arg, 1)?.into())?.check_init()?;
+        let instance = match self.memory.get_fn(formatter)? {
+            FnVal::Instance(instance) => instance,
+            FnVal::Other(never) => match never {},
+        };
+
+        // `formatter` is the `fmt` method of the `Display` or `Debug` impl for the type of the
+        // value.
+        let tcx = *self.tcx;
+        let trait_ref = tcx
+            .impl_of_method(instance.def_id())
+            .and_then(|impl_def_id| tcx.impl_trait_ref(impl_def_id))
+            .map(|trait_ref| trait_ref.subst(tcx, instance.substs));
+        let (debug, ty) = match trait_ref {
+            Some(trait_ref) if tcx.is_diagnostic_item(sym::debug_trait, trait_ref.def_id) => {
+                (true, trait_ref.self_ty())
+            }
+            Some(trait_ref) if tcx.is_diagnostic_item(sym::display_trait, trait_ref.def_id) => {
+                (false, trait_ref.self_ty())
+            }
+            _ => throw_unsup_format!("calling non-const function `{}`", instance),
+        };
+
+        let ref_layout = self.layout_of(tcx.mk_imm_ref(tcx.lifetimes.re_erased, ty))?;
+        let place = self.deref_operand(ImmTy::from_scalar(value, ref_layout).into())?;
+        self.format_panic_value(place, debug)
+    }
+
+    fn format_panic_value(&self, place: MPlaceTy<'tcx>, debug: bool) -> InterpResult<'tcx, String> {
+        let ty = place.layout.ty;
+        Ok(match ty.kind {
+            // The impls for references forward to the referent.
+            ty::Ref(..) => {
+                let place = self.deref_operand(place.into())?;
+                return self.format_panic_value(place, debug);
+            }
+            ty::Str if debug => format!("{:?}", self.read_str(place)?),
+            ty::Str => self.read_str(place)?.to_owned(),
+            ty::Char if debug => format!("{:?}", self.read_scalar(place.into())?.to_char()?),
+            ty::Char => self.read_scalar(place.into())?.to_char()?.to_string(),
+            ty::Bool => self.read_scalar(place.into())?.to_bool()?.to_string(),
+            ty::Int(_) | ty::Uint(_) => {
+                let size = place.layout.size;
+                let bits = self.force_bits(self.read_scalar(place.into())?.check_init()?, size)?;
+                if ty.is_signed() {
+                    (sign_extend(bits, size) as i128).to_string()
+                } else {
+                    bits.to_string()
+                }
+            }
+            _ => throw_unsup_format!("formatting values of type `{}` in const panics", ty),
+        })
     }
 }
 
@@ -168,6 +305,31 @@ impl<K: Hash + Eq, V> interpret::AllocMap<K, V> for FxHashMap<K, V> {
 crate type CompileTimeEvalContext<'mir, 'tcx> =
     InterpCx<'mir, 'tcx, CompileTimeInterpreter<'mir, 'tcx>>;
 
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum MemoryKind {
+    /// Memory allocated by the `const_allocate` intrinsic.
+    Heap,
+}
+
+impl fmt::Display for MemoryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryKind::Heap => write!(f, "const heap"),
+        }
+    }
+}
+
+impl interpret::MayLeak for MemoryKind {
+    #[inline(always)]
+    fn may_leak(self) -> bool {
+        match self {
+            // Heap allocations that are still around at the end of the evaluation are either
+            // interned as part of the final value or leaked.
+            MemoryKind::Heap => false,
+        }
+    }
+}
+
 impl interpret::MayLeak for ! {
     #[inline(always)]
     fn may_leak(self) -> bool {
@@ -179,6 +341,8 @@ impl interpret::MayLeak for ! {
 impl<'mir, 'tcx> interpret::Machine<'mir, 'tcx> for CompileTimeInterpreter<'mir, 'tcx> {
     compile_time_machine!(<'mir, 'tcx>);
 
+    type MemoryKind = MemoryKind;
+
     type MemoryExtra = MemoryExtra;
 
     fn find_mir_or_eval_fn(
@@ -204,6 +368,9 @@ impl<'mir, 'tcx> interpret::Machine<'mir, 'tcx> for CompileTimeInterpreter<'mir,
             } else {
                 // Some functions we support even if they are non-const -- but avoid testing
                 // that for const fn!
+                if ecx.hook_lowered_128bit_fn(instance, args, ret)? {
+                    return Ok(None);
+                }
                 ecx.hook_panic_fn(instance, args)?;
                 // We certainly do *not* want to actually call the fn
                 // though, so be sure we return here.
@@ -237,9 +404,63 @@ impl<'mir, 'tcx> interpret::Machine<'mir, 'tcx> for CompileTimeInterpreter<'mir,
         if ecx.emulate_intrinsic(instance, args, ret)? {
             return Ok(());
         }
-        // An intrinsic that we do not support
         let intrinsic_name = ecx.tcx.item_name(instance.def_id());
-        Err(ConstEvalErrKind::NeedsRfc(format!("calling intrinsic `{}`", intrinsic_name)).into())
+
+        // CTFE-specific intrinsics.
+        let (dest, ret) = match ret {
+            None => {
+                return Err(ConstEvalErrKind::NeedsRfc(format!(
+                    "calling intrinsic `{}`",
+                    intrinsic_name
+                ))
+                .into());
+            }
+            Some(p) => p,
+        };
+        match intrinsic_name {
+            sym::const_allocate => {
+                let size = ecx.read_scalar(args[0])?.to_machine_usize(ecx)?;
+                let align = ecx.read_scalar(args[1])?.to_machine_usize(ecx)?;
+                let align = match Align::from_bytes(align) {
+                    Ok(align) => align,
+                    Err(err) => throw_ub_format!("align has to be a power of 2, {}", err),
+                };
+
+                let ptr = ecx.memory.allocate(
+                    Size::from_bytes(size),
+                    align,
+                    interpret::MemoryKind::Machine(MemoryKind::Heap),
+                );
+                ecx.write_scalar(Scalar::Ptr(ptr), dest)?;
+            }
+            sym::const_deallocate => {
+                let ptr = ecx.read_scalar(args[0])?.check_init()?;
+                let size = ecx.read_scalar(args[1])?.to_machine_usize(ecx)?;
+                let align = ecx.read_scalar(args[2])?.to_machine_usize(ecx)?;
+                let align = match Align::from_bytes(align) {
+                    Ok(align) => align,
+                    Err(err) => throw_ub_format!("align has to be a power of 2, {}", err),
+                };
+
+                let ptr = ecx.force_ptr(ptr)?;
+                ecx.memory.deallocate(
+                    ptr,
+                    Some((Size::from_bytes(size), align)),
+                    interpret::MemoryKind::Machine(MemoryKind::Heap),
+                )?;
+            }
+            // An intrinsic that we do not support
+            _ => {
+                return Err(ConstEvalErrKind::NeedsRfc(format!(
+                    "calling intrinsic `{}`",
+                    intrinsic_name
+                ))
+                .into());
+            }
+        }
+
+        ecx.go_to_block(ret);
+        Ok(())
     }
 
     fn assert_panic(
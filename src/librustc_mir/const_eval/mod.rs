@@ -3,16 +3,22 @@
 use std::convert::TryFrom;
 
 use rustc_middle::mir;
-use rustc_middle::ty::{self, TyCtxt};
-use rustc_span::{source_map::DUMMY_SP, symbol::Symbol};
+use rustc_middle::ty::{self, Ty, TyCtxt};
+use rustc_span::{source_map::DUMMY_SP, symbol::Symbol, Span};
+use rustc_target::abi::LayoutOf;
 
-use crate::interpret::{intern_const_alloc_recursive, ConstValue, InternKind, InterpCx};
+use crate::interpret::{
+    intern_const_alloc_recursive, ConstValue, InternKind, InterpCx, InterpResult, MemoryKind,
+    Scalar, StackPopCleanup,
+};
 
+mod check_bodies;
 mod error;
 mod eval_queries;
 mod fn_queries;
 mod machine;
 
+pub use check_bodies::*;
 pub use error::*;
 pub use eval_queries::*;
 pub use fn_queries::*;
@@ -30,6 +36,55 @@ pub(crate) fn const_caller_location(
     ConstValue::Scalar(loc_place.ptr)
 }
 
+/// The number of terminators that `eval_const_fn_call` runs at most, which keeps the calls folded
+/// during optimization to small functions.
+const CONST_FN_CALL_STEP_LIMIT: usize = 100;
+
+/// Evaluates a call of the `const fn` `instance`, whose MIR is `body`, with the scalar `args`,
+/// for `ConstProp`. Returns the result if it is a scalar of type `ret_ty` that doesn't point to
+/// memory, and `None` if the call panics, accesses a static, runs for too long or fails otherwise,
+/// without reporting anything, since the call is then left for runtime.
+pub(crate) fn eval_const_fn_call<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    instance: ty::Instance<'tcx>,
+    body: &'tcx mir::Body<'tcx>,
+    args: &[Scalar],
+    ret_ty: Ty<'tcx>,
+    span: Span,
+) -> Option<Scalar> {
+    trace!("eval_const_fn_call: {:?}({:?})", instance, args);
+    if args.len() != body.arg_count {
+        return None;
+    }
+    let mut ecx = mk_eval_cx(tcx, span, param_env, false);
+    ecx.machine.steps_remaining = CONST_FN_CALL_STEP_LIMIT;
+    let result: InterpResult<'tcx, Scalar> = (|| {
+        let ret_layout = ecx.layout_of(ret_ty)?;
+        let ret = ecx.allocate(ret_layout, MemoryKind::Stack);
+        ecx.push_stack_frame(
+            instance,
+            body,
+            Some(ret.into()),
+            StackPopCleanup::None { cleanup: false },
+        )?;
+        for (arg, &value) in body.args_iter().zip(args) {
+            let dest = ecx.eval_place(mir::Place::from(arg))?;
+            ecx.write_scalar(value, dest)?;
+        }
+        ecx.run()?;
+        Ok(ecx.read_scalar(ret.into())?.check_init()?)
+    })();
+    match result {
+        Ok(scalar) if scalar.is_bits() => Some(scalar),
+        Ok(_) => None,
+        Err(error) => {
+            trace!("eval_const_fn_call failed: {}", error);
+            None
+        }
+    }
+}
+
 /// This function uses `unwrap` copiously, because an already validated constant
 /// must have valid fields and can thus never fail outside of compiler bugs. However, it is
 /// invoked from the pretty printer, where it can receive enums with no variants and e.g.
@@ -10,7 +10,7 @@ use rustc_graphviz as dot;
 use rustc_hir::def_id::DefId;
 use rustc_index::bit_set::BitSet;
 use rustc_index::vec::IndexVec;
-use rustc_middle::mir::{self, traversal, BasicBlock};
+use rustc_middle::mir::{self, BasicBlock};
 use rustc_middle::ty::{self, TyCtxt};
 use rustc_span::symbol::{sym, Symbol};
 
@@ -201,14 +201,15 @@ where
         let mut dirty_queue: WorkQueue<BasicBlock> =
             WorkQueue::with_none(body.basic_blocks().len());
 
+        // The postorder is cached on the body, and shared by every analysis of it.
         if A::Direction::is_forward() {
-            for (bb, _) in traversal::reverse_postorder(body) {
+            for &bb in body.postorder().iter().rev() {
                 dirty_queue.insert(bb);
             }
         } else {
             // Reverse post-order on the reverse CFG may generate a better iteration order for
             // backward dataflow analyses, but probably not enough to matter.
-            for (bb, _) in traversal::postorder(body) {
+            for &bb in body.postorder() {
                 dirty_queue.insert(bb);
             }
         }
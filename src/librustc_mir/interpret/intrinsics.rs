@@ -435,6 +435,8 @@ impl<'mir, 'tcx: 'mir, M: Machine<'mir, 'tcx>> InterpCx<'mir, 'tcx, M> {
                 // These just return their argument
                 self.copy_op(args[0], dest)?;
             }
+            // Only a marker for codegen.
+            sym::instrument_function_entry => (),
             // FIXME(#73156): Handle source code coverage in const eval
             sym::count_code_region
             | sym::coverage_counter_add
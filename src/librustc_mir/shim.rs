@@ -86,6 +86,9 @@ fn make_shim<'tcx>(tcx: TyCtxt<'tcx>, instance: ty::InstanceDef<'tcx>) -> Body<'
         ty::InstanceDef::Intrinsic(_) => {
             bug!("creating shims from intrinsics ({:?}) is unsupported", instance)
         }
+        ty::InstanceDef::Outlined(..) => {
+            bug!("outlined blocks ({:?}) have their MIR in their parent's body", instance)
+        }
     };
     debug!("make_shim({:?}) = untransformed {:?}", instance, result);
 
@@ -313,15 +316,15 @@ fn build_clone_shim<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId, self_ty: Ty<'tcx>) -
     let src = tcx.mk_place_deref(Place::from(Local::new(1 + 0)));
 
     match self_ty.kind {
+        ty::Closure(_, substs) => {
+            builder.tuple_like_shim(dest, src, param_env, substs.as_closure().upvar_tys())
+        }
+        ty::Tuple(..) => builder.tuple_like_shim(dest, src, param_env, self_ty.tuple_fields()),
         _ if is_copy => builder.copy_shim(),
         ty::Array(ty, len) => {
             let len = len.eval_usize(tcx, param_env);
             builder.array_shim(dest, src, ty, len)
         }
-        ty::Closure(_, substs) => {
-            builder.tuple_like_shim(dest, src, substs.as_closure().upvar_tys())
-        }
-        ty::Tuple(..) => builder.tuple_like_shim(dest, src, self_ty.tuple_fields()),
         _ => bug!("clone shim for `{:?}` which is not `Copy` and is not an aggregate", self_ty),
     };
 
@@ -595,17 +598,47 @@ impl CloneShimBuilder<'tcx> {
         self.block(vec![], TerminatorKind::Resume, true);
     }
 
-    fn tuple_like_shim<I>(&mut self, dest: Place<'tcx>, src: Place<'tcx>, tys: I)
-    where
+    fn tuple_like_shim<I>(
+        &mut self,
+        dest: Place<'tcx>,
+        src: Place<'tcx>,
+        param_env: ty::ParamEnv<'tcx>,
+        tys: I,
+    ) where
         I: Iterator<Item = Ty<'tcx>>,
     {
+        let tys: Vec<_> = tys.collect();
+        let is_copy: Vec<_> = tys
+            .iter()
+            .map(|ty| ty.is_copy_modulo_regions(self.tcx.at(self.span), param_env))
+            .collect();
+
+        // If every field is `Copy`, `dest = src;` clones the whole value with a single copy.
+        if is_copy.iter().all(|&is_copy| is_copy) {
+            self.copy_shim();
+            return;
+        }
+
+        // Otherwise, the `Copy` fields are copied in the last block instead of calling
+        // `Clone::clone` on them. They don't need to be dropped if cloning another field unwinds,
+        // so they take no part in the cleanup chain either.
+        let mut copies = vec![];
         let mut previous_field = None;
-        for (i, ity) in tys.enumerate() {
+        for (i, (ity, is_copy)) in tys.into_iter().zip(is_copy).enumerate() {
             let field = Field::new(i);
             let src_field = self.tcx.mk_place_field(src, field, ity);
 
             let dest_field = self.tcx.mk_place_field(dest, field, ity);
 
+            if is_copy {
+                // `dest.i = src.i;`
+                copies.push(self.make_statement(StatementKind::Assign(box (
+                    dest_field,
+                    Rvalue::Use(Operand::Copy(src_field)),
+                ))));
+                continue;
+            }
+
             // #(2i + 1) is the cleanup block for the previous clone operation
             let cleanup_block = self.block_index_offset(1);
             // #(2i + 2) is the next cloning block
@@ -637,7 +670,7 @@ impl CloneShimBuilder<'tcx> {
             previous_field = Some((dest_field, cleanup_block));
         }
 
-        self.block(vec![], TerminatorKind::Return, false);
+        self.block(copies, TerminatorKind::Return, false);
     }
 }
 
@@ -1,4 +1,4 @@
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 use rustc_index::vec::{Idx, IndexVec};
 use rustc_middle::mir::*;
 use rustc_middle::ty::TyCtxt;
@@ -22,7 +22,9 @@ pub use self::AddCallGuards::*;
  * "edge actions" from affecting other edges. We need this for calls that are
  * codegened to LLVM invoke instructions, because invoke is a block terminator
  * in LLVM so we can't insert any code to handle the call's result into the
- * block that performs the call.
+ * block that performs the call. The same holds for inline assembly with
+ * goto labels, which is codegened to a `callbr` terminator, and LLVM rejects
+ * a `callbr` listing the same block twice.
  *
  * This function will break those edges by inserting new blocks along them.
  *
@@ -31,13 +33,18 @@ pub use self::AddCallGuards::*;
  */
 
 impl<'tcx> MirPass<'tcx> for AddCallGuards {
-    fn run_pass(&self, _tcx: TyCtxt<'tcx>, _src: MirSource<'tcx>, body: &mut Body<'tcx>) {
-        self.add_call_guards(body);
+    fn run_pass(
+        &self,
+        _tcx: TyCtxt<'tcx>,
+        _src: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        self.add_call_guards(body)
     }
 }
 
 impl AddCallGuards {
-    pub fn add_call_guards(&self, body: &mut Body<'_>) {
+    pub fn add_call_guards(&self, body: &mut Body<'_>) -> PassOutcome {
         let pred_count: IndexVec<_, _> = body.predecessors().iter().map(|ps| ps.len()).collect();
 
         // We need a place to store the new blocks generated
@@ -73,12 +80,42 @@ impl AddCallGuards {
                     new_blocks.push(call_guard);
                     *destination = BasicBlock::new(idx);
                 }
+                Some(Terminator {
+                    kind:
+                        TerminatorKind::InlineAsm {
+                            ref mut destination, ref mut labels, cleanup, ..
+                        },
+                    source_info,
+                }) => {
+                    let has_other_edges = !labels.is_empty() || cleanup.is_some();
+                    for target in destination.iter_mut().chain(labels.iter_mut()) {
+                        if pred_count[*target] > 1 && (has_other_edges || self == &AllCallEdges) {
+                            // It's a critical edge, break it
+                            let asm_guard = BasicBlockData {
+                                statements: vec![],
+                                is_cleanup: block.is_cleanup,
+                                terminator: Some(Terminator {
+                                    source_info,
+                                    kind: TerminatorKind::Goto { target: *target },
+                                }),
+                            };
+
+                            let idx = cur_len + new_blocks.len();
+                            new_blocks.push(asm_guard);
+                            *target = BasicBlock::new(idx);
+                        }
+                    }
+                }
                 _ => {}
             }
         }
 
         debug!("Broke {} N edges", new_blocks.len());
+        if new_blocks.is_empty() {
+            return PassOutcome::Unchanged;
+        }
 
         body.basic_blocks_mut().extend(new_blocks);
+        PassOutcome::Changed
     }
 }
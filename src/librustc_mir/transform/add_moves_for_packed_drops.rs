@@ -1,8 +1,9 @@
 use rustc_hir::def_id::DefId;
 use rustc_middle::mir::*;
 use rustc_middle::ty::TyCtxt;
+use std::ops::RangeInclusive;
 
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 use crate::util;
 use crate::util::patch::MirPatch;
 
@@ -40,9 +41,25 @@ use crate::util::patch::MirPatch;
 pub struct AddMovesForPackedDrops;
 
 impl<'tcx> MirPass<'tcx> for AddMovesForPackedDrops {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, src: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    /// Only elaborated drops are rewritten.
+    fn requires(&self) -> &[&str] {
+        &["ElaborateDrops"]
+    }
+
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        // Only elaborated drops can be moved out of packed structs.
+        MirPhase::DropElab..=MirPhase::DropElab
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        src: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
         debug!("add_moves_for_packed_drops({:?} @ {:?})", src, body.span);
         add_moves_for_packed_drops(tcx, body, src.def_id());
+        PassOutcome::Changed
     }
 }
 
@@ -4,9 +4,11 @@
 //! of MIR building, and only after this pass we think of the program has having the
 //! normal MIR semantics.
 
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 use rustc_middle::mir::*;
 use rustc_middle::ty::{self, Ty, TyCtxt};
+use rustc_session::Session;
+use std::ops::RangeInclusive;
 
 pub struct AddRetag;
 
@@ -58,11 +60,26 @@ fn may_be_reference(ty: Ty<'tcx>) -> bool {
 }
 
 impl<'tcx> MirPass<'tcx> for AddRetag {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, src: MirSource<'tcx>, body: &mut Body<'tcx>) {
-        if !tcx.sess.opts.debugging_opts.mir_emit_retag {
-            return;
-        }
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.mir_emit_retag
+    }
+
+    /// The calls that drop elaboration introduces are retagged as well.
+    fn requires(&self) -> &[&str] {
+        &["ElaborateDrops"]
+    }
+
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        // Retags go in once drops are elaborated, and before optimizations move code around.
+        MirPhase::DropElab..=MirPhase::DropElab
+    }
 
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        src: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
         // We need an `AllCallEdges` pass before we can do any work.
         super::add_call_guards::AllCallEdges.run_pass(tcx, src, body);
 
@@ -171,5 +188,6 @@ impl<'tcx> MirPass<'tcx> for AddRetag {
                 );
             }
         }
+        PassOutcome::Changed
     }
 }
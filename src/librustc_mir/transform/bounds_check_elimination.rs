@@ -0,0 +1,69 @@
+//! Removes bounds checks that earlier comparisons already decided.
+//!
+//! Indexing checks that the index is below the length, even where a condition the indexing is
+//! nested in, or an earlier indexing with the same index, has checked it already:
+//!
+//!     bb1: { _5 = Lt(_2, const 4_usize); switchInt(move _5) -> [false: bb4, otherwise: bb2]; }
+//!     bb2: { _7 = _2; _8 = Lt(_7, const 4_usize); assert(move _8, ...) -> bb3; }
+//!
+//! An `assert` of a bounds check that follows from the comparisons of the branches and assertions
+//! dominating it, as found by `DominatingConditions`, is replaced by a `goto` to its target:
+//!
+//!     bb2: { _7 = _2; _8 = Lt(_7, const 4_usize); goto -> bb3; }
+//!
+//! The comparison itself is left to the passes that remove unused assignments.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::dominating_conditions::DominatingConditions;
+use rustc_middle::mir::*;
+use rustc_middle::ty::TyCtxt;
+
+pub struct BoundsCheckElimination;
+
+impl<'tcx> MirPass<'tcx> for BoundsCheckElimination {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let param_env = tcx.param_env(source.def_id());
+        let conditions = DominatingConditions::new(tcx, param_env, body);
+
+        // Removing a check doesn't change what the others can rely on, since it could never fail.
+        let mut removable = Vec::new();
+        for (bb, data) in body.basic_blocks().iter_enumerated() {
+            let (cond, target) = match data.terminator().kind {
+                TerminatorKind::Assert {
+                    cond: Operand::Copy(place) | Operand::Move(place),
+                    expected: true,
+                    msg: AssertKind::BoundsCheck { .. },
+                    target,
+                    ..
+                } => match place.as_local() {
+                    Some(cond) => (cond, target),
+                    None => continue,
+                },
+                _ => continue,
+            };
+            let comparison = match conditions.comparison(bb, data.statements.len(), cond) {
+                Some((_, comparison)) => comparison,
+                None => continue,
+            };
+            if conditions.implies(bb, &comparison) {
+                debug!("removing the bounds check {:?} in {:?}", comparison, bb);
+                removable.push((bb, target));
+            }
+        }
+
+        let changed = !removable.is_empty();
+        for (bb, target) in removable {
+            body.basic_blocks_mut()[bb].terminator_mut().kind = TerminatorKind::Goto { target };
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
@@ -0,0 +1,133 @@
+//! Records how likely the targets of every `switchInt` are in `Body::branch_weights`.
+//!
+//! The weights come from two sources. A switch on the result of the `likely` or `unlikely`
+//! intrinsics favors the targets it expects:
+//!
+//!     bb0: { _2 = likely(move _3) -> bb1; }
+//!     bb1: { switchInt(move _2) -> [false: bb3, otherwise: bb2]; }
+//!
+//! gives `bb2` a weight of 2000 and `bb3` a weight of 1, the weights LLVM uses for
+//! `llvm.expect`. Otherwise, the targets from which every path ends in a call to a `#[cold]`
+//! function, like a panic, are unlikely next to the others.
+//!
+//! With `-C profile-use`, the profile knows better than either, so the pass leaves the weights to
+//! LLVM. Codegen attaches the weights to the branches it emits for the switches.
+
+use crate::transform::jump_threading::writes_to;
+use crate::transform::reorder_blocks::cold_blocks;
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::liveness::ever_borrowed_locals;
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_session::Session;
+use rustc_span::symbol::sym;
+use rustc_target::spec::abi::Abi;
+use std::ops::RangeInclusive;
+
+/// The weight of a target that is expected to be taken.
+const LIKELY_WEIGHT: u32 = 2000;
+/// The weight of a target that is expected not to be taken.
+const UNLIKELY_WEIGHT: u32 = 1;
+
+pub struct BranchWeights;
+
+impl<'tcx> MirPass<'tcx> for BranchWeights {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.mir_branch_weights && sess.opts.cg.profile_use.is_none()
+    }
+
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        // Any later change to the blocks would invalidate the weights.
+        MirPhase::Optimized..=MirPhase::Optimized
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let cold = cold_blocks(tcx, body);
+        let borrowed = ever_borrowed_locals(body);
+        let mut weights = IndexVec::from_elem(Vec::new(), body.basic_blocks());
+        let mut annotated = false;
+        for (bb, data) in body.basic_blocks().iter_enumerated() {
+            let (discr, values, targets) = match data.terminator().kind {
+                TerminatorKind::SwitchInt { ref discr, ref values, ref targets, .. } => {
+                    (discr, values, targets)
+                }
+                _ => continue,
+            };
+
+            let expected = discr
+                .place()
+                .and_then(|place| place.as_local())
+                .filter(|&local| !borrowed.contains(local))
+                .and_then(|local| expected_value(tcx, body, bb, local));
+            weights[bb] = match (expected, &values[..]) {
+                (Some(expected), &[value]) => {
+                    // The first target is taken for `value`, the `otherwise` target for the
+                    // other boolean.
+                    let first_expected = (value != 0) == expected;
+                    if first_expected {
+                        vec![LIKELY_WEIGHT, UNLIKELY_WEIGHT]
+                    } else {
+                        vec![UNLIKELY_WEIGHT, LIKELY_WEIGHT]
+                    }
+                }
+                _ if targets.iter().any(|&target| cold.contains(target))
+                    && !targets.iter().all(|&target| cold.contains(target)) =>
+                {
+                    let weight = |&target: &BasicBlock| {
+                        if cold.contains(target) { UNLIKELY_WEIGHT } else { LIKELY_WEIGHT }
+                    };
+                    targets.iter().map(weight).collect()
+                }
+                _ => continue,
+            };
+            debug!("weighting the targets {:?} of {:?} with {:?}", targets, bb, weights[bb]);
+            annotated = true;
+        }
+
+        if !annotated {
+            return PassOutcome::Unchanged;
+        }
+        body.branch_weights = Some(weights);
+        PassOutcome::Changed
+    }
+}
+
+/// Returns the value `local` is expected to have at the end of `bb`, if the only predecessor of
+/// `bb` assigns it the result of `likely` or `unlikely`.
+fn expected_value<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    body: &Body<'tcx>,
+    bb: BasicBlock,
+    local: Local,
+) -> Option<bool> {
+    if body[bb].statements.iter().any(|statement| writes_to(statement, local)) {
+        return None;
+    }
+    let predecessor = match body.predecessors()[bb][..] {
+        [predecessor] => predecessor,
+        _ => return None,
+    };
+    match body[predecessor].terminator().kind {
+        TerminatorKind::Call { ref func, destination: Some((destination, target)), .. }
+            if target == bb && destination.as_local() == Some(local) =>
+        {
+            match func.ty(body, tcx).kind {
+                ty::FnDef(def_id, _) if tcx.fn_sig(def_id).abi() == Abi::RustIntrinsic => {
+                    match tcx.item_name(def_id) {
+                        sym::likely => Some(true),
+                        sym::unlikely => Some(false),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
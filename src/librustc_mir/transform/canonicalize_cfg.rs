@@ -0,0 +1,129 @@
+//! Brings the control-flow graph into the shape that the optimizations expect.
+//!
+//! MIR building and inlining leave blocks behind that nothing reaches, and chains of blocks that
+//! only jump to each other. Before the optimizations, the pass removes and merges those as
+//! `SimplifyCfg` does, and then makes two kinds of edges explicit:
+//!
+//! * A critical edge leaving a `switchInt`, one that neither is the only edge out of its source
+//!   nor the only edge into its target, gets a block of its own, so that statements can be moved
+//!   onto the edge without running on the other paths into the target:
+//!
+//!       bb1: { switchInt(_2) -> [0: bb3, otherwise: bb2]; }
+//!       bb2: { ...; goto -> bb3; }
+//!
+//!   becomes
+//!
+//!       bb1: { switchInt(_2) -> [0: bb4, otherwise: bb2]; }
+//!       bb4: { goto -> bb3; }
+//!
+//! * A loop header whose entries from outside of the loop don't all come from one block that only
+//!   jumps to it gets such a block, the preheader, and the entries go through it instead.
+//!
+//! Passes that run before the next `SimplifyCfg`, which undoes both, can rely on them, though
+//! they still have to check for them if they run on bodies that other passes changed since.
+
+use crate::transform::simplify::simplify_cfg;
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::loops::natural_loops;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_middle::mir::*;
+use rustc_middle::ty::TyCtxt;
+
+pub struct CanonicalizeCfg;
+
+impl<'tcx> MirPass<'tcx> for CanonicalizeCfg {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        _tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        simplify_cfg(body);
+        split_critical_switch_edges(body);
+        insert_preheaders(body);
+        PassOutcome::Changed
+    }
+}
+
+/// Inserts a block on every critical edge out of a `switchInt` outside of the cleanup blocks.
+/// Several edges from the same switch to the same target share their block.
+fn split_critical_switch_edges(body: &mut Body<'_>) {
+    let predecessors = body.predecessors().clone();
+    for bb in body.basic_blocks().indices() {
+        let data = &body[bb];
+        if data.is_cleanup {
+            continue;
+        }
+        let source_info = data.terminator().source_info;
+        let targets = match data.terminator().kind {
+            TerminatorKind::SwitchInt { ref targets, .. } => targets.clone(),
+            _ => continue,
+        };
+        let mut edge_blocks = FxHashMap::default();
+        for (index, &target) in targets.iter().enumerate() {
+            let critical = targets.iter().any(|&other| other != target)
+                && predecessors[target].iter().any(|&pred| pred != bb);
+            if !critical {
+                continue;
+            }
+            let edge = *edge_blocks.entry(target).or_insert_with(|| {
+                debug!("splitting the critical edge from {:?} to {:?}", bb, target);
+                body.basic_blocks_mut().push(BasicBlockData {
+                    statements: vec![],
+                    terminator: Some(Terminator {
+                        source_info,
+                        kind: TerminatorKind::Goto { target },
+                    }),
+                    is_cleanup: false,
+                })
+            });
+            match body[bb].terminator_mut().kind {
+                TerminatorKind::SwitchInt { ref mut targets, .. } => targets[index] = edge,
+                _ => unreachable!(),
+            }
+        }
+    }
+}
+
+/// Makes every loop header outside of the cleanup blocks, other than the entry block, entered
+/// from outside of its loop by a single block ending in a `goto` to it.
+fn insert_preheaders(body: &mut Body<'_>) {
+    for natural_loop in natural_loops(body) {
+        let header = natural_loop.header;
+        if header == START_BLOCK || body[header].is_cleanup {
+            continue;
+        }
+        let outside: Vec<BasicBlock> = body.predecessors()[header]
+            .iter()
+            .copied()
+            .filter(|&pred| !natural_loop.blocks.contains(pred))
+            .collect();
+        if let [pred] = outside[..] {
+            if let TerminatorKind::Goto { .. } = body[pred].terminator().kind {
+                continue;
+            }
+        }
+
+        debug!("inserting a preheader for the loop at {:?}", header);
+        let source_info = body[header].terminator().source_info;
+        let preheader = body.basic_blocks_mut().push(BasicBlockData {
+            statements: vec![],
+            terminator: Some(Terminator {
+                source_info,
+                kind: TerminatorKind::Goto { target: header },
+            }),
+            is_cleanup: false,
+        });
+        for pred in outside {
+            for target in body[pred].terminator_mut().successors_mut() {
+                if *target == header {
+                    *target = preheader;
+                }
+            }
+        }
+    }
+}
@@ -142,6 +142,23 @@ impl NonConstOp for HeapAllocation {
     }
 }
 
+/// A raw pointer into the const heap in the final value of a constant or static.
+#[derive(Debug)]
+pub struct ConstHeapPtr;
+impl NonConstOp for ConstHeapPtr {
+    fn emit_error(&self, ccx: &ConstCx<'_, '_>, span: Span) {
+        let mut err = ccx.tcx.sess.struct_span_err(
+            span,
+            &format!(
+                "raw pointers into the const heap are not allowed in the final value of {}s",
+                ccx.const_kind()
+            ),
+        );
+        err.note("memory allocated by `const_allocate` can only be used behind a reference");
+        err.emit();
+    }
+}
+
 #[derive(Debug)]
 pub struct InlineAsm;
 impl NonConstOp for InlineAsm {}
@@ -2,9 +2,12 @@
 //!
 //! See the `Qualif` trait for more info.
 
+use rustc_data_structures::fx::FxHashSet;
 use rustc_middle::mir::*;
 use rustc_middle::ty::{self, subst::SubstsRef, AdtDef, Ty};
+use rustc_span::symbol::sym;
 use rustc_span::DUMMY_SP;
+use rustc_target::spec::abi::Abi;
 use rustc_trait_selection::traits;
 
 use super::ConstCx;
@@ -47,6 +50,14 @@ pub trait Qualif {
     /// It also determines the `Qualif`s for primitive types.
     fn in_any_value_of_ty(cx: &ConstCx<'_, 'tcx>, ty: Ty<'tcx>) -> bool;
 
+    /// Returns `true` if the value returned by a call to `func` could have this `Qualif`.
+    ///
+    /// We cannot reason about another function's internals, so this defaults to the conservative
+    /// type-based qualification of the returned value.
+    fn in_call_return(cx: &ConstCx<'_, 'tcx>, _func: &Operand<'tcx>, return_ty: Ty<'tcx>) -> bool {
+        Self::in_any_value_of_ty(cx, return_ty)
+    }
+
     /// Returns `true` if this `Qualif` is inherent to the given struct or enum.
     ///
     /// By default, `Qualif`s propagate into ADTs in a structural way: An ADT only becomes
@@ -140,6 +151,77 @@ impl Qualif for CustomEq {
     }
 }
 
+/// A value that may contain a raw pointer into the const heap (see `intrinsics::const_allocate`).
+///
+/// Interning turns the const heap into global memory only behind references, which make it
+/// immutable `'static` data. A raw pointer to it must not end up in the final value of a constant
+/// or static. Without `#![feature(const_heap)]`, nothing can allocate from the const heap.
+///
+/// Only the values returned by `const_allocate` get this qualif, so raw pointers from anywhere
+/// else, like `ptr::null()`, are fine. A pointer into the const heap returned by another
+/// `const fn` is rejected when the final value is interned instead.
+pub struct HasConstHeapPtr;
+
+impl Qualif for HasConstHeapPtr {
+    const ANALYSIS_NAME: &'static str = "flow_has_const_heap_ptr";
+
+    fn in_qualifs(_qualifs: &ConstQualifs) -> bool {
+        // Constants that pass const-checking never contain a raw pointer into the const heap.
+        false
+    }
+
+    fn in_any_value_of_ty(cx: &ConstCx<'_, 'tcx>, ty: Ty<'tcx>) -> bool {
+        let const_heap_enabled =
+            cx.tcx.features().declared_lib_features.iter().any(|&(f, _)| f == sym::const_heap);
+        const_heap_enabled && may_contain_raw_ptr(cx, ty, &mut FxHashSet::default())
+    }
+
+    fn in_call_return(cx: &ConstCx<'_, 'tcx>, func: &Operand<'tcx>, return_ty: Ty<'tcx>) -> bool {
+        let is_const_allocate = match func.ty(cx.body, cx.tcx).kind {
+            ty::FnDef(def_id, _) if cx.tcx.fn_sig(def_id).abi() == Abi::RustIntrinsic => {
+                cx.tcx.item_name(def_id) == sym::const_allocate
+            }
+            _ => false,
+        };
+        is_const_allocate && Self::in_any_value_of_ty(cx, return_ty)
+    }
+
+    fn in_adt_inherently(_: &ConstCx<'_, 'tcx>, _: &'tcx AdtDef, _: SubstsRef<'tcx>) -> bool {
+        false
+    }
+}
+
+/// Returns `true` if a value of type `ty` may contain a raw pointer, possibly behind a reference.
+fn may_contain_raw_ptr(
+    cx: &ConstCx<'_, 'tcx>,
+    ty: Ty<'tcx>,
+    visited: &mut FxHashSet<Ty<'tcx>>,
+) -> bool {
+    match ty.kind {
+        ty::RawPtr(_) => true,
+
+        ty::Ref(_, ty, _) | ty::Array(ty, _) | ty::Slice(ty) => {
+            may_contain_raw_ptr(cx, ty, visited)
+        }
+        ty::Tuple(..) => ty.tuple_fields().any(|ty| may_contain_raw_ptr(cx, ty, visited)),
+        ty::Closure(_, substs) => {
+            substs.as_closure().upvar_tys().any(|ty| may_contain_raw_ptr(cx, ty, visited))
+        }
+        ty::Adt(adt, substs) => {
+            // Recursive types are only searched once.
+            visited.insert(ty)
+                && adt
+                    .all_fields()
+                    .any(|field| may_contain_raw_ptr(cx, field.ty(cx.tcx, substs), visited))
+        }
+
+        // We can't look inside these.
+        ty::Param(_) | ty::Projection(_) | ty::Opaque(..) | ty::Dynamic(..) => true,
+
+        _ => false,
+    }
+}
+
 // FIXME: Use `mir::visit::Visitor` for the `in_*` functions if/when it supports early return.
 
 /// Returns `true` if this `Rvalue` contains qualif `Q`.
@@ -66,14 +66,12 @@ where
     fn apply_call_return_effect(
         &mut self,
         _block: BasicBlock,
-        _func: &mir::Operand<'tcx>,
+        func: &mir::Operand<'tcx>,
         _args: &[mir::Operand<'tcx>],
         return_place: mir::Place<'tcx>,
     ) {
-        // We cannot reason about another function's internals, so use conservative type-based
-        // qualification for the result of a function call.
         let return_ty = return_place.ty(self.ccx.body, self.ccx.tcx).ty;
-        let qualif = Q::in_any_value_of_ty(self.ccx, return_ty);
+        let qualif = Q::in_call_return(self.ccx, func, return_ty);
 
         if !return_place.is_indirect() {
             self.assign_qualif_direct(&return_place, qualif);
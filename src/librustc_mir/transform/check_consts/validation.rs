@@ -6,7 +6,9 @@ use rustc_hir::{def_id::DefId, HirId};
 use rustc_infer::infer::TyCtxtInferExt;
 use rustc_middle::mir::visit::{MutatingUseContext, NonMutatingUseContext, PlaceContext, Visitor};
 use rustc_middle::mir::*;
+use rustc_middle::ty::adjustment::PointerCast;
 use rustc_middle::ty::cast::CastTy;
+use rustc_middle::ty::subst::SubstsRef;
 use rustc_middle::ty::{self, Instance, InstanceDef, TyCtxt};
 use rustc_span::Span;
 use rustc_trait_selection::traits::error_reporting::InferCtxtExt;
@@ -16,12 +18,13 @@ use std::borrow::Cow;
 use std::ops::Deref;
 
 use super::ops::{self, NonConstOp};
-use super::qualifs::{self, CustomEq, HasMutInterior, NeedsDrop};
+use super::qualifs::{self, CustomEq, HasConstHeapPtr, HasMutInterior, NeedsDrop};
 use super::resolver::FlowSensitiveAnalysis;
 use super::{is_lang_panic_fn, ConstCx, Qualif};
 use crate::const_eval::{is_const_fn, is_unstable_const_fn};
 use crate::dataflow::impls::MaybeMutBorrowedLocals;
 use crate::dataflow::{self, Analysis};
+use crate::util::def_use::DefUseAnalysis;
 
 // We are using `MaybeMutBorrowedLocals` as a proxy for whether an item may have been mutated
 // through a pointer prior to the given point. This is okay even though `MaybeMutBorrowedLocals`
@@ -36,6 +39,7 @@ type QualifResults<'mir, 'tcx, Q> =
 pub struct Qualifs<'mir, 'tcx> {
     has_mut_interior: Option<QualifResults<'mir, 'tcx, HasMutInterior>>,
     needs_drop: Option<QualifResults<'mir, 'tcx, NeedsDrop>>,
+    has_const_heap_ptr: Option<QualifResults<'mir, 'tcx, HasConstHeapPtr>>,
     indirectly_mutable: Option<IndirectlyMutableResults<'mir, 'tcx>>,
 }
 
@@ -119,28 +123,41 @@ impl Qualifs<'mir, 'tcx> {
         has_mut_interior.get().contains(local) || self.indirectly_mutable(ccx, local, location)
     }
 
+    /// Returns `true` if `local` is `HasConstHeapPtr` at the given `Location`.
+    ///
+    /// Only updates the cursor if absolutely necessary.
+    pub fn has_const_heap_ptr(
+        &mut self,
+        ccx: &'mir ConstCx<'mir, 'tcx>,
+        local: Local,
+        location: Location,
+    ) -> bool {
+        let ty = ccx.body.local_decls[local].ty;
+        if !HasConstHeapPtr::in_any_value_of_ty(ccx, ty) {
+            return false;
+        }
+
+        let has_const_heap_ptr = self.has_const_heap_ptr.get_or_insert_with(|| {
+            let ConstCx { tcx, body, def_id, .. } = *ccx;
+
+            FlowSensitiveAnalysis::new(HasConstHeapPtr, ccx)
+                .into_engine(tcx, &body, def_id.to_def_id())
+                .iterate_to_fixpoint()
+                .into_results_cursor(&body)
+        });
+
+        has_const_heap_ptr.seek_before_primary_effect(location);
+        has_const_heap_ptr.get().contains(local)
+    }
+
     fn in_return_place(&mut self, ccx: &'mir ConstCx<'mir, 'tcx>) -> ConstQualifs {
-        // Find the `Return` terminator if one exists.
-        //
         // If no `Return` terminator exists, this MIR is divergent. Just return the conservative
         // qualifs for the return type.
-        let return_block = ccx
-            .body
-            .basic_blocks()
-            .iter_enumerated()
-            .find(|(_, block)| match block.terminator().kind {
-                TerminatorKind::Return => true,
-                _ => false,
-            })
-            .map(|(bb, _)| bb);
-
-        let return_block = match return_block {
+        let return_loc = match return_location(ccx) {
             None => return qualifs::in_any_value_of_ty(ccx, ccx.body.return_ty()),
-            Some(bb) => bb,
+            Some(loc) => loc,
         };
 
-        let return_loc = ccx.body.terminator_loc(return_block);
-
         let custom_eq = match ccx.const_kind() {
             // We don't care whether a `const fn` returns a value that is not structurally
             // matchable. Functions calls are opaque and always use type-based qualification, so
@@ -174,6 +191,9 @@ pub struct Validator<'mir, 'tcx> {
     ccx: &'mir ConstCx<'mir, 'tcx>,
     qualifs: Qualifs<'mir, 'tcx>,
 
+    /// Computed on demand to find out where function pointers come from.
+    def_use: Option<DefUseAnalysis>,
+
     /// The span of the current statement.
     span: Span,
 }
@@ -188,7 +208,7 @@ impl Deref for Validator<'mir, 'tcx> {
 
 impl Validator<'mir, 'tcx> {
     pub fn new(ccx: &'mir ConstCx<'mir, 'tcx>) -> Self {
-        Validator { span: ccx.body.span, ccx, qualifs: Default::default() }
+        Validator { span: ccx.body.span, ccx, qualifs: Default::default(), def_use: None }
     }
 
     pub fn check_body(&mut self) {
@@ -218,6 +238,17 @@ impl Validator<'mir, 'tcx> {
             let hir_id = tcx.hir().as_local_hir_id(def_id);
             check_return_ty_is_sync(tcx, &body, hir_id);
         }
+
+        // Ensure that no raw pointer into the const heap escapes into the final value.
+        if let Some(hir::ConstContext::Const | hir::ConstContext::Static(_)) = const_kind {
+            let has_const_heap_ptr = return_location(self.ccx).map_or(false, |return_loc| {
+                self.qualifs.has_const_heap_ptr(self.ccx, RETURN_PLACE, return_loc)
+            });
+            if has_const_heap_ptr {
+                let span = body.local_decls[RETURN_PLACE].source_info.span;
+                self.check_op_spanned(ops::ConstHeapPtr, span);
+            }
+        }
     }
 
     pub fn qualifs_in_return_place(&mut self) -> ConstQualifs {
@@ -235,6 +266,60 @@ impl Validator<'mir, 'tcx> {
         ops::non_const(self.ccx, op, span);
     }
 
+    /// Returns the function item a function pointer operand was created from, if the pointer is
+    /// held in a local that is assigned exactly once in this body, from a reified function item
+    /// (possibly through copies).
+    fn fn_item_behind_pointer(&mut self, func: &Operand<'tcx>) -> Option<(DefId, SubstsRef<'tcx>)> {
+        let body = self.ccx.body;
+        let def_use = self.def_use.get_or_insert_with(|| {
+            let mut def_use = DefUseAnalysis::new(body);
+            def_use.analyze(body);
+            def_use
+        });
+
+        let mut operand = func;
+        loop {
+            let local = match operand {
+                Operand::Constant(constant) => {
+                    return match constant.literal.ty.kind {
+                        ty::FnDef(def_id, substs) => Some((def_id, substs)),
+                        _ => None,
+                    };
+                }
+                Operand::Copy(place) | Operand::Move(place) => place.as_local()?,
+            };
+
+            // Arguments are defined by the caller, and anything that is assigned more than once
+            // (or borrowed mutably) could hold any function by the time it is called.
+            if body.local_kind(local) == LocalKind::Arg {
+                return None;
+            }
+            let mut defs = def_use.local_info(local).defs_not_including_drop();
+            let def = defs.next()?;
+            if defs.next().is_some() {
+                return None;
+            }
+
+            let statement = body.basic_blocks()[def.location.block]
+                .statements
+                .get(def.location.statement_index)?;
+            operand = match &statement.kind {
+                StatementKind::Assign(box (place, rvalue)) if place.as_local() == Some(local) => {
+                    match rvalue {
+                        Rvalue::Use(operand)
+                        | Rvalue::Cast(
+                            CastKind::Pointer(PointerCast::ReifyFnPointer),
+                            operand,
+                            _,
+                        ) => operand,
+                        _ => return None,
+                    }
+                }
+                _ => return None,
+            };
+        }
+    }
+
     fn check_static(&mut self, def_id: DefId, span: Span) {
         assert!(
             !self.tcx.is_thread_local_static(def_id),
@@ -501,8 +586,20 @@ impl Visitor<'tcx> for Validator<'mir, 'tcx> {
                     ty::FnDef(def_id, substs) => (def_id, substs),
 
                     ty::FnPtr(_) => {
-                        self.check_op(ops::FnCallIndirect);
-                        return;
+                        // With `const_fn_ptr_call`, a pointer that can only point to one function
+                        // item is checked as if that item was called directly.
+                        let fn_item = if self.tcx.features().const_fn_ptr_call {
+                            self.fn_item_behind_pointer(func)
+                        } else {
+                            None
+                        };
+                        match fn_item {
+                            Some(fn_item) => fn_item,
+                            None => {
+                                self.check_op(ops::FnCallIndirect);
+                                return;
+                            }
+                        }
                     }
                     _ => {
                         span_bug!(terminator.source_info.span, "invalid callee of type {:?}", fn_ty)
@@ -625,6 +722,18 @@ fn check_return_ty_is_sync(tcx: TyCtxt<'tcx>, body: &Body<'tcx>, hir_id: HirId)
     });
 }
 
+/// Returns the location of the first `Return` terminator, if there is one.
+fn return_location(ccx: &ConstCx<'_, '_>) -> Option<Location> {
+    ccx.body
+        .basic_blocks()
+        .iter_enumerated()
+        .find(|(_, block)| match block.terminator().kind {
+            TerminatorKind::Return => true,
+            _ => false,
+        })
+        .map(|(bb, _)| ccx.body.terminator_loc(bb))
+}
+
 fn place_as_reborrow(
     tcx: TyCtxt<'tcx>,
     body: &Body<'tcx>,
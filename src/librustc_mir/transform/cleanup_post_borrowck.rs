@@ -18,11 +18,12 @@
 //! [`ForMatchGuard`]: rustc_middle::mir::FakeReadCause::ForMatchGuard
 //! [`Nop`]: rustc_middle::mir::StatementKind::Nop
 
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 use rustc_middle::mir::visit::MutVisitor;
-use rustc_middle::mir::{Body, BorrowKind, Location, Rvalue};
+use rustc_middle::mir::{Body, BorrowKind, Location, MirPhase, Rvalue};
 use rustc_middle::mir::{Statement, StatementKind};
 use rustc_middle::ty::TyCtxt;
+use std::ops::RangeInclusive;
 
 pub struct CleanupNonCodegenStatements;
 
@@ -31,7 +32,17 @@ pub struct DeleteNonCodegenStatements<'tcx> {
 }
 
 impl<'tcx> MirPass<'tcx> for CleanupNonCodegenStatements {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, _source: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        // The statements it removes are still needed by borrowck.
+        MirPhase::DropElab..=MirPhase::Optimized
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
         let mut delete = DeleteNonCodegenStatements { tcx };
         delete.visit_body(body);
         body.user_type_annotations.raw.clear();
@@ -39,6 +50,7 @@ impl<'tcx> MirPass<'tcx> for CleanupNonCodegenStatements {
         for decl in &mut body.local_decls {
             decl.user_ty = None;
         }
+        PassOutcome::Changed
     }
 }
 
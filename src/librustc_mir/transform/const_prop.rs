@@ -1,10 +1,16 @@
 //! Propagates constants for early reporting of statically known
 //! assertion failures
-
+//!
+//! Blocks are visited in reverse postorder. User variables can be assigned more than once, so
+//! their values flow along the edges of the CFG: a block starts with the values that all of its
+//! predecessors agree on, and with none if it is entered through a back edge. A `switchInt` on a
+//! known value becomes a `goto` to its target, and its other edges don't contribute to the values
+//! of their targets. Lints are only reported for values known within their own block, as before.
+
+use std::borrow::Cow;
 use std::cell::Cell;
 
 use rustc_ast::ast::Mutability;
-use rustc_data_structures::fx::FxHashSet;
 use rustc_hir::def::DefKind;
 use rustc_hir::HirId;
 use rustc_index::bit_set::BitSet;
@@ -14,9 +20,10 @@ use rustc_middle::mir::visit::{
     MutVisitor, MutatingUseContext, NonMutatingUseContext, PlaceContext, Visitor,
 };
 use rustc_middle::mir::{
-    AggregateKind, AssertKind, BasicBlock, BinOp, Body, ClearCrossCrate, Constant, Local,
-    LocalDecl, LocalKind, Location, Operand, Place, Rvalue, SourceInfo, SourceScope,
-    SourceScopeData, Statement, StatementKind, Terminator, TerminatorKind, UnOp, RETURN_PLACE,
+    traversal, AggregateKind, AssertKind, BasicBlock, BasicBlockData, BinOp, Body, ClearCrossCrate,
+    Constant, Field, InlineAsmOperand, Local, LocalDecl, LocalKind, Location, Operand, Place,
+    ProjectionElem, Rvalue, SourceInfo, SourceScope, SourceScopeData, Statement, StatementKind,
+    Terminator, TerminatorKind, UnOp, RETURN_PLACE, START_BLOCK,
 };
 use rustc_middle::ty::layout::{HasTyCtxt, LayoutError, TyAndLayout};
 use rustc_middle::ty::subst::{InternalSubsts, Subst};
@@ -26,13 +33,13 @@ use rustc_span::{def_id::DefId, Span};
 use rustc_target::abi::{HasDataLayout, LayoutOf, Size, TargetDataLayout};
 use rustc_trait_selection::traits;
 
-use crate::const_eval::error_to_const_error;
+use crate::const_eval::{error_to_const_error, eval_const_fn_call};
 use crate::interpret::{
     self, compile_time_machine, truncate, AllocId, Allocation, Frame, ImmTy, Immediate, InterpCx,
-    LocalState, LocalValue, MemPlace, Memory, MemoryKind, OpTy, Operand as InterpOperand, PlaceTy,
-    Pointer, ScalarMaybeUninit, StackPopCleanup,
+    LocalState, LocalValue, Memory, MemoryKind, OpTy, Operand as InterpOperand, PlaceTy, Pointer,
+    ScalarMaybeUninit, StackPopCleanup,
 };
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 
 /// The maximum number of bytes that we'll allocate space for a return value.
 const MAX_ALLOC_LIMIT: u64 = 1024;
@@ -58,118 +65,158 @@ macro_rules! throw_machine_stop_str {
 pub struct ConstProp;
 
 impl<'tcx> MirPass<'tcx> for ConstProp {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>) {
-        // will be evaluated by miri and produce its errors there
-        if source.promoted.is_some() {
-            return;
-        }
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        run_const_prop(tcx, source, body, true)
+    }
+}
 
-        use rustc_middle::hir::map::blocks::FnLikeNode;
-        let hir_id = tcx.hir().as_local_hir_id(source.def_id().expect_local());
+/// Runs `ConstProp` again, after later passes simplified the body. It neither lints nor reports
+/// errors, since the first run already did.
+pub struct ConstPropRepeat {
+    label: String,
+}
 
-        let is_fn_like = FnLikeNode::from_node(tcx.hir().get(hir_id)).is_some();
-        let is_assoc_const = tcx.def_kind(source.def_id()) == DefKind::AssocConst;
+impl ConstPropRepeat {
+    pub fn new(label: &str) -> Self {
+        ConstPropRepeat { label: format!("ConstProp-{}", label) }
+    }
+}
 
-        // Only run const prop on functions, methods, closures and associated constants
-        if !is_fn_like && !is_assoc_const {
-            // skip anon_const/statics/consts because they'll be evaluated by miri anyway
-            trace!("ConstProp skipped for {:?}", source.def_id());
-            return;
-        }
+impl<'tcx> MirPass<'tcx> for ConstPropRepeat {
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.label)
+    }
 
-        let is_generator = tcx.type_of(source.def_id()).is_generator();
-        // FIXME(welseywiser) const prop doesn't work on generators because of query cycles
-        // computing their layout.
-        if is_generator {
-            trace!("ConstProp skipped for generator {:?}", source.def_id());
-            return;
-        }
+    fn is_optimization(&self) -> bool {
+        true
+    }
 
-        // Check if it's even possible to satisfy the 'where' clauses
-        // for this item.
-        // This branch will never be taken for any normal function.
-        // However, it's possible to `#!feature(trivial_bounds)]` to write
-        // a function with impossible to satisfy clauses, e.g.:
-        // `fn foo() where String: Copy {}`
-        //
-        // We don't usually need to worry about this kind of case,
-        // since we would get a compilation error if the user tried
-        // to call it. However, since we can do const propagation
-        // even without any calls to the function, we need to make
-        // sure that it even makes sense to try to evaluate the body.
-        // If there are unsatisfiable where clauses, then all bets are
-        // off, and we just give up.
-        //
-        // We manually filter the predicates, skipping anything that's not
-        // "global". We are in a potentially generic context
-        // (e.g. we are evaluating a function without substituting generic
-        // parameters, so this filtering serves two purposes:
-        //
-        // 1. We skip evaluating any predicates that we would
-        // never be able prove are unsatisfiable (e.g. `<T as Foo>`
-        // 2. We avoid trying to normalize predicates involving generic
-        // parameters (e.g. `<T as Foo>::MyItem`). This can confuse
-        // the normalization code (leading to cycle errors), since
-        // it's usually never invoked in this way.
-        let predicates = tcx
-            .predicates_of(source.def_id())
-            .predicates
-            .iter()
-            .filter_map(|(p, _)| if p.is_global() { Some(*p) } else { None });
-        if traits::impossible_predicates(
-            tcx,
-            traits::elaborate_predicates(tcx, predicates).map(|o| o.predicate).collect(),
-        ) {
-            trace!("ConstProp skipped for {:?}: found unsatisfiable predicates", source.def_id());
-            return;
-        }
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        run_const_prop(tcx, source, body, false)
+    }
+}
 
-        trace!("ConstProp starting for {:?}", source.def_id());
-
-        let dummy_body = &Body::new(
-            body.basic_blocks().clone(),
-            body.source_scopes.clone(),
-            body.local_decls.clone(),
-            Default::default(),
-            body.arg_count,
-            Default::default(),
-            tcx.def_span(source.def_id()),
-            body.generator_kind,
-        );
-
-        // FIXME(oli-obk, eddyb) Optimize locals (or even local paths) to hold
-        // constants, instead of just checking for const-folding succeeding.
-        // That would require an uniform one-def no-mutation analysis
-        // and RPO (or recursing when needing the value of a local).
-        let mut optimization_finder = ConstPropagator::new(body, dummy_body, tcx, source);
-        optimization_finder.visit_body(body);
-
-        trace!("ConstProp done for {:?}", source.def_id());
+fn run_const_prop<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    source: MirSource<'tcx>,
+    body: &mut Body<'tcx>,
+    report: bool,
+) -> PassOutcome {
+    // will be evaluated by miri and produce its errors there
+    if source.promoted.is_some() {
+        return PassOutcome::Unchanged;
     }
+
+    use rustc_middle::hir::map::blocks::FnLikeNode;
+    let hir_id = tcx.hir().as_local_hir_id(source.def_id().expect_local());
+
+    let is_fn_like = FnLikeNode::from_node(tcx.hir().get(hir_id)).is_some();
+    let is_assoc_const = tcx.def_kind(source.def_id()) == DefKind::AssocConst;
+
+    // Only run const prop on functions, methods, closures and associated constants
+    if !is_fn_like && !is_assoc_const {
+        // skip anon_const/statics/consts because they'll be evaluated by miri anyway
+        trace!("ConstProp skipped for {:?}", source.def_id());
+        return PassOutcome::Unchanged;
+    }
+
+    let is_generator = tcx.type_of(source.def_id()).is_generator();
+    // FIXME(welseywiser) const prop doesn't work on generators because of query cycles
+    // computing their layout.
+    if is_generator {
+        trace!("ConstProp skipped for generator {:?}", source.def_id());
+        return PassOutcome::Unchanged;
+    }
+
+    // Check if it's even possible to satisfy the 'where' clauses
+    // for this item.
+    // This branch will never be taken for any normal function.
+    // However, it's possible to `#!feature(trivial_bounds)]` to write
+    // a function with impossible to satisfy clauses, e.g.:
+    // `fn foo() where String: Copy {}`
+    //
+    // We don't usually need to worry about this kind of case,
+    // since we would get a compilation error if the user tried
+    // to call it. However, since we can do const propagation
+    // even without any calls to the function, we need to make
+    // sure that it even makes sense to try to evaluate the body.
+    // If there are unsatisfiable where clauses, then all bets are
+    // off, and we just give up.
+    //
+    // We manually filter the predicates, skipping anything that's not
+    // "global". We are in a potentially generic context
+    // (e.g. we are evaluating a function without substituting generic
+    // parameters, so this filtering serves two purposes:
+    //
+    // 1. We skip evaluating any predicates that we would
+    // never be able prove are unsatisfiable (e.g. `<T as Foo>`
+    // 2. We avoid trying to normalize predicates involving generic
+    // parameters (e.g. `<T as Foo>::MyItem`). This can confuse
+    // the normalization code (leading to cycle errors), since
+    // it's usually never invoked in this way.
+    let predicates = tcx
+        .predicates_of(source.def_id())
+        .predicates
+        .iter()
+        .filter_map(|(p, _)| if p.is_global() { Some(*p) } else { None });
+    if traits::impossible_predicates(
+        tcx,
+        traits::elaborate_predicates(tcx, predicates).map(|o| o.predicate).collect(),
+    ) {
+        trace!("ConstProp skipped for {:?}: found unsatisfiable predicates", source.def_id());
+        return PassOutcome::Unchanged;
+    }
+
+    trace!("ConstProp starting for {:?}", source.def_id());
+
+    let dummy_body = &Body::new(
+        body.basic_blocks().clone(),
+        body.source_scopes.clone(),
+        body.local_decls.clone(),
+        Default::default(),
+        body.arg_count,
+        Default::default(),
+        tcx.def_span(source.def_id()),
+        body.generator_kind,
+    );
+
+    // FIXME(oli-obk, eddyb) Optimize locals (or even local paths) to hold
+    // constants, instead of just checking for const-folding succeeding.
+    // That would require an uniform one-def no-mutation analysis
+    // and RPO (or recursing when needing the value of a local).
+    let mut optimization_finder = ConstPropagator::new(body, dummy_body, tcx, source, report);
+    optimization_finder.visit_body(body);
+
+    trace!("ConstProp done for {:?}", source.def_id());
+    PassOutcome::changed_if(optimization_finder.changed)
 }
 
 struct ConstPropMachine<'mir, 'tcx> {
     /// The virtual call stack.
     stack: Vec<Frame<'mir, 'tcx, (), ()>>,
-    /// `OnlyInsideOwnBlock` locals that were written in the current block get erased at the end.
-    written_only_inside_own_block_locals: FxHashSet<Local>,
-    /// Locals that need to be cleared after every block terminates.
-    only_propagate_inside_block_locals: BitSet<Local>,
 }
 
 impl<'mir, 'tcx> ConstPropMachine<'mir, 'tcx> {
-    fn new(only_propagate_inside_block_locals: BitSet<Local>) -> Self {
-        Self {
-            stack: Vec::new(),
-            written_only_inside_own_block_locals: Default::default(),
-            only_propagate_inside_block_locals,
-        }
+    fn new() -> Self {
+        Self { stack: Vec::new() }
     }
 }
 
 impl<'mir, 'tcx> interpret::Machine<'mir, 'tcx> for ConstPropMachine<'mir, 'tcx> {
     compile_time_machine!(<'mir, 'tcx>);
 
+    type MemoryKind = !;
+
     type MemoryExtra = ();
 
     fn find_mir_or_eval_fn(
@@ -235,18 +282,6 @@ impl<'mir, 'tcx> interpret::Machine<'mir, 'tcx> for ConstPropMachine<'mir, 'tcx>
         l.access()
     }
 
-    fn access_local_mut<'a>(
-        ecx: &'a mut InterpCx<'mir, 'tcx, Self>,
-        frame: usize,
-        local: Local,
-    ) -> InterpResult<'tcx, Result<&'a mut LocalValue<Self::PointerTag>, MemPlace<Self::PointerTag>>>
-    {
-        if frame == 0 && ecx.machine.only_propagate_inside_block_locals.contains(local) {
-            ecx.machine.written_only_inside_own_block_locals.insert(local);
-        }
-        ecx.machine.stack[frame].locals[local].access_mut()
-    }
-
     fn before_access_global(
         _memory_extra: &(),
         _alloc_id: AllocId,
@@ -258,7 +293,9 @@ impl<'mir, 'tcx> interpret::Machine<'mir, 'tcx> for ConstPropMachine<'mir, 'tcx>
             throw_machine_stop_str!("can't write to global");
         }
         // If the static allocation is mutable, then we can't const prop it as its content
-        // might be different at runtime.
+        // might be different at runtime. This covers `static mut` as well as statics with
+        // interior mutability, whose allocations interning leaves mutable. All other statics
+        // are `Freeze` and never change, so reads from them are folded into their values.
         if allocation.mutability == Mutability::Mut {
             throw_machine_stop_str!("can't access mutable globals in ConstProp");
         }
@@ -294,6 +331,22 @@ struct ConstPropagator<'mir, 'tcx> {
     // Because we have `MutVisitor` we can't obtain the `SourceInfo` from a `Location`. So we store
     // the last known `SourceInfo` here and just keep revisiting it.
     source_info: Option<SourceInfo>,
+    // Whether to emit lints and errors, which only the first run of const prop does.
+    report: bool,
+    /// The locals whose values came from another block, directly or through other locals.
+    from_other_blocks: BitSet<Local>,
+    /// Whether the statement or terminator being visited reads a local in `from_other_blocks`.
+    /// Lints aren't reported for it, so that they fire on the same code as when values didn't
+    /// flow between blocks.
+    reads_other_blocks: bool,
+    /// The target of the `switchInt` of the block being visited, if its value is known.
+    known_target: Option<BasicBlock>,
+    /// The assignment of the result of the call that ended the block being visited, if the call
+    /// was folded into a `goto`.
+    folded_call: Option<Statement<'tcx>>,
+    /// Whether anything in the body was replaced so far.
+    changed: bool,
+    def_id: DefId,
 }
 
 impl<'mir, 'tcx> LayoutOf for ConstPropagator<'mir, 'tcx> {
@@ -325,6 +378,7 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
         dummy_body: &'mir Body<'tcx>,
         tcx: TyCtxt<'tcx>,
         source: MirSource<'tcx>,
+        report: bool,
     ) -> ConstPropagator<'mir, 'tcx> {
         let def_id = source.def_id();
         let substs = &InternalSubsts::identity_for_item(tcx, def_id);
@@ -332,19 +386,7 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
 
         let span = tcx.def_span(def_id);
         let can_const_prop = CanConstProp::check(body);
-        let mut only_propagate_inside_block_locals = BitSet::new_empty(can_const_prop.len());
-        for (l, mode) in can_const_prop.iter_enumerated() {
-            if *mode == ConstPropMode::OnlyInsideOwnBlock {
-                only_propagate_inside_block_locals.insert(l);
-            }
-        }
-        let mut ecx = InterpCx::new(
-            tcx,
-            span,
-            param_env,
-            ConstPropMachine::new(only_propagate_inside_block_locals),
-            (),
-        );
+        let mut ecx = InterpCx::new(tcx, span, param_env, ConstPropMachine::new(), ());
 
         let ret = ecx
             .layout_of(body.return_ty().subst(tcx, substs))
@@ -375,6 +417,13 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
             //FIXME(wesleywiser) we can't steal this because `Visitor::super_visit_body()` needs it
             local_decls: body.local_decls.clone(),
             source_info: None,
+            report,
+            from_other_blocks: BitSet::new_empty(body.local_decls.len()),
+            reads_other_blocks: false,
+            known_target: None,
+            folded_call: None,
+            changed: false,
+            def_id,
         }
     }
 
@@ -402,6 +451,49 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
             LocalState { value: LocalValue::Uninitialized, layout: Cell::new(None) };
     }
 
+    /// Returns the values of the `FlowSensitive` locals, in the order of their indices. Values in
+    /// memory, like aggregates assigned field by field, are kept if they can be read as immediates,
+    /// since the memory may change before they are restored.
+    fn flow_sensitive_values(&self) -> Vec<LocalValue> {
+        self.flow_sensitive_locals()
+            .map(|local| match self.ecx.frame().locals[local].value {
+                LocalValue::Live(InterpOperand::Indirect(_)) => {
+                    match self.get_const(local.into()).map(|op| *op) {
+                        Some(imm @ InterpOperand::Immediate(_)) => LocalValue::Live(imm),
+                        _ => LocalValue::Uninitialized,
+                    }
+                }
+                value => value,
+            })
+            .collect()
+    }
+
+    /// Sets the `FlowSensitive` locals to the values that `predecessors` agree on, or to no value
+    /// if there are no predecessors.
+    fn join_flow_sensitive_values(&mut self, predecessors: &[&Vec<LocalValue>]) {
+        let locals: Vec<Local> = self.flow_sensitive_locals().collect();
+        for (index, local) in locals.into_iter().enumerate() {
+            let mut values = predecessors.iter().map(|values| values[index]);
+            let value = match values.next() {
+                Some(first) if values.all(|value| value == first) => first,
+                _ => LocalValue::Uninitialized,
+            };
+            if let LocalValue::Live(_) = value {
+                self.from_other_blocks.insert(local);
+            } else {
+                self.from_other_blocks.remove(local);
+            }
+            self.ecx.frame_mut().locals[local] = LocalState { value, layout: Cell::new(None) };
+        }
+    }
+
+    fn flow_sensitive_locals(&self) -> impl Iterator<Item = Local> + '_ {
+        self.can_const_prop
+            .iter_enumerated()
+            .filter(|&(_, &mode)| mode == ConstPropMode::FlowSensitive)
+            .map(|(local, _)| local)
+    }
+
     fn lint_root(&self, source_info: SourceInfo) -> Option<HirId> {
         match &self.source_scopes[source_info.scope].local_data {
             ClearCrossCrate::Set(data) => Some(data.lint_root),
@@ -438,6 +530,7 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
 
         match self.ecx.const_to_op(c.literal, None) {
             Ok(op) => Some(op),
+            Err(_) if !self.report => None,
             Err(error) => {
                 let tcx = self.ecx.tcx.at(c.span);
                 let err = error_to_const_error(&self.ecx, error, Some(c.span));
@@ -486,6 +579,9 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
         message: &'static str,
         panic: AssertKind<impl std::fmt::Debug>,
     ) -> Option<()> {
+        if !self.report || self.reads_other_blocks {
+            return None;
+        }
         let lint_root = self.lint_root(source_info)?;
         self.tcx.struct_span_lint_hir(lint, lint_root, source_info.span, |lint| {
             let mut err = lint.build(message);
@@ -601,6 +697,7 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
                                 value.layout.ty,
                                 self.source_info.unwrap().span,
                             );
+                            self.changed = true;
                         }
                     }
                 }
@@ -803,6 +900,7 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
                         value.layout.ty,
                         source_info.span,
                     ));
+                    self.changed = true;
                 }
                 Immediate::ScalarPair(
                     ScalarMaybeUninit::Scalar(one),
@@ -832,13 +930,18 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
                             });
 
                             if let Some(Some((ty1, ty2))) = opt_ty1_ty2 {
-                                *rval = Rvalue::Aggregate(
+                                let aggregate = Rvalue::Aggregate(
                                     Box::new(AggregateKind::Tuple),
                                     vec![
                                         self.operand_from_scalar(one, ty1, source_info.span),
                                         self.operand_from_scalar(two, ty2, source_info.span),
                                     ],
                                 );
+                                // A tuple of constants is built again on every run.
+                                if *rval != aggregate {
+                                    *rval = aggregate;
+                                    self.changed = true;
+                                }
                             }
                         }
                     }
@@ -867,6 +970,98 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
             _ => false,
         }
     }
+
+    /// Returns the constant that the function called by `func` always returns, if it does nothing
+    /// else. Only done on `mir_opt_level>=2`.
+    fn constant_return(&self, func: &Operand<'tcx>) -> Option<&'tcx ty::Const<'tcx>> {
+        if self.tcx.sess.opts.debugging_opts.mir_opt_level < 2 {
+            return None;
+        }
+        match self.resolve_callee(func)?.def {
+            ty::InstanceDef::Item(def) if self.can_use_optimized_mir(def.did) => {
+                self.tcx.mir_constant_return(def.did)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the instance that `func` calls, if it is known.
+    fn resolve_callee(&self, func: &Operand<'tcx>) -> Option<Instance<'tcx>> {
+        match func.ty(&self.local_decls, self.tcx).kind {
+            ty::FnDef(def_id, substs) if !substs.needs_subst() => {
+                Instance::resolve(self.tcx, self.param_env, def_id, substs).ok().flatten()
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the optimized MIR of `callee` may be used. As in inlining, the optimized MIR of a
+    /// local callee is only used if the callee comes after this body, so that it never uses ours,
+    /// and never with incremental compilation.
+    fn can_use_optimized_mir(&self, callee: DefId) -> bool {
+        match callee.as_local() {
+            Some(callee) => {
+                let hir = self.tcx.hir();
+                !self.tcx.dep_graph.is_fully_enabled()
+                    && hir.as_local_hir_id(self.def_id.expect_local()) < hir.as_local_hir_id(callee)
+            }
+            None => true,
+        }
+    }
+
+    /// Returns the result of a call of `func` with `args`, if it calls a `const fn` that only
+    /// computes its result from its arguments, the arguments are known scalars, and evaluating the
+    /// call at compile time succeeds. Only done on `mir_opt_level>=2`.
+    fn eval_const_fn_call(
+        &mut self,
+        func: &Operand<'tcx>,
+        args: &[Operand<'tcx>],
+        ret_ty: Ty<'tcx>,
+        source_info: SourceInfo,
+    ) -> Option<Scalar> {
+        if self.tcx.sess.opts.debugging_opts.mir_opt_level < 2 {
+            return None;
+        }
+        if !self.layout_of(ret_ty).map_or(false, |layout| layout.abi.is_scalar()) {
+            return None;
+        }
+        let instance = self.resolve_callee(func)?;
+        let callee = match instance.def {
+            ty::InstanceDef::Item(def) if self.tcx.is_const_fn_raw(def.did) => def.did,
+            _ => return None,
+        };
+        if !self.tcx.is_mir_available(callee) || !self.can_use_optimized_mir(callee) {
+            return None;
+        }
+        let body = self.tcx.optimized_mir(callee);
+        if !only_computes_result(body) {
+            trace!("not folding the call of {:?}, which may have side effects", callee);
+            return None;
+        }
+
+        let mut values = Vec::with_capacity(args.len());
+        for arg in args {
+            let value = self.eval_operand(arg, source_info)?;
+            if !self.should_const_prop(value) {
+                return None;
+            }
+            match *value {
+                InterpOperand::Immediate(Immediate::Scalar(ScalarMaybeUninit::Scalar(scalar))) => {
+                    values.push(scalar)
+                }
+                _ => return None,
+            }
+        }
+        eval_const_fn_call(
+            self.tcx,
+            self.param_env,
+            instance,
+            body,
+            &values,
+            ret_ty,
+            source_info.span,
+        )
+    }
 }
 
 /// The mode that `ConstProp` is allowed to run in for a given `Local`.
@@ -874,8 +1069,9 @@ impl<'mir, 'tcx> ConstPropagator<'mir, 'tcx> {
 enum ConstPropMode {
     /// The `Local` can be propagated into and reads of this `Local` can also be propagated.
     FullConstProp,
-    /// The `Local` can only be propagated into and from its own block.
-    OnlyInsideOwnBlock,
+    /// The `Local` can be propagated into, and its reads can be propagated in the blocks that it
+    /// has the same value on entry to from all of their predecessors.
+    FlowSensitive,
     /// The `Local` can be propagated into but reads cannot be propagated.
     OnlyPropagateInto,
     /// The `Local` cannot be part of propagation at all. Any statement
@@ -885,8 +1081,10 @@ enum ConstPropMode {
 
 struct CanConstProp {
     can_const_prop: IndexVec<Local, ConstPropMode>,
-    // False at the beginning. Once set, no more assignments are allowed to that local.
-    found_assignment: BitSet<Local>,
+    // The fields assigned so far, as the fields projected to reach them, with no fields for an
+    // assignment of the whole local. Once a field is assigned, no more assignments are allowed to
+    // it, to the fields it contains, or to the fields that contain it.
+    assigned_fields: IndexVec<Local, Vec<Vec<Field>>>,
     // Cache of locals' information
     local_kinds: IndexVec<Local, LocalKind>,
 }
@@ -896,7 +1094,7 @@ impl CanConstProp {
     fn check(body: &Body<'_>) -> IndexVec<Local, ConstPropMode> {
         let mut cpv = CanConstProp {
             can_const_prop: IndexVec::from_elem(ConstPropMode::FullConstProp, &body.local_decls),
-            found_assignment: BitSet::new_empty(body.local_decls.len()),
+            assigned_fields: IndexVec::from_elem(vec![], &body.local_decls),
             local_kinds: IndexVec::from_fn_n(
                 |local| body.local_kind(local),
                 body.local_decls.len(),
@@ -915,9 +1113,9 @@ impl CanConstProp {
                     local
                 );
             } else if cpv.local_kinds[local] == LocalKind::Var {
-                *val = ConstPropMode::OnlyInsideOwnBlock;
+                *val = ConstPropMode::FlowSensitive;
                 trace!(
-                    "local {:?} will only be propagated inside its block, because it's a user variable",
+                    "local {:?} will be propagated along edges, because it's a user variable",
                     local
                 );
             }
@@ -925,9 +1123,50 @@ impl CanConstProp {
         cpv.visit_body(&body);
         cpv.can_const_prop
     }
+
+    fn assign(&mut self, local: Local, fields: Vec<Field>) {
+        let assigned = &self.assigned_fields[local];
+        // Of two paths of fields, one contains the other if it is a prefix of it.
+        if assigned.iter().any(|other| other.iter().zip(&fields).all(|(a, b)| a == b)) {
+            match &mut self.can_const_prop[local] {
+                // If the local is propagated along edges, then we don't have to worry
+                // about multiple assignments, as every block only starts with the values
+                // its predecessors agree on, and inside the block we overwrite previous
+                // states as applicable.
+                ConstPropMode::FlowSensitive => {}
+                ConstPropMode::NoPropagation => {}
+                ConstPropMode::OnlyPropagateInto => {}
+                other @ ConstPropMode::FullConstProp => {
+                    trace!("local {:?} can't be propagated because of multiple assignments", local);
+                    *other = ConstPropMode::OnlyPropagateInto;
+                }
+            }
+        }
+        self.assigned_fields[local].push(fields);
+    }
 }
 
 impl<'tcx> Visitor<'tcx> for CanConstProp {
+    fn visit_place(&mut self, place: &Place<'tcx>, context: PlaceContext, location: Location) {
+        // A store into a field only assigns that field, so that aggregates built field by field
+        // are propagated like those assigned at once.
+        if let PlaceContext::MutatingUse(MutatingUseContext::Store) = context {
+            let fields: Option<Vec<Field>> = place
+                .projection
+                .iter()
+                .map(|elem| match elem {
+                    ProjectionElem::Field(field, _) => Some(field),
+                    _ => None,
+                })
+                .collect();
+            if let Some(fields) = fields.filter(|fields| !fields.is_empty()) {
+                self.assign(place.local, fields);
+                return;
+            }
+        }
+        self.super_place(place, context, location);
+    }
+
     fn visit_local(&mut self, &local: &Local, context: PlaceContext, _: Location) {
         use rustc_middle::mir::visit::PlaceContext::*;
         match context {
@@ -938,26 +1177,7 @@ impl<'tcx> Visitor<'tcx> for CanConstProp {
             // mutations of the same local via `Store`
             | MutatingUse(MutatingUseContext::Call)
             // Actual store that can possibly even propagate a value
-            | MutatingUse(MutatingUseContext::Store) => {
-                if !self.found_assignment.insert(local) {
-                    match &mut self.can_const_prop[local] {
-                        // If the local can only get propagated in its own block, then we don't have
-                        // to worry about multiple assignments, as we'll nuke the const state at the
-                        // end of the block anyway, and inside the block we overwrite previous
-                        // states as applicable.
-                        ConstPropMode::OnlyInsideOwnBlock => {}
-                        ConstPropMode::NoPropagation => {}
-                        ConstPropMode::OnlyPropagateInto => {}
-                        other @ ConstPropMode::FullConstProp => {
-                            trace!(
-                                "local {:?} can't be propagated because of multiple assignments",
-                                local,
-                            );
-                            *other = ConstPropMode::OnlyPropagateInto;
-                        }
-                    }
-                }
-            }
+            | MutatingUse(MutatingUseContext::Store) => self.assign(local, vec![]),
             // Reading constants is allowed an arbitrary number of times
             NonMutatingUse(NonMutatingUseContext::Copy)
             | NonMutatingUse(NonMutatingUseContext::Move)
@@ -992,8 +1212,58 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
     }
 
     fn visit_body(&mut self, body: &mut Body<'tcx>) {
-        for (bb, data) in body.basic_blocks_mut().iter_enumerated_mut() {
-            self.visit_basic_block_data(bb, data);
+        let predecessors = body.predecessors().clone();
+        let rpo: Vec<BasicBlock> = traversal::reverse_postorder(body).map(|(bb, _)| bb).collect();
+        // The values of the `FlowSensitive` locals at the end of every visited block, and the
+        // target of its `switchInt`, if it is known.
+        let mut exit_values: IndexVec<BasicBlock, Option<Vec<LocalValue>>> =
+            IndexVec::from_elem(None, body.basic_blocks());
+        let mut known_targets: IndexVec<BasicBlock, Option<BasicBlock>> =
+            IndexVec::from_elem(None, body.basic_blocks());
+        // The blocks that are unreachable, or only reachable along edges that aren't taken.
+        let mut dead = BitSet::new_filled(body.basic_blocks().len());
+        for &bb in &rpo {
+            dead.remove(bb);
+        }
+
+        for bb in rpo {
+            if bb != START_BLOCK {
+                let entered_from = |pred: BasicBlock| {
+                    !dead.contains(pred)
+                        && match known_targets[pred] {
+                            Some(target) => target == bb,
+                            None => body[pred].terminator().successors().any(|&succ| succ == bb),
+                        }
+                };
+                let entries: Vec<BasicBlock> =
+                    predecessors[bb].iter().copied().filter(|&pred| entered_from(pred)).collect();
+                if entries.is_empty() {
+                    dead.insert(bb);
+                    continue;
+                }
+                // A predecessor that isn't visited yet enters through a back edge.
+                let values: Option<Vec<&Vec<LocalValue>>> =
+                    entries.iter().map(|&pred| exit_values[pred].as_ref()).collect();
+                self.join_flow_sensitive_values(&values.unwrap_or_default());
+            }
+            self.visit_basic_block_data(bb, &mut body.basic_blocks_mut()[bb]);
+            exit_values[bb] = Some(self.flow_sensitive_values());
+            known_targets[bb] = self.known_target.take();
+        }
+
+        // Dead blocks are still checked for lints, knowing nothing about the `FlowSensitive`
+        // locals.
+        for bb in dead.iter() {
+            self.join_flow_sensitive_values(&[]);
+            self.visit_basic_block_data(bb, &mut body.basic_blocks_mut()[bb]);
+            self.known_target = None;
+        }
+    }
+
+    fn visit_basic_block_data(&mut self, block: BasicBlock, data: &mut BasicBlockData<'tcx>) {
+        self.super_basic_block_data(block, data);
+        if let Some(statement) = self.folded_call.take() {
+            data.statements.push(statement);
         }
     }
 
@@ -1018,6 +1288,8 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
         let source_info = statement.source_info;
         self.source_info = Some(source_info);
         if let StatementKind::Assign(box (place, ref mut rval)) = statement.kind {
+            let reads_other_blocks = reads_any(rval, &self.from_other_blocks);
+            self.reads_other_blocks = reads_other_blocks;
             let place_ty: Ty<'tcx> = place.ty(&self.local_decls, self.tcx).ty;
             if let Ok(place_layout) = self.tcx.layout_of(self.param_env.and(place_ty)) {
                 let can_const_prop = self.can_const_prop[place.local];
@@ -1030,19 +1302,15 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
                             trace!("replacing {:?} with {:?}", rval, value);
                             self.replace_with_const(rval, value, source_info);
                             if can_const_prop == ConstPropMode::FullConstProp
-                                || can_const_prop == ConstPropMode::OnlyInsideOwnBlock
+                                || can_const_prop == ConstPropMode::FlowSensitive
                             {
                                 trace!("propagated into {:?}", place);
                             }
                         }
                     }
                     match can_const_prop {
-                        ConstPropMode::OnlyInsideOwnBlock => {
-                            trace!(
-                                "found local restricted to its block. \
-                                Will remove it from const-prop after block is finished. Local: {:?}",
-                                place.local
-                            );
+                        ConstPropMode::FlowSensitive => {
+                            trace!("the value of {:?} flows along edges", place.local);
                         }
                         ConstPropMode::OnlyPropagateInto | ConstPropMode::NoPropagation => {
                             trace!("can't propagate into {:?}", place);
@@ -1077,6 +1345,11 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
                 );
                 Self::remove_const(&mut self.ecx, place.local);
             }
+            if reads_other_blocks {
+                self.from_other_blocks.insert(place.local);
+            } else if place.projection.is_empty() {
+                self.from_other_blocks.remove(place.local);
+            }
         } else {
             match statement.kind {
                 StatementKind::StorageLive(local) | StatementKind::StorageDead(local) => {
@@ -1098,9 +1371,15 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
     fn visit_terminator(&mut self, terminator: &mut Terminator<'tcx>, location: Location) {
         let source_info = terminator.source_info;
         self.source_info = Some(source_info);
+        self.reads_other_blocks = false;
         self.super_terminator(terminator, location);
+        let mut fold_switch = false;
+        let mut fold_call = None;
         match &mut terminator.kind {
             TerminatorKind::Assert { expected, ref msg, ref mut cond, .. } => {
+                self.reads_other_blocks = cond
+                    .place()
+                    .map_or(false, |place| self.from_other_blocks.contains(place.local));
                 if let Some(value) = self.eval_operand(&cond, source_info) {
                     trace!("assertion on {:?} should be {:?}", value, expected);
                     let expected = ScalarMaybeUninit::from(Scalar::from_bool(*expected));
@@ -1164,21 +1443,37 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
                     } else {
                         if self.should_const_prop(value) {
                             if let ScalarMaybeUninit::Scalar(scalar) = value_const {
-                                *cond = self.operand_from_scalar(
+                                let constant = self.operand_from_scalar(
                                     scalar,
                                     self.tcx.types.bool,
                                     source_info.span,
                                 );
+                                if *cond != constant {
+                                    *cond = constant;
+                                    self.changed = true;
+                                }
                             }
                         }
                     }
                 }
             }
-            TerminatorKind::SwitchInt { ref mut discr, .. } => {
+            TerminatorKind::SwitchInt { ref mut discr, ref values, ref targets, .. } => {
                 // FIXME: This is currently redundant with `visit_operand`, but sadly
                 // always visiting operands currently causes a perf regression in LLVM codegen, so
                 // `visit_operand` currently only runs for propagates places for `mir_opt_level=3`.
-                self.propagate_operand(discr)
+                self.propagate_operand(discr);
+                if let Some(value) = self.eval_operand(discr, source_info) {
+                    let bits = self.ecx.read_scalar(value).ok().and_then(|scalar| {
+                        let scalar = scalar.check_init().ok()?;
+                        scalar.to_bits_or_ptr(value.layout.size, &self.tcx).ok()
+                    });
+                    if let Some(bits) = bits {
+                        let index = values.iter().position(|&v| v == bits);
+                        let target = index.map_or_else(|| *targets.last().unwrap(), |i| targets[i]);
+                        self.known_target = Some(target);
+                        fold_switch = self.should_const_prop(value);
+                    }
+                }
             }
             // None of these have Operands to const-propagate.
             TerminatorKind::Goto { .. }
@@ -1197,29 +1492,125 @@ impl<'mir, 'tcx> MutVisitor<'tcx> for ConstPropagator<'mir, 'tcx> {
             //
             // NOTE: because LLVM codegen gives performance regressions with it, so this is gated
             // on `mir_opt_level=3`.
-            TerminatorKind::Call { .. } => {}
+            //
+            // Calls of functions that always return the same constant, and of trivial `const fn`s
+            // with known arguments, are folded into their result.
+            TerminatorKind::Call {
+                ref func, ref args, destination: Some((place, target)), ..
+            } => {
+                let ret_ty = place.ty(&self.local_decls, self.tcx).ty;
+                if let Some(literal) = self.constant_return(func) {
+                    fold_call = Some((*place, literal, *target));
+                } else if let Some(scalar) =
+                    self.eval_const_fn_call(func, args, ret_ty, source_info)
+                {
+                    self.reads_other_blocks = args.iter().any(|arg| {
+                        arg.place().map_or(false, |arg| self.from_other_blocks.contains(arg.local))
+                    });
+                    let literal = ty::Const::from_scalar(self.tcx, scalar, ret_ty);
+                    fold_call = Some((*place, literal, *target));
+                }
+            }
+            TerminatorKind::Call { destination: None, .. } => {}
         }
 
-        // We remove all Locals which are restricted in propagation to their containing blocks and
-        // which were modified in the current block.
-        // Take it out of the ecx so we can get a mutable reference to the ecx for `remove_const`.
-        let mut locals = std::mem::take(&mut self.ecx.machine.written_only_inside_own_block_locals);
-        for &local in locals.iter() {
-            Self::remove_const(&mut self.ecx, local);
+        if let (true, Some(target)) = (fold_switch, self.known_target) {
+            trace!("folding {:?} into a goto to {:?}", terminator.kind, target);
+            terminator.kind = TerminatorKind::Goto { target };
+            self.changed = true;
         }
-        locals.clear();
-        // Put it back so we reuse the heap of the storage
-        self.ecx.machine.written_only_inside_own_block_locals = locals;
-        if cfg!(debug_assertions) {
-            // Ensure we are correctly erasing locals with the non-debug-assert logic.
-            for local in self.ecx.machine.only_propagate_inside_block_locals.iter() {
-                assert!(
-                    self.get_const(local.into()).is_none()
-                        || self
-                            .layout_of(self.local_decls[local].ty)
-                            .map_or(true, |layout| layout.is_zst())
-                )
+
+        if let Some((place, literal, target)) = fold_call {
+            trace!("folding {:?} into {:?}", terminator.kind, literal);
+            let constant = Constant { span: source_info.span, user_ty: None, literal };
+            let rvalue = Rvalue::Use(Operand::Constant(Box::new(constant)));
+            self.folded_call =
+                Some(Statement { source_info, kind: StatementKind::Assign(box (place, rvalue)) });
+            terminator.kind = TerminatorKind::Goto { target };
+            self.changed = true;
+
+            // The result is known afterwards as if it was assigned by a statement.
+            let written = self.use_ecx(|this| {
+                let value = this.ecx.const_to_op(literal, None)?;
+                let dest = this.ecx.eval_place(place)?;
+                this.ecx.copy_op(value, dest)
+            });
+            let can_const_prop = self.can_const_prop[place.local];
+            if written.is_none()
+                || (place.local != RETURN_PLACE
+                    && (can_const_prop == ConstPropMode::OnlyPropagateInto
+                        || can_const_prop == ConstPropMode::NoPropagation))
+            {
+                Self::remove_const(&mut self.ecx, place.local);
+            }
+            if self.reads_other_blocks {
+                self.from_other_blocks.insert(place.local);
+            } else if place.projection.is_empty() {
+                self.from_other_blocks.remove(place.local);
+            }
+        }
+
+        // The interpreter doesn't run terminators, so the values of the locals they write to are
+        // unknown afterwards.
+        let mut written = vec![];
+        match terminator.kind {
+            TerminatorKind::Call { destination: Some((place, _)), .. }
+            | TerminatorKind::DropAndReplace { place, .. }
+            | TerminatorKind::Yield { resume_arg: place, .. } => written.push(place),
+            TerminatorKind::InlineAsm { ref operands, .. } => {
+                for operand in operands {
+                    match *operand {
+                        InlineAsmOperand::Out { place: Some(place), .. }
+                        | InlineAsmOperand::InOut { out_place: Some(place), .. } => {
+                            written.push(place)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+        for place in written {
+            if place.local != RETURN_PLACE {
+                Self::remove_const(&mut self.ecx, place.local);
             }
         }
     }
 }
+
+/// Whether `body` only computes its return value from its arguments, without calling anything
+/// that might have side effects, or running drop glue.
+fn only_computes_result(body: &Body<'_>) -> bool {
+    body.basic_blocks().iter().all(|data| {
+        matches!(
+            data.terminator().kind,
+            TerminatorKind::Goto { .. }
+                | TerminatorKind::SwitchInt { .. }
+                | TerminatorKind::Assert { .. }
+                | TerminatorKind::Return
+                | TerminatorKind::Resume
+                | TerminatorKind::Abort
+                | TerminatorKind::Unreachable
+                | TerminatorKind::FalseEdge { .. }
+                | TerminatorKind::FalseUnwind { .. }
+        )
+    })
+}
+
+/// Whether `rvalue` reads any of `locals`.
+fn reads_any<'tcx>(rvalue: &Rvalue<'tcx>, locals: &BitSet<Local>) -> bool {
+    struct FindLocal<'a> {
+        locals: &'a BitSet<Local>,
+        found: bool,
+    }
+
+    impl<'tcx> Visitor<'tcx> for FindLocal<'_> {
+        fn visit_local(&mut self, &local: &Local, _: PlaceContext, _: Location) {
+            self.found |= self.locals.contains(local);
+        }
+    }
+
+    let mut finder = FindLocal { locals, found: false };
+    finder.visit_rvalue(rvalue, Location::START);
+    finder.found
+}
@@ -0,0 +1,81 @@
+//! Finds the functions that always return the same constant, like the methods of trait impls
+//! returning a tag:
+//!
+//!     bb0: { _0 = const 3_u32; return; }
+//!
+//! `ConstProp` replaces their calls with the constant, without inlining them. A function only
+//! qualifies if dropping its call can't be observed: it may branch, but not loop, call anything,
+//! assert, drop values or write through pointers.
+
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::*;
+use rustc_middle::ty::query::Providers;
+use rustc_middle::ty::{self, TyCtxt, TypeFoldable};
+
+pub(crate) fn provide(providers: &mut Providers) {
+    providers.mir_constant_return = mir_constant_return;
+}
+
+fn mir_constant_return<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> Option<&'tcx ty::Const<'tcx>> {
+    if !matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn)
+        || !tcx.is_mir_available(def_id)
+    {
+        return None;
+    }
+    let body = tcx.optimized_mir(def_id);
+    if body.is_cfg_cyclic() {
+        return None;
+    }
+
+    let mut constant = None;
+    for data in body.basic_blocks() {
+        match data.terminator().kind {
+            TerminatorKind::Goto { .. }
+            | TerminatorKind::SwitchInt { .. }
+            | TerminatorKind::Unreachable
+            | TerminatorKind::FalseEdge { .. } => {}
+            TerminatorKind::Return => {
+                // The last write of the return place before returning must be in this block.
+                let returned = data.statements.iter().any(|statement| match statement.kind {
+                    StatementKind::Assign(box (place, _)) => place == Place::return_place(),
+                    _ => false,
+                });
+                if !returned {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+
+        for statement in &data.statements {
+            match statement.kind {
+                StatementKind::Assign(box (place, ref rvalue)) if place.local == RETURN_PLACE => {
+                    let literal = match *rvalue {
+                        Rvalue::Use(Operand::Constant(ref c)) if place.projection.is_empty() => {
+                            c.literal
+                        }
+                        _ => return None,
+                    };
+                    if !matches!(literal.val, ty::ConstKind::Value(_)) || literal.needs_subst() {
+                        return None;
+                    }
+                    if *constant.get_or_insert(literal) != literal {
+                        return None;
+                    }
+                }
+                StatementKind::Assign(box (place, _))
+                | StatementKind::SetDiscriminant { place: box place, .. }
+                    if place.is_indirect() =>
+                {
+                    return None;
+                }
+                StatementKind::LlvmInlineAsm(_) => return None,
+                _ => {}
+            }
+        }
+    }
+
+    debug!("{:?} always returns {:?}", def_id, constant);
+    constant
+}
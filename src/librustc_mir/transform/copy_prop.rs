@@ -19,30 +19,45 @@
 //! (non-mutating) use of `SRC`. These restrictions are conservative and may be relaxed in the
 //! future.
 
-use crate::transform::{MirPass, MirSource};
+use crate::transform::strip_debuginfo::strips_debuginfo;
+use crate::transform::{MirPass, MirSource, PassOutcome};
 use crate::util::def_use::DefUseAnalysis;
 use rustc_middle::mir::visit::MutVisitor;
 use rustc_middle::mir::{
     Body, Constant, Local, LocalKind, Location, Operand, Place, Rvalue, StatementKind,
 };
 use rustc_middle::ty::TyCtxt;
+use rustc_session::Session;
 
 pub struct CopyPropagation;
 
 impl<'tcx> MirPass<'tcx> for CopyPropagation {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, _source: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    fn is_enabled(&self, sess: &Session) -> bool {
         // We only run when the MIR optimization level is > 1.
-        // This avoids a slow pass, and messing up debug info.
-        if tcx.sess.opts.debugging_opts.mir_opt_level <= 1 {
-            return;
-        }
+        // This avoids a slow pass, and messing up debug info. Without debug info, the latter
+        // doesn't apply and the pass is worth its cost.
+        let mir_opt_level = sess.opts.debugging_opts.mir_opt_level;
+        mir_opt_level >= 2 || (mir_opt_level == 1 && strips_debuginfo(sess))
+    }
+
+    fn is_optimization(&self) -> bool {
+        true
+    }
 
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let mut changed_any = false;
         let mut def_use_analysis = DefUseAnalysis::new(body);
         loop {
             def_use_analysis.analyze(body);
 
             if eliminate_self_assignments(body, &def_use_analysis) {
                 def_use_analysis.analyze(body);
+                changed_any = true;
             }
 
             let mut changed = false;
@@ -146,7 +161,9 @@ impl<'tcx> MirPass<'tcx> for CopyPropagation {
             if !changed {
                 break;
             }
+            changed_any = true;
         }
+        PassOutcome::changed_if(changed_any)
     }
 }
 
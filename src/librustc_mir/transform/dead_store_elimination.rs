@@ -0,0 +1,76 @@
+//! Removes assignments to locals that are overwritten or go out of scope before they are read.
+//!
+//! Inlining and deaggregation leave behind many such stores, e.g. fields of a temporary that the
+//! callee never looked at. A store is dead if its local isn't live right after it, as computed by
+//! `LiveLocals`. Liveness doesn't see reads through references, so stores to locals that may be
+//! borrowed at that point are kept. So are stores whose rvalue has side effects, and stores of
+//! values that need dropping, since removing those could change when the value is dropped.
+
+use crate::dataflow::impls::MaybeBorrowedLocals;
+use crate::dataflow::Analysis;
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::liveness::{rvalue_is_removable, DebugInfoUses, LiveLocals};
+use rustc_middle::mir::*;
+use rustc_middle::ty::TyCtxt;
+
+pub struct DeadStoreElimination;
+
+impl<'tcx> MirPass<'tcx> for DeadStoreElimination {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let def_id = source.def_id();
+        let param_env = tcx.param_env(def_id);
+        let dead_stores = {
+            let body: &Body<'tcx> = body;
+            let mut live = LiveLocals::new(tcx, body, def_id, DebugInfoUses::Live);
+            let mut borrowed = MaybeBorrowedLocals::all_borrows()
+                .into_engine(tcx, body, def_id)
+                .iterate_to_fixpoint()
+                .into_results_cursor(body);
+
+            let mut dead_stores = Vec::new();
+            for (bb, data) in body.basic_blocks().iter_enumerated() {
+                for (statement_index, statement) in data.statements.iter().enumerate() {
+                    let place = match statement.kind {
+                        StatementKind::Assign(box (place, ref rvalue))
+                            if !place.is_indirect() && rvalue_is_removable(rvalue) =>
+                        {
+                            place
+                        }
+                        _ => continue,
+                    };
+                    let location = Location { block: bb, statement_index };
+                    if live.is_live_after(place.local, location) {
+                        continue;
+                    }
+                    borrowed.seek_before_primary_effect(location);
+                    if borrowed.contains(place.local) {
+                        continue;
+                    }
+                    if place.ty(body, tcx).ty.needs_drop(tcx, param_env) {
+                        continue;
+                    }
+                    debug!("removing dead store {:?} at {:?}", statement, location);
+                    dead_stores.push(location);
+                }
+            }
+            dead_stores
+        };
+
+        if dead_stores.is_empty() {
+            return PassOutcome::Unchanged;
+        }
+        for location in dead_stores {
+            body.make_statement_nop(location);
+        }
+        PassOutcome::Changed
+    }
+}
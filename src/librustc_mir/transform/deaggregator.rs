@@ -1,12 +1,23 @@
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 use crate::util::expand_aggregate;
 use rustc_middle::mir::*;
 use rustc_middle::ty::TyCtxt;
+use std::ops::RangeInclusive;
 
 pub struct Deaggregator;
 
 impl<'tcx> MirPass<'tcx> for Deaggregator {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, _source: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        // Const checking and borrowck work on aggregates.
+        MirPhase::DropElab..=MirPhase::Optimized
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
         let (basic_blocks, local_decls) = body.basic_blocks_and_local_decls_mut();
         let local_decls = &*local_decls;
         for bb in basic_blocks {
@@ -47,5 +58,6 @@ impl<'tcx> MirPass<'tcx> for Deaggregator {
                 ))
             });
         }
+        PassOutcome::Changed
     }
 }
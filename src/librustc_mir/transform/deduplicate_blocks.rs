@@ -0,0 +1,98 @@
+//! Merges basic blocks that are exact duplicates of each other.
+//!
+//! Match lowering and drop elaboration generate many blocks that do the same thing and go to the
+//! same places, for example one `drop(_3) -> bb7` block per path that leaves a scope:
+//!
+//!     bb4: { StorageDead(_5); drop(_3) -> bb7; }
+//!     bb6: { StorageDead(_5); drop(_3) -> bb7; }
+//!
+//! The predecessors of every duplicate are redirected to the first block with the same contents,
+//! and the duplicates are removed. Blocks are bucketed by a hash of their statements and
+//! terminator and then compared exactly, ignoring the source info of the statements. The source
+//! info of calls and assertions is compared as well, since it is the location a panic reports.
+//! Merging blocks can make their predecessors identical, so this is repeated until no more
+//! duplicates are found.
+
+use crate::transform::{simplify, MirPass, MirSource, PassOutcome};
+use rustc_data_structures::fingerprint::Fingerprint;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
+use rustc_middle::ich::StableHashingContext;
+use rustc_middle::mir::*;
+use rustc_middle::ty::TyCtxt;
+
+pub struct DeduplicateBlocks;
+
+impl<'tcx> MirPass<'tcx> for DeduplicateBlocks {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let mut changed = false;
+        loop {
+            let duplicates = find_duplicates(tcx, body);
+            if duplicates.is_empty() {
+                break;
+            }
+            debug!("merging duplicate blocks {:?}", duplicates);
+
+            for data in body.basic_blocks_mut() {
+                for successor in data.terminator_mut().successors_mut() {
+                    if let Some(&original) = duplicates.get(&*successor) {
+                        *successor = original;
+                    }
+                }
+            }
+            // The duplicates are unreachable now, so every round removes at least one block.
+            simplify::remove_dead_blocks(body);
+            changed = true;
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
+
+/// Maps every block that duplicates an earlier block to that block.
+fn find_duplicates<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    body: &Body<'tcx>,
+) -> FxHashMap<BasicBlock, BasicBlock> {
+    let mut hcx = tcx.create_no_span_stable_hashing_context();
+    let mut buckets: FxHashMap<Fingerprint, Vec<BasicBlock>> = FxHashMap::default();
+    let mut duplicates = FxHashMap::default();
+    for (bb, data) in body.basic_blocks().iter_enumerated() {
+        let bucket = buckets.entry(hash_block(&mut hcx, data)).or_default();
+        match bucket.iter().find(|&&other| same_contents(&body[other], data)) {
+            Some(&original) => {
+                duplicates.insert(bb, original);
+            }
+            None => bucket.push(bb),
+        }
+    }
+    duplicates
+}
+
+fn hash_block(hcx: &mut StableHashingContext<'_>, data: &BasicBlockData<'_>) -> Fingerprint {
+    let mut hasher = StableHasher::new();
+    data.is_cleanup.hash_stable(hcx, &mut hasher);
+    for statement in &data.statements {
+        statement.kind.hash_stable(hcx, &mut hasher);
+    }
+    data.terminator().kind.hash_stable(hcx, &mut hasher);
+    hasher.finish()
+}
+
+fn same_contents(a: &BasicBlockData<'_>, b: &BasicBlockData<'_>) -> bool {
+    let (terminator_a, terminator_b) = (a.terminator(), b.terminator());
+    let reports_location =
+        matches!(terminator_a.kind, TerminatorKind::Call { .. } | TerminatorKind::Assert { .. });
+    a.is_cleanup == b.is_cleanup
+        && terminator_a.kind == terminator_b.kind
+        && (!reports_location || terminator_a.source_info == terminator_b.source_info)
+        && a.statements.iter().map(|s| &s.kind).eq(b.statements.iter().map(|s| &s.kind))
+}
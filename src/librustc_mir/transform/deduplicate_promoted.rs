@@ -0,0 +1,170 @@
+//! Merges promoted bodies that are exact duplicates of each other.
+//!
+//! Every promotable expression gets a promoted body of its own, so code that repeats the same
+//! constant borrows, typically through macros, ends up with many copies of the same promoted:
+//!
+//!     promoted[0]: { _1 = [const 1_u32, const 2_u32]; _0 = &_1; return; }
+//!     promoted[1]: { _1 = [const 1_u32, const 2_u32]; _0 = &_1; return; }
+//!
+//! Right after promotion, the promoted bodies of a function are bucketed by a hash of their
+//! locals and blocks and then compared exactly, ignoring source info and the spans of constants.
+//! Every use of a duplicate is redirected to the first promoted with the same contents, and the
+//! duplicates are dropped, so that borrowck, const evaluation, metadata and codegen only see each
+//! of them once.
+//!
+//! Only promoteds whose blocks can't panic are merged, since a panic during their evaluation
+//! reports the location of the expression they were promoted from.
+
+use rustc_data_structures::fingerprint::Fingerprint;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
+use rustc_hir::def_id::DefId;
+use rustc_index::vec::IndexVec;
+use rustc_middle::ich::StableHashingContext;
+use rustc_middle::mir::visit::MutVisitor;
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_span::DUMMY_SP;
+use std::iter;
+use std::mem;
+
+/// Removes the duplicates among the `promoted` bodies of `body`, the body of `def_id`, and
+/// renumbers the uses of the others.
+pub fn deduplicate_promoted<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+    body: &mut Body<'tcx>,
+    promoted: &mut IndexVec<Promoted, Body<'tcx>>,
+) {
+    let mut hcx = tcx.create_no_span_stable_hashing_context();
+    let mut buckets: FxHashMap<Fingerprint, Vec<(Promoted, Body<'tcx>)>> = FxHashMap::default();
+    let mut new_index: IndexVec<Promoted, Promoted> = IndexVec::with_capacity(promoted.len());
+    let mut kept: IndexVec<Promoted, Promoted> = IndexVec::new();
+    for (p, promoted_body) in promoted.iter_enumerated() {
+        let original = if can_merge(promoted_body) {
+            let contents = without_constant_spans(tcx, promoted_body);
+            let bucket = buckets.entry(hash_promoted(&mut hcx, promoted_body)).or_default();
+            let original = bucket
+                .iter()
+                .find(|(_, other)| same_contents(other, &contents))
+                .map(|&(other, _)| other);
+            if original.is_none() {
+                bucket.push((p, contents));
+            }
+            original
+        } else {
+            None
+        };
+        match original {
+            Some(original) => {
+                debug!("merging {:?} into {:?}", p, original);
+                let index = new_index[original];
+                new_index.push(index);
+            }
+            None => {
+                new_index.push(kept.next_index());
+                kept.push(p);
+            }
+        }
+    }
+    if kept.len() == promoted.len() {
+        return;
+    }
+
+    let mut renumber = RenumberPromoted { tcx, def_id, new_index };
+    let mut old_promoted: IndexVec<Promoted, Option<Body<'tcx>>> =
+        mem::take(promoted).into_iter().map(Some).collect();
+    for p in kept {
+        promoted.push(old_promoted[p].take().unwrap());
+    }
+    for body in iter::once(body).chain(promoted.iter_mut()) {
+        renumber.visit_body(body);
+        for constant in &mut body.required_consts {
+            renumber.visit_constant(constant, Location::START);
+        }
+    }
+}
+
+fn can_merge(body: &Body<'_>) -> bool {
+    body.basic_blocks().iter().all(|data| {
+        !data.is_cleanup
+            && matches!(
+                data.terminator().kind,
+                TerminatorKind::Goto { .. } | TerminatorKind::Return
+            )
+    })
+}
+
+fn hash_promoted(hcx: &mut StableHashingContext<'_>, body: &Body<'_>) -> Fingerprint {
+    let mut hasher = StableHasher::new();
+    for decl in body.local_decls.iter() {
+        decl.ty.hash_stable(hcx, &mut hasher);
+        decl.mutability.hash_stable(hcx, &mut hasher);
+    }
+    for data in body.basic_blocks() {
+        for statement in &data.statements {
+            statement.kind.hash_stable(hcx, &mut hasher);
+        }
+        data.terminator().kind.hash_stable(hcx, &mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Returns a copy of `body` whose constants have no span, since promoteds of different expressions
+/// have constants with different spans, which `same_contents` would tell apart.
+fn without_constant_spans<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> Body<'tcx> {
+    let mut body = body.clone();
+    EraseConstantSpans { tcx }.visit_body(&mut body);
+    body
+}
+
+fn same_contents(a: &Body<'_>, b: &Body<'_>) -> bool {
+    let same_decls =
+        |a: &LocalDecl<'_>, b: &LocalDecl<'_>| a.ty == b.ty && a.mutability == b.mutability;
+    let same_blocks = |a: &BasicBlockData<'_>, b: &BasicBlockData<'_>| {
+        a.terminator().kind == b.terminator().kind
+            && a.statements.iter().map(|s| &s.kind).eq(b.statements.iter().map(|s| &s.kind))
+    };
+    a.local_decls.len() == b.local_decls.len()
+        && a.local_decls.iter().zip(b.local_decls.iter()).all(|(a, b)| same_decls(a, b))
+        && a.basic_blocks().len() == b.basic_blocks().len()
+        && a.basic_blocks().iter().zip(b.basic_blocks().iter()).all(|(a, b)| same_blocks(a, b))
+}
+
+struct EraseConstantSpans<'tcx> {
+    tcx: TyCtxt<'tcx>,
+}
+
+impl<'tcx> MutVisitor<'tcx> for EraseConstantSpans<'tcx> {
+    fn tcx(&self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    fn visit_constant(&mut self, constant: &mut Constant<'tcx>, _: Location) {
+        constant.span = DUMMY_SP;
+    }
+}
+
+/// Points the uses of the promoteds of `def_id` at their new index.
+struct RenumberPromoted<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+    new_index: IndexVec<Promoted, Promoted>,
+}
+
+impl<'tcx> MutVisitor<'tcx> for RenumberPromoted<'tcx> {
+    fn tcx(&self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    fn visit_constant(&mut self, constant: &mut Constant<'tcx>, _: Location) {
+        if let ty::ConstKind::Unevaluated(def, substs, Some(p)) = constant.literal.val {
+            if def.did == self.def_id {
+                constant.literal = self.tcx.mk_const(ty::Const {
+                    ty: constant.literal.ty,
+                    val: ty::ConstKind::Unevaluated(def, substs, Some(self.new_index[p])),
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Destination propagation.
+//!
+//! Call results and other values are often computed into a temporary, only to be moved into
+//! their real destination right after:
+//!
+//!     _5 = foo() -> bb1
+//!     ...
+//!     _0 = move _5
+//!
+//! which codegen turns into a `memcpy`. If the source and the destination of such a copy are
+//! never live at the same time, they can be merged into one local, and the copy removed:
+//!
+//!     _0 = foo() -> bb1
+//!
+//! The source is renamed to the destination everywhere, which generalizes `RenameReturnPlace` to
+//! any pair of locals. Liveness doesn't see reads through references, so locals that are ever
+//! borrowed are left alone, and so are the locals described by debuginfo, which are considered
+//! live everywhere. Each local is merged at most once per run of the pass. The merged local loses
+//! the storage markers of both, since neither describes its live range anymore.
+//!
+//! The destination of a call is never merged with one of its arguments, even a moved one: in
+//! `_t = f(move _d); _d = move _t`, `_d` is dead after the call, but codegen may pass `_d` by
+//! reference, so writing the result into it would clobber the argument while `f` reads it.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::liveness::{ever_borrowed_locals, DebugInfoUses, LiveLocals};
+use rustc_index::bit_set::BitSet;
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::visit::{MutVisitor, MutatingUseContext, PlaceContext, Visitor};
+use rustc_middle::mir::*;
+use rustc_middle::ty::TyCtxt;
+
+pub struct DestinationPropagation;
+
+impl<'tcx> MirPass<'tcx> for DestinationPropagation {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let merges = {
+            let body: &Body<'tcx> = body;
+            let candidates = find_candidates(body);
+            if candidates.is_empty() {
+                return PassOutcome::Unchanged;
+            }
+            let mut live = LiveLocals::new(tcx, body, source.def_id(), DebugInfoUses::Live);
+            remove_conflicting(body, &mut live, candidates)
+        };
+        if merges.is_empty() {
+            return PassOutcome::Unchanged;
+        }
+
+        let mut renames = IndexVec::from_elem(None, &body.local_decls);
+        let mut merged = BitSet::new_empty(body.local_decls.len());
+        for candidate in &merges {
+            debug!("merging {:?} into {:?}", candidate.src, candidate.dest);
+            renames[candidate.src] = Some(candidate.dest);
+            merged.insert(candidate.src);
+            merged.insert(candidate.dest);
+        }
+        for candidate in &merges {
+            body.make_statement_nop(candidate.location);
+        }
+        Renamer { tcx, renames, merged }.visit_body(body);
+        PassOutcome::Changed
+    }
+}
+
+/// A copy `dest = src` whose locals may be merged.
+struct Candidate {
+    dest: Local,
+    src: Local,
+    location: Location,
+}
+
+/// Finds the copies between locals of the same type that are never borrowed, using every local
+/// in at most one of them.
+fn find_candidates(body: &Body<'_>) -> Vec<Candidate> {
+    let mut excluded = ever_borrowed_locals(body);
+
+    let mut candidates = Vec::new();
+    for (bb, data) in body.basic_blocks().iter_enumerated() {
+        for (statement_index, statement) in data.statements.iter().enumerate() {
+            let (dest, src) = match statement.kind {
+                StatementKind::Assign(box (
+                    dest,
+                    Rvalue::Use(Operand::Copy(src) | Operand::Move(src)),
+                )) => match (dest.as_local(), src.as_local()) {
+                    (Some(dest), Some(src)) => (dest, src),
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            // Only the source is renamed, so it can't be the return place or an argument.
+            if dest == src
+                || body.local_kind(src) == LocalKind::Arg
+                || body.local_kind(src) == LocalKind::ReturnPointer
+                || body.local_decls[dest].ty != body.local_decls[src].ty
+                || excluded.contains(dest)
+                || excluded.contains(src)
+            {
+                continue;
+            }
+            excluded.insert(dest);
+            excluded.insert(src);
+            candidates.push(Candidate {
+                dest,
+                src,
+                location: Location { block: bb, statement_index },
+            });
+        }
+    }
+    candidates
+}
+
+/// Returns the candidates whose locals are never live at the same time, except for the value
+/// the copy hands over.
+fn remove_conflicting(
+    body: &Body<'_>,
+    live: &mut LiveLocals<'_, '_>,
+    mut candidates: Vec<Candidate>,
+) -> Vec<Candidate> {
+    let mut written = BitSet::new_empty(body.local_decls.len());
+    let mut outputs = BitSet::new_empty(body.local_decls.len());
+    let mut inputs = BitSet::new_empty(body.local_decls.len());
+    for (bb, data) in body.basic_blocks().iter_enumerated() {
+        for statement_index in 0..=data.statements.len() {
+            let location = Location { block: bb, statement_index };
+            written.clear();
+            outputs.clear();
+            inputs.clear();
+            let mut finder = WriteFinder { written: &mut written };
+            match data.statements.get(statement_index) {
+                Some(statement) => finder.visit_statement(statement, location),
+                None => {
+                    finder.visit_terminator(data.terminator(), location);
+                    collect_terminator_operands(
+                        data.terminator(),
+                        location,
+                        &mut outputs,
+                        &mut inputs,
+                    );
+                }
+            }
+
+            candidates.retain(|candidate| {
+                if candidate.location == location {
+                    // The source may only be read again if it still holds the copied value, which
+                    // isn't worth finding out.
+                    return !live.is_live_after(candidate.src, location);
+                }
+                let (src, dest) = (candidate.src, candidate.dest);
+                if (outputs.contains(src) && inputs.contains(dest))
+                    || (outputs.contains(dest) && inputs.contains(src))
+                {
+                    return false;
+                }
+                // A local is occupied where it is written to or may be read later.
+                let src_occupied =
+                    written.contains(candidate.src) || live.is_live_after(candidate.src, location);
+                let dest_occupied = written.contains(candidate.dest)
+                    || live.is_live_after(candidate.dest, location);
+                !(src_occupied && dest_occupied)
+            });
+            if candidates.is_empty() {
+                return candidates;
+            }
+        }
+    }
+    candidates
+}
+
+/// Collects the locals that `terminator` writes its results to into `outputs`, and the locals its
+/// operands mention into `inputs`. Codegen may pass the arguments of a call by reference, and
+/// read the inputs of inline assembly after writing its outputs, so the two must stay distinct.
+fn collect_terminator_operands(
+    terminator: &Terminator<'tcx>,
+    location: Location,
+    outputs: &mut BitSet<Local>,
+    inputs: &mut BitSet<Local>,
+) {
+    let mut input_collector = LocalCollector { locals: inputs };
+    match terminator.kind {
+        TerminatorKind::Call {
+            ref func, ref args, destination: Some((destination, _)), ..
+        } => {
+            outputs.insert(destination.local);
+            input_collector.visit_operand(func, location);
+            for arg in args {
+                input_collector.visit_operand(arg, location);
+            }
+        }
+        TerminatorKind::InlineAsm { ref operands, .. } => {
+            for operand in operands {
+                match *operand {
+                    InlineAsmOperand::In { ref value, .. }
+                    | InlineAsmOperand::Const { ref value } => {
+                        input_collector.visit_operand(value, location);
+                    }
+                    InlineAsmOperand::Out { place, .. } => {
+                        if let Some(place) = place {
+                            outputs.insert(place.local);
+                        }
+                    }
+                    InlineAsmOperand::InOut { ref in_value, out_place, .. } => {
+                        input_collector.visit_operand(in_value, location);
+                        if let Some(place) = out_place {
+                            outputs.insert(place.local);
+                        }
+                    }
+                    InlineAsmOperand::SymFn { .. }
+                    | InlineAsmOperand::SymStatic { .. }
+                    | InlineAsmOperand::Label { .. } => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+struct LocalCollector<'a> {
+    locals: &'a mut BitSet<Local>,
+}
+
+impl<'tcx> Visitor<'tcx> for LocalCollector<'_> {
+    fn visit_local(&mut self, local: &Local, _: PlaceContext, _: Location) {
+        self.locals.insert(*local);
+    }
+}
+
+struct WriteFinder<'a> {
+    written: &'a mut BitSet<Local>,
+}
+
+impl<'tcx> Visitor<'tcx> for WriteFinder<'_> {
+    fn visit_place(&mut self, place: &Place<'tcx>, context: PlaceContext, location: Location) {
+        match context {
+            // Drops read the value, which liveness already accounts for.
+            PlaceContext::MutatingUse(MutatingUseContext::Drop) => {}
+            PlaceContext::MutatingUse(_) if !place.is_indirect() => {
+                self.written.insert(place.local);
+            }
+            _ => {}
+        }
+        self.super_place(place, context, location);
+    }
+}
+
+struct Renamer<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    renames: IndexVec<Local, Option<Local>>,
+    merged: BitSet<Local>,
+}
+
+impl<'tcx> MutVisitor<'tcx> for Renamer<'tcx> {
+    fn tcx(&self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    fn visit_statement(&mut self, statement: &mut Statement<'tcx>, location: Location) {
+        if let StatementKind::StorageLive(local) | StatementKind::StorageDead(local) =
+            statement.kind
+        {
+            if self.merged.contains(local) {
+                statement.make_nop();
+                return;
+            }
+        }
+        self.super_statement(statement, location);
+    }
+
+    fn visit_local(&mut self, local: &mut Local, _: PlaceContext, _: Location) {
+        if let Some(dest) = self.renames[*local] {
+            *local = dest;
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Devirtualization of trait object calls.
+//!
+//! Inlining often leaves a reference that is turned into a trait object right before a method is
+//! called on it, so that the type behind the trait object is known statically:
+//!
+//!     _2 = move _3 as &dyn Trait (Pointer(Unsize));
+//!     _1 = <dyn Trait as Trait>::method(move _2) -> bb1;
+//!
+//! Such a call goes through the vtable for no reason. The pass calls the method of the concrete
+//! type directly instead, which the inliner can then inline:
+//!
+//!     _2 = _3 as &dyn Trait (Pointer(Unsize));
+//!     _1 = <Concrete as Trait>::method(_3) -> bb1;
+//!
+//! The coercion has to be in the block of the call and only shared references are handled, so
+//! that the reference can be copied to pass it on its own. If the reference dies before the call,
+//! its `StorageDead` is removed. The trait object may still be used elsewhere, so the coercion is
+//! left for `SimplifyLocals` to remove if it is dead.
+
+use super::jump_threading::writes_to;
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::liveness::ever_borrowed_locals;
+use rustc_hir::def_id::DefId;
+use rustc_index::bit_set::BitSet;
+use rustc_middle::mir::*;
+use rustc_middle::ty::adjustment::PointerCast;
+use rustc_middle::ty::subst::SubstsRef;
+use rustc_middle::ty::{self, Instance, InstanceDef, TyCtxt};
+
+pub struct Devirtualize;
+
+impl<'tcx> MirPass<'tcx> for Devirtualize {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let param_env = tcx.param_env_reveal_all_normalized(source.def_id());
+        let borrowed = ever_borrowed_locals(body);
+
+        let mut changed = false;
+        for bb in body.basic_blocks().indices() {
+            let call = match find_call(tcx, param_env, body, bb, &borrowed) {
+                Some(call) => call,
+                None => continue,
+            };
+            debug!("devirtualizing the call in {:?} to {:?}", bb, call.substs);
+
+            // The reference is now used twice, so the coercion can't move it anymore.
+            let receiver = match call.receiver {
+                Operand::Move(place) => Operand::Copy(place),
+                receiver => receiver,
+            };
+            let data = &mut body.basic_blocks_mut()[bb];
+            if let StatementKind::Assign(box (_, Rvalue::Cast(_, ref mut operand, _))) =
+                data.statements[call.coercion].kind
+            {
+                *operand = receiver.clone();
+            }
+            // The reference usually is a temporary that dies right after the coercion, but it
+            // has to live until the call now.
+            if let Some(place) = receiver.place() {
+                for statement in &mut data.statements[call.coercion + 1..] {
+                    if statement.kind == StatementKind::StorageDead(place.local) {
+                        statement.make_nop();
+                    }
+                }
+            }
+            let terminator = data.terminator_mut();
+            if let TerminatorKind::Call { ref mut func, ref mut args, .. } = terminator.kind {
+                let span = terminator.source_info.span;
+                *func = Operand::function_handle(tcx, call.method, call.substs, span);
+                args[0] = receiver;
+            }
+            changed = true;
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
+
+/// A virtual call whose receiver is coerced from `receiver` by the statement with index
+/// `coercion` in the same block, with the substs that call the method of the concrete type.
+struct VirtualCall<'tcx> {
+    method: DefId,
+    substs: SubstsRef<'tcx>,
+    coercion: usize,
+    receiver: Operand<'tcx>,
+}
+
+fn find_call<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    body: &Body<'tcx>,
+    bb: BasicBlock,
+    borrowed: &BitSet<Local>,
+) -> Option<VirtualCall<'tcx>> {
+    let data = &body[bb];
+    let (func, args) = match data.terminator().kind {
+        TerminatorKind::Call { ref func, ref args, .. } => (func, args),
+        _ => return None,
+    };
+    let (method, substs) = match func.ty(body, tcx).kind {
+        ty::FnDef(method, substs) => (method, substs),
+        _ => return None,
+    };
+    tcx.trait_of_item(method)?;
+    if !matches!(substs.type_at(0).kind, ty::Dynamic(..)) {
+        return None;
+    }
+    let object = match args.first()? {
+        Operand::Copy(place) | Operand::Move(place) => place.as_local()?,
+        Operand::Constant(_) => return None,
+    };
+    if borrowed.contains(object) {
+        return None;
+    }
+
+    // The last write to the trait object in the block has to be the coercion.
+    let (coercion, statement) = data
+        .statements
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, statement)| writes_to(statement, object))?;
+    let receiver = match statement.kind {
+        StatementKind::Assign(box (
+            place,
+            Rvalue::Cast(CastKind::Pointer(PointerCast::Unsize), ref operand, _),
+        )) if place.as_local() == Some(object) => operand,
+        _ => return None,
+    };
+    // The reference must still hold the same value at the call.
+    match receiver {
+        Operand::Copy(place) | Operand::Move(place) => {
+            let local = place.as_local()?;
+            if borrowed.contains(local)
+                || data.statements[coercion + 1..].iter().any(|s| writes_to(s, local))
+            {
+                return None;
+            }
+        }
+        Operand::Constant(_) => {}
+    }
+    let self_ty = match receiver.ty(body, tcx).kind {
+        ty::Ref(_, pointee, Mutability::Not) => pointee,
+        _ => return None,
+    };
+
+    // Only call the concrete method if it can be found here already.
+    let substs = tcx.mk_substs_trait(self_ty, &substs[1..]);
+    let instance = Instance::resolve(tcx, param_env, method, substs).ok().flatten()?;
+    if let InstanceDef::Virtual(..) = instance.def {
+        return None;
+    }
+
+    Some(VirtualCall { method, substs, coercion, receiver: receiver.clone() })
+}
@@ -0,0 +1,159 @@
+//! Folds switches on discriminants that were just set.
+//!
+//! The deaggregator turns the construction of an enum into writes of its fields and of its
+//! discriminant, which a match on the new value soon reads again:
+//!
+//!     bb0: {
+//!         ((_1 as Some).0: u32) = _2;
+//!         discriminant(_1) = 1;
+//!         _3 = discriminant(_1);
+//!         switchInt(move _3) -> [0_isize: bb1, 1_isize: bb2, otherwise: bb3];
+//!     }
+//!
+//! The pass goes forward through every block, remembering the variant of the places whose
+//! discriminant was set, or that were assigned a new value of a variant, and the discriminant
+//! values that were read into locals. A switch on a local with a known value becomes a `goto` to
+//! the target of that value:
+//!
+//!         switchInt(move _3) -> [0_isize: bb1, 1_isize: bb2, otherwise: bb3];
+//!
+//! becomes
+//!
+//!         goto -> bb2;
+//!
+//! What is known at the end of a block carries over to a successor that a `goto` leads to and
+//! that has no other predecessor. Places of borrowed locals, or behind a pointer, are not tracked,
+//! and any write to the local of a place, or the end of its storage, forgets its variant.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::liveness::ever_borrowed_locals;
+use rustc_index::bit_set::BitSet;
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::visit::{PlaceContext, Visitor};
+use rustc_middle::mir::*;
+use rustc_middle::ty::TyCtxt;
+use rustc_target::abi::VariantIdx;
+
+pub struct DiscriminantPropagation;
+
+impl<'tcx> MirPass<'tcx> for DiscriminantPropagation {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let borrowed = ever_borrowed_locals(body);
+        let predecessor_counts: IndexVec<BasicBlock, usize> =
+            body.predecessors().iter().map(|predecessors| predecessors.len()).collect();
+        let rpo: Vec<BasicBlock> = body.postorder().iter().rev().copied().collect();
+
+        let mut entry_states: IndexVec<BasicBlock, Option<Known<'tcx>>> =
+            IndexVec::from_elem(None, body.basic_blocks());
+        let mut changed = false;
+        for bb in rpo {
+            let mut known = entry_states[bb].take().unwrap_or_default();
+            for statement in &body[bb].statements {
+                known.apply(tcx, body, &borrowed, statement);
+            }
+
+            let data = &body[bb];
+            let target = match data.terminator().kind {
+                TerminatorKind::SwitchInt {
+                    discr: Operand::Copy(place) | Operand::Move(place),
+                    ref values,
+                    ref targets,
+                    ..
+                } => place.as_local().and_then(|local| known.value_of(local)).map(|value| {
+                    let index = values.iter().position(|&v| v == value);
+                    index.map_or_else(|| *targets.last().unwrap(), |i| targets[i])
+                }),
+                _ => None,
+            };
+            if let Some(target) = target {
+                debug!("folding the switch in {:?} to {:?}", bb, target);
+                body.basic_blocks_mut()[bb].terminator_mut().kind = TerminatorKind::Goto { target };
+                changed = true;
+            }
+
+            if let TerminatorKind::Goto { target } = body[bb].terminator().kind {
+                if predecessor_counts[target] == 1 && body[target].is_cleanup == body[bb].is_cleanup
+                {
+                    entry_states[target] = Some(known);
+                }
+            }
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
+
+/// The variants places are known to have, and the discriminant values locals are known to hold.
+#[derive(Clone, Default)]
+struct Known<'tcx> {
+    variants: Vec<(Place<'tcx>, VariantIdx)>,
+    values: Vec<(Local, u128)>,
+}
+
+impl<'tcx> Known<'tcx> {
+    fn apply(
+        &mut self,
+        tcx: TyCtxt<'tcx>,
+        body: &Body<'tcx>,
+        borrowed: &BitSet<Local>,
+        statement: &Statement<'tcx>,
+    ) {
+        let mut writes = Writes(Vec::new());
+        writes.visit_statement(statement, Location::START);
+        for local in writes.0 {
+            self.variants.retain(|&(place, _)| place.local != local);
+            self.values.retain(|&(value_local, _)| value_local != local);
+        }
+
+        let trackable =
+            |place: &Place<'tcx>| !borrowed.contains(place.local) && !place.is_indirect();
+        match statement.kind {
+            StatementKind::SetDiscriminant { ref place, variant_index } if trackable(place) => {
+                self.variants.push((**place, variant_index));
+            }
+            StatementKind::Assign(box (
+                place,
+                Rvalue::Aggregate(box AggregateKind::Adt(adt_def, variant_index, ..), _),
+            )) if adt_def.is_enum() && trackable(&place) => {
+                self.variants.push((place, variant_index));
+            }
+            StatementKind::Assign(box (place, Rvalue::Discriminant(source))) => {
+                let local = match place.as_local() {
+                    Some(local) => local,
+                    None => return,
+                };
+                let variant = self.variants.iter().find(|&&(known, _)| known == source);
+                let ty = source.ty(body, tcx).ty;
+                let discr =
+                    variant.and_then(|&(_, variant)| ty.discriminant_for_variant(tcx, variant));
+                if let Some(discr) = discr {
+                    self.values.push((local, discr.val));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn value_of(&self, local: Local) -> Option<u128> {
+        self.values.iter().find(|&&(known, _)| known == local).map(|&(_, value)| value)
+    }
+}
+
+/// Collects the locals that a statement writes to, or whose storage it starts or ends.
+struct Writes(Vec<Local>);
+
+impl<'tcx> Visitor<'tcx> for Writes {
+    fn visit_local(&mut self, &local: &Local, context: PlaceContext, _: Location) {
+        if context.is_mutating_use() || context.is_storage_marker() {
+            self.0.push(local);
+        }
+    }
+}
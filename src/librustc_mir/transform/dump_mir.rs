@@ -2,14 +2,15 @@
 
 use std::borrow::Cow;
 use std::fmt;
-use std::fs::File;
-use std::io;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
 
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 use crate::util as mir_util;
-use rustc_middle::mir::Body;
-use rustc_middle::ty::TyCtxt;
-use rustc_session::config::{OutputFilenames, OutputType};
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::mir::{Body, MirPhase};
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_session::config::{MirEmitPhase, OutputFilenames, OutputType};
 
 pub struct Marker(pub &'static str);
 
@@ -18,7 +19,14 @@ impl<'tcx> MirPass<'tcx> for Marker {
         Cow::Borrowed(self.0)
     }
 
-    fn run_pass(&self, _tcx: TyCtxt<'tcx>, _source: MirSource<'tcx>, _body: &mut Body<'tcx>) {}
+    fn run_pass(
+        &self,
+        _tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        _body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        PassOutcome::Unchanged
+    }
 }
 
 pub struct Disambiguator {
@@ -36,12 +44,17 @@ pub fn on_mir_pass<'tcx>(
     tcx: TyCtxt<'tcx>,
     pass_num: &dyn fmt::Display,
     pass_name: &str,
+    pass_id: &str,
     source: MirSource<'tcx>,
     body: &Body<'tcx>,
     is_after: bool,
 ) {
-    if mir_util::dump_enabled(tcx, pass_name, source.def_id()) {
-        mir_util::dump_mir(
+    // The filters can pick out one of the runs of a pass scheduled several times by its
+    // identifier, e.g. `AddCallGuards#2`.
+    let enabled = mir_util::dump_enabled_for_source(tcx, pass_name, source)
+        || (pass_id != pass_name && mir_util::dump_enabled_for_source(tcx, pass_id, source));
+    if enabled {
+        mir_util::pretty::dump_matched_mir_node(
             tcx,
             Some(pass_num),
             pass_name,
@@ -59,3 +72,71 @@ pub fn emit_mir(tcx: TyCtxt<'_>, outputs: &OutputFilenames) -> io::Result<()> {
     mir_util::write_mir_pretty(tcx, None, &mut f)?;
     Ok(())
 }
+
+fn mir_phase(phase: MirEmitPhase) -> MirPhase {
+    match phase {
+        MirEmitPhase::Const => MirPhase::Const,
+        MirEmitPhase::Validated => MirPhase::Validated,
+        MirEmitPhase::DropElab => MirPhase::DropElab,
+        MirEmitPhase::Inlined => MirPhase::Inlined,
+        MirEmitPhase::Optimized => MirPhase::Optimized,
+    }
+}
+
+/// Creates the output file for `-Z mir-emit-phase`. Bodies are appended to it by
+/// `on_mir_phase_end` as they reach the requested phase, which must happen before anything
+/// can steal them for a later phase.
+pub fn start_emit_mir_phase(tcx: TyCtxt<'_>) -> io::Result<()> {
+    let path = tcx.output_filenames(LOCAL_CRATE).path(OutputType::Mir);
+    write_emit_mir_header(&mut File::create(&path)?)
+}
+
+fn write_emit_mir_header(f: &mut File) -> io::Result<()> {
+    writeln!(f, "// WARNING: This output format is intended for human consumers only")?;
+    writeln!(f, "// and is subject to change without notice. Knock yourself out.")
+}
+
+pub fn on_mir_phase_end<'tcx>(tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &Body<'tcx>) {
+    match tcx.sess.opts.debugging_opts.mir_emit_phase {
+        Some(phase) if mir_phase(phase) == body.phase => {}
+        _ => return,
+    }
+
+    let emit = || -> io::Result<()> {
+        // Render the whole body first so that concurrently emitted bodies do not interleave.
+        let mut buf = Vec::new();
+        writeln!(buf)?;
+        mir_util::write_mir_fn(tcx, source, body, &mut |_, _| Ok(()), &mut buf)?;
+        let path = tcx.output_filenames(LOCAL_CRATE).path(OutputType::Mir);
+        // Drivers that don't call `start_emit_mir_phase` leave the file to the first body.
+        let mut f = OpenOptions::new().create(true).append(true).open(&path)?;
+        if f.metadata()?.len() == 0 {
+            write_emit_mir_header(&mut f)?;
+        }
+        f.write_all(&buf)
+    };
+    if let Err(e) = emit() {
+        tcx.sess.err(&format!("could not emit MIR: {}", e));
+    }
+}
+
+/// Drives every body of the local crate up to the phase requested by `-Z mir-emit-phase`.
+/// Bodies that already got there (e.g., during analysis) have been emitted at that point.
+pub fn finish_emit_mir_phase(tcx: TyCtxt<'_>, phase: MirEmitPhase) {
+    for def_id in tcx.body_owners() {
+        let def = ty::WithOptConstParam::unknown(def_id);
+        match phase {
+            MirEmitPhase::Const => tcx.ensure().mir_const(def),
+            MirEmitPhase::Validated => tcx.ensure().mir_validated(def),
+            MirEmitPhase::DropElab => tcx.ensure().mir_drops_elaborated_and_const_checked(def),
+            MirEmitPhase::Inlined => {
+                tcx.ensure().mir_inlined(def);
+                tcx.ensure().promoted_mir(def);
+            }
+            MirEmitPhase::Optimized => {
+                tcx.ensure().optimized_mir(def_id.to_def_id());
+                tcx.ensure().promoted_mir(def);
+            }
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! Merges nested switches on the discriminants of two places into a single switch.
+//!
+//! Matching on a tuple of two enums first switches on the discriminant of the first enum, and then
+//! on the discriminant of the second one in every arm, with a shared otherwise target:
+//!
+//!     bb0: { _3 = discriminant(_1); switchInt(move _3) -> [0: bb2, 1: bb3, otherwise: bb1]; }
+//!     bb2: { _4 = discriminant(_2); switchInt(move _4) -> [0: bb5, otherwise: bb4]; }
+//!     bb3: { _5 = discriminant(_2); switchInt(move _5) -> [1: bb6, otherwise: bb4]; }
+//!
+//! If every arm of the outer switch only continues when the second discriminant has the same value
+//! as the first one, the second discriminant is read early, and the outer switch is only reached
+//! if both are equal, going straight to the targets of the inner switches:
+//!
+//!     bb0: {
+//!         _3 = discriminant(_1);
+//!         _6 = discriminant(_2);
+//!         _7 = Ne(_3, move _6);
+//!         switchInt(move _7) -> [false: bb7, otherwise: bb4];
+//!     }
+//!     bb7: { switchInt(move _3) -> [0: bb5, 1: bb6, otherwise: bb1]; }
+//!
+//! The outer otherwise target has to be the one of the inner switches, or unreachable. The second
+//! place is only read early if it can't depend on the first discriminant, so it may not contain a
+//! dereference or a downcast. The inner switching blocks become unreachable and are removed by a
+//! later `SimplifyCfg`.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_index::bit_set::BitSet;
+use rustc_middle::mir::*;
+use rustc_middle::ty::{Ty, TyCtxt};
+
+pub struct EarlyOtherwiseBranch;
+
+impl<'tcx> MirPass<'tcx> for EarlyOtherwiseBranch {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let opts = find_opts(body);
+        if opts.is_empty() {
+            return PassOutcome::Unchanged;
+        }
+
+        for opt in opts {
+            debug!("merging the switches on {:?} into {:?}", opt.second_place, opt.parent);
+            let parent = &body[opt.parent];
+            let source_info = parent.terminator().source_info;
+            let is_cleanup = parent.is_cleanup;
+            let (values, otherwise) = match parent.terminator().kind {
+                TerminatorKind::SwitchInt { ref values, ref targets, .. } => {
+                    (values.clone(), *targets.last().unwrap())
+                }
+                _ => bug!("`EarlyOtherwiseBranch` opt does not end in a switch"),
+            };
+            let mut targets = opt.inner_targets;
+            targets.push(otherwise);
+            let both_equal = body.basic_blocks_mut().push(BasicBlockData {
+                statements: vec![],
+                terminator: Some(Terminator {
+                    source_info,
+                    kind: TerminatorKind::SwitchInt {
+                        discr: Operand::Move(Place::from(opt.first_discr)),
+                        switch_ty: opt.switch_ty,
+                        values,
+                        targets,
+                    },
+                }),
+                is_cleanup,
+            });
+
+            let second_discr = body
+                .local_decls
+                .push(LocalDecl::with_source_info(opt.discr_ty, source_info).internal());
+            let not_equal = body
+                .local_decls
+                .push(LocalDecl::with_source_info(tcx.types.bool, source_info).internal());
+            let parent = &mut body.basic_blocks_mut()[opt.parent];
+            parent.statements.push(Statement {
+                source_info,
+                kind: StatementKind::Assign(box (
+                    Place::from(second_discr),
+                    Rvalue::Discriminant(opt.second_place),
+                )),
+            });
+            parent.statements.push(Statement {
+                source_info,
+                kind: StatementKind::Assign(box (
+                    Place::from(not_equal),
+                    Rvalue::BinaryOp(
+                        BinOp::Ne,
+                        Operand::Copy(Place::from(opt.first_discr)),
+                        Operand::Move(Place::from(second_discr)),
+                    ),
+                )),
+            });
+            parent.terminator_mut().kind = TerminatorKind::if_(
+                tcx,
+                Operand::Move(Place::from(not_equal)),
+                opt.inner_otherwise,
+                both_equal,
+            );
+        }
+        PassOutcome::Changed
+    }
+}
+
+/// A switch on `first_discr` at the end of `parent` whose targets all switch on the discriminant
+/// of `second_place`, going to `inner_targets` if it is equal to the first one, and to
+/// `inner_otherwise` if not.
+struct OptimizationInfo<'tcx> {
+    parent: BasicBlock,
+    first_discr: Local,
+    discr_ty: Ty<'tcx>,
+    switch_ty: Ty<'tcx>,
+    second_place: Place<'tcx>,
+    inner_targets: Vec<BasicBlock>,
+    inner_otherwise: BasicBlock,
+}
+
+fn find_opts<'tcx>(body: &Body<'tcx>) -> Vec<OptimizationInfo<'tcx>> {
+    let predecessors = body.predecessors();
+    // Blocks that an earlier opt changes or relies on, which later opts must leave alone.
+    let mut touched = BitSet::new_empty(body.basic_blocks().len());
+    let mut opts = Vec::new();
+    for (bb, data) in body.basic_blocks().iter_enumerated() {
+        let (first_discr, switch_ty, values, targets) = match discriminant_switch(data) {
+            Some((local, _, switch_ty, values, targets)) => (local, switch_ty, values, targets),
+            None => continue,
+        };
+        if values.is_empty() {
+            continue;
+        }
+        let discr_ty = body.local_decls[first_discr].ty;
+
+        let mut second_place = None;
+        let mut inner_otherwise = None;
+        let mut inner_targets = Vec::with_capacity(values.len());
+        for (&value, &target) in values.iter().zip(targets) {
+            let child = &body[target];
+            if target == bb
+                || predecessors[target].len() != 1
+                || child.statements.len() != 1
+                || child.is_cleanup != data.is_cleanup
+            {
+                break;
+            }
+            let (local, place, child_switch_ty, child_values, child_targets) =
+                match discriminant_switch(child) {
+                    Some(switch) => switch,
+                    None => break,
+                };
+            if body.local_decls[local].ty != discr_ty
+                || child_switch_ty != switch_ty
+                || child_values != [value]
+                || *second_place.get_or_insert(place) != place
+                || *inner_otherwise.get_or_insert(child_targets[1]) != child_targets[1]
+            {
+                break;
+            }
+            inner_targets.push(child_targets[0]);
+        }
+        if inner_targets.len() != values.len() {
+            continue;
+        }
+        let (second_place, inner_otherwise) = (second_place.unwrap(), inner_otherwise.unwrap());
+
+        let otherwise = *targets.last().unwrap();
+        let otherwise_unreachable = body[otherwise].statements.is_empty()
+            && body[otherwise].terminator().kind == TerminatorKind::Unreachable;
+        if otherwise != inner_otherwise && !otherwise_unreachable {
+            continue;
+        }
+        // Reading the second place must not depend on the value of the first discriminant.
+        if second_place
+            .projection
+            .iter()
+            .any(|elem| matches!(elem, ProjectionElem::Deref | ProjectionElem::Downcast(..)))
+        {
+            continue;
+        }
+
+        let blocks = || std::iter::once(bb).chain(values.iter().zip(targets).map(|(_, &t)| t));
+        if blocks().any(|block| touched.contains(block)) {
+            continue;
+        }
+        for block in blocks() {
+            touched.insert(block);
+        }
+        opts.push(OptimizationInfo {
+            parent: bb,
+            first_discr,
+            discr_ty,
+            switch_ty,
+            second_place,
+            inner_targets,
+            inner_otherwise,
+        });
+    }
+    opts
+}
+
+/// Matches a block whose last statement reads the discriminant of a place into a local that the
+/// terminator then switches on.
+fn discriminant_switch<'a, 'tcx>(
+    data: &'a BasicBlockData<'tcx>,
+) -> Option<(Local, Place<'tcx>, Ty<'tcx>, &'a [u128], &'a [BasicBlock])> {
+    let (discr, switch_ty, values, targets) = match data.terminator().kind {
+        TerminatorKind::SwitchInt {
+            discr: Operand::Move(discr),
+            switch_ty,
+            ref values,
+            ref targets,
+        } => (discr.as_local()?, switch_ty, &values[..], &targets[..]),
+        _ => return None,
+    };
+    match data.statements.last()?.kind {
+        StatementKind::Assign(box (place, Rvalue::Discriminant(discr_place)))
+            if place.as_local() == Some(discr) =>
+        {
+            Some((discr, discr_place, switch_ty, values, targets))
+        }
+        _ => None,
+    }
+}
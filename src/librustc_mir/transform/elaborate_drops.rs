@@ -5,23 +5,41 @@ use crate::dataflow::on_lookup_result_bits;
 use crate::dataflow::MoveDataParamEnv;
 use crate::dataflow::{on_all_children_bits, on_all_drop_children_bits};
 use crate::dataflow::{Analysis, ResultsCursor};
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 use crate::util::elaborate_drops::{elaborate_drop, DropFlagState, Unwind};
 use crate::util::elaborate_drops::{DropElaborator, DropFlagMode, DropStyle};
 use crate::util::patch::MirPatch;
 use rustc_data_structures::fx::FxHashMap;
 use rustc_hir as hir;
 use rustc_index::bit_set::BitSet;
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::visit::{PlaceContext, Visitor};
 use rustc_middle::mir::*;
 use rustc_middle::ty::{self, TyCtxt};
 use rustc_span::Span;
 use rustc_target::abi::VariantIdx;
 use std::fmt;
+use std::ops::RangeInclusive;
 
 pub struct ElaborateDrops;
 
 impl<'tcx> MirPass<'tcx> for ElaborateDrops {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, src: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    /// The drops of a call's destination are elaborated on the call's outgoing edges.
+    fn requires(&self) -> &[&str] {
+        &["AddCallGuards"]
+    }
+
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        // Borrowck needs the unelaborated drops, and everything after it the elaborated ones.
+        MirPhase::DropElab..=MirPhase::DropElab
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        src: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
         debug!("elaborate_drops({:?} @ {:?})", src, body.span);
 
         let def_id = src.def_id();
@@ -36,7 +54,7 @@ impl<'tcx> MirPass<'tcx> for ElaborateDrops {
                 move_data
             }
         };
-        let elaborate_patch = {
+        let (elaborate_patch, drop_flags) = {
             let body = &*body;
             let env = MoveDataParamEnv { move_data, param_env };
             let dead_unwinds = find_dead_unwinds(tcx, body, def_id, &env);
@@ -65,6 +83,10 @@ impl<'tcx> MirPass<'tcx> for ElaborateDrops {
             .elaborate()
         };
         elaborate_patch.apply(body);
+        if drop_flags.len() > MAX_UNPACKED_DROP_FLAGS {
+            pack_drop_flags(tcx, body, drop_flags);
+        }
+        PassOutcome::Changed
     }
 }
 
@@ -278,8 +300,8 @@ impl<'b, 'tcx> ElaborateDropsCtxt<'b, 'tcx> {
     }
 
     /// create a patch that elaborates all drops in the input
-    /// MIR.
-    fn elaborate(mut self) -> MirPatch<'tcx> {
+    /// MIR, and return it along with the drop flags it uses.
+    fn elaborate(mut self) -> (MirPatch<'tcx>, Vec<Local>) {
         self.collect_drop_flags();
 
         self.elaborate_drops();
@@ -289,7 +311,9 @@ impl<'b, 'tcx> ElaborateDropsCtxt<'b, 'tcx> {
         self.drop_flags_for_args();
         self.drop_flags_for_locs();
 
-        self.patch
+        let mut drop_flags: Vec<Local> = self.drop_flags.values().copied().collect();
+        drop_flags.sort();
+        (self.patch, drop_flags)
     }
 
     fn collect_drop_flags(&mut self) {
@@ -586,3 +610,154 @@ impl<'b, 'tcx> ElaborateDropsCtxt<'b, 'tcx> {
         }
     }
 }
+
+/// Functions with more drop flags than this keep them in the bits of `u64` locals rather than in
+/// a `bool` local each. Packed flags are out of reach of `RemoveDeadDropFlags`, so functions with
+/// only a few of them keep them apart.
+const MAX_UNPACKED_DROP_FLAGS: usize = 8;
+
+/// Moves the drop `flags` into the bits of `u64` locals, 64 to a local. Storing a flag sets or
+/// clears its bit:
+///
+///     _5 = const true;      becomes      _12 = BitOr(_12, const 4_u64);
+///
+/// and a switch on a flag switches on its bit instead:
+///
+///     _13 = BitAnd(_12, const 4_u64);
+///     switchInt(move _13) -> [0_u64: bb4, otherwise: bb3];
+///
+/// Flags that are used in any other way stay where they are.
+fn pack_drop_flags<'tcx>(tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>, mut flags: Vec<Local>) {
+    let mut uses = FlagUses {
+        flags: BitSet::new_empty(body.local_decls.len()),
+        other_uses: BitSet::new_empty(body.local_decls.len()),
+    };
+    for &flag in &flags {
+        uses.flags.insert(flag);
+    }
+    uses.visit_body(body);
+    flags.retain(|&flag| !uses.other_uses.contains(flag));
+    if flags.len() <= MAX_UNPACKED_DROP_FLAGS {
+        return;
+    }
+    debug!("pack_drop_flags({:?}): packing {:?}", body.span, flags);
+
+    let span = body.span;
+    let mut bits: IndexVec<Local, Option<(Local, u64)>> =
+        IndexVec::from_elem_n(None, body.local_decls.len());
+    let mut words = Vec::new();
+    for chunk in flags.chunks(64) {
+        let word = body.local_decls.push(LocalDecl::new(tcx.types.u64, span).internal());
+        words.push(word);
+        for (bit, &flag) in chunk.iter().enumerate() {
+            bits[flag] = Some((word, 1 << bit));
+        }
+    }
+    let masked = body.local_decls.push(LocalDecl::new(tcx.types.u64, span).internal());
+    let bit_of =
+        |place: Place<'tcx>| place.as_local().and_then(|local| bits.get(local).copied().flatten());
+    let constant = |span, value: u64| {
+        Operand::Constant(Box::new(Constant {
+            span,
+            user_ty: None,
+            literal: ty::Const::from_bits(
+                tcx,
+                value.into(),
+                ty::ParamEnv::empty().and(tcx.types.u64),
+            ),
+        }))
+    };
+
+    for data in body.basic_blocks_mut() {
+        for statement in &mut data.statements {
+            let (word, mask, value) = match statement.kind {
+                StatementKind::Assign(box (place, Rvalue::Use(Operand::Constant(ref value)))) => {
+                    match (bit_of(place), value.literal.try_eval_bool(tcx, ty::ParamEnv::empty())) {
+                        (Some((word, mask)), Some(value)) => (word, mask, value),
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
+            let span = statement.source_info.span;
+            let rvalue = if value {
+                Rvalue::BinaryOp(BinOp::BitOr, Operand::Copy(word.into()), constant(span, mask))
+            } else {
+                Rvalue::BinaryOp(BinOp::BitAnd, Operand::Copy(word.into()), constant(span, !mask))
+            };
+            statement.kind = StatementKind::Assign(box (word.into(), rvalue));
+        }
+
+        let terminator = data.terminator.as_mut().unwrap();
+        let source_info = terminator.source_info;
+        if let TerminatorKind::SwitchInt { ref mut discr, ref mut switch_ty, .. } = terminator.kind
+        {
+            let (word, mask) = match discr.place().and_then(bit_of) {
+                Some(bit) => bit,
+                None => continue,
+            };
+            *discr = Operand::Move(masked.into());
+            *switch_ty = tcx.types.u64;
+            data.statements.push(Statement {
+                source_info,
+                kind: StatementKind::Assign(box (
+                    masked.into(),
+                    Rvalue::BinaryOp(
+                        BinOp::BitAnd,
+                        Operand::Copy(word.into()),
+                        constant(source_info.span, mask),
+                    ),
+                )),
+            });
+        }
+    }
+
+    // The words start out clear, which is what storing `false` in every flag on entry did.
+    let source_info = SourceInfo::outermost(span);
+    let start = &mut body.basic_blocks_mut()[START_BLOCK].statements;
+    for word in words {
+        let clear = StatementKind::Assign(box (word.into(), Rvalue::Use(constant(span, 0))));
+        start.insert(0, Statement { source_info, kind: clear });
+    }
+}
+
+/// Finds the `flags` that are used other than by storing a constant in them, or by a switch on
+/// them whose only value is `false`.
+struct FlagUses {
+    flags: BitSet<Local>,
+    other_uses: BitSet<Local>,
+}
+
+impl<'tcx> Visitor<'tcx> for FlagUses {
+    fn visit_statement(&mut self, statement: &Statement<'tcx>, location: Location) {
+        if let StatementKind::Assign(box (place, Rvalue::Use(Operand::Constant(_)))) =
+            statement.kind
+        {
+            if place.as_local().map_or(false, |local| self.flags.contains(local)) {
+                return;
+            }
+        }
+        self.super_statement(statement, location);
+    }
+
+    fn visit_terminator(&mut self, terminator: &Terminator<'tcx>, location: Location) {
+        if let TerminatorKind::SwitchInt {
+            discr: Operand::Copy(place) | Operand::Move(place),
+            ref values,
+            ..
+        } = terminator.kind
+        {
+            let is_flag = place.as_local().map_or(false, |local| self.flags.contains(local));
+            if is_flag && values[..] == [0] {
+                return;
+            }
+        }
+        self.super_terminator(terminator, location);
+    }
+
+    fn visit_local(&mut self, &local: &Local, _: PlaceContext, _: Location) {
+        if self.flags.contains(local) {
+            self.other_uses.insert(local);
+        }
+    }
+}
@@ -55,21 +55,24 @@ use crate::dataflow::impls::{
 use crate::dataflow::{self, Analysis};
 use crate::transform::no_landing_pads::no_landing_pads;
 use crate::transform::simplify;
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 use crate::util::dump_mir;
 use crate::util::storage;
+use rustc_ast::ast::InlineAsmOptions;
 use rustc_data_structures::fx::FxHashMap;
 use rustc_hir as hir;
 use rustc_hir::def_id::DefId;
 use rustc_hir::lang_items::{GeneratorStateLangItem, PinTypeLangItem};
 use rustc_index::bit_set::{BitMatrix, BitSet};
 use rustc_index::vec::{Idx, IndexVec};
+use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
 use rustc_middle::mir::visit::{MutVisitor, PlaceContext, Visitor};
 use rustc_middle::mir::*;
 use rustc_middle::ty::subst::SubstsRef;
 use rustc_middle::ty::GeneratorSubsts;
 use rustc_middle::ty::{self, AdtDef, Ty, TyCtxt};
 use rustc_target::abi::VariantIdx;
+use rustc_target::spec::abi::Abi;
 use rustc_target::spec::PanicStrategy;
 use std::borrow::Cow;
 use std::{iter, ops};
@@ -618,15 +621,8 @@ fn compute_storage_conflicts(
     ineligible_locals.intersect(saved_locals);
 
     // Compute the storage conflicts for all eligible locals.
-    let mut visitor = StorageConflictVisitor {
-        body,
-        saved_locals: &saved_locals,
-        local_conflicts: BitMatrix::from_row_n(&ineligible_locals, body.local_decls.len()),
-    };
-
-    requires_storage.visit_reachable_with(body, &mut visitor);
-
-    let local_conflicts = visitor.local_conflicts;
+    let local_conflicts =
+        local_storage_conflicts(body, saved_locals, &ineligible_locals, requires_storage);
 
     // Compress the matrix using only stored locals (Local -> GeneratorSavedLocal).
     //
@@ -652,9 +648,29 @@ fn compute_storage_conflicts(
     storage_conflicts
 }
 
+/// For every local in `candidates`, computes the other candidates that may be StorageLive at
+/// the same time. Locals in `ineligible_locals` conflict with everything. This is shared with
+/// the `StackSlotHints` pass, which looks for locals that can share a stack slot.
+crate fn local_storage_conflicts(
+    body: &'mir Body<'tcx>,
+    candidates: &BitSet<Local>,
+    ineligible_locals: &BitSet<Local>,
+    requires_storage: dataflow::Results<'tcx, MaybeRequiresStorage<'mir, 'tcx>>,
+) -> BitMatrix<Local, Local> {
+    let mut visitor = StorageConflictVisitor {
+        body,
+        candidates,
+        local_conflicts: BitMatrix::from_row_n(ineligible_locals, body.local_decls.len()),
+    };
+
+    requires_storage.visit_reachable_with(body, &mut visitor);
+
+    visitor.local_conflicts
+}
+
 struct StorageConflictVisitor<'mir, 'tcx, 's> {
     body: &'mir Body<'tcx>,
-    saved_locals: &'s GeneratorSavedLocals,
+    candidates: &'s BitSet<Local>,
     // FIXME(tmandry): Consider using sparse bitsets here once we have good
     // benchmarks for generators.
     local_conflicts: BitMatrix<Local, Local>,
@@ -690,7 +706,7 @@ impl<'body, 'tcx, 's> StorageConflictVisitor<'body, 'tcx, 's> {
         }
 
         let mut eligible_storage_live = flow_state.clone();
-        eligible_storage_live.intersect(&self.saved_locals);
+        eligible_storage_live.intersect(self.candidates);
 
         for local in eligible_storage_live.iter() {
             self.local_conflicts.union_row_with(&eligible_storage_live, local);
@@ -1028,8 +1044,14 @@ fn can_unwind<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> bool {
             | TerminatorKind::Unreachable
             | TerminatorKind::GeneratorDrop
             | TerminatorKind::FalseEdge { .. }
-            | TerminatorKind::FalseUnwind { .. }
-            | TerminatorKind::InlineAsm { .. } => {}
+            | TerminatorKind::FalseUnwind { .. } => {}
+
+            // Inline assembly only unwinds if it says so.
+            TerminatorKind::InlineAsm { options, .. } => {
+                if options.contains(InlineAsmOptions::MAY_UNWIND) {
+                    return true;
+                }
+            }
 
             // Resume will *continue* unwinding, but if there's no other unwinding terminator it
             // will never be reached.
@@ -1039,10 +1061,16 @@ fn can_unwind<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> bool {
                 unreachable!("`can_unwind` called before generator transform")
             }
 
+            // Calls only unwind if the callee can.
+            TerminatorKind::Call { ref func, .. } => {
+                if callee_can_unwind(tcx, body, func) {
+                    return true;
+                }
+            }
+
             // These may unwind.
             TerminatorKind::Drop { .. }
             | TerminatorKind::DropAndReplace { .. }
-            | TerminatorKind::Call { .. }
             | TerminatorKind::Assert { .. } => return true,
         }
     }
@@ -1051,6 +1079,29 @@ fn can_unwind<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> bool {
     false
 }
 
+/// Whether a call to `func` may unwind, following the same rules as the `nounwind` attribute
+/// codegen puts on the call.
+fn callee_can_unwind<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>, func: &Operand<'tcx>) -> bool {
+    let fn_ty = func.ty(body, tcx);
+    let flags = match fn_ty.kind {
+        ty::FnDef(def_id, _) => tcx.codegen_fn_attrs(def_id).flags,
+        ty::FnPtr(_) => CodegenFnAttrFlags::empty(),
+        _ => return true,
+    };
+
+    if flags.contains(CodegenFnAttrFlags::UNWIND) {
+        true
+    } else if flags.contains(CodegenFnAttrFlags::RUSTC_ALLOCATOR_NOUNWIND) {
+        false
+    } else {
+        // Functions with a non-Rust ABI are assumed not to unwind.
+        match fn_ty.fn_sig(tcx).abi() {
+            Abi::Rust | Abi::RustCall | Abi::RustIntrinsic | Abi::PlatformIntrinsic => true,
+            _ => false,
+        }
+    }
+}
+
 fn create_generator_resume_function<'tcx>(
     tcx: TyCtxt<'tcx>,
     transform: TransformVisitor<'tcx>,
@@ -1223,12 +1274,22 @@ fn create_cases<'tcx>(
 }
 
 impl<'tcx> MirPass<'tcx> for StateTransform {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    fn phases(&self) -> ops::RangeInclusive<MirPhase> {
+        // The lowering needs elaborated drops.
+        MirPhase::DropElab..=MirPhase::Optimized
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
         let yield_ty = if let Some(yield_ty) = body.yield_ty {
             yield_ty
         } else {
             // This only applies to generators
-            return;
+            return PassOutcome::Unchanged;
         };
 
         assert!(body.generator_drop.is_none());
@@ -1252,7 +1313,7 @@ impl<'tcx> MirPass<'tcx> for StateTransform {
             _ => {
                 tcx.sess
                     .delay_span_bug(body.span, &format!("unexpected generator type {}", gen_ty));
-                return;
+                return PassOutcome::Unchanged;
             }
         };
 
@@ -1357,6 +1418,7 @@ impl<'tcx> MirPass<'tcx> for StateTransform {
 
         // Create the Generator::resume function
         create_generator_resume_function(tcx, transform, source, body, can_return);
+        PassOutcome::Changed
     }
 }
 
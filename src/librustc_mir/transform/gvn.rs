@@ -0,0 +1,290 @@
+//! Global value numbering.
+//!
+//! Finds assignments of values that a dominating assignment already computed, and replaces them
+//! with a copy of the earlier result. To give an example:
+//!
+//!     _3 = Len((*_1))
+//!     ...
+//!     _5 = Len((*_1))
+//!
+//! becomes:
+//!
+//!     _3 = Len((*_1))
+//!     ...
+//!     _5 = _3
+//!
+//! which copy propagation and `SimplifyLocals` can clean up further.
+//!
+//! Only values that can't change between the two assignments are numbered. Their operands have to
+//! be constants, or locals that are assigned at most once and never borrowed ("SSA locals"), read
+//! through field, downcast and constant index projections. The rvalue itself can't have side
+//! effects. The earlier result has to be an SSA local as well, and loses its storage markers,
+//! since it is now used outside of them. Only `Copy` values are reused, so that nothing which
+//! needs dropping is duplicated.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_data_structures::fx::FxHashMap;
+use rustc_index::bit_set::BitSet;
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::visit::{MutatingUseContext, NonMutatingUseContext, PlaceContext, Visitor};
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, List, Ty, TyCtxt};
+
+pub struct GlobalValueNumbering;
+
+impl<'tcx> MirPass<'tcx> for GlobalValueNumbering {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let param_env = tcx.param_env(source.def_id());
+        let ssa = SsaLocals::new(body);
+
+        let mut numbering = ValueNumbering {
+            ssa: &ssa,
+            representatives: IndexVec::from_fn_n(|local| local, body.local_decls.len()),
+            values: FxHashMap::default(),
+        };
+        let mut replacements = Vec::new();
+        let dominators = body.dominators();
+        for &bb in body.postorder().iter().rev() {
+            for (statement_index, statement) in body[bb].statements.iter().enumerate() {
+                let location = Location { block: bb, statement_index };
+                let (dest, rvalue) = match statement.kind {
+                    StatementKind::Assign(box (ref dest, ref rvalue)) => match dest.as_local() {
+                        Some(dest) => (dest, rvalue),
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+
+                if let Rvalue::Use(Operand::Copy(place) | Operand::Move(place)) = *rvalue {
+                    if let Some(local) = place.as_local() {
+                        // Plain copies are left to copy propagation, but the copy has the same
+                        // value as its source.
+                        if ssa.is_ssa(dest) && ssa.is_ssa(local) {
+                            numbering.representatives[dest] = numbering.representatives[local];
+                        }
+                        continue;
+                    }
+                }
+
+                let value = match numbering.value_of(rvalue) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                let ty = body.local_decls[dest].ty;
+                if !ty.is_copy_modulo_regions(tcx.at(statement.source_info.span), param_env) {
+                    continue;
+                }
+
+                let computed = numbering.values.entry(value).or_default();
+                match computed.iter().find(|&&(_, def)| def.dominates(location, dominators)) {
+                    Some(&(earlier, _)) => {
+                        debug!("replacing {:?} at {:?} by {:?}", rvalue, location, earlier);
+                        replacements.push((location, earlier));
+                        if ssa.is_ssa(dest) {
+                            numbering.representatives[dest] = numbering.representatives[earlier];
+                        }
+                    }
+                    None if ssa.is_ssa(dest) => computed.push((dest, location)),
+                    None => {}
+                }
+            }
+        }
+
+        if replacements.is_empty() {
+            return PassOutcome::Unchanged;
+        }
+
+        let mut reused = BitSet::new_empty(body.local_decls.len());
+        for (location, earlier) in replacements {
+            let statement = &mut body[location.block].statements[location.statement_index];
+            if let StatementKind::Assign(box (_, ref mut rvalue)) = statement.kind {
+                *rvalue = Rvalue::Use(Operand::Copy(Place::from(earlier)));
+            }
+            reused.insert(earlier);
+        }
+        // The reused locals are live wherever they are used now, which their storage markers
+        // don't account for.
+        for data in body.basic_blocks_mut() {
+            for statement in &mut data.statements {
+                if let StatementKind::StorageLive(local) | StatementKind::StorageDead(local) =
+                    statement.kind
+                {
+                    if reused.contains(local) {
+                        statement.make_nop();
+                    }
+                }
+            }
+        }
+        PassOutcome::Changed
+    }
+}
+
+/// A value that an rvalue computes, in terms of the representatives of the locals it reads.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+enum Value<'tcx> {
+    Use(ValuePlace<'tcx>),
+    Len(ValuePlace<'tcx>),
+    Discriminant(ValuePlace<'tcx>),
+    Cast(ValueOperand<'tcx>, Ty<'tcx>),
+    BinaryOp(BinOp, ValueOperand<'tcx>, ValueOperand<'tcx>),
+    CheckedBinaryOp(BinOp, ValueOperand<'tcx>, ValueOperand<'tcx>),
+    UnaryOp(UnOp, ValueOperand<'tcx>),
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+struct ValuePlace<'tcx> {
+    local: Local,
+    projection: &'tcx List<PlaceElem<'tcx>>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+enum ValueOperand<'tcx> {
+    Place(ValuePlace<'tcx>),
+    Constant(&'tcx ty::Const<'tcx>),
+}
+
+struct ValueNumbering<'a, 'tcx> {
+    ssa: &'a SsaLocals,
+    /// The SSA local each local is known to have the same value as.
+    representatives: IndexVec<Local, Local>,
+    /// The SSA locals that hold each value, along with where they were assigned.
+    values: FxHashMap<Value<'tcx>, Vec<(Local, Location)>>,
+}
+
+impl<'tcx> ValueNumbering<'_, 'tcx> {
+    fn value_of(&self, rvalue: &Rvalue<'tcx>) -> Option<Value<'tcx>> {
+        Some(match *rvalue {
+            Rvalue::Use(Operand::Copy(place) | Operand::Move(place)) => {
+                Value::Use(self.place(place)?)
+            }
+            // The length of a slice is part of the reference, so it stays the same even if the
+            // slice itself is written to.
+            Rvalue::Len(place) => match place.as_ref() {
+                PlaceRef { local, projection: &[ProjectionElem::Deref] }
+                    if self.ssa.is_ssa(local) =>
+                {
+                    Value::Len(ValuePlace {
+                        local: self.representatives[local],
+                        projection: place.projection,
+                    })
+                }
+                _ => Value::Len(self.place(place)?),
+            },
+            Rvalue::Discriminant(place) => Value::Discriminant(self.place(place)?),
+            Rvalue::Cast(CastKind::Misc, ref operand, ty) => {
+                Value::Cast(self.operand(operand)?, ty)
+            }
+            Rvalue::BinaryOp(op, ref lhs, ref rhs) => {
+                Value::BinaryOp(op, self.operand(lhs)?, self.operand(rhs)?)
+            }
+            Rvalue::CheckedBinaryOp(op, ref lhs, ref rhs) => {
+                Value::CheckedBinaryOp(op, self.operand(lhs)?, self.operand(rhs)?)
+            }
+            Rvalue::UnaryOp(op, ref operand) => Value::UnaryOp(op, self.operand(operand)?),
+            _ => return None,
+        })
+    }
+
+    fn operand(&self, operand: &Operand<'tcx>) -> Option<ValueOperand<'tcx>> {
+        match *operand {
+            Operand::Copy(place) | Operand::Move(place) => {
+                self.place(place).map(ValueOperand::Place)
+            }
+            Operand::Constant(ref constant) => Some(ValueOperand::Constant(constant.literal)),
+        }
+    }
+
+    /// Numbers `place` if it can't change while its local is live.
+    fn place(&self, place: Place<'tcx>) -> Option<ValuePlace<'tcx>> {
+        if !self.ssa.is_ssa(place.local) {
+            return None;
+        }
+        let stable = place.projection.iter().all(|elem| match elem {
+            ProjectionElem::Field(..)
+            | ProjectionElem::Downcast(..)
+            | ProjectionElem::ConstantIndex { .. } => true,
+            ProjectionElem::Deref | ProjectionElem::Index(_) | ProjectionElem::Subslice { .. } => {
+                false
+            }
+        });
+        if !stable {
+            return None;
+        }
+        Some(ValuePlace { local: self.representatives[place.local], projection: place.projection })
+    }
+}
+
+/// The locals that are assigned at most once and never borrowed, so that they hold a single
+/// value wherever their assignment dominates.
+struct SsaLocals {
+    ssa: BitSet<Local>,
+}
+
+impl SsaLocals {
+    fn new(body: &Body<'_>) -> Self {
+        let mut finder = SsaFinder {
+            assignments: IndexVec::from_elem(0, &body.local_decls),
+            disqualified: BitSet::new_empty(body.local_decls.len()),
+        };
+        // Arguments are assigned on entry.
+        for arg in body.args_iter() {
+            finder.assignments[arg] = 1;
+        }
+        finder.visit_body(body);
+
+        let mut ssa = BitSet::new_empty(body.local_decls.len());
+        for (local, &assignments) in finder.assignments.iter_enumerated() {
+            if assignments <= 1 && !finder.disqualified.contains(local) {
+                ssa.insert(local);
+            }
+        }
+        SsaLocals { ssa }
+    }
+
+    fn is_ssa(&self, local: Local) -> bool {
+        self.ssa.contains(local)
+    }
+}
+
+struct SsaFinder {
+    assignments: IndexVec<Local, usize>,
+    disqualified: BitSet<Local>,
+}
+
+impl<'tcx> Visitor<'tcx> for SsaFinder {
+    fn visit_place(&mut self, place: &Place<'tcx>, context: PlaceContext, _: Location) {
+        // Writing or borrowing through a reference doesn't affect the reference itself.
+        if place.is_indirect() {
+            return;
+        }
+        match context {
+            PlaceContext::MutatingUse(
+                MutatingUseContext::Store
+                | MutatingUseContext::Call
+                | MutatingUseContext::AsmOutput
+                | MutatingUseContext::Yield,
+            ) if place.projection.is_empty() => self.assignments[place.local] += 1,
+            // Drops end the life of the value rather than changing it.
+            PlaceContext::MutatingUse(MutatingUseContext::Drop) => {}
+            PlaceContext::MutatingUse(_)
+            | PlaceContext::NonMutatingUse(
+                NonMutatingUseContext::SharedBorrow
+                | NonMutatingUseContext::ShallowBorrow
+                | NonMutatingUseContext::UniqueBorrow
+                | NonMutatingUseContext::AddressOf,
+            ) => {
+                self.disqualified.insert(place.local);
+            }
+            PlaceContext::NonMutatingUse(_) | PlaceContext::NonUse(_) => {}
+        }
+    }
+}
@@ -1,23 +1,36 @@
 //! Inlining pass for MIR functions
 
+use rustc_ast::ast::InlineAsmOptions;
 use rustc_attr as attr;
-use rustc_hir::def_id::DefId;
+use rustc_hir::def_id::{DefId, LocalDefId, LOCAL_CRATE};
 use rustc_index::bit_set::BitSet;
 use rustc_index::vec::{Idx, IndexVec};
 use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
+use rustc_middle::mir::interpret::{ConstValue, Scalar};
 use rustc_middle::mir::visit::*;
 use rustc_middle::mir::*;
+use rustc_middle::ty::adjustment::PointerCast;
+use rustc_middle::ty::query::Providers;
 use rustc_middle::ty::subst::{Subst, SubstsRef};
 use rustc_middle::ty::{self, ConstKind, Instance, InstanceDef, ParamEnv, Ty, TyCtxt};
+use rustc_session::Session;
+use rustc_span::symbol::sym;
 use rustc_target::spec::abi::Abi;
 
 use super::simplify::{remove_dead_blocks, CfgSimplifier};
-use crate::transform::{MirPass, MirSource};
+use super::validate::Validator;
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::remark::emit_remark;
 use std::collections::VecDeque;
 use std::iter;
+use std::ops::RangeInclusive;
 
+// The defaults of `-Z inline-mir-threshold` and `-Z inline-mir-hint-threshold`.
 const DEFAULT_THRESHOLD: usize = 50;
 const HINT_THRESHOLD: usize = 100;
+// Used instead of the above at `-Z mir-opt-level=3`.
+const AGGRESSIVE_DEFAULT_THRESHOLD: usize = 100;
+const AGGRESSIVE_HINT_THRESHOLD: usize = 200;
 
 const INSTR_COST: usize = 5;
 const CALL_PENALTY: usize = 25;
@@ -26,6 +39,33 @@ const RESUME_PENALTY: usize = 45;
 
 const UNKNOWN_SIZE_COST: usize = 10;
 
+// The most blocks that a callee may have to be inlined below `-Z mir-opt-level=2`.
+const TRIVIAL_BODY_BLOCKS: usize = 8;
+
+// The most statements and terminators that a function which isn't generic or `#[inline]` may have
+// for its MIR to be exported to other crates.
+const CROSS_CRATE_MAX_SIZE: usize = 10;
+
+pub(crate) fn provide(providers: &mut Providers) {
+    providers.cross_crate_inlinable = cross_crate_inlinable;
+}
+
+fn cross_crate_inlinable(tcx: TyCtxt<'_>, def_id: LocalDefId) -> bool {
+    // Code from these crates has to stay in them, so that the backend doesn't turn it into calls
+    // of itself.
+    if tcx.is_compiler_builtins(LOCAL_CRATE) || tcx.is_no_builtins(LOCAL_CRATE) {
+        return false;
+    }
+    let def_id = def_id.to_def_id();
+    let codegen_fn_attrs = tcx.codegen_fn_attrs(def_id);
+    if codegen_fn_attrs.inline == attr::InlineAttr::Never
+        || codegen_fn_attrs.flags.contains(CodegenFnAttrFlags::TRACK_CALLER)
+    {
+        return false;
+    }
+    is_self_contained_and_small(tcx, tcx.optimized_mir(def_id))
+}
+
 pub struct Inline;
 
 #[derive(Copy, Clone, Debug)]
@@ -37,17 +77,30 @@ struct CallSite<'tcx> {
 }
 
 impl<'tcx> MirPass<'tcx> for Inline {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>) {
-        if tcx.sess.opts.debugging_opts.mir_opt_level >= 2 {
-            if tcx.sess.opts.debugging_opts.instrument_coverage {
-                // The current implementation of source code coverage injects code region counters
-                // into the MIR, and assumes a 1-to-1 correspondence between MIR and source-code-
-                // based function.
-                debug!("function inlining is disabled when compiling with `instrument_coverage`");
-            } else {
-                Inliner { tcx, source }.run_pass(body);
-            }
-        }
+    fn is_enabled(&self, sess: &Session) -> bool {
+        // The current implementation of source code coverage injects code region counters
+        // into the MIR, and assumes a 1-to-1 correspondence between MIR and source-code-
+        // based function.
+        // Below `-Z mir-opt-level=2`, only the restricted mode runs, see `Inliner::restricted`.
+        !sess.opts.debugging_opts.instrument_coverage
+    }
+
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        // Callees are inlined in the form they have at the end of `MirPhase::Inlined`.
+        MirPhase::Inlined..=MirPhase::Optimized
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        Inliner { tcx, source }.run_pass(body)
     }
 }
 
@@ -57,7 +110,7 @@ struct Inliner<'tcx> {
 }
 
 impl Inliner<'tcx> {
-    fn run_pass(&self, caller_body: &mut Body<'tcx>) {
+    fn run_pass(&self, caller_body: &mut Body<'tcx>) -> PassOutcome {
         // Keep a queue of callsites to try inlining on. We take
         // advantage of the fact that queries detect cycles here to
         // allow us to try and fetch the fully optimized MIR of a
@@ -85,7 +138,7 @@ impl Inliner<'tcx> {
                 }
             }
         } else {
-            return;
+            return PassOutcome::Unchanged;
         }
 
         let mut local_change;
@@ -173,7 +226,27 @@ impl Inliner<'tcx> {
             debug!("running simplify cfg on {:?}", self.source);
             CfgSimplifier::new(caller_body).simplify();
             remove_dead_blocks(caller_body);
+
+            // The relaxed mode splices bodies in places the default mode stays away from, so
+            // check that the result is still well-formed right away.
+            if self.relaxed() {
+                let when = "after relaxed inlining".to_owned();
+                Validator { when }.run_pass(self.tcx, self.source, caller_body);
+            }
         }
+        PassOutcome::changed_if(changed)
+    }
+
+    /// Whether `-Z inline-mir-relaxed` allows inlining calls in cleanup blocks, diverging calls,
+    /// and callees with the constructs of `RelaxedOnly`.
+    fn relaxed(&self) -> bool {
+        self.tcx.sess.opts.debugging_opts.inline_mir_relaxed
+    }
+
+    /// Whether only `#[inline(always)]` callees with trivial bodies are inlined, which is the case
+    /// below `-Z mir-opt-level=2`, so that small wrappers cost nothing in debug builds either.
+    fn restricted(&self) -> bool {
+        self.tcx.sess.opts.debugging_opts.mir_opt_level < 2
     }
 
     fn get_valid_function_call(
@@ -183,8 +256,8 @@ impl Inliner<'tcx> {
         caller_body: &Body<'tcx>,
         param_env: ParamEnv<'tcx>,
     ) -> Option<CallSite<'tcx>> {
-        // Don't inline calls that are in cleanup blocks.
-        if bb_data.is_cleanup {
+        // Don't inline calls that are in cleanup blocks, unless the relaxed mode is enabled.
+        if bb_data.is_cleanup && !self.relaxed() {
             return None;
         }
 
@@ -242,6 +315,45 @@ impl Inliner<'tcx> {
             return false;
         }
 
+        if self.restricted() {
+            if codegen_fn_attrs.inline != attr::InlineAttr::Always {
+                debug!("    not `#[inline(always)]` in the restricted mode - not inlining");
+                return false;
+            }
+            if !is_trivial(tcx, callee_body) {
+                debug!("    body isn't trivial in the restricted mode - not inlining");
+                self.remark(callsite, |callee, caller| {
+                    format!(
+                        "not inlining `{}` into `{}` because its body isn't trivial enough \
+                         without optimizations",
+                        callee, caller
+                    )
+                });
+                return false;
+            }
+        }
+
+        if let Some(construct) = relaxed_only_construct(tcx, callee_body) {
+            // Even the relaxed mode can't give `caller_location` the right frame when the caller
+            // has an implicit location argument of its own.
+            let caller_tracks = tcx
+                .codegen_fn_attrs(self.source.def_id())
+                .flags
+                .contains(CodegenFnAttrFlags::TRACK_CALLER);
+            if !self.relaxed() || (construct == RelaxedOnly::CallerLocation && caller_tracks) {
+                debug!("    callee contains {:?} - not inlining", construct);
+                self.remark(callsite, |callee, caller| {
+                    format!(
+                        "not inlining `{}` into `{}` because it contains {}",
+                        callee,
+                        caller,
+                        construct.description()
+                    )
+                });
+                return false;
+            }
+        }
+
         let hinted = match codegen_fn_attrs.inline {
             // Just treat inline(always) as a hint for now,
             // there are cases that prevent inlining that we
@@ -249,6 +361,12 @@ impl Inliner<'tcx> {
             attr::InlineAttr::Always => true,
             attr::InlineAttr::Never => {
                 debug!("`#[inline(never)]` present - not inlining");
+                self.remark(callsite, |callee, caller| {
+                    format!(
+                        "not inlining `{}` into `{}` because of `#[inline(never)]`",
+                        callee, caller
+                    )
+                });
                 return false;
             }
             attr::InlineAttr::Hint => true,
@@ -259,13 +377,23 @@ impl Inliner<'tcx> {
         // inlining. This is to ensure that the final crate doesn't have MIR that
         // reference unexported symbols
         if callsite.callee.is_local() {
-            if callsite.substs.non_erasable_generics().count() == 0 && !hinted {
+            if callsite.substs.non_erasable_generics().count() == 0
+                && !hinted
+                && !is_self_contained_and_small(tcx, callee_body)
+            {
                 debug!("    callee is an exported function - not inlining");
                 return false;
             }
         }
 
-        let mut threshold = if hinted { HINT_THRESHOLD } else { DEFAULT_THRESHOLD };
+        let opts = &tcx.sess.opts.debugging_opts;
+        let aggressive = opts.mir_opt_level >= 3;
+        let mut threshold = match (hinted, aggressive) {
+            (true, false) => opts.inline_mir_hint_threshold.unwrap_or(HINT_THRESHOLD),
+            (false, false) => opts.inline_mir_threshold.unwrap_or(DEFAULT_THRESHOLD),
+            (true, true) => opts.inline_mir_hint_threshold.unwrap_or(AGGRESSIVE_HINT_THRESHOLD),
+            (false, true) => opts.inline_mir_threshold.unwrap_or(AGGRESSIVE_DEFAULT_THRESHOLD),
+        };
 
         // Significantly lower the threshold for inlining cold functions
         if codegen_fn_attrs.flags.contains(CodegenFnAttrFlags::COLD) {
@@ -390,28 +518,65 @@ impl Inliner<'tcx> {
 
         if let attr::InlineAttr::Always = codegen_fn_attrs.inline {
             debug!("INLINING {:?} because inline(always) [cost={}]", callsite, cost);
+            self.remark(callsite, |callee, caller| {
+                format!(
+                    "inlining `{}` into `{}` because of `#[inline(always)]` (cost {})",
+                    callee, caller, cost
+                )
+            });
             true
         } else {
             if cost <= threshold {
                 debug!("INLINING {:?} [cost={} <= threshold={}]", callsite, cost, threshold);
+                self.remark(callsite, |callee, caller| {
+                    format!(
+                        "inlining `{}` into `{}` (cost {}, threshold {})",
+                        callee, caller, cost, threshold
+                    )
+                });
                 true
             } else {
                 debug!("NOT inlining {:?} [cost={} > threshold={}]", callsite, cost, threshold);
+                self.remark(callsite, |callee, caller| {
+                    format!(
+                        "not inlining `{}` into `{}` (cost {} > threshold {})",
+                        callee, caller, cost, threshold
+                    )
+                });
                 false
             }
         }
     }
 
+    /// Emits a `-Z remark-mir=Inline` remark about `callsite`. `message` gets the paths of the
+    /// callee and the caller.
+    fn remark(&self, callsite: CallSite<'tcx>, message: impl FnOnce(String, String) -> String) {
+        emit_remark(self.tcx, "Inline", callsite.location.span, || {
+            let callee = self.tcx.def_path_str(callsite.callee);
+            let caller = self.tcx.def_path_str(self.source.def_id());
+            message(callee, caller)
+        });
+    }
+
     fn inline_call(
         &self,
         callsite: CallSite<'tcx>,
         caller_body: &mut Body<'tcx>,
         mut callee_body: Body<'tcx>,
     ) -> bool {
+        // Calls in cleanup blocks can't unwind, so the callee must not have cleanup blocks of
+        // its own.
+        let in_cleanup_block = caller_body[callsite.bb].is_cleanup;
+        if in_cleanup_block && callee_body.basic_blocks().iter().any(|data| data.is_cleanup) {
+            debug!("callee has cleanup blocks - not inlining into a cleanup block");
+            return false;
+        }
+
         let terminator = caller_body[callsite.bb].terminator.take().unwrap();
         match terminator.kind {
-            // FIXME: Handle inlining of diverging calls
-            TerminatorKind::Call { args, destination: Some(destination), cleanup, .. } => {
+            TerminatorKind::Call { args, destination, cleanup, .. }
+                if destination.is_some() || self.relaxed() =>
+            {
                 debug!("inlined {:?} into {:?}", callsite.callee, self.source);
 
                 let mut local_map = IndexVec::with_capacity(callee_body.local_decls.len());
@@ -460,12 +625,24 @@ impl Inliner<'tcx> {
                     false
                 }
 
-                let dest = if dest_needs_borrow(destination.0) {
+                // A diverging call has no destination, but the callee may still write to its
+                // return place before it diverges.
+                let (destination, return_block) = match destination {
+                    Some((place, target)) => (place, Some(target)),
+                    None => {
+                        debug!("creating temp for the return place of a diverging call");
+                        let ty = callee_body.return_ty();
+                        let temp = LocalDecl::new(ty, callsite.location.span);
+                        (Place::from(caller_body.local_decls.push(temp)), None)
+                    }
+                };
+
+                let dest = if dest_needs_borrow(destination) {
                     debug!("creating temp for return destination");
                     let dest = Rvalue::Ref(
                         self.tcx.lifetimes.re_erased,
                         BorrowKind::Mut { allow_two_phase_borrow: false },
-                        destination.0,
+                        destination,
                     );
 
                     let ty = dest.ty(caller_body, self.tcx);
@@ -482,11 +659,9 @@ impl Inliner<'tcx> {
                     caller_body[callsite.bb].statements.push(stmt);
                     self.tcx.mk_place_deref(tmp)
                 } else {
-                    destination.0
+                    destination
                 };
 
-                let return_block = destination.1;
-
                 // Copy the arguments if needed.
                 let args: Vec<_> = self.make_call_args(args, &callsite, caller_body);
 
@@ -500,6 +675,7 @@ impl Inliner<'tcx> {
                     return_block,
                     cleanup_block: cleanup,
                     in_cleanup_block: false,
+                    callsite_in_cleanup_block: in_cleanup_block,
                     tcx: self.tcx,
                 };
 
@@ -633,6 +809,151 @@ impl Inliner<'tcx> {
     }
 }
 
+/// Whether `body` is simple enough to inline without optimizations: it has at most
+/// `TRIVIAL_BODY_BLOCKS` blocks and no loops, and calls nothing but intrinsics. Since inlined
+/// calls leave no frame behind in backtraces, only such leaves of the call graph are inlined.
+fn is_trivial<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> bool {
+    let calls_only_intrinsics =
+        body.basic_blocks().iter().all(|data| match data.terminator().kind {
+            TerminatorKind::Goto { .. }
+            | TerminatorKind::SwitchInt { .. }
+            | TerminatorKind::Assert { .. }
+            | TerminatorKind::Return
+            | TerminatorKind::Unreachable => true,
+            TerminatorKind::Call { ref func, .. } => match func.ty(body, tcx).kind {
+                ty::FnDef(def_id, _) => {
+                    matches!(tcx.fn_sig(def_id).abi(), Abi::RustIntrinsic | Abi::PlatformIntrinsic)
+                }
+                _ => false,
+            },
+            _ => false,
+        });
+    body.basic_blocks().len() <= TRIVIAL_BODY_BLOCKS
+        && calls_only_intrinsics
+        && !body.is_cfg_cyclic()
+}
+
+/// A construct that only `-Z inline-mir-relaxed` inlines.
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum RelaxedOnly {
+    /// Inline assembly, which every inlined copy of the callee duplicates, so that assembly that
+    /// defines symbols no longer assembles.
+    InlineAsm,
+    /// A call of the `caller_location` intrinsic, which codegen answers with the implicit location
+    /// argument of the function it ends up in.
+    CallerLocation,
+}
+
+impl RelaxedOnly {
+    fn description(self) -> &'static str {
+        match self {
+            RelaxedOnly::InlineAsm => "inline assembly",
+            RelaxedOnly::CallerLocation => "a call of `caller_location`",
+        }
+    }
+}
+
+/// The first construct in `body` that only the relaxed mode inlines, if there is one.
+fn relaxed_only_construct<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> Option<RelaxedOnly> {
+    for data in body.basic_blocks() {
+        if data.statements.iter().any(|s| matches!(s.kind, StatementKind::LlvmInlineAsm(_))) {
+            return Some(RelaxedOnly::InlineAsm);
+        }
+        match data.terminator().kind {
+            TerminatorKind::InlineAsm { .. } => return Some(RelaxedOnly::InlineAsm),
+            TerminatorKind::Call { ref func, .. } => {
+                if let ty::FnDef(def_id, _) = func.ty(body, tcx).kind {
+                    if tcx.fn_sig(def_id).abi() == Abi::RustIntrinsic
+                        && tcx.item_name(def_id) == sym::caller_location
+                    {
+                        return Some(RelaxedOnly::CallerLocation);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Whether `body`, of a function that isn't generic or `#[inline]`, is small enough to export to
+/// other crates for inlining, and only refers to what they can refer to as well: it calls nothing
+/// but intrinsics, and creates no pointers to statics, functions, closures or vtables.
+fn is_self_contained_and_small<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> bool {
+    let mut size = 0;
+    for data in body.basic_blocks() {
+        size += 1 + data
+            .statements
+            .iter()
+            .filter(|statement| {
+                !matches!(
+                    statement.kind,
+                    StatementKind::StorageLive(_)
+                        | StatementKind::StorageDead(_)
+                        | StatementKind::Nop
+                )
+            })
+            .count();
+        match data.terminator().kind {
+            TerminatorKind::Goto { .. }
+            | TerminatorKind::SwitchInt { .. }
+            | TerminatorKind::Assert { .. }
+            | TerminatorKind::Return
+            | TerminatorKind::Unreachable
+            | TerminatorKind::Call { .. } => {}
+            _ => return false,
+        }
+    }
+    if size > CROSS_CRATE_MAX_SIZE {
+        return false;
+    }
+
+    let mut checker = SelfContainedChecker { tcx, self_contained: true };
+    checker.visit_body(body);
+    checker.self_contained
+}
+
+struct SelfContainedChecker<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    self_contained: bool,
+}
+
+impl<'tcx> Visitor<'tcx> for SelfContainedChecker<'tcx> {
+    fn visit_rvalue(&mut self, rvalue: &Rvalue<'tcx>, location: Location) {
+        match rvalue {
+            Rvalue::ThreadLocalRef(_)
+            | Rvalue::Aggregate(box AggregateKind::Closure(..), _)
+            | Rvalue::Aggregate(box AggregateKind::Generator(..), _)
+            | Rvalue::Cast(
+                CastKind::Pointer(
+                    PointerCast::ReifyFnPointer
+                    | PointerCast::ClosureFnPointer(_)
+                    | PointerCast::Unsize,
+                ),
+                ..,
+            ) => self.self_contained = false,
+            _ => self.super_rvalue(rvalue, location),
+        }
+    }
+
+    fn visit_constant(&mut self, constant: &Constant<'tcx>, _: Location) {
+        // Calls go through the zero-sized constants of their callees.
+        if let ty::FnDef(def_id, _) = constant.literal.ty.kind {
+            let abi = self.tcx.fn_sig(def_id).abi();
+            if abi != Abi::RustIntrinsic && abi != Abi::PlatformIntrinsic {
+                self.self_contained = false;
+            }
+            return;
+        }
+        self.self_contained &= match constant.literal.val {
+            ConstKind::Value(ConstValue::Scalar(Scalar::Raw { .. })) => true,
+            ConstKind::Value(ConstValue::Slice { data: alloc, .. })
+            | ConstKind::Value(ConstValue::ByRef { alloc, .. }) => alloc.relocations().is_empty(),
+            _ => false,
+        };
+    }
+}
+
 fn type_size_of<'tcx>(
     tcx: TyCtxt<'tcx>,
     param_env: ty::ParamEnv<'tcx>,
@@ -654,9 +975,10 @@ struct Integrator<'a, 'tcx> {
     local_map: IndexVec<Local, Local>,
     scope_map: IndexVec<SourceScope, SourceScope>,
     destination: Place<'tcx>,
-    return_block: BasicBlock,
+    return_block: Option<BasicBlock>,
     cleanup_block: Option<BasicBlock>,
     in_cleanup_block: bool,
+    callsite_in_cleanup_block: bool,
     tcx: TyCtxt<'tcx>,
 }
 
@@ -709,6 +1031,11 @@ impl<'a, 'tcx> MutVisitor<'tcx> for Integrator<'a, 'tcx> {
         self.in_cleanup_block = data.is_cleanup;
         self.super_basic_block_data(block, data);
         self.in_cleanup_block = false;
+
+        // Everything inlined into a cleanup block is part of the cleanup.
+        if self.callsite_in_cleanup_block {
+            data.is_cleanup = true;
+        }
     }
 
     fn visit_retag(&mut self, kind: &mut RetagKind, place: &mut Place<'tcx>, loc: Location) {
@@ -772,7 +1099,11 @@ impl<'a, 'tcx> MutVisitor<'tcx> for Integrator<'a, 'tcx> {
                 }
             }
             TerminatorKind::Return => {
-                terminator.kind = TerminatorKind::Goto { target: self.return_block };
+                terminator.kind = match self.return_block {
+                    Some(target) => TerminatorKind::Goto { target },
+                    // The callee of a diverging call can't actually return.
+                    None => TerminatorKind::Unreachable,
+                };
             }
             TerminatorKind::Resume => {
                 if let Some(tgt) = self.cleanup_block {
@@ -790,10 +1121,26 @@ impl<'a, 'tcx> MutVisitor<'tcx> for Integrator<'a, 'tcx> {
             {
                 bug!("False unwinds should have been removed before inlining")
             }
-            TerminatorKind::InlineAsm { ref mut destination, .. } => {
+            TerminatorKind::InlineAsm {
+                ref mut destination,
+                ref mut labels,
+                ref mut cleanup,
+                options,
+                ..
+            } => {
                 if let Some(ref mut tgt) = *destination {
                     *tgt = self.update_target(*tgt);
                 }
+                for tgt in labels.iter_mut() {
+                    *tgt = self.update_target(*tgt);
+                }
+                if let Some(tgt) = *cleanup {
+                    *cleanup = Some(self.update_target(tgt));
+                } else if options.contains(InlineAsmOptions::MAY_UNWIND) && !self.in_cleanup_block {
+                    // Unless this inline assembly is in a cleanup block, add an unwind edge to
+                    // the original call's cleanup block
+                    *cleanup = self.cleanup_block;
+                }
             }
         }
     }
@@ -0,0 +1,235 @@
+//! Calls `assume` with the facts that MIR proves, so that codegen keeps them.
+//!
+//! An assertion only lets execution continue when its condition holds, and a discriminant read
+//! from a valid enum is one of the discriminants of its variants. Once lowered, the backend
+//! rarely recovers either fact, and none at all once the checks they come from are optimized
+//! away. With `-Z mir-insert-assumes`, the pass spells them out right after the check or read:
+//!
+//!     bb0: { assert(move _4, "index out of bounds: ...") -> bb1; }
+//!
+//! becomes
+//!
+//!     bb0: { assert(_4, "index out of bounds: ...") -> bb2; }
+//!     bb2: { _6 = assume(_4) -> bb1; }
+//!
+//! and `_2 = discriminant(_1)` of an `Option` is followed by calls assuming `Ge(_2, 0)` and
+//! `Le(_2, 1)`. Comparisons that the type of the discriminant already implies are left out.
+//!
+//! CTFE doesn't know `assume`, so bodies that may be evaluated at compile time are left alone.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::interpret::sign_extend;
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, ParamEnv, Ty, TyCtxt};
+use rustc_session::Session;
+use std::mem;
+use std::ops::RangeInclusive;
+
+pub struct InsertAssumes;
+
+impl<'tcx> MirPass<'tcx> for InsertAssumes {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.mir_insert_assumes
+    }
+
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        // The calls would only get in the way of the optimizations.
+        MirPhase::Optimized..=MirPhase::Optimized
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let def_id = source.def_id();
+        if source.promoted.is_some()
+            || def_id
+                .as_local()
+                .map_or(true, |def_id| tcx.hir().body_const_context(def_id).is_some())
+        {
+            return PassOutcome::Unchanged;
+        }
+        let assume_fn = match tcx.lang_items().assume_fn() {
+            Some(assume_fn) => assume_fn,
+            None => return PassOutcome::Unchanged,
+        };
+
+        let mut inserter = AssumeInserter { tcx, assume_fn, condition: None, unit: None };
+        let mut changed = false;
+        // The blocks split off or inserted are visited as well, since they may hold more reads.
+        let mut index = 0;
+        while index < body.basic_blocks().len() {
+            let bb = BasicBlock::new(index);
+            index += 1;
+
+            let read = body[bb].statements.iter().enumerate().find_map(|(i, statement)| {
+                match statement.kind {
+                    StatementKind::Assign(box (place, Rvalue::Discriminant(ref enum_place))) => {
+                        let source_info = statement.source_info;
+                        let enum_ty = enum_place.ty(body, tcx).ty;
+                        let conditions = discriminant_bounds(tcx, source_info, enum_ty, place);
+                        if conditions.is_empty() {
+                            None
+                        } else {
+                            Some((i, source_info, conditions))
+                        }
+                    }
+                    _ => None,
+                }
+            });
+            if let Some((i, source_info, conditions)) = read {
+                debug!("assuming {:?} after the discriminant read {:?}[{}]", conditions, bb, i);
+                inserter.call_assume(body, bb, i + 1, source_info, conditions);
+                changed = true;
+                // The rest of the block, with its terminator, moved to a block visited later.
+                continue;
+            }
+
+            // The target may have other predecessors, so the call goes on a new edge.
+            let edge = body.basic_blocks().next_index();
+            let is_cleanup = body[bb].is_cleanup;
+            let terminator = body[bb].terminator_mut();
+            let source_info = terminator.source_info;
+            let (place, expected, target) = match terminator.kind {
+                TerminatorKind::Assert { ref mut cond, expected, ref mut target, .. } => {
+                    match cond.place() {
+                        Some(place) => {
+                            // The condition is read again after the assertion.
+                            *cond = Operand::Copy(place);
+                            (place, expected, mem::replace(target, edge))
+                        }
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            body.basic_blocks_mut().push(BasicBlockData {
+                statements: vec![],
+                terminator: Some(Terminator { source_info, kind: TerminatorKind::Goto { target } }),
+                is_cleanup,
+            });
+            let condition = if expected {
+                Rvalue::Use(Operand::Copy(place))
+            } else {
+                Rvalue::UnaryOp(UnOp::Not, Operand::Copy(place))
+            };
+            debug!("assuming {:?} after the assertion in {:?}", condition, bb);
+            inserter.call_assume(body, edge, 0, source_info, vec![condition]);
+            changed = true;
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
+
+/// Returns the comparisons of the discriminant of `enum_ty`, read into `place`, with the smallest
+/// and the largest discriminant of its variants that the type of the discriminant doesn't imply.
+fn discriminant_bounds<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    source_info: SourceInfo,
+    enum_ty: Ty<'tcx>,
+    place: Place<'tcx>,
+) -> Vec<Rvalue<'tcx>> {
+    let adt = match enum_ty.kind {
+        ty::Adt(adt, _) if adt.is_enum() && !adt.variants.is_empty() => adt,
+        _ => return vec![],
+    };
+    let discr_ty = enum_ty.discriminant_ty(tcx);
+    let size = match tcx.layout_of(ParamEnv::empty().and(discr_ty)) {
+        Ok(layout) => layout.size,
+        Err(_) => return vec![],
+    };
+    let compare = |a: &u128, b: &u128| {
+        if discr_ty.is_signed() {
+            (sign_extend(*a, size) as i128).cmp(&(sign_extend(*b, size) as i128))
+        } else {
+            a.cmp(b)
+        }
+    };
+    let values = || adt.discriminants(tcx).map(|(_, discr)| discr.val);
+    let (min, max) = match (values().min_by(compare), values().max_by(compare)) {
+        (Some(min), Some(max)) => (min, max),
+        _ => return vec![],
+    };
+
+    let mut conditions = vec![];
+    let mut compare_with = |op, bits, bound: Option<&'tcx ty::Const<'tcx>>| {
+        let value = ty::Const::from_bits(tcx, bits, ParamEnv::empty().and(discr_ty));
+        if bound != Some(value) {
+            let span = source_info.span;
+            let value = Operand::Constant(box Constant { span, user_ty: None, literal: value });
+            conditions.push(Rvalue::BinaryOp(op, Operand::Copy(place), value));
+        }
+    };
+    compare_with(BinOp::Ge, min, discr_ty.numeric_min_val(tcx));
+    compare_with(BinOp::Le, max, discr_ty.numeric_max_val(tcx));
+    conditions
+}
+
+struct AssumeInserter<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    assume_fn: DefId,
+    /// The temporary holding the condition passed to `assume`, once it exists.
+    condition: Option<Local>,
+    /// The temporary receiving the result of `assume`, once it exists.
+    unit: Option<Local>,
+}
+
+impl<'tcx> AssumeInserter<'tcx> {
+    /// Splits `bb` after its first `index` statements, and calls `assume` on each of the
+    /// `conditions` in between, at `source_info`.
+    fn call_assume(
+        &mut self,
+        body: &mut Body<'tcx>,
+        bb: BasicBlock,
+        index: usize,
+        source_info: SourceInfo,
+        conditions: Vec<Rvalue<'tcx>>,
+    ) {
+        let tcx = self.tcx;
+        let span = source_info.span;
+        let data = &mut body[bb];
+        let is_cleanup = data.is_cleanup;
+        let rest = data.statements.split_off(index);
+        let terminator = data.terminator.take();
+
+        let condition = *self.condition.get_or_insert_with(|| {
+            body.local_decls.push(LocalDecl::new(tcx.types.bool, span).internal())
+        });
+        let unit = *self.unit.get_or_insert_with(|| {
+            body.local_decls.push(LocalDecl::new(tcx.mk_unit(), span).internal())
+        });
+        let func = Operand::function_handle(tcx, self.assume_fn, tcx.intern_substs(&[]), span);
+
+        let mut current = bb;
+        for rvalue in conditions {
+            let next = body.basic_blocks_mut().push(BasicBlockData {
+                statements: vec![],
+                terminator: None,
+                is_cleanup,
+            });
+            let data = &mut body[current];
+            data.statements.push(Statement {
+                source_info,
+                kind: StatementKind::Assign(box (condition.into(), rvalue)),
+            });
+            data.terminator = Some(Terminator {
+                source_info,
+                kind: TerminatorKind::Call {
+                    func: func.clone(),
+                    args: vec![Operand::Move(condition.into())],
+                    destination: Some((unit.into(), next)),
+                    cleanup: None,
+                    from_hir_call: false,
+                    fn_span: span,
+                },
+            });
+            current = next;
+        }
+        let data = &mut body[current];
+        data.statements = rest;
+        data.terminator = terminator;
+    }
+}
@@ -1,31 +1,66 @@
 //! Performs various peephole optimizations.
 
-use crate::transform::{MirPass, MirSource};
+use crate::transform::strength_reduction::reduce_division;
+use crate::transform::{MirPass, MirSource, PassOutcome};
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_hir::Mutability;
 use rustc_index::vec::Idx;
 use rustc_middle::mir::visit::{MutVisitor, Visitor};
 use rustc_middle::mir::{
-    Body, Constant, Local, Location, Operand, Place, PlaceRef, ProjectionElem, Rvalue,
+    AggregateKind, BinOp, Body, Constant, Local, Location, Operand, Place, PlaceRef, ProjectionElem,
+    Rvalue, Statement, StatementKind,
 };
-use rustc_middle::ty::{self, TyCtxt};
+use rustc_middle::ty::{self, Ty, TyCtxt};
+use std::borrow::Cow;
 use std::mem;
 
-pub struct InstCombine;
+#[derive(Default)]
+pub struct InstCombine {
+    /// Tells apart the runs scheduled after the first one, which is just called `InstCombine`.
+    label: Option<String>,
+}
+
+impl InstCombine {
+    pub fn new(label: &str) -> Self {
+        InstCombine { label: Some(format!("InstCombine-{}", label)) }
+    }
+}
 
 impl<'tcx> MirPass<'tcx> for InstCombine {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, _: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    fn name(&self) -> Cow<'_, str> {
+        match self.label {
+            Some(ref label) => Cow::Borrowed(label),
+            None => Cow::Borrowed("InstCombine"),
+        }
+    }
+
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
         // First, find optimization opportunities. This is done in a pre-pass to keep the MIR
         // read-only so that we can do global analyses on the MIR in the process (e.g.
         // `Place::ty()`).
         let optimizations = {
-            let mut optimization_finder = OptimizationFinder::new(body, tcx);
+            let param_env = tcx.param_env(source.def_id());
+            let mut optimization_finder = OptimizationFinder::new(body, tcx, param_env);
             optimization_finder.visit_body(body);
             optimization_finder.optimizations
         };
 
+        if optimizations.is_empty() {
+            return PassOutcome::Unchanged;
+        }
+
         // Then carry out those optimizations.
         MutVisitor::visit_body(&mut InstCombineVisitor { optimizations, tcx }, body);
+        PassOutcome::Changed
     }
 }
 
@@ -39,6 +74,26 @@ impl<'tcx> MutVisitor<'tcx> for InstCombineVisitor<'tcx> {
         self.tcx
     }
 
+    fn visit_statement(&mut self, statement: &mut Statement<'tcx>, location: Location) {
+        if let Some(repeat) = self.optimizations.degenerate_repeats.remove(&location) {
+            debug!("replacing `[x; 0]` or `[x; 1]`: {:?}", statement);
+            match repeat {
+                // The operand has no side effects, and a zero-sized array needs no initialization.
+                None => statement.make_nop(),
+                Some(element_ty) => {
+                    if let StatementKind::Assign(box (_, ref mut rvalue)) = statement.kind {
+                        if let Rvalue::Repeat(ref operand, _) = *rvalue {
+                            let kind = box AggregateKind::Array(element_ty);
+                            *rvalue = Rvalue::Aggregate(kind, vec![operand.clone()]);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.super_statement(statement, location)
+    }
+
     fn visit_rvalue(&mut self, rvalue: &mut Rvalue<'tcx>, location: Location) {
         if self.optimizations.and_stars.remove(&location) {
             debug!("replacing `&*`: {:?}", rvalue);
@@ -66,6 +121,26 @@ impl<'tcx> MutVisitor<'tcx> for InstCombineVisitor<'tcx> {
             *rvalue = Rvalue::Use(Operand::Constant(box constant));
         }
 
+        if self.optimizations.commutative_swaps.remove(&location) {
+            debug!("swapping the operands of {:?}", rvalue);
+            match rvalue {
+                Rvalue::BinaryOp(_, lhs, rhs) | Rvalue::CheckedBinaryOp(_, lhs, rhs) => {
+                    mem::swap(lhs, rhs)
+                }
+                _ => bug!("Detected a commutative operation but didn't find one!"),
+            }
+        }
+
+        if let Some((new_op, constant)) =
+            self.optimizations.power_of_two_divisions.remove(&location)
+        {
+            debug!("replacing division by a power of two: {:?}", rvalue);
+            if let Rvalue::BinaryOp(ref mut op, _, ref mut rhs) = *rvalue {
+                *op = new_op;
+                *rhs = Operand::Constant(box constant);
+            }
+        }
+
         self.super_rvalue(rvalue, location)
     }
 }
@@ -74,12 +149,17 @@ impl<'tcx> MutVisitor<'tcx> for InstCombineVisitor<'tcx> {
 struct OptimizationFinder<'b, 'tcx> {
     body: &'b Body<'tcx>,
     tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
     optimizations: OptimizationList<'tcx>,
 }
 
 impl OptimizationFinder<'b, 'tcx> {
-    fn new(body: &'b Body<'tcx>, tcx: TyCtxt<'tcx>) -> OptimizationFinder<'b, 'tcx> {
-        OptimizationFinder { body, tcx, optimizations: OptimizationList::default() }
+    fn new(
+        body: &'b Body<'tcx>,
+        tcx: TyCtxt<'tcx>,
+        param_env: ty::ParamEnv<'tcx>,
+    ) -> OptimizationFinder<'b, 'tcx> {
+        OptimizationFinder { body, tcx, param_env, optimizations: OptimizationList::default() }
     }
 }
 
@@ -97,6 +177,19 @@ impl Visitor<'tcx> for OptimizationFinder<'b, 'tcx> {
             }
         }
 
+        if let Rvalue::Repeat(ref operand, len) = *rvalue {
+            match len.try_eval_usize(self.tcx, self.param_env) {
+                Some(0) => {
+                    self.optimizations.degenerate_repeats.insert(location, None);
+                }
+                Some(1) => {
+                    let element_ty = operand.ty(self.body, self.tcx);
+                    self.optimizations.degenerate_repeats.insert(location, Some(element_ty));
+                }
+                _ => {}
+            }
+        }
+
         if let Rvalue::Len(ref place) = *rvalue {
             let place_ty = place.ty(&self.body.local_decls, self.tcx).ty;
             if let ty::Array(_, len) = place_ty.kind {
@@ -106,12 +199,50 @@ impl Visitor<'tcx> for OptimizationFinder<'b, 'tcx> {
             }
         }
 
+        match *rvalue {
+            Rvalue::BinaryOp(op, ref lhs, ref rhs) | Rvalue::CheckedBinaryOp(op, ref lhs, ref rhs)
+                if op.is_commutative() && operand_order(rhs) < operand_order(lhs) =>
+            {
+                self.optimizations.commutative_swaps.insert(location);
+            }
+            _ => {}
+        }
+
+        if let Some(reduced) = reduce_division(self.tcx, self.param_env, rvalue) {
+            self.optimizations.power_of_two_divisions.insert(location, reduced);
+        }
+
         self.super_rvalue(rvalue, location)
     }
 }
 
+/// The canonical order of the operands of commutative operations: places ordered by their local,
+/// then constants. Structurally comparing MIR only finds equal computations if their operands
+/// come in the same order.
+fn operand_order(operand: &Operand<'_>) -> (bool, usize) {
+    match operand {
+        Operand::Copy(place) | Operand::Move(place) => (false, place.local.index()),
+        Operand::Constant(_) => (true, 0),
+    }
+}
+
 #[derive(Default)]
 struct OptimizationList<'tcx> {
     and_stars: FxHashSet<Location>,
+    commutative_swaps: FxHashSet<Location>,
     arrays_lengths: FxHashMap<Location, Constant<'tcx>>,
+    /// Repeat expressions of length 0 (`None`) or 1 (`Some` of the element type).
+    degenerate_repeats: FxHashMap<Location, Option<Ty<'tcx>>>,
+    /// Unsigned divisions and remainders by a power of two, with the shift or mask replacing them.
+    power_of_two_divisions: FxHashMap<Location, (BinOp, Constant<'tcx>)>,
+}
+
+impl OptimizationList<'_> {
+    fn is_empty(&self) -> bool {
+        self.and_stars.is_empty()
+            && self.commutative_swaps.is_empty()
+            && self.arrays_lengths.is_empty()
+            && self.degenerate_repeats.is_empty()
+            && self.power_of_two_divisions.is_empty()
+    }
 }
@@ -0,0 +1,137 @@
+//! Implements `-Z profile` on the MIR, so that it works the same with every codegen backend.
+//!
+//! Every non-cleanup block of an instrumented function starts with a call to the
+//! `profile_count_block` lang item, which gets the file the counts of the crate go to
+//! (`-Z profile-emit`, `<output>.mirprof` by default), the name of the function, a hash of
+//! that name and the index the block had before instrumentation. The lang item is provided by the
+//! profiler runtime, which merges the counts into those files when the process exits.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_hir::def_id::{DefId, LOCAL_CRATE};
+use rustc_middle::mir::interpret::{Allocation, ConstValue, Scalar};
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, List, TyCtxt};
+use rustc_session::Session;
+use rustc_span::Span;
+use std::mem;
+
+pub struct InstrumentBlockCounters;
+
+impl<'tcx> MirPass<'tcx> for InstrumentBlockCounters {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.profile
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        // Bodies that may be evaluated at compile time can't call the runtime, which is not
+        // `const`. Shims are not local code.
+        let def_id = match source.instance {
+            ty::InstanceDef::Item(def) if source.promoted.is_none() => def.did,
+            _ => return PassOutcome::Unchanged,
+        };
+        let local_def_id = match def_id.as_local() {
+            Some(local_def_id) => local_def_id,
+            None => return PassOutcome::Unchanged,
+        };
+        if tcx.hir().body_const_context(local_def_id).is_some() {
+            return PassOutcome::Unchanged;
+        }
+
+        // Without the profiler runtime (`-Z no-profiler-runtime`) there is nothing to call.
+        let count_block_fn = match tcx.lang_items().profile_count_block_fn() {
+            Some(count_block_fn) => count_block_fn,
+            None => return PassOutcome::Unchanged,
+        };
+
+        let path = match tcx.sess.opts.debugging_opts.profile_emit {
+            Some(ref path) => path.clone(),
+            None => tcx.output_filenames(LOCAL_CRATE).with_extension("mirprof"),
+        };
+        let path = format!("{}\0", path.display());
+        let function = format!("{}::{}", tcx.crate_name(LOCAL_CRATE), tcx.def_path_str(def_id));
+        count_blocks(tcx, body, count_block_fn, &path, &function);
+        PassOutcome::Changed
+    }
+}
+
+/// Moves the contents of every non-cleanup block into a new block, and makes the original block
+/// call `count_block_fn` before jumping there.
+fn count_blocks<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    body: &mut Body<'tcx>,
+    count_block_fn: DefId,
+    path: &str,
+    function: &str,
+) {
+    let function_hash = hash_function_name(function);
+    let unit_temp = body.local_decls.push(LocalDecl::new(tcx.mk_unit(), body.span).internal());
+    let basic_blocks = body.basic_blocks_mut();
+    for bb in basic_blocks.indices() {
+        if basic_blocks[bb].is_cleanup {
+            continue;
+        }
+
+        let data = &mut basic_blocks[bb];
+        let source_info = match data.statements.first() {
+            Some(statement) => statement.source_info,
+            None => data.terminator().source_info,
+        };
+        let counted = BasicBlockData {
+            statements: mem::take(&mut data.statements),
+            terminator: data.terminator.take(),
+            is_cleanup: false,
+        };
+        let counted = basic_blocks.push(counted);
+
+        let span = source_info.span;
+        basic_blocks[bb].terminator = Some(Terminator {
+            source_info,
+            kind: TerminatorKind::Call {
+                func: Operand::function_handle(tcx, count_block_fn, List::empty(), span),
+                args: vec![
+                    const_str(tcx, path, span),
+                    const_str(tcx, function, span),
+                    Operand::const_from_scalar(
+                        tcx,
+                        tcx.types.u64,
+                        Scalar::from_u64(function_hash),
+                        span,
+                    ),
+                    Operand::const_from_scalar(
+                        tcx,
+                        tcx.types.u32,
+                        Scalar::from_u32(bb.as_u32()),
+                        span,
+                    ),
+                ],
+                destination: Some((Place::from(unit_temp), counted)),
+                cleanup: None,
+                from_hir_call: false,
+                fn_span: span,
+            },
+        });
+    }
+}
+
+/// The 64-bit FNV-1a hash of `function`, which the runtime also computes for the names it reads
+/// back from the file.
+fn hash_function_name(function: &str) -> u64 {
+    function.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn const_str<'tcx>(tcx: TyCtxt<'tcx>, s: &str, span: Span) -> Operand<'tcx> {
+    let allocation = tcx.intern_const_alloc(Allocation::from_byte_aligned_bytes(s.as_bytes()));
+    let value = ConstValue::Slice { data: allocation, start: 0, end: s.len() };
+    Operand::Constant(box Constant {
+        span,
+        user_ty: None,
+        literal: ty::Const::from_value(tcx, value, tcx.mk_static_str()),
+    })
+}
@@ -1,6 +1,7 @@
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 use crate::util::patch::MirPatch;
 use rustc_data_structures::fingerprint::Fingerprint;
+use rustc_data_structures::fx::FxHashSet;
 use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
 use rustc_hir::lang_items;
 use rustc_middle::hir;
@@ -8,14 +9,15 @@ use rustc_middle::ich::StableHashingContext;
 use rustc_middle::mir::coverage::*;
 use rustc_middle::mir::interpret::Scalar;
 use rustc_middle::mir::{
-    self, traversal, BasicBlock, BasicBlockData, CoverageInfo, Operand, Place, SourceInfo,
-    SourceScope, StatementKind, Terminator, TerminatorKind,
+    self, traversal, BasicBlock, BasicBlockData, BodyKind, CoverageInfo, Operand, Place,
+    SourceInfo, SourceScope, StatementKind, Terminator, TerminatorKind,
 };
 use rustc_middle::ty;
 use rustc_middle::ty::query::Providers;
 use rustc_middle::ty::FnDef;
 use rustc_middle::ty::TyCtxt;
-use rustc_span::def_id::DefId;
+use rustc_session::Session;
+use rustc_span::def_id::{DefId, LOCAL_CRATE};
 use rustc_span::{Pos, Span};
 
 /// Inserts call to count_code_region() as a placeholder to be replaced during code generation with
@@ -48,20 +50,37 @@ fn coverageinfo_from_mir<'tcx>(tcx: TyCtxt<'tcx>, mir_def_id: DefId) -> Coverage
     // `num_expressions` is the number of counter expressions added to the MIR body. Both
     // `num_counters` and `num_expressions` are used to initialize new vectors, during backend
     // code generate, to lookup counters and expressions by simple u32 indexes.
+    //
+    // The code regions of the calls removed by optimizations are never executed. They are
+    // reported as unreachable, so that coverage reports show them as not covered.
     let mut num_counters: u32 = 0;
     let mut num_expressions: u32 = 0;
+    let mut function_source_hash = None;
+    let mut counted_regions = FxHashSet::default();
     for terminator in
         traversal::preorder(mir_body).map(|(_, data)| data).filter_map(call_terminators)
     {
         if let TerminatorKind::Call { func: Operand::Constant(func), args, .. } = &terminator.kind {
             match func.literal.ty.kind {
                 FnDef(id, _) if id == count_code_region_fn => {
+                    let hash_arg =
+                        args.get(count_code_region_args::FUNCTION_SOURCE_HASH).expect("arg found");
+                    function_source_hash = Some(
+                        mir::Operand::scalar_from_const(hash_arg)
+                            .to_u64()
+                            .expect("function_source_hash arg is u64"),
+                    );
                     let counter_id_arg =
                         args.get(count_code_region_args::COUNTER_ID).expect("arg found");
                     let counter_id = mir::Operand::scalar_from_const(counter_id_arg)
                         .to_u32()
                         .expect("counter_id arg is u32");
                     num_counters = std::cmp::max(num_counters, counter_id + 1);
+                    counted_regions.insert(code_region(
+                        args,
+                        count_code_region_args::START_BYTE_POS,
+                        count_code_region_args::END_BYTE_POS,
+                    ));
                 }
                 FnDef(id, _)
                     if id == coverage_counter_add_fn || id == coverage_counter_subtract_fn =>
@@ -77,12 +96,80 @@ fn coverageinfo_from_mir<'tcx>(tcx: TyCtxt<'tcx>, mir_def_id: DefId) -> Coverage
                     // way, both counters and expressions can be operands in other expressions.
                     let expression_index = u32::MAX - id_descending_from_max;
                     num_expressions = std::cmp::max(num_expressions, expression_index + 1);
+                    counted_regions.insert(code_region(
+                        args,
+                        coverage_counter_expression_args::START_BYTE_POS,
+                        coverage_counter_expression_args::END_BYTE_POS,
+                    ));
                 }
                 _ => {}
             }
         }
     }
-    CoverageInfo { num_counters, num_expressions }
+
+    let mut regions = FxHashSet::default();
+    let mut unreachable_regions = Vec::new();
+    for &region in &mir_body.coverage_regions {
+        if regions.insert(region) && !counted_regions.contains(&region) {
+            unreachable_regions.push(region);
+        }
+    }
+
+    // Without counters, the hash has to be computed again. That's only possible for local
+    // functions, which are the only ones whose code can be reported as unused anyway.
+    let function_source_hash = match function_source_hash {
+        Some(hash) => hash,
+        None if mir_def_id.is_local() && !mir_body.coverage_regions.is_empty() => {
+            hash_mir_source(tcx, hir_body(tcx, mir_def_id))
+        }
+        None => 0,
+    };
+
+    CoverageInfo {
+        num_counters,
+        num_expressions,
+        num_regions: regions.len() as u32,
+        unreachable_regions,
+        function_source_hash,
+    }
+}
+
+/// Returns the code region passed to a coverage intrinsic.
+fn code_region(args: &[Operand<'_>], start_index: usize, end_index: usize) -> CodeRegion {
+    let byte_pos = |index| {
+        let arg = args.get(index).expect("arg found");
+        mir::Operand::scalar_from_const(arg).to_u32().expect("byte pos arg is u32")
+    };
+    CodeRegion { start_byte_pos: byte_pos(start_index), end_byte_pos: byte_pos(end_index) }
+}
+
+/// Prints the coverage counters, expressions and regions of every instrumented function of the
+/// local crate, for `-Z print-coverage-summary`.
+pub fn print_coverage_summary(tcx: TyCtxt<'_>) {
+    let mut lines = Vec::new();
+    for &def_id in tcx.mir_keys(LOCAL_CRATE).iter() {
+        match tcx.mir_body_kind(def_id).kind {
+            BodyKind::Fn | BodyKind::Closure | BodyKind::Generator => {}
+            BodyKind::Const | BodyKind::Static | BodyKind::Ctor => continue,
+        }
+
+        let coverageinfo = tcx.coverageinfo(def_id.to_def_id());
+        if coverageinfo.num_counters == 0 {
+            continue;
+        }
+        lines.push(format!(
+            "print-coverage-summary `{}`: counters: {}, expressions: {}, regions: {}",
+            tcx.def_path_str(def_id.to_def_id()),
+            coverageinfo.num_counters,
+            coverageinfo.num_expressions,
+            coverageinfo.num_regions,
+        ));
+    }
+
+    lines.sort();
+    for line in lines {
+        println!("{}", line);
+    }
 }
 
 fn call_terminators(data: &'tcx BasicBlockData<'tcx>) -> Option<&'tcx Terminator<'tcx>> {
@@ -94,12 +181,23 @@ fn call_terminators(data: &'tcx BasicBlockData<'tcx>) -> Option<&'tcx Terminator
 }
 
 impl<'tcx> MirPass<'tcx> for InstrumentCoverage {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, src: MirSource<'tcx>, mir_body: &mut mir::Body<'tcx>) {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.instrument_coverage
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        src: MirSource<'tcx>,
+        mir_body: &mut mir::Body<'tcx>,
+    ) -> PassOutcome {
         // If the InstrumentCoverage pass is called on promoted MIRs, skip them.
         // See: https://github.com/rust-lang/rust/pull/73011#discussion_r438317601
-        if src.promoted.is_none() {
-            Instrumentor::new(tcx, src, mir_body).inject_counters();
+        if src.promoted.is_some() {
+            return PassOutcome::Unchanged;
         }
+        Instrumentor::new(tcx, src, mir_body).inject_counters();
+        PassOutcome::Changed
     }
 }
 
@@ -113,6 +211,7 @@ struct InjectedCall<'tcx> {
     func: Operand<'tcx>,
     args: Vec<Operand<'tcx>>,
     inject_at: Span,
+    code_region: CodeRegion,
 }
 
 struct Instrumentor<'a, 'tcx> {
@@ -245,7 +344,7 @@ impl<'a, 'tcx> Instrumentor<'a, 'tcx> {
         debug_assert_eq!(END_BYTE_POS, args.len());
         args.push(self.const_u32(code_region.hi().to_u32(), inject_at));
 
-        InjectedCall { func, args, inject_at }
+        InjectedCall { func, args, inject_at, code_region: make_code_region(code_region) }
     }
 
     fn make_expression(
@@ -288,7 +387,7 @@ impl<'a, 'tcx> Instrumentor<'a, 'tcx> {
         debug_assert_eq!(END_BYTE_POS, args.len());
         args.push(self.const_u32(code_region.hi().to_u32(), inject_at));
 
-        InjectedCall { func, args, inject_at }
+        InjectedCall { func, args, inject_at, code_region: make_code_region(code_region) }
     }
 
     fn inject_call(
@@ -298,7 +397,7 @@ impl<'a, 'tcx> Instrumentor<'a, 'tcx> {
         is_cleanup: bool,
         next_block: BasicBlock,
     ) {
-        let InjectedCall { func, args, inject_at } = call;
+        let InjectedCall { func, args, inject_at, code_region } = call;
         debug!(
             "  injecting {}call to {:?}({:?}) at: {:?}, scope: {:?}",
             if is_cleanup { "cleanup " } else { "" },
@@ -329,6 +428,7 @@ impl<'a, 'tcx> Instrumentor<'a, 'tcx> {
         patch.add_statement(next_block.start_location(), StatementKind::StorageDead(temp));
 
         patch.apply(self.mir_body);
+        self.mir_body.coverage_regions.push(code_region);
 
         // To insert the `new_block` in front of the first block in the counted branch (the
         // `next_block`), just swap the indexes, leaving the rest of the graph unchanged.
@@ -351,6 +451,10 @@ fn function_handle<'tcx>(tcx: TyCtxt<'tcx>, fn_def_id: DefId, span: Span) -> Ope
     Operand::function_handle(tcx, fn_def_id, substs, span)
 }
 
+fn make_code_region(span: Span) -> CodeRegion {
+    CodeRegion { start_byte_pos: span.lo().to_u32(), end_byte_pos: span.hi().to_u32() }
+}
+
 fn placeholder_block(span: Span, scope: SourceScope, is_cleanup: bool) -> BasicBlockData<'tcx> {
     BasicBlockData {
         statements: vec![],
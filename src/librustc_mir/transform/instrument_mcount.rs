@@ -0,0 +1,99 @@
+//! Implements `-Z instrument-mcount` on the MIR, so that every codegen backend instruments the
+//! same functions.
+//!
+//! The entry of every instrumented function calls the `instrument_function_entry` lang item, a
+//! placeholder that codegen replaces with a call to the target's `mcount` function:
+//!
+//!     bb0: { _5 = instrument_function_entry() -> bb4; }
+//!     bb4: { /* the former entry block */ }
+//!
+//! The pass runs after MIR inlining, so functions inlined into their callers aren't counted.
+//! Naked functions and functions marked `#[no_instrument_function]` aren't instrumented, and
+//! neither are the bodies of constants, statics and promoteds, nor shims.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, List, TyCtxt};
+use rustc_session::Session;
+use std::mem;
+use std::ops::RangeInclusive;
+
+pub struct InstrumentMcount;
+
+impl<'tcx> MirPass<'tcx> for InstrumentMcount {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.instrument_mcount()
+    }
+
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        // After inlining, which decides what is a function of its own.
+        MirPhase::Optimized..=MirPhase::Optimized
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let def_id = match source.instance {
+            ty::InstanceDef::Item(def) if source.promoted.is_none() => def.did,
+            _ => return PassOutcome::Unchanged,
+        };
+        let local_def_id = match def_id.as_local() {
+            Some(local_def_id) => local_def_id,
+            None => return PassOutcome::Unchanged,
+        };
+        let hir_id = tcx.hir().local_def_id_to_hir_id(local_def_id);
+        if !tcx.hir().body_owner_kind(hir_id).is_fn_or_closure() {
+            return PassOutcome::Unchanged;
+        }
+        let flags = tcx.codegen_fn_attrs(def_id).flags;
+        if flags.intersects(CodegenFnAttrFlags::NAKED | CodegenFnAttrFlags::NO_INSTRUMENT_FUNCTION)
+        {
+            debug!("not instrumenting {:?}", def_id);
+            return PassOutcome::Unchanged;
+        }
+        let entry_fn = match tcx.lang_items().instrument_function_entry_fn() {
+            Some(entry_fn) => entry_fn,
+            None => return PassOutcome::Unchanged,
+        };
+
+        // Move the entry block out of the way, with the edges that come back to it, so that the
+        // call only happens once.
+        let unit_temp = body.local_decls.push(LocalDecl::new(tcx.mk_unit(), body.span).internal());
+        let basic_blocks = body.basic_blocks_mut();
+        let entry = &mut basic_blocks[START_BLOCK];
+        let former_entry = BasicBlockData {
+            statements: mem::take(&mut entry.statements),
+            terminator: entry.terminator.take(),
+            is_cleanup: false,
+        };
+        let former_entry = basic_blocks.push(former_entry);
+        for data in basic_blocks.iter_mut() {
+            if let Some(ref mut terminator) = data.terminator {
+                for target in terminator.successors_mut() {
+                    if *target == START_BLOCK {
+                        *target = former_entry;
+                    }
+                }
+            }
+        }
+
+        let span = body.span;
+        debug!("instrumenting the entry of {:?}", def_id);
+        body.basic_blocks_mut()[START_BLOCK].terminator = Some(Terminator {
+            source_info: SourceInfo::outermost(span),
+            kind: TerminatorKind::Call {
+                func: Operand::function_handle(tcx, entry_fn, List::empty(), span),
+                args: vec![],
+                destination: Some((Place::from(unit_temp), former_entry)),
+                cleanup: None,
+                from_hir_call: false,
+                fn_span: span,
+            },
+        });
+        PassOutcome::Changed
+    }
+}
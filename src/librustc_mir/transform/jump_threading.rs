@@ -0,0 +1,194 @@
+//! Jump threading.
+//!
+//! After inlining, code like `let x = cond; if x { ... }` leaves blocks that branch on a local
+//! whose value the predecessor already knows, because it either assigned a constant to it or
+//! branched on it itself:
+//!
+//!     bb0: { _2 = const true; goto -> bb1; }
+//!     bb1: { StorageDead(_3); switchInt(_2) -> [false: bb3, otherwise: bb2]; }
+//!
+//! For every such edge, the pass makes the predecessor jump to a copy of the switching block that
+//! goes straight to the known target instead:
+//!
+//!     bb0: { _2 = const true; goto -> bb4; }
+//!     bb4: { StorageDead(_3); goto -> bb2; }
+//!
+//! Blocks without statements are skipped entirely, and only small blocks are copied. The local
+//! switched on must not be borrowed anywhere, so that it can only change through assignments
+//! that are visible in the blocks involved. The switching block may become unreachable, which
+//! the later `SimplifyCfg` runs take care of.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::liveness::ever_borrowed_locals;
+use rustc_middle::mir::visit::{PlaceContext, Visitor};
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, Ty, TyCtxt};
+
+/// The largest number of statements a block can have to be copied for a threaded edge.
+const MAX_COPIED_STATEMENTS: usize = 8;
+
+pub struct JumpThreading;
+
+impl<'tcx> MirPass<'tcx> for JumpThreading {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let param_env = tcx.param_env(source.def_id());
+        let threads = find_threads(tcx, param_env, body);
+        if threads.is_empty() {
+            return PassOutcome::Unchanged;
+        }
+
+        for thread in threads {
+            debug!("threading {:?} -> {:?} to {:?}", thread.pred, thread.block, thread.target);
+            let data = &body.basic_blocks()[thread.block];
+            let target = if data.statements.iter().all(|s| s.kind == StatementKind::Nop) {
+                thread.target
+            } else {
+                let copy = BasicBlockData {
+                    statements: data.statements.clone(),
+                    terminator: Some(Terminator {
+                        source_info: data.terminator().source_info,
+                        kind: TerminatorKind::Goto { target: thread.target },
+                    }),
+                    is_cleanup: data.is_cleanup,
+                };
+                body.basic_blocks_mut().push(copy)
+            };
+            for successor in body[thread.pred].terminator_mut().successors_mut() {
+                if *successor == thread.block {
+                    *successor = target;
+                }
+            }
+        }
+        PassOutcome::Changed
+    }
+}
+
+/// An edge from `pred` to `block` on which the `SwitchInt` of `block` always goes to `target`.
+struct Thread {
+    pred: BasicBlock,
+    block: BasicBlock,
+    target: BasicBlock,
+}
+
+fn find_threads<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    body: &Body<'tcx>,
+) -> Vec<Thread> {
+    let borrowed = ever_borrowed_locals(body);
+
+    let predecessors = body.predecessors();
+    let mut threads = Vec::new();
+    for (bb, data) in body.basic_blocks().iter_enumerated() {
+        let (local, switch_ty, values, targets) = match data.terminator().kind {
+            TerminatorKind::SwitchInt {
+                discr: Operand::Copy(place) | Operand::Move(place),
+                switch_ty,
+                ref values,
+                ref targets,
+            } => match place.as_local() {
+                Some(local) => (local, switch_ty, values, targets),
+                None => continue,
+            },
+            _ => continue,
+        };
+        if borrowed.contains(local)
+            || data.statements.len() > MAX_COPIED_STATEMENTS
+            || data.statements.iter().any(|statement| writes_to(statement, local))
+        {
+            continue;
+        }
+
+        let mut preds = predecessors[bb].clone();
+        preds.sort();
+        preds.dedup();
+        for pred in preds {
+            if pred == bb {
+                continue;
+            }
+            let value = match known_value(tcx, param_env, body, pred, bb, local, switch_ty) {
+                Some(value) => value,
+                None => continue,
+            };
+            let target = values
+                .iter()
+                .position(|&v| v == value)
+                .map_or_else(|| *targets.last().unwrap(), |i| targets[i]);
+            threads.push(Thread { pred, block: bb, target });
+        }
+    }
+    threads
+}
+
+/// Returns the value `local` has when `pred` jumps to `block`, if it is known.
+fn known_value<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    body: &Body<'tcx>,
+    pred: BasicBlock,
+    block: BasicBlock,
+    local: Local,
+    switch_ty: Ty<'tcx>,
+) -> Option<u128> {
+    let data = &body[pred];
+    match data.terminator().kind {
+        TerminatorKind::Goto { .. } => {
+            // The last assignment of `local` in `pred` has to be a constant.
+            let statement =
+                data.statements.iter().rev().find(|statement| writes_to(statement, local))?;
+            match statement.kind {
+                StatementKind::Assign(box (place, Rvalue::Use(Operand::Constant(ref c))))
+                    if place.as_local() == Some(local) =>
+                {
+                    c.literal.try_eval_bits(tcx, param_env, switch_ty)
+                }
+                _ => None,
+            }
+        }
+        TerminatorKind::SwitchInt {
+            discr: Operand::Copy(place) | Operand::Move(place),
+            switch_ty: pred_switch_ty,
+            ref values,
+            ..
+        } if place.as_local() == Some(local) && pred_switch_ty == switch_ty => {
+            match body.switch_sources().get(&(block, pred))?[..] {
+                [Some(value)] => Some(value),
+                // On the otherwise edge of a switch on a `bool` that tests one value, the local
+                // holds the other one.
+                [None] if switch_ty.is_bool() && values.len() == 1 => Some(1 - values[0]),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Whether `statement` may change the value of `local`, assuming it is not borrowed.
+pub(crate) fn writes_to(statement: &Statement<'_>, local: Local) -> bool {
+    let mut finder = WriteFinder { local, found: false };
+    finder.visit_statement(statement, Location::START);
+    finder.found
+}
+
+struct WriteFinder {
+    local: Local,
+    found: bool,
+}
+
+impl<'tcx> Visitor<'tcx> for WriteFinder {
+    fn visit_place(&mut self, place: &Place<'tcx>, context: PlaceContext, location: Location) {
+        if place.local == self.local && context.is_mutating_use() && !place.is_indirect() {
+            self.found = true;
+        }
+        self.super_place(place, context, location);
+    }
+}
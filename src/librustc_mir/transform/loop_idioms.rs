@@ -0,0 +1,670 @@
+//! Replaces loops that fill or copy slices element by element with calls to `write_bytes` and
+//! `copy_nonoverlapping`.
+//!
+//! A loop over a counter that starts at zero, stores into the element it indexes, and steps by
+//! one until it reaches a bound, writes a prefix of the slice:
+//!
+//!     bb0: { _3 = const 0_usize; goto -> bb1; }
+//!     bb1: { _4 = Lt(_3, _2); switchInt(move _4) -> [false: bb4, otherwise: bb2]; }
+//!     bb2: { _5 = Len((*_1)); _6 = Lt(_3, _5); assert(move _6, ...) -> bb3; }
+//!     bb3: { (*_1)[_3] = const 0_u32; _3 = Add(_3, const 1_usize); goto -> bb1; }
+//!
+//! When the stored value is a constant whose bytes are all the same, or the element with the same
+//! index of another slice, the loop is `write_bytes` or `copy_nonoverlapping` of `_2` elements,
+//! as long as the bound is within the slices. The pass checks that before the loop, and calls the
+//! intrinsic if it is, or runs the loop as before, which panics at the same element, if it isn't:
+//!
+//!     bb0: { _3 = const 0_usize; goto -> bb5; }
+//!     bb5: { _8 = Len((*_1)); _7 = Le(_2, move _8); switchInt(move _7) -> [0: bb1, ...: bb6]; }
+//!     bb6: { _9 = &raw mut (*_1); _10 = move _9 as *mut u32 (Misc); ... }
+//!     bb7: { _11 = write_bytes::<u32>(move _10, const 0_u8, _2) -> bb8; }
+//!     bb8: { _3 = _2; goto -> bb4; }
+//!
+//! Besides the store and the step of the counter, the loop may only compute temporaries that
+//! aren't used after it, and check them in assertions. The comparison, the index and the bounds
+//! check may read copies of the counter made earlier in the iteration, which is how MIR building
+//! reads it. The slices are the targets of references, or arrays that aren't borrowed, and the
+//! one copied to is a different place than the one copied from, so that they can't overlap.
+
+use crate::transform::unroll_loops::starting_value;
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::liveness::ever_borrowed_locals;
+use crate::util::loops::{natural_loops, NaturalLoop};
+use rustc_hir::def_id::DefId;
+use rustc_index::bit_set::BitSet;
+use rustc_middle::mir::interpret::Scalar;
+use rustc_middle::mir::visit::{PlaceContext, Visitor};
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, Ty, TyCtxt};
+
+pub struct LoopIdioms;
+
+impl<'tcx> MirPass<'tcx> for LoopIdioms {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        // CTFE doesn't implement the intrinsics.
+        let def_id = source.def_id();
+        if source.promoted.is_some()
+            || def_id
+                .as_local()
+                .map_or(true, |def_id| tcx.hir().body_const_context(def_id).is_some())
+        {
+            return PassOutcome::Unchanged;
+        }
+        let lang_items = tcx.lang_items();
+        let intrinsics = match (lang_items.write_bytes_fn(), lang_items.copy_nonoverlapping_fn()) {
+            (Some(write_bytes_fn), Some(copy_fn)) => Intrinsics { write_bytes_fn, copy_fn },
+            _ => return PassOutcome::Unchanged,
+        };
+
+        let finder = IdiomFinder {
+            tcx,
+            param_env: tcx.param_env(def_id),
+            body,
+            borrowed: ever_borrowed_locals(body),
+        };
+        // Every idiom only adds blocks, and redirects the edge into its loop, which is in a block
+        // outside of all the others.
+        let idioms: Vec<Idiom<'tcx>> = natural_loops(body)
+            .iter()
+            .filter_map(|natural_loop| finder.find(natural_loop))
+            .collect();
+        for idiom in &idioms {
+            debug!("replacing the loop of {:?} with {:?}", idiom.header, idiom.kind);
+            replace_loop(tcx, body, &intrinsics, idiom);
+        }
+        PassOutcome::changed_if(!idioms.is_empty())
+    }
+}
+
+struct Intrinsics {
+    write_bytes_fn: DefId,
+    copy_fn: DefId,
+}
+
+#[derive(Debug)]
+enum IdiomKind<'tcx> {
+    /// Every element is set to a value made of this byte.
+    Fill(u8),
+    /// Every element is copied from the element with the same index in this array or slice.
+    Copy(Place<'tcx>),
+}
+
+#[derive(Debug)]
+struct Idiom<'tcx> {
+    header: BasicBlock,
+    preheader: BasicBlock,
+    exit: BasicBlock,
+    counter: Local,
+    /// The number of iterations of the loop.
+    bound: Operand<'tcx>,
+    /// The array or slice stored into.
+    dst: Place<'tcx>,
+    element_ty: Ty<'tcx>,
+    kind: IdiomKind<'tcx>,
+}
+
+struct IdiomFinder<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    body: &'a Body<'tcx>,
+    borrowed: BitSet<Local>,
+}
+
+impl<'tcx> IdiomFinder<'_, 'tcx> {
+    fn find(&self, natural_loop: &NaturalLoop) -> Option<Idiom<'tcx>> {
+        let body = self.body;
+        let NaturalLoop { header, ref blocks } = *natural_loop;
+        if header == START_BLOCK || blocks.iter().any(|bb| body[bb].is_cleanup) {
+            return None;
+        }
+        let predecessors = body.predecessors();
+        let mut outside = predecessors[header].iter().filter(|&&pred| !blocks.contains(pred));
+        let preheader = *outside.next()?;
+        if outside.next().is_some() {
+            return None;
+        }
+
+        // The header compares the counter with the bound, and nothing else.
+        let (condition, values, targets) = match body[header].terminator().kind {
+            TerminatorKind::SwitchInt {
+                discr: Operand::Copy(place) | Operand::Move(place),
+                switch_ty,
+                ref values,
+                ref targets,
+            } if switch_ty.is_bool() => (place.as_local()?, values, targets),
+            _ => return None,
+        };
+        let target_for = |value| {
+            let index = values.iter().position(|&v| v == value);
+            index.map_or_else(|| *targets.last().unwrap(), |i| targets[i])
+        };
+        let (body_entry, exit) = (target_for(1), target_for(0));
+        if !blocks.contains(body_entry) || blocks.contains(exit) {
+            return None;
+        }
+        // MIR building compares copies of the counter and the bound, which it makes right before.
+        let mut copies = vec![];
+        let mut comparison = None;
+        for statement in &body[header].statements {
+            match statement.kind {
+                StatementKind::StorageLive(_)
+                | StatementKind::StorageDead(_)
+                | StatementKind::Nop => {}
+                StatementKind::Assign(box (place, Rvalue::Use(Operand::Copy(copied))))
+                    if comparison.is_none() =>
+                {
+                    copies.push((place.as_local()?, copied.as_local()?))
+                }
+                StatementKind::Assign(box (
+                    place,
+                    Rvalue::BinaryOp(BinOp::Lt, ref lhs, ref rhs),
+                )) if place.as_local() == Some(condition) && comparison.is_none() => {
+                    comparison = Some((lhs, rhs))
+                }
+                _ => return None,
+            }
+        }
+        let through_copies = |operand: &Operand<'tcx>| match *operand {
+            Operand::Copy(place) | Operand::Move(place) => {
+                let local = place.as_local()?;
+                match copies.iter().rev().find(|&&(temp, _)| temp == local) {
+                    Some(&(_, copied)) => Some(Operand::Copy(copied.into())),
+                    None => Some(operand.clone()),
+                }
+            }
+            Operand::Constant(_) => Some(operand.clone()),
+        };
+        let (lhs, rhs) = comparison?;
+        let (counter, bound) = match (through_copies(lhs)?, through_copies(rhs)?) {
+            (Operand::Copy(counter), bound) => (counter.as_local()?, bound),
+            _ => return None,
+        };
+        let bound_local = match bound {
+            Operand::Copy(place) => Some(place.as_local()?),
+            Operand::Constant(_) => None,
+            Operand::Move(_) => return None,
+        };
+        if body.local_decls[counter].ty != self.tcx.types.usize
+            || self.borrowed.contains(counter)
+            || self.borrowed.contains(condition)
+            || bound_local.map_or(false, |bound| bound == counter || self.borrowed.contains(bound))
+            || copies.iter().any(|&(temp, _)| {
+                temp == counter || Some(temp) == bound_local || self.borrowed.contains(temp)
+            })
+        {
+            return None;
+        }
+
+        // The other blocks run one after the other, in every iteration.
+        let mut chain = vec![];
+        let mut bb = body_entry;
+        while bb != header {
+            if !blocks.contains(bb) || chain.contains(&bb) {
+                return None;
+            }
+            chain.push(bb);
+            bb = match body[bb].terminator().kind {
+                TerminatorKind::Goto { target } | TerminatorKind::Assert { target, .. } => target,
+                _ => return None,
+            };
+        }
+        if chain.len() + 1 != blocks.count() {
+            return None;
+        }
+
+        // Their assertions may only check the index of the arrays and the step of the counter,
+        // which can't fail when the bound is within the arrays.
+        let mut iteration = Iteration::new(self, counter, condition, bound_local);
+        for &(temp, _) in &copies {
+            iteration.temps.insert(temp);
+        }
+        let mut checked_lens = vec![];
+        for &bb in &chain {
+            for statement in &body[bb].statements {
+                iteration.visit_statement(statement)?;
+            }
+            if let TerminatorKind::Assert { ref msg, .. } = body[bb].terminator().kind {
+                match **msg {
+                    AssertKind::BoundsCheck { ref len, ref index }
+                        if iteration.reads_counter(index) =>
+                    {
+                        checked_lens.push(len)
+                    }
+                    AssertKind::Overflow(BinOp::Add, ref lhs, _)
+                        if iteration.reads_counter(lhs) => {}
+                    _ => return None,
+                }
+            }
+        }
+        let (store_value, dst) = match iteration.store {
+            Some(store) if iteration.stepped => store,
+            _ => return None,
+        };
+
+        // The temporaries of the loop may not be read after it.
+        let mut uses = UsedLocals { used: BitSet::new_empty(body.local_decls.len()) };
+        for (bb, data) in body.basic_blocks().iter_enumerated() {
+            if !blocks.contains(bb) {
+                uses.visit_basic_block_data(bb, data);
+            }
+        }
+        if iteration.temps.iter().any(|temp| uses.used.contains(temp)) {
+            return None;
+        }
+        if starting_value(self.tcx, self.param_env, body, preheader, counter)? != 0 {
+            return None;
+        }
+
+        let dst_local = self.array_local(dst, true)?;
+        let element_ty = match dst.ty(body, self.tcx).ty.kind {
+            ty::Slice(element_ty) | ty::Array(element_ty, _) => element_ty,
+            _ => return None,
+        };
+        if iteration.temps.contains(dst_local) {
+            return None;
+        }
+        let kind = match store_value {
+            StoreValue::Constant(constant) => {
+                IdiomKind::Fill(self.fill_byte(element_ty, constant)?)
+            }
+            StoreValue::Element(src) => {
+                let src_local = self.array_local(src, false)?;
+                if src_local == dst_local
+                    || iteration.temps.contains(src_local)
+                    || src.ty(body, self.tcx).ty.builtin_index() != Some(element_ty)
+                {
+                    return None;
+                }
+                IdiomKind::Copy(src)
+            }
+        };
+        let arrays = match kind {
+            IdiomKind::Fill(_) => vec![dst],
+            IdiomKind::Copy(src) => vec![dst, src],
+        };
+        if !iteration.loads.iter().all(|(_, array)| arrays.contains(array) && *array != dst) {
+            return None;
+        }
+        let checks_array = |len: &Operand<'tcx>| match *len {
+            Operand::Copy(place) | Operand::Move(place) => iteration
+                .lens
+                .iter()
+                .any(|&(temp, array)| place.as_local() == Some(temp) && arrays.contains(&array)),
+            Operand::Constant(ref len) => {
+                arrays.iter().any(|array| match array.ty(body, self.tcx).ty.kind {
+                    ty::Array(_, array_len) => array_len == len.literal,
+                    _ => false,
+                })
+            }
+        };
+        if !checked_lens.into_iter().all(checks_array) {
+            return None;
+        }
+        Some(Idiom {
+            header,
+            preheader,
+            exit,
+            counter,
+            bound,
+            dst,
+            element_ty,
+            kind,
+        })
+    }
+
+    /// Returns the local holding the array or slice `place`, if it is an array that isn't
+    /// borrowed, or the target of a reference, which has to be mutable for a `store`.
+    fn array_local(&self, place: Place<'tcx>, store: bool) -> Option<Local> {
+        let ty = self.body.local_decls[place.local].ty;
+        match (&place.projection[..], &ty.kind) {
+            ([], ty::Array(..)) if !self.borrowed.contains(place.local) => Some(place.local),
+            ([ProjectionElem::Deref], &ty::Ref(_, pointee, mutability))
+                if matches!(pointee.kind, ty::Slice(_) | ty::Array(..))
+                    && (!store || mutability == Mutability::Mut) =>
+            {
+                Some(place.local)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the byte that every byte of `constant` is, if it is an integer or a `bool`.
+    fn fill_byte(&self, element_ty: Ty<'tcx>, constant: &Constant<'tcx>) -> Option<u8> {
+        if !matches!(element_ty.kind, ty::Int(_) | ty::Uint(_) | ty::Bool) {
+            return None;
+        }
+        let bits = constant.literal.try_eval_bits(self.tcx, self.param_env, element_ty)?;
+        let size = self.tcx.layout_of(self.param_env.and(element_ty)).ok()?.size;
+        let byte = bits as u8;
+        if (0..size.bytes()).all(|i| (bits >> (8 * i)) as u8 == byte) { Some(byte) } else { None }
+    }
+}
+
+enum StoreValue<'a, 'tcx> {
+    Constant(&'a Constant<'tcx>),
+    /// The element with the counter as its index in this array or slice.
+    Element(Place<'tcx>),
+}
+
+/// What the statements of an iteration do, besides computing temporaries.
+struct Iteration<'a, 'b, 'tcx> {
+    finder: &'b IdiomFinder<'a, 'tcx>,
+    counter: Local,
+    bound: Option<Local>,
+    /// The locals written by the iteration, besides the counter.
+    temps: BitSet<Local>,
+    /// The temporaries holding elements loaded with the counter as their index.
+    loads: Vec<(Local, Place<'tcx>)>,
+    /// The temporaries holding the length of an array.
+    lens: Vec<(Local, Place<'tcx>)>,
+    /// The temporaries holding a copy of the counter, which MIR building indexes with.
+    counter_copies: Vec<Local>,
+    /// The temporary holding the checked step of the counter.
+    checked_step: Option<Local>,
+    store: Option<(StoreValue<'b, 'tcx>, Place<'tcx>)>,
+    stepped: bool,
+}
+
+impl<'a, 'b, 'tcx> Iteration<'a, 'b, 'tcx> {
+    fn new(
+        finder: &'b IdiomFinder<'a, 'tcx>,
+        counter: Local,
+        condition: Local,
+        bound: Option<Local>,
+    ) -> Self {
+        let mut temps = BitSet::new_empty(finder.body.local_decls.len());
+        temps.insert(condition);
+        Iteration {
+            finder,
+            counter,
+            bound,
+            temps,
+            loads: vec![],
+            lens: vec![],
+            counter_copies: vec![],
+            checked_step: None,
+            store: None,
+            stepped: false,
+        }
+    }
+
+    /// Whether `local` is the counter, or a copy of it.
+    fn holds_counter(&self, local: Local) -> bool {
+        local == self.counter || self.counter_copies.contains(&local)
+    }
+
+    fn reads_counter(&self, operand: &Operand<'tcx>) -> bool {
+        match *operand {
+            Operand::Copy(place) => place.as_local().map_or(false, |l| self.holds_counter(l)),
+            _ => false,
+        }
+    }
+
+    /// Returns the array or slice `place` is the element of, if its index is the counter.
+    fn indexed_array(&self, place: Place<'tcx>) -> Option<Place<'tcx>> {
+        match place.projection.split_last() {
+            Some((&ProjectionElem::Index(index), base)) if self.holds_counter(index) => {
+                let projection = self.finder.tcx.intern_place_elems(base);
+                Some(Place { local: place.local, projection })
+            }
+            _ => None,
+        }
+    }
+
+    fn is_step_of_counter(&self, op: BinOp, lhs: &Operand<'tcx>, rhs: &Operand<'tcx>) -> bool {
+        let tcx = self.finder.tcx;
+        let one = |operand: &Operand<'tcx>| match operand {
+            Operand::Constant(constant) => {
+                constant.literal.try_eval_usize(tcx, self.finder.param_env) == Some(1)
+            }
+            _ => false,
+        };
+        op == BinOp::Add && *lhs == Operand::Copy(self.counter.into()) && one(rhs)
+    }
+
+    fn visit_statement(&mut self, statement: &'b Statement<'tcx>) -> Option<()> {
+        let (place, rvalue) = match statement.kind {
+            StatementKind::StorageLive(local) | StatementKind::StorageDead(local) => {
+                return if local != self.counter && Some(local) != self.bound {
+                    Some(())
+                } else {
+                    None
+                };
+            }
+            StatementKind::Nop => return Some(()),
+            StatementKind::Assign(box (place, ref rvalue)) => (place, rvalue),
+            _ => return None,
+        };
+        // Nothing may use the counter as an index once it moved on.
+        if self.stepped && mentions_index(rvalue, self.counter) {
+            return None;
+        }
+
+        if let Some(dst) = self.indexed_array(place) {
+            if self.store.is_some() || self.stepped {
+                return None;
+            }
+            let value = match *rvalue {
+                Rvalue::Use(Operand::Constant(ref constant)) => StoreValue::Constant(constant),
+                Rvalue::Use(Operand::Copy(loaded)) if self.indexed_array(loaded).is_some() => {
+                    StoreValue::Element(self.indexed_array(loaded)?)
+                }
+                Rvalue::Use(Operand::Copy(loaded) | Operand::Move(loaded)) => {
+                    let local = loaded.as_local()?;
+                    let &(_, src) = self.loads.iter().find(|&&(temp, _)| temp == local)?;
+                    StoreValue::Element(src)
+                }
+                _ => return None,
+            };
+            self.store = Some((value, dst));
+            return Some(());
+        }
+
+        let local = place.as_local()?;
+        if local == self.counter {
+            if self.stepped {
+                return None;
+            }
+            self.stepped = match *rvalue {
+                Rvalue::BinaryOp(op, ref lhs, ref rhs) => self.is_step_of_counter(op, lhs, rhs),
+                Rvalue::Use(Operand::Copy(result) | Operand::Move(result)) => {
+                    matches!(
+                        result.projection[..],
+                        [ProjectionElem::Field(field, _)] if field.index() == 0
+                    ) && Some(result.local) == self.checked_step
+                }
+                _ => false,
+            };
+            return if self.stepped { Some(()) } else { None };
+        }
+        if Some(local) == self.bound || self.finder.borrowed.contains(local) {
+            return None;
+        }
+        self.temps.insert(local);
+        self.counter_copies.retain(|&copy| copy != local);
+
+        match *rvalue {
+            Rvalue::Use(Operand::Copy(loaded)) if self.indexed_array(loaded).is_some() => {
+                let src = self.indexed_array(loaded)?;
+                self.loads.push((local, src));
+            }
+            Rvalue::Use(Operand::Copy(copied))
+                if copied.as_local() == Some(self.counter) && !self.stepped =>
+            {
+                self.counter_copies.push(local);
+            }
+            Rvalue::CheckedBinaryOp(op, ref lhs, ref rhs)
+                if self.is_step_of_counter(op, lhs, rhs) =>
+            {
+                self.checked_step = Some(local);
+            }
+            Rvalue::Len(array) => self.lens.push((local, array)),
+            Rvalue::Use(ref operand) | Rvalue::UnaryOp(_, ref operand)
+                if reads_locals_only(operand) => {}
+            Rvalue::BinaryOp(_, ref lhs, ref rhs)
+            | Rvalue::CheckedBinaryOp(_, ref lhs, ref rhs)
+                if reads_locals_only(lhs) && reads_locals_only(rhs) => {}
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+/// Whether `operand` is a constant, or reads a local or a field of one, rather than memory.
+fn reads_locals_only(operand: &Operand<'_>) -> bool {
+    match operand {
+        Operand::Copy(place) | Operand::Move(place) => {
+            place.projection.iter().all(|elem| matches!(elem, ProjectionElem::Field(..)))
+        }
+        Operand::Constant(_) => true,
+    }
+}
+
+fn mentions_index(rvalue: &Rvalue<'_>, counter: Local) -> bool {
+    let indexes = |operand: &Operand<'_>| {
+        operand.place().map_or(false, |place| {
+            place.projection.iter().any(|elem| elem == ProjectionElem::Index(counter))
+        })
+    };
+    match rvalue {
+        Rvalue::Use(operand) | Rvalue::UnaryOp(_, operand) => indexes(operand),
+        Rvalue::BinaryOp(_, lhs, rhs) | Rvalue::CheckedBinaryOp(_, lhs, rhs) => {
+            indexes(lhs) || indexes(rhs)
+        }
+        _ => false,
+    }
+}
+
+/// Collects the locals that are used, besides their storage markers.
+struct UsedLocals {
+    used: BitSet<Local>,
+}
+
+impl<'tcx> Visitor<'tcx> for UsedLocals {
+    fn visit_local(&mut self, &local: &Local, context: PlaceContext, _: Location) {
+        if !matches!(context, PlaceContext::NonUse(_)) {
+            self.used.insert(local);
+        }
+    }
+}
+
+/// Makes the edge from the preheader into the loop check whether the bound is within the arrays
+/// and call the intrinsic if it is.
+fn replace_loop<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    body: &mut Body<'tcx>,
+    intrinsics: &Intrinsics,
+    idiom: &Idiom<'tcx>,
+) {
+    let source_info = body[idiom.header].terminator().source_info;
+    let span = source_info.span;
+    let array_ty = |array: Place<'tcx>| array.ty(&*body, tcx).ty;
+    let dst_ty = array_ty(idiom.dst);
+    let src_ty = match idiom.kind {
+        IdiomKind::Copy(src) => Some(array_ty(src)),
+        IdiomKind::Fill(_) => None,
+    };
+    let local_decls = &mut body.local_decls;
+    let mut temp = |ty| Place::from(local_decls.push(LocalDecl::new(ty, span).internal()));
+    let assign =
+        |place, rvalue| Statement { source_info, kind: StatementKind::Assign(box (place, rvalue)) };
+    let bound = idiom.bound.clone();
+
+    // Whether the bound is at most the length of every array.
+    let mut arrays = vec![idiom.dst];
+    if let IdiomKind::Copy(src) = idiom.kind {
+        arrays.push(src);
+    }
+    let mut check = vec![];
+    let mut in_bounds: Option<Place<'tcx>> = None;
+    for &array in &arrays {
+        let len = temp(tcx.types.usize);
+        let within = temp(tcx.types.bool);
+        check.push(assign(len, Rvalue::Len(array)));
+        check.push(assign(within, Rvalue::BinaryOp(BinOp::Le, bound.clone(), Operand::Move(len))));
+        in_bounds = Some(match in_bounds {
+            Some(previous) => {
+                let both = temp(tcx.types.bool);
+                let rvalue =
+                    Rvalue::BinaryOp(BinOp::BitAnd, Operand::Move(previous), Operand::Move(within));
+                check.push(assign(both, rvalue));
+                both
+            }
+            None => within,
+        });
+    }
+
+    // Pointers to the first elements of the arrays.
+    let element_ty = idiom.element_ty;
+    let mut call_statements = vec![];
+    let mut pointer = |array, array_ty, mutbl| {
+        let array_pointer = temp(tcx.mk_ptr(ty::TypeAndMut { ty: array_ty, mutbl }));
+        let element_pointer_ty = tcx.mk_ptr(ty::TypeAndMut { ty: element_ty, mutbl });
+        let element_pointer = temp(element_pointer_ty);
+        call_statements.push(assign(array_pointer, Rvalue::AddressOf(mutbl, array)));
+        let cast = Rvalue::Cast(CastKind::Misc, Operand::Move(array_pointer), element_pointer_ty);
+        call_statements.push(assign(element_pointer, cast));
+        Operand::Move(element_pointer)
+    };
+    let dst = pointer(idiom.dst, dst_ty, Mutability::Mut);
+    let (func, args) = match (&idiom.kind, src_ty) {
+        (&IdiomKind::Fill(byte), _) => {
+            let byte = Operand::const_from_scalar(tcx, tcx.types.u8, Scalar::from_u8(byte), span);
+            (intrinsics.write_bytes_fn, vec![dst, byte, bound.clone()])
+        }
+        (&IdiomKind::Copy(src), Some(src_ty)) => {
+            let src = pointer(src, src_ty, Mutability::Not);
+            (intrinsics.copy_fn, vec![src, dst, bound.clone()])
+        }
+        (IdiomKind::Copy(_), None) => unreachable!(),
+    };
+    let unit = temp(tcx.mk_unit());
+
+    let basic_blocks = body.basic_blocks_mut();
+    let done = basic_blocks.push(BasicBlockData {
+        statements: vec![assign(Place::from(idiom.counter), Rvalue::Use(bound))],
+        terminator: Some(Terminator {
+            source_info,
+            kind: TerminatorKind::Goto { target: idiom.exit },
+        }),
+        is_cleanup: false,
+    });
+    let substs = tcx.intern_substs(&[element_ty.into()]);
+    let call = basic_blocks.push(BasicBlockData {
+        statements: call_statements,
+        terminator: Some(Terminator {
+            source_info,
+            kind: TerminatorKind::Call {
+                func: Operand::function_handle(tcx, func, substs, span),
+                args,
+                destination: Some((unit, done)),
+                cleanup: None,
+                from_hir_call: false,
+                fn_span: span,
+            },
+        }),
+        is_cleanup: false,
+    });
+    let checked = basic_blocks.push(BasicBlockData {
+        statements: check,
+        terminator: Some(Terminator {
+            source_info,
+            kind: TerminatorKind::if_(tcx, Operand::Move(in_bounds.unwrap()), call, idiom.header),
+        }),
+        is_cleanup: false,
+    });
+    for target in basic_blocks[idiom.preheader].terminator_mut().successors_mut() {
+        if *target == idiom.header {
+            *target = checked;
+        }
+    }
+}
@@ -0,0 +1,355 @@
+//! Loop-invariant code motion.
+//!
+//! Computations inside a loop whose operands don't change while the loop runs produce the same
+//! value in every iteration:
+//!
+//!     bb1: {
+//!         _4 = Len((*_1));
+//!         _5 = Lt(_3, _4);
+//!         switchInt(move _5) -> [false: bb3, otherwise: bb2];
+//!     }
+//!     bb2: { ...; goto -> bb1; }
+//!
+//! The pass moves them into a preheader, a block that ends in a `goto` to the loop header and is
+//! the only way into the loop, and creates one if there is none yet:
+//!
+//!     bb0: { ...; _4 = Len((*_1)); goto -> bb1; }
+//!     bb1: { _5 = Lt(_3, _4); switchInt(move _5) -> [false: bb3, otherwise: bb2]; }
+//!
+//! An assignment is moved if the loop doesn't otherwise write to its local, the local isn't live
+//! on entry to the loop or after it, and the rvalue only reads locals that the loop doesn't write
+//! or end the storage of. As the moved assignment may run when it wouldn't have run before, the
+//! rvalue must not be able to cause undefined behavior, which rules out divisions, shifts and
+//! pointer offsets. Reads through references are only moved if the reference is an argument
+//! pointing to memory without interior mutability, or if they only read the length of a slice.
+//! Indexing and reading the fields of an enum variant are never moved: they are only valid after
+//! the bounds check or the discriminant test that guards them, which stays in the loop, and the
+//! loop may not run at all. The storage markers of the moved locals are removed, since their
+//! values now outlive every iteration. Inner loops are handled first, so that their invariants can
+//! move further out.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::liveness::{ever_borrowed_locals, live_locals_on_entry};
+use crate::util::loops::natural_loops;
+use rustc_index::bit_set::BitSet;
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::visit::{NonMutatingUseContext, NonUseContext, PlaceContext, Visitor};
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, TyCtxt};
+
+pub struct LoopInvariantCodeMotion;
+
+impl<'tcx> MirPass<'tcx> for LoopInvariantCodeMotion {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let def_id = source.def_id();
+        let param_env = tcx.param_env(def_id);
+        let loops = natural_loops(body);
+        if loops.is_empty() {
+            return PassOutcome::Unchanged;
+        }
+        let borrowed = ever_borrowed_locals(body);
+        let live_on_entry = live_locals_on_entry(tcx, body, def_id).clone();
+        let rpo: Vec<BasicBlock> = body.postorder().iter().rev().copied().collect();
+
+        // The preheaders this pass created, with the headers of their loops. They belong to every
+        // loop that contains the header.
+        let mut new_preheaders: Vec<(BasicBlock, BasicBlock)> = Vec::new();
+        let mut changed = false;
+        for natural_loop in &loops {
+            let header = natural_loop.header;
+            if header == START_BLOCK || body[header].is_cleanup {
+                continue;
+            }
+            let blocks: Vec<BasicBlock> = rpo
+                .iter()
+                .copied()
+                .filter(|&bb| natural_loop.blocks.contains(bb))
+                .flat_map(|bb| {
+                    let preheaders = new_preheaders.iter().filter(move |&&(_, h)| h == bb);
+                    preheaders.map(|&(preheader, _)| preheader).chain(Some(bb))
+                })
+                .collect();
+            let in_loop = |bb: BasicBlock| blocks.contains(&bb);
+
+            // The locals whose values the code after the loop, or the next iteration, may read.
+            let mut outside_live = live_on_entry[header].clone();
+            for &bb in &blocks {
+                for &succ in body[bb].terminator().successors() {
+                    if in_loop(succ) {
+                        continue;
+                    }
+                    match new_preheaders.iter().find(|&&(preheader, _)| preheader == succ) {
+                        Some(&(preheader, next_header)) => {
+                            outside_live.union(&live_on_entry[next_header]);
+                            let mut mentions = Mentions(&mut outside_live);
+                            mentions.visit_basic_block_data(preheader, &body[preheader]);
+                        }
+                        None => {
+                            outside_live.union(&live_on_entry[succ]);
+                        }
+                    }
+                }
+            }
+            let mut accesses = Accesses::new(body);
+            for &bb in &blocks {
+                accesses.visit_basic_block_data(bb, &body[bb]);
+            }
+
+            let mut hoisted = Vec::new();
+            loop {
+                let mut found = false;
+                for &bb in &blocks {
+                    for statement_index in 0..body[bb].statements.len() {
+                        let statement = &body[bb].statements[statement_index];
+                        let (local, rvalue) = match statement.kind {
+                            StatementKind::Assign(box (place, ref rvalue)) => {
+                                match place.as_local() {
+                                    Some(local) => (local, rvalue),
+                                    None => continue,
+                                }
+                            }
+                            _ => continue,
+                        };
+                        if !matches!(body.local_kind(local), LocalKind::Temp | LocalKind::Var)
+                            || borrowed.contains(local)
+                            || accesses.writes[local] != 1
+                            || outside_live.contains(local)
+                            || body.local_decls[local].ty.needs_drop(tcx, param_env)
+                            || !can_hoist(rvalue)
+                        {
+                            continue;
+                        }
+                        let reads = match invariant_reads(tcx, param_env, body, rvalue) {
+                            Some(reads) => reads,
+                            None => continue,
+                        };
+                        if reads.iter().any(|&read| {
+                            read == local
+                                || borrowed.contains(read)
+                                || accesses.writes[read] != 0
+                                || accesses.storage.contains(read)
+                        }) {
+                            continue;
+                        }
+
+                        debug!("hoisting {:?} out of the loop at {:?}", statement, header);
+                        hoisted.push(statement.clone());
+                        body.make_statement_nop(Location { block: bb, statement_index });
+                        accesses.writes[local] = 0;
+                        accesses.storage.remove(local);
+                        remove_storage_markers(body, local);
+                        found = true;
+                    }
+                }
+                if !found {
+                    break;
+                }
+            }
+            if hoisted.is_empty() {
+                continue;
+            }
+
+            let preheader = match find_preheader(body, header, &in_loop) {
+                Some(preheader) => preheader,
+                None => {
+                    let source_info = body[header].terminator().source_info;
+                    let preheader = body.basic_blocks_mut().push(BasicBlockData {
+                        statements: vec![],
+                        terminator: Some(Terminator {
+                            source_info,
+                            kind: TerminatorKind::Goto { target: header },
+                        }),
+                        is_cleanup: false,
+                    });
+                    for (bb, data) in body.basic_blocks_mut().iter_enumerated_mut() {
+                        if bb == preheader || in_loop(bb) {
+                            continue;
+                        }
+                        for successor in data.terminator_mut().successors_mut() {
+                            if *successor == header {
+                                *successor = preheader;
+                            }
+                        }
+                    }
+                    new_preheaders.push((preheader, header));
+                    preheader
+                }
+            };
+            body.basic_blocks_mut()[preheader].statements.extend(hoisted);
+            changed = true;
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
+
+/// Returns the only block outside of the loop that goes to `header`, if it can only go there.
+fn find_preheader(
+    body: &Body<'_>,
+    header: BasicBlock,
+    in_loop: &impl Fn(BasicBlock) -> bool,
+) -> Option<BasicBlock> {
+    let predecessors = body.predecessors();
+    let mut outside = predecessors[header].iter().copied().filter(|&pred| !in_loop(pred));
+    let preheader = outside.next()?;
+    let data = &body[preheader];
+    match data.terminator().kind {
+        TerminatorKind::Goto { .. } if outside.next().is_none() && !data.is_cleanup => {
+            Some(preheader)
+        }
+        _ => None,
+    }
+}
+
+/// Whether evaluating `rvalue` where it wasn't evaluated before can't cause undefined behavior.
+fn can_hoist(rvalue: &Rvalue<'_>) -> bool {
+    match rvalue {
+        Rvalue::Use(_)
+        | Rvalue::Repeat(..)
+        | Rvalue::Len(_)
+        | Rvalue::Cast(..)
+        | Rvalue::CheckedBinaryOp(..)
+        | Rvalue::UnaryOp(..)
+        | Rvalue::Aggregate(..) => true,
+
+        Rvalue::BinaryOp(op, ..) => {
+            !matches!(op, BinOp::Div | BinOp::Rem | BinOp::Shl | BinOp::Shr | BinOp::Offset)
+        }
+
+        // Reading the discriminant of an enum that isn't initialized yet is undefined behavior.
+        Rvalue::Discriminant(_)
+        | Rvalue::Ref(..)
+        | Rvalue::ThreadLocalRef(_)
+        | Rvalue::AddressOf(..)
+        | Rvalue::NullaryOp(..) => false,
+    }
+}
+
+/// Returns the locals `rvalue` reads, or `None` if it reads memory that may change.
+fn invariant_reads<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    body: &Body<'tcx>,
+    rvalue: &Rvalue<'tcx>,
+) -> Option<Vec<Local>> {
+    let mut collector = ReadCollector { tcx, param_env, body, reads: Vec::new(), invariant: true };
+    if let Rvalue::Len(place) = rvalue {
+        // The length of a slice behind a reference is part of the reference.
+        if let [ProjectionElem::Deref] = place.projection[..] {
+            return Some(vec![place.local]);
+        }
+    }
+    collector.visit_rvalue(rvalue, Location::START);
+    if collector.invariant { Some(collector.reads) } else { None }
+}
+
+struct ReadCollector<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    body: &'a Body<'tcx>,
+    reads: Vec<Local>,
+    invariant: bool,
+}
+
+impl<'tcx> Visitor<'tcx> for ReadCollector<'_, 'tcx> {
+    fn visit_place(&mut self, place: &Place<'tcx>, context: PlaceContext, location: Location) {
+        let guarded = place.projection.iter().any(|elem| {
+            matches!(
+                elem,
+                ProjectionElem::Index(_)
+                    | ProjectionElem::ConstantIndex { .. }
+                    | ProjectionElem::Subslice { .. }
+                    | ProjectionElem::Downcast(..)
+            )
+        });
+        if guarded {
+            self.invariant = false;
+        }
+        if place.is_indirect() {
+            // Arguments that are shared references to memory without interior mutability point
+            // to the same valid value during the whole call.
+            let base_ty = self.body.local_decls[place.local].ty;
+            let frozen = match base_ty.kind {
+                ty::Ref(_, pointee, Mutability::Not) => {
+                    pointee.is_freeze(self.tcx.at(self.body.span), self.param_env)
+                }
+                _ => false,
+            };
+            let single_deref = place.projection.first() == Some(&ProjectionElem::Deref)
+                && !place.projection[1..].iter().any(|elem| *elem == ProjectionElem::Deref);
+            if !frozen || !single_deref || self.body.local_kind(place.local) != LocalKind::Arg {
+                self.invariant = false;
+            }
+        }
+        self.super_place(place, context, location);
+    }
+
+    fn visit_local(&mut self, &local: &Local, _: PlaceContext, _: Location) {
+        if !self.reads.contains(&local) {
+            self.reads.push(local);
+        }
+    }
+}
+
+/// Counts the writes to every local in the blocks of a loop, and finds the locals whose storage
+/// starts or ends there. Moves count as writes, since the local can't be read afterwards.
+struct Accesses {
+    writes: IndexVec<Local, u32>,
+    storage: BitSet<Local>,
+}
+
+impl Accesses {
+    fn new(body: &Body<'_>) -> Accesses {
+        Accesses {
+            writes: IndexVec::from_elem(0, &body.local_decls),
+            storage: BitSet::new_empty(body.local_decls.len()),
+        }
+    }
+}
+
+impl<'tcx> Visitor<'tcx> for Accesses {
+    fn visit_local(&mut self, &local: &Local, context: PlaceContext, _: Location) {
+        match context {
+            PlaceContext::NonUse(NonUseContext::StorageLive | NonUseContext::StorageDead) => {
+                self.storage.insert(local);
+            }
+            PlaceContext::NonMutatingUse(NonMutatingUseContext::Move) => {
+                self.writes[local] += 1;
+            }
+            _ if context.is_mutating_use() => {
+                self.writes[local] += 1;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Adds every local that is mentioned to the set.
+struct Mentions<'a>(&'a mut BitSet<Local>);
+
+impl<'tcx> Visitor<'tcx> for Mentions<'_> {
+    fn visit_local(&mut self, &local: &Local, _: PlaceContext, _: Location) {
+        self.0.insert(local);
+    }
+}
+
+fn remove_storage_markers(body: &mut Body<'_>, local: Local) {
+    for data in body.basic_blocks_mut() {
+        for statement in &mut data.statements {
+            match statement.kind {
+                StatementKind::StorageLive(l) | StatementKind::StorageDead(l) if l == local => {
+                    statement.make_nop();
+                }
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,342 @@
+//! Replaces 128-bit operators with calls to the `i128_*`/`u128_*` lang items, for codegen
+//! backends that have no native support for 128-bit integers.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_ast::ast::{IntTy, UintTy};
+use rustc_hir as hir;
+use rustc_hir::def_id::DefId;
+use rustc_index::vec::Idx;
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, List, Ty, TyCtxt};
+use rustc_session::Session;
+use std::ops::RangeInclusive;
+
+pub struct Lower128Bit;
+
+impl<'tcx> MirPass<'tcx> for Lower128Bit {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        let debugging_override = sess.opts.debugging_opts.lower_128bit_ops;
+        debugging_override.unwrap_or(sess.target.target.options.i128_lowering)
+    }
+
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        // Only for codegen, after all optimizations have had the chance to fold the operations.
+        MirPhase::Optimized..=MirPhase::Optimized
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        // Constants, statics and promoteds are only ever evaluated by CTFE, which doesn't need
+        // the lowering. `const fn`s are lowered like other functions, since they are also
+        // codegened; CTFE evaluates the calls to the lang items in them by itself.
+        let def_id = source.def_id();
+        let ctfe_only = source.promoted.is_some()
+            || def_id.as_local().map_or(false, |def_id| {
+                matches!(
+                    tcx.hir().body_const_context(def_id),
+                    Some(hir::ConstContext::Const | hir::ConstContext::Static(_))
+                )
+            });
+        if ctfe_only {
+            return PassOutcome::Unchanged;
+        }
+
+        // The lang items don't use the operators we lower, but their bodies are skipped anyway, so
+        // that a use of one of them can't make a lang item call itself.
+        if lowered_op(tcx, def_id).is_some() {
+            return PassOutcome::Unchanged;
+        }
+
+        lower_128bit_ops(tcx, body)
+    }
+}
+
+fn lower_128bit_ops<'tcx>(tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) -> PassOutcome {
+    let mut new_blocks = Vec::new();
+    let cur_len = body.basic_blocks().len();
+
+    let (basic_blocks, local_decls) = body.basic_blocks_and_local_decls_mut();
+    for block in basic_blocks.iter_mut() {
+        // Walk backwards so that splitting off the tail of the block does not disturb the
+        // indices of the statements that remain to be visited.
+        for i in (0..block.statements.len()).rev() {
+            let (call_did, rhs_kind, result_kind) =
+                match lower_to(tcx, &block.statements[i], &*local_decls) {
+                    Some(lowering) => lowering,
+                    None => continue,
+                };
+
+            let bin_statement = block.statements.remove(i);
+            let source_info = bin_statement.source_info;
+            let (place, lhs, mut rhs) = match bin_statement.kind {
+                StatementKind::Assign(box (place, rvalue)) => match rvalue {
+                    Rvalue::BinaryOp(_, lhs, rhs) | Rvalue::CheckedBinaryOp(_, lhs, rhs) => {
+                        (place, lhs, rhs)
+                    }
+                    _ => bug!("lower_to accepted a non-binary rvalue"),
+                },
+                _ => bug!("lower_to accepted a non-assignment statement"),
+            };
+
+            let mut new_temp =
+                |ty: Ty<'tcx>| local_decls.push(LocalDecl::new(ty, source_info.span).internal());
+            let cast_local = rhs_kind.ty(tcx).map(|ty| (new_temp(ty), ty));
+            let cmp_local = match result_kind {
+                ResultKind::Direct => None,
+                ResultKind::Compare(_) => Some(new_temp(tcx.types.i8)),
+            };
+
+            // Everything after the operator moves into a new block that the call returns to.
+            let mut after_call = BasicBlockData {
+                statements: block.statements.split_off(i),
+                is_cleanup: block.is_cleanup,
+                terminator: block.terminator.take(),
+            };
+            let mut prefix = Vec::new();
+            if let (Some(cmp_local), ResultKind::Compare(op)) = (cmp_local, result_kind) {
+                let zero = Operand::Constant(box Constant {
+                    span: source_info.span,
+                    user_ty: None,
+                    literal: ty::Const::from_bits(tcx, 0, ty::ParamEnv::empty().and(tcx.types.i8)),
+                });
+                prefix.push(Statement {
+                    source_info,
+                    kind: StatementKind::Assign(box (
+                        place,
+                        Rvalue::BinaryOp(op, Operand::Move(Place::from(cmp_local)), zero),
+                    )),
+                });
+                prefix.push(Statement { source_info, kind: StatementKind::StorageDead(cmp_local) });
+            }
+            if let Some((cast_local, _)) = cast_local {
+                prefix
+                    .push(Statement { source_info, kind: StatementKind::StorageDead(cast_local) });
+            }
+            after_call.statements.splice(0..0, prefix);
+
+            if let Some((cast_local, cast_ty)) = cast_local {
+                block
+                    .statements
+                    .push(Statement { source_info, kind: StatementKind::StorageLive(cast_local) });
+                block.statements.push(Statement {
+                    source_info,
+                    kind: StatementKind::Assign(box (
+                        Place::from(cast_local),
+                        Rvalue::Cast(CastKind::Misc, rhs, cast_ty),
+                    )),
+                });
+                rhs = Operand::Move(Place::from(cast_local));
+            }
+            let destination = match cmp_local {
+                Some(cmp_local) => {
+                    block.statements.push(Statement {
+                        source_info,
+                        kind: StatementKind::StorageLive(cmp_local),
+                    });
+                    Place::from(cmp_local)
+                }
+                None => place,
+            };
+
+            let bb = BasicBlock::new(cur_len + new_blocks.len());
+            new_blocks.push(after_call);
+
+            block.terminator = Some(Terminator {
+                source_info,
+                kind: TerminatorKind::Call {
+                    func: Operand::function_handle(tcx, call_did, List::empty(), source_info.span),
+                    args: vec![lhs, rhs],
+                    destination: Some((destination, bb)),
+                    cleanup: None,
+                    from_hir_call: false,
+                    fn_span: source_info.span,
+                },
+            });
+        }
+    }
+
+    let changed = !new_blocks.is_empty();
+    basic_blocks.extend(new_blocks);
+    PassOutcome::changed_if(changed)
+}
+
+/// How the right-hand operand has to be adjusted to match the lang item's signature.
+#[derive(Copy, Clone)]
+enum RhsKind {
+    Unchanged,
+    /// Shift amounts of unchecked shifts are only meaningful modulo 128.
+    ForceU32,
+    /// Checked shifts need to see the whole amount to detect overflow.
+    ForceU128,
+}
+
+impl RhsKind {
+    fn ty<'tcx>(&self, tcx: TyCtxt<'tcx>) -> Option<Ty<'tcx>> {
+        match *self {
+            RhsKind::Unchanged => None,
+            RhsKind::ForceU32 => Some(tcx.types.u32),
+            RhsKind::ForceU128 => Some(tcx.types.u128),
+        }
+    }
+}
+
+/// How the lang item's return value relates to the original destination.
+#[derive(Copy, Clone)]
+enum ResultKind {
+    Direct,
+    /// The lang item is a three-way comparison whose result has to be compared against zero
+    /// with the given operator.
+    Compare(BinOp),
+}
+
+fn lower_to<'tcx, D>(
+    tcx: TyCtxt<'tcx>,
+    statement: &Statement<'tcx>,
+    local_decls: &D,
+) -> Option<(DefId, RhsKind, ResultKind)>
+where
+    D: HasLocalDecls<'tcx>,
+{
+    let lang_items = tcx.lang_items();
+    match statement.kind {
+        StatementKind::Assign(box (_, Rvalue::BinaryOp(bin_op, ref lhs, _))) => {
+            let is_signed = sign_of_128bit(lhs.ty(local_decls, tcx))?;
+            let (did, rhs_kind, result_kind) = match bin_op {
+                BinOp::Add => (
+                    pick(is_signed, lang_items.i128_add_fn(), lang_items.u128_add_fn()),
+                    RhsKind::Unchanged,
+                    ResultKind::Direct,
+                ),
+                BinOp::Sub => (
+                    pick(is_signed, lang_items.i128_sub_fn(), lang_items.u128_sub_fn()),
+                    RhsKind::Unchanged,
+                    ResultKind::Direct,
+                ),
+                BinOp::Mul => (
+                    pick(is_signed, lang_items.i128_mul_fn(), lang_items.u128_mul_fn()),
+                    RhsKind::Unchanged,
+                    ResultKind::Direct,
+                ),
+                BinOp::Div => (
+                    pick(is_signed, lang_items.i128_div_fn(), lang_items.u128_div_fn()),
+                    RhsKind::Unchanged,
+                    ResultKind::Direct,
+                ),
+                BinOp::Rem => (
+                    pick(is_signed, lang_items.i128_rem_fn(), lang_items.u128_rem_fn()),
+                    RhsKind::Unchanged,
+                    ResultKind::Direct,
+                ),
+                BinOp::Shl => (
+                    pick(is_signed, lang_items.i128_shl_fn(), lang_items.u128_shl_fn()),
+                    RhsKind::ForceU32,
+                    ResultKind::Direct,
+                ),
+                BinOp::Shr => (
+                    pick(is_signed, lang_items.i128_shr_fn(), lang_items.u128_shr_fn()),
+                    RhsKind::ForceU32,
+                    ResultKind::Direct,
+                ),
+                BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => (
+                    pick(is_signed, lang_items.i128_cmp_fn(), lang_items.u128_cmp_fn()),
+                    RhsKind::Unchanged,
+                    ResultKind::Compare(bin_op),
+                ),
+                BinOp::BitXor | BinOp::BitAnd | BinOp::BitOr | BinOp::Offset => return None,
+            };
+            Some((did?, rhs_kind, result_kind))
+        }
+        StatementKind::Assign(box (_, Rvalue::CheckedBinaryOp(bin_op, ref lhs, _))) => {
+            let is_signed = sign_of_128bit(lhs.ty(local_decls, tcx))?;
+            let (did, rhs_kind) = match bin_op {
+                BinOp::Add => (
+                    pick(is_signed, lang_items.i128_addo_fn(), lang_items.u128_addo_fn()),
+                    RhsKind::Unchanged,
+                ),
+                BinOp::Sub => (
+                    pick(is_signed, lang_items.i128_subo_fn(), lang_items.u128_subo_fn()),
+                    RhsKind::Unchanged,
+                ),
+                BinOp::Mul => (
+                    pick(is_signed, lang_items.i128_mulo_fn(), lang_items.u128_mulo_fn()),
+                    RhsKind::Unchanged,
+                ),
+                BinOp::Shl => (
+                    pick(is_signed, lang_items.i128_shlo_fn(), lang_items.u128_shlo_fn()),
+                    RhsKind::ForceU128,
+                ),
+                BinOp::Shr => (
+                    pick(is_signed, lang_items.i128_shro_fn(), lang_items.u128_shro_fn()),
+                    RhsKind::ForceU128,
+                ),
+                _ => bug!("That should be all the checked ones?"),
+            };
+            Some((did?, rhs_kind, ResultKind::Direct))
+        }
+        _ => None,
+    }
+}
+
+fn pick(is_signed: bool, signed: Option<DefId>, unsigned: Option<DefId>) -> Option<DefId> {
+    if is_signed { signed } else { unsigned }
+}
+
+fn sign_of_128bit(ty: Ty<'_>) -> Option<bool> {
+    match ty.kind {
+        ty::Int(IntTy::I128) => Some(true),
+        ty::Uint(UintTy::U128) => Some(false),
+        _ => None,
+    }
+}
+
+/// What a lang item that 128-bit operators are lowered to computes.
+#[derive(Copy, Clone, Debug)]
+pub enum LoweredOp {
+    /// The result of the operator, ignoring overflow.
+    Direct(BinOp),
+    /// The result of the operator, along with whether it overflowed.
+    Checked(BinOp),
+    /// -1, 0 or 1 as an `i8`, when the left operand is less than, equal to or greater than the
+    /// right one.
+    Compare,
+}
+
+/// Returns what `def_id` computes if it is one of the lang items this pass calls. CTFE uses it
+/// to evaluate the calls in the `const fn`s this pass lowered, since the lang items aren't
+/// `const fn`s themselves.
+pub fn lowered_op(tcx: TyCtxt<'_>, def_id: DefId) -> Option<LoweredOp> {
+    let lang_items = tcx.lang_items();
+    let items = [
+        (lang_items.i128_add_fn(), LoweredOp::Direct(BinOp::Add)),
+        (lang_items.u128_add_fn(), LoweredOp::Direct(BinOp::Add)),
+        (lang_items.i128_sub_fn(), LoweredOp::Direct(BinOp::Sub)),
+        (lang_items.u128_sub_fn(), LoweredOp::Direct(BinOp::Sub)),
+        (lang_items.i128_mul_fn(), LoweredOp::Direct(BinOp::Mul)),
+        (lang_items.u128_mul_fn(), LoweredOp::Direct(BinOp::Mul)),
+        (lang_items.i128_div_fn(), LoweredOp::Direct(BinOp::Div)),
+        (lang_items.u128_div_fn(), LoweredOp::Direct(BinOp::Div)),
+        (lang_items.i128_rem_fn(), LoweredOp::Direct(BinOp::Rem)),
+        (lang_items.u128_rem_fn(), LoweredOp::Direct(BinOp::Rem)),
+        (lang_items.i128_shl_fn(), LoweredOp::Direct(BinOp::Shl)),
+        (lang_items.u128_shl_fn(), LoweredOp::Direct(BinOp::Shl)),
+        (lang_items.i128_shr_fn(), LoweredOp::Direct(BinOp::Shr)),
+        (lang_items.u128_shr_fn(), LoweredOp::Direct(BinOp::Shr)),
+        (lang_items.i128_addo_fn(), LoweredOp::Checked(BinOp::Add)),
+        (lang_items.u128_addo_fn(), LoweredOp::Checked(BinOp::Add)),
+        (lang_items.i128_subo_fn(), LoweredOp::Checked(BinOp::Sub)),
+        (lang_items.u128_subo_fn(), LoweredOp::Checked(BinOp::Sub)),
+        (lang_items.i128_mulo_fn(), LoweredOp::Checked(BinOp::Mul)),
+        (lang_items.u128_mulo_fn(), LoweredOp::Checked(BinOp::Mul)),
+        (lang_items.i128_shlo_fn(), LoweredOp::Checked(BinOp::Shl)),
+        (lang_items.u128_shlo_fn(), LoweredOp::Checked(BinOp::Shl)),
+        (lang_items.i128_shro_fn(), LoweredOp::Checked(BinOp::Shr)),
+        (lang_items.u128_shro_fn(), LoweredOp::Checked(BinOp::Shr)),
+        (lang_items.i128_cmp_fn(), LoweredOp::Compare),
+        (lang_items.u128_cmp_fn(), LoweredOp::Compare),
+    ];
+    items.iter().find(|&&(item, _)| item == Some(def_id)).map(|&(_, op)| op)
+}
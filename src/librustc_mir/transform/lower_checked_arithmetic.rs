@@ -0,0 +1,116 @@
+//! Lowers checked arithmetic to plain operators when overflow checks are disabled.
+//!
+//! Functions marked `#[rustc_inherit_overflow_checks]`, like the operator implementations of the
+//! standard library, always check for overflow in their MIR, and leave the decision to the crate
+//! they end up in. Once inlined into a crate built with `-C overflow-checks=off`, the checks
+//! stay in its MIR, and only codegen knows to skip them:
+//!
+//!     _4 = CheckedAdd(_2, _3);
+//!     assert(!move (_4.1: bool), "attempt to add with overflow") -> bb1;
+//!
+//! In such a crate, the pass rewrites the checked operators into the plain ones, which wrap, and
+//! the overflow assertions into `goto`s:
+//!
+//!     (_4.0: u8) = Add(_2, _3);
+//!     (_4.1: bool) = const false;
+//!     goto -> bb1;
+//!
+//! Bodies that may be evaluated at compile time always check for overflow, and so do the bodies
+//! that inherit their checks, whose MIR other crates may still inline.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_session::Session;
+use rustc_span::symbol::sym;
+
+pub struct LowerCheckedArithmetic;
+
+impl<'tcx> MirPass<'tcx> for LowerCheckedArithmetic {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        !sess.overflow_checks()
+    }
+
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let def_id = source.def_id();
+        if source.promoted.is_some()
+            || def_id
+                .as_local()
+                .map_or(true, |def_id| tcx.hir().body_const_context(def_id).is_some())
+            || tcx.has_attr(def_id, sym::rustc_inherit_overflow_checks)
+        {
+            return PassOutcome::Unchanged;
+        }
+
+        let mut changed = false;
+        let (basic_blocks, local_decls) = body.basic_blocks_and_local_decls_mut();
+        let local_decls = &*local_decls;
+        for (bb, data) in basic_blocks.iter_enumerated_mut() {
+            data.expand_statements(|statement| {
+                let (place, op, left, right) = match statement.kind {
+                    StatementKind::Assign(box (
+                        place,
+                        Rvalue::CheckedBinaryOp(op, ref left, ref right),
+                    )) => (place, op, left.clone(), right.clone()),
+                    _ => return None,
+                };
+                debug!("lowering {:?} in {:?}", statement, bb);
+                changed = true;
+
+                let source_info = statement.source_info;
+                let result_ty = op.ty(tcx, left.ty(local_decls, tcx), right.ty(local_decls, tcx));
+                let result = tcx.mk_place_field(place, Field::new(0), result_ty);
+                let overflowed = tcx.mk_place_field(place, Field::new(1), tcx.types.bool);
+                let no_overflow = Operand::Constant(box Constant {
+                    span: source_info.span,
+                    user_ty: None,
+                    literal: ty::Const::from_bool(tcx, false),
+                });
+                Some(
+                    vec![
+                        Statement {
+                            source_info,
+                            kind: StatementKind::Assign(box (
+                                result,
+                                Rvalue::BinaryOp(op, left, right),
+                            )),
+                        },
+                        Statement {
+                            source_info,
+                            kind: StatementKind::Assign(box (overflowed, Rvalue::Use(no_overflow))),
+                        },
+                    ]
+                    .into_iter(),
+                )
+            });
+
+            let terminator = data.terminator_mut();
+            if let TerminatorKind::Assert { ref msg, target, .. } = terminator.kind {
+                // Signed division still has to check for overflow, which is undefined behavior
+                // in the backends rather than wrapping.
+                let optional = match *msg {
+                    AssertKind::Overflow(op, ..) => {
+                        matches!(op, BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Shl | BinOp::Shr)
+                    }
+                    AssertKind::OverflowNeg(_) => true,
+                    _ => false,
+                };
+                if optional {
+                    debug!("removing the overflow check in {:?}", bb);
+                    terminator.kind = TerminatorKind::Goto { target };
+                    changed = true;
+                }
+            }
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
@@ -0,0 +1,130 @@
+//! Lowers calls to simple intrinsics into statements.
+//!
+//! Calls to intrinsics like `size_of` look like any other call to the passes that follow, which
+//! can neither fold them nor see through them:
+//!
+//!     _2 = std::intrinsics::size_of::<u32>() -> bb1;
+//!
+//! The pass replaces the calls it knows the meaning of with an assignment of their result and a
+//! `goto` to their return block:
+//!
+//!     _2 = SizeOf(u32);
+//!     goto -> bb1;
+//!
+//! `size_of`, `min_align_of` and `pref_align_of` become constants once the layout of their type is
+//! known, and `size_of` becomes `SizeOf` otherwise. The other intrinsics without arguments,
+//! `needs_drop`, `type_id`, `type_name` and `variant_count`, are evaluated once their type no
+//! longer depends on generic parameters. `discriminant_value` reads the discriminant of the place
+//! its argument points to, and `forget` does nothing at all.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, TyCtxt, TypeFoldable};
+use rustc_span::symbol::sym;
+use rustc_target::spec::abi::Abi;
+
+pub struct LowerIntrinsics;
+
+impl<'tcx> MirPass<'tcx> for LowerIntrinsics {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let param_env = tcx.param_env(source.def_id());
+        let mut changed = false;
+        let (basic_blocks, local_decls) = body.basic_blocks_and_local_decls_mut();
+        for (bb, data) in basic_blocks.iter_enumerated_mut() {
+            let terminator = data.terminator_mut();
+            let (def_id, substs, args, destination, target) = match terminator.kind {
+                TerminatorKind::Call {
+                    ref func,
+                    ref args,
+                    destination: Some((destination, target)),
+                    ..
+                } => match func.ty(&*local_decls, tcx).kind {
+                    ty::FnDef(def_id, substs) if tcx.fn_sig(def_id).abi() == Abi::RustIntrinsic => {
+                        (def_id, substs, args, destination, target)
+                    }
+                    _ => continue,
+                },
+                _ => continue,
+            };
+
+            let name = tcx.item_name(def_id);
+            let rvalue = match name {
+                sym::size_of | sym::min_align_of | sym::pref_align_of => {
+                    let ty = substs.type_at(0);
+                    match tcx.layout_of(param_env.and(ty)) {
+                        Ok(layout) => {
+                            let bytes = match name {
+                                sym::size_of => layout.size.bytes(),
+                                sym::min_align_of => layout.align.abi.bytes(),
+                                _ => layout.align.pref.bytes(),
+                            };
+                            let value = ty::Const::from_usize(tcx, bytes);
+                            Rvalue::Use(constant(terminator.source_info, value))
+                        }
+                        Err(_) if name == sym::size_of => Rvalue::NullaryOp(NullOp::SizeOf, ty),
+                        Err(_) => continue,
+                    }
+                }
+                sym::needs_drop | sym::type_id | sym::type_name | sym::variant_count => {
+                    // Evaluating them reports an error for types that aren't known yet.
+                    if substs.needs_subst() {
+                        continue;
+                    }
+                    let param_env = ty::ParamEnv::reveal_all();
+                    let instance = match ty::Instance::resolve(tcx, param_env, def_id, substs) {
+                        Ok(Some(instance)) => instance,
+                        _ => continue,
+                    };
+                    let value = match tcx.const_eval_instance(param_env, instance, None) {
+                        Ok(value) => value,
+                        Err(_) => continue,
+                    };
+                    let ty = destination.ty(&*local_decls, tcx).ty;
+                    let value = ty::Const::from_value(tcx, value, ty);
+                    Rvalue::Use(constant(terminator.source_info, value))
+                }
+                sym::discriminant_value => {
+                    let pointee = match args[0].place() {
+                        Some(pointer) => tcx.mk_place_deref(pointer),
+                        None => continue,
+                    };
+                    // In generic code the result is still a projection, which the type of
+                    // `Discriminant` can't be compared with.
+                    let ty = pointee.ty(&*local_decls, tcx).ty;
+                    if ty.discriminant_ty(tcx) != destination.ty(&*local_decls, tcx).ty {
+                        continue;
+                    }
+                    Rvalue::Discriminant(pointee)
+                }
+                sym::forget => {
+                    let unit = ty::Const::zero_sized(tcx, tcx.mk_unit());
+                    Rvalue::Use(constant(terminator.source_info, unit))
+                }
+                _ => continue,
+            };
+
+            debug!("lowering the call to `{}` in {:?} to {:?}", name, bb, rvalue);
+            let source_info = terminator.source_info;
+            terminator.kind = TerminatorKind::Goto { target };
+            data.statements.push(Statement {
+                source_info,
+                kind: StatementKind::Assign(box (destination, rvalue)),
+            });
+            changed = true;
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
+
+fn constant<'tcx>(source_info: SourceInfo, literal: &'tcx ty::Const<'tcx>) -> Operand<'tcx> {
+    Operand::Constant(box Constant { span: source_info.span, user_ty: None, literal })
+}
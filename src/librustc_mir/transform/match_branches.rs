@@ -0,0 +1,236 @@
+//! Replaces switches whose arms only differ in the constants they assign by straight-line code.
+//!
+//! `if x == 0 { false } else { true }`, or a match that maps consecutive discriminants to the same
+//! integers, leaves a switch whose arms do the same thing apart from assigning different
+//! constants:
+//!
+//!     bb0: { switchInt(move _2) -> [0_u8: bb2, otherwise: bb1]; }
+//!     bb1: { _0 = const true; goto -> bb3; }
+//!     bb2: { _0 = const false; goto -> bb3; }
+//!
+//! The statements of the arms are moved into the switching block, with each differing constant
+//! computed from the switched-on value instead:
+//!
+//!     bb0: { _3 = move _2; _0 = Ne(_3, const 0_u8); goto -> bb3; }
+//!
+//! Constants that equal the value every arm is taken for become a copy of that value, or a cast
+//! of it if the types differ. Otherwise, bool constants that are opposite in the two arms of a
+//! switch become a comparison with the value of the first arm. An otherwise arm that is unreachable
+//! is ignored. The arms must have the same terminator and otherwise equal statements, and may
+//! become unreachable, which the later `SimplifyCfg` runs take care of.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, Ty, TyCtxt};
+
+pub struct MatchBranchSimplification;
+
+impl<'tcx> MirPass<'tcx> for MatchBranchSimplification {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let param_env = tcx.param_env(source.def_id());
+
+        let mut changed = false;
+        for bb in body.basic_blocks().indices() {
+            let simplification = match find_simplification(tcx, param_env, body, bb) {
+                Some(simplification) => simplification,
+                None => continue,
+            };
+            debug!("replacing the switch in {:?} by straight-line code", bb);
+
+            // The arms may change the switched-on place, so its value is saved first.
+            let source_info = body[bb].terminator().source_info;
+            let switch_ty = simplification.switch_ty;
+            let discr = body
+                .local_decls
+                .push(LocalDecl::with_source_info(switch_ty, source_info).internal());
+            let mut statements = vec![Statement {
+                source_info,
+                kind: StatementKind::Assign(box (
+                    Place::from(discr),
+                    Rvalue::Use(simplification.discr),
+                )),
+            }];
+            for merged in simplification.merged {
+                let (source_info, place, rvalue) = match merged {
+                    Merged::Same(statement) => {
+                        statements.push(statement);
+                        continue;
+                    }
+                    Merged::Compare { source_info, place, op, value } => {
+                        let value = Operand::Constant(box Constant {
+                            span: source_info.span,
+                            user_ty: None,
+                            literal: ty::Const::from_bits(tcx, value, param_env.and(switch_ty)),
+                        });
+                        let rvalue = Rvalue::BinaryOp(op, Operand::Copy(Place::from(discr)), value);
+                        (source_info, place, rvalue)
+                    }
+                    Merged::Convert { source_info, place, ty } => {
+                        let operand = Operand::Copy(Place::from(discr));
+                        let rvalue = if ty == switch_ty {
+                            Rvalue::Use(operand)
+                        } else {
+                            Rvalue::Cast(CastKind::Misc, operand, ty)
+                        };
+                        (source_info, place, rvalue)
+                    }
+                };
+                statements.push(Statement {
+                    source_info,
+                    kind: StatementKind::Assign(box (place, rvalue)),
+                });
+            }
+
+            let data = &mut body.basic_blocks_mut()[bb];
+            data.statements.extend(statements);
+            *data.terminator_mut() = simplification.terminator;
+            changed = true;
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
+
+/// The straight-line code that replaces the switch on `discr` at the end of a block.
+struct Simplification<'tcx> {
+    discr: Operand<'tcx>,
+    switch_ty: Ty<'tcx>,
+    merged: Vec<Merged<'tcx>>,
+    terminator: Terminator<'tcx>,
+}
+
+/// A statement of the arms of a switch, as it is done in the switching block.
+enum Merged<'tcx> {
+    /// A statement that is the same in every arm.
+    Same(Statement<'tcx>),
+    /// Assigns whether the switched-on value compares to `value` with `op`.
+    Compare { source_info: SourceInfo, place: Place<'tcx>, op: BinOp, value: u128 },
+    /// Assigns the switched-on value, converted to `ty`.
+    Convert { source_info: SourceInfo, place: Place<'tcx>, ty: Ty<'tcx> },
+}
+
+fn find_simplification<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    body: &Body<'tcx>,
+    bb: BasicBlock,
+) -> Option<Simplification<'tcx>> {
+    let data = &body[bb];
+    let (discr, switch_ty, values, targets) = match data.terminator().kind {
+        TerminatorKind::SwitchInt { ref discr, switch_ty, ref values, ref targets } => {
+            (discr, switch_ty, values, targets)
+        }
+        _ => return None,
+    };
+
+    // The value each arm is taken for, which isn't known for most otherwise arms.
+    let mut arms: Vec<(Option<u128>, BasicBlock)> =
+        values.iter().zip(targets).map(|(&value, &target)| (Some(value), target)).collect();
+    let otherwise = *targets.last().unwrap();
+    let otherwise_data = &body[otherwise];
+    if !otherwise_data.statements.is_empty()
+        || otherwise_data.terminator().kind != TerminatorKind::Unreachable
+    {
+        let value =
+            if switch_ty.is_bool() && values.len() == 1 { Some(1 - values[0]) } else { None };
+        arms.push((value, otherwise));
+    }
+    if arms.len() < 2 {
+        return None;
+    }
+
+    let first = &body[arms[0].1];
+    if arms.iter().any(|&(_, target)| {
+        let arm = &body[target];
+        target == bb
+            || arm.is_cleanup != data.is_cleanup
+            || arm.statements.len() != first.statements.len()
+            || arm.terminator().kind != first.terminator().kind
+    }) {
+        return None;
+    }
+
+    let mut merged = Vec::with_capacity(first.statements.len());
+    for (i, statement) in first.statements.iter().enumerate() {
+        if arms.iter().all(|&(_, target)| body[target].statements[i].kind == statement.kind) {
+            merged.push(Merged::Same(statement.clone()));
+            continue;
+        }
+
+        // Only assignments of different constants to the same place are merged.
+        let place = match statement.kind {
+            StatementKind::Assign(box (place, Rvalue::Use(Operand::Constant(_)))) => place,
+            _ => return None,
+        };
+        let ty = place.ty(body, tcx).ty;
+        let mut constants = Vec::with_capacity(arms.len());
+        for &(_, target) in &arms {
+            match body[target].statements[i].kind {
+                StatementKind::Assign(box (other, Rvalue::Use(Operand::Constant(ref c))))
+                    if other == place =>
+                {
+                    constants.push(c.literal.try_eval_bits(tcx, param_env, ty)?);
+                }
+                _ => return None,
+            }
+        }
+        if constants.iter().all(|&c| c == constants[0]) {
+            // The constants only differ in their spans.
+            merged.push(Merged::Same(statement.clone()));
+            continue;
+        }
+        merged.push(merge_constants(
+            statement.source_info,
+            place,
+            ty,
+            switch_ty,
+            &arms,
+            &constants,
+        )?);
+    }
+
+    Some(Simplification {
+        discr: discr.clone(),
+        switch_ty,
+        merged,
+        terminator: first.terminator().clone(),
+    })
+}
+
+/// Computes the constant each arm assigns to `place` from the value the arm is taken for.
+fn merge_constants<'tcx>(
+    source_info: SourceInfo,
+    place: Place<'tcx>,
+    ty: Ty<'tcx>,
+    switch_ty: Ty<'tcx>,
+    arms: &[(Option<u128>, BasicBlock)],
+    constants: &[u128],
+) -> Option<Merged<'tcx>> {
+    // Small non-negative values keep their value when cast to any integer type.
+    let same_value = arms.iter().zip(constants).all(|(&(value, _), &constant)| {
+        value == Some(constant) && (ty == switch_ty || constant < 128)
+    });
+    let convertible = ty == switch_ty
+        || ty.is_integral()
+            && (switch_ty.is_integral() || switch_ty.is_bool() || switch_ty.is_char());
+    if same_value && convertible {
+        return Some(Merged::Convert { source_info, place, ty });
+    }
+
+    match arms {
+        &[(Some(value), _), _] if ty.is_bool() => {
+            // The constants are different, so one of them is `true` and the other `false`.
+            let op = if constants[0] == 1 { BinOp::Eq } else { BinOp::Ne };
+            Some(Merged::Compare { source_info, place, op, value })
+        }
+        _ => None,
+    }
+}
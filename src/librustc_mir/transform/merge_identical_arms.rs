@@ -0,0 +1,188 @@
+//! Redirects `SwitchInt` targets to an earlier target whose block does the same thing.
+//!
+//! `SimplifyBranchSame` only fires when all targets are equal statement for statement, but arms
+//! of big matches (e.g. in generated parsers) often have bodies that only differ in the
+//! temporaries they use. Two blocks are considered identical if they are equal after renaming the
+//! temporaries that are only mentioned in one of them and ignoring the spans of constants. The
+//! duplicate blocks become unreachable and are removed.
+
+use crate::transform::{simplify, MirPass, MirSource, PassOutcome};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_index::bit_set::BitSet;
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::visit::{MutVisitor, PlaceContext, Visitor};
+use rustc_middle::mir::*;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::DUMMY_SP;
+
+pub struct MergeIdenticalArms;
+
+impl<'tcx> MirPass<'tcx> for MergeIdenticalArms {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let block_locals = BlockLocals::new(body);
+
+        let mut did_merge = false;
+        for bb in body.basic_blocks().indices() {
+            let targets = match body[bb].terminator().kind {
+                TerminatorKind::SwitchInt { ref targets, .. } => targets,
+                _ => continue,
+            };
+
+            let mut new_targets = targets.clone();
+            for i in 1..targets.len() {
+                let target = targets[i];
+                let equivalent = targets[..i].iter().find(|&&other| {
+                    other != target && blocks_equivalent(tcx, body, &block_locals, other, target)
+                });
+                if let Some(&other) = equivalent {
+                    debug!("merging {:?} into {:?} in switch of {:?}", target, other, bb);
+                    new_targets[i] = other;
+                    did_merge = true;
+                }
+            }
+
+            if let TerminatorKind::SwitchInt { ref mut targets, .. } =
+                body.basic_blocks_mut()[bb].terminator_mut().kind
+            {
+                *targets = new_targets;
+            }
+        }
+
+        if did_merge {
+            simplify::remove_dead_blocks(body);
+        }
+        PassOutcome::changed_if(did_merge)
+    }
+}
+
+/// Returns whether `b` does the same as `a` once its block-local temporaries are renamed to the
+/// ones of `a`.
+fn blocks_equivalent<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    body: &Body<'tcx>,
+    block_locals: &BlockLocals,
+    a: BasicBlock,
+    b: BasicBlock,
+) -> bool {
+    let (data_a, data_b) = (&body[a], &body[b]);
+    if data_a.is_cleanup != data_b.is_cleanup || data_a.statements.len() != data_b.statements.len()
+    {
+        return false;
+    }
+
+    let locals_a = block_locals.in_order_of_mention(a, data_a);
+    let locals_b = block_locals.in_order_of_mention(b, data_b);
+    if locals_a.len() != locals_b.len()
+        || locals_a
+            .iter()
+            .zip(&locals_b)
+            .any(|(&l, &r)| body.local_decls[l].ty != body.local_decls[r].ty)
+    {
+        return false;
+    }
+
+    let mut normalized_a = data_a.clone();
+    Normalizer { tcx, map: FxHashMap::default() }.visit_basic_block_data(a, &mut normalized_a);
+    let mut normalized_b = data_b.clone();
+    let map = locals_b.into_iter().zip(locals_a).collect();
+    Normalizer { tcx, map }.visit_basic_block_data(b, &mut normalized_b);
+
+    normalized_b.terminator().kind == normalized_a.terminator().kind
+        && normalized_b.statements.iter().eq_by(&normalized_a.statements, |x, y| x.kind == y.kind)
+}
+
+/// Finds the temporaries that are only mentioned in a single block.
+struct BlockLocals {
+    block: IndexVec<Local, Option<BasicBlock>>,
+    shared: BitSet<Local>,
+}
+
+impl BlockLocals {
+    fn new(body: &Body<'_>) -> BlockLocals {
+        let mut block_locals = BlockLocals {
+            block: IndexVec::from_elem(None, &body.local_decls),
+            shared: BitSet::new_empty(body.local_decls.len()),
+        };
+        for local in body.local_decls.indices() {
+            if body.local_kind(local) != LocalKind::Temp {
+                block_locals.shared.insert(local);
+            }
+        }
+        for debug_info in &body.var_debug_info {
+            block_locals.shared.insert(debug_info.place.local);
+        }
+
+        for (bb, data) in body.basic_blocks().iter_enumerated() {
+            let mut collector = LocalCollector::default();
+            collector.visit_basic_block_data(bb, data);
+            for local in collector.locals {
+                match block_locals.block[local] {
+                    None => block_locals.block[local] = Some(bb),
+                    Some(other) if other == bb => {}
+                    Some(_) => {
+                        block_locals.shared.insert(local);
+                    }
+                }
+            }
+        }
+
+        block_locals
+    }
+
+    /// The temporaries local to `bb`, in the order they are first mentioned.
+    fn in_order_of_mention(&self, bb: BasicBlock, data: &BasicBlockData<'_>) -> Vec<Local> {
+        let mut collector = LocalCollector::default();
+        collector.visit_basic_block_data(bb, data);
+        let mut locals = collector.locals;
+        locals.retain(|&local| !self.shared.contains(local) && self.block[local] == Some(bb));
+        locals
+    }
+}
+
+/// Collects the locals mentioned in a block, without duplicates.
+#[derive(Default)]
+struct LocalCollector {
+    locals: Vec<Local>,
+    seen: FxHashSet<Local>,
+}
+
+impl<'tcx> Visitor<'tcx> for LocalCollector {
+    fn visit_local(&mut self, &local: &Local, _: PlaceContext, _: Location) {
+        if self.seen.insert(local) {
+            self.locals.push(local);
+        }
+    }
+}
+
+/// Renames locals according to `map` and erases the spans of constants, which differ between
+/// otherwise identical arms.
+struct Normalizer<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    map: FxHashMap<Local, Local>,
+}
+
+impl<'tcx> MutVisitor<'tcx> for Normalizer<'tcx> {
+    fn tcx(&self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    fn visit_local(&mut self, local: &mut Local, _: PlaceContext, _: Location) {
+        if let Some(&renamed) = self.map.get(local) {
+            *local = renamed;
+        }
+    }
+
+    fn visit_constant(&mut self, constant: &mut Constant<'tcx>, location: Location) {
+        constant.span = DUMMY_SP;
+        self.super_constant(constant, location);
+    }
+}
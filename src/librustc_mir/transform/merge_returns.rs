@@ -0,0 +1,67 @@
+//! Leaves a body with a single block that returns.
+//!
+//! Inlining and the lowering of matches leave several blocks behind that return, often with
+//! nothing else in them:
+//!
+//!     bb3: { _0 = _5; return; }
+//!     bb4: { return; }
+//!     bb6: { return; }
+//!
+//! One block whose only content is the `return` is kept, or added if there is none, and every
+//! other `return` becomes a `goto` to it:
+//!
+//!     bb3: { _0 = _5; goto -> bb4; }
+//!     bb4: { return; }
+//!     bb6: { goto -> bb4; }
+//!
+//! The blocks that only go to the return block afterwards are removed by `SimplifyCfg`, and
+//! blocks that only differed in how they returned can be merged by `DeduplicateBlocks`.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_middle::mir::*;
+use rustc_middle::ty::TyCtxt;
+
+pub struct MergeReturns;
+
+impl<'tcx> MirPass<'tcx> for MergeReturns {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        _tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let returns: Vec<BasicBlock> = body
+            .basic_blocks()
+            .iter_enumerated()
+            .filter(|(_, data)| matches!(data.terminator().kind, TerminatorKind::Return))
+            .map(|(bb, _)| bb)
+            .collect();
+        if returns.len() < 2 {
+            return PassOutcome::Unchanged;
+        }
+
+        let return_block = match returns.iter().find(|&&bb| body[bb].statements.is_empty()) {
+            Some(&bb) => bb,
+            None => {
+                let source_info = body[returns[0]].terminator().source_info;
+                body.basic_blocks_mut().push(BasicBlockData {
+                    statements: vec![],
+                    terminator: Some(Terminator { source_info, kind: TerminatorKind::Return }),
+                    is_cleanup: false,
+                })
+            }
+        };
+        debug!("merging the returns of {:?} into {:?}", returns, return_block);
+        for bb in returns {
+            if bb != return_block {
+                body.basic_blocks_mut()[bb].terminator_mut().kind =
+                    TerminatorKind::Goto { target: return_block };
+            }
+        }
+        PassOutcome::Changed
+    }
+}
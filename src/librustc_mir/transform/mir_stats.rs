@@ -0,0 +1,66 @@
+//! Implements `-Z mir-stats`, a crate-wide report of how big the optimized MIR of each function
+//! turned out. Bodies that grew far beyond their source, usually through inlining or macro
+//! expansion, end up at the top, which makes them easy to tell apart when a codegen unit is
+//! bigger than expected.
+
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::mir::BodyKind;
+use rustc_middle::ty::TyCtxt;
+use rustc_middle::util::common::to_readable_str;
+
+struct BodyStats {
+    path: String,
+    statements: usize,
+    blocks: usize,
+    locals: usize,
+    promoted: usize,
+}
+
+/// Prints the size of the optimized MIR of every function of the local crate, sorted by
+/// statement count, largest first.
+pub fn print_mir_stats(tcx: TyCtxt<'_>) {
+    let mut stats = Vec::new();
+    for &def_id in tcx.mir_keys(LOCAL_CRATE).iter() {
+        match tcx.mir_body_kind(def_id).kind {
+            BodyKind::Fn | BodyKind::Closure | BodyKind::Generator => {}
+            BodyKind::Const | BodyKind::Static | BodyKind::Ctor => continue,
+        }
+
+        let body = tcx.optimized_mir(def_id.to_def_id());
+        stats.push(BodyStats {
+            path: tcx.def_path_str(def_id.to_def_id()),
+            statements: body.basic_blocks().iter().map(|data| data.statements.len()).sum(),
+            blocks: body.basic_blocks().len(),
+            locals: body.local_decls.len(),
+            promoted: tcx.promoted_mir(def_id.to_def_id()).len(),
+        });
+    }
+
+    stats.sort_by(|a, b| b.statements.cmp(&a.statements).then_with(|| a.path.cmp(&b.path)));
+
+    println!("\nMIR STATS\n");
+    println!(
+        "{:<48}{:>12}{:>10}{:>10}{:>10}",
+        "Function", "Statements", "Blocks", "Locals", "Promoted"
+    );
+    println!("{}", "-".repeat(90));
+    for body in &stats {
+        println!(
+            "{:<48}{:>12}{:>10}{:>10}{:>10}",
+            body.path,
+            to_readable_str(body.statements),
+            to_readable_str(body.blocks),
+            to_readable_str(body.locals),
+            to_readable_str(body.promoted)
+        );
+    }
+    println!("{}", "-".repeat(90));
+    println!(
+        "{:<48}{:>12}{:>10}{:>10}{:>10}",
+        format!("Total ({} functions)", stats.len()),
+        to_readable_str(stats.iter().map(|body| body.statements).sum()),
+        to_readable_str(stats.iter().map(|body| body.blocks).sum()),
+        to_readable_str(stats.iter().map(|body| body.locals).sum()),
+        to_readable_str(stats.iter().map(|body| body.promoted).sum())
+    );
+}
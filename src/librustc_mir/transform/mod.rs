@@ -1,52 +1,107 @@
+use crate::util::BodySize;
 use crate::{shim, util};
 use required_consts::RequiredConstsVisitor;
 use rustc_data_structures::fx::FxHashSet;
+use rustc_data_structures::profiling::get_resident;
 use rustc_hir as hir;
+use rustc_hir::def::DefKind;
 use rustc_hir::def_id::{CrateNum, DefId, LocalDefId, LOCAL_CRATE};
 use rustc_hir::intravisit::{self, NestedVisitorMap, Visitor};
-use rustc_index::vec::IndexVec;
+use rustc_index::vec::{Idx, IndexVec};
 use rustc_middle::mir::visit::Visitor as _;
 use rustc_middle::mir::{traversal, Body, ConstQualifs, MirPhase, Promoted};
+use rustc_middle::mir::{BodyClassification, BodyKind};
+use rustc_middle::mir::{Statement, StatementKind};
 use rustc_middle::ty::query::Providers;
 use rustc_middle::ty::steal::Steal;
 use rustc_middle::ty::{self, InstanceDef, TyCtxt, TypeFoldable};
-use rustc_span::{Span, Symbol};
+use rustc_session::Session;
+use rustc_span::{sym, Span, Symbol};
 use std::borrow::Cow;
+use std::iter;
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
 
 pub mod add_call_guards;
 pub mod add_moves_for_packed_drops;
 pub mod add_retag;
+pub mod bounds_check_elimination;
+pub mod branch_weights;
+pub mod canonicalize_cfg;
 pub mod check_consts;
 pub mod check_packed_ref;
 pub mod check_unsafety;
 pub mod cleanup_post_borrowck;
 pub mod const_prop;
+pub mod constant_return;
 pub mod copy_prop;
+pub mod dead_store_elimination;
 pub mod deaggregator;
+pub mod deduplicate_blocks;
+pub mod deduplicate_promoted;
+pub mod dest_prop;
+pub mod devirtualize;
+pub mod discriminant_propagation;
 pub mod dump_mir;
+pub mod early_otherwise_branch;
 pub mod elaborate_drops;
 pub mod generator;
+pub mod gvn;
 pub mod inline;
+pub mod insert_assumes;
 pub mod instcombine;
+pub mod instrument_block_counters;
 pub mod instrument_coverage;
+pub mod instrument_mcount;
+pub mod jump_threading;
+pub mod loop_idioms;
+pub mod loop_invariant_code_motion;
+pub mod lower_128bit;
+pub mod lower_checked_arithmetic;
+pub mod lower_intrinsics;
+pub mod match_branches;
+pub mod merge_identical_arms;
+pub mod merge_returns;
+pub mod mir_stats;
 pub mod no_landing_pads;
 pub mod nrvo;
+pub mod outline_cold_blocks;
+pub mod pass_pipeline;
+pub mod pass_registry;
+pub mod plugin;
 pub mod promote_consts;
+pub mod provenance;
 pub mod qualify_min_const_fn;
+pub mod reaggregate;
+pub mod remove_dead_drop_flags;
 pub mod remove_noop_landing_pads;
+pub mod remove_redundant_reborrows;
+pub mod remove_storage_markers;
+pub mod remove_unneeded_drops;
+pub mod remove_zsts;
+pub mod reorder_blocks;
 pub mod required_consts;
 pub mod rustc_peek;
 pub mod simplify;
 pub mod simplify_branches;
 pub mod simplify_try;
+pub mod sink_statements;
+pub mod split_oversized_blocks;
+pub mod sroa;
+pub mod stack_slot_hints;
+pub mod stack_usage;
+pub mod strength_reduction;
+pub mod strip_debuginfo;
 pub mod uninhabited_enum_branching;
 pub mod unreachable_prop;
+pub mod unroll_loops;
 pub mod validate;
 
 pub(crate) fn provide(providers: &mut Providers) {
     self::check_unsafety::provide(providers);
     *providers = Providers {
         mir_keys,
+        mir_body_kind,
         mir_const,
         mir_const_qualif: |tcx, def_id| {
             let def_id = def_id.expect_local();
@@ -61,6 +116,7 @@ pub(crate) fn provide(providers: &mut Providers) {
         },
         mir_validated,
         mir_drops_elaborated_and_const_checked,
+        mir_inlined,
         optimized_mir,
         optimized_mir_of_const_arg,
         is_mir_available,
@@ -77,7 +133,10 @@ pub(crate) fn provide(providers: &mut Providers) {
         },
         ..*providers
     };
+    constant_return::provide(providers);
+    inline::provide(providers);
     instrument_coverage::provide(providers);
+    stack_usage::provide(providers);
 }
 
 fn is_mir_available(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
@@ -126,6 +185,19 @@ fn mir_keys(tcx: TyCtxt<'_>, krate: CrateNum) -> FxHashSet<LocalDefId> {
     set
 }
 
+fn mir_body_kind(tcx: TyCtxt<'_>, def_id: LocalDefId) -> BodyClassification {
+    let kind = match tcx.def_kind(def_id) {
+        DefKind::Fn | DefKind::AssocFn => BodyKind::Fn,
+        DefKind::Closure => BodyKind::Closure,
+        DefKind::Generator => BodyKind::Generator,
+        DefKind::Const | DefKind::AssocConst | DefKind::AnonConst => BodyKind::Const,
+        DefKind::Static => BodyKind::Static,
+        DefKind::Ctor(..) => BodyKind::Ctor,
+        kind => bug!("`{:?}` is a {:?}, which has no MIR", def_id, kind),
+    };
+    BodyClassification { kind, const_context: tcx.hir().body_const_context(def_id) }
+}
+
 /// Where a specific `mir::Body` comes from.
 #[derive(Debug, Copy, Clone)]
 pub struct MirSource<'tcx> {
@@ -133,6 +205,9 @@ pub struct MirSource<'tcx> {
 
     /// If `Some`, this is a promoted rvalue within the parent function.
     pub promoted: Option<Promoted>,
+
+    /// For promoted bodies, the span of the expression in the parent function that got promoted.
+    pub promoted_span: Option<Span>,
 }
 
 impl<'tcx> MirSource<'tcx> {
@@ -140,6 +215,7 @@ impl<'tcx> MirSource<'tcx> {
         MirSource {
             instance: InstanceDef::Item(ty::WithOptConstParam::unknown(def_id)),
             promoted: None,
+            promoted_span: None,
         }
     }
 
@@ -151,6 +227,20 @@ impl<'tcx> MirSource<'tcx> {
     pub fn def_id(&self) -> DefId {
         self.instance.def_id()
     }
+
+    /// Identifies the promoted body this is, if any, when appended to the path of the parent
+    /// body, e.g. `::promoted[0] (promoted from src/lib.rs:3:13: 3:20)`.
+    pub fn promoted_suffix(&self, tcx: TyCtxt<'_>) -> String {
+        match (self.promoted, self.promoted_span) {
+            (Some(promoted), Some(span)) => format!(
+                "::{:?} (promoted from {})",
+                promoted,
+                tcx.sess.source_map().span_to_string(span)
+            ),
+            (Some(promoted), None) => format!("::{:?}", promoted),
+            (None, _) => String::new(),
+        }
+    }
 }
 
 /// Generates a default name for the pass based on the name of the
@@ -168,7 +258,160 @@ pub trait MirPass<'tcx> {
         default_name::<Self>()
     }
 
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>);
+    /// Whether this pass can run in any order relative to the adjacent passes of its group
+    /// that also return `true`. `-Z mir-shuffle-passes` permutes such runs of passes to
+    /// expose hidden ordering assumptions.
+    fn is_order_independent(&self) -> bool {
+        false
+    }
+
+    /// Whether this pass does anything in this session. Passes that are turned on by flags or
+    /// by the target say so here instead of returning early from `run_pass`, so that they are
+    /// left out of dumps and of `-Z print-mir-pass-pipeline`.
+    fn is_enabled(&self, _sess: &Session) -> bool {
+        true
+    }
+
+    /// The passes that have to run before this one in the same pipeline, with none of the passes
+    /// that invalidate them in between. Passes are named as in `-Z mir-enable-passes`.
+    fn requires(&self) -> &[&str] {
+        &[]
+    }
+
+    /// The passes whose work this pass may undo, so that passes which require them can't run
+    /// after this one until they run again.
+    fn invalidates(&self) -> &[&str] {
+        &[]
+    }
+
+    /// The phases whose pipelines may include this pass, as in the phase `run_passes` moves the
+    /// body to. Passes that only work on MIR of a certain shape narrow this down.
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        MirPhase::Build..=MirPhase::Optimized
+    }
+
+    /// Whether the pass only improves the MIR, so that skipping it can't break anything. Such
+    /// passes use up a unit of `-Z fuel` for every body they run on, and don't run once it is
+    /// exhausted.
+    fn is_optimization(&self) -> bool {
+        false
+    }
+
+    /// The passes of a `Fixpoint` group, which the pass manager runs itself instead of calling
+    /// `run_pass`.
+    fn fixpoint_passes(&self) -> Option<&[&dyn MirPass<'tcx>]> {
+        None
+    }
+
+    /// Runs the pass on `body`. Passes that can't cheaply tell whether they did anything
+    /// return `PassOutcome::Changed`.
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome;
+}
+
+/// Whether a MIR pass modified the body it ran on.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PassOutcome {
+    Changed,
+    /// The body is exactly as it was before the pass, so the pass manager doesn't need to
+    /// validate it again.
+    Unchanged,
+}
+
+impl PassOutcome {
+    pub fn changed_if(changed: bool) -> Self {
+        if changed { PassOutcome::Changed } else { PassOutcome::Unchanged }
+    }
+}
+
+/// A group of passes that `run_passes` repeats until none of them changes the body, up to
+/// `-Z mir-fixpoint-iterations` times. Below `-Z mir-opt-level=2`, the group runs once.
+///
+/// Every pass of the group should report `PassOutcome::Unchanged` accurately, since a pass that
+/// always reports a change makes the group run the maximum number of times.
+pub struct Fixpoint<'a, 'tcx> {
+    label: String,
+    passes: &'a [&'a dyn MirPass<'tcx>],
+}
+
+impl<'a, 'tcx> Fixpoint<'a, 'tcx> {
+    pub fn new(label: &str, passes: &'a [&'a dyn MirPass<'tcx>]) -> Self {
+        Fixpoint { label: format!("Fixpoint-{}", label), passes }
+    }
+}
+
+impl<'tcx> MirPass<'tcx> for Fixpoint<'_, 'tcx> {
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.label)
+    }
+
+    fn fixpoint_passes(&self) -> Option<&[&dyn MirPass<'tcx>]> {
+        Some(self.passes)
+    }
+
+    /// Runs every pass once, for callers other than the pass manager.
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let mut changed = false;
+        for pass in self.passes {
+            changed |= pass.run_pass(tcx, source, body) == PassOutcome::Changed;
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
+
+/// Runs a pass only at `-Z mir-opt-level` `self.0` or higher, on top of its own `is_enabled`.
+pub struct WithMinOptLevel<T>(pub usize, pub T);
+
+impl<'tcx, T: MirPass<'tcx>> MirPass<'tcx> for WithMinOptLevel<T> {
+    fn name(&self) -> Cow<'_, str> {
+        self.1.name()
+    }
+
+    fn is_order_independent(&self) -> bool {
+        self.1.is_order_independent()
+    }
+
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.mir_opt_level >= self.0 && self.1.is_enabled(sess)
+    }
+
+    fn is_optimization(&self) -> bool {
+        self.1.is_optimization()
+    }
+
+    fn requires(&self) -> &[&str] {
+        self.1.requires()
+    }
+
+    fn invalidates(&self) -> &[&str] {
+        self.1.invalidates()
+    }
+
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        self.1.phases()
+    }
+
+    fn fixpoint_passes(&self) -> Option<&[&dyn MirPass<'tcx>]> {
+        self.1.fixpoint_passes()
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        self.1.run_pass(tcx, source, body)
+    }
 }
 
 pub fn run_passes(
@@ -180,45 +423,186 @@ pub fn run_passes(
     passes: &[&[&dyn MirPass<'tcx>]],
 ) {
     let phase_index = mir_phase.phase_index();
-    let source = MirSource { instance, promoted };
-    let validate = tcx.sess.opts.debugging_opts.validate_mir;
+    // The span of a promoted body is the one of the expression it was promoted from.
+    let source = MirSource { instance, promoted, promoted_span: promoted.map(|_| body.span) };
+    let shuffle_seed = tcx.sess.opts.debugging_opts.mir_shuffle_passes;
+    // Reordered passes are only useful if their mistakes get caught right away.
+    let validate = tcx.sess.opts.debugging_opts.validate_mir || shuffle_seed.is_some();
+    let memory_threshold = tcx.sess.opts.debugging_opts.mir_pass_memory_threshold;
+    let growth_limit = tcx.sess.opts.debugging_opts.mir_pass_growth_limit;
+    let time_passes = tcx.sess.opts.debugging_opts.time_mir_passes;
 
     if body.phase >= mir_phase {
         return;
     }
 
+    // Every pass gets its own `-Z self-profile` event, which records the body it ran on.
+    let body_path = if tcx.prof.enabled() {
+        let def_path =
+            ty::print::with_forced_impl_filename_line(|| tcx.def_path_str(source.def_id()));
+        format!("{}{}", def_path, source.promoted_suffix(tcx))
+    } else {
+        String::new()
+    };
+
     if validate {
         validate::Validator { when: format!("input to phase {:?}", mir_phase) }
             .run_pass(tcx, source, body);
     }
 
+    // With `-Z mir-provenance`, the statements of the body before optimizations derive from
+    // themselves, and every pass that changes the body updates their provenance.
+    let track_provenance =
+        mir_phase >= MirPhase::Inlined && tcx.sess.opts.debugging_opts.mir_provenance;
+    if track_provenance && body.statement_provenance.is_none() {
+        body.statement_provenance = Some(provenance::initial_provenance(body));
+    }
+
     let mut index = 0;
-    let mut run_pass = |pass: &dyn MirPass<'tcx>| {
+    let mut run_pass = |pass: &dyn MirPass<'tcx>, id: &str, iteration: usize| -> PassOutcome {
+        // Later iterations of a `Fixpoint` group don't overwrite the dumps of the first one.
+        let dump_name = match iteration {
+            0 => pass.name(),
+            _ => Cow::Owned(format!("{}-{}", pass.name(), iteration + 1)),
+        };
         let run_hooks = |body: &_, index, is_after| {
             dump_mir::on_mir_pass(
                 tcx,
                 &format_args!("{:03}-{:03}", phase_index, index),
-                &pass.name(),
+                &dump_name,
+                id,
                 source,
                 body,
                 is_after,
             );
         };
         run_hooks(body, index, false);
-        pass.run_pass(tcx, source, body);
+        let memory_before = memory_threshold.map(|_| (BodySize::of(body), get_resident()));
+        let size_before =
+            if growth_limit != 0 || time_passes { Some(BodySize::of(body)) } else { None };
+        let start = if time_passes { Some(Instant::now()) } else { None };
+        let outcome = {
+            let pass_name = pass.name();
+            let _timer = tcx.prof.generic_activity_with_args("mir_pass", &[&pass_name, &body_path]);
+            let snapshot = if track_provenance {
+                Some(provenance::StatementSnapshot::new(body))
+            } else {
+                None
+            };
+            let outcome = pass.run_pass(tcx, source, body);
+            if outcome == PassOutcome::Changed {
+                remove_nops(body);
+                // The analyses cached on the body don't notice every change by themselves.
+                body.invalidate_analyses();
+                if let Some(snapshot) = snapshot {
+                    let provenance = body.statement_provenance.as_ref().unwrap();
+                    body.statement_provenance = Some(snapshot.track(body, provenance));
+                }
+            }
+            outcome
+        };
+        if let (Some(start), Some(size_before)) = (start, size_before) {
+            record_pass_time(tcx.sess, &pass.name(), start.elapsed(), size_before, body);
+        }
+        // `-Z dump-mir` only dumps the passes it asks for, and then always after them too, so that
+        // a pass changing the body without saying so shows up in the dumps.
         run_hooks(body, index, true);
+        index += 1;
+        if tcx.sess.opts.debugging_opts.perf_stats {
+            record_pass_outcome(tcx.sess, &pass.name(), outcome);
+        }
+        if outcome == PassOutcome::Unchanged {
+            return outcome;
+        }
+
+        if let (Some(threshold), Some(before)) = (memory_threshold, memory_before) {
+            report_memory_growth(tcx, source, &pass.name(), before, body, threshold);
+        }
+        if let Some(size_before) = size_before.filter(|_| growth_limit != 0) {
+            check_growth(tcx, &pass.name(), size_before, body, growth_limit);
+        }
 
         if validate {
             validate::Validator { when: format!("after {} in phase {:?}", pass.name(), mir_phase) }
                 .run_pass(tcx, source, body);
         }
+        outcome
+    };
 
-        index += 1;
+    // With `-Z mir-opt-stop-after`, the optimizations after the named pass are skipped, while
+    // the passes codegen needs keep running. The pass may belong to an earlier phase.
+    let stop_after = tcx.sess.opts.debugging_opts.mir_opt_stop_after.as_deref();
+    let mut stopped = match stop_after.map(|name| (name, stop_after_phase(name))) {
+        Some((_, Some(stop_phase))) => stop_phase < mir_phase,
+        Some((name, None)) => tcx
+            .sess
+            .fatal(&format!("`-Z mir-opt-stop-after`: there is no MIR pass named `{}`", name)),
+        None => false,
+    };
+
+    let skipped = skipped_passes(tcx, instance);
+    // Shims have a pipeline of their own, which is not registered.
+    let registry = match instance {
+        InstanceDef::Item(_) => Some(pass_registry::PassRegistry::get(tcx)),
+        _ => None,
+    };
+    let mut ids = pass_registry::PhasePassIds::new(registry, mir_phase);
+    // Runs the enabled passes of `passes` along with the plugin passes around them, and returns
+    // whether any of them changed the body.
+    let mut run_enabled_passes = |passes: &[(&dyn MirPass<'tcx>, String)], iteration: usize| {
+        let mut changed = false;
+        for (pass, id) in passes {
+            for (pass, id) in with_plugin_passes(tcx.sess, *pass, id) {
+                let runs = pass_enabled(tcx.sess, pass, &id, &skipped).0
+                    && !(pass.is_optimization()
+                        && (stopped
+                            || !consider_optimizing(tcx, pass, source)
+                            || !consider_bisecting(tcx, pass, source)));
+                if runs {
+                    changed |= run_pass(pass, &id, iteration) == PassOutcome::Changed;
+                }
+                // The pipeline is cut after the pass, whether or not it ran.
+                if stop_after.map_or(false, |name| name_matches(name, &pass.name())) {
+                    stopped = true;
+                }
+            }
+        }
+        changed
     };
 
+    let max_iterations = fixpoint_iterations(tcx.sess);
+    // Plugin passes that run at the start or at the end of the phase form groups of their own.
+    let (phase_start, phase_end) = plugin_passes_in_phase(tcx.sess, mir_phase);
+    let passes = iter::once(&phase_start[..])
+        .chain(passes.iter().copied())
+        .chain(iter::once(&phase_end[..]));
     for pass_group in passes {
-        for pass in *pass_group {
-            run_pass(*pass);
+        // Identifiers follow the schedule, so that shuffling doesn't change them.
+        let mut pass_group: Vec<_> =
+            pass_group.iter().map(|&pass| (pass, ids.next(&pass.name()))).collect();
+        if let Some(seed) = shuffle_seed {
+            shuffle_passes(tcx, source, seed, &mut pass_group);
+        }
+        for (pass, id) in pass_group {
+            match pass.fixpoint_passes() {
+                Some(fixpoint_passes) => {
+                    let fixpoint_passes: Vec<_> =
+                        fixpoint_passes.iter().map(|&p| (p, ids.next(&p.name()))).collect();
+                    // The passes of the group are enabled one by one, but the group can be
+                    // disabled or skipped as a whole as well.
+                    if !pass_enabled(tcx.sess, pass, &id, &skipped).0 {
+                        continue;
+                    }
+                    for iteration in 0..max_iterations {
+                        if !run_enabled_passes(&fixpoint_passes, iteration) {
+                            break;
+                        }
+                    }
+                }
+                None => {
+                    run_enabled_passes(&[(pass, id)], 0);
+                }
+            }
         }
     }
 
@@ -228,6 +612,379 @@ pub fn run_passes(
         validate::Validator { when: format!("end of phase {:?}", mir_phase) }
             .run_pass(tcx, source, body);
     }
+
+    dump_mir::on_mir_phase_end(tcx, source, body);
+}
+
+/// Checks that every pass of the pipeline of `mir_phase` may run in that phase, and runs after the
+/// passes it requires. The pipelines of item bodies are checked once per session, when the pass
+/// registry is built.
+fn check_pass_order(passes: &[&[&dyn MirPass<'_>]], mir_phase: MirPhase) {
+    let mut pipeline = Vec::new();
+    for &pass in passes.iter().flat_map(|pass_group| pass_group.iter()) {
+        match pass.fixpoint_passes() {
+            Some(fixpoint_passes) => pipeline.extend(fixpoint_passes.iter().copied()),
+            None => pipeline.push(pass),
+        }
+    }
+
+    for (index, pass) in pipeline.iter().enumerate() {
+        let phases = pass.phases();
+        if !phases.contains(&mir_phase) {
+            bug!(
+                "MIR pass `{}` runs in phase {:?}, but may only run in {:?} to {:?}",
+                pass.name(),
+                mir_phase,
+                phases.start(),
+                phases.end()
+            );
+        }
+        for &required in pass.requires() {
+            let earlier = &pipeline[..index];
+            let provider = match earlier.iter().rposition(|p| name_matches(required, &p.name())) {
+                Some(provider) => provider,
+                None => bug!("MIR pass `{}` requires `{}` to run before it", pass.name(), required),
+            };
+            let provider_name = earlier[provider].name();
+            let invalidator = earlier[provider + 1..].iter().find(|p| {
+                p.invalidates().iter().any(|&invalidated| name_matches(invalidated, &provider_name))
+            });
+            if let Some(invalidator) = invalidator {
+                bug!(
+                    "MIR pass `{}` requires `{}`, but `{}` runs in between and invalidates it",
+                    pass.name(),
+                    provider_name,
+                    invalidator.name()
+                );
+            }
+        }
+    }
+}
+
+/// Returns the phase whose passes include the one `-Z mir-opt-stop-after` names, or `None` if no
+/// optimization phase does. Plugin passes cannot be named.
+fn stop_after_phase(name: &str) -> Option<MirPhase> {
+    fn contains_pass(passes: &[&[&dyn MirPass<'_>]], name: &str) -> bool {
+        let mut passes = passes.iter().flat_map(|pass_group| pass_group.iter());
+        passes.any(|&pass| match pass.fixpoint_passes() {
+            Some(fixpoint_passes) => fixpoint_passes.iter().any(|p| name_matches(name, &p.name())),
+            None => name_matches(name, &pass.name()),
+        })
+    }
+
+    if with_inlining_passes(|passes| contains_pass(passes, name)) {
+        Some(MirPhase::Inlined)
+    } else if with_optimization_passes(|passes| contains_pass(passes, name)) {
+        Some(MirPhase::Optimized)
+    } else {
+        None
+    }
+}
+
+/// Collects the pass names listed in `#[rustc_mir(skip = "Inline,ConstProp")]` on the item
+/// `instance` was built from. Naming a `Fixpoint` group skips all of its passes. Shims are never
+/// affected.
+fn skipped_passes(tcx: TyCtxt<'_>, instance: InstanceDef<'_>) -> Vec<String> {
+    let def_id = match instance {
+        InstanceDef::Item(def) => def.did,
+        _ => return Vec::new(),
+    };
+
+    let mut skipped = Vec::new();
+    for attr in tcx.get_attrs(def_id).iter().filter(|attr| attr.check_name(sym::rustc_mir)) {
+        for item in attr.meta_item_list().into_iter().flatten() {
+            if !item.check_name(sym::skip) {
+                continue;
+            }
+            match item.value_str() {
+                Some(names) => {
+                    skipped.extend(names.as_str().split(',').map(|name| name.trim().to_string()))
+                }
+                None => tcx.sess.span_err(
+                    item.span(),
+                    "`skip` expects a comma-separated list of MIR pass names",
+                ),
+            }
+        }
+    }
+    skipped
+}
+
+/// Returns `pass`, whose identifier is `id`, along with the plugin passes that run right before
+/// and after it, and their identifiers.
+fn with_plugin_passes<'a, 'tcx>(
+    sess: &'a Session,
+    pass: &'a dyn MirPass<'tcx>,
+    id: &str,
+) -> Vec<(&'a dyn MirPass<'tcx>, String)> {
+    let (before, after) = plugin::passes_around(sess, &pass.name());
+    let plugin_pass =
+        |pass: &'a plugin::PluginMirPass| (pass as &dyn MirPass<'tcx>, pass.name().into_owned());
+    let before = before.into_iter().map(plugin_pass);
+    let after = after.into_iter().map(plugin_pass);
+    before.chain(iter::once((pass, id.to_string()))).chain(after).collect()
+}
+
+/// What decided whether a pass runs.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum PassToggle {
+    /// `-Z mir-enable-passes`.
+    CommandLine,
+    /// `#[rustc_mir(skip)]` on the item.
+    SkipAttribute,
+    /// `MirPass::is_enabled`.
+    Session,
+}
+
+impl PassToggle {
+    fn describe(self) -> &'static str {
+        match self {
+            PassToggle::CommandLine => "-Z mir-enable-passes",
+            PassToggle::SkipAttribute => "#[rustc_mir(skip)]",
+            PassToggle::Session => "the session options",
+        }
+    }
+}
+
+/// Returns the plugin passes that run at the start and at the end of `phase`.
+fn plugin_passes_in_phase<'a, 'tcx>(
+    sess: &'a Session,
+    phase: MirPhase,
+) -> (Vec<&'a dyn MirPass<'tcx>>, Vec<&'a dyn MirPass<'tcx>>) {
+    let (start, end) = plugin::passes_in_phase(sess, phase);
+    let start = start.into_iter().map(|pass| pass as &dyn MirPass<'tcx>).collect();
+    let end = end.into_iter().map(|pass| pass as &dyn MirPass<'tcx>).collect();
+    (start, end)
+}
+
+/// Whether `pass`, whose identifier is `id`, runs on a body for which `#[rustc_mir(skip)]` lists
+/// `skipped`, along with what decided it.
+fn pass_enabled(
+    sess: &Session,
+    pass: &dyn MirPass<'_>,
+    id: &str,
+    skipped: &[String],
+) -> (bool, PassToggle) {
+    let name = pass.name();
+    if let Some(enabled) = enabled_override(sess, &name, id) {
+        (enabled, PassToggle::CommandLine)
+    } else if is_skipped(skipped, &name, id) {
+        (false, PassToggle::SkipAttribute)
+    } else {
+        (pass.is_enabled(sess), PassToggle::Session)
+    }
+}
+
+/// Uses up a unit of `-Z fuel` for running the optimization `pass` on the body of `source`, and
+/// returns whether there was any left.
+fn consider_optimizing(
+    tcx: TyCtxt<'tcx>,
+    pass: &dyn MirPass<'tcx>,
+    source: MirSource<'tcx>,
+) -> bool {
+    let crate_name = tcx.crate_name(LOCAL_CRATE).as_str();
+    if tcx.sess.print_fuel_crate.as_deref() == Some(&*crate_name) {
+        *tcx.sess.print_fuel_by_mir_pass.lock().entry(pass.name().into_owned()).or_default() += 1;
+    }
+    tcx.sess.consider_optimizing(&crate_name, || describe_pass_run(tcx, pass, source))
+}
+
+/// Numbers this run of the optimization `pass` on the body of `source` for
+/// `-Z mir-opt-bisect-limit`, and returns whether it is within the limit.
+fn consider_bisecting(
+    tcx: TyCtxt<'tcx>,
+    pass: &dyn MirPass<'tcx>,
+    source: MirSource<'tcx>,
+) -> bool {
+    tcx.sess.consider_mir_opt_bisect(|| describe_pass_run(tcx, pass, source))
+}
+
+fn describe_pass_run(
+    tcx: TyCtxt<'tcx>,
+    pass: &dyn MirPass<'tcx>,
+    source: MirSource<'tcx>,
+) -> String {
+    let def_path = ty::print::with_forced_impl_filename_line(|| tcx.def_path_str(source.def_id()));
+    format!("MIR pass {} on `{}{}`", pass.name(), def_path, source.promoted_suffix(tcx))
+}
+
+/// The maximum number of times a `Fixpoint` group runs.
+fn fixpoint_iterations(sess: &Session) -> usize {
+    if sess.opts.debugging_opts.mir_opt_level >= 2 {
+        sess.opts.debugging_opts.mir_fixpoint_iterations.max(1)
+    } else {
+        1
+    }
+}
+
+/// Whether `pass_name` is listed in `skipped`.
+fn is_skipped(skipped: &[String], pass_name: &str, id: &str) -> bool {
+    skipped.iter().any(|name| name == id || name_matches(name, pass_name))
+}
+
+/// Whether `-Z mir-enable-passes` forces the pass `pass_name` on or off. The last mention of a
+/// pass wins, and takes precedence over `MirPass::is_enabled` and `#[rustc_mir(skip)]`.
+fn enabled_override(sess: &Session, pass_name: &str, id: &str) -> Option<bool> {
+    let overrides = &sess.opts.debugging_opts.mir_enable_passes;
+    let refers_to_pass = |name: &str| name == id || name_matches(name, pass_name);
+    overrides.iter().rev().find(|(name, _)| refers_to_pass(name)).map(|&(_, on)| on)
+}
+
+/// Whether `name` refers to `pass_name`. Passes that are instantiated several times with a
+/// suffix (e.g. `SimplifyCfg-initial`) can be named either by full name or all at once.
+fn name_matches(name: &str, pass_name: &str) -> bool {
+    let base_name = pass_name.split('-').next().unwrap_or(pass_name);
+    name == pass_name || name == base_name
+}
+
+/// Counts how often `pass_name` ran and how often it changed the body, for `-Z perf-stats`.
+fn record_pass_outcome(sess: &Session, pass_name: &str, outcome: PassOutcome) {
+    let mut outcomes = sess.perf_stats.mir_pass_outcomes.lock();
+    let (runs, changes) = outcomes.entry(pass_name.to_string()).or_default();
+    *runs += 1;
+    if outcome == PassOutcome::Changed {
+        *changes += 1;
+    }
+}
+
+/// Adds the time `pass_name` took and how it changed the size of `body`, for
+/// `-Z time-mir-passes`.
+fn record_pass_time(
+    sess: &Session,
+    pass_name: &str,
+    time: Duration,
+    size_before: BodySize,
+    body: &Body<'_>,
+) {
+    let size_after = BodySize::of(body);
+    let delta = |before: usize, after: usize| after as isize - before as isize;
+    let mut times = sess.perf_stats.mir_pass_times.lock();
+    let pass_time = times.entry(pass_name.to_string()).or_default();
+    pass_time.runs += 1;
+    pass_time.time += time;
+    pass_time.blocks_delta += delta(size_before.basic_blocks, size_after.basic_blocks);
+    pass_time.statements_delta += delta(size_before.statements, size_after.statements);
+    pass_time.locals_delta += delta(size_before.locals, size_after.locals);
+}
+
+/// Sweeps away the `Nop`s a pass left behind (passes use them to avoid shifting statement
+/// indices), so that neither dumps nor later passes have to deal with them.
+fn remove_nops(body: &mut Body<'_>) {
+    let is_nop = |statement: &Statement<'_>| statement.kind == StatementKind::Nop;
+    if !body.basic_blocks().iter().any(|data| data.statements.iter().any(is_nop)) {
+        // Don't invalidate the predecessor cache for nothing.
+        return;
+    }
+
+    for data in body.basic_blocks_mut() {
+        data.statements.retain(|statement| !is_nop(statement));
+        // Give back the memory of blocks that lost most of their statements.
+        if data.statements.capacity() > 2 * data.statements.len() {
+            data.statements.shrink_to_fit();
+        }
+    }
+}
+
+/// Randomly permutes every run of adjacent order-independent passes in `passes`, along with
+/// their identifiers. The order only depends on `seed` and on the body, so that a failure can be
+/// reproduced.
+fn shuffle_passes<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    source: MirSource<'tcx>,
+    seed: usize,
+    passes: &mut [(&dyn MirPass<'tcx>, String)],
+) {
+    let body_hash = tcx.def_path_hash(source.def_id()).0.to_smaller_hash();
+    let promoted = source.promoted.map_or(0, |p| p.index() as u64 + 1);
+    let mut state = seed as u64 ^ body_hash ^ promoted.rotate_left(32);
+    // SplitMix64, which is plenty for picking permutations.
+    let mut next = || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+
+    let mut start = 0;
+    while start < passes.len() {
+        let len =
+            passes[start..].iter().take_while(|(pass, _)| pass.is_order_independent()).count();
+        // Fisher-Yates on `passes[start..start + len]`.
+        for i in (1..len).rev() {
+            let j = (next() % (i as u64 + 1)) as usize;
+            passes.swap(start + i, start + j);
+        }
+        start += len.max(1);
+    }
+}
+
+/// Reports `pass_name` if it grew the estimated size of `body` by more than `threshold_kb`
+/// kilobytes. Used by `-Z mir-pass-memory-threshold` to attribute MIR blow-ups to passes.
+fn report_memory_growth<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    source: MirSource<'tcx>,
+    pass_name: &str,
+    (size_before, rss_before): (BodySize, Option<usize>),
+    body: &Body<'tcx>,
+    threshold_kb: usize,
+) {
+    let size_after = BodySize::of(body);
+    let growth = size_after.estimated_bytes().saturating_sub(size_before.estimated_bytes());
+    if growth <= threshold_kb * 1024 {
+        return;
+    }
+
+    let rss = match (rss_before, get_resident()) {
+        (Some(before), Some(after)) => {
+            format!("; rss: {:+}KB", (after as i64 - before as i64) / 1024)
+        }
+        _ => String::new(),
+    };
+    let def_path = ty::print::with_forced_impl_filename_line(|| tcx.def_path_str(source.def_id()));
+    let promoted = source.promoted_suffix(tcx);
+    eprintln!(
+        "MIR pass `{}` grew `{}{}` by {}KB: {} -> {}{}",
+        pass_name,
+        def_path,
+        promoted,
+        growth / 1024,
+        size_before,
+        size_after,
+        rss,
+    );
+}
+
+/// Bodies smaller than this are never reported by `check_growth`, since inlining a few calls
+/// into a tiny body easily makes it many times bigger.
+const MIN_REPORTED_GROWTH_BYTES: usize = 64 * 1024;
+
+/// Warns if `pass_name` made `body` more than `limit` times bigger than `size_before`, or ICEs
+/// with `-Z mir-pass-growth-strict`. Such growth usually means that a pass is running away.
+fn check_growth<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    pass_name: &str,
+    size_before: BodySize,
+    body: &Body<'tcx>,
+    limit: usize,
+) {
+    let size_after = BodySize::of(body);
+    let (before, after) = (size_before.estimated_bytes(), size_after.estimated_bytes());
+    if after < MIN_REPORTED_GROWTH_BYTES || after <= before.saturating_mul(limit) {
+        return;
+    }
+
+    let msg = format!(
+        "MIR pass `{}` grew this body more than {} times: {} -> {}",
+        pass_name, limit, size_before, size_after,
+    );
+    if tcx.sess.opts.debugging_opts.mir_pass_growth_strict {
+        span_bug!(body.span, "{}", msg);
+    }
+    tcx.sess
+        .struct_span_warn(body.span, &msg)
+        .note("use `-Z mir-pass-growth-limit` to change the limit, or `0` to disable the check")
+        .emit();
 }
 
 fn mir_const_qualif(tcx: TyCtxt<'_>, def: ty::WithOptConstParam<LocalDefId>) -> ConstQualifs {
@@ -288,28 +1045,40 @@ fn mir_const<'tcx>(
         None,
         "mir_map",
         &0,
-        MirSource { instance: InstanceDef::Item(def.to_global()), promoted: None },
+        MirSource {
+            instance: InstanceDef::Item(def.to_global()),
+            promoted: None,
+            promoted_span: None,
+        },
         &body,
         |_, _| Ok(()),
     );
 
-    run_passes(
-        tcx,
-        &mut body,
-        InstanceDef::Item(def.to_global()),
-        None,
-        MirPhase::Const,
-        &[&[
-            // MIR-level lints.
-            &check_packed_ref::CheckPackedRef,
-            // What we need to do constant evaluation.
-            &simplify::SimplifyCfg::new("initial"),
-            &rustc_peek::SanityCheck,
-        ]],
-    );
+    with_const_passes(|passes| {
+        run_passes(
+            tcx,
+            &mut body,
+            InstanceDef::Item(def.to_global()),
+            None,
+            MirPhase::Const,
+            passes,
+        )
+    });
     tcx.alloc_steal_mir(body)
 }
 
+/// Calls `f` with the passes of `MirPhase::Const`. Like the other `with_*_passes` functions, this
+/// is shared by the queries and `-Z print-mir-pass-pipeline`.
+fn with_const_passes<'tcx, R>(f: impl FnOnce(&[&[&dyn MirPass<'tcx>]]) -> R) -> R {
+    f(&[&[
+        // MIR-level lints.
+        &check_packed_ref::CheckPackedRef,
+        // What we need to do constant evaluation.
+        &simplify::SimplifyCfg::new("initial"),
+        &rustc_peek::SanityCheck,
+    ]])
+}
+
 fn mir_validated(
     tcx: TyCtxt<'tcx>,
     def: ty::WithOptConstParam<LocalDefId>,
@@ -331,30 +1100,36 @@ fn mir_validated(
     }
     body.required_consts = required_consts;
 
+    let instance = InstanceDef::Item(def.to_global());
     let promote_pass = promote_consts::PromoteTemps::default();
+    with_validated_passes(&promote_pass, |passes| {
+        run_passes(tcx, &mut body, instance, None, MirPhase::Validated, passes)
+    });
+
+    // The promoted bodies join their parent in this phase, and get validated and dumped at its end
+    // the way the parent does.
+    let mut promoted = promote_pass.promoted_fragments.into_inner();
+    deduplicate_promoted::deduplicate_promoted(tcx, def.did.to_def_id(), &mut body, &mut promoted);
+    for (p, body) in promoted.iter_enumerated_mut() {
+        run_passes(tcx, body, instance, Some(p), MirPhase::Validated, &[]);
+    }
+    (tcx.alloc_steal_mir(body), tcx.alloc_steal_promoted(promoted))
+}
+
+/// Calls `f` with the passes of `MirPhase::Validated`, which promote into `promote_pass`.
+fn with_validated_passes<'tcx, R>(
+    promote_pass: &promote_consts::PromoteTemps<'tcx>,
+    f: impl FnOnce(&[&[&dyn MirPass<'tcx>]]) -> R,
+) -> R {
     let promote: &[&dyn MirPass<'tcx>] = &[
         // What we need to run borrowck etc.
-        &promote_pass,
+        promote_pass,
         &simplify::SimplifyCfg::new("qualify-consts"),
     ];
 
-    let opt_coverage: &[&dyn MirPass<'tcx>] = if tcx.sess.opts.debugging_opts.instrument_coverage {
-        &[&instrument_coverage::InstrumentCoverage]
-    } else {
-        &[]
-    };
-
-    run_passes(
-        tcx,
-        &mut body,
-        InstanceDef::Item(def.to_global()),
-        None,
-        MirPhase::Validated,
-        &[promote, opt_coverage],
-    );
+    let opt_coverage: &[&dyn MirPass<'tcx>] = &[&instrument_coverage::InstrumentCoverage];
 
-    let promoted = promote_pass.promoted_fragments.into_inner();
-    (tcx.alloc_steal_mir(body), tcx.alloc_steal_promoted(promoted))
+    f(&[promote, opt_coverage])
 }
 
 fn mir_drops_elaborated_and_const_checked<'tcx>(
@@ -390,6 +1165,23 @@ fn run_post_borrowck_cleanup_passes<'tcx>(
 ) {
     debug!("post_borrowck_cleanup({:?})", def_id);
 
+    with_post_borrowck_cleanup_passes(tcx, |passes| {
+        run_passes(
+            tcx,
+            body,
+            InstanceDef::Item(ty::WithOptConstParam::unknown(def_id.to_def_id())),
+            promoted,
+            MirPhase::DropElab,
+            passes,
+        )
+    });
+}
+
+/// Calls `f` with the passes of `MirPhase::DropElab`.
+fn with_post_borrowck_cleanup_passes<'tcx, R>(
+    tcx: TyCtxt<'tcx>,
+    f: impl FnOnce(&[&[&dyn MirPass<'tcx>]]) -> R,
+) -> R {
     let post_borrowck_cleanup: &[&dyn MirPass<'tcx>] = &[
         // Remove all things only needed by analysis
         &no_landing_pads::NoLandingPads::new(tcx),
@@ -410,81 +1202,165 @@ fn run_post_borrowck_cleanup_passes<'tcx>(
         &simplify::SimplifyCfg::new("elaborate-drops"),
     ];
 
-    run_passes(
-        tcx,
-        body,
-        InstanceDef::Item(ty::WithOptConstParam::unknown(def_id.to_def_id())),
-        promoted,
-        MirPhase::DropElab,
-        &[post_borrowck_cleanup],
-    );
+    f(&[post_borrowck_cleanup])
 }
 
-fn run_optimization_passes<'tcx>(
+fn run_inlining_passes<'tcx>(
     tcx: TyCtxt<'tcx>,
     body: &mut Body<'tcx>,
     def_id: LocalDefId,
     promoted: Option<Promoted>,
 ) {
-    let optimizations: &[&dyn MirPass<'tcx>] = &[
+    with_inlining_passes(|passes| {
+        run_passes(
+            tcx,
+            body,
+            InstanceDef::Item(ty::WithOptConstParam::unknown(def_id.to_def_id())),
+            promoted,
+            MirPhase::Inlined,
+            passes,
+        )
+    });
+}
+
+/// Calls `f` with the passes of `MirPhase::Inlined`.
+fn with_inlining_passes<'tcx, R>(f: impl FnOnce(&[&[&dyn MirPass<'tcx>]]) -> R) -> R {
+    // Passes that only run at some `-Z mir-opt-level` say so in their `is_enabled`, so that
+    // optimizations and the passes codegen needs form a single pipeline.
+    f(&[&[
+        // Stripping debuginfo first frees the memory it uses and lets every later pass ignore it.
+        &WithMinOptLevel(1, strip_debuginfo::StripDebugInfo),
         &unreachable_prop::UnreachablePropagation,
-        &uninhabited_enum_branching::UninhabitedEnumBranching,
-        &simplify::SimplifyCfg::new("after-uninhabited-enum-branching"),
+        // Drops that turn out to be unneeded shouldn't count towards the cost of inlining.
+        &WithMinOptLevel(2, remove_unneeded_drops::RemoveUnneededDrops),
+        &WithMinOptLevel(1, uninhabited_enum_branching::UninhabitedEnumBranching),
+        &WithMinOptLevel(1, simplify::SimplifyCfg::new("after-uninhabited-enum-branching")),
+        // Calls on trait objects of a known type can be inlined once they are direct calls.
+        &WithMinOptLevel(2, devirtualize::Devirtualize),
         &inline::Inline,
+    ]])
+}
+
+fn run_optimization_passes<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    body: &mut Body<'tcx>,
+    def_id: LocalDefId,
+    promoted: Option<Promoted>,
+) {
+    with_optimization_passes(|passes| {
+        run_passes(
+            tcx,
+            body,
+            InstanceDef::Item(ty::WithOptConstParam::unknown(def_id.to_def_id())),
+            promoted,
+            MirPhase::Optimized,
+            passes,
+        )
+    });
+}
+
+/// Calls `f` with the passes of `MirPhase::Optimized`.
+fn with_optimization_passes<'tcx, R>(f: impl FnOnce(&[&[&dyn MirPass<'tcx>]]) -> R) -> R {
+    // Removing branches makes more drop flags and values constant, and the other way around.
+    let after_const_prop: &[&dyn MirPass<'tcx>] = &[
+        &simplify_branches::SimplifyBranches::new("after-const-prop"),
+        &remove_dead_drop_flags::RemoveDeadDropFlags,
+        &simplify_branches::SimplifyBranches::new("after-remove-dead-drop-flags"),
+        &simplify::SimplifyCfg::new("after-const-prop"),
+        &instcombine::InstCombine::new("after-const-prop"),
+        // Only worth it if the group runs more than once.
+        &WithMinOptLevel(2, const_prop::ConstPropRepeat::new("after-instcombine")),
+    ];
+
+    // Like in `with_inlining_passes`, optimizations and the passes codegen needs form a single
+    // pipeline.
+    let optimizations: &[&dyn MirPass<'tcx>] = &[
+        // Gives the passes that follow critical edges and preheaders to put statements on.
+        &WithMinOptLevel(2, canonicalize_cfg::CanonicalizeCfg),
+        &WithMinOptLevel(1, remove_redundant_reborrows::RemoveRedundantReborrows),
         // Lowering generator control-flow and variables has to happen before we do anything else
         // to them. We do this inside the "optimizations" block so that it can benefit from
         // optimizations that run before, that might be harder to do on the state machine than MIR
-        // with async primitives.
+        // with async primitives. Even if we don't do optimizations, we still have to lower
+        // generators for codegen.
         &generator::StateTransform,
-        &instcombine::InstCombine,
+        // Inlined operators of the standard library check for overflow where this crate doesn't.
+        &WithMinOptLevel(1, lower_checked_arithmetic::LowerCheckedArithmetic),
+        // The intrinsics that inlining exposed are opaque calls to the passes that follow.
+        &WithMinOptLevel(1, lower_intrinsics::LowerIntrinsics),
+        &WithMinOptLevel(1, instcombine::InstCombine::default()),
+        // Inlining brings coercions and the virtual calls they feed into the same body.
+        &WithMinOptLevel(2, devirtualize::Devirtualize),
+        // Inlined generic code drops values whose types are only known now.
+        &WithMinOptLevel(2, remove_unneeded_drops::RemoveUnneededDrops),
+        // Unrolled loops leave counters with known values behind for the passes that follow.
+        &WithMinOptLevel(2, unroll_loops::UnrollLoops),
+        &WithMinOptLevel(2, gvn::GlobalValueNumbering),
+        // FIXME(#70073): This pass is responsible for both optimization as well as some lints.
         &const_prop::ConstProp,
-        &simplify_branches::SimplifyBranches::new("after-const-prop"),
+        &WithMinOptLevel(1, Fixpoint::new("after-const-prop", after_const_prop)),
+        // Constant propagation leaves divisors behind that `InstCombine` didn't know yet.
+        &WithMinOptLevel(2, strength_reduction::StrengthReduction),
         // Run deaggregation here because:
-        //   1. Some codegen backends require it
+        //   1. Some codegen backends require it, so it runs even without optimizations
         //   2. It creates additional possibilities for some MIR optimizations to trigger
         // FIXME(#70073): Why is this done here and not in `post_borrowck_cleanup`?
         &deaggregator::Deaggregator,
+        &WithMinOptLevel(2, discriminant_propagation::DiscriminantPropagation),
+        &WithMinOptLevel(2, sroa::ScalarReplacementOfAggregates),
         &simplify_try::SimplifyArmIdentity,
-        &simplify_try::SimplifyBranchSame,
+        &WithMinOptLevel(1, simplify_try::SimplifyBranchSame),
+        &WithMinOptLevel(1, merge_identical_arms::MergeIdenticalArms),
+        &WithMinOptLevel(2, early_otherwise_branch::EarlyOtherwiseBranch),
+        &WithMinOptLevel(2, match_branches::MatchBranchSimplification),
         &copy_prop::CopyPropagation,
-        &simplify_branches::SimplifyBranches::new("after-copy-prop"),
-        &remove_noop_landing_pads::RemoveNoopLandingPads,
-        &simplify::SimplifyCfg::new("after-remove-noop-landing-pads"),
-        &simplify::SimplifyCfg::new("final"),
+        &WithMinOptLevel(2, dead_store_elimination::DeadStoreElimination),
+        &WithMinOptLevel(2, dest_prop::DestinationPropagation),
+        &WithMinOptLevel(1, simplify_branches::SimplifyBranches::new("after-copy-prop")),
+        &WithMinOptLevel(2, bounds_check_elimination::BoundsCheckElimination),
+        &WithMinOptLevel(2, jump_threading::JumpThreading),
+        &WithMinOptLevel(2, loop_invariant_code_motion::LoopInvariantCodeMotion),
+        &WithMinOptLevel(2, loop_idioms::LoopIdioms),
+        &WithMinOptLevel(2, sink_statements::SinkStatements),
+        // At `-Z mir-opt-level=3`, the passes since `ConstProp` leave enough behind to be worth
+        // another round of propagation and cleanup.
+        &WithMinOptLevel(3, const_prop::ConstPropRepeat::new("repeat")),
+        &WithMinOptLevel(3, simplify_branches::SimplifyBranches::new("after-const-prop-repeat")),
+        &WithMinOptLevel(3, simplify::SimplifyCfg::new("after-const-prop-repeat")),
+        &WithMinOptLevel(1, remove_noop_landing_pads::RemoveNoopLandingPads),
+        &WithMinOptLevel(1, simplify::SimplifyCfg::new("after-remove-noop-landing-pads")),
+        &WithMinOptLevel(2, merge_returns::MergeReturns),
+        &WithMinOptLevel(2, deduplicate_blocks::DeduplicateBlocks),
+        &WithMinOptLevel(1, simplify::SimplifyCfg::new("final")),
         &nrvo::RenameReturnPlace,
-        &simplify::SimplifyLocals,
-    ];
-
-    let no_optimizations: &[&dyn MirPass<'tcx>] = &[
-        // Even if we don't do optimizations, we still have to lower generators for codegen.
-        &generator::StateTransform,
-        // FIXME(#70073): This pass is responsible for both optimization as well as some lints.
-        &const_prop::ConstProp,
-        // Even if we don't do optimizations, still run deaggregation because some backends assume
-        // that deaggregation always occurs.
-        &deaggregator::Deaggregator,
+        &WithMinOptLevel(2, remove_zsts::RemoveZsts),
+        &WithMinOptLevel(1, simplify::SimplifyLocals),
+        &WithMinOptLevel(2, remove_storage_markers::RemoveStorageMarkers),
+        &instrument_mcount::InstrumentMcount,
     ];
 
     let pre_codegen_cleanup: &[&dyn MirPass<'tcx>] = &[
+        // Counts the blocks of the optimized MIR, before anything is split for codegen.
+        &instrument_block_counters::InstrumentBlockCounters,
+        // Lowering 128-bit operations to calls is only done for codegen, after all optimizations
+        // have had the chance to fold them.
+        &lower_128bit::Lower128Bit,
+        &reaggregate::Reaggregate,
+        &insert_assumes::InsertAssumes,
+        &split_oversized_blocks::SplitOversizedBlocks,
+        // The outlined blocks get no passes of their own, so they leave once they are final.
+        &outline_cold_blocks::OutlineColdBlocks,
         &add_call_guards::CriticalCallEdges,
+        // Must run last, as any later change to the body would invalidate the weights and hints.
+        // Reordering the blocks keeps both up to date.
+        &branch_weights::BranchWeights,
+        &reorder_blocks::ReorderBlocks,
+        &stack_slot_hints::StackSlotHints,
         // Dump the end result for testing and debugging purposes.
         &dump_mir::Marker("PreCodegen"),
     ];
 
-    let mir_opt_level = tcx.sess.opts.debugging_opts.mir_opt_level;
-
-    #[rustfmt::skip]
-    run_passes(
-        tcx,
-        body,
-        InstanceDef::Item(ty::WithOptConstParam::unknown(def_id.to_def_id())),
-        promoted,
-        MirPhase::Optimized,
-        &[
-            if mir_opt_level > 0 { optimizations } else { no_optimizations },
-            pre_codegen_cleanup,
-        ],
-    );
+    f(&[optimizations, pre_codegen_cleanup])
 }
 
 fn optimized_mir<'tcx>(tcx: TyCtxt<'tcx>, did: DefId) -> &'tcx Body<'tcx> {
@@ -506,6 +1382,19 @@ fn optimized_mir_of_const_arg<'tcx>(
     ))
 }
 
+fn mir_inlined<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def: ty::WithOptConstParam<LocalDefId>,
+) -> &'tcx Steal<Body<'tcx>> {
+    if let Some(def) = def.try_upgrade(tcx) {
+        return tcx.mir_inlined(def);
+    }
+
+    let mut body = tcx.mir_drops_elaborated_and_const_checked(def).steal();
+    run_inlining_passes(tcx, &mut body, def.did, None);
+    tcx.alloc_steal_mir(body)
+}
+
 fn inner_optimized_mir(tcx: TyCtxt<'_>, def: ty::WithOptConstParam<LocalDefId>) -> Body<'_> {
     if tcx.is_constructor(def.did.to_def_id()) {
         // There's no reason to run all of the MIR passes on constructors when
@@ -515,7 +1404,7 @@ fn inner_optimized_mir(tcx: TyCtxt<'_>, def: ty::WithOptConstParam<LocalDefId>)
         return shim::build_adt_ctor(tcx, def.did.to_def_id());
     }
 
-    let mut body = tcx.mir_drops_elaborated_and_const_checked(def).steal();
+    let mut body = tcx.mir_inlined(def).steal();
     run_optimization_passes(tcx, &mut body, def.did, None);
 
     debug_assert!(!body.has_free_regions(), "Free regions in optimized MIR");
@@ -541,6 +1430,7 @@ fn promoted_mir<'tcx>(
 
     for (p, mut body) in promoted.iter_enumerated_mut() {
         run_post_borrowck_cleanup_passes(tcx, &mut body, def.did, Some(p));
+        run_inlining_passes(tcx, &mut body, def.did, Some(p));
         run_optimization_passes(tcx, &mut body, def.did, Some(p));
     }
 
@@ -1,7 +1,7 @@
 //! This pass removes the unwind branch of all the terminators when the no-landing-pads option is
 //! specified.
 
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 use rustc_middle::mir::visit::MutVisitor;
 use rustc_middle::mir::*;
 use rustc_middle::ty::TyCtxt;
@@ -18,8 +18,17 @@ impl<'tcx> NoLandingPads<'tcx> {
 }
 
 impl<'tcx> MirPass<'tcx> for NoLandingPads<'tcx> {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, _: MirSource<'tcx>, body: &mut Body<'tcx>) {
-        no_landing_pads(tcx, body)
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        if tcx.sess.panic_strategy() != PanicStrategy::Abort {
+            return PassOutcome::Unchanged;
+        }
+        no_landing_pads(tcx, body);
+        PassOutcome::Changed
     }
 }
 
@@ -3,8 +3,9 @@ use rustc_index::bit_set::HybridBitSet;
 use rustc_middle::mir::visit::{MutVisitor, PlaceContext, Visitor};
 use rustc_middle::mir::{self, BasicBlock, Local, Location};
 use rustc_middle::ty::TyCtxt;
+use rustc_session::Session;
 
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 
 /// This pass looks for MIR that always copies the same local into the return place and eliminates
 /// the copy by renaming all uses of that local to `_0`.
@@ -31,16 +32,25 @@ use crate::transform::{MirPass, MirSource};
 pub struct RenameReturnPlace;
 
 impl<'tcx> MirPass<'tcx> for RenameReturnPlace {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, src: MirSource<'tcx>, body: &mut mir::Body<'tcx>) {
-        if tcx.sess.opts.debugging_opts.mir_opt_level == 0 {
-            return;
-        }
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.mir_opt_level > 0
+    }
+
+    fn is_optimization(&self) -> bool {
+        true
+    }
 
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        src: MirSource<'tcx>,
+        body: &mut mir::Body<'tcx>,
+    ) -> PassOutcome {
         let returned_local = match local_eligible_for_nrvo(body) {
             Some(l) => l,
             None => {
                 debug!("`{:?}` was ineligible for NRVO", src.def_id());
-                return;
+                return PassOutcome::Unchanged;
             }
         };
 
@@ -50,13 +60,10 @@ impl<'tcx> MirPass<'tcx> for RenameReturnPlace {
             returned_local
         );
 
+        // The `Nop`s left behind for statements made useless by the renaming are cleaned up by
+        // the pass manager.
         RenameToReturnPlace { tcx, to_rename: returned_local }.visit_body(body);
 
-        // Clean up the `NOP`s we inserted for statements made useless by our renaming.
-        for block_data in body.basic_blocks_mut() {
-            block_data.statements.retain(|stmt| stmt.kind != mir::StatementKind::Nop);
-        }
-
         // Overwrite the debuginfo of `_0` with that of the renamed local.
         let (renamed_decl, ret_decl) =
             body.local_decls.pick2_mut(returned_local, mir::RETURN_PLACE);
@@ -70,6 +77,7 @@ impl<'tcx> MirPass<'tcx> for RenameReturnPlace {
 
         // The return place is always mutable.
         ret_decl.mutability = Mutability::Mut;
+        PassOutcome::Changed
     }
 }
 
@@ -118,8 +126,16 @@ fn find_local_assigned_to_return_place(
     start: BasicBlock,
     body: &mut mir::Body<'_>,
 ) -> Option<Local> {
-    let mut block = start;
     let mut seen = HybridBitSet::new_empty(body.basic_blocks().len());
+    find_local_assigned_on_all_paths(start, body, &mut seen)
+}
+
+fn find_local_assigned_on_all_paths(
+    start: BasicBlock,
+    body: &mut mir::Body<'_>,
+    seen: &mut HybridBitSet<BasicBlock>,
+) -> Option<Local> {
+    let mut block = start;
 
     // Iterate as long as `block` has exactly one predecessor that we have not yet visited.
     while seen.insert(block) {
@@ -132,6 +148,16 @@ fn find_local_assigned_to_return_place(
 
         match body.predecessors()[block].as_slice() {
             &[pred] => block = pred,
+            // A block that several paths lead to, like the single return block left by
+            // `MergeReturns`, is fine if all of them assign the same local.
+            preds if preds.len() > 1 => {
+                let preds = preds.to_vec();
+                let mut locals = preds
+                    .into_iter()
+                    .map(|pred| find_local_assigned_on_all_paths(pred, body, seen));
+                let first = locals.next()??;
+                return if locals.all(|local| local == Some(first)) { Some(first) } else { None };
+            }
             _ => return None,
         }
     }
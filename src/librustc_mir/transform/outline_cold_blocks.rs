@@ -0,0 +1,458 @@
+//! Moves the blocks of a function that only lead to a panic into functions of their own.
+//!
+//! With `-Z mir-outline-cold-blocks`, the blocks from which every path ends in a call to a
+//! `#[cold]` function that never returns, like the functions behind `panic!`, are taken out of
+//! the body. Each group of them that is only entered through one block becomes the body of an
+//! `InstanceDef::Outlined` function, which is kept in `Body::outlined`, and its entry block is
+//! replaced with a call to that function. Codegen marks the outlined functions as cold and never
+//! inlines them, so that their code, which builds panic messages and drops values on the way
+//! out, doesn't take up room in the body it came from.
+//!
+//! The outlined function takes the locals its blocks share with the rest of the body as
+//! arguments: by value if they are `Copy` and neither changed by the blocks nor borrowed, and as
+//! raw pointers to the locals of the caller otherwise. Unwinding out of it continues where
+//! unwinding out of its blocks did, so all of them have to unwind to the same block.
+
+use crate::transform::required_consts::RequiredConstsVisitor;
+use crate::transform::{simplify, MirPass, MirSource, PassOutcome};
+use crate::util::dump_mir;
+use rustc_hir as hir;
+use rustc_hir::def_id::DefId;
+use rustc_index::bit_set::BitSet;
+use rustc_index::vec::{Idx, IndexVec};
+use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
+use rustc_middle::mir::interpret::Scalar;
+use rustc_middle::mir::visit::*;
+use rustc_middle::mir::*;
+use rustc_middle::ty::subst::InternalSubsts;
+use rustc_middle::ty::{self, Instance, InstanceDef, TyCtxt};
+use rustc_session::Session;
+use rustc_target::spec::abi::Abi;
+use std::ops::RangeInclusive;
+
+pub struct OutlineColdBlocks;
+
+/// Regions with fewer blocks and statements than this stay where they are, as the call that
+/// would replace them is about as big.
+const MIN_REGION_SIZE: usize = 4;
+
+impl<'tcx> MirPass<'tcx> for OutlineColdBlocks {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.mir_outline_cold_blocks
+    }
+
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        // The outlined bodies don't go through any passes of their own.
+        MirPhase::Optimized..=MirPhase::Optimized
+    }
+
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        // Bodies that may be evaluated at compile time can't call functions without a `DefId`,
+        // and the outlined functions of generic bodies would need substitutions of their own.
+        let def_id = match source.instance {
+            InstanceDef::Item(def) if source.promoted.is_none() => def.did,
+            _ => return PassOutcome::Unchanged,
+        };
+        if body.generator_kind.is_some()
+            || def_id
+                .as_local()
+                .map_or(true, |def_id| tcx.hir().body_const_context(def_id).is_some())
+            || tcx.generics_of(def_id).requires_monomorphization(tcx)
+        {
+            return PassOutcome::Unchanged;
+        }
+        // Outlined blocks would report their own location to `#[track_caller]` callees, and
+        // don't belong in naked or already cold functions.
+        let unsupported_attrs =
+            CodegenFnAttrFlags::TRACK_CALLER | CodegenFnAttrFlags::NAKED | CodegenFnAttrFlags::COLD;
+        if tcx.codegen_fn_attrs(def_id).flags.intersects(unsupported_attrs) {
+            return PassOutcome::Unchanged;
+        }
+
+        let cold = diverging_cold_blocks(tcx, body);
+        if cold.is_empty() {
+            return PassOutcome::Unchanged;
+        }
+        // Regions are entered from a block that isn't cold, and don't overlap, so outlining one
+        // of them leaves the blocks of the others alone.
+        let regions: Vec<Region> = cold
+            .iter()
+            .filter(|&bb| body.predecessors()[bb].iter().any(|&pred| !cold.contains(pred)))
+            .filter_map(|entry| Region::find(body, entry))
+            .filter(|region| region.size(body) >= MIN_REGION_SIZE)
+            .collect();
+
+        let mut changed = false;
+        for region in &regions {
+            changed |= outline(tcx, def_id, body, region);
+        }
+        if !changed {
+            return PassOutcome::Unchanged;
+        }
+        simplify::remove_dead_blocks(body);
+        PassOutcome::Changed
+    }
+}
+
+/// Returns the blocks from which every path ends in a call to a `#[cold]` function that never
+/// returns. Unlike with `reorder_blocks::cold_blocks`, cleanup blocks and calls to `#[cold]`
+/// functions that return don't count, as the blocks can't come back to the body.
+fn diverging_cold_blocks<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> BitSet<BasicBlock> {
+    let mut cold = BitSet::new_empty(body.basic_blocks().len());
+    for (bb, data) in body.basic_blocks().iter_enumerated() {
+        if data.is_cleanup {
+            continue;
+        }
+        if let TerminatorKind::Call { ref func, destination: None, .. } = data.terminator().kind {
+            if let ty::FnDef(def_id, _) = func.ty(body, tcx).kind {
+                if tcx.codegen_fn_attrs(def_id).flags.contains(CodegenFnAttrFlags::COLD) {
+                    cold.insert(bb);
+                }
+            }
+        }
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (bb, data) in body.basic_blocks().iter_enumerated().rev() {
+            if data.is_cleanup || cold.contains(bb) {
+                continue;
+            }
+            // Unwinding leaves the blocks either way, so only the normal successors count.
+            let mut successors =
+                data.terminator().successors().filter(|&&succ| !body[succ].is_cleanup).peekable();
+            if successors.peek().is_some() && successors.all(|&succ| cold.contains(succ)) {
+                cold.insert(bb);
+                changed = true;
+            }
+        }
+    }
+    cold
+}
+
+/// Cold blocks that can only be entered through the first of them.
+struct Region {
+    /// The blocks of the region, starting with its entry.
+    blocks: Vec<BasicBlock>,
+    contains: BitSet<BasicBlock>,
+    /// Where unwinding out of any of the blocks continues.
+    unwind: Option<BasicBlock>,
+}
+
+impl Region {
+    /// Collects the blocks reachable from the cold block `entry`, which are all cold. Returns
+    /// `None` if another block of them can be reached from outside, or if they unwind to
+    /// different blocks.
+    fn find(body: &Body<'_>, entry: BasicBlock) -> Option<Region> {
+        let mut contains = BitSet::new_empty(body.basic_blocks().len());
+        contains.insert(entry);
+        let mut blocks = vec![entry];
+        let mut index = 0;
+        while index < blocks.len() {
+            let bb = blocks[index];
+            index += 1;
+            for &succ in body[bb].terminator().successors() {
+                if !body[succ].is_cleanup && contains.insert(succ) {
+                    blocks.push(succ);
+                }
+            }
+        }
+        let predecessors = body.predecessors();
+        let entered_from_outside =
+            |bb: BasicBlock| predecessors[bb].iter().any(|&pred| !contains.contains(pred));
+        if blocks[1..].iter().any(|&bb| entered_from_outside(bb)) {
+            return None;
+        }
+
+        let mut unwinds = blocks.iter().filter_map(|&bb| body[bb].terminator().unwind().copied());
+        let unwind = unwinds.next().flatten();
+        if unwinds.any(|other| other != unwind) {
+            return None;
+        }
+        Some(Region { blocks, contains, unwind })
+    }
+
+    /// The number of blocks and of statements other than storage markers.
+    fn size(&self, body: &Body<'_>) -> usize {
+        let statements = self.blocks.iter().flat_map(|&bb| &body[bb].statements);
+        self.blocks.len()
+            + statements
+                .filter(|statement| match statement.kind {
+                    StatementKind::StorageLive(_)
+                    | StatementKind::StorageDead(_)
+                    | StatementKind::Nop => false,
+                    _ => true,
+                })
+                .count()
+    }
+}
+
+/// Moves the blocks of `region` into a new function and calls it from its entry block instead.
+/// Returns `false` if the blocks use the locals of the body in ways the function can't.
+fn outline<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+    body: &mut Body<'tcx>,
+    region: &Region,
+) -> bool {
+    let mut uses = RegionUses::new(body, &region.contains);
+    uses.visit_body(body);
+    let mut shared = uses.inside.clone();
+    shared.intersect(&uses.outside);
+    let mut private = uses.inside.clone();
+    private.subtract(&uses.outside);
+
+    let param_env = tcx.param_env(def_id);
+    let mut by_pointer = BitSet::new_empty(body.local_decls.len());
+    for local in shared.iter() {
+        let decl = &body.local_decls[local];
+        let tcx_at = tcx.at(decl.source_info.span);
+        // The storage of shared locals belongs to the caller.
+        if uses.storage_live.contains(local) || !decl.ty.is_sized(tcx_at, param_env) {
+            return false;
+        }
+        if uses.mutated.contains(local)
+            || uses.borrowed.contains(local)
+            || !decl.ty.is_copy_modulo_regions(tcx_at, param_env)
+        {
+            // Index projections can't go through a pointer.
+            if uses.indexes.contains(local) {
+                return false;
+            }
+            by_pointer.insert(local);
+        }
+    }
+
+    // The outlined function returns `!`, takes the shared locals in order, and owns the rest.
+    let mut local_map = IndexVec::from_elem(None, &body.local_decls);
+    let mut local_decls = IndexVec::new();
+    local_decls.push(LocalDecl::new(tcx.types.never, body.span));
+    for local in shared.iter() {
+        let decl = &body.local_decls[local];
+        let ty = if by_pointer.contains(local) { tcx.mk_mut_ptr(decl.ty) } else { decl.ty };
+        local_map[local] = Some(local_decls.push(LocalDecl::new(ty, decl.source_info.span)));
+    }
+    for local in private.iter() {
+        local_map[local] = Some(local_decls.push(body.local_decls[local].clone()));
+    }
+
+    let mut block_map = IndexVec::from_elem(None, body.basic_blocks());
+    for (index, &bb) in region.blocks.iter().enumerate() {
+        block_map[bb] = Some(BasicBlock::new(index));
+    }
+    let mut blocks = IndexVec::with_capacity(region.blocks.len());
+    for &bb in &region.blocks {
+        let data = &body[bb];
+        let statements = data
+            .statements
+            .iter()
+            .filter(|statement| match statement.kind {
+                StatementKind::StorageLive(local) | StatementKind::StorageDead(local) => {
+                    private.contains(local)
+                }
+                StatementKind::Nop => false,
+                _ => true,
+            })
+            .cloned()
+            .collect();
+        let mut terminator = data.terminator().clone();
+        // Unwinding out of the function continues at the call that replaces the blocks.
+        if let Some(unwind) = terminator.unwind_mut() {
+            *unwind = None;
+        }
+        for succ in terminator.successors_mut() {
+            *succ = block_map[*succ].unwrap();
+        }
+        blocks.push(BasicBlockData { statements, terminator: Some(terminator), is_cleanup: false });
+    }
+
+    let mut outlined = Body::new(
+        blocks,
+        body.source_scopes.clone(),
+        local_decls,
+        body.user_type_annotations.clone(),
+        shared.count(),
+        Vec::new(),
+        body.span,
+        None,
+    );
+    outlined.phase = MirPhase::Optimized;
+    LocalRenamer { tcx, local_map: &local_map, by_pointer: &by_pointer }.visit_body(&mut outlined);
+    let mut required_consts = Vec::new();
+    RequiredConstsVisitor::new(&mut required_consts).visit_body(&outlined);
+    outlined.required_consts = required_consts;
+
+    let index = body.outlined.len() as u32;
+    let instance = Instance {
+        def: InstanceDef::Outlined(def_id, index),
+        substs: tcx.erase_regions(&InternalSubsts::identity_for_item(tcx, def_id)),
+    };
+    let outlined_source = MirSource { instance: instance.def, promoted: None, promoted_span: None };
+    dump_mir(tcx, None, "outlined", &0, outlined_source, &outlined, |_, _| Ok(()));
+
+    let sig = tcx.mk_fn_sig(
+        outlined.args_iter().map(|arg| outlined.local_decls[arg].ty),
+        tcx.types.never,
+        false,
+        hir::Unsafety::Normal,
+        Abi::Rust,
+    );
+    let func_ty = tcx.mk_fn_ptr(ty::Binder::dummy(sig));
+    let func_ptr = Scalar::Ptr(tcx.create_fn_alloc(instance).into());
+    body.outlined.push(outlined);
+
+    let entry = region.blocks[0];
+    let source_info = body[entry].terminator().source_info;
+    let mut statements = Vec::new();
+    let mut args = Vec::with_capacity(shared.count());
+    for local in shared.iter() {
+        if by_pointer.contains(local) {
+            let ty = tcx.mk_mut_ptr(body.local_decls[local].ty);
+            let pointer = body.local_decls.push(LocalDecl::new(ty, source_info.span).internal());
+            statements.push(Statement {
+                source_info,
+                kind: StatementKind::Assign(box (
+                    Place::from(pointer),
+                    Rvalue::AddressOf(Mutability::Mut, Place::from(local)),
+                )),
+            });
+            args.push(Operand::Move(Place::from(pointer)));
+        } else {
+            args.push(Operand::Copy(Place::from(local)));
+        }
+    }
+    let func = Operand::Constant(box Constant {
+        span: source_info.span,
+        user_ty: None,
+        literal: ty::Const::from_scalar(tcx, func_ptr, func_ty),
+    });
+    let terminator = Terminator {
+        source_info,
+        kind: TerminatorKind::Call {
+            func,
+            args,
+            destination: None,
+            cleanup: region.unwind,
+            from_hir_call: false,
+            fn_span: source_info.span,
+        },
+    };
+    // The other blocks of the region are only reachable from here, so they are dead now.
+    body.basic_blocks_mut()[entry] =
+        BasicBlockData { statements, terminator: Some(terminator), is_cleanup: false };
+    true
+}
+
+/// How a body uses its locals inside and outside of a region.
+struct RegionUses<'a> {
+    region: &'a BitSet<BasicBlock>,
+    /// Locals used inside the region, not counting storage markers and debuginfo.
+    inside: BitSet<Local>,
+    /// Locals used outside the region, including the arguments and the return place.
+    outside: BitSet<Local>,
+    /// Locals the region may change.
+    mutated: BitSet<Local>,
+    /// Locals borrowed, or whose address is taken, anywhere in the body.
+    borrowed: BitSet<Local>,
+    /// Locals whose storage the region starts.
+    storage_live: BitSet<Local>,
+    /// Locals the region indexes with.
+    indexes: BitSet<Local>,
+}
+
+impl<'a> RegionUses<'a> {
+    fn new(body: &Body<'_>, region: &'a BitSet<BasicBlock>) -> Self {
+        let empty = BitSet::new_empty(body.local_decls.len());
+        let mut outside = empty.clone();
+        for local in (0..=body.arg_count).map(Local::new) {
+            outside.insert(local);
+        }
+        RegionUses {
+            region,
+            inside: empty.clone(),
+            outside,
+            mutated: empty.clone(),
+            borrowed: empty.clone(),
+            storage_live: empty.clone(),
+            indexes: empty,
+        }
+    }
+}
+
+impl<'tcx> Visitor<'tcx> for RegionUses<'_> {
+    fn visit_place(&mut self, place: &Place<'tcx>, context: PlaceContext, location: Location) {
+        // `super_place` only passes the context of projections on to the local.
+        let address_taken = match context {
+            PlaceContext::NonMutatingUse(NonMutatingUseContext::AddressOf)
+            | PlaceContext::MutatingUse(MutatingUseContext::AddressOf) => true,
+            _ => context.is_borrow(),
+        };
+        if address_taken {
+            self.borrowed.insert(place.local);
+        }
+        if self.region.contains(location.block) {
+            if context.is_mutating_use() {
+                self.mutated.insert(place.local);
+            }
+            for elem in place.projection.iter() {
+                if let ProjectionElem::Index(index) = *elem {
+                    self.indexes.insert(index);
+                }
+            }
+        }
+        self.super_place(place, context, location);
+    }
+
+    fn visit_local(&mut self, &local: &Local, context: PlaceContext, location: Location) {
+        let inside = self.region.contains(location.block);
+        if inside && context.is_storage_live_marker() {
+            self.storage_live.insert(local);
+        }
+        if !context.is_use() {
+            return;
+        }
+        if inside {
+            self.inside.insert(local);
+        } else {
+            self.outside.insert(local);
+        }
+    }
+}
+
+/// Renames the locals of the blocks moved into an outlined function to its own, and goes through
+/// the pointer argument for the locals it gets a pointer to.
+struct LocalRenamer<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    local_map: &'a IndexVec<Local, Option<Local>>,
+    by_pointer: &'a BitSet<Local>,
+}
+
+impl<'a, 'tcx> MutVisitor<'tcx> for LocalRenamer<'a, 'tcx> {
+    fn tcx(&self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    fn visit_local(&mut self, local: &mut Local, _: PlaceContext, _: Location) {
+        *local = self.local_map[*local].unwrap();
+    }
+
+    fn visit_place(&mut self, place: &mut Place<'tcx>, context: PlaceContext, location: Location) {
+        let by_pointer = self.by_pointer.contains(place.local);
+        self.super_place(place, context, location);
+        if by_pointer {
+            let mut projection = vec![ProjectionElem::Deref];
+            projection.extend(place.projection);
+            place.projection = self.tcx.intern_place_elems(&projection);
+        }
+    }
+}
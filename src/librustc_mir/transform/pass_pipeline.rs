@@ -0,0 +1,103 @@
+//! Implements `-Z print-mir-pass-pipeline`, which prints the passes every phase of an item's MIR
+//! goes through in this session, without compiling anything.
+//!
+//! The passes come from the same lists `run_passes` gets, and are enabled the same way, so the
+//! output only differs from what happens to a body where `#[rustc_mir(skip)]` applies. Enabled
+//! passes are numbered like their `-Z dump-mir` files, as long as every `Fixpoint` group runs
+//! once. Passes are printed by their identifier, which tells apart the runs of a pass that is
+//! scheduled several times. Shims have a pipeline of their own, which is not printed.
+
+use crate::transform::pass_registry::{PassRegistry, PhasePassIds};
+use crate::transform::promote_consts::PromoteTemps;
+use crate::transform::{fixpoint_iterations, pass_enabled, plugin_passes_in_phase};
+use crate::transform::with_plugin_passes;
+use crate::transform::{MirPass, PassToggle};
+use crate::transform::{with_const_passes, with_inlining_passes, with_optimization_passes};
+use crate::transform::{with_post_borrowck_cleanup_passes, with_validated_passes};
+use rustc_middle::mir::MirPhase;
+use rustc_middle::ty::TyCtxt;
+use rustc_session::Session;
+use std::iter;
+
+/// Prints the pass pipeline of every MIR phase.
+pub fn print_pass_pipeline(tcx: TyCtxt<'_>) {
+    println!(
+        "MIR pass pipeline at -Z mir-opt-level={}:",
+        tcx.sess.opts.debugging_opts.mir_opt_level
+    );
+    let registry = PassRegistry::get(tcx);
+    let print =
+        |phase, passes: &[&[&dyn MirPass<'_>]]| print_phase(tcx.sess, registry, phase, passes);
+    with_const_passes(|passes| print(MirPhase::Const, passes));
+    let promote_pass = PromoteTemps::default();
+    with_validated_passes(&promote_pass, |passes| print(MirPhase::Validated, passes));
+    with_post_borrowck_cleanup_passes(tcx, |passes| print(MirPhase::DropElab, passes));
+    with_inlining_passes(|passes| print(MirPhase::Inlined, passes));
+    with_optimization_passes(|passes| print(MirPhase::Optimized, passes));
+}
+
+fn print_phase<'tcx>(
+    sess: &Session,
+    registry: &PassRegistry,
+    phase: MirPhase,
+    passes: &[&[&dyn MirPass<'tcx>]],
+) {
+    println!("{:?}:", phase);
+    let mut printer = PhasePrinter {
+        sess,
+        phase_index: phase.phase_index(),
+        index: 0,
+        ids: PhasePassIds::new(Some(registry), phase),
+    };
+    let (phase_start, phase_end) = plugin_passes_in_phase(sess, phase);
+    let passes = iter::once(&phase_start[..])
+        .chain(passes.iter().copied())
+        .chain(iter::once(&phase_end[..]));
+    for &pass in passes.flat_map(|pass_group| pass_group.iter()) {
+        let id = printer.ids.next(&pass.name());
+        match pass.fixpoint_passes() {
+            Some(fixpoint_passes) => {
+                let fixpoint_ids: Vec<_> =
+                    fixpoint_passes.iter().map(|p| printer.ids.next(&p.name())).collect();
+                let (enabled, toggle) = pass_enabled(sess, pass, &id, &[]);
+                if !enabled {
+                    println!("  {:7} {} (disabled by {})", "-", id, toggle.describe());
+                    continue;
+                }
+                println!("  {} (up to {} times):", id, fixpoint_iterations(sess));
+                for (&fixpoint_pass, id) in fixpoint_passes.iter().zip(&fixpoint_ids) {
+                    printer.print_pass(fixpoint_pass, id, "    ");
+                }
+            }
+            None => printer.print_pass(pass, &id, "  "),
+        }
+    }
+}
+
+struct PhasePrinter<'a> {
+    sess: &'a Session,
+    phase_index: usize,
+    /// The number of the next enabled pass.
+    index: usize,
+    ids: PhasePassIds<'a>,
+}
+
+impl PhasePrinter<'_> {
+    /// Prints `pass`, whose identifier is `id`, along with the plugin passes around it.
+    fn print_pass(&mut self, pass: &dyn MirPass<'_>, id: &str, indent: &str) {
+        for (pass, id) in with_plugin_passes(self.sess, pass, id) {
+            let (enabled, toggle) = pass_enabled(self.sess, pass, &id, &[]);
+            if !enabled {
+                println!("{}{:7} {} (disabled by {})", indent, "-", id, toggle.describe());
+                continue;
+            }
+            // Only forcing a pass on is worth pointing out.
+            let note = match toggle {
+                PassToggle::CommandLine => format!(" (enabled by {})", toggle.describe()),
+                _ => String::new(),
+            };
+            println!("{}{:03}-{:03} {}{}", indent, self.phase_index, self.index, id, note);
+            self.index += 1;
+        }
+    }
+}
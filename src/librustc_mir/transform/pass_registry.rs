@@ -0,0 +1,105 @@
+//! Identifies every pass of the pipeline that items go through, so that one run of a pass that
+//! is scheduled several times can be picked out. Such passes are identified by their name and
+//! the number of the run over all phases, e.g. `AddCallGuards#2`, and all other passes by their
+//! name. The pipeline of shims and plugin passes don't take part, so their passes are always
+//! identified by their name.
+//!
+//! `-Z mir-enable-passes`, `#[rustc_mir(skip)]` and the pass filter of `-Z dump-mir` accept the
+//! identifiers along with the names, and `-Z print-mir-pass-pipeline` prints them.
+
+use crate::transform::promote_consts::PromoteTemps;
+use crate::transform::{with_const_passes, with_inlining_passes, with_optimization_passes};
+use crate::transform::{check_pass_order, with_post_borrowck_cleanup_passes};
+use crate::transform::{with_validated_passes, MirPass};
+use rustc_data_structures::fx::FxHashMap;
+use rustc_middle::mir::MirPhase;
+use rustc_middle::ty::TyCtxt;
+use rustc_session::MirPassData;
+use std::any::Any;
+
+pub struct PassRegistry {
+    /// How many times each pass name is scheduled, over all phases.
+    totals: FxHashMap<String, usize>,
+    /// How many times each pass name is scheduled before each phase, by phase index.
+    before_phase: FxHashMap<(usize, String), usize>,
+    /// Whether to check the order of the passes of each phase as it is added.
+    check_order: bool,
+}
+
+impl PassRegistry {
+    /// Returns the registry, which only depends on how the session schedules passes.
+    pub fn get(tcx: TyCtxt<'tcx>) -> &'tcx PassRegistry {
+        let registry = tcx.sess.mir_pass_registry.get_or_init(|| {
+            let opts = &tcx.sess.opts.debugging_opts;
+            let check_order =
+                opts.validate_mir || opts.mir_shuffle_passes.is_some() || cfg!(debug_assertions);
+            let mut registry = PassRegistry {
+                totals: FxHashMap::default(),
+                before_phase: FxHashMap::default(),
+                check_order,
+            };
+            with_const_passes(|passes| registry.add_phase(MirPhase::Const, passes));
+            let promote_pass = PromoteTemps::default();
+            with_validated_passes(&promote_pass, |passes| {
+                registry.add_phase(MirPhase::Validated, passes)
+            });
+            with_post_borrowck_cleanup_passes(tcx, |passes| {
+                registry.add_phase(MirPhase::DropElab, passes)
+            });
+            with_inlining_passes(|passes| registry.add_phase(MirPhase::Inlined, passes));
+            with_optimization_passes(|passes| registry.add_phase(MirPhase::Optimized, passes));
+            Box::new(registry)
+        });
+        registry.as_any().downcast_ref().unwrap()
+    }
+
+    fn add_phase(&mut self, phase: MirPhase, passes: &[&[&dyn MirPass<'_>]]) {
+        if self.check_order {
+            check_pass_order(passes, phase);
+        }
+        for (name, &total) in &self.totals {
+            self.before_phase.insert((phase.phase_index(), name.clone()), total);
+        }
+        for &pass in passes.iter().flat_map(|pass_group| pass_group.iter()) {
+            *self.totals.entry(pass.name().into_owned()).or_default() += 1;
+            for fixpoint_pass in pass.fixpoint_passes().unwrap_or(&[]) {
+                *self.totals.entry(fixpoint_pass.name().into_owned()).or_default() += 1;
+            }
+        }
+    }
+}
+
+impl MirPassData for PassRegistry {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Hands out the identifiers of the passes of one phase, in the order they are scheduled.
+pub struct PhasePassIds<'a> {
+    registry: Option<&'a PassRegistry>,
+    phase: MirPhase,
+    seen: FxHashMap<String, usize>,
+}
+
+impl<'a> PhasePassIds<'a> {
+    /// `registry` is `None` for pipelines that are not registered, such as the one of shims.
+    pub fn new(registry: Option<&'a PassRegistry>, phase: MirPhase) -> Self {
+        PhasePassIds { registry, phase, seen: FxHashMap::default() }
+    }
+
+    /// Returns the identifier of the next scheduled pass called `name`.
+    pub fn next(&mut self, name: &str) -> String {
+        let registry = match self.registry {
+            Some(registry) if registry.totals.get(name).map_or(false, |&total| total > 1) => {
+                registry
+            }
+            _ => return name.to_string(),
+        };
+        let seen = self.seen.entry(name.to_string()).or_default();
+        *seen += 1;
+        let before =
+            registry.before_phase.get(&(self.phase.phase_index(), name.to_string())).unwrap_or(&0);
+        format!("{}#{}", name, before + *seen)
+    }
+}
@@ -0,0 +1,100 @@
+//! Implements `-Z mir-pass-plugin`, which adds MIR passes from dynamic libraries to the pipeline.
+//!
+//! A plugin is a dynamic library built with the same compiler, which exports
+//!
+//! ```ignore (illustrative)
+//! #[no_mangle]
+//! pub fn __rustc_mir_pass_plugin() -> Vec<PluginPass> { ... }
+//! ```
+//!
+//! Every pass it returns runs right before or right after the built-in pass it names, in every
+//! phase that pass is part of, whether or not the built-in pass is enabled. Names are the ones
+//! `-Z print-mir-pass-pipeline` prints, without the number of the run, such as `Inline` or
+//! `SimplifyCfg-final`. A pass can also run at the start or the end of a phase instead.
+//!
+//! Drivers can add passes the same way without a dynamic library, by setting
+//! `rustc_interface::Config::register_mir_passes` to a function like `__rustc_mir_pass_plugin`.
+//! `rustc_interface` loads the plugins and calls the registrars when it creates the compiler, and
+//! keeps the passes in `Session::mir_pass_plugins`. Like codegen backends, plugins stay loaded for
+//! the rest of the process.
+
+use crate::transform::MirPass;
+use rustc_middle::mir::MirPhase;
+use rustc_session::{MirPassData, Session};
+use std::any::Any;
+
+/// A MIR pass that can be used in every phase.
+pub type PluginMirPass = dyn for<'tcx> MirPass<'tcx> + Send + Sync;
+
+/// Where a plugin pass runs, relative to the built-in pass with the given name or to a phase.
+pub enum PluginPassPosition {
+    Before(String),
+    After(String),
+    /// Before the first pass that brings a body to this phase.
+    PhaseStart(MirPhase),
+    /// After the last pass that brings a body to this phase.
+    PhaseEnd(MirPhase),
+}
+
+/// A pass registered by a plugin.
+pub struct PluginPass {
+    pub position: PluginPassPosition,
+    pub pass: Box<PluginMirPass>,
+}
+
+/// The signature of `__rustc_mir_pass_plugin` and of `Config::register_mir_passes`.
+pub type PluginRegistrarFn = fn() -> Vec<PluginPass>;
+
+/// The passes of a session, kept in `Session::mir_pass_plugins`.
+pub struct PluginPasses(pub Vec<PluginPass>);
+
+impl MirPassData for PluginPasses {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Returns the plugin passes that run before and after the built-in pass `name`.
+pub fn passes_around<'a>(
+    sess: &'a Session,
+    name: &str,
+) -> (Vec<&'a PluginMirPass>, Vec<&'a PluginMirPass>) {
+    let (mut before, mut after) = (vec![], vec![]);
+    for plugin_pass in plugin_passes(sess) {
+        match plugin_pass.position {
+            PluginPassPosition::Before(ref anchor) if anchor == name => {
+                before.push(&*plugin_pass.pass)
+            }
+            PluginPassPosition::After(ref anchor) if anchor == name => {
+                after.push(&*plugin_pass.pass)
+            }
+            _ => {}
+        }
+    }
+    (before, after)
+}
+
+/// Returns the plugin passes that run at the start and at the end of `phase`.
+pub fn passes_in_phase(
+    sess: &Session,
+    phase: MirPhase,
+) -> (Vec<&PluginMirPass>, Vec<&PluginMirPass>) {
+    let (mut start, mut end) = (vec![], vec![]);
+    for plugin_pass in plugin_passes(sess) {
+        match plugin_pass.position {
+            PluginPassPosition::PhaseStart(anchor) if anchor == phase => {
+                start.push(&*plugin_pass.pass)
+            }
+            PluginPassPosition::PhaseEnd(anchor) if anchor == phase => end.push(&*plugin_pass.pass),
+            _ => {}
+        }
+    }
+    (start, end)
+}
+
+fn plugin_passes(sess: &Session) -> &[PluginPass] {
+    match sess.mir_pass_plugins.get() {
+        Some(passes) => &passes.as_any().downcast_ref::<PluginPasses>().unwrap().0,
+        None => &[],
+    }
+}
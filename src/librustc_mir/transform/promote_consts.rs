@@ -28,11 +28,12 @@ use rustc_index::vec::{Idx, IndexVec};
 use rustc_target::spec::abi::Abi;
 
 use std::cell::Cell;
+use std::ops::RangeInclusive;
 use std::{cmp, iter, mem};
 
 use crate::const_eval::{is_const_fn, is_unstable_const_fn};
 use crate::transform::check_consts::{is_lang_panic_fn, qualifs, ConstCx};
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 
 /// A `MirPass` for promotion.
 ///
@@ -47,17 +48,27 @@ pub struct PromoteTemps<'tcx> {
 }
 
 impl<'tcx> MirPass<'tcx> for PromoteTemps<'tcx> {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, src: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        // The promoted fragments are collected at the end of this phase.
+        MirPhase::Validated..=MirPhase::Validated
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        src: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
         // There's not really any point in promoting errorful MIR.
         //
         // This does not include MIR that failed const-checking, which we still try to promote.
         if body.return_ty().references_error() {
             tcx.sess.delay_span_bug(body.span, "PromoteTemps: MIR had errors");
-            return;
+            return PassOutcome::Unchanged;
         }
 
         if src.promoted.is_some() {
-            return;
+            return PassOutcome::Unchanged;
         }
 
         let def = src.with_opt_param().expect_local();
@@ -67,9 +78,13 @@ impl<'tcx> MirPass<'tcx> for PromoteTemps<'tcx> {
         let (temps, all_candidates) = collect_temps_and_candidates(&ccx, &mut rpo);
 
         let promotable_candidates = validate_candidates(&ccx, &temps, &all_candidates);
+        if promotable_candidates.is_empty() {
+            return PassOutcome::Unchanged;
+        }
 
         let promoted = promote_candidates(def.to_global(), body, tcx, temps, promotable_candidates);
         self.promoted_fragments.set(promoted);
+        PassOutcome::Changed
     }
 }
 
@@ -0,0 +1,132 @@
+//! Implements `-Z mir-provenance`, which records which statements of the MIR before
+//! optimizations every statement of the optimized MIR derives from.
+//!
+//! Passes don't report what they do to statements, so `run_passes` takes a snapshot of the
+//! statements before every pass, and matches the statements the pass leaves against it:
+//!
+//! - A statement the pass kept or only moved is equal to a statement of the snapshot, and has
+//!   its provenance. Every statement of the snapshot is claimed by one such statement at most,
+//!   so that equal statements with the same span keep their own provenance. Further copies, like
+//!   those of jump threading, share it.
+//! - A statement the pass created or changed derives from the statements of the snapshot with the
+//!   same `SourceInfo` that the pass didn't keep, following the convention that a statement
+//!   created from other statements keeps their `SourceInfo`. Statements inlined from another body
+//!   have a scope that didn't exist before, so they derive from nothing.
+
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::*;
+
+/// The provenance of the statements of a body before optimizations, which derive from
+/// themselves.
+pub fn initial_provenance(body: &Body<'_>) -> IndexVec<BasicBlock, Vec<Vec<Location>>> {
+    body.basic_blocks()
+        .iter_enumerated()
+        .map(|(block, data)| {
+            data.statements
+                .iter()
+                .enumerate()
+                .map(|(statement_index, statement)| match statement.kind {
+                    StatementKind::Nop => vec![],
+                    _ => vec![Location { block, statement_index }],
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The statements of a body before a pass runs on it, by source info.
+pub struct StatementSnapshot<'tcx> {
+    statements: FxHashMap<SourceInfo, Vec<(Location, StatementKind<'tcx>)>>,
+}
+
+impl<'tcx> StatementSnapshot<'tcx> {
+    pub fn new(body: &Body<'tcx>) -> Self {
+        let mut statements: FxHashMap<_, Vec<_>> = FxHashMap::default();
+        for (block, data) in body.basic_blocks().iter_enumerated() {
+            for (statement_index, statement) in data.statements.iter().enumerate() {
+                let location = Location { block, statement_index };
+                statements
+                    .entry(statement.source_info)
+                    .or_default()
+                    .push((location, statement.kind.clone()));
+            }
+        }
+        StatementSnapshot { statements }
+    }
+
+    /// Returns the provenance of the statements of `body` after the pass, given `provenance`,
+    /// the provenance of the statements of the snapshot.
+    pub fn track(
+        &self,
+        body: &Body<'tcx>,
+        provenance: &IndexVec<BasicBlock, Vec<Vec<Location>>>,
+    ) -> IndexVec<BasicBlock, Vec<Vec<Location>>> {
+        let origins_of =
+            |location: &Location| &provenance[location.block][location.statement_index];
+
+        // First, the statements the pass kept or moved claim their counterparts.
+        let mut claimed = FxHashSet::default();
+        let kept: IndexVec<BasicBlock, Vec<Option<Location>>> = body
+            .basic_blocks()
+            .iter()
+            .map(|data| {
+                data.statements
+                    .iter()
+                    .map(|statement| {
+                        let (location, _) =
+                            self.candidates(statement).find(|(location, kind)| {
+                                *kind == statement.kind && !claimed.contains(location)
+                            })?;
+                        claimed.insert(*location);
+                        Some(*location)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        kept.into_iter_enumerated()
+            .map(|(block, statements)| {
+                statements
+                    .into_iter()
+                    .enumerate()
+                    .map(|(statement_index, kept)| {
+                        if let Some(location) = kept {
+                            return origins_of(&location).clone();
+                        }
+                        let statement = &body[block].statements[statement_index];
+                        // A copy of a statement the pass kept shares its provenance.
+                        let copied =
+                            self.candidates(statement).find(|(_, kind)| *kind == statement.kind);
+                        if let Some((location, _)) = copied {
+                            return origins_of(location).clone();
+                        }
+                        let unclaimed: Vec<_> = self
+                            .candidates(statement)
+                            .map(|(location, _)| location)
+                            .filter(|location| !claimed.contains(*location))
+                            .collect();
+                        let sources = if unclaimed.is_empty() {
+                            self.candidates(statement).map(|(location, _)| location).collect()
+                        } else {
+                            unclaimed
+                        };
+                        let mut origins: Vec<Location> =
+                            sources.into_iter().flat_map(origins_of).copied().collect();
+                        origins.sort();
+                        origins.dedup();
+                        origins
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The statements of the snapshot with the source info of `statement`.
+    fn candidates<'a>(
+        &'a self,
+        statement: &Statement<'tcx>,
+    ) -> impl Iterator<Item = &'a (Location, StatementKind<'tcx>)> {
+        self.statements.get(&statement.source_info).into_iter().flatten()
+    }
+}
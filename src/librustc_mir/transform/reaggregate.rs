@@ -0,0 +1,158 @@
+//! Turns the field-by-field initialization `Deaggregator` produces back into aggregate rvalues.
+//!
+//! Some backends generate better code for `_1 = Foo { 0: _2, 1: _3 }` than for the equivalent
+//! stores to `_1.0` and `_1.1`. Such backends say so with `CodegenBackend::prefers_aggregate_mir`;
+//! `-Z mir-reaggregate` overrides it. Only complete runs of consecutive stores into a local that
+//! is never borrowed, whose operands don't mention the local, are merged, so that nothing can
+//! observe the difference.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_index::bit_set::BitSet;
+use rustc_index::vec::{Idx, IndexVec};
+use rustc_middle::mir::visit::Visitor;
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_session::Session;
+use rustc_target::abi::VariantIdx;
+
+pub struct Reaggregate;
+
+impl<'tcx> MirPass<'tcx> for Reaggregate {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        let debugging_override = sess.opts.debugging_opts.mir_reaggregate;
+        debugging_override.unwrap_or(sess.backend_prefers_aggregates)
+    }
+
+    fn invalidates(&self) -> &[&str] {
+        &["Deaggregator"]
+    }
+
+    fn run_pass(
+        &self,
+        _tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let mut borrowed = BorrowedLocals(BitSet::new_empty(body.local_decls.len()));
+        borrowed.visit_body(body);
+        let borrowed = borrowed.0;
+
+        let mut changed = false;
+        let (basic_blocks, local_decls) = body.basic_blocks_and_local_decls_mut();
+        for data in basic_blocks.iter_mut() {
+            let mut i = 0;
+            while i < data.statements.len() {
+                match find_aggregate(local_decls, &borrowed, &data.statements[i..]) {
+                    Some((len, statement)) => {
+                        debug!("reaggregating {} statements into {:?}", len, statement);
+                        data.statements[i] = statement;
+                        for statement in &mut data.statements[i + 1..i + len] {
+                            statement.make_nop();
+                        }
+                        i += len;
+                        changed = true;
+                    }
+                    None => i += 1,
+                }
+            }
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
+
+/// If `statements` starts with the deaggregated initialization of a local, returns the number of
+/// statements it consists of and the equivalent aggregate assignment.
+fn find_aggregate<'tcx>(
+    local_decls: &IndexVec<Local, LocalDecl<'tcx>>,
+    borrowed: &BitSet<Local>,
+    statements: &[Statement<'tcx>],
+) -> Option<(usize, Statement<'tcx>)> {
+    let (local, variant, field, _) = field_store(statements.first()?)?;
+    if field.index() != 0 || borrowed.contains(local) {
+        return None;
+    }
+
+    let (kind, field_count, is_enum) = match local_decls[local].ty.kind {
+        ty::Adt(adt_def, substs) if !adt_def.is_union() => {
+            let variant_index = match variant {
+                Some(variant_index) if adt_def.is_enum() => variant_index,
+                None if !adt_def.is_enum() => VariantIdx::new(0),
+                _ => return None,
+            };
+            let field_count = adt_def.variants[variant_index].fields.len();
+            let kind = AggregateKind::Adt(adt_def, variant_index, substs, None, None);
+            (kind, field_count, adt_def.is_enum())
+        }
+        ty::Tuple(fields) if variant.is_none() => (AggregateKind::Tuple, fields.len(), false),
+        ty::Closure(def_id, substs) if variant.is_none() => {
+            let field_count = substs.as_closure().upvar_tys().count();
+            (AggregateKind::Closure(def_id, substs), field_count, false)
+        }
+        _ => return None,
+    };
+
+    let len = field_count + is_enum as usize;
+    if statements.len() < len {
+        return None;
+    }
+
+    let mut operands = Vec::with_capacity(field_count);
+    for (i, statement) in statements[..field_count].iter().enumerate() {
+        let (store_local, store_variant, field, operand) = field_store(statement)?;
+        if store_local != local
+            || store_variant != variant
+            || field.index() != i
+            || mentions_local(operand, local)
+        {
+            return None;
+        }
+        operands.push(operand.clone());
+    }
+
+    if is_enum {
+        match statements[field_count].kind {
+            StatementKind::SetDiscriminant { ref place, variant_index }
+                if place.as_local() == Some(local) && Some(variant_index) == variant => {}
+            _ => return None,
+        }
+    }
+
+    let rvalue = Rvalue::Aggregate(box kind, operands);
+    let kind = StatementKind::Assign(box (Place::from(local), rvalue));
+    Some((len, Statement { source_info: statements[0].source_info, kind }))
+}
+
+/// Matches `local.field = operand` and `(local as variant).field = operand`.
+fn field_store<'a, 'tcx>(
+    statement: &'a Statement<'tcx>,
+) -> Option<(Local, Option<VariantIdx>, Field, &'a Operand<'tcx>)> {
+    match statement.kind {
+        StatementKind::Assign(box (ref place, Rvalue::Use(ref operand))) => {
+            match place.projection[..] {
+                [ProjectionElem::Field(field, _)] => Some((place.local, None, field, operand)),
+                [ProjectionElem::Downcast(_, variant), ProjectionElem::Field(field, _)] => {
+                    Some((place.local, Some(variant), field, operand))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn mentions_local(operand: &Operand<'_>, local: Local) -> bool {
+    operand.place().map_or(false, |place| {
+        place.local == local || place.projection.contains(&ProjectionElem::Index(local))
+    })
+}
+
+struct BorrowedLocals(BitSet<Local>);
+
+impl<'tcx> Visitor<'tcx> for BorrowedLocals {
+    fn visit_rvalue(&mut self, rvalue: &Rvalue<'tcx>, location: Location) {
+        if let Rvalue::Ref(_, _, place) | Rvalue::AddressOf(_, place) = rvalue {
+            self.0.insert(place.local);
+        }
+        self.super_rvalue(rvalue, location);
+    }
+}
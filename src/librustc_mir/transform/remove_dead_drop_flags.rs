@@ -0,0 +1,162 @@
+//! Removes drop flags whose value is known wherever they are read.
+//!
+//! `ElaborateDrops` creates a flag for every place that may or may not be initialized when it
+//! goes out of scope, before any of the branches that decide this have been simplified. Once
+//! `ConstProp` and `SimplifyBranches` have removed the branches that set a flag to another value,
+//! every reachable store of the flag writes the same constant. The reads are then replaced by
+//! that constant and the stores are removed, which lets the next `SimplifyBranches` remove the
+//! conditional drops the flag guarded.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::interpret::Scalar;
+use rustc_middle::mir::visit::{MutVisitor, NonMutatingUseContext, NonUseContext};
+use rustc_middle::mir::visit::{PlaceContext, Visitor};
+use rustc_middle::mir::*;
+use rustc_middle::ty::{ParamEnv, TyCtxt};
+use rustc_span::Span;
+
+pub struct RemoveDeadDropFlags;
+
+impl<'tcx> MirPass<'tcx> for RemoveDeadDropFlags {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        // Drop flags are internal boolean temporaries. Other locals that look like them are
+        // handled the same way, which is fine since nothing here is specific to drops.
+        let mut flags: IndexVec<Local, FlagState> = body
+            .local_decls
+            .iter_enumerated()
+            .map(|(local, decl)| {
+                let is_candidate = decl.internal
+                    && decl.ty == tcx.types.bool
+                    && body.local_kind(local) == LocalKind::Temp;
+                if is_candidate { FlagState::Unwritten } else { FlagState::NotAFlag }
+            })
+            .collect();
+
+        // Stores in unreachable blocks don't matter, they are removed by the next `SimplifyCfg`.
+        let param_env = tcx.param_env(source.def_id());
+        let mut collector = FlagCollector { tcx, param_env, flags: &mut flags };
+        for (bb, data) in traversal::reachable(body) {
+            collector.visit_basic_block_data(bb, data);
+        }
+
+        let replacements: IndexVec<Local, Option<(bool, Span)>> = flags
+            .iter_enumerated()
+            .map(|(local, &state)| match state {
+                FlagState::Constant(value) => {
+                    Some((value, body.local_decls[local].source_info.span))
+                }
+                _ => None,
+            })
+            .collect();
+        if replacements.iter().all(Option::is_none) {
+            return PassOutcome::Unchanged;
+        }
+
+        debug!("removing constant drop flags {:?}", replacements);
+        FlagReplacer { tcx, replacements }.visit_body(body);
+        PassOutcome::Changed
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum FlagState {
+    /// The local is not a flag, or it is used in a way other than storing constants and
+    /// reading the value.
+    NotAFlag,
+    /// No reachable store of the flag has been seen yet.
+    Unwritten,
+    /// Every reachable store of the flag writes this value.
+    Constant(bool),
+    /// The flag is set to different values.
+    Varying,
+}
+
+struct FlagCollector<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    param_env: ParamEnv<'tcx>,
+    flags: &'a mut IndexVec<Local, FlagState>,
+}
+
+impl<'tcx> Visitor<'tcx> for FlagCollector<'_, 'tcx> {
+    fn visit_statement(&mut self, statement: &Statement<'tcx>, location: Location) {
+        if let StatementKind::Assign(box (place, rvalue)) = &statement.kind {
+            if let Some(local) = place.as_local() {
+                if self.flags[local] != FlagState::NotAFlag {
+                    let value = match rvalue {
+                        Rvalue::Use(Operand::Constant(constant)) => {
+                            constant.literal.try_eval_bool(self.tcx, self.param_env)
+                        }
+                        _ => None,
+                    };
+                    self.flags[local] = match (self.flags[local], value) {
+                        (FlagState::Unwritten, Some(value)) => FlagState::Constant(value),
+                        (FlagState::Constant(old), Some(value)) if old == value => {
+                            FlagState::Constant(value)
+                        }
+                        _ => FlagState::Varying,
+                    };
+                    self.visit_rvalue(rvalue, location);
+                    return;
+                }
+            }
+        }
+        self.super_statement(statement, location);
+    }
+
+    fn visit_local(&mut self, local: &Local, context: PlaceContext, _: Location) {
+        match context {
+            PlaceContext::NonMutatingUse(NonMutatingUseContext::Copy)
+            | PlaceContext::NonMutatingUse(NonMutatingUseContext::Move)
+            | PlaceContext::NonUse(NonUseContext::StorageLive)
+            | PlaceContext::NonUse(NonUseContext::StorageDead) => {}
+            _ => self.flags[*local] = FlagState::NotAFlag,
+        }
+    }
+}
+
+struct FlagReplacer<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    replacements: IndexVec<Local, Option<(bool, Span)>>,
+}
+
+impl<'tcx> MutVisitor<'tcx> for FlagReplacer<'tcx> {
+    fn tcx(&self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    fn visit_statement(&mut self, statement: &mut Statement<'tcx>, location: Location) {
+        let local = match statement.kind {
+            StatementKind::Assign(box (place, _)) => place.as_local(),
+            StatementKind::StorageLive(local) | StatementKind::StorageDead(local) => Some(local),
+            _ => None,
+        };
+        if let Some(local) = local {
+            if self.replacements[local].is_some() {
+                statement.make_nop();
+                return;
+            }
+        }
+        self.super_statement(statement, location);
+    }
+
+    fn visit_operand(&mut self, operand: &mut Operand<'tcx>, location: Location) {
+        if let Some(local) = operand.place().and_then(|place| place.as_local()) {
+            if let Some((value, span)) = self.replacements[local] {
+                let value = Scalar::from_bool(value);
+                *operand = Operand::const_from_scalar(self.tcx, self.tcx.types.bool, value, span);
+                return;
+            }
+        }
+        self.super_operand(operand, location);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 use crate::util::patch::MirPatch;
 use rustc_index::bit_set::BitSet;
 use rustc_middle::mir::*;
@@ -20,8 +20,21 @@ pub fn remove_noop_landing_pads<'tcx>(tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>)
 }
 
 impl<'tcx> MirPass<'tcx> for RemoveNoopLandingPads {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, _src: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    fn is_order_independent(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _src: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        if tcx.sess.panic_strategy() == PanicStrategy::Abort {
+            return PassOutcome::Unchanged;
+        }
         remove_noop_landing_pads(tcx, body);
+        PassOutcome::Changed
     }
 }
 
@@ -98,7 +111,7 @@ impl RemoveNoopLandingPads {
 
         // This is a post-order traversal, so that if A post-dominates B
         // then A will be visited before B.
-        let postorder: Vec<_> = traversal::postorder(body).map(|(bb, _)| bb).collect();
+        let postorder = body.postorder().to_vec();
         for bb in postorder {
             debug!("  processing {:?}", bb);
             for target in body[bb].terminator_mut().successors_mut() {
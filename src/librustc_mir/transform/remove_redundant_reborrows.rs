@@ -0,0 +1,173 @@
+//! Removes reborrows of references that are only used once.
+//!
+//! Method call chains produce lots of `_2 = &mut (*_1)` (and `_2 = &(*_1)`) statements whose
+//! result is passed straight on to the next call. When the intermediate reference is used exactly
+//! once, right after it was created in the same block, using the original reference instead is
+//! equivalent. This does not hold with `-Z mir-emit-retag`, where the reborrow is a retag point,
+//! so the pass does nothing then.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::def_use::DefUseAnalysis;
+use rustc_hir::Mutability;
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::visit::{MutVisitor, NonUseContext, PlaceContext};
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_session::Session;
+
+pub struct RemoveRedundantReborrows;
+
+impl<'tcx> MirPass<'tcx> for RemoveRedundantReborrows {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        !sess.opts.debugging_opts.mir_emit_retag
+    }
+
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let mut def_use_analysis = DefUseAnalysis::new(body);
+        def_use_analysis.analyze(body);
+
+        let mut replacements = IndexVec::from_elem(None, &body.local_decls);
+        let mut removed = Vec::new();
+        for (bb, data) in body.basic_blocks().iter_enumerated() {
+            for (statement_index, statement) in data.statements.iter().enumerate() {
+                let location = Location { block: bb, statement_index };
+                if let Some((reborrow, source, mutability)) =
+                    find_redundant_reborrow(body, &def_use_analysis, statement, location)
+                {
+                    // Only the first link of a chain of reborrows is removed, since
+                    // `find_redundant_reborrow` only checks the liveness of the direct source.
+                    if replacements[source].is_some() {
+                        continue;
+                    }
+                    debug!("replacing reborrow {:?} of {:?} at {:?}", reborrow, source, location);
+                    replacements[reborrow] = Some((source, mutability));
+                    removed.push(location);
+                }
+            }
+        }
+
+        if removed.is_empty() {
+            return PassOutcome::Unchanged;
+        }
+
+        for location in removed {
+            body.make_statement_nop(location);
+        }
+        ReborrowReplacer { tcx, replacements }.visit_body(body);
+        PassOutcome::Changed
+    }
+}
+
+/// Returns the reborrowing local, the reborrowed local and the mutability of the reborrow if
+/// `statement` is a reborrow that can be removed.
+fn find_redundant_reborrow<'tcx>(
+    body: &Body<'tcx>,
+    def_use_analysis: &DefUseAnalysis,
+    statement: &Statement<'tcx>,
+    location: Location,
+) -> Option<(Local, Local, Mutability)> {
+    let (dest, place, mutability) = match statement.kind {
+        StatementKind::Assign(box (ref dest, Rvalue::Ref(_, kind, ref place))) => {
+            (dest.as_local()?, place, kind.to_mutbl_lossy())
+        }
+        _ => return None,
+    };
+    let source = match place.as_ref() {
+        PlaceRef { local, projection: &[ProjectionElem::Deref] } => local,
+        _ => return None,
+    };
+
+    // Only reborrows of references to the same type are no-ops.
+    let source_ty = body.local_decls[source].ty;
+    match source_ty.kind {
+        ty::Ref(_, _, source_mutability) if source_mutability == mutability => {}
+        _ => return None,
+    }
+    if body.local_decls[dest].ty != source_ty || body.local_kind(dest) != LocalKind::Temp {
+        return None;
+    }
+
+    let dest_info = def_use_analysis.local_info(dest);
+    if dest_info.def_count_not_including_drop() != 1 || dest_info.use_count() != 1 {
+        return None;
+    }
+    let use_location = dest_info
+        .defs_and_uses
+        .iter()
+        .find(|place_use| place_use.context.is_nonmutating_use())?
+        .location;
+    if use_location.block != location.block
+        || use_location.statement_index <= location.statement_index
+    {
+        return None;
+    }
+
+    let source_info = def_use_analysis.local_info(source);
+    if mutability == Mutability::Mut && source_info.use_count() != 1 {
+        // The source has to stay usable while the reborrow is alive, so it can't be moved.
+        return None;
+    }
+    // The source must still hold the same reference when the reborrow is used.
+    let source_changed = source_info.defs_and_uses.iter().any(|place_use| {
+        (place_use.context.is_mutating_use()
+            || place_use.context == PlaceContext::NonUse(NonUseContext::StorageDead))
+            && place_use.location.block == location.block
+            && place_use.location.statement_index > location.statement_index
+            && place_use.location.statement_index <= use_location.statement_index
+    });
+    if source_changed {
+        return None;
+    }
+
+    Some((dest, source, mutability))
+}
+
+struct ReborrowReplacer<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    replacements: IndexVec<Local, Option<(Local, Mutability)>>,
+}
+
+impl<'tcx> MutVisitor<'tcx> for ReborrowReplacer<'tcx> {
+    fn tcx(&self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    fn visit_statement(&mut self, statement: &mut Statement<'tcx>, location: Location) {
+        // The storage of the removed locals is dead code now.
+        if let StatementKind::StorageLive(local) | StatementKind::StorageDead(local) =
+            statement.kind
+        {
+            if self.replacements[local].is_some() {
+                statement.make_nop();
+                return;
+            }
+        }
+        self.super_statement(statement, location);
+    }
+
+    fn visit_operand(&mut self, operand: &mut Operand<'tcx>, location: Location) {
+        // Shared references are `Copy`, and the source of a shared reborrow may have other uses,
+        // so it must not be moved out of.
+        if let Operand::Move(place) = *operand {
+            if let Some((_, Mutability::Not)) = self.replacements[place.local] {
+                *operand = Operand::Copy(place);
+            }
+        }
+        self.super_operand(operand, location);
+    }
+
+    fn visit_local(&mut self, local: &mut Local, _: PlaceContext, _: Location) {
+        if let Some((source, _)) = self.replacements[*local] {
+            *local = source;
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Removes storage markers that don't tell codegen anything useful.
+//!
+//! `StorageLive` and `StorageDead` let codegen emit lifetime markers, so that the stack slots of
+//! locals whose storage never overlaps can be shared. That only matters for locals that live in
+//! memory, which in practice means the locals that are borrowed. The markers of the other locals
+//! are removed, as are those of locals whose storage starts in the first block of the body and
+//! only ends right before the body returns or resumes unwinding, since it covers the whole body.
+//!
+//! Of the remaining markers, a `StorageDead` directly followed by a `StorageLive` of the same local
+//! ends the storage only to start it again:
+//!
+//!     StorageDead(_5);
+//!     StorageLive(_5);
+//!
+//! Both are removed, as is a `StorageLive` directly followed by a `StorageDead`.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::liveness::ever_borrowed_locals;
+use rustc_index::bit_set::BitSet;
+use rustc_middle::mir::visit::{PlaceContext, Visitor};
+use rustc_middle::mir::*;
+use rustc_middle::ty::TyCtxt;
+
+pub struct RemoveStorageMarkers;
+
+impl<'tcx> MirPass<'tcx> for RemoveStorageMarkers {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        _tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let borrowed = ever_borrowed_locals(body);
+        let whole_body = storage_of_whole_body(body);
+        let removable = |local| !borrowed.contains(local) || whole_body.contains(local);
+
+        let mut changed = false;
+        for data in body.basic_blocks_mut() {
+            for statement in &mut data.statements {
+                match statement.kind {
+                    StatementKind::StorageLive(local) | StatementKind::StorageDead(local)
+                        if removable(local) =>
+                    {
+                        statement.make_nop();
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+
+            // The index of the last statement that isn't a `nop`.
+            let mut previous: Option<usize> = None;
+            for index in 0..data.statements.len() {
+                let kinds =
+                    (previous.map(|p| &data.statements[p].kind), &data.statements[index].kind);
+                let pair = match kinds {
+                    (_, StatementKind::Nop) => continue,
+                    (
+                        Some(&StatementKind::StorageDead(first)),
+                        &StatementKind::StorageLive(second),
+                    )
+                    | (
+                        Some(&StatementKind::StorageLive(first)),
+                        &StatementKind::StorageDead(second),
+                    ) => first == second,
+                    _ => false,
+                };
+                match previous {
+                    Some(first) if pair => {
+                        debug!(
+                            "removing the markers of {:?} at {:?}",
+                            data.statements[index], first
+                        );
+                        data.statements[first].make_nop();
+                        data.statements[index].make_nop();
+                        previous = None;
+                        changed = true;
+                    }
+                    _ => previous = Some(index),
+                }
+            }
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
+
+/// Returns the locals whose storage covers the whole body: those that have their `StorageLive` in
+/// the first block, and their `StorageDead` only where nothing but the end of the body follows.
+fn storage_of_whole_body(body: &Body<'_>) -> BitSet<Local> {
+    let mut partial = BitSet::new_empty(body.local_decls.len());
+    let mut started = BitSet::new_empty(body.local_decls.len());
+    let entry_is_loop = !body.predecessors()[START_BLOCK].is_empty();
+    for (bb, data) in body.basic_blocks().iter_enumerated() {
+        let ends_body =
+            matches!(data.terminator().kind, TerminatorKind::Return | TerminatorKind::Resume);
+        for (index, statement) in data.statements.iter().enumerate() {
+            match statement.kind {
+                StatementKind::StorageLive(local) => {
+                    if bb == START_BLOCK && !entry_is_loop {
+                        started.insert(local);
+                    } else {
+                        partial.insert(local);
+                    }
+                }
+                StatementKind::StorageDead(local) => {
+                    let mut mentions = Mentions { local, found: false };
+                    for statement in &data.statements[index + 1..] {
+                        mentions.visit_statement(statement, Location::START);
+                    }
+                    mentions.visit_terminator(data.terminator(), Location::START);
+                    if !ends_body || mentions.found {
+                        partial.insert(local);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    started.subtract(&partial);
+    started
+}
+
+/// Finds a mention of `local`.
+struct Mentions {
+    local: Local,
+    found: bool,
+}
+
+impl<'tcx> Visitor<'tcx> for Mentions {
+    fn visit_local(&mut self, &local: &Local, _: PlaceContext, _: Location) {
+        if local == self.local {
+            self.found = true;
+        }
+    }
+}
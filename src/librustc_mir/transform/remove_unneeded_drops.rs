@@ -0,0 +1,61 @@
+//! Removes drops of places whose type needs no drop glue.
+//!
+//! Generic code drops its values even if they turn out to be `Copy` once it is inlined into a
+//! caller that knows their types, and the same goes for code that is generic over a `T: Copy`.
+//! Such drops only get removed at codegen, so they count towards the size of the MIR for inlining
+//! and get in the way of other passes until then. This pass replaces every `Drop` of a place whose
+//! type doesn't need to be dropped with a `Goto`, and every such `DropAndReplace` with a plain
+//! assignment. The cleanup blocks that become unreachable are removed by the next `SimplifyCfg`.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_middle::mir::*;
+use rustc_middle::ty::TyCtxt;
+
+pub struct RemoveUnneededDrops;
+
+impl<'tcx> MirPass<'tcx> for RemoveUnneededDrops {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let param_env = tcx.param_env_reveal_all_normalized(source.def_id());
+        let unneeded: Vec<_> = body
+            .basic_blocks()
+            .iter_enumerated()
+            .filter(|(_, data)| match data.terminator().kind {
+                TerminatorKind::Drop { place, .. }
+                | TerminatorKind::DropAndReplace { place, .. } => {
+                    !place.ty(body, tcx).ty.needs_drop(tcx, param_env)
+                }
+                _ => false,
+            })
+            .map(|(bb, _)| bb)
+            .collect();
+        if unneeded.is_empty() {
+            return PassOutcome::Unchanged;
+        }
+
+        for bb in unneeded {
+            debug!("removing the unneeded drop in {:?}", bb);
+            let data = &mut body.basic_blocks_mut()[bb];
+            let terminator = data.terminator_mut();
+            let (target, assign) = match terminator.kind {
+                TerminatorKind::Drop { target, .. } => (target, None),
+                TerminatorKind::DropAndReplace { place, ref value, target, .. } => {
+                    let kind = StatementKind::Assign(box (place, Rvalue::Use(value.clone())));
+                    (target, Some(Statement { source_info: terminator.source_info, kind }))
+                }
+                _ => bug!("unneeded drop in {:?} is not a drop", bb),
+            };
+            terminator.kind = TerminatorKind::Goto { target };
+            data.statements.extend(assign);
+        }
+        PassOutcome::Changed
+    }
+}
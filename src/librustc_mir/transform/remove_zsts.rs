@@ -0,0 +1,110 @@
+//! Removes the assignments and reads of zero-sized values.
+//!
+//! Unit returns, `PhantomData` fields and the payloads of iterator adapters leave assignments of
+//! zero-sized values behind, along with the locals they go through:
+//!
+//!     _3 = const ();
+//!     _4 = move _3;
+//!     _5 = Foo::<T> { phantom: move _6 };
+//!
+//! Codegen emits nothing for either, but they count towards the size of the MIR for inlining, and
+//! every pass walks them until then. The pass deletes the assignments whose place has a
+//! zero-sized type, and replaces the other reads of zero-sized places with constants, so that
+//! `SimplifyLocals` removes the locals too. An rvalue of a zero-sized type only reads other
+//! zero-sized values, so removing it has no effect, unless it evaluates a constant, which may
+//! fail and is kept. Uninhabited types, which are zero-sized as well, are left alone.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_middle::mir::visit::MutVisitor;
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, ParamEnv, Ty, TyCtxt};
+
+pub struct RemoveZsts;
+
+impl<'tcx> MirPass<'tcx> for RemoveZsts {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let param_env = tcx.param_env_reveal_all_normalized(source.def_id());
+        let (basic_blocks, local_decls) = body.basic_blocks_and_local_decls_mut();
+        let mut remover = ZstRemover { tcx, param_env, local_decls, changed: false };
+        for (bb, data) in basic_blocks.iter_enumerated_mut() {
+            remover.visit_basic_block_data(bb, data);
+        }
+        PassOutcome::changed_if(remover.changed)
+    }
+}
+
+struct ZstRemover<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    param_env: ParamEnv<'tcx>,
+    local_decls: &'a LocalDecls<'tcx>,
+    changed: bool,
+}
+
+impl<'tcx> ZstRemover<'_, 'tcx> {
+    fn is_zst(&self, ty: Ty<'tcx>) -> bool {
+        self.tcx
+            .layout_of(self.param_env.and(ty))
+            .map_or(false, |layout| layout.is_zst() && !layout.abi.is_uninhabited())
+    }
+}
+
+impl<'tcx> MutVisitor<'tcx> for ZstRemover<'_, 'tcx> {
+    fn tcx(&self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    fn visit_statement(&mut self, statement: &mut Statement<'tcx>, location: Location) {
+        if let StatementKind::Assign(box (place, ref rvalue)) = statement.kind {
+            let ty = place.ty(self.local_decls, self.tcx).ty;
+            if self.is_zst(ty) && !may_have_side_effects(rvalue) {
+                debug!("removing the zero-sized assignment {:?} at {:?}", statement, location);
+                statement.make_nop();
+                self.changed = true;
+                return;
+            }
+        }
+        self.super_statement(statement, location);
+    }
+
+    fn visit_operand(&mut self, operand: &mut Operand<'tcx>, location: Location) {
+        if let Operand::Copy(place) | Operand::Move(place) = *operand {
+            let ty = place.ty(self.local_decls, self.tcx).ty;
+            if self.is_zst(ty) {
+                debug!("replacing the zero-sized read of {:?} at {:?}", place, location);
+                let constant = box Constant {
+                    span: self.local_decls[place.local].source_info.span,
+                    user_ty: None,
+                    literal: ty::Const::zero_sized(self.tcx, ty),
+                };
+                *operand = Operand::Constant(constant);
+                self.changed = true;
+                return;
+            }
+        }
+        self.super_operand(operand, location);
+    }
+}
+
+/// Returns whether `rvalue` may do more than read values, like evaluating a constant.
+fn may_have_side_effects(rvalue: &Rvalue<'_>) -> bool {
+    let unevaluated = |operand: &Operand<'_>| match operand {
+        Operand::Constant(constant) => {
+            matches!(constant.literal.val, ty::ConstKind::Unevaluated(..))
+        }
+        Operand::Copy(_) | Operand::Move(_) => false,
+    };
+    match rvalue {
+        Rvalue::Use(operand) | Rvalue::Repeat(operand, _) => unevaluated(operand),
+        Rvalue::Aggregate(_, operands) => operands.iter().any(unevaluated),
+        _ => true,
+    }
+}
@@ -0,0 +1,162 @@
+//! Orders the blocks of a body so that the likely successor of each block follows it.
+//!
+//! Codegen emits the blocks of a body in the order of their indices, which is the order MIR
+//! building and the passes happened to create them in. With `-Z mir-reorder-blocks`, the blocks
+//! are laid out in chains instead: starting with the entry block, each block is followed by its
+//! most likely successor that isn't placed yet, so that the likely path falls through. For a
+//! `switchInt`, that is the target with the highest weight in `Body::branch_weights`, if
+//! `-Z mir-branch-weights` recorded any, and otherwise its first target. When a chain can't go
+//! on, the next one starts at the first block that isn't placed yet.
+//!
+//! Blocks from which every path ends in a call to a `#[cold]` function, and cleanup blocks, are
+//! chained separately after all the others, so that they cluster at the end of the body.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_index::bit_set::BitSet;
+use rustc_index::vec::IndexVec;
+use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_session::Session;
+use std::mem;
+use std::ops::RangeInclusive;
+
+pub struct ReorderBlocks;
+
+impl<'tcx> MirPass<'tcx> for ReorderBlocks {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.mir_reorder_blocks
+    }
+
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        // Only for codegen, once the branch weights are known.
+        MirPhase::Optimized..=MirPhase::Optimized
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let cold = cold_blocks(tcx, body);
+        let blocks = body.basic_blocks().indices();
+        let starts: Vec<BasicBlock> = blocks
+            .clone()
+            .filter(|&bb| !cold.contains(bb))
+            .chain(blocks.filter(|&bb| cold.contains(bb)))
+            .collect();
+
+        let mut placed = BitSet::new_empty(body.basic_blocks().len());
+        let mut order = Vec::with_capacity(body.basic_blocks().len());
+        for start in starts {
+            let mut bb = start;
+            while placed.insert(bb) {
+                order.push(bb);
+                match likely_successor(body, &cold, &placed, bb) {
+                    Some(successor) => bb = successor,
+                    None => break,
+                }
+            }
+        }
+        if order.iter().enumerate().all(|(index, bb)| bb.index() == index) {
+            return PassOutcome::Unchanged;
+        }
+        debug!("reordering the blocks to {:?}", order);
+        permute_blocks(body, order);
+        PassOutcome::Changed
+    }
+}
+
+/// Returns the successor of `bb` that isn't placed yet and should follow it, if any. Successors
+/// that are colder than `bb`, or on an unwind path `bb` isn't on, don't.
+fn likely_successor(
+    body: &Body<'_>,
+    cold: &BitSet<BasicBlock>,
+    placed: &BitSet<BasicBlock>,
+    bb: BasicBlock,
+) -> Option<BasicBlock> {
+    let data = &body[bb];
+    let eligible = |successor: BasicBlock| {
+        !placed.contains(successor)
+            && body[successor].is_cleanup == data.is_cleanup
+            && (cold.contains(bb) || !cold.contains(successor))
+    };
+
+    let weights = body.branch_weights.as_ref().map_or(&[][..], |weights| &weights[bb][..]);
+    match data.terminator().kind {
+        TerminatorKind::SwitchInt { ref targets, .. } if weights.len() == targets.len() => targets
+            .iter()
+            .zip(weights)
+            .filter(|&(&target, _)| eligible(target))
+            // `max_by_key` returns the last of equal elements, so go through them backwards.
+            .rev()
+            .max_by_key(|&(_, &weight)| weight)
+            .map(|(&target, _)| target),
+        _ => data.terminator().successors().copied().find(|&successor| eligible(successor)),
+    }
+}
+
+/// Puts the blocks of `body` in the given `order`, which has to contain each of them once and
+/// start with `START_BLOCK`, and points the terminators and the branch weights at their new
+/// indices.
+pub(crate) fn permute_blocks<'tcx>(body: &mut Body<'tcx>, order: Vec<BasicBlock>) {
+    let mut new_index = IndexVec::from_elem(START_BLOCK, body.basic_blocks());
+    for (index, &bb) in order.iter().enumerate() {
+        new_index[bb] = BasicBlock::new(index);
+    }
+    if let Some(weights) = &mut body.branch_weights {
+        let mut old_weights: IndexVec<BasicBlock, Vec<u32>> = mem::take(weights);
+        *weights = order.iter().map(|&bb| mem::take(&mut old_weights[bb])).collect();
+    }
+    let basic_blocks = body.basic_blocks_mut();
+    let mut old_blocks: IndexVec<BasicBlock, Option<BasicBlockData<'tcx>>> =
+        mem::replace(basic_blocks, IndexVec::new()).into_iter().map(Some).collect();
+    for bb in order {
+        let mut data = old_blocks[bb].take().unwrap();
+        for successor in data.terminator_mut().successors_mut() {
+            *successor = new_index[*successor];
+        }
+        basic_blocks.push(data);
+    }
+}
+
+/// Returns the cleanup blocks, and the blocks from which every path ends in a call to a `#[cold]`
+/// function.
+pub(crate) fn cold_blocks<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> BitSet<BasicBlock> {
+    let mut cold = BitSet::new_empty(body.basic_blocks().len());
+    for (bb, data) in body.basic_blocks().iter_enumerated() {
+        let calls_cold = match data.terminator().kind {
+            TerminatorKind::Call { ref func, .. } => match func.ty(body, tcx).kind {
+                ty::FnDef(def_id, _) => {
+                    tcx.codegen_fn_attrs(def_id).flags.contains(CodegenFnAttrFlags::COLD)
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+        if data.is_cleanup || calls_cold {
+            cold.insert(bb);
+        }
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (bb, data) in body.basic_blocks().iter_enumerated().rev() {
+            if cold.contains(bb) {
+                continue;
+            }
+            // Unwinding is cold anyway, so only the normal successors count.
+            let mut successors =
+                data.terminator().successors().filter(|&&succ| !body[succ].is_cleanup).peekable();
+            if successors.peek().is_some() && successors.all(|&succ| cold.contains(succ)) {
+                cold.insert(bb);
+                changed = true;
+            }
+        }
+    }
+    // The body has to start where it did.
+    cold.remove(START_BLOCK);
+    cold
+}
@@ -27,10 +27,11 @@
 //! naively generate still contains the `_a = ()` write in the unreachable block "after" the
 //! return.
 
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::liveness::{DebugInfoUses, UsedLocals};
 use rustc_index::bit_set::BitSet;
 use rustc_index::vec::{Idx, IndexVec};
-use rustc_middle::mir::visit::{MutVisitor, MutatingUseContext, PlaceContext, Visitor};
+use rustc_middle::mir::visit::{MutVisitor, PlaceContext};
 use rustc_middle::mir::*;
 use rustc_middle::ty::TyCtxt;
 use smallvec::SmallVec;
@@ -46,12 +47,14 @@ impl SimplifyCfg {
     }
 }
 
-pub fn simplify_cfg(body: &mut Body<'_>) {
-    CfgSimplifier::new(body).simplify();
-    remove_dead_blocks(body);
+/// Returns whether the control-flow graph of `body` changed.
+pub fn simplify_cfg(body: &mut Body<'_>) -> bool {
+    let simplified = CfgSimplifier::new(body).simplify();
+    let removed = remove_dead_blocks(body);
 
     // FIXME: Should probably be moved into some kind of pass manager
     body.basic_blocks_mut().raw.shrink_to_fit();
+    simplified || removed
 }
 
 impl<'tcx> MirPass<'tcx> for SimplifyCfg {
@@ -59,9 +62,23 @@ impl<'tcx> MirPass<'tcx> for SimplifyCfg {
         Cow::Borrowed(&self.label)
     }
 
-    fn run_pass(&self, _tcx: TyCtxt<'tcx>, _src: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    fn is_order_independent(&self) -> bool {
+        true
+    }
+
+    /// Merging blocks brings back critical edges.
+    fn invalidates(&self) -> &[&str] {
+        &["AddCallGuards", "CanonicalizeCfg"]
+    }
+
+    fn run_pass(
+        &self,
+        _tcx: TyCtxt<'tcx>,
+        _src: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
         debug!("SimplifyCfg({:?}) - simplifying {:?}", self.label, body);
-        simplify_cfg(body);
+        PassOutcome::changed_if(simplify_cfg(body))
     }
 }
 
@@ -91,8 +108,9 @@ impl<'a, 'tcx> CfgSimplifier<'a, 'tcx> {
         CfgSimplifier { basic_blocks, pred_count }
     }
 
-    pub fn simplify(mut self) {
-        self.strip_nops();
+    /// Returns whether any block changed.
+    pub fn simplify(mut self) -> bool {
+        let mut simplified = self.strip_nops();
 
         let mut start = START_BLOCK;
 
@@ -149,6 +167,7 @@ impl<'a, 'tcx> CfgSimplifier<'a, 'tcx> {
             if !changed {
                 break;
             }
+            simplified = true;
         }
 
         if start != START_BLOCK {
@@ -170,7 +189,9 @@ impl<'a, 'tcx> CfgSimplifier<'a, 'tcx> {
                     }
                 }
             }
+            simplified = true;
         }
+        simplified
     }
 
     /// This function will return `None` if
@@ -281,15 +302,20 @@ impl<'a, 'tcx> CfgSimplifier<'a, 'tcx> {
         true
     }
 
-    fn strip_nops(&mut self) {
+    fn strip_nops(&mut self) -> bool {
+        let mut stripped = false;
         for blk in self.basic_blocks.iter_mut() {
+            let len = blk.statements.len();
             blk.statements
-                .retain(|stmt| if let StatementKind::Nop = stmt.kind { false } else { true })
+                .retain(|stmt| if let StatementKind::Nop = stmt.kind { false } else { true });
+            stripped |= blk.statements.len() != len;
         }
+        stripped
     }
 }
 
-pub fn remove_dead_blocks(body: &mut Body<'_>) {
+/// Returns whether any block was removed.
+pub fn remove_dead_blocks(body: &mut Body<'_>) -> bool {
     let mut seen = BitSet::new_empty(body.basic_blocks().len());
     for (bb, _) in traversal::preorder(body) {
         seen.insert(bb.index());
@@ -316,64 +342,66 @@ pub fn remove_dead_blocks(body: &mut Body<'_>) {
             *target = replacements[target.index()];
         }
     }
+    used_blocks != num_blocks
 }
 
 pub struct SimplifyLocals;
 
 impl<'tcx> MirPass<'tcx> for SimplifyLocals {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
         trace!("running SimplifyLocals on {:?}", source);
 
-        // First, we're going to get a count of *actual* uses for every `Local`.
-        // Take a look at `DeclMarker::visit_local()` to see exactly what is ignored.
-        let mut used_locals = {
-            let mut marker = DeclMarker::new(body);
-            marker.visit_body(&body);
-
-            marker.local_counts
-        };
-
-        let arg_count = body.arg_count;
+        // First, we're going to get a count of *actual* uses for every `Local`. Debuginfo
+        // keeps locals alive, since nothing would update it for the locals we remove.
+        let mut used_locals = UsedLocals::new(body, DebugInfoUses::Live);
 
         // Next, we're going to remove any `Local` with zero actual uses. When we remove those
-        // `Locals`, we're also going to subtract any uses of other `Locals` from the `used_locals`
-        // count. For example, if we removed `_2 = discriminant(_1)`, then we'll subtract one from
-        // `use_counts[_1]`. That in turn might make `_1` unused, so we loop until we hit a
-        // fixedpoint where there are no more unused locals.
+        // `Locals`, we're also going to subtract any uses of other `Locals` from `used_locals`.
+        // For example, if we removed `_2 = discriminant(_1)`, then `_1` has one use less. That in
+        // turn might make `_1` unused, so we loop until we hit a fixedpoint where there are no
+        // more unused locals.
+        let mut removed_statements = false;
         loop {
-            let mut remove_statements = RemoveStatements::new(&mut used_locals, arg_count, tcx);
+            let mut remove_statements = RemoveStatements::new(&mut used_locals, tcx);
             remove_statements.visit_body(body);
 
             if !remove_statements.modified {
                 break;
             }
+            removed_statements = true;
         }
 
         // Finally, we'll actually do the work of shrinking `body.local_decls` and remapping the `Local`s.
-        let map = make_local_map(&mut body.local_decls, used_locals, arg_count);
+        let map = make_local_map(&mut body.local_decls, &used_locals);
 
         // Only bother running the `LocalUpdater` if we actually found locals to remove.
-        if map.iter().any(Option::is_none) {
+        let removed_locals = map.iter().any(Option::is_none);
+        if removed_locals {
             // Update references to all vars and tmps now
             let mut updater = LocalUpdater { map, tcx };
             updater.visit_body(body);
 
             body.local_decls.shrink_to_fit();
         }
+        PassOutcome::changed_if(removed_statements || removed_locals)
     }
 }
 
 /// Construct the mapping while swapping out unused stuff out from the `vec`.
 fn make_local_map<V>(
     local_decls: &mut IndexVec<Local, V>,
-    used_locals: IndexVec<Local, usize>,
-    arg_count: usize,
+    used_locals: &UsedLocals,
 ) -> IndexVec<Local, Option<Local>> {
     let mut map: IndexVec<Local, Option<Local>> = IndexVec::from_elem(None, &*local_decls);
     let mut used = Local::new(0);
-    for (alive_index, count) in used_locals.iter_enumerated() {
-        // The `RETURN_PLACE` and arguments are always live.
-        if alive_index.as_usize() > arg_count && *count == 0 {
+    for alive_index in local_decls.indices() {
+        // The `RETURN_PLACE` and arguments are always used.
+        if !used_locals.is_used(alive_index) {
             continue;
         }
 
@@ -387,115 +415,15 @@ fn make_local_map<V>(
     map
 }
 
-struct DeclMarker<'a, 'tcx> {
-    pub local_counts: IndexVec<Local, usize>,
-    pub body: &'a Body<'tcx>,
-}
-
-impl<'a, 'tcx> DeclMarker<'a, 'tcx> {
-    pub fn new(body: &'a Body<'tcx>) -> Self {
-        Self { local_counts: IndexVec::from_elem(0, &body.local_decls), body }
-    }
-}
-
-impl<'a, 'tcx> Visitor<'tcx> for DeclMarker<'a, 'tcx> {
-    fn visit_local(&mut self, local: &Local, ctx: PlaceContext, location: Location) {
-        // Ignore storage markers altogether, they get removed along with their otherwise unused
-        // decls.
-        // FIXME: Extend this to all non-uses.
-        if ctx.is_storage_marker() {
-            return;
-        }
-
-        // Ignore stores of constants because `ConstProp` and `CopyProp` can remove uses of many
-        // of these locals. However, if the local is still needed, then it will be referenced in
-        // another place and we'll mark it as being used there.
-        if ctx == PlaceContext::MutatingUse(MutatingUseContext::Store)
-            || ctx == PlaceContext::MutatingUse(MutatingUseContext::Projection)
-        {
-            let block = &self.body.basic_blocks()[location.block];
-            if location.statement_index != block.statements.len() {
-                let stmt = &block.statements[location.statement_index];
-
-                if let StatementKind::Assign(box (dest, rvalue)) = &stmt.kind {
-                    if !dest.is_indirect() && dest.local == *local {
-                        let can_skip = match rvalue {
-                            Rvalue::Use(_)
-                            | Rvalue::Discriminant(_)
-                            | Rvalue::BinaryOp(_, _, _)
-                            | Rvalue::CheckedBinaryOp(_, _, _)
-                            | Rvalue::Repeat(_, _)
-                            | Rvalue::AddressOf(_, _)
-                            | Rvalue::Len(_)
-                            | Rvalue::UnaryOp(_, _)
-                            | Rvalue::Aggregate(_, _) => true,
-
-                            _ => false,
-                        };
-
-                        if can_skip {
-                            trace!("skipping store of {:?} to {:?}", rvalue, dest);
-                            return;
-                        }
-                    }
-                }
-            }
-        }
-
-        self.local_counts[*local] += 1;
-    }
-}
-
-struct StatementDeclMarker<'a, 'tcx> {
-    used_locals: &'a mut IndexVec<Local, usize>,
-    statement: &'a Statement<'tcx>,
-}
-
-impl<'a, 'tcx> StatementDeclMarker<'a, 'tcx> {
-    pub fn new(
-        used_locals: &'a mut IndexVec<Local, usize>,
-        statement: &'a Statement<'tcx>,
-    ) -> Self {
-        Self { used_locals, statement }
-    }
-}
-
-impl<'a, 'tcx> Visitor<'tcx> for StatementDeclMarker<'a, 'tcx> {
-    fn visit_local(&mut self, local: &Local, context: PlaceContext, _location: Location) {
-        // Skip the lvalue for assignments
-        if let StatementKind::Assign(box (p, _)) = self.statement.kind {
-            if p.local == *local && context.is_place_assignment() {
-                return;
-            }
-        }
-
-        let use_count = &mut self.used_locals[*local];
-        // If this is the local we're removing...
-        if *use_count != 0 {
-            *use_count -= 1;
-        }
-    }
-}
-
 struct RemoveStatements<'a, 'tcx> {
-    used_locals: &'a mut IndexVec<Local, usize>,
-    arg_count: usize,
+    used_locals: &'a mut UsedLocals,
     tcx: TyCtxt<'tcx>,
     modified: bool,
 }
 
 impl<'a, 'tcx> RemoveStatements<'a, 'tcx> {
-    fn new(
-        used_locals: &'a mut IndexVec<Local, usize>,
-        arg_count: usize,
-        tcx: TyCtxt<'tcx>,
-    ) -> Self {
-        Self { used_locals, arg_count, tcx, modified: false }
-    }
-
-    fn keep_local(&self, l: Local) -> bool {
-        trace!("keep_local({:?}): count: {:?}", l, self.used_locals[l]);
-        l.as_usize() <= self.arg_count || self.used_locals[l] != 0
+    fn new(used_locals: &'a mut UsedLocals, tcx: TyCtxt<'tcx>) -> Self {
+        Self { used_locals, tcx, modified: false }
     }
 }
 
@@ -510,9 +438,9 @@ impl<'a, 'tcx> MutVisitor<'tcx> for RemoveStatements<'a, 'tcx> {
         data.statements.retain(|stmt| {
             let keep = match &stmt.kind {
                 StatementKind::StorageLive(l) | StatementKind::StorageDead(l) => {
-                    self.keep_local(*l)
+                    self.used_locals.is_used(*l)
                 }
-                StatementKind::Assign(box (place, _)) => self.keep_local(place.local),
+                StatementKind::Assign(box (place, _)) => self.used_locals.is_used(place.local),
                 _ => true,
             };
 
@@ -520,8 +448,7 @@ impl<'a, 'tcx> MutVisitor<'tcx> for RemoveStatements<'a, 'tcx> {
                 trace!("removing statement {:?}", stmt);
                 self.modified = true;
 
-                let mut visitor = StatementDeclMarker::new(self.used_locals, stmt);
-                visitor.visit_statement(stmt, Location { block, statement_index: i });
+                self.used_locals.statement_removed(stmt, Location { block, statement_index: i });
             }
 
             i += 1;
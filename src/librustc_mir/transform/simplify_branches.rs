@@ -1,6 +1,7 @@
 //! A pass that simplifies branches when their condition is known.
 
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::remark::emit_remark;
 use rustc_middle::mir::*;
 use rustc_middle::ty::TyCtxt;
 
@@ -21,8 +22,18 @@ impl<'tcx> MirPass<'tcx> for SimplifyBranches {
         Cow::Borrowed(&self.label)
     }
 
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, src: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    fn is_order_independent(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        src: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
         let param_env = tcx.param_env(src.def_id());
+        let mut changed = false;
         for block in body.basic_blocks_mut() {
             let terminator = block.terminator_mut();
             terminator.kind = match terminator.kind {
@@ -49,8 +60,21 @@ impl<'tcx> MirPass<'tcx> for SimplifyBranches {
                     }
                 }
                 TerminatorKind::Assert {
-                    target, cond: Operand::Constant(ref c), expected, ..
+                    target,
+                    cond: Operand::Constant(ref c),
+                    expected,
+                    ref msg,
+                    ..
                 } if (c.literal.try_eval_bool(tcx, param_env) == Some(true)) == expected => {
+                    emit_remark(
+                        tcx,
+                        "SimplifyBranches",
+                        terminator.source_info.span,
+                        || match msg {
+                            AssertKind::BoundsCheck { .. } => "eliminated bounds check".to_owned(),
+                            _ => format!("eliminated check for \"{}\"", msg.description()),
+                        },
+                    );
                     TerminatorKind::Goto { target }
                 }
                 TerminatorKind::FalseEdge { real_target, .. } => {
@@ -61,6 +85,8 @@ impl<'tcx> MirPass<'tcx> for SimplifyBranches {
                 }
                 _ => continue,
             };
+            changed = true;
         }
+        PassOutcome::changed_if(changed)
     }
 }
@@ -0,0 +1,195 @@
+//! Moves assignments down into the only successor that reads their result.
+//!
+//! Values that are only needed on one side of a branch are often computed before it, so the other
+//! side pays for them as well:
+//!
+//!     bb0: { _3 = Mul(_1, _2); switchInt(_4) -> [false: bb2, otherwise: bb1]; }
+//!     bb1: { _0 = Add(_3, const 1_u32); return; }
+//!
+//! If the local is live on entry to just one successor, and that successor can only be reached
+//! from the block, the assignment is moved to the start of the successor:
+//!
+//!     bb0: { switchInt(_4) -> [false: bb2, otherwise: bb1]; }
+//!     bb1: { _3 = Mul(_1, _2); _0 = Add(_3, const 1_u32); return; }
+//!
+//! Only assignments without side effects are moved, of locals that are not borrowed and whose
+//! values need no drop. The rvalue has to read the same values in its new place, so it may only
+//! read locals that are not borrowed, and nothing after the assignment in the block may write to
+//! them or end their storage. The statements of a block are visited from last to first, so that
+//! a chain of assignments feeding each other can move together.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::liveness::{ever_borrowed_locals, live_locals_on_entry};
+use rustc_index::bit_set::BitSet;
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::visit::{NonUseContext, PlaceContext, Visitor};
+use rustc_middle::mir::*;
+use rustc_middle::ty::TyCtxt;
+
+pub struct SinkStatements;
+
+impl<'tcx> MirPass<'tcx> for SinkStatements {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let def_id = source.def_id();
+        let param_env = tcx.param_env(def_id);
+        let borrowed = ever_borrowed_locals(body);
+        let predecessor_counts: IndexVec<BasicBlock, usize> =
+            body.predecessors().iter().map(|predecessors| predecessors.len()).collect();
+        let live_on_entry = live_locals_on_entry(tcx, body, def_id).clone();
+
+        let mut changed = false;
+        for bb in body.basic_blocks().indices() {
+            let data = &body[bb];
+            let mut targets: Vec<BasicBlock> = data
+                .terminator()
+                .successors()
+                .copied()
+                .filter(|&succ| succ != bb && body[succ].is_cleanup == data.is_cleanup)
+                .collect();
+            targets.sort();
+            targets.dedup();
+            // The successors the assignments may move to, with the locals live on entry to them,
+            // which are kept up to date as assignments move.
+            let mut successors: Vec<(BasicBlock, BitSet<Local>)> =
+                targets.into_iter().map(|succ| (succ, live_on_entry[succ].clone())).collect();
+
+            for statement_index in (0..body[bb].statements.len()).rev() {
+                let data = &body[bb];
+                let (local, reads) = match data.statements[statement_index].kind {
+                    StatementKind::Assign(box (place, ref rvalue)) if can_sink(rvalue) => {
+                        match (place.as_local(), reads_of(rvalue)) {
+                            (Some(local), Some(reads)) => (local, reads),
+                            _ => continue,
+                        }
+                    }
+                    _ => continue,
+                };
+                if borrowed.contains(local)
+                    || reads.iter().any(|&read| borrowed.contains(read))
+                    || body.local_decls[local].ty.needs_drop(tcx, param_env)
+                {
+                    continue;
+                }
+
+                let mut live_successors =
+                    successors.iter_mut().filter(|(_, live)| live.contains(local));
+                let (target, target_live) = match (live_successors.next(), live_successors.next()) {
+                    (Some(successor), None) => successor,
+                    _ => continue,
+                };
+                // The successors left out above, like the block itself, must not need the local
+                // either.
+                let others_live = data
+                    .terminator()
+                    .successors()
+                    .any(|&succ| succ != *target && live_on_entry[succ].contains(local));
+                if others_live || predecessor_counts[*target] != 1 {
+                    continue;
+                }
+
+                let mut conflicts = ConflictFinder { local, reads: &reads, found: false };
+                for (i, statement) in data.statements.iter().enumerate().skip(statement_index + 1) {
+                    let location = Location { block: bb, statement_index: i };
+                    conflicts.visit_statement(statement, location);
+                }
+                let location = Location { block: bb, statement_index: data.statements.len() };
+                conflicts.visit_terminator(data.terminator(), location);
+                if conflicts.found {
+                    continue;
+                }
+
+                debug!(
+                    "sinking {:?} from {:?} into {:?}",
+                    data.statements[statement_index], bb, target
+                );
+                target_live.remove(local);
+                for &read in &reads {
+                    target_live.insert(read);
+                }
+                let target = *target;
+                let statement = body[bb].statements[statement_index].clone();
+                body.make_statement_nop(Location { block: bb, statement_index });
+                body.basic_blocks_mut()[target].statements.insert(0, statement);
+                changed = true;
+            }
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
+
+/// Whether evaluating `rvalue` later can't change anything but when its result is computed.
+fn can_sink(rvalue: &Rvalue<'_>) -> bool {
+    match rvalue {
+        Rvalue::Use(_)
+        | Rvalue::Repeat(..)
+        | Rvalue::Len(_)
+        | Rvalue::Cast(..)
+        | Rvalue::BinaryOp(..)
+        | Rvalue::CheckedBinaryOp(..)
+        | Rvalue::UnaryOp(..)
+        | Rvalue::Discriminant(_)
+        | Rvalue::Aggregate(..) => true,
+
+        Rvalue::Ref(..)
+        | Rvalue::ThreadLocalRef(_)
+        | Rvalue::AddressOf(..)
+        | Rvalue::NullaryOp(..) => false,
+    }
+}
+
+/// Returns the locals `rvalue` reads, or `None` if it reads through a reference.
+fn reads_of(rvalue: &Rvalue<'_>) -> Option<Vec<Local>> {
+    let mut collector = ReadCollector { reads: Vec::new(), indirect: false };
+    collector.visit_rvalue(rvalue, Location::START);
+    if collector.indirect { None } else { Some(collector.reads) }
+}
+
+struct ReadCollector {
+    reads: Vec<Local>,
+    indirect: bool,
+}
+
+impl<'tcx> Visitor<'tcx> for ReadCollector {
+    fn visit_place(&mut self, place: &Place<'tcx>, context: PlaceContext, location: Location) {
+        if place.is_indirect() {
+            self.indirect = true;
+        }
+        self.super_place(place, context, location);
+    }
+
+    fn visit_local(&mut self, &local: &Local, _: PlaceContext, _: Location) {
+        if !self.reads.contains(&local) {
+            self.reads.push(local);
+        }
+    }
+}
+
+/// Finds mentions of `local`, and writes to or the end of the storage of `reads`, after the
+/// assignment that is to move.
+struct ConflictFinder<'a> {
+    local: Local,
+    reads: &'a [Local],
+    found: bool,
+}
+
+impl<'tcx> Visitor<'tcx> for ConflictFinder<'_> {
+    fn visit_local(&mut self, &local: &Local, context: PlaceContext, _: Location) {
+        if local == self.local {
+            self.found = true;
+        } else if self.reads.contains(&local)
+            && (context.is_mutating_use()
+                || matches!(context, PlaceContext::NonUse(NonUseContext::StorageDead)))
+        {
+            self.found = true;
+        }
+    }
+}
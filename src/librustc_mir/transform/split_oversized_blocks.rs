@@ -0,0 +1,72 @@
+//! Splits basic blocks with very many statements into chains of smaller blocks.
+//!
+//! Deaggregation of big arrays and derive expansions can produce blocks with thousands of
+//! statements, and some backend passes behave superlinearly in the size of a block. Targets
+//! that care set `max_mir_block_statements`; `-Z mir-max-block-statements` overrides it.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_middle::mir::*;
+use rustc_middle::ty::TyCtxt;
+use rustc_session::Session;
+use std::mem;
+
+pub struct SplitOversizedBlocks;
+
+impl<'tcx> MirPass<'tcx> for SplitOversizedBlocks {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        block_statement_limit(sess).is_some()
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        match block_statement_limit(tcx.sess) {
+            Some(limit) => split_oversized_blocks(body, limit),
+            None => PassOutcome::Unchanged,
+        }
+    }
+}
+
+fn block_statement_limit(sess: &Session) -> Option<usize> {
+    let limit = match sess.opts.debugging_opts.mir_max_block_statements {
+        Some(limit) => limit,
+        None => sess.target.target.options.max_mir_block_statements? as usize,
+    };
+    if limit == 0 { None } else { Some(limit) }
+}
+
+fn split_oversized_blocks(body: &mut Body<'_>, limit: usize) -> PassOutcome {
+    if body.basic_blocks().iter().all(|data| data.statements.len() <= limit) {
+        return PassOutcome::Unchanged;
+    }
+
+    let basic_blocks = body.basic_blocks_mut();
+    for bb in basic_blocks.indices() {
+        if basic_blocks[bb].statements.len() <= limit {
+            continue;
+        }
+
+        let is_cleanup = basic_blocks[bb].is_cleanup;
+        let mut statements = mem::take(&mut basic_blocks[bb].statements);
+        let mut terminator = basic_blocks[bb].terminator.take();
+
+        // Peel chunks off the end, so that each new block can jump to the one created before
+        // it and no statement is moved more than once.
+        while statements.len() > limit {
+            let split_at = (statements.len() - 1) / limit * limit;
+            let source_info = statements[split_at - 1].source_info;
+            let tail = statements.split_off(split_at);
+            let next =
+                basic_blocks.push(BasicBlockData { statements: tail, terminator, is_cleanup });
+            terminator =
+                Some(Terminator { source_info, kind: TerminatorKind::Goto { target: next } });
+        }
+
+        basic_blocks[bb].statements = statements;
+        basic_blocks[bb].terminator = terminator;
+    }
+    PassOutcome::Changed
+}
@@ -0,0 +1,193 @@
+//! Scalar replacement of aggregates.
+//!
+//! Once aggregates are deaggregated, many locals of struct or tuple type are only ever accessed
+//! one field at a time:
+//!
+//!     (_1.0: u32) = const 1_u32
+//!     (_1.1: bool) = const true
+//!     _2 = (_1.0: u32)
+//!
+//! Such a local is split into one fresh local per field that is used, which const propagation
+//! and copy propagation handle much better than fields:
+//!
+//!     _3 = const 1_u32
+//!     _4 = const true
+//!     _2 = _3
+//!
+//! A local is only split if every mention of it starts with a field projection, apart from its
+//! storage markers, which are repeated for every new local. That rules out locals that are
+//! borrowed, moved or dropped as a whole, as well as the arguments and the return place, whose
+//! value crosses the function boundary. Unions, enums and SIMD types are never split.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::liveness::ever_borrowed_locals;
+use rustc_index::bit_set::BitSet;
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::visit::{MutVisitor, NonUseContext, PlaceContext, Visitor};
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, Ty, TyCtxt};
+
+pub struct ScalarReplacementOfAggregates;
+
+impl<'tcx> MirPass<'tcx> for ScalarReplacementOfAggregates {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let candidates = find_candidates(body);
+        if candidates.is_empty() {
+            return PassOutcome::Unchanged;
+        }
+
+        // Creates the locals of the fields that are used, in the order they are first mentioned.
+        let mut fields = IndexVec::from_elem(Vec::new(), &body.local_decls);
+        let mut collector = FieldCollector { candidates: &candidates, fields: &mut fields };
+        collector.visit_body(body);
+        let mut replacements: IndexVec<Local, Vec<(Field, Local)>> =
+            IndexVec::from_elem(Vec::new(), &body.local_decls);
+        for local in candidates.iter() {
+            let source_info = body.local_decls[local].source_info;
+            for &(field, ty) in &fields[local] {
+                let new_local =
+                    body.local_decls.push(LocalDecl::with_source_info(ty, source_info).internal());
+                debug!("replacing field {:?} of {:?} by {:?}", field, local, new_local);
+                replacements[local].push((field, new_local));
+            }
+        }
+
+        for data in body.basic_blocks_mut() {
+            let statements = std::mem::take(&mut data.statements);
+            for statement in statements {
+                let (local, is_live) = match statement.kind {
+                    StatementKind::StorageLive(local) if candidates.contains(local) => {
+                        (local, true)
+                    }
+                    StatementKind::StorageDead(local) if candidates.contains(local) => {
+                        (local, false)
+                    }
+                    _ => {
+                        data.statements.push(statement);
+                        continue;
+                    }
+                };
+                for &(_, new_local) in &replacements[local] {
+                    let kind = if is_live {
+                        StatementKind::StorageLive(new_local)
+                    } else {
+                        StatementKind::StorageDead(new_local)
+                    };
+                    data.statements.push(Statement { source_info: statement.source_info, kind });
+                }
+            }
+        }
+        FieldReplacer { tcx, replacements }.visit_body(body);
+        PassOutcome::Changed
+    }
+}
+
+/// Finds the locals that can be split, which are all the locals of a splittable type minus
+/// the ones that are mentioned without a field projection.
+fn find_candidates(body: &Body<'_>) -> BitSet<Local> {
+    let mut candidates = BitSet::new_empty(body.local_decls.len());
+    for (local, decl) in body.local_decls.iter_enumerated() {
+        if body.local_kind(local) == LocalKind::Arg || local == RETURN_PLACE {
+            continue;
+        }
+        let splittable = match decl.ty.kind {
+            ty::Tuple(fields) => !fields.is_empty(),
+            ty::Adt(adt_def, _) => adt_def.is_struct() && !adt_def.repr.simd(),
+            _ => false,
+        };
+        if splittable {
+            candidates.insert(local);
+        }
+    }
+    if candidates.is_empty() {
+        return candidates;
+    }
+
+    candidates.subtract(&ever_borrowed_locals(body));
+    WholeUseFinder { candidates: &mut candidates }.visit_body(body);
+    candidates
+}
+
+/// Removes the locals that are mentioned without a field projection from `candidates`.
+struct WholeUseFinder<'a> {
+    candidates: &'a mut BitSet<Local>,
+}
+
+impl<'tcx> Visitor<'tcx> for WholeUseFinder<'_> {
+    fn visit_place(&mut self, place: &Place<'tcx>, context: PlaceContext, location: Location) {
+        // Visits the locals of index projections, but not the base local.
+        self.visit_projection(place.local, place.projection, context, location);
+        match place.projection.first() {
+            Some(ProjectionElem::Field(..)) => {}
+            _ => {
+                self.candidates.remove(place.local);
+            }
+        }
+    }
+
+    fn visit_local(&mut self, &local: &Local, context: PlaceContext, _: Location) {
+        // Besides index projections, only storage markers and the return terminator mention
+        // locals outside of places.
+        match context {
+            PlaceContext::NonUse(NonUseContext::StorageLive | NonUseContext::StorageDead) => {}
+            _ => {
+                self.candidates.remove(local);
+            }
+        }
+    }
+}
+
+struct FieldCollector<'a, 'tcx> {
+    candidates: &'a BitSet<Local>,
+    fields: &'a mut IndexVec<Local, Vec<(Field, Ty<'tcx>)>>,
+}
+
+impl<'tcx> Visitor<'tcx> for FieldCollector<'_, 'tcx> {
+    fn visit_place(&mut self, place: &Place<'tcx>, context: PlaceContext, location: Location) {
+        if self.candidates.contains(place.local) {
+            if let Some(&ProjectionElem::Field(field, ty)) = place.projection.first() {
+                let fields = &mut self.fields[place.local];
+                if !fields.iter().any(|&(f, _)| f == field) {
+                    fields.push((field, ty));
+                }
+            }
+        }
+        self.super_place(place, context, location);
+    }
+}
+
+struct FieldReplacer<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    replacements: IndexVec<Local, Vec<(Field, Local)>>,
+}
+
+impl<'tcx> MutVisitor<'tcx> for FieldReplacer<'tcx> {
+    fn tcx(&self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    fn visit_place(&mut self, place: &mut Place<'tcx>, context: PlaceContext, location: Location) {
+        if let Some(&ProjectionElem::Field(field, _)) = place.projection.first() {
+            let new_local = self.replacements[place.local]
+                .iter()
+                .find(|&&(f, _)| f == field)
+                .map(|&(_, new_local)| new_local);
+            if let Some(new_local) = new_local {
+                *place = Place {
+                    local: new_local,
+                    projection: self.tcx.intern_place_elems(&place.projection[1..]),
+                };
+            }
+        }
+        self.super_place(place, context, location);
+    }
+}
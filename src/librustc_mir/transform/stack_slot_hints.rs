@@ -0,0 +1,98 @@
+//! Computes which locals can share a stack slot and records the result in
+//! `Body::stack_slot_hints`.
+//!
+//! Locals whose storage is never required at the same time do not conflict, which is the same
+//! question the generator transform answers to overlap saved locals in the generator layout.
+//! Only locals of the same type are grouped, so that codegen can use a single allocation for a
+//! slot without having to reconcile layouts. This matters mostly for debug builds, where LLVM
+//! does not color stack slots aggressively.
+
+use crate::dataflow::impls::{MaybeBorrowedLocals, MaybeRequiresStorage};
+use crate::dataflow::Analysis;
+use crate::transform::generator::local_storage_conflicts;
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::storage::AlwaysLiveLocals;
+use rustc_index::bit_set::{BitMatrix, BitSet};
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::*;
+use rustc_middle::ty::{Ty, TyCtxt};
+use rustc_session::Session;
+use std::ops::RangeInclusive;
+
+pub struct StackSlotHints;
+
+impl<'tcx> MirPass<'tcx> for StackSlotHints {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        sess.opts.debugging_opts.mir_stack_slot_hints
+    }
+
+    fn phases(&self) -> RangeInclusive<MirPhase> {
+        // Any later change to the body would invalidate the hints.
+        MirPhase::Optimized..=MirPhase::Optimized
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let def_id = source.def_id();
+        let hints = {
+            let body: &Body<'tcx> = body;
+
+            // Locals without storage annotations (including the arguments and the return
+            // place) are live for the whole body and can't share anything.
+            let always_live_locals = AlwaysLiveLocals::new(body);
+            let mut candidates = BitSet::new_filled(body.local_decls.len());
+            candidates.subtract(&*always_live_locals);
+            if candidates.is_empty() {
+                return PassOutcome::Unchanged;
+            }
+
+            let borrowed_locals = MaybeBorrowedLocals::all_borrows()
+                .into_engine(tcx, body, def_id)
+                .iterate_to_fixpoint();
+            let requires_storage = MaybeRequiresStorage::new(body, &borrowed_locals)
+                .into_engine(tcx, body, def_id)
+                .iterate_to_fixpoint();
+            let conflicts = local_storage_conflicts(
+                body,
+                &candidates,
+                &BitSet::new_empty(body.local_decls.len()),
+                requires_storage,
+            );
+
+            assign_slots(body, &candidates, &conflicts)
+        };
+
+        body.stack_slot_hints = Some(hints);
+        PassOutcome::Changed
+    }
+}
+
+/// Greedily puts every candidate into the first slot of the same type whose members it does
+/// not conflict with, opening a new slot if there is none.
+fn assign_slots<'tcx>(
+    body: &Body<'tcx>,
+    candidates: &BitSet<Local>,
+    conflicts: &BitMatrix<Local, Local>,
+) -> IndexVec<Local, Option<StackSlot>> {
+    let mut hints = IndexVec::from_elem(None, &body.local_decls);
+    let mut slots: IndexVec<StackSlot, (Ty<'tcx>, Vec<Local>)> = IndexVec::new();
+
+    for local in candidates.iter() {
+        let ty = body.local_decls[local].ty;
+        let fits = |&(slot_ty, ref members): &(Ty<'tcx>, Vec<Local>)| {
+            slot_ty == ty && members.iter().all(|&other| !conflicts.contains(local, other))
+        };
+        let slot = match slots.iter().position(fits) {
+            Some(slot) => StackSlot::from_usize(slot),
+            None => slots.push((ty, Vec::new())),
+        };
+        slots[slot].1.push(local);
+        hints[local] = Some(slot);
+    }
+
+    hints
+}
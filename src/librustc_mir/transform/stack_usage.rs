@@ -0,0 +1,77 @@
+//! Estimates the stack frame of a body from its optimized MIR, before the backend has allocated
+//! anything. The estimate assumes that every local gets its own stack slot (unless the
+//! `StackSlotHints` pass found that it can share one), which is a worst case for optimized
+//! builds and close to what debug builds do.
+
+use rustc_data_structures::fx::FxHashSet;
+use rustc_hir::def_id::{LocalDefId, LOCAL_CRATE};
+use rustc_middle::mir::{BodyKind, StackUsage, TerminatorKind};
+use rustc_middle::ty::query::Providers;
+use rustc_middle::ty::TyCtxt;
+use rustc_target::abi::Size;
+
+pub(crate) fn provide(providers: &mut Providers) {
+    providers.mir_stack_usage = mir_stack_usage;
+}
+
+fn mir_stack_usage(tcx: TyCtxt<'_>, def_id: LocalDefId) -> StackUsage {
+    let body = tcx.optimized_mir(def_id.to_def_id());
+    let param_env = tcx.param_env(def_id.to_def_id()).with_reveal_all();
+
+    let mut usage = StackUsage { max_align: 1, ..StackUsage::default() };
+    let mut seen_slots = FxHashSet::default();
+    for (local, decl) in body.local_decls.iter_enumerated() {
+        // Locals sharing a slot all have the same type, so the first one stands for the rest.
+        if let Some(slot) = body.stack_slot_hints.as_ref().and_then(|hints| hints[local]) {
+            if !seen_slots.insert(slot) {
+                continue;
+            }
+        }
+
+        match tcx.layout_of(param_env.and(decl.ty)) {
+            Ok(layout) => {
+                let align = layout.align.abi;
+                let offset = Size::from_bytes(usage.locals_size).align_to(align);
+                usage.locals_size = (offset + layout.size).bytes();
+                usage.max_align = usage.max_align.max(align.bytes());
+            }
+            // The size depends on generic parameters.
+            Err(_) => usage.is_lower_bound = true,
+        }
+    }
+
+    usage.call_sites = body
+        .basic_blocks()
+        .iter()
+        .filter(|data| matches!(data.terminator().kind, TerminatorKind::Call { .. }))
+        .count() as u64;
+
+    usage
+}
+
+/// Prints the estimated stack usage of every function of the local crate, for
+/// `-Z print-mir-stack-usage`.
+pub fn print_stack_usage(tcx: TyCtxt<'_>) {
+    let mut lines = Vec::new();
+    for &def_id in tcx.mir_keys(LOCAL_CRATE).iter() {
+        match tcx.mir_body_kind(def_id).kind {
+            BodyKind::Fn | BodyKind::Closure | BodyKind::Generator => {}
+            BodyKind::Const | BodyKind::Static | BodyKind::Ctor => continue,
+        }
+
+        let usage = tcx.mir_stack_usage(def_id);
+        lines.push(format!(
+            "print-mir-stack-usage `{}`: {} bytes, alignment: {} bytes, calls: {}{}",
+            tcx.def_path_str(def_id.to_def_id()),
+            usage.locals_size,
+            usage.max_align,
+            usage.call_sites,
+            if usage.is_lower_bound { " (lower bound)" } else { "" },
+        ));
+    }
+
+    lines.sort();
+    for line in lines {
+        println!("{}", line);
+    }
+}
@@ -0,0 +1,80 @@
+//! Replaces unsigned divisions and remainders by powers of two with shifts and masks.
+//!
+//! `InstCombine` already does this for the divisors that are constants when it runs, but the
+//! last run of constant propagation in the cleanups after `ConstProp` comes after it, and can turn
+//! more divisors into constants:
+//!
+//!     _3 = Div(_1, const 8_u32);
+//!     _4 = Rem(_1, const 8_u32);
+//!
+//! Some backends turn divisions into shifts poorly or not at all, so this pass does it again after
+//! constant propagation:
+//!
+//!     _3 = Shr(_1, const 3_u32);
+//!     _4 = BitAnd(_1, const 7_u32);
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, TyCtxt};
+
+pub struct StrengthReduction;
+
+impl<'tcx> MirPass<'tcx> for StrengthReduction {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let param_env = tcx.param_env(source.def_id());
+        let mut changed = false;
+        for data in body.basic_blocks_mut() {
+            for statement in &mut data.statements {
+                if let StatementKind::Assign(box (_, ref mut rvalue)) = statement.kind {
+                    if let Some((new_op, constant)) = reduce_division(tcx, param_env, rvalue) {
+                        debug!("replacing division by a power of two: {:?}", rvalue);
+                        if let Rvalue::BinaryOp(ref mut op, _, ref mut rhs) = *rvalue {
+                            *op = new_op;
+                            *rhs = Operand::Constant(box constant);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
+
+/// Returns the shift or mask, with its right operand, that replaces `rvalue` if it is an unsigned
+/// division or remainder by a power of two.
+pub(crate) fn reduce_division<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    rvalue: &Rvalue<'tcx>,
+) -> Option<(BinOp, Constant<'tcx>)> {
+    let (op, divisor) = match *rvalue {
+        Rvalue::BinaryOp(op @ BinOp::Div, _, Operand::Constant(ref divisor))
+        | Rvalue::BinaryOp(op @ BinOp::Rem, _, Operand::Constant(ref divisor)) => (op, divisor),
+        _ => return None,
+    };
+    // Signed division rounds towards zero, so it can't be done with a shift.
+    let ty = divisor.literal.ty;
+    if !matches!(ty.kind, ty::Uint(_)) {
+        return None;
+    }
+    let bits = divisor.literal.try_eval_bits(tcx, param_env, ty)?;
+    if !bits.is_power_of_two() {
+        return None;
+    }
+    let (new_op, new_bits) = match op {
+        BinOp::Div => (BinOp::Shr, u128::from(bits.trailing_zeros())),
+        _ => (BinOp::BitAnd, bits - 1),
+    };
+    let literal = ty::Const::from_bits(tcx, new_bits, param_env.and(ty));
+    Some((new_op, Constant { span: divisor.span, user_ty: None, literal }))
+}
@@ -0,0 +1,95 @@
+//! Removes the MIR that only exists for debuginfo, when no debuginfo is emitted.
+//!
+//! Without `-C debuginfo`, `VarDebugInfo` and most source scopes are never read by codegen, but
+//! they still take up memory and keep `SimplifyLocals` and `SimplifyArmIdentity` from removing
+//! locals a debugger could otherwise observe. This pass drops the debuginfo and merges the source
+//! scopes. Scopes with different lint levels or safety stay apart, since `ConstProp` and the
+//! unsafety checks still need to tell them apart; for most bodies, a single scope remains.
+//! `-Z mir-strip-debuginfo` overrides the default.
+
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::visit::MutVisitor;
+use rustc_middle::mir::*;
+use rustc_middle::ty::TyCtxt;
+use rustc_session::config::DebugInfo;
+use rustc_session::Session;
+
+pub struct StripDebugInfo;
+
+/// Returns whether the MIR of this session is optimized without regard for debuginfo.
+pub fn strips_debuginfo(sess: &Session) -> bool {
+    let debugging_override = sess.opts.debugging_opts.mir_strip_debuginfo;
+    debugging_override.unwrap_or(sess.opts.debuginfo == DebugInfo::None)
+}
+
+impl<'tcx> MirPass<'tcx> for StripDebugInfo {
+    fn is_enabled(&self, sess: &Session) -> bool {
+        strips_debuginfo(sess)
+    }
+
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let had_debuginfo = !body.var_debug_info.is_empty();
+        body.var_debug_info.clear();
+
+        // Every scope is merged into the first scope with the same local data. Parents always
+        // come before their children, so the parent of a kept scope has already been mapped.
+        let mut kept: IndexVec<SourceScope, SourceScopeData> = IndexVec::new();
+        let mut map: IndexVec<SourceScope, SourceScope> =
+            IndexVec::with_capacity(body.source_scopes.len());
+        for scope_data in body.source_scopes.iter() {
+            let same_local_data = kept
+                .iter_enumerated()
+                .find(|(_, kept_data)| same_local_data(kept_data, scope_data))
+                .map(|(scope, _)| scope);
+            let scope = match same_local_data {
+                Some(scope) => scope,
+                None => kept.push(scope_data.clone()),
+            };
+            map.push(scope);
+        }
+        if kept.len() == body.source_scopes.len() {
+            return PassOutcome::changed_if(had_debuginfo);
+        }
+
+        debug!("merging {} source scopes into {}", body.source_scopes.len(), kept.len());
+        // The parents of the kept scopes are renumbered along with everything else.
+        body.source_scopes = kept;
+        ScopeRenumberer { tcx, map }.visit_body(body);
+        PassOutcome::Changed
+    }
+}
+
+fn same_local_data(a: &SourceScopeData, b: &SourceScopeData) -> bool {
+    match (&a.local_data, &b.local_data) {
+        (ClearCrossCrate::Set(a), ClearCrossCrate::Set(b)) => {
+            a.lint_root == b.lint_root && a.safety == b.safety
+        }
+        (ClearCrossCrate::Clear, ClearCrossCrate::Clear) => true,
+        _ => false,
+    }
+}
+
+struct ScopeRenumberer<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    map: IndexVec<SourceScope, SourceScope>,
+}
+
+impl<'tcx> MutVisitor<'tcx> for ScopeRenumberer<'tcx> {
+    fn tcx(&self) -> TyCtxt<'tcx> {
+        self.tcx
+    }
+
+    fn visit_source_scope(&mut self, scope: &mut SourceScope) {
+        *scope = self.map[*scope];
+    }
+}
@@ -1,6 +1,6 @@
 //! A pass that eliminates branches on uninhabited enum variants.
 
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 use rustc_middle::mir::{
     BasicBlock, BasicBlockData, Body, Local, Operand, Rvalue, StatementKind, TerminatorKind,
 };
@@ -66,14 +66,24 @@ fn variant_discriminants<'tcx>(
 }
 
 impl<'tcx> MirPass<'tcx> for UninhabitedEnumBranching {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
         if source.promoted.is_some() {
-            return;
+            return PassOutcome::Unchanged;
         }
 
         trace!("UninhabitedEnumBranching starting for {:?}", source);
 
         let basic_block_count = body.basic_blocks().len();
+        let mut changed = false;
 
         for bb in 0..basic_block_count {
             let bb = BasicBlock::from_usize(bb);
@@ -111,6 +121,7 @@ impl<'tcx> MirPass<'tcx> for UninhabitedEnumBranching {
                         matched_targets.push(*target);
                     } else {
                         trace!("eliminating {:?} -> {:?}", val, target);
+                        changed = true;
                     }
                 }
 
@@ -123,5 +134,6 @@ impl<'tcx> MirPass<'tcx> for UninhabitedEnumBranching {
                 unreachable!()
             }
         }
+        PassOutcome::changed_if(changed)
     }
 }
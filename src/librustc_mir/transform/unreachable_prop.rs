@@ -3,26 +3,37 @@
 //! post-order traversal of the blocks.
 
 use crate::transform::simplify;
-use crate::transform::{MirPass, MirSource};
+use crate::transform::{MirPass, MirSource, PassOutcome};
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_middle::mir::*;
 use rustc_middle::ty::TyCtxt;
+use rustc_session::Session;
 use std::borrow::Cow;
 
 pub struct UnreachablePropagation;
 
 impl MirPass<'_> for UnreachablePropagation {
-    fn run_pass<'tcx>(&self, tcx: TyCtxt<'tcx>, _: MirSource<'tcx>, body: &mut Body<'tcx>) {
-        if tcx.sess.opts.debugging_opts.mir_opt_level < 3 {
-            // Enable only under -Zmir-opt-level=3 as in some cases (check the deeply-nested-opt
-            // perf benchmark) LLVM may spend quite a lot of time optimizing the generated code.
-            return;
-        }
+    fn is_enabled(&self, sess: &Session) -> bool {
+        // Enable only under -Zmir-opt-level=3 as in some cases (check the deeply-nested-opt
+        // perf benchmark) LLVM may spend quite a lot of time optimizing the generated code.
+        sess.opts.debugging_opts.mir_opt_level >= 3
+    }
+
+    fn is_optimization(&self) -> bool {
+        true
+    }
 
+    fn run_pass<'tcx>(
+        &self,
+        _tcx: TyCtxt<'tcx>,
+        _: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
         let mut unreachable_blocks = FxHashSet::default();
         let mut replacements = FxHashMap::default();
 
-        for (bb, bb_data) in traversal::postorder(body) {
+        for &bb in body.postorder() {
+            let bb_data = &body[bb];
             let terminator = bb_data.terminator();
             // HACK: If the block contains any asm statement it is not regarded as unreachable.
             // This is a temporary solution that handles possibly diverging asm statements.
@@ -57,6 +68,7 @@ impl MirPass<'_> for UnreachablePropagation {
         if replaced {
             simplify::remove_dead_blocks(body);
         }
+        PassOutcome::changed_if(replaced)
     }
 }
 
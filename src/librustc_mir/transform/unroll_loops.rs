@@ -0,0 +1,450 @@
+//! Fully unrolls small loops that run a constant number of times.
+//!
+//! A loop over a counter that starts at a constant, moves by a constant step in every iteration,
+//! and is compared against a constant in the loop header runs a number of times that is known at
+//! compile time:
+//!
+//!     bb0: { _2 = const 0_u32; goto -> bb1; }
+//!     bb1: { _3 = Lt(_2, const 2_u32); switchInt(move _3) -> [false: bb3, otherwise: bb2]; }
+//!     bb2: { _1 = Add(_1, _2); _2 = Add(_2, const 1_u32); goto -> bb1; }
+//!
+//! If that number is small, the blocks of the loop are copied once per iteration, and the header
+//! of every copy jumps straight to the body of the copy or, after the last iteration, out of the
+//! loop. Every copy then reads a counter with a known value, which constant propagation and the
+//! scalar replacement of aggregates can use:
+//!
+//!     bb1: { _3 = Lt(_2, const 2_u32); goto -> bb2; }
+//!     bb2: { _1 = Add(_1, _2); _2 = Add(_2, const 1_u32); goto -> bb4; }
+//!     bb4: { _3 = Lt(_2, const 2_u32); goto -> bb5; }
+//!     bb5: { _1 = Add(_1, _2); _2 = Add(_2, const 1_u32); goto -> bb6; }
+//!     bb6: { _3 = Lt(_2, const 2_u32); goto -> bb3; }
+//!
+//! The counter must not be borrowed, and must be written exactly once in the loop, in a block that
+//! runs in every iteration that goes back to the header. The step is either a plain addition or
+//! subtraction, or a checked one followed by the read of its result. The counter gets its
+//! starting value in the only block that enters the loop from outside. Loops that contain other
+//! loops, or whose counter would leave the range of its type, are left alone. Loops may still be
+//! left early by other edges, which the copies keep.
+
+use crate::transform::jump_threading::writes_to;
+use crate::transform::{MirPass, MirSource, PassOutcome};
+use crate::util::liveness::ever_borrowed_locals;
+use crate::util::loops::natural_loops;
+use rustc_data_structures::graph::dominators::Dominators;
+use rustc_index::bit_set::BitSet;
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::interpret::sign_extend;
+use rustc_middle::mir::visit::{NonMutatingUseContext, PlaceContext, Visitor};
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, Ty, TyCtxt};
+use rustc_target::abi::Size;
+
+/// The largest number of iterations of a loop that is unrolled.
+const MAX_TRIP_COUNT: usize = 8;
+
+/// The largest number of statements and terminators that the copies of a loop may have.
+const MAX_UNROLLED_SIZE: usize = 64;
+
+pub struct UnrollLoops;
+
+impl<'tcx> MirPass<'tcx> for UnrollLoops {
+    fn is_optimization(&self) -> bool {
+        true
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let param_env = tcx.param_env(source.def_id());
+        // Copying blocks doesn't borrow anything new.
+        let borrowed = ever_borrowed_locals(body);
+
+        // Unrolling a loop changes the loops around it, so they are found again every time.
+        let mut changed = false;
+        while let Some(unrolling) = find_unrolling(tcx, param_env, body, &borrowed) {
+            debug!("unrolling {:?}", unrolling);
+            unroll(body, &unrolling);
+            changed = true;
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
+
+#[derive(Debug)]
+struct Unrolling {
+    header: BasicBlock,
+    /// The blocks of the loop besides the header.
+    blocks: Vec<BasicBlock>,
+    /// The successor of the header that stays in the loop.
+    body_entry: BasicBlock,
+    /// The successor of the header that leaves the loop.
+    exit: BasicBlock,
+    trip_count: usize,
+}
+
+fn find_unrolling<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    body: &Body<'tcx>,
+    borrowed: &BitSet<Local>,
+) -> Option<Unrolling> {
+    let dominators = body.dominators();
+    natural_loops(body).into_iter().find_map(|natural_loop| {
+        let header = natural_loop.header;
+        let blocks = natural_loop.blocks;
+        if header == START_BLOCK || blocks.iter().any(|bb| body[bb].is_cleanup) {
+            return None;
+        }
+        let size: usize = blocks.iter().map(|bb| body[bb].statements.len() + 1).sum();
+        if size > MAX_UNROLLED_SIZE {
+            return None;
+        }
+        let predecessors = body.predecessors();
+        let mut outside = predecessors[header].iter().filter(|&&pred| !blocks.contains(pred));
+        let preheader = *outside.next()?;
+        if outside.next().is_some() {
+            return None;
+        }
+
+        // The header has to decide whether the loop goes on by comparing the counter.
+        let (condition, values, targets) = match body[header].terminator().kind {
+            TerminatorKind::SwitchInt {
+                discr: Operand::Copy(place) | Operand::Move(place),
+                switch_ty,
+                ref values,
+                ref targets,
+            } if switch_ty.is_bool() => (place.as_local()?, values, targets),
+            _ => return None,
+        };
+        let target_for = |value| {
+            let index = values.iter().position(|&v| v == value);
+            index.map_or_else(|| *targets.last().unwrap(), |i| targets[i])
+        };
+        let (body_entry, exit) = (target_for(1), target_for(0));
+        if !blocks.contains(body_entry) || blocks.contains(exit) || borrowed.contains(condition) {
+            return None;
+        }
+        let statements = &body[header].statements;
+        let comparison = statements.iter().rev().find(|s| writes_to(s, condition))?;
+        let (op, counter, bound) = match comparison.kind {
+            StatementKind::Assign(box (place, Rvalue::BinaryOp(op, ref lhs, ref rhs)))
+                if place.as_local() == Some(condition) =>
+            {
+                match (lhs, rhs) {
+                    (Operand::Copy(counter), Operand::Constant(bound)) => {
+                        (op, counter.as_local()?, bound)
+                    }
+                    (Operand::Constant(bound), Operand::Copy(counter)) => {
+                        (swap_comparison(op)?, counter.as_local()?, bound)
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        if borrowed.contains(counter) {
+            return None;
+        }
+        let ty = body.local_decls[counter].ty;
+        let counter_ty = CounterTy::new(tcx, param_env, ty)?;
+        let bound = counter_ty.value(bound.literal.try_eval_bits(tcx, param_env, ty)?);
+
+        // Without cycles besides the loop itself, every block runs at most once per iteration.
+        let others: Vec<BasicBlock> = blocks.iter().filter(|&bb| bb != header).collect();
+        if !is_acyclic(body, &others) {
+            return None;
+        }
+        let mut writes = WriteCounter { writes: IndexVec::from_elem(0, &body.local_decls) };
+        for bb in blocks.iter() {
+            writes.visit_basic_block_data(bb, &body[bb]);
+        }
+        if writes.writes[counter] != 1 {
+            return None;
+        }
+        let step_finder = StepFinder { tcx, param_env, body, borrowed, writes: &writes.writes };
+        let (step_block, step) = step_finder.find(&others, counter, &dominators, &counter_ty)?;
+        // Every iteration that goes back to the header has to step the counter.
+        let mut latches = others
+            .iter()
+            .filter(|&&bb| body[bb].terminator().successors().any(|&succ| succ == header))
+            .peekable();
+        if latches.peek().is_none()
+            || !latches.all(|&latch| dominators.is_dominated_by(latch, step_block))
+        {
+            return None;
+        }
+
+        let start = counter_ty.value(starting_value(tcx, param_env, body, preheader, counter)?);
+        let mut value = start;
+        let mut trip_count = 0;
+        while compare(op, value, bound)? {
+            trip_count += 1;
+            value = value.checked_add(step)?;
+            if trip_count > MAX_TRIP_COUNT || !counter_ty.contains(value) {
+                return None;
+            }
+        }
+        if size * trip_count > MAX_UNROLLED_SIZE {
+            return None;
+        }
+        Some(Unrolling { header, blocks: others, body_entry, exit, trip_count })
+    })
+}
+
+/// Copies the blocks of the loop once per iteration, and points the headers of the copies to
+/// where they are known to go.
+fn unroll(body: &mut Body<'_>, unrolling: &Unrolling) {
+    let Unrolling { header, ref blocks, body_entry, exit, trip_count } = *unrolling;
+    let header_data = body[header].clone();
+    let basic_blocks = body.basic_blocks_mut();
+
+    // The loop itself is the copy for the first iteration. The header is copied once more than
+    // the rest, for the last test of the counter.
+    let mut headers = vec![header];
+    let mut copies: Vec<Vec<BasicBlock>> = Vec::new();
+    for iteration in 0..trip_count {
+        headers.push(basic_blocks.push(header_data.clone()));
+        let copy = if iteration == 0 {
+            blocks.clone()
+        } else {
+            blocks.iter().map(|&bb| basic_blocks.push(basic_blocks[bb].clone())).collect()
+        };
+        copies.push(copy);
+    }
+
+    let entry_index = blocks.iter().position(|&bb| bb == body_entry).unwrap();
+    for (iteration, &copy_header) in headers.iter().enumerate() {
+        let target = match copies.get(iteration) {
+            Some(copy) => copy[entry_index],
+            None => exit,
+        };
+        basic_blocks[copy_header].terminator_mut().kind = TerminatorKind::Goto { target };
+    }
+    for (iteration, copy) in copies.iter().enumerate() {
+        for &bb in copy {
+            for successor in basic_blocks[bb].terminator_mut().successors_mut() {
+                if *successor == header {
+                    *successor = headers[iteration + 1];
+                } else if let Some(index) = blocks.iter().position(|&b| b == *successor) {
+                    *successor = copy[index];
+                }
+            }
+        }
+    }
+}
+
+/// Whether the edges between `blocks` form no cycle.
+fn is_acyclic(body: &Body<'_>, blocks: &[BasicBlock]) -> bool {
+    let index_of = |bb| blocks.iter().position(|&b| b == bb);
+    let mut in_degrees = vec![0; blocks.len()];
+    for &bb in blocks {
+        for &succ in body[bb].terminator().successors() {
+            if let Some(index) = index_of(succ) {
+                in_degrees[index] += 1;
+            }
+        }
+    }
+    let mut ready: Vec<usize> = (0..blocks.len()).filter(|&i| in_degrees[i] == 0).collect();
+    let mut visited = 0;
+    while let Some(index) = ready.pop() {
+        visited += 1;
+        for &succ in body[blocks[index]].terminator().successors() {
+            if let Some(index) = index_of(succ) {
+                in_degrees[index] -= 1;
+                if in_degrees[index] == 0 {
+                    ready.push(index);
+                }
+            }
+        }
+    }
+    visited == blocks.len()
+}
+
+/// Returns the constant that `counter` holds when `preheader` enters the loop.
+pub(crate) fn starting_value<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    body: &Body<'tcx>,
+    preheader: BasicBlock,
+    counter: Local,
+) -> Option<u128> {
+    let data = &body[preheader];
+    if !matches!(
+        data.terminator().kind,
+        TerminatorKind::Goto { .. } | TerminatorKind::SwitchInt { .. }
+    ) {
+        return None;
+    }
+    let statement = data.statements.iter().rev().find(|statement| writes_to(statement, counter))?;
+    match statement.kind {
+        StatementKind::Assign(box (place, Rvalue::Use(Operand::Constant(ref c))))
+            if place.as_local() == Some(counter) =>
+        {
+            c.literal.try_eval_bits(tcx, param_env, body.local_decls[counter].ty)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the comparison that gives the same result with its operands swapped.
+fn swap_comparison(op: BinOp) -> Option<BinOp> {
+    match op {
+        BinOp::Eq | BinOp::Ne => Some(op),
+        BinOp::Lt => Some(BinOp::Gt),
+        BinOp::Le => Some(BinOp::Ge),
+        BinOp::Gt => Some(BinOp::Lt),
+        BinOp::Ge => Some(BinOp::Le),
+        _ => None,
+    }
+}
+
+fn compare(op: BinOp, lhs: i128, rhs: i128) -> Option<bool> {
+    match op {
+        BinOp::Eq => Some(lhs == rhs),
+        BinOp::Ne => Some(lhs != rhs),
+        BinOp::Lt => Some(lhs < rhs),
+        BinOp::Le => Some(lhs <= rhs),
+        BinOp::Gt => Some(lhs > rhs),
+        BinOp::Ge => Some(lhs >= rhs),
+        _ => None,
+    }
+}
+
+/// The integer type of a loop counter.
+struct CounterTy {
+    size: Size,
+    signed: bool,
+}
+
+impl CounterTy {
+    fn new<'tcx>(
+        tcx: TyCtxt<'tcx>,
+        param_env: ty::ParamEnv<'tcx>,
+        ty: Ty<'tcx>,
+    ) -> Option<CounterTy> {
+        let signed = match ty.kind {
+            ty::Int(_) => true,
+            ty::Uint(_) => false,
+            _ => return None,
+        };
+        let size = tcx.layout_of(param_env.and(ty)).ok()?.size;
+        // Wider values may not fit the `i128` the counter is simulated in.
+        if size.bits() > 64 {
+            return None;
+        }
+        Some(CounterTy { size, signed })
+    }
+
+    fn value(&self, bits: u128) -> i128 {
+        if self.signed { sign_extend(bits, self.size) as i128 } else { bits as i128 }
+    }
+
+    fn contains(&self, value: i128) -> bool {
+        let bits = self.size.bits();
+        if self.signed {
+            let limit = 1 << (bits - 1);
+            -limit <= value && value < limit
+        } else {
+            0 <= value && value < 1 << bits
+        }
+    }
+}
+
+/// Finds how the counter of a loop changes in every iteration.
+struct StepFinder<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    body: &'a Body<'tcx>,
+    borrowed: &'a BitSet<Local>,
+    writes: &'a IndexVec<Local, u32>,
+}
+
+impl<'tcx> StepFinder<'_, 'tcx> {
+    /// Returns the block that writes to `counter`, and by how much it changes it.
+    fn find(
+        &self,
+        blocks: &[BasicBlock],
+        counter: Local,
+        dominators: &Dominators<BasicBlock>,
+        counter_ty: &CounterTy,
+    ) -> Option<(BasicBlock, i128)> {
+        let (location, statement) = self.find_write(blocks, counter)?;
+        let (op, lhs, rhs) = match statement.kind {
+            StatementKind::Assign(box (place, ref rvalue)) if place.as_local() == Some(counter) => {
+                match *rvalue {
+                    Rvalue::BinaryOp(op, ref lhs, ref rhs) => (op, lhs, rhs),
+                    // A checked step writes the counter from the result of the checked operation,
+                    // which has to be computed earlier in the same iteration.
+                    Rvalue::Use(Operand::Copy(result) | Operand::Move(result)) => {
+                        let checked = match result.projection[..] {
+                            [ProjectionElem::Field(field, _)] if field.index() == 0 => result.local,
+                            _ => return None,
+                        };
+                        if self.borrowed.contains(checked) || self.writes[checked] != 1 {
+                            return None;
+                        }
+                        let (checked_location, checked_statement) =
+                            self.find_write(blocks, checked)?;
+                        if !checked_location.dominates(location, dominators) {
+                            return None;
+                        }
+                        match checked_statement.kind {
+                            StatementKind::Assign(box (
+                                place,
+                                Rvalue::CheckedBinaryOp(op, ref lhs, ref rhs),
+                            )) if place.as_local() == Some(checked) => (op, lhs, rhs),
+                            _ => return None,
+                        }
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+
+        let ty = self.body.local_decls[counter].ty;
+        let step = match (op, lhs, rhs) {
+            (BinOp::Add, Operand::Copy(place), Operand::Constant(step))
+            | (BinOp::Add, Operand::Constant(step), Operand::Copy(place))
+            | (BinOp::Sub, Operand::Copy(place), Operand::Constant(step))
+                if place.as_local() == Some(counter) =>
+            {
+                counter_ty.value(step.literal.try_eval_bits(self.tcx, self.param_env, ty)?)
+            }
+            _ => return None,
+        };
+        Some((location.block, if op == BinOp::Sub { -step } else { step }))
+    }
+
+    /// Returns the only statement in `blocks` that writes to `local`.
+    fn find_write(
+        &self,
+        blocks: &[BasicBlock],
+        local: Local,
+    ) -> Option<(Location, &'a Statement<'tcx>)> {
+        blocks.iter().find_map(|&block| {
+            let statements = &self.body[block].statements;
+            let statement_index = statements.iter().position(|s| writes_to(s, local))?;
+            Some((Location { block, statement_index }, &statements[statement_index]))
+        })
+    }
+}
+
+/// Counts the writes to every local. Moves count as writes, since the local can't be read
+/// afterwards.
+struct WriteCounter {
+    writes: IndexVec<Local, u32>,
+}
+
+impl<'tcx> Visitor<'tcx> for WriteCounter {
+    fn visit_local(&mut self, &local: &Local, context: PlaceContext, _: Location) {
+        if context.is_mutating_use()
+            || context == PlaceContext::NonMutatingUse(NonMutatingUseContext::Move)
+        {
+            self.writes[local] += 1;
+        }
+    }
+}
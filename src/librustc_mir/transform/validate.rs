@@ -1,11 +1,11 @@
 //! Validates the MIR to ensure that invariants are upheld.
 
-use super::{MirPass, MirSource};
+use super::{MirPass, MirSource, PassOutcome};
 use rustc_middle::mir::visit::Visitor;
 use rustc_middle::{
     mir::{
-        BasicBlock, Body, Location, Operand, Rvalue, Statement, StatementKind, Terminator,
-        TerminatorKind,
+        tcx::PlaceTy, BasicBlock, Body, Field, Location, Operand, ProjectionElem, Rvalue,
+        Statement, StatementKind, Terminator, TerminatorKind, VarDebugInfo,
     },
     ty::{
         self,
@@ -26,9 +26,15 @@ pub struct Validator {
 }
 
 impl<'tcx> MirPass<'tcx> for Validator {
-    fn run_pass(&self, tcx: TyCtxt<'tcx>, source: MirSource<'tcx>, body: &mut Body<'tcx>) {
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
         let param_env = tcx.param_env(source.def_id());
         TypeChecker { when: &self.when, source, body, tcx, param_env }.visit_body(body);
+        PassOutcome::Unchanged
     }
 }
 
@@ -140,8 +146,9 @@ impl<'a, 'tcx> TypeChecker<'a, 'tcx> {
         self.tcx.sess.diagnostic().delay_span_bug(
             span,
             &format!(
-                "broken MIR in {:?} ({}) at {:?}:\n{}",
+                "broken MIR in {:?}{} ({}) at {:?}:\n{}",
                 self.source.instance,
+                self.source.promoted_suffix(self.tcx),
                 self.when,
                 location,
                 msg.as_ref()
@@ -149,6 +156,20 @@ impl<'a, 'tcx> TypeChecker<'a, 'tcx> {
         );
     }
 
+    fn fail_var_debug_info(&self, var_debug_info: &VarDebugInfo<'tcx>, msg: impl AsRef<str>) {
+        self.tcx.sess.diagnostic().delay_span_bug(
+            var_debug_info.source_info.span,
+            &format!(
+                "broken MIR in {:?}{} ({}) in debuginfo for `{}`:\n{}",
+                self.source.instance,
+                self.source.promoted_suffix(self.tcx),
+                self.when,
+                var_debug_info.name,
+                msg.as_ref()
+            ),
+        );
+    }
+
     fn check_edge(&self, location: Location, bb: BasicBlock, edge_kind: EdgeKind) {
         if let Some(bb) = self.body.basic_blocks().get(bb) {
             let src = self.body.basic_blocks().get(location.block).unwrap();
@@ -199,9 +220,108 @@ impl<'a, 'tcx> TypeChecker<'a, 'tcx> {
         // differences. So we compare ignoring lifetimes.
         equal_up_to_regions(self.tcx, param_env, src, dest)
     }
+
+    /// Returns the type of field `field` of a value of type `place_ty`, or `None` if there is no
+    /// such field. `recorded_ty` is the type the projection claims the field has, which is
+    /// trusted where the field types aren't known.
+    fn field_ty(
+        &self,
+        place_ty: PlaceTy<'tcx>,
+        field: Field,
+        recorded_ty: Ty<'tcx>,
+    ) -> Option<Ty<'tcx>> {
+        match place_ty.ty.kind {
+            ty::Adt(adt_def, substs) => {
+                let variant = match place_ty.variant_index {
+                    Some(variant_index) => &adt_def.variants[variant_index],
+                    None if !adt_def.is_enum() => adt_def.non_enum_variant(),
+                    None => return None,
+                };
+                variant.fields.get(field.index()).map(|field| field.ty(self.tcx, substs))
+            }
+            ty::Tuple(tys) => tys.get(field.index()).map(|ty| ty.expect_ty()),
+            ty::Closure(_, substs) => substs.as_closure().upvar_tys().nth(field.index()),
+            // The fields of a generator depend on its layout, which isn't known here.
+            ty::Generator(..) => Some(recorded_ty),
+            _ => None,
+        }
+    }
 }
 
 impl<'a, 'tcx> Visitor<'tcx> for TypeChecker<'a, 'tcx> {
+    fn visit_var_debug_info(&mut self, var_debug_info: &VarDebugInfo<'tcx>) {
+        // Passes that remove or renumber locals have to update debuginfo as well. If they don't,
+        // the place refers to a local that doesn't exist anymore, or to another local that took
+        // its index, which is usually of a different type.
+        if self.body.source_scopes.get(var_debug_info.source_info.scope).is_none() {
+            self.fail_var_debug_info(
+                var_debug_info,
+                format!("encountered invalid source scope {:?}", var_debug_info.source_info.scope),
+            );
+        }
+
+        let place = var_debug_info.place;
+        let local_decl = match self.body.local_decls.get(place.local) {
+            Some(local_decl) => local_decl,
+            None => {
+                self.fail_var_debug_info(
+                    var_debug_info,
+                    format!("encountered place of removed local {:?}", place.local),
+                );
+                return;
+            }
+        };
+
+        let mut place_ty = PlaceTy::from_ty(local_decl.ty);
+        for elem in place.projection {
+            match elem {
+                ProjectionElem::Deref => {
+                    if place_ty.ty.builtin_deref(true).is_none() {
+                        self.fail_var_debug_info(
+                            var_debug_info,
+                            format!("encountered deref of non-pointer type {}", place_ty.ty),
+                        );
+                        return;
+                    }
+                }
+                ProjectionElem::Field(field, ty) => match self.field_ty(place_ty, field, ty) {
+                    Some(field_ty) if self.mir_assign_valid_types(field_ty, ty) => {}
+                    Some(field_ty) => {
+                        self.fail_var_debug_info(
+                            var_debug_info,
+                            format!(
+                                "encountered field {:?} of type {}, but the field has type {}",
+                                field, ty, field_ty,
+                            ),
+                        );
+                        return;
+                    }
+                    None => {
+                        self.fail_var_debug_info(
+                            var_debug_info,
+                            format!(
+                                "encountered invalid field {:?} of type {}",
+                                field, place_ty.ty
+                            ),
+                        );
+                        return;
+                    }
+                },
+                ProjectionElem::Downcast(..) => {}
+                ProjectionElem::Index(_)
+                | ProjectionElem::ConstantIndex { .. }
+                | ProjectionElem::Subslice { .. } => {
+                    self.fail_var_debug_info(
+                        var_debug_info,
+                        format!("encountered indexing projection {:?}", elem),
+                    );
+                    return;
+                }
+            }
+            place_ty = place_ty.projection_ty(self.tcx, elem);
+        }
+    }
+
     fn visit_operand(&mut self, operand: &Operand<'tcx>, location: Location) {
         // `Operand::Copy` is only supposed to be used with `Copy` types.
         if let Operand::Copy(place) = operand {
@@ -341,10 +461,16 @@ impl<'a, 'tcx> Visitor<'tcx> for TypeChecker<'a, 'tcx> {
                     self.check_edge(location, *unwind, EdgeKind::Unwind);
                 }
             }
-            TerminatorKind::InlineAsm { destination, .. } => {
+            TerminatorKind::InlineAsm { destination, labels, cleanup, .. } => {
                 if let Some(destination) = destination {
                     self.check_edge(location, *destination, EdgeKind::Normal);
                 }
+                for label in labels {
+                    self.check_edge(location, *label, EdgeKind::Normal);
+                }
+                if let Some(cleanup) = cleanup {
+                    self.check_edge(location, *cleanup, EdgeKind::Unwind);
+                }
             }
             // Nothing to validate for these.
             TerminatorKind::Resume
@@ -0,0 +1,53 @@
+//! Cheap size metrics for a MIR body, used by the pass manager to attribute
+//! growth to individual passes.
+
+use rustc_middle::mir::*;
+use std::fmt;
+use std::mem::size_of;
+
+/// A snapshot of how big a `Body` is. Taking one is linear in the number of
+/// basic blocks and does not look at the contents of individual statements.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BodySize {
+    pub basic_blocks: usize,
+    pub statements: usize,
+    pub locals: usize,
+    pub var_debug_info: usize,
+    pub source_scopes: usize,
+}
+
+impl BodySize {
+    pub fn of(body: &Body<'_>) -> Self {
+        BodySize {
+            basic_blocks: body.basic_blocks().len(),
+            statements: body.basic_blocks().iter().map(|data| data.statements.len()).sum(),
+            locals: body.local_decls.len(),
+            var_debug_info: body.var_debug_info.len(),
+            source_scopes: body.source_scopes.len(),
+        }
+    }
+
+    /// An estimate of the heap memory owned by the body, in bytes. Only the
+    /// top-level vectors are accounted for; boxed operands, projections and
+    /// the like are ignored, so this underestimates but scales with the body.
+    pub fn estimated_bytes(&self) -> usize {
+        self.basic_blocks * size_of::<BasicBlockData<'_>>()
+            + self.statements * size_of::<Statement<'_>>()
+            + self.locals * size_of::<LocalDecl<'_>>()
+            + self.var_debug_info * size_of::<VarDebugInfo<'_>>()
+            + self.source_scopes * size_of::<SourceScopeData>()
+    }
+}
+
+impl fmt::Display for BodySize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} blocks, {} statements, {} locals (~{} bytes)",
+            self.basic_blocks,
+            self.statements,
+            self.locals,
+            self.estimated_bytes()
+        )
+    }
+}
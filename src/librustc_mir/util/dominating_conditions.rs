@@ -0,0 +1,371 @@
+//! Comparisons between unsigned integers that are known to hold in a block of a MIR body.
+//!
+//! A `switchInt` on the result of a comparison, or an `assert` of it, only goes to some of its
+//! successors if the comparison has a certain outcome. If such a successor has no other
+//! predecessor, the comparison holds in every block it dominates, as long as the values it
+//! compares don't change on the way there. Values are traced back through copies inside of a
+//! block, so that the comparisons of temporaries holding copies of the same local, or the length
+//! of the same slice, can be matched with each other.
+//!
+//! Whether a comparison follows from the known ones is decided with the bounds the known ones
+//! give its operands, and by chaining at most two of them.
+
+use crate::util::liveness::ever_borrowed_locals;
+use rustc_data_structures::graph::dominators::Dominators;
+use rustc_index::bit_set::BitSet;
+use rustc_middle::mir::visit::{PlaceContext, Visitor};
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, TyCtxt};
+
+/// An unsigned integer in a MIR body.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Value<'tcx> {
+    Const(u128),
+    /// The value of a local, which is only meaningful at a point of the body.
+    Local(Local),
+    /// The length of an array or slice.
+    Len(Place<'tcx>),
+}
+
+impl<'tcx> Value<'tcx> {
+    /// The locals that have to keep their values for the value to stay the same.
+    fn locals(&self) -> Vec<Local> {
+        match *self {
+            Value::Const(_) => vec![],
+            Value::Local(local) => vec![local],
+            Value::Len(place) => {
+                let indices = place.projection.iter().filter_map(|elem| match *elem {
+                    ProjectionElem::Index(local) => Some(local),
+                    _ => None,
+                });
+                Some(place.local).into_iter().chain(indices).collect()
+            }
+        }
+    }
+}
+
+/// `lhs < rhs` if `strict`, `lhs <= rhs` otherwise.
+#[derive(Copy, Clone, Debug)]
+pub struct Comparison<'tcx> {
+    pub lhs: Value<'tcx>,
+    pub rhs: Value<'tcx>,
+    pub strict: bool,
+}
+
+impl<'tcx> Comparison<'tcx> {
+    fn negate(self) -> Comparison<'tcx> {
+        Comparison { lhs: self.rhs, rhs: self.lhs, strict: !self.strict }
+    }
+}
+
+/// A comparison that holds when `source` goes to `target`.
+struct EdgeCondition<'tcx> {
+    source: BasicBlock,
+    target: BasicBlock,
+    /// The index of the statement in `source` that computes the comparison.
+    statement_index: usize,
+    comparison: Comparison<'tcx>,
+}
+
+pub struct DominatingConditions<'a, 'tcx> {
+    tcx: TyCtxt<'tcx>,
+    param_env: ty::ParamEnv<'tcx>,
+    body: &'a Body<'tcx>,
+    borrowed: BitSet<Local>,
+    dominators: Dominators<BasicBlock>,
+    conditions: Vec<EdgeCondition<'tcx>>,
+}
+
+impl<'a, 'tcx> DominatingConditions<'a, 'tcx> {
+    pub fn new(
+        tcx: TyCtxt<'tcx>,
+        param_env: ty::ParamEnv<'tcx>,
+        body: &'a Body<'tcx>,
+    ) -> DominatingConditions<'a, 'tcx> {
+        let mut this = DominatingConditions {
+            tcx,
+            param_env,
+            body,
+            borrowed: ever_borrowed_locals(body),
+            dominators: body.dominators(),
+            conditions: Vec::new(),
+        };
+        let predecessors = body.predecessors();
+        for (source, data) in body.basic_blocks().iter_enumerated() {
+            // The successors the terminator goes to if the condition is true and if it is false.
+            let (condition, if_true, if_false) = match data.terminator().kind {
+                TerminatorKind::SwitchInt {
+                    discr: Operand::Copy(place) | Operand::Move(place),
+                    switch_ty,
+                    ref values,
+                    ref targets,
+                } if switch_ty.is_bool() && values.len() == 1 && targets.len() == 2 => {
+                    let (if_true, if_false) = match values[0] {
+                        0 => (targets[1], targets[0]),
+                        _ => (targets[0], targets[1]),
+                    };
+                    (place, Some(if_true), Some(if_false))
+                }
+                TerminatorKind::Assert {
+                    cond: Operand::Copy(place) | Operand::Move(place),
+                    expected,
+                    target,
+                    ..
+                } => {
+                    if expected {
+                        (place, Some(target), None)
+                    } else {
+                        (place, None, Some(target))
+                    }
+                }
+                _ => continue,
+            };
+            let condition = match condition.as_local() {
+                Some(condition) => condition,
+                None => continue,
+            };
+            let (statement_index, comparison) =
+                match this.comparison(source, data.statements.len(), condition) {
+                    Some(found) => found,
+                    None => continue,
+                };
+            let edges = if_true
+                .map(|target| (target, comparison))
+                .into_iter()
+                .chain(if_false.map(|target| (target, comparison.negate())));
+            for (target, comparison) in edges {
+                if if_true == if_false || predecessors[target].len() != 1 {
+                    continue;
+                }
+                this.conditions.push(EdgeCondition { source, target, statement_index, comparison });
+            }
+        }
+        this
+    }
+
+    /// Returns the comparison of unsigned integers that `local` was last assigned before the
+    /// statement `statement_index` of `block`, with the index of the assignment.
+    pub fn comparison(
+        &self,
+        block: BasicBlock,
+        statement_index: usize,
+        local: Local,
+    ) -> Option<(usize, Comparison<'tcx>)> {
+        let statements = &self.body[block].statements[..statement_index];
+        let index = statements.iter().rposition(|statement| writes_to(statement, &[local]))?;
+        let (op, lhs, rhs) = match statements[index].kind {
+            StatementKind::Assign(box (place, Rvalue::BinaryOp(op, ref lhs, ref rhs)))
+                if place.as_local() == Some(local) =>
+            {
+                (op, lhs, rhs)
+            }
+            _ => return None,
+        };
+        if !matches!(lhs.ty(self.body, self.tcx).kind, ty::Uint(_)) {
+            return None;
+        }
+        let lhs = self.value(block, index, lhs)?;
+        let rhs = self.value(block, index, rhs)?;
+        let comparison = match op {
+            BinOp::Lt => Comparison { lhs, rhs, strict: true },
+            BinOp::Le => Comparison { lhs, rhs, strict: false },
+            BinOp::Gt => Comparison { lhs: rhs, rhs: lhs, strict: true },
+            BinOp::Ge => Comparison { lhs: rhs, rhs: lhs, strict: false },
+            _ => return None,
+        };
+        Some((index, comparison))
+    }
+
+    /// Returns the value `operand` has at the statement `statement_index` of `block`, traced back
+    /// through the copies before it in the block.
+    fn value(
+        &self,
+        block: BasicBlock,
+        statement_index: usize,
+        operand: &Operand<'tcx>,
+    ) -> Option<Value<'tcx>> {
+        let mut local = match operand {
+            Operand::Constant(c) => {
+                return Some(Value::Const(c.literal.try_eval_bits(
+                    self.tcx,
+                    self.param_env,
+                    c.literal.ty,
+                )?));
+            }
+            Operand::Copy(place) | Operand::Move(place) => place.as_local()?,
+        };
+        let statements = &self.body[block].statements[..statement_index];
+        let mut end = statements.len();
+        // Whether nothing between the assignment at `index` and the use writes to `value`.
+        let unchanged = |index: usize, value: Value<'tcx>| {
+            !statements[index + 1..].iter().any(|statement| writes_to(statement, &value.locals()))
+        };
+        loop {
+            let index = match statements[..end].iter().rposition(|s| writes_to(s, &[local])) {
+                Some(index) => index,
+                None => return Some(Value::Local(local)),
+            };
+            let rvalue = match statements[index].kind {
+                StatementKind::Assign(box (place, ref rvalue))
+                    if place.as_local() == Some(local) =>
+                {
+                    rvalue
+                }
+                _ => return Some(Value::Local(local)),
+            };
+            let value = match *rvalue {
+                Rvalue::Use(Operand::Constant(ref c)) => {
+                    return Some(Value::Const(c.literal.try_eval_bits(
+                        self.tcx,
+                        self.param_env,
+                        c.literal.ty,
+                    )?));
+                }
+                Rvalue::Use(Operand::Copy(source) | Operand::Move(source)) => {
+                    match source.as_local() {
+                        Some(source) => Value::Local(source),
+                        None => return Some(Value::Local(local)),
+                    }
+                }
+                Rvalue::Len(place) => Value::Len(place),
+                _ => return Some(Value::Local(local)),
+            };
+            if !unchanged(index, value) {
+                return Some(Value::Local(local));
+            }
+            match value {
+                Value::Local(source) => {
+                    local = source;
+                    end = index;
+                }
+                _ => return Some(value),
+            }
+        }
+    }
+
+    /// Whether `comparison`, between values traced back inside of `block`, holds there because of
+    /// the conditions on the way to it.
+    pub fn implies(&self, block: BasicBlock, comparison: &Comparison<'tcx>) -> bool {
+        let known: Vec<Comparison<'tcx>> = self
+            .conditions
+            .iter()
+            .filter(|condition| {
+                condition.source != block
+                    && self.dominators.is_dominated_by(block, condition.target)
+                    && self.holds_in(condition, block)
+            })
+            .map(|condition| condition.comparison)
+            .collect();
+        debug!("conditions known in {:?}: {:?}", block, known);
+        follows(&known, comparison)
+    }
+
+    /// Whether the values `condition` compares keep their values from the comparison to the end
+    /// of `block`.
+    fn holds_in(&self, condition: &EdgeCondition<'tcx>, block: BasicBlock) -> bool {
+        let mut locals = condition.comparison.lhs.locals();
+        locals.extend(condition.comparison.rhs.locals());
+        if locals.iter().any(|&local| self.borrowed.contains(local)) {
+            return false;
+        }
+        let source_statements = &self.body[condition.source].statements;
+        if source_statements[condition.statement_index + 1..]
+            .iter()
+            .any(|statement| writes_to(statement, &locals))
+        {
+            return false;
+        }
+
+        // The blocks on the paths from the target to `block` that don't go through the source
+        // again, which would compare the values anew.
+        let predecessors = self.body.predecessors();
+        let blocks = self.body.basic_blocks().len();
+        let mut from_target = BitSet::new_empty(blocks);
+        let mut stack = vec![condition.target];
+        while let Some(bb) = stack.pop() {
+            if bb != condition.source && from_target.insert(bb) {
+                stack.extend(self.body[bb].terminator().successors());
+            }
+        }
+        let mut to_block = BitSet::new_empty(blocks);
+        let mut stack = vec![block];
+        while let Some(bb) = stack.pop() {
+            if bb != condition.source && to_block.insert(bb) {
+                stack.extend(predecessors[bb].iter());
+            }
+        }
+        from_target.intersect(&to_block);
+        from_target.iter().all(|bb| {
+            let mut finder = WriteFinder { locals: &locals, found: false };
+            finder.visit_basic_block_data(bb, &self.body[bb]);
+            !finder.found
+        })
+    }
+}
+
+/// Whether `goal` follows from the `known` comparisons.
+fn follows<'tcx>(known: &[Comparison<'tcx>], goal: &Comparison<'tcx>) -> bool {
+    let at_least_as_strong = |c: &Comparison<'tcx>| c.strict || !goal.strict;
+    if known.iter().any(|c| c.lhs == goal.lhs && c.rhs == goal.rhs && at_least_as_strong(c)) {
+        return true;
+    }
+    // `lhs < middle <= rhs`, `lhs <= middle < rhs`, or both non-strict.
+    let chained = known.iter().filter(|c| c.lhs == goal.lhs).any(|first| {
+        known
+            .iter()
+            .filter(|c| c.lhs == first.rhs && c.rhs == goal.rhs)
+            .any(|second| first.strict || second.strict || !goal.strict)
+    });
+    if chained {
+        return true;
+    }
+
+    // The smallest value `lhs` is known to be below, and the smallest one `rhs` may have.
+    let mut upper = match goal.lhs {
+        Value::Const(value) => value.checked_add(1),
+        _ => None,
+    };
+    let mut lower = match goal.rhs {
+        Value::Const(value) => value,
+        _ => 0,
+    };
+    for c in known {
+        if let (true, Value::Const(value)) = (c.lhs == goal.lhs, c.rhs) {
+            let bound = if c.strict { Some(value) } else { value.checked_add(1) };
+            upper = match (upper, bound) {
+                (Some(upper), Some(bound)) => Some(upper.min(bound)),
+                (upper, bound) => upper.or(bound),
+            };
+        }
+        if let (Value::Const(value), true) = (c.lhs, c.rhs == goal.rhs) {
+            let bound = if c.strict { value.saturating_add(1) } else { value };
+            lower = lower.max(bound);
+        }
+    }
+    match upper {
+        Some(upper) if goal.strict => upper <= lower,
+        Some(upper) => upper <= lower.saturating_add(1),
+        None => false,
+    }
+}
+
+fn writes_to(statement: &Statement<'_>, locals: &[Local]) -> bool {
+    let mut finder = WriteFinder { locals, found: false };
+    finder.visit_statement(statement, Location::START);
+    finder.found
+}
+
+/// Finds writes to `locals`. Writes through a local that is a pointer don't change its value.
+struct WriteFinder<'a> {
+    locals: &'a [Local],
+    found: bool,
+}
+
+impl<'tcx> Visitor<'tcx> for WriteFinder<'_> {
+    fn visit_place(&mut self, place: &Place<'tcx>, context: PlaceContext, location: Location) {
+        if self.locals.contains(&place.local) && context.is_mutating_use() && !place.is_indirect() {
+            self.found = true;
+        }
+        self.super_place(place, context, location);
+    }
+}
@@ -0,0 +1,220 @@
+//! Liveness of locals, for the passes that remove code whose result is never used.
+//!
+//! `UsedLocals` answers whether a local is used anywhere in the body, and keeps the answer
+//! up to date while statements are removed. `LiveLocals` answers whether a local may still be
+//! read after a given statement, which is what dead store elimination needs. Both treat
+//! mentions in `var_debug_info` according to `DebugInfoUses`, so that passes agree on which
+//! user variables must stay observable in a debugger. `live_locals_on_entry` gives the liveness
+//! at block boundaries from a cache that passes share. None of them sees accesses through
+//! references, so `ever_borrowed_locals` tells which locals they don't describe fully.
+
+use crate::dataflow::impls::MaybeLiveLocals;
+use crate::dataflow::{Analysis, Results, ResultsCursor};
+use rustc_hir::def_id::DefId;
+use rustc_index::bit_set::BitSet;
+use rustc_index::vec::IndexVec;
+use rustc_middle::mir::visit::{MutatingUseContext, PlaceContext, Visitor};
+use rustc_middle::mir::*;
+use rustc_middle::ty::TyCtxt;
+
+/// Returns the locals that may be read before they are overwritten on entry to each block, with
+/// no locals for the unreachable blocks. The result is cached on the body until it changes, so
+/// passes that only need liveness at block boundaries should use this instead of `LiveLocals`.
+/// Mentions in `var_debug_info` don't count.
+pub fn live_locals_on_entry<'a, 'tcx>(
+    tcx: TyCtxt<'tcx>,
+    body: &'a Body<'tcx>,
+    def_id: DefId,
+) -> &'a IndexVec<BasicBlock, BitSet<Local>> {
+    body.live_locals_on_entry(|| {
+        let mut cursor = MaybeLiveLocals
+            .into_engine(tcx, body, def_id)
+            .iterate_to_fixpoint()
+            .into_results_cursor(body);
+        let mut live =
+            IndexVec::from_elem(BitSet::new_empty(body.local_decls.len()), body.basic_blocks());
+        for &bb in body.postorder() {
+            cursor.seek_to_block_start(bb);
+            live[bb].overwrite(cursor.get());
+        }
+        live
+    })
+}
+
+/// Whether a mention of a local in `var_debug_info` counts as a use.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DebugInfoUses {
+    /// Locals described by debuginfo are live everywhere.
+    Live,
+    /// Debuginfo does not keep locals alive. The caller has to update or remove the
+    /// `var_debug_info` entries of the locals it gets rid of.
+    Ignored,
+}
+
+/// Counts the uses of every local in a body.
+///
+/// An assignment to a local (or one of its fields) whose rvalue has no side effects is not a
+/// use of that local, since removing the local allows removing the assignment as well. Storage
+/// markers are not uses either. The return place and the arguments are always used.
+pub struct UsedLocals {
+    use_counts: IndexVec<Local, u32>,
+    arg_count: usize,
+    debuginfo: DebugInfoUses,
+}
+
+impl UsedLocals {
+    pub fn new(body: &Body<'_>, debuginfo: DebugInfoUses) -> Self {
+        let mut used_locals = UsedLocals {
+            use_counts: IndexVec::from_elem(0, &body.local_decls),
+            arg_count: body.arg_count,
+            debuginfo,
+        };
+        UseCounter { used_locals: &mut used_locals, increment: true }.visit_body(body);
+        used_locals
+    }
+
+    /// Returns whether `local` is used anywhere in the body.
+    pub fn is_used(&self, local: Local) -> bool {
+        trace!("is_used({:?}): use_count: {:?}", local, self.use_counts[local]);
+        local.as_usize() <= self.arg_count || self.use_counts[local] != 0
+    }
+
+    /// Updates the use counts to account for the removal of `statement` from the body.
+    pub fn statement_removed(&mut self, statement: &Statement<'tcx>, location: Location) {
+        UseCounter { used_locals: self, increment: false }.visit_statement(statement, location);
+    }
+}
+
+/// Returns whether an assignment of `rvalue` can be removed when its destination is unused.
+pub fn rvalue_is_removable(rvalue: &Rvalue<'_>) -> bool {
+    match rvalue {
+        Rvalue::Use(_)
+        | Rvalue::Discriminant(_)
+        | Rvalue::BinaryOp(_, _, _)
+        | Rvalue::CheckedBinaryOp(_, _, _)
+        | Rvalue::Repeat(_, _)
+        | Rvalue::AddressOf(_, _)
+        | Rvalue::Len(_)
+        | Rvalue::UnaryOp(_, _)
+        | Rvalue::Aggregate(_, _) => true,
+
+        Rvalue::Ref(..) | Rvalue::ThreadLocalRef(_) | Rvalue::Cast(..) | Rvalue::NullaryOp(..) => {
+            false
+        }
+    }
+}
+
+/// Returns the locals that are borrowed somewhere in `body`, so that they may be read or written
+/// through a reference.
+pub fn ever_borrowed_locals(body: &Body<'_>) -> BitSet<Local> {
+    let mut finder = BorrowFinder { borrowed: BitSet::new_empty(body.local_decls.len()) };
+    finder.visit_body(body);
+    finder.borrowed
+}
+
+struct BorrowFinder {
+    borrowed: BitSet<Local>,
+}
+
+impl Visitor<'tcx> for BorrowFinder {
+    fn visit_rvalue(&mut self, rvalue: &Rvalue<'tcx>, location: Location) {
+        if let Rvalue::Ref(_, _, place) | Rvalue::AddressOf(_, place) = *rvalue {
+            if !place.is_indirect() {
+                self.borrowed.insert(place.local);
+            }
+        }
+        self.super_rvalue(rvalue, location);
+    }
+}
+
+struct UseCounter<'a> {
+    used_locals: &'a mut UsedLocals,
+    increment: bool,
+}
+
+impl Visitor<'tcx> for UseCounter<'_> {
+    fn visit_statement(&mut self, statement: &Statement<'tcx>, location: Location) {
+        match statement.kind {
+            StatementKind::StorageLive(_) | StatementKind::StorageDead(_) => {}
+
+            // Only the locals in the index projections of the destination are used.
+            StatementKind::Assign(box (ref place, ref rvalue))
+                if !place.is_indirect() && rvalue_is_removable(rvalue) =>
+            {
+                let context = PlaceContext::MutatingUse(MutatingUseContext::Store);
+                self.visit_projection(place.local, &place.projection, context, location);
+                self.visit_rvalue(rvalue, location);
+            }
+
+            _ => self.super_statement(statement, location),
+        }
+    }
+
+    fn visit_var_debug_info(&mut self, var_debug_info: &VarDebugInfo<'tcx>) {
+        if self.used_locals.debuginfo == DebugInfoUses::Live {
+            self.super_var_debug_info(var_debug_info);
+        }
+    }
+
+    fn visit_local(&mut self, local: &Local, _: PlaceContext, _: Location) {
+        let use_count = &mut self.used_locals.use_counts[*local];
+        if self.increment {
+            *use_count += 1;
+        } else {
+            assert_ne!(*use_count, 0, "removed a use of {:?} that was never counted", local);
+            *use_count -= 1;
+        }
+    }
+}
+
+/// The liveness of every local at every statement of a body.
+///
+/// Like `MaybeLiveLocals`, which this is built on, a borrow is a use of the borrowed local only
+/// where the borrow is created.
+pub struct LiveLocals<'mir, 'tcx> {
+    cursor: ResultsCursor<'mir, 'tcx, MaybeLiveLocals>,
+    debuginfo_locals: BitSet<Local>,
+}
+
+impl LiveLocals<'mir, 'tcx> {
+    pub fn new(
+        tcx: TyCtxt<'tcx>,
+        body: &'mir Body<'tcx>,
+        def_id: DefId,
+        debuginfo: DebugInfoUses,
+    ) -> Self {
+        let results: Results<'tcx, MaybeLiveLocals> =
+            MaybeLiveLocals.into_engine(tcx, body, def_id).iterate_to_fixpoint();
+
+        let mut debuginfo_locals = BitSet::new_empty(body.local_decls.len());
+        if debuginfo == DebugInfoUses::Live {
+            for var_debug_info in &body.var_debug_info {
+                debuginfo_locals.insert(var_debug_info.place.local);
+            }
+        }
+
+        LiveLocals { cursor: results.into_results_cursor(body), debuginfo_locals }
+    }
+
+    /// Returns whether `local` may be read before it is overwritten, once the statement or
+    /// terminator at `location` has executed.
+    pub fn is_live_after(&mut self, local: Local, location: Location) -> bool {
+        if self.debuginfo_locals.contains(local) {
+            return true;
+        }
+        // Liveness is a backward analysis, so the state after a statement in program order is
+        // the one before its effect is applied.
+        self.cursor.seek_before_primary_effect(location);
+        self.cursor.contains(local)
+    }
+
+    /// Returns whether `local` may be read before it is overwritten, right before the statement
+    /// or terminator at `location` executes.
+    pub fn is_live_before(&mut self, local: Local, location: Location) -> bool {
+        if self.debuginfo_locals.contains(local) {
+            return true;
+        }
+        self.cursor.seek_after_primary_effect(location);
+        self.cursor.contains(local)
+    }
+}
@@ -0,0 +1,59 @@
+//! Natural loops of a MIR body.
+//!
+//! A back edge is an edge to a block that dominates its source. The natural loop of a back edge
+//! consists of its target, the header of the loop, and every block that can reach the source of
+//! the back edge without going through the header. Back edges to the same header belong to the
+//! same loop. Irreducible control flow, which MIR built from Rust code does not have, forms no
+//! natural loops.
+
+use rustc_index::bit_set::BitSet;
+use rustc_middle::mir::*;
+
+pub struct NaturalLoop {
+    /// The block that dominates the whole loop, and the only one entered from outside of it.
+    pub header: BasicBlock,
+    /// The blocks of the loop, including the header.
+    pub blocks: BitSet<BasicBlock>,
+}
+
+/// Returns the natural loops of `body`, with every loop coming before the loops that contain it.
+pub fn natural_loops(body: &Body<'_>) -> Vec<NaturalLoop> {
+    let dominators = body.dominators();
+    let predecessors = body.predecessors();
+    let mut loops: Vec<NaturalLoop> = Vec::new();
+    for (bb, data) in body.basic_blocks().iter_enumerated() {
+        if !dominators.is_reachable(bb) {
+            continue;
+        }
+        for &header in data.terminator().successors() {
+            if !dominators.is_dominated_by(bb, header) {
+                continue;
+            }
+            let index = match loops.iter().position(|natural_loop| natural_loop.header == header) {
+                Some(index) => index,
+                None => {
+                    let mut blocks = BitSet::new_empty(body.basic_blocks().len());
+                    blocks.insert(header);
+                    loops.push(NaturalLoop { header, blocks });
+                    loops.len() - 1
+                }
+            };
+
+            // Walks backwards from the back edge, which stops at the header since it is already
+            // part of the loop.
+            let blocks = &mut loops[index].blocks;
+            let mut stack = vec![bb];
+            while let Some(block) = stack.pop() {
+                if blocks.insert(block) {
+                    stack.extend(
+                        predecessors[block].iter().filter(|&&pred| dominators.is_reachable(pred)),
+                    );
+                }
+            }
+        }
+    }
+
+    // A loop that is nested in another one has fewer blocks.
+    loops.sort_by_key(|natural_loop| natural_loop.blocks.count());
+    loops
+}
@@ -1,8 +1,13 @@
 pub mod aggregate;
+pub mod body_size;
 pub mod borrowck_errors;
 pub mod def_use;
+pub mod dominating_conditions;
 pub mod elaborate_drops;
+pub mod liveness;
+pub mod loops;
 pub mod patch;
+pub mod remark;
 pub mod storage;
 
 mod alignment;
@@ -12,6 +17,7 @@ pub(crate) mod pretty;
 
 pub use self::aggregate::expand_aggregate;
 pub use self::alignment::is_disaligned;
+pub use self::body_size::BodySize;
 pub use self::graphviz::write_node_label as write_graphviz_node_label;
 pub use self::graphviz::{graphviz_safe_def_name, write_mir_graphviz};
-pub use self::pretty::{dump_enabled, dump_mir, write_mir_pretty, PassWhere};
+pub use self::pretty::{dump_enabled, dump_enabled_for_source, dump_mir, write_mir_pretty, PassWhere};
@@ -60,6 +60,17 @@ pub enum PassWhere {
 ///   (`&` has higher precedence). At least one of the `|`-separated groups
 ///   must match; an `|`-separated group matches if all of its `&`-separated
 ///   substrings are matched.
+/// - substrings containing `*` or `?` are globs, which have to match the whole
+///   pass name or item path instead. `*` matches any number of characters,
+///   including `::`, and `?` matches a single character.
+///
+/// The item path of a shim ends with the kind of shim, the same way instances
+/// are printed, e.g. `std::ptr::drop_in_place - shim(Some(Foo))`.
+/// The item path of a promoted body is the one of its parent followed by the
+/// index of the promoted, e.g. `foo::bar::promoted[0]`, so that `promoted`
+/// selects every promoted body and `bar::promoted[0]` a single one.
+/// MIR passes that are scheduled several times are also matched by their
+/// identifier, e.g. `AddCallGuards#2` for the second run of `AddCallGuards`.
 ///
 /// Example:
 ///
@@ -69,6 +80,7 @@ pub enum PassWhere {
 ///   or `typeck` appears in the name.
 /// - `foo & nll | bar & typeck` == match if `foo` and `nll` both appear in the name
 ///   or `typeck` and `bar` both appear in the name.
+/// - `foo::bar::*{{closure}}*` == match the closures in module `foo::bar`.
 pub fn dump_mir<'tcx, F>(
     tcx: TyCtxt<'tcx>,
     pass_num: Option<&dyn Display>,
@@ -80,7 +92,7 @@ pub fn dump_mir<'tcx, F>(
 ) where
     F: FnMut(PassWhere, &mut dyn Write) -> io::Result<()>,
 {
-    if !dump_enabled(tcx, pass_name, source.def_id()) {
+    if !dump_enabled_for_source(tcx, pass_name, source) {
         return;
     }
 
@@ -88,26 +100,104 @@ pub fn dump_mir<'tcx, F>(
 }
 
 pub fn dump_enabled<'tcx>(tcx: TyCtxt<'tcx>, pass_name: &str, def_id: DefId) -> bool {
+    filters_match(tcx, pass_name, || node_path(tcx, def_id))
+}
+
+/// Like `dump_enabled`, but lets the filters tell shims apart from the item they belong to.
+pub fn dump_enabled_for_source<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    pass_name: &str,
+    source: MirSource<'tcx>,
+) -> bool {
+    filters_match(tcx, pass_name, || {
+        let mut path = node_path(tcx, source.def_id());
+        match source.instance {
+            ty::InstanceDef::Item(_) => {}
+            ty::InstanceDef::VtableShim(_) => path.push_str(" - shim(vtable)"),
+            ty::InstanceDef::ReifyShim(_) => path.push_str(" - shim(reify)"),
+            ty::InstanceDef::Intrinsic(_) => path.push_str(" - intrinsic"),
+            ty::InstanceDef::Virtual(_, num) => write!(path, " - virtual#{}", num).unwrap(),
+            ty::InstanceDef::ClosureOnceShim { .. } => path.push_str(" - shim"),
+            ty::InstanceDef::FnPtrShim(_, ty) | ty::InstanceDef::CloneShim(_, ty) => {
+                write!(path, " - shim({:?})", ty).unwrap()
+            }
+            ty::InstanceDef::DropGlue(_, ty) => write!(path, " - shim({:?})", ty).unwrap(),
+            ty::InstanceDef::Outlined(_, index) => write!(path, " - outlined#{}", index).unwrap(),
+        }
+        if let Some(promoted) = source.promoted {
+            write!(path, "::{:?}", promoted).unwrap();
+        }
+        path
+    })
+}
+
+fn node_path(tcx: TyCtxt<'_>, def_id: DefId) -> String {
+    ty::print::with_forced_impl_filename_line(|| {
+        // see notes on #41697 below
+        tcx.def_path_str(def_id)
+    })
+}
+
+fn filters_match(tcx: TyCtxt<'_>, pass_name: &str, node_path: impl FnOnce() -> String) -> bool {
     let filters = match tcx.sess.opts.debugging_opts.dump_mir {
         None => return false,
         Some(ref filters) => filters,
     };
-    let node_path = ty::print::with_forced_impl_filename_line(|| {
-        // see notes on #41697 below
-        tcx.def_path_str(def_id)
-    });
+    let node_path = node_path();
     filters.split('|').any(|or_filter| {
         or_filter.split('&').all(|and_filter| {
-            and_filter == "all" || pass_name.contains(and_filter) || node_path.contains(and_filter)
+            let and_filter = and_filter.trim();
+            and_filter == "all"
+                || filter_matches(and_filter, pass_name)
+                || filter_matches(and_filter, &node_path)
         })
     })
 }
 
+/// Globs have to match all of `name`, other filters any part of it.
+fn filter_matches(filter: &str, name: &str) -> bool {
+    if filter.contains(|c| c == '*' || c == '?') {
+        glob_matches(filter.as_bytes(), name.as_bytes())
+    } else {
+        name.contains(filter)
+    }
+}
+
+fn glob_matches(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // The position of the last `*` in the pattern, and of the byte in `name` that the part of
+    // the pattern after it is matched from, so that a failed match can be retried with the `*`
+    // matching one more byte.
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, star_n)) => {
+                    backtrack = Some((star, star_n + 1));
+                    p = star + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
 // #41697 -- we use `with_forced_impl_filename_line()` because
 // `def_path_str()` would otherwise trigger `type_of`, and this can
 // run while we are already attempting to evaluate `type_of`.
 
-fn dump_matched_mir_node<'tcx, F>(
+/// Dumps `body` like `dump_mir`, for callers that already checked the filters.
+pub fn dump_matched_mir_node<'tcx, F>(
     tcx: TyCtxt<'tcx>,
     pass_num: Option<&dyn Display>,
     pass_name: &str,
@@ -130,6 +220,9 @@ fn dump_matched_mir_node<'tcx, F>(
             Some(promoted) => write!(file, "::{:?}`", promoted)?,
         }
         writeln!(file, " {} {}", disambiguator, pass_name)?;
+        if let Some(span) = source.promoted_span {
+            writeln!(file, "// promoted from {}", tcx.sess.source_map().span_to_string(span))?;
+        }
         if let Some(ref layout) = body.generator_layout {
             writeln!(file, "/* generator_layout = {:#?} */", layout)?;
         }
@@ -179,10 +272,10 @@ fn dump_path(
 
     let crate_name = tcx.crate_name(source.def_id().krate);
     let item_name = tcx.def_path(source.def_id()).to_filename_friendly_no_crate();
-    // All drop shims have the same DefId, so we have to add the type
+    // All drop shims have the same DefId, and so do all clone shims, so we have to add the type
     // to get unique file names.
     let shim_disambiguator = match source.instance {
-        ty::InstanceDef::DropGlue(_, Some(ty)) => {
+        ty::InstanceDef::DropGlue(_, Some(ty)) | ty::InstanceDef::CloneShim(_, ty) => {
             // Unfortunately, pretty-printed typed are not very filename-friendly.
             // We dome some filtering.
             let mut s = ".".to_owned();
@@ -193,6 +286,8 @@ fn dump_path(
             }));
             s
         }
+        // Outlined blocks share the `DefId` of the body they were moved out of.
+        ty::InstanceDef::Outlined(_, index) => format!(".outlined{}", index),
         _ => String::new(),
     };
 
@@ -259,6 +354,7 @@ pub fn write_mir_pretty<'tcx>(
             let src = MirSource {
                 instance: ty::InstanceDef::Item(ty::WithOptConstParam::unknown(def_id)),
                 promoted: Some(i),
+                promoted_span: Some(body.span),
             };
             write_mir_fn(tcx, src, body, &mut |_, _| Ok(()), w)?;
         }
@@ -315,12 +411,22 @@ where
     for statement in &data.statements {
         extra_data(PassWhere::BeforeLocation(current_location), w)?;
         let indented_body = format!("{0}{0}{1:?};", INDENT, statement);
+        let provenance = match body.statement_provenance {
+            Some(ref provenance) => {
+                let origins = &provenance[block][current_location.statement_index];
+                let origins: Vec<_> =
+                    origins.iter().map(|origin| format!("{:?}", origin)).collect();
+                format!(" (from [{}])", origins.join(", "))
+            }
+            None => String::new(),
+        };
         writeln!(
             w,
-            "{:A$} // {}{}",
+            "{:A$} // {}{}{}",
             indented_body,
             if tcx.sess.verbose() { format!("{:?}: ", current_location) } else { String::new() },
             comment(tcx, statement.source_info),
+            provenance,
             A = ALIGN,
         )?;
 
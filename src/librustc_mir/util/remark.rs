@@ -0,0 +1,24 @@
+//! Optimization remarks, which tell users about the decisions of MIR passes (`-Z remark-mir`).
+//!
+//! Remarks are emitted as notes, so `--error-format=json` turns them into JSON like any other
+//! diagnostic.
+
+use rustc_middle::ty::TyCtxt;
+use rustc_session::config::Passes;
+use rustc_span::Span;
+
+/// Returns whether the remarks of `pass_name` were requested.
+pub fn remarks_enabled(tcx: TyCtxt<'_>, pass_name: &str) -> bool {
+    match tcx.sess.opts.debugging_opts.remark_mir {
+        Passes::All => true,
+        Passes::Some(ref passes) => passes.iter().any(|pass| pass == pass_name),
+    }
+}
+
+/// Emits a remark of `pass_name` about the code at `span`. `message` is only called if the
+/// remarks of the pass were requested.
+pub fn emit_remark(tcx: TyCtxt<'_>, pass_name: &str, span: Span, message: impl FnOnce() -> String) {
+    if remarks_enabled(tcx, pass_name) {
+        tcx.sess.span_note_without_error(span, &format!("{}: {}", pass_name, message()));
+    }
+}
@@ -322,6 +322,7 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
             ExprKind::InlineAsm { template, operands, options, line_spans } => {
                 use crate::thir;
                 use rustc_middle::mir;
+                let mut label_exprs = vec![];
                 let operands = operands
                     .into_iter()
                     .map(|op| match op {
@@ -365,10 +366,22 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
                         thir::InlineAsmOperand::SymStatic { def_id } => {
                             mir::InlineAsmOperand::SymStatic { def_id }
                         }
+                        thir::InlineAsmOperand::Label { block } => {
+                            let target_index = label_exprs.len();
+                            label_exprs.push(block);
+                            mir::InlineAsmOperand::Label { target_index }
+                        }
                     })
                     .collect();
 
                 let destination = this.cfg.start_new_block();
+                let labels: Vec<_> =
+                    label_exprs.iter().map(|_| this.cfg.start_new_block()).collect();
+                let cleanup = if options.contains(InlineAsmOptions::MAY_UNWIND) {
+                    Some(this.diverge_cleanup())
+                } else {
+                    None
+                };
 
                 this.cfg.terminate(
                     block,
@@ -383,8 +396,18 @@ impl<'a, 'tcx> Builder<'a, 'tcx> {
                         } else {
                             Some(destination)
                         },
+                        labels: labels.clone(),
+                        cleanup,
                     },
                 );
+
+                // Each label block is evaluated for its side effects only and then
+                // continues after the asm, just like the fallthrough edge.
+                for (label_block, expr) in labels.into_iter().zip(label_exprs) {
+                    let tmp = this.get_unit_temp();
+                    let label_block_end = unpack!(this.into(tmp, label_block, expr));
+                    this.cfg.goto(label_block_end, source_info, destination);
+                }
                 destination.unit()
             }
 
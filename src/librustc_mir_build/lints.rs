@@ -115,8 +115,8 @@ impl<'mir, 'tcx> TriColorVisitor<&'mir Body<'tcx>> for Search<'mir, 'tcx> {
             | TerminatorKind::Yield { .. } => ControlFlow::Break(NonRecursive),
 
             // A diverging InlineAsm is treated as non-recursing
-            TerminatorKind::InlineAsm { destination, .. } => {
-                if destination.is_some() {
+            TerminatorKind::InlineAsm { destination, ref labels, .. } => {
+                if destination.is_some() || !labels.is_empty() {
                     ControlFlow::Continue
                 } else {
                     ControlFlow::Break(NonRecursive)
@@ -480,6 +480,9 @@ fn make_mirror_unadjusted<'a, 'tcx>(
                                 }
                             }
                         }
+                        hir::InlineAsmOperand::Label { ref block } => {
+                            InlineAsmOperand::Label { block: block::to_expr_ref(cx, block) }
+                        }
                     }
                 })
                 .collect(),
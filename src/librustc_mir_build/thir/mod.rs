@@ -379,6 +379,9 @@ crate enum InlineAsmOperand<'tcx> {
     SymStatic {
         def_id: DefId,
     },
+    Label {
+        block: ExprRef<'tcx>,
+    },
 }
 
 ///////////////////////////////////////////////////////////////////////////
@@ -383,7 +383,7 @@ impl ExprVisitor<'tcx> {
                         }
                     }
                 }
-                hir::InlineAsmOperand::Sym { .. } => {}
+                hir::InlineAsmOperand::Sym { .. } | hir::InlineAsmOperand::Label { .. } => {}
             }
         }
     }
@@ -498,6 +498,12 @@ fn visit_expr<'tcx>(ir: &mut IrMaps<'tcx>, expr: &'tcx Expr<'tcx>) {
             ir.add_live_node_for_node(expr.hir_id, ExprNode(expr.span));
             intravisit::walk_expr(ir, expr);
         }
+        hir::ExprKind::InlineAsm(asm)
+            if asm.operands.iter().any(|op| matches!(op, hir::InlineAsmOperand::Label { .. })) =>
+        {
+            ir.add_live_node_for_node(expr.hir_id, ExprNode(expr.span));
+            intravisit::walk_expr(ir, expr);
+        }
 
         // otherwise, live nodes are not required:
         hir::ExprKind::Index(..)
@@ -1231,6 +1237,10 @@ impl<'a, 'tcx> Liveness<'a, 'tcx> {
             | hir::ExprKind::Repeat(ref e, _) => self.propagate_through_expr(&e, succ),
 
             hir::ExprKind::InlineAsm(ref asm) => {
+                // Label blocks are additional successors of the asm, each of which
+                // continues with the expression following it
+                let label_succ = succ;
+
                 // Handle non-returning asm
                 let mut succ = if asm.options.contains(InlineAsmOptions::NORETURN) {
                     self.s.exit_ln
@@ -1238,12 +1248,25 @@ impl<'a, 'tcx> Liveness<'a, 'tcx> {
                     succ
                 };
 
+                if asm.operands.iter().any(|op| matches!(op, hir::InlineAsmOperand::Label { .. })) {
+                    let ln = self.live_node(expr.hir_id, expr.span);
+                    self.init_from_succ(ln, succ);
+                    for op in asm.operands {
+                        if let hir::InlineAsmOperand::Label { block } = op {
+                            let block_succ = self.propagate_through_block(block, label_succ);
+                            self.merge_from_succ(ln, block_succ, false);
+                        }
+                    }
+                    succ = ln;
+                }
+
                 // Do a first pass for writing outputs only
                 for op in asm.operands.iter().rev() {
                     match op {
                         hir::InlineAsmOperand::In { .. }
                         | hir::InlineAsmOperand::Const { .. }
-                        | hir::InlineAsmOperand::Sym { .. } => {}
+                        | hir::InlineAsmOperand::Sym { .. }
+                        | hir::InlineAsmOperand::Label { .. } => {}
                         hir::InlineAsmOperand::Out { expr, .. } => {
                             if let Some(expr) = expr {
                                 succ = self.write_place(expr, succ, ACC_WRITE);
@@ -1283,6 +1306,7 @@ impl<'a, 'tcx> Liveness<'a, 'tcx> {
                             }
                             succ = self.propagate_through_expr(in_expr, succ);
                         }
+                        hir::InlineAsmOperand::Label { .. } => {}
                     }
                 }
                 succ
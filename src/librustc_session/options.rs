@@ -242,6 +242,8 @@ macro_rules! options {
         pub const parse_list: &str = "a space-separated list of strings";
         pub const parse_opt_list: &str = parse_list;
         pub const parse_opt_comma_list: &str = "a comma-separated list of strings";
+        pub const parse_list_with_polarity: &str =
+            "a comma-separated list of strings, each prefixed with `+` or `-`";
         pub const parse_uint: &str = "a number";
         pub const parse_opt_uint: &str = parse_uint;
         pub const parse_threads: &str = parse_uint;
@@ -266,6 +268,8 @@ macro_rules! options {
         pub const parse_merge_functions: &str = "one of: `disabled`, `trampolines`, or `aliases`";
         pub const parse_symbol_mangling_version: &str = "either `legacy` or `v0` (RFC 2603)";
         pub const parse_src_file_hash: &str = "either `md5` or `sha1`";
+        pub const parse_mir_emit_phase: &str =
+            "one of: `const`, `validated`, `drop-elab`, or `optimized`";
         pub const parse_relocation_model: &str =
             "one of supported relocation models (`rustc --print relocation-models`)";
         pub const parse_code_model: &str =
@@ -395,6 +399,26 @@ macro_rules! options {
             }
         }
 
+        fn parse_list_with_polarity(slot: &mut Vec<(String, bool)>, v: Option<&str>) -> bool {
+            match v {
+                Some(s) => {
+                    for s in s.split(',') {
+                        let pass_name = match s.get(1..) {
+                            Some(pass_name) if !pass_name.is_empty() => pass_name.to_string(),
+                            _ => return false,
+                        };
+                        match s.as_bytes()[0] {
+                            b'+' => slot.push((pass_name, true)),
+                            b'-' => slot.push((pass_name, false)),
+                            _ => return false,
+                        }
+                    }
+                    true
+                },
+                None => false,
+            }
+        }
+
         fn parse_threads(slot: &mut usize, v: Option<&str>) -> bool {
             match v.and_then(|s| s.parse().ok()) {
                 Some(0) => { *slot = ::num_cpus::get(); true },
@@ -653,6 +677,18 @@ macro_rules! options {
             true
         }
 
+        fn parse_mir_emit_phase(slot: &mut Option<MirEmitPhase>, v: Option<&str>) -> bool {
+            *slot = match v {
+                Some("const") => Some(MirEmitPhase::Const),
+                Some("validated") => Some(MirEmitPhase::Validated),
+                Some("drop-elab") => Some(MirEmitPhase::DropElab),
+                Some("inlined") => Some(MirEmitPhase::Inlined),
+                Some("optimized") => Some(MirEmitPhase::Optimized),
+                _ => return false,
+            };
+            true
+        }
+
         fn parse_src_file_hash(slot: &mut Option<SourceFileHashAlgorithm>, v: Option<&str>) -> bool {
             match v.and_then(|s| SourceFileHashAlgorithm::from_str(s).ok()) {
                 Some(hash_kind) => *slot = Some(hash_kind),
@@ -809,6 +845,9 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "gather borrowck statistics (default: no)"),
     chalk: bool = (false, parse_bool, [TRACKED],
         "enable the experimental Chalk-based trait solving engine"),
+    check_ctfe_bodies: bool = (false, parse_bool, [UNTRACKED],
+        "evaluate every const fn without inputs and every non-generic constant of the crate, \
+        to report CTFE errors in them eagerly (default: no)"),
     codegen_backend: Option<String> = (None, parse_opt_string, [TRACKED],
         "the backend to use"),
     crate_attr: Vec<String> = (Vec::new(), parse_string_push, [TRACKED],
@@ -837,7 +876,9 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         `all` matches all passes and functions,
         `foo` matches all passes for functions whose name contains 'foo',
         `foo & ConstProp` only the 'ConstProp' pass for function names containing 'foo',
-        `foo | bar` all passes for function names containing 'foo' or 'bar'."),
+        `foo | bar` all passes for function names containing 'foo' or 'bar',
+        `foo::bar::*` all passes for items in module `foo::bar` (`*` and `?` make a glob that
+        has to match the whole name)."),
     dump_mir_dataflow: bool = (false, parse_bool, [UNTRACKED],
         "in addition to `.mir` files, create graphviz `.dot` files with dataflow results \
         (default: no)"),
@@ -857,7 +898,8 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
     force_unstable_if_unmarked: bool = (false, parse_bool, [TRACKED],
         "force all crates to be `rustc_private` unstable (default: no)"),
     fuel: Option<(String, u64)> = (None, parse_optimization_fuel, [TRACKED],
-        "set the optimization fuel quota for a crate"),
+        "set the optimization fuel quota for a crate (MIR optimization passes use up a unit \
+        for every body they run on)"),
     hir_stats: bool = (false, parse_bool, [UNTRACKED],
         "print some statistics about AST and HIR (default: no)"),
     human_readable_cgu_names: bool = (false, parse_bool, [TRACKED],
@@ -873,6 +915,15 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "verify incr. comp. hashes of green query instances (default: no)"),
     inline_in_all_cgus: Option<bool> = (None, parse_opt_bool, [TRACKED],
         "control whether `#[inline]` functions are in all CGUs"),
+    inline_mir_hint_threshold: Option<usize> = (None, parse_opt_uint, [TRACKED],
+        "the largest cost of a function marked `#[inline]` that MIR inlining inlines \
+        (default: 100, or 200 with `-Z mir-opt-level=3`)"),
+    inline_mir_relaxed: bool = (false, parse_bool, [TRACKED],
+        "also inline MIR calls in cleanup blocks, calls that diverge, and callees with inline \
+        assembly or calls of `caller_location`, validating the result (default: no)"),
+    inline_mir_threshold: Option<usize> = (None, parse_opt_uint, [TRACKED],
+        "the largest cost of a function that MIR inlining inlines \
+        (default: 50, or 100 with `-Z mir-opt-level=3`)"),
     input_stats: bool = (false, parse_bool, [UNTRACKED],
         "gather statistics about the input (default: no)"),
     insert_sideeffect: bool = (false, parse_bool, [TRACKED],
@@ -899,6 +950,9 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "link the `.rlink` file generated by `-Z no-link` (default: no)"),
     llvm_time_trace: bool = (false, parse_bool, [UNTRACKED],
         "generate JSON tracing data file from LLVM data (default: no)"),
+    lower_128bit_ops: Option<bool> = (None, parse_opt_bool, [TRACKED],
+        "lower 128-bit integer operations in MIR to calls to the `i128_*`/`u128_*` lang items \
+        (default: the target's `i128_lowering` option)"),
     ls: bool = (false, parse_bool, [UNTRACKED],
         "list the symbols defined by a library crate (default: no)"),
     macro_backtrace: bool = (false, parse_bool, [UNTRACKED],
@@ -908,11 +962,71 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         the same values as the target option of the same name"),
     meta_stats: bool = (false, parse_bool, [UNTRACKED],
         "gather metadata statistics (default: no)"),
+    mir_branch_weights: bool = (false, parse_bool, [TRACKED],
+        "record how likely the targets of MIR branches are from `likely`, `unlikely` and \
+        `#[cold]` calls, and pass them to codegen as branch weights (default: no)"),
+    mir_emit_phase: Option<MirEmitPhase> = (None, parse_mir_emit_phase, [TRACKED],
+        "stop after the given MIR phase (`const`, `validated`, `drop-elab`, `inlined` or \
+        `optimized`) and emit the MIR of all bodies instead of generating code (default: no)"),
     mir_emit_retag: bool = (false, parse_bool, [TRACKED],
         "emit Retagging MIR statements, interpreted e.g., by miri; implies -Zmir-opt-level=0 \
         (default: no)"),
+    mir_enable_passes: Vec<(String, bool)> = (Vec::new(), parse_list_with_polarity, [TRACKED],
+        "force MIR passes on (`+Name`) or off (`-Name`), overriding optimization levels and \
+        other flags; `Name#N` only refers to the N-th run of a pass scheduled several times; \
+        the last mention of a pass wins (default: none)"),
+    mir_fixpoint_iterations: usize = (3, parse_uint, [TRACKED],
+        "the maximum number of times a group of MIR cleanup passes is repeated while it keeps \
+        changing the body, with `-Z mir-opt-level=2` or higher (default: 3)"),
+    mir_insert_assumes: bool = (false, parse_bool, [TRACKED],
+        "call `assume` after MIR bounds checks and other assertions, and after discriminant \
+        reads, so that codegen keeps the facts they prove (default: no)"),
+    mir_max_block_statements: Option<usize> = (None, parse_opt_uint, [TRACKED],
+        "split MIR basic blocks with more than this many statements before codegen; 0 disables \
+        splitting (default: the target's `max-mir-block-statements`)"),
+    mir_opt_bisect_limit: Option<usize> = (None, parse_opt_uint, [TRACKED],
+        "only run the first N runs of MIR optimization passes on the bodies of the crate, and \
+        print the last one that ran (default: no limit)"),
     mir_opt_level: usize = (1, parse_uint, [TRACKED],
-        "MIR optimization level (0-3; default: 1)"),
+        "MIR optimization level (0-3, where 3 adds costlier passes and inlines more; default: 1)"),
+    mir_opt_stop_after: Option<String> = (None, parse_opt_string, [TRACKED],
+        "skip the MIR optimizations that come after the named pass, including those of later \
+        phases, while still running the passes codegen needs (default: no)"),
+    mir_outline_cold_blocks: bool = (false, parse_bool, [TRACKED],
+        "move the blocks of a function from which every path ends in a panic or another call \
+        to a `#[cold]` function into functions of their own, before codegen (default: no)"),
+    mir_pass_growth_limit: usize = (10, parse_uint, [TRACKED],
+        "warn when a single MIR pass makes a body more than this many times bigger; 0 \
+        disables the check (default: 10)"),
+    mir_pass_growth_strict: bool = (false, parse_bool, [TRACKED],
+        "report MIR passes exceeding `-Z mir-pass-growth-limit` as an internal compiler error \
+        instead of a warning (default: no)"),
+    mir_pass_memory_threshold: Option<usize> = (None, parse_opt_uint, [UNTRACKED],
+        "report MIR passes that grow the estimated size of a body by more than the given \
+        number of kilobytes, along with the change in resident memory (default: no)"),
+    mir_pass_plugin: Vec<PathBuf> = (Vec::new(), parse_pathbuf_push, [TRACKED],
+        "load additional MIR passes from a dynamic library built with this exact compiler; can \
+        be given multiple times (default: none)"),
+    mir_provenance: bool = (false, parse_bool, [TRACKED],
+        "record which statements of the MIR before optimizations every statement of the \
+        optimized MIR derives from, and show them in MIR dumps (default: no)"),
+    mir_reaggregate: Option<bool> = (None, parse_opt_bool, [TRACKED],
+        "turn field-by-field initialization in MIR back into aggregate assignments before \
+        codegen (default: if the codegen backend prefers them)"),
+    mir_reorder_blocks: bool = (false, parse_bool, [TRACKED],
+        "order the MIR basic blocks so that the likely successor of each block follows it, and \
+        the cold blocks come last, before codegen (default: no)"),
+    mir_shuffle_passes: Option<usize> = (None, parse_opt_uint, [TRACKED],
+        "randomly reorder MIR passes whose relative order is unconstrained, using the given \
+        seed, and validate the MIR after each pass (default: no)"),
+    mir_stack_slot_hints: bool = (false, parse_bool, [TRACKED],
+        "compute which MIR locals can share a stack slot, and give the locals of each slot a \
+        single allocation in codegen (default: no)"),
+    mir_stats: bool = (false, parse_bool, [UNTRACKED],
+        "print the size of the optimized MIR of each function, largest first (default: no)"),
+    mir_strip_debuginfo: Option<bool> = (None, parse_opt_bool, [TRACKED],
+        "remove variable debuginfo and merge source scopes in MIR before optimizing it \
+        (default: when `-C debuginfo=0`)"),
     mutable_noalias: bool = (false, parse_bool, [TRACKED],
         "emit noalias metadata for mutable references (default: no)"),
     new_llvm_pass_manager: bool = (false, parse_bool, [TRACKED],
@@ -955,27 +1069,39 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "a single extra argument to prepend the linker invocation (can be used several times)"),
     pre_link_args: Vec<String> = (Vec::new(), parse_list, [UNTRACKED],
         "extra arguments to prepend to the linker invocation (space separated)"),
+    print_coverage_summary: bool = (false, parse_bool, [UNTRACKED],
+        "print the number of coverage counters, expressions and regions of each function \
+        instrumented by `-Z instrument-coverage` (default: no)"),
     print_fuel: Option<String> = (None, parse_opt_string, [TRACKED],
-        "make rustc print the total optimization fuel used by a crate"),
+        "make rustc print the total optimization fuel used by a crate, and the part each MIR \
+        pass used"),
     print_link_args: bool = (false, parse_bool, [UNTRACKED],
         "print the arguments passed to the linker (default: no)"),
     print_llvm_passes: bool = (false, parse_bool, [UNTRACKED],
         "print the LLVM optimization passes being run (default: no)"),
     print_mono_items: Option<String> = (None, parse_opt_string, [UNTRACKED],
         "print the result of the monomorphization collection pass"),
+    print_mir_pass_pipeline: bool = (false, parse_bool, [UNTRACKED],
+        "print the MIR passes every phase of an item runs, and why the others don't, then stop \
+        (default: no)"),
+    print_mir_stack_usage: bool = (false, parse_bool, [UNTRACKED],
+        "print an estimate of the stack frame of each function, computed from its optimized \
+        MIR (default: no)"),
     print_type_sizes: bool = (false, parse_bool, [UNTRACKED],
         "print layout information for each type encountered (default: no)"),
     profile: bool = (false, parse_bool, [TRACKED],
-        "insert profiling code (default: no)"),
+        "count how often each MIR basic block is executed (default: no)"),
     profile_emit: Option<PathBuf> = (None, parse_opt_pathbuf, [TRACKED],
-        "file path to emit profiling data at runtime when using 'profile' \
-        (default based on relative source path)"),
+        "file path to merge the block counts into at runtime when using 'profile' \
+        (default: the output path with a `.mirprof` extension)"),
     query_dep_graph: bool = (false, parse_bool, [UNTRACKED],
         "enable queries of the dependency graph for regression testing (default: no)"),
     query_stats: bool = (false, parse_bool, [UNTRACKED],
         "print some statistics about the query system (default: no)"),
     relro_level: Option<RelroLevel> = (None, parse_relro_level, [TRACKED],
         "choose which RELRO level to use"),
+    remark_mir: Passes = (Passes::Some(Vec::new()), parse_passes, [UNTRACKED],
+        "print remarks about the decisions of these MIR passes (space separated, or \"all\")"),
     report_delayed_bugs: bool = (false, parse_bool, [TRACKED],
         "immediately print bugs registered with `delay_span_bug` (default: no)"),
     // The default historical behavior was to always run dsymutil, so we're
@@ -1036,6 +1162,9 @@ options! {DebuggingOptions, DebuggingSetter, basic_debugging_options,
         "measure time of rustc processes (default: no)"),
     time_llvm_passes: bool = (false, parse_bool, [UNTRACKED],
         "measure time of each LLVM pass (default: no)"),
+    time_mir_passes: bool = (false, parse_bool, [UNTRACKED],
+        "measure time and body size changes of each MIR pass, and print a summary \
+        (default: no)"),
     time_passes: bool = (false, parse_bool, [UNTRACKED],
         "measure time of each rustc pass (default: no)"),
     tls_model: Option<TlsModel> = (None, parse_tls_model, [TRACKED],
@@ -27,6 +27,7 @@ use rustc_target::asm::InlineAsmArch;
 use rustc_target::spec::{CodeModel, PanicStrategy, RelocModel, RelroLevel};
 use rustc_target::spec::{Target, TargetTriple, TlsModel};
 
+use std::any::Any;
 use std::cell::{self, RefCell};
 use std::env;
 use std::fmt;
@@ -45,6 +46,15 @@ pub struct OptimizationFuel {
     out_of_fuel: bool,
 }
 
+/// The state of `-Z mir-opt-bisect-limit`.
+#[derive(Default)]
+pub struct MirOptBisect {
+    /// The number of runs of MIR optimization passes so far, including the skipped ones.
+    runs: usize,
+    /// The number and description of the last run that was within the limit.
+    pub last_run: Option<(usize, String)>,
+}
+
 /// The behavior of the CTFE engine when an error occurs with regards to backtraces.
 #[derive(Clone, Copy)]
 pub enum CtfeBacktrace {
@@ -159,11 +169,15 @@ pub struct Session {
     /// Tracks fuel info if `-zfuel=crate=n` is specified.
     optimization_fuel: Lock<OptimizationFuel>,
 
-    // The next two are public because the driver needs to read them.
+    // The next three are public because the driver needs to read them.
     /// If `-zprint-fuel=crate`, `Some(crate)`.
     pub print_fuel_crate: Option<String>,
     /// Always set to zero and incremented so that we can print fuel expended by a crate.
     pub print_fuel: AtomicU64,
+    /// The part of `print_fuel` that each MIR pass expended by running on a body.
+    pub print_fuel_by_mir_pass: Lock<FxHashMap<String, u64>>,
+    /// Numbers the runs of MIR optimization passes if `-Z mir-opt-bisect-limit` is specified.
+    pub mir_opt_bisect: Lock<MirOptBisect>,
 
     /// Loaded up early on in the initialization of this `Session` to avoid
     /// false positives about a job server in our environment.
@@ -208,6 +222,22 @@ pub struct Session {
 
     /// Set of enabled features for the current target.
     pub target_features: FxHashSet<Symbol>,
+
+    /// Whether the codegen backend lowers aggregate assignments in MIR better than the
+    /// field-by-field initialization MIR optimizations turn them into.
+    pub backend_prefers_aggregates: bool,
+
+    /// The MIR passes that the driver and `-Z mir-pass-plugin` libraries add to the pipeline,
+    /// set when the compiler is created. See `rustc_mir::transform::plugin`.
+    pub mir_pass_plugins: OnceCell<Box<dyn MirPassData>>,
+
+    /// The identifiers of the scheduled MIR passes. See `rustc_mir::transform::pass_registry`.
+    pub mir_pass_registry: OnceCell<Box<dyn MirPassData>>,
+}
+
+/// Data about MIR passes that `rustc_mir` keeps on the session, whose types this crate can't name.
+pub trait MirPassData: Any + sync::Send + sync::Sync {
+    fn as_any(&self) -> &dyn Any;
 }
 
 pub struct PerfStats {
@@ -221,6 +251,22 @@ pub struct PerfStats {
     pub normalize_generic_arg_after_erasing_regions: AtomicUsize,
     /// Number of times this query is invoked.
     pub normalize_projection_ty: AtomicUsize,
+    /// How often each MIR pass ran, and how often it changed the body it ran on.
+    pub mir_pass_outcomes: Lock<FxHashMap<String, (usize, usize)>>,
+    /// The time spent in each MIR pass and how much it grew the bodies, for `-Z time-mir-passes`.
+    pub mir_pass_times: Lock<FxHashMap<String, MirPassTime>>,
+}
+
+/// What a MIR pass did over the whole crate.
+#[derive(Clone, Debug, Default)]
+pub struct MirPassTime {
+    /// The number of bodies the pass ran on.
+    pub runs: usize,
+    pub time: Duration,
+    /// The change in the number of basic blocks, statements and locals, summed over all bodies.
+    pub blocks_delta: isize,
+    pub statements_delta: isize,
+    pub locals_delta: isize,
 }
 
 /// Enum to support dispatch of one-time diagnostics (in `Session.diag_once`).
@@ -872,6 +918,43 @@ impl Session {
             "normalize_projection_ty:                       {}",
             self.perf_stats.normalize_projection_ty.load(Ordering::Relaxed)
         );
+        let outcomes = self.perf_stats.mir_pass_outcomes.lock();
+        let mut outcomes: Vec<_> = outcomes.iter().collect();
+        outcomes.sort();
+        for (pass_name, (runs, changes)) in outcomes {
+            println!("MIR pass {:<36} changed {} of {} bodies", pass_name, changes, runs);
+        }
+    }
+
+    /// Prints the table of `-Z time-mir-passes`, slowest pass first.
+    pub fn print_mir_pass_times(&self) {
+        let times = self.perf_stats.mir_pass_times.lock();
+        let mut times: Vec<_> = times.iter().collect();
+        times.sort_by(|(a_name, a), (b_name, b)| b.time.cmp(&a.time).then(a_name.cmp(b_name)));
+        let total: Duration = times.iter().map(|(_, time)| time.time).sum();
+
+        println!(
+            "{:<36} {:>8} {:>10} {:>6} {:>10} {:>12} {:>10}",
+            "MIR pass", "bodies", "time (s)", "%", "blocks", "statements", "locals"
+        );
+        for (pass_name, time) in times {
+            let percent = if total == Duration::from_secs(0) {
+                0.0
+            } else {
+                time.time.as_secs_f64() * 100.0 / total.as_secs_f64()
+            };
+            println!(
+                "{:<36} {:>8} {:>10} {:>6.1} {:>+10} {:>+12} {:>+10}",
+                pass_name,
+                time.runs,
+                duration_to_secs_str(time.time),
+                percent,
+                time.blocks_delta,
+                time.statements_delta,
+                time.locals_delta
+            );
+        }
+        println!("{:<36} {:>8} {:>10}", "total", "", duration_to_secs_str(total));
     }
 
     /// We want to know if we're allowed to do an optimization for crate foo from -z fuel=foo=n.
@@ -900,6 +983,25 @@ impl Session {
         ret
     }
 
+    /// Numbers a run of a MIR optimization pass for `-Z mir-opt-bisect-limit`, starting at 1, and
+    /// returns whether the number is within the limit. The numbers are only reproducible if the
+    /// same bodies get optimized in the same order, which rules out parallel and incremental
+    /// compilation.
+    pub fn consider_mir_opt_bisect<T: FnOnce() -> String>(&self, msg: T) -> bool {
+        let limit = match self.opts.debugging_opts.mir_opt_bisect_limit {
+            Some(limit) => limit,
+            None => return true,
+        };
+        assert_eq!(self.threads(), 1);
+        let mut bisect = self.mir_opt_bisect.lock();
+        bisect.runs += 1;
+        if bisect.runs > limit {
+            return false;
+        }
+        bisect.last_run = Some((bisect.runs, msg()));
+        true
+    }
+
     /// Returns the number of query threads that should be used for this
     /// compilation
     pub fn threads(&self) -> usize {
@@ -1267,12 +1369,16 @@ pub fn build_session(
             queries_canonicalized: AtomicUsize::new(0),
             normalize_generic_arg_after_erasing_regions: AtomicUsize::new(0),
             normalize_projection_ty: AtomicUsize::new(0),
+            mir_pass_outcomes: Default::default(),
+            mir_pass_times: Default::default(),
         },
         code_stats: Default::default(),
         optimization_fuel_crate,
         optimization_fuel,
         print_fuel_crate,
         print_fuel,
+        print_fuel_by_mir_pass: Default::default(),
+        mir_opt_bisect: Default::default(),
         jobserver: jobserver::client(),
         driver_lint_caps,
         trait_methods_not_found: Lock::new(Default::default()),
@@ -1283,6 +1389,9 @@ pub fn build_session(
         real_rust_source_base_dir,
         asm_arch,
         target_features: FxHashSet::default(),
+        backend_prefers_aggregates: false,
+        mir_pass_plugins: OnceCell::new(),
+        mir_pass_registry: OnceCell::new(),
     };
 
     validate_commandline_args_with_session_available(&sess);
@@ -285,6 +285,7 @@ symbols! {
         await_macro,
         bang,
         begin_panic,
+        begin_panic_fmt,
         bench,
         bin,
         bind_by_move_pattern_guards,
@@ -345,14 +346,19 @@ symbols! {
         concat_idents,
         conservative_impl_trait,
         console,
+        const_allocate,
         const_compare_raw_pointers,
         const_constructor,
+        const_deallocate,
         const_eval_limit,
         const_extern_fn,
+        const_fmt_arguments_new,
         const_fn,
+        const_fn_ptr_call,
         const_fn_transmute,
         const_fn_union,
         const_generics,
+        const_heap,
         const_if_match,
         const_in_array_repeat_expressions,
         const_indexing,
@@ -427,6 +433,7 @@ symbols! {
         discriminant_type,
         discriminant_value,
         dispatch_from_dyn,
+        display_trait,
         div,
         div_assign,
         doc,
@@ -565,6 +572,19 @@ symbols! {
         html_root_url,
         i,
         i128,
+        i128_add,
+        i128_addo,
+        i128_cmp,
+        i128_div,
+        i128_mul,
+        i128_mulo,
+        i128_rem,
+        i128_shl,
+        i128_shlo,
+        i128_shr,
+        i128_shro,
+        i128_sub,
+        i128_subo,
         i128_type,
         i16,
         i32,
@@ -590,6 +610,7 @@ symbols! {
         inlateout,
         inline,
         inout,
+        instrument_function_entry,
         intel,
         into_iter,
         into_result,
@@ -660,6 +681,7 @@ symbols! {
         maxnumf32,
         maxnumf64,
         may_dangle,
+        may_unwind,
         maybe_uninit,
         maybe_uninit_uninit,
         maybe_uninit_zeroed,
@@ -716,6 +738,7 @@ symbols! {
         no_default_passes,
         no_implicit_prelude,
         no_inline,
+        no_instrument_function,
         no_link,
         no_main,
         no_mangle,
@@ -761,6 +784,7 @@ symbols! {
         panic,
         panic_abort,
         panic_bounds_check,
+        panic_fmt,
         panic_handler,
         panic_impl,
         panic_implementation,
@@ -816,6 +840,7 @@ symbols! {
         proc_macro_mod,
         proc_macro_non_items,
         proc_macro_path_invoc,
+        profile_count_block,
         profiler_builtins,
         profiler_runtime,
         ptr_guaranteed_eq,
@@ -918,6 +943,7 @@ symbols! {
         rustc_peek_definite_init,
         rustc_peek_indirectly_mutable,
         rustc_peek_liveness,
+        rustc_peek_maybe_borrowed,
         rustc_peek_maybe_init,
         rustc_peek_maybe_uninit,
         rustc_polymorphize_error,
@@ -1015,6 +1041,7 @@ symbols! {
         size_of,
         size_of_val,
         sized,
+        skip,
         slice,
         slice_alloc,
         slice_patterns,
@@ -1113,6 +1140,19 @@ symbols! {
         type_macros,
         type_name,
         u128,
+        u128_add,
+        u128_addo,
+        u128_cmp,
+        u128_div,
+        u128_mul,
+        u128_mulo,
+        u128_rem,
+        u128_shl,
+        u128_shlo,
+        u128_shr,
+        u128_shro,
+        u128_sub,
+        u128_subo,
         u16,
         u32,
         u64,
@@ -67,6 +67,10 @@ pub(super) fn mangle(
         let _ = printer.write_str("{{reify-shim}}");
     }
 
+    if let ty::InstanceDef::Outlined(..) = instance.def {
+        let _ = printer.write_str("{{outlined}}");
+    }
+
     printer.path.finish(hash)
 }
 
@@ -129,6 +133,11 @@ fn get_symbol_hash<'tcx>(
         // Especially, `VtableShim`s and `ReifyShim`s may overlap with their original
         // instances without this.
         discriminant(&instance.def).hash_stable(&mut hcx, &mut hasher);
+
+        // Several blocks can be outlined from the same function.
+        if let ty::InstanceDef::Outlined(_, index) = instance.def {
+            index.hash_stable(&mut hcx, &mut hasher);
+        }
     });
 
     // 64 bits should be enough to avoid collisions.
@@ -211,12 +211,15 @@ fn compute_symbol_name(
         }
     }
 
-    if let Some(name) = attrs.export_name {
+    // Blocks outlined from a function must not take over the name it was given.
+    let is_outlined = matches!(instance.def, ty::InstanceDef::Outlined(..));
+
+    if let (Some(name), false) = (attrs.export_name, is_outlined) {
         // Use provided name
         return name.to_string();
     }
 
-    if attrs.flags.contains(CodegenFnAttrFlags::NO_MANGLE) {
+    if attrs.flags.contains(CodegenFnAttrFlags::NO_MANGLE) && !is_outlined {
         // Don't mangle
         return tcx.item_name(def_id).to_string();
     }
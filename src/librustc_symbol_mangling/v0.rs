@@ -35,16 +35,18 @@ pub(super) fn mangle(
         out: String::from(prefix),
     };
 
-    // Append `::{shim:...#0}` to shims that can coexist with a non-shim instance.
+    // Append `::{shim:...#N}` to shims that can coexist with a non-shim instance.
     let shim_kind = match instance.def {
-        ty::InstanceDef::VtableShim(_) => Some("vtable"),
-        ty::InstanceDef::ReifyShim(_) => Some("reify"),
+        ty::InstanceDef::VtableShim(_) => Some(("vtable", 0)),
+        ty::InstanceDef::ReifyShim(_) => Some(("reify", 0)),
+        ty::InstanceDef::Outlined(_, index) => Some(("outlined", u64::from(index))),
 
         _ => None,
     };
 
-    cx = if let Some(shim_kind) = shim_kind {
-        cx.path_append_ns(|cx| cx.print_def_path(def_id, substs), 'S', 0, shim_kind).unwrap()
+    cx = if let Some((shim_kind, disambiguator)) = shim_kind {
+        cx.path_append_ns(|cx| cx.print_def_path(def_id, substs), 'S', disambiguator, shim_kind)
+            .unwrap()
     } else {
         cx.print_def_path(def_id, substs).unwrap()
     };
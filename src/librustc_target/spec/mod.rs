@@ -952,6 +952,16 @@ pub struct TargetOptions {
     /// unwinders.
     pub requires_uwtable: bool,
 
+    /// Whether 128-bit integer arithmetic, comparisons and checked operations should be lowered
+    /// in MIR to calls to the `i128_*`/`u128_*` lang items instead of being handed to the
+    /// codegen backend. Can be overridden with `-Z lower-128bit-ops`.
+    pub i128_lowering: bool,
+
+    /// The maximum number of statements a MIR basic block may have before codegen. Longer
+    /// blocks are split into a chain of blocks, for backends that handle huge blocks poorly.
+    /// Can be overridden with `-Z mir-max-block-statements`.
+    pub max_mir_block_statements: Option<u64>,
+
     /// Whether or not SIMD types are passed by reference in the Rust ABI,
     /// typically required if a target can be compiled with a mixed set of
     /// target features. This is `true` by default, and `false` for targets like
@@ -1075,6 +1085,8 @@ impl Default for TargetOptions {
             default_hidden_visibility: false,
             emit_debug_gdb_scripts: true,
             requires_uwtable: false,
+            i128_lowering: false,
+            max_mir_block_statements: None,
             simd_types_indirect: true,
             limit_rdylib_exports: true,
             override_export_symbols: None,
@@ -1468,6 +1480,8 @@ impl Target {
         key!(default_hidden_visibility, bool);
         key!(emit_debug_gdb_scripts, bool);
         key!(requires_uwtable, bool);
+        key!(i128_lowering, bool);
+        key!(max_mir_block_statements, Option<u64>);
         key!(simd_types_indirect, bool);
         key!(limit_rdylib_exports, bool);
         key!(override_export_symbols, opt_list);
@@ -1706,6 +1720,8 @@ impl ToJson for Target {
         target_option_val!(default_hidden_visibility);
         target_option_val!(emit_debug_gdb_scripts);
         target_option_val!(requires_uwtable);
+        target_option_val!(i128_lowering);
+        target_option_val!(max_mir_block_statements);
         target_option_val!(simd_types_indirect);
         target_option_val!(limit_rdylib_exports);
         target_option_val!(override_export_symbols);
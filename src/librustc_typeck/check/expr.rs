@@ -1829,6 +1829,7 @@ impl<'a, 'tcx> FnCtxt<'a, 'tcx> {
     }
 
     fn check_expr_asm(&self, asm: &'tcx hir::InlineAsm<'tcx>) -> Ty<'tcx> {
+        let mut diverge = asm.options.contains(ast::InlineAsmOptions::NORETURN);
         for op in asm.operands {
             match op {
                 hir::InlineAsmOperand::In { expr, .. } | hir::InlineAsmOperand::Const { expr } => {
@@ -1851,13 +1852,22 @@ impl<'a, 'tcx> FnCtxt<'a, 'tcx> {
                 hir::InlineAsmOperand::Sym { expr } => {
                     self.check_expr(expr);
                 }
+                hir::InlineAsmOperand::Label { block } => {
+                    let previous_diverges = self.diverges.get();
+                    let unit = self.tcx.mk_unit();
+                    let ty = self.check_block_with_expected(block, ExpectHasType(unit));
+                    // Like the assembly, a label block that doesn't diverge continues after the
+                    // `asm!`.
+                    if !ty.is_never() {
+                        self.demand_suptype(block.span, unit, ty);
+                        diverge = false;
+                    }
+                    // The other label blocks and the fallthrough aren't dominated by this block.
+                    self.diverges.set(previous_diverges);
+                }
             }
         }
-        if asm.options.contains(ast::InlineAsmOptions::NORETURN) {
-            self.tcx.types.never
-        } else {
-            self.tcx.mk_unit()
-        }
+        if diverge { self.tcx.types.never } else { self.tcx.mk_unit() }
     }
 }
 
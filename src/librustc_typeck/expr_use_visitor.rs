@@ -243,6 +243,7 @@ impl<'a, 'tcx> ExprUseVisitor<'a, 'tcx> {
                                 self.mutate_expr(out_expr);
                             }
                         }
+                        hir::InlineAsmOperand::Label { block } => self.walk_block(block),
                     }
                 }
             }
@@ -406,6 +406,7 @@ pub fn run_core(options: RustdocOptions) -> (clean::Crate, RenderInfo, RenderOpt
                 (rustc_interface::DEFAULT_QUERY_PROVIDERS.typeck)(tcx, def_id)
             };
         }),
+        register_mir_passes: None,
         registry: rustc_driver::diagnostics_registry(),
     };
 
@@ -95,6 +95,7 @@ pub fn run(options: Options) -> Result<(), String> {
         lint_caps,
         register_lints: None,
         override_queries: None,
+        register_mir_passes: None,
         registry: rustc_driver::diagnostics_registry(),
     };
 
@@ -0,0 +1,40 @@
+// Checks that the weights `-Z mir-branch-weights` finds for MIR branches are passed to LLVM.
+
+// compile-flags: -C no-prepopulate-passes -Z mir-branch-weights
+
+#![crate_type = "lib"]
+#![feature(core_intrinsics)]
+
+use std::intrinsics::{likely, unlikely};
+
+// CHECK-LABEL: @expects_true
+#[no_mangle]
+pub fn expects_true(x: bool) -> u32 {
+    // CHECK: br i1 %{{.*}}, label %{{.*}}, label %{{.*}}, !prof ![[LIKELY:[0-9]+]]
+    if unsafe { likely(x) } { 1 } else { 2 }
+}
+
+// CHECK-LABEL: @expects_false
+#[no_mangle]
+pub fn expects_false(x: bool) -> u32 {
+    // CHECK: br i1 %{{.*}}, label %{{.*}}, label %{{.*}}, !prof ![[UNLIKELY:[0-9]+]]
+    if unsafe { unlikely(x) } { 1 } else { 2 }
+}
+
+#[cold]
+#[inline(never)]
+#[no_mangle]
+pub fn fail() {}
+
+// CHECK-LABEL: @calls_cold
+#[no_mangle]
+pub fn calls_cold(x: u32) -> u32 {
+    // CHECK: br i1 %{{.*}}, label %{{.*}}, label %{{.*}}, !prof ![[UNLIKELY]]
+    if x > 10 {
+        fail();
+    }
+    x
+}
+
+// CHECK-DAG: ![[LIKELY]] = !{!"branch_weights", i32 2000, i32 1}
+// CHECK-DAG: ![[UNLIKELY]] = !{!"branch_weights", i32 1, i32 2000}
@@ -0,0 +1,16 @@
+// compile-flags: -Z instrument-mcount
+
+#![crate_type = "lib"]
+#![feature(no_instrument_function)]
+
+// Check that functions marked `#[no_instrument_function]` are not instrumented, and neither are
+// the other bodies of the crate.
+
+// CHECK-NOT: instrument-function-entry-inlined
+
+pub static TABLE: [u32; 2] = [1, 2];
+
+#[no_instrument_function]
+pub fn excluded(x: u32) -> u32 {
+    x + TABLE[1]
+}
@@ -0,0 +1,35 @@
+// compile-flags: -C no-prepopulate-passes -Z mir-stack-slot-hints
+
+#![crate_type = "lib"]
+
+// Check that locals of the same type whose storage never overlaps share an alloca.
+
+#[inline(never)]
+fn consume(_: &[u64; 4]) {}
+
+// CHECK-LABEL: @share
+#[no_mangle]
+pub fn share() {
+    // CHECK: %a = alloca [4 x i64]
+    // CHECK-NOT: alloca [4 x i64]
+    // CHECK: ret void
+    {
+        let a = [1u64; 4];
+        consume(&a);
+    }
+    {
+        let b = [2u64; 4];
+        consume(&b);
+    }
+}
+
+// CHECK-LABEL: @overlap
+#[no_mangle]
+pub fn overlap() {
+    // CHECK: %a = alloca [4 x i64]
+    // CHECK: %b = alloca [4 x i64]
+    let a = [1u64; 4];
+    let b = [2u64; 4];
+    consume(&a);
+    consume(&b);
+}
@@ -0,0 +1,12 @@
+// compile-flags: -C overflow-checks=off -Z mir-opt-level=0
+// compile-flags: -Z mir-enable-passes=+BoundsCheckElimination
+// Tests that a bounds check an earlier indexing with the same index has already made is removed.
+
+// EMIT_MIR bounds_check_elimination.twice.BoundsCheckElimination.diff
+fn twice(values: &[u32], i: usize) -> u32 {
+    values[i] + values[i]
+}
+
+fn main() {
+    twice(&[1, 2, 3], 1);
+}
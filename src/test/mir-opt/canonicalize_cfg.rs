@@ -0,0 +1,18 @@
+// compile-flags: -Z mir-opt-level=0 -Z mir-enable-passes=+CanonicalizeCfg
+// Tests that the critical edge from a branch into a loop header gets a block of its own, which
+// becomes the preheader of the loop.
+
+// EMIT_MIR canonicalize_cfg.count_to.CanonicalizeCfg.diff
+fn count_to(run: bool, n: u32) -> u32 {
+    let mut i = 0;
+    if run {
+        while i < n {
+            i += 1;
+        }
+    }
+    i
+}
+
+fn main() {
+    count_to(true, 3);
+}
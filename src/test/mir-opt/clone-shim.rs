@@ -0,0 +1,11 @@
+// Tests that the `Clone` shims of tuples copy the fields that are `Copy` instead of cloning them,
+// and that they clone tuples whose fields are all `Copy` with a single copy.
+
+// EMIT_MIR core.clone-Clone-clone.(i32,u8).SimplifyCfg-make_shim.after.mir
+// EMIT_MIR core.clone-Clone-clone.(i32,std__string__String).SimplifyCfg-make_shim.after.mir
+fn main() {
+    let copy = (1i32, 2u8);
+    let _ = copy.clone();
+    let mixed = (1i32, String::new());
+    let _ = mixed.clone();
+}
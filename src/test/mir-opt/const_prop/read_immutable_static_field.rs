@@ -0,0 +1,14 @@
+// compile-flags: -O
+
+#[repr(C)]
+struct Config {
+    verbose: bool,
+    level: u8,
+}
+
+static CONFIG: Config = Config { verbose: false, level: 3 };
+
+// EMIT_MIR read_immutable_static_field.main.ConstProp.diff
+fn main() {
+    let x = CONFIG.level;
+}
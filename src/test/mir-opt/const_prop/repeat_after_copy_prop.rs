@@ -0,0 +1,19 @@
+// compile-flags: -C overflow-checks=off
+// compile-flags: -Z mir-enable-passes=-GlobalValueNumbering,-ScalarReplacementOfAggregates
+// compile-flags: -Z mir-enable-passes=-DeadStoreElimination,-DestinationPropagation
+// Tests that `-Z mir-opt-level=3` propagates constants again after the other passes: `x` is
+// borrowed, so the first run of `ConstProp` leaves its read alone, but `CopyPropagation` then
+// replaces it with the value of `x`. The passes turned off above would otherwise remove the
+// borrow or the read before `ConstProp-repeat` gets to them.
+
+// EMIT_MIR repeat_after_copy_prop.borrowed.ConstProp-repeat.diff
+#[allow(unused_must_use)]
+fn borrowed() -> u32 {
+    let x = 5;
+    &x;
+    x + 1
+}
+
+fn main() {
+    borrowed();
+}
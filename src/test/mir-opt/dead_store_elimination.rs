@@ -0,0 +1,15 @@
+// compile-flags: -C overflow-checks=off -Z mir-opt-level=0
+// compile-flags: -Z mir-enable-passes=+StripDebugInfo,+DeadStoreElimination
+// Tests that a store which is overwritten before it is read is removed.
+
+// EMIT_MIR dead_store_elimination.overwritten.DeadStoreElimination.diff
+#[allow(unused_assignments)]
+fn overwritten(x: u32) -> u32 {
+    let mut y = x * 2;
+    y = x + 1;
+    y
+}
+
+fn main() {
+    overwritten(4);
+}
@@ -0,0 +1,15 @@
+// compile-flags: -Z mir-opt-level=0 -Z mir-enable-passes=+DeduplicateBlocks
+// Tests that blocks with the same statements and terminator are merged.
+
+// EMIT_MIR deduplicate_blocks.arms.DeduplicateBlocks.diff
+fn arms(x: u8, y: u32, z: u32) -> u32 {
+    match x {
+        0 => y,
+        1 => z,
+        _ => y,
+    }
+}
+
+fn main() {
+    arms(0, 1, 2);
+}
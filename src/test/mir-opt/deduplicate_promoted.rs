@@ -0,0 +1,10 @@
+// Tests that identical promoted constants of a function are merged into one.
+
+// EMIT_MIR deduplicate_promoted.pair PromoteTemps.after SimplifyBranches-initial.before
+fn pair() -> (&'static [u32; 2], &'static [u32; 2]) {
+    (&[1, 2], &[1, 2])
+}
+
+fn main() {
+    pair();
+}
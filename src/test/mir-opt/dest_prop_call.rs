@@ -0,0 +1,27 @@
+// Tests that the destination of a call is not merged with one of its arguments: `buf` is dead
+// between the call to `shift` and the assignment of its result, but `shift` still reads it.
+// compile-flags: -Z mir-opt-level=0
+// compile-flags: -Z mir-enable-passes=+StripDebugInfo,+RemoveRedundantReborrows
+// compile-flags: -Z mir-enable-passes=+DestinationPropagation
+
+#[inline(never)]
+fn shift(buf: &mut [u8]) -> &mut [u8] {
+    &mut buf[1..]
+}
+
+#[inline(never)]
+fn count() -> usize {
+    1
+}
+
+// EMIT_MIR dest_prop_call.advance.DestinationPropagation.diff
+#[allow(unused_assignments)]
+fn advance(mut buf: &mut [u8]) -> usize {
+    buf = shift(buf);
+    let n = count();
+    n
+}
+
+fn main() {
+    advance(&mut [0; 2]);
+}
@@ -0,0 +1,24 @@
+// compile-flags: -Z mir-opt-level=0 -Z mir-enable-passes=+Devirtualize#1
+// Tests that a call through a trait object that was just created from a reference calls the
+// method of the type behind the reference.
+
+trait Shape {
+    fn area(&self) -> u32;
+}
+
+struct Square(u32);
+
+impl Shape for Square {
+    fn area(&self) -> u32 {
+        self.0 * self.0
+    }
+}
+
+// EMIT_MIR devirtualize.known.Devirtualize.diff
+fn known(square: &Square) -> u32 {
+    <dyn Shape as Shape>::area(square)
+}
+
+fn main() {
+    known(&Square(2));
+}
@@ -0,0 +1,15 @@
+// compile-flags: -Z mir-opt-level=0 -Z mir-enable-passes=+DiscriminantPropagation
+// Tests that a match on a value whose variant was just set goes straight to the arm of the variant.
+
+// EMIT_MIR discriminant_propagation.wrap.DiscriminantPropagation.diff
+fn wrap(x: u32) -> u32 {
+    let value = Some(x);
+    match value {
+        Some(y) => y,
+        None => 0,
+    }
+}
+
+fn main() {
+    wrap(1);
+}
@@ -0,0 +1,14 @@
+// compile-flags: -Z mir-opt-level=0 -Z mir-enable-passes=+EarlyOtherwiseBranch
+// Tests that the switches on the discriminants of two options are merged into one switch.
+
+// EMIT_MIR early_otherwise_branch.both.EarlyOtherwiseBranch.diff
+fn both(a: Option<u8>, b: Option<u8>) -> u32 {
+    match (a, b) {
+        (Some(_), Some(_)) => 1,
+        _ => 0,
+    }
+}
+
+fn main() {
+    both(Some(1), None);
+}
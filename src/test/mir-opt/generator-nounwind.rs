@@ -0,0 +1,21 @@
+//! Tests that generators that only call functions which cannot unwind don't
+//! have a poisoned state, even with `-C panic=unwind`.
+
+// compile-flags: -C panic=unwind
+
+#![feature(generators, generator_trait)]
+
+struct NoDrop;
+
+extern "C" fn callee() {}
+
+// EMIT_MIR generator_nounwind.main-{{closure}}.generator_resume.0.mir
+fn main() {
+    let _gen = |_x: u8| {
+        let _d = NoDrop;
+        loop {
+            yield;
+            callee();
+        }
+    };
+}
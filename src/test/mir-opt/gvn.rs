@@ -0,0 +1,15 @@
+// compile-flags: -C overflow-checks=off -Z mir-opt-level=0
+// compile-flags: -Z mir-enable-passes=+GlobalValueNumbering
+// Tests that a value computed a second time reuses the first result, and so do the casts it is
+// computed from.
+
+// EMIT_MIR gvn.recompute.GlobalValueNumbering.diff
+fn recompute(a: u8, b: u64) -> u64 {
+    let x = a as u64 + b;
+    let y = a as u64 + b;
+    x * y
+}
+
+fn main() {
+    recompute(1, 2);
+}
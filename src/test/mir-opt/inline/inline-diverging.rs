@@ -0,0 +1,17 @@
+// compile-flags: -Z inline-mir-relaxed
+
+// Tests that the relaxed mode of the MIR inliner inlines diverging calls.
+
+// EMIT_MIR inline_diverging.f.Inline.diff
+pub fn f() -> ! {
+    halt()
+}
+
+#[inline(always)]
+fn halt() -> ! {
+    loop {}
+}
+
+fn main() {
+    f();
+}
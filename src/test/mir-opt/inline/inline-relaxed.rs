@@ -0,0 +1,19 @@
+// compile-flags: -Z inline-mir-relaxed
+
+// Tests that the relaxed mode of the MIR inliner inlines callees with inline assembly.
+
+#![feature(llvm_asm)]
+
+// EMIT_MIR inline_relaxed.f.Inline.diff
+pub fn f() {
+    nop();
+}
+
+#[inline(always)]
+fn nop() {
+    unsafe { llvm_asm!("NOP") }
+}
+
+fn main() {
+    f();
+}
@@ -0,0 +1,11 @@
+// compile-flags: -C overflow-checks=on -Z mir-opt-level=0 -Z mir-insert-assumes
+// Tests that the fact an overflow check proves is assumed right after the check.
+
+// EMIT_MIR insert_assumes.add.InsertAssumes.diff
+fn add(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+fn main() {
+    add(1, 2);
+}
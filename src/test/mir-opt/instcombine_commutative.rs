@@ -0,0 +1,10 @@
+// Test that constants are moved to the right of commutative operations.
+
+// EMIT_MIR instcombine_commutative.eq.InstCombine.diff
+fn eq(x: u32) -> bool {
+    5 == x
+}
+
+fn main() {
+    eq(5);
+}
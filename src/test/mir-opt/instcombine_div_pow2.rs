@@ -0,0 +1,16 @@
+// Test that unsigned divisions and remainders by powers of two become shifts and masks.
+
+// EMIT_MIR instcombine_div_pow2.div.InstCombine.diff
+fn div(x: u32) -> u32 {
+    x / 8
+}
+
+// EMIT_MIR instcombine_div_pow2.rem.InstCombine.diff
+fn rem(x: u32) -> u32 {
+    x % 8
+}
+
+fn main() {
+    div(1);
+    rem(1);
+}
@@ -0,0 +1,16 @@
+// Test that repeat expressions of length 0 and 1 are simplified.
+
+// EMIT_MIR instcombine_repeat.zero.InstCombine.diff
+fn zero(x: u32) -> [u32; 0] {
+    [x; 0]
+}
+
+// EMIT_MIR instcombine_repeat.one.InstCombine.diff
+fn one(x: u32) -> [u32; 1] {
+    [x; 1]
+}
+
+fn main() {
+    zero(0);
+    one(0);
+}
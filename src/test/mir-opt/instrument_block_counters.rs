@@ -0,0 +1,14 @@
+// Test that `-Z profile` makes every block of a function call the block counter of the
+// profiler runtime first.
+
+// needs-profiler-support
+// compile-flags: -Z profile -Z profile-emit=bar.mirprof
+// EMIT_MIR instrument_block_counters.bar.InstrumentBlockCounters.diff
+fn main() {
+    bar();
+}
+
+#[inline(never)]
+fn bar() -> bool {
+    true
+}
@@ -0,0 +1,16 @@
+// compile-flags: -Z mir-opt-level=0 -Z mir-enable-passes=+JumpThreading
+// Tests that the predecessors of a block switching on a local they set to constants jump to copies
+// of it going straight to the known targets.
+
+// EMIT_MIR jump_threading.known_flag.JumpThreading.diff
+fn known_flag(c: bool) -> u32 {
+    let flag = if c { true } else { false };
+    match flag {
+        true => 1,
+        false => 2,
+    }
+}
+
+fn main() {
+    known_flag(true);
+}
@@ -0,0 +1,17 @@
+// compile-flags: -C overflow-checks=off -Z mir-opt-level=0 -Z mir-enable-passes=+LoopIdioms
+// Tests that a loop storing zero into a prefix of a slice calls `write_bytes` instead, when the
+// prefix is within the slice. The loop compares and indexes with copies of the counter.
+
+// EMIT_MIR_FOR_EACH_BIT_WIDTH
+// EMIT_MIR loop_idioms.zero.LoopIdioms.diff
+fn zero(a: &mut [u32], n: usize) {
+    let mut i = 0;
+    while i < n {
+        a[i] = 0;
+        i += 1;
+    }
+}
+
+fn main() {
+    zero(&mut [1, 2, 3], 2);
+}
@@ -0,0 +1,17 @@
+// compile-flags: -C overflow-checks=off -Z mir-opt-level=0
+// compile-flags: -Z mir-enable-passes=+LoopInvariantCodeMotion
+// Tests that assignments computing the same value in every iteration are moved before the loop.
+
+// EMIT_MIR loop_invariant_code_motion.add_steps.LoopInvariantCodeMotion.diff
+fn add_steps(n: u32) -> u32 {
+    let mut total = 0;
+    while total < n {
+        let step = 5;
+        total += step;
+    }
+    total
+}
+
+fn main() {
+    add_steps(12);
+}
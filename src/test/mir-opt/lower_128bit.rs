@@ -0,0 +1,10 @@
+// compile-flags: -Z lower-128bit-ops=yes
+
+// EMIT_MIR lower_128bit.lt.Lower128Bit.after.mir
+fn lt(a: u128, b: u128) -> bool {
+    a < b
+}
+
+fn main() {
+    lt(1, 2);
+}
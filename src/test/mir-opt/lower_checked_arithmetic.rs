@@ -0,0 +1,21 @@
+// compile-flags: -C overflow-checks=off -Z mir-opt-level=0
+// compile-flags: -Z mir-enable-passes=+LowerCheckedArithmetic
+// Tests that the overflow check of an addition inlined from a function that inherits its overflow
+// checks is removed in a crate that doesn't check for overflow.
+
+#![feature(rustc_attrs)]
+
+#[inline(always)]
+#[rustc_inherit_overflow_checks]
+fn add(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+// EMIT_MIR lower_checked_arithmetic.wrapping.LowerCheckedArithmetic.diff
+fn wrapping(a: u8, b: u8) -> u8 {
+    add(a, b)
+}
+
+fn main() {
+    wrapping(200, 100);
+}
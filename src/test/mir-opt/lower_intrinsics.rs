@@ -0,0 +1,13 @@
+// compile-flags: -Z mir-opt-level=0 -Z mir-enable-passes=+LowerIntrinsics
+// Tests that a call to an intrinsic whose result is known becomes an assignment of the result.
+
+#![feature(core_intrinsics)]
+
+// EMIT_MIR lower_intrinsics.drops.LowerIntrinsics.diff
+fn drops() -> bool {
+    std::intrinsics::needs_drop::<u32>()
+}
+
+fn main() {
+    drops();
+}
@@ -0,0 +1,14 @@
+// compile-flags: -Z mir-opt-level=0 -Z mir-enable-passes=+MatchBranchSimplification
+// Tests that a switch whose arms only assign different bools is replaced by a comparison.
+
+// EMIT_MIR match_branches.is_zero.MatchBranchSimplification.diff
+fn is_zero(x: u8) -> bool {
+    match x {
+        0 => true,
+        _ => false,
+    }
+}
+
+fn main() {
+    is_zero(1);
+}
@@ -0,0 +1,18 @@
+// Test that match arms doing the same thing are merged.
+
+#[inline(never)]
+fn bar(_: u32) {}
+
+// EMIT_MIR merge_identical_arms.foo.MergeIdenticalArms.after.mir
+fn foo(x: u8) {
+    match x {
+        0 => bar(1),
+        1 => bar(2),
+        2 => bar(1),
+        _ => bar(3),
+    }
+}
+
+fn main() {
+    foo(0);
+}
@@ -0,0 +1,12 @@
+// compile-flags: -C panic=abort -Z mir-opt-level=0 -Z mir-enable-passes=+MergeReturns
+// no-prefer-dynamic
+// Tests that the blocks a generator returns from when it yields and when it completes share one.
+
+#![feature(generators)]
+
+// EMIT_MIR merge_returns.main-{{closure}}.MergeReturns.diff
+fn main() {
+    let _gen = || {
+        yield;
+    };
+}
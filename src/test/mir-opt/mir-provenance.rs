@@ -0,0 +1,12 @@
+// compile-flags: -Z mir-opt-level=0 -Z mir-provenance
+// Tests that `-Z mir-provenance` follows statements that share their span and scope to their own
+// origins, and that the statements a pass creates from another one derive from it.
+
+// EMIT_MIR mir_provenance.pair.Deaggregator.diff
+fn pair(x: u32, y: u32) -> (u32, u32) {
+    (y, x)
+}
+
+fn main() {
+    pair(1, 2);
+}
@@ -0,0 +1,23 @@
+// compile-flags: -Z mir-opt-level=0 -Z mir-outline-cold-blocks
+// Tests that the blocks from which every path ends in a call to a `#[cold]` function that never
+// returns are moved into a function of their own, which takes the locals it shares with the rest
+// of the body as arguments.
+
+#[cold]
+#[inline(never)]
+fn fail(_a: u32, _b: u32) -> ! {
+    loop {}
+}
+
+// EMIT_MIR outline_cold_blocks.check.OutlineColdBlocks.diff
+// EMIT_MIR outline_cold_blocks.check.outlined0.outlined.0.mir
+fn check(x: u32, fatal: bool) -> u32 {
+    if fatal {
+        fail(x ^ 0xff, x | 1);
+    }
+    x
+}
+
+fn main() {
+    check(4, false);
+}
@@ -0,0 +1,44 @@
+// compile-flags: -C panic=abort -Z mir-opt-level=0
+// no-prefer-dynamic
+// Tests that the drop flags of a function with more than eight of them are kept in the bits of a
+// `u64`.
+
+struct Noisy;
+
+impl Drop for Noisy {
+    fn drop(&mut self) {}
+}
+
+fn sink(
+    _: Noisy,
+    _: Noisy,
+    _: Noisy,
+    _: Noisy,
+    _: Noisy,
+    _: Noisy,
+    _: Noisy,
+    _: Noisy,
+    _: Noisy,
+) {}
+
+// EMIT_MIR pack_drop_flags.nine.ElaborateDrops.diff
+fn nine(
+    moved: bool,
+    a: Noisy,
+    b: Noisy,
+    c: Noisy,
+    d: Noisy,
+    e: Noisy,
+    f: Noisy,
+    g: Noisy,
+    h: Noisy,
+    i: Noisy,
+) {
+    if moved {
+        sink(a, b, c, d, e, f, g, h, i);
+    }
+}
+
+fn main() {
+    nine(false, Noisy, Noisy, Noisy, Noisy, Noisy, Noisy, Noisy, Noisy, Noisy);
+}
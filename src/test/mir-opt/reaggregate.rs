@@ -0,0 +1,16 @@
+// compile-flags: -Z mir-reaggregate=yes
+
+struct Baz {
+    x: u32,
+    y: f32,
+    z: bool,
+}
+
+// EMIT_MIR reaggregate.bar.Reaggregate.diff
+fn bar() -> Baz {
+    Baz { x: 1, y: 0.0, z: false }
+}
+
+fn main() {
+    bar();
+}
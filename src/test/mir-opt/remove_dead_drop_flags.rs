@@ -0,0 +1,20 @@
+// Tests that drop flags which are false wherever they are read are removed.
+
+struct Noisy;
+
+impl Drop for Noisy {
+    fn drop(&mut self) {}
+}
+
+// EMIT_MIR remove_dead_drop_flags.f.RemoveDeadDropFlags.diff
+fn f() {
+    let x;
+    let c = false;
+    if c {
+        x = Noisy;
+    }
+}
+
+fn main() {
+    f();
+}
@@ -0,0 +1,11 @@
+// EMIT_MIR remove_redundant_reborrows.foo.RemoveRedundantReborrows.diff
+fn foo(x: &mut u32) {
+    bar(x);
+}
+
+#[inline(never)]
+fn bar(_: &mut u32) {}
+
+fn main() {
+    foo(&mut 0);
+}
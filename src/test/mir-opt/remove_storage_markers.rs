@@ -0,0 +1,18 @@
+// compile-flags: -Z mir-opt-level=0 -Z mir-enable-passes=+RemoveStorageMarkers
+// Tests that only the storage markers of locals that are borrowed, and whose storage doesn't span
+// the whole body, are kept.
+
+// EMIT_MIR remove_storage_markers.pick.RemoveStorageMarkers.diff
+fn pick(flag: bool) -> u32 {
+    if flag {
+        let x = 5;
+        let r = &x;
+        *r
+    } else {
+        0
+    }
+}
+
+fn main() {
+    pick(true);
+}
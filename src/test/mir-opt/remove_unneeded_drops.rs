@@ -0,0 +1,14 @@
+// compile-flags: -Z mir-opt-level=2 -Z mir-enable-passes=+RemoveUnneededDrops#2
+// Tests that the drop of a generic value is removed once inlining shows that its type is `Copy`.
+
+#[inline]
+fn discard<T>(_value: T) {}
+
+// EMIT_MIR remove_unneeded_drops.copies.RemoveUnneededDrops.diff
+fn copies(x: u32) {
+    discard(x);
+}
+
+fn main() {
+    copies(1);
+}
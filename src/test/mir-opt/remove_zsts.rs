@@ -0,0 +1,14 @@
+// compile-flags: -Z mir-opt-level=0 -Z mir-enable-passes=+RemoveZsts
+// Tests that zero-sized assignments are removed, and zero-sized reads replaced with constants.
+
+fn take(_: ()) {}
+
+// EMIT_MIR remove_zsts.pass_unit.RemoveZsts.diff
+fn pass_unit() {
+    let unit = ();
+    take(unit);
+}
+
+fn main() {
+    pass_unit();
+}
@@ -0,0 +1,12 @@
+// compile-flags: -Z mir-opt-level=0 -Z mir-reorder-blocks
+// Tests that the first target of a `switchInt` without branch weights, and the block it jumps to,
+// are laid out right after it.
+
+// EMIT_MIR reorder_blocks.pick.ReorderBlocks.diff
+fn pick(flag: bool) -> u32 {
+    if flag { 1 } else { 2 }
+}
+
+fn main() {
+    pick(true);
+}
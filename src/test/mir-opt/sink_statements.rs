@@ -0,0 +1,12 @@
+// compile-flags: -Z mir-opt-level=0 -Z mir-enable-passes=+SinkStatements
+// Tests that an assignment whose result only one branch reads is moved into that branch.
+
+// EMIT_MIR sink_statements.one_side.SinkStatements.diff
+fn one_side(a: u32, b: u32, flag: bool) -> u32 {
+    let copy = a;
+    if flag { copy } else { b }
+}
+
+fn main() {
+    one_side(1, 2, true);
+}
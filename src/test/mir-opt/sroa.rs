@@ -0,0 +1,13 @@
+// compile-flags: -C overflow-checks=off -Z mir-opt-level=0
+// compile-flags: -Z mir-enable-passes=+StripDebugInfo,+ScalarReplacementOfAggregates
+// Tests that a tuple whose fields are only used one at a time is split into its fields.
+
+// EMIT_MIR sroa.fields.ScalarReplacementOfAggregates.diff
+fn fields(x: u32) -> u32 {
+    let pair = (x, 1_u32);
+    pair.0 + pair.1
+}
+
+fn main() {
+    fields(4);
+}
@@ -0,0 +1,11 @@
+// compile-flags: -Z mir-opt-level=0 -Z mir-enable-passes=+StrengthReduction
+// Tests that unsigned divisions and remainders by powers of two become shifts and masks.
+
+// EMIT_MIR strength_reduction.split.StrengthReduction.diff
+fn split(x: u32) -> (u32, u32) {
+    (x / 8, x % 8)
+}
+
+fn main() {
+    split(1);
+}
@@ -0,0 +1,18 @@
+// compile-flags: -C overflow-checks=off -Z mir-opt-level=0 -Z mir-enable-passes=+UnrollLoops
+// Tests that a loop running a constant number of times is copied once per iteration. The counter
+// is tested against a range pattern, whose comparisons read it directly rather than a copy.
+
+// EMIT_MIR unroll_loops.count_down.UnrollLoops.diff
+fn count_down(x: i32) -> i32 {
+    let mut total = 0;
+    let mut i = 2;
+    while let 0..=9 = i {
+        total += x;
+        i -= 1;
+    }
+    total
+}
+
+fn main() {
+    count_down(1);
+}
@@ -0,0 +1,12 @@
+#[allow(dead_code)]
+fn unused() {
+    println!("unused");
+}
+
+fn main() {
+    // MIR optimizations remove this block along with its counter.
+    if false {
+        println!("removed");
+    }
+    println!("used");
+}
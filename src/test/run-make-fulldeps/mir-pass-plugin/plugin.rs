@@ -0,0 +1,37 @@
+#![feature(rustc_private)]
+
+extern crate rustc_driver;
+extern crate rustc_middle;
+extern crate rustc_mir;
+
+use rustc_middle::mir::Body;
+use rustc_middle::ty::TyCtxt;
+use rustc_mir::transform::plugin::{PluginPass, PluginPassPosition};
+use rustc_mir::transform::{MirPass, MirSource, PassOutcome};
+use std::borrow::Cow;
+
+struct PrintBodies;
+
+impl<'tcx> MirPass<'tcx> for PrintBodies {
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed("PrintBodies")
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        source: MirSource<'tcx>,
+        _body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        eprintln!("PrintBodies ran on {}", tcx.def_path_str(source.def_id()));
+        PassOutcome::Unchanged
+    }
+}
+
+#[no_mangle]
+pub fn __rustc_mir_pass_plugin() -> Vec<PluginPass> {
+    vec![PluginPass {
+        position: PluginPassPosition::After("Inline".to_string()),
+        pass: Box::new(PrintBodies),
+    }]
+}
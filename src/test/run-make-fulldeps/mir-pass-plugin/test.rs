@@ -0,0 +1,5 @@
+fn main() {
+    foo();
+}
+
+fn foo() {}
@@ -0,0 +1,51 @@
+#![feature(rustc_private)]
+
+extern crate rustc_driver;
+extern crate rustc_middle;
+extern crate rustc_mir;
+
+use rustc_middle::mir::{Body, Field, Local, Place};
+use rustc_middle::ty::TyCtxt;
+use rustc_mir::transform::plugin::{PluginPass, PluginPassPosition};
+use rustc_mir::transform::{MirPass, MirSource, PassOutcome};
+use std::borrow::Cow;
+
+/// Points the debuginfo of `x` at a local past the last one, as if it had been removed, and the
+/// debuginfo of `pair` at its first field, with the wrong type.
+struct BreakDebugInfo;
+
+impl<'tcx> MirPass<'tcx> for BreakDebugInfo {
+    fn name(&self) -> Cow<'_, str> {
+        Cow::Borrowed("BreakDebugInfo")
+    }
+
+    fn run_pass(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _source: MirSource<'tcx>,
+        body: &mut Body<'tcx>,
+    ) -> PassOutcome {
+        let removed = Local::new(body.local_decls.len());
+        let mut changed = false;
+        for var_debug_info in &mut body.var_debug_info {
+            match &*var_debug_info.name.as_str() {
+                "x" => var_debug_info.place = Place::from(removed),
+                "pair" => {
+                    let pair = var_debug_info.place;
+                    var_debug_info.place = tcx.mk_place_field(pair, Field::new(0), tcx.types.bool);
+                }
+                _ => continue,
+            }
+            changed = true;
+        }
+        PassOutcome::changed_if(changed)
+    }
+}
+
+#[no_mangle]
+pub fn __rustc_mir_pass_plugin() -> Vec<PluginPass> {
+    vec![PluginPass {
+        position: PluginPassPosition::After("Inline".to_string()),
+        pass: Box::new(BreakDebugInfo),
+    }]
+}
@@ -0,0 +1,5 @@
+fn main() {
+    let x = 1u32;
+    let pair = (x, x);
+    drop(pair);
+}
@@ -14,5 +14,9 @@ fn main() {
         //~^ ERROR asm with `pure` option must have at least one output
         asm!("{}", out(reg) foo, options(noreturn));
         //~^ ERROR asm outputs are not allowed with the `noreturn` option
+        asm!("{}", out(reg) foo, label {});
+        //~^ ERROR asm outputs are not allowed with labels
+        asm!("{}", label {}, options(may_unwind));
+        //~^ ERROR asm labels are not allowed with the `may_unwind` option
     }
 }
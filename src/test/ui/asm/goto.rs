@@ -0,0 +1,51 @@
+// only-x86_64
+// run-pass
+// compile-flags: -Z validate-mir
+
+// `label` blocks of `asm!` run when the assembly jumps to them and continue after the `asm!`.
+
+#![feature(asm)]
+
+fn goto_fallthrough() -> bool {
+    let mut jumped = false;
+    unsafe {
+        asm!("/* {} */", label { jumped = true; });
+    }
+    jumped
+}
+
+fn goto_jump() -> bool {
+    let mut jumped = false;
+    unsafe {
+        asm!("jmp {}", label { jumped = true; });
+    }
+    jumped
+}
+
+fn goto_second(x: u32) -> u32 {
+    let mut which = 0;
+    unsafe {
+        asm!(
+            "cmp {0:e}, 1",
+            "je {1}",
+            "jmp {2}",
+            in(reg) x,
+            label { which = 1; },
+            label { which = 2; },
+        );
+    }
+    which
+}
+
+#[inline(always)]
+unsafe fn inlined() -> u32 {
+    asm!("jmp {}", label { return 1; }, options(noreturn));
+}
+
+fn main() {
+    assert!(!goto_fallthrough());
+    assert!(goto_jump());
+    assert_eq!(goto_second(1), 1);
+    assert_eq!(goto_second(7), 2);
+    assert_eq!(unsafe { inlined() }, 1);
+}
@@ -0,0 +1,42 @@
+// no-system-llvm
+// only-x86_64
+// run-pass
+
+// Unwinding out of `asm!` with `may_unwind` runs the destructors of the function it is in.
+
+#![feature(asm)]
+
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static DROPPED: AtomicBool = AtomicBool::new(false);
+
+struct Guard;
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        DROPPED.store(true, Ordering::SeqCst);
+    }
+}
+
+#[inline(never)]
+fn panics() {
+    panic!("unwinding out of asm!");
+}
+
+fn main() {
+    let result = panic::catch_unwind(|| unsafe {
+        let _guard = Guard;
+        asm!("call {}", sym panics,
+            out("rax") _, out("rcx") _, out("rdx") _, out("rdi") _, out("rsi") _,
+            out("r8") _, out("r9") _, out("r10") _, out("r11") _,
+            out("xmm0") _, out("xmm1") _, out("xmm2") _, out("xmm3") _,
+            out("xmm4") _, out("xmm5") _, out("xmm6") _, out("xmm7") _,
+            out("xmm8") _, out("xmm9") _, out("xmm10") _, out("xmm11") _,
+            out("xmm12") _, out("xmm13") _, out("xmm14") _, out("xmm15") _,
+            options(may_unwind),
+        );
+    });
+    assert!(result.is_err());
+    assert!(DROPPED.load(Ordering::SeqCst));
+}
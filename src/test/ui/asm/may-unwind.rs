@@ -0,0 +1,36 @@
+// only-x86_64
+// build-pass
+// compile-flags: -Z validate-mir
+
+// The unwind edge of `asm!` with `may_unwind` must survive MIR building, inlining and the
+// cleanup passes.
+
+#![feature(asm)]
+#![crate_type = "rlib"]
+
+struct Noisy;
+
+impl Drop for Noisy {
+    fn drop(&mut self) {}
+}
+
+#[inline(always)]
+unsafe fn inlined() {
+    let _guard = Noisy;
+    asm!("", options(may_unwind));
+}
+
+pub unsafe fn asm1() {
+    let _guard = Noisy;
+    asm!("", options(may_unwind));
+}
+
+pub unsafe fn asm2() {
+    let _guard = Noisy;
+    inlined();
+}
+
+pub unsafe fn asm3() -> ! {
+    let _guard = Noisy;
+    asm!("", options(noreturn, may_unwind));
+}
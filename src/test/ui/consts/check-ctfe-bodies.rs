@@ -0,0 +1,30 @@
+// compile-flags: -Z check-ctfe-bodies
+// Const fns without inputs and non-generic associated constants are evaluated eagerly, so the
+// errors in them are reported even though nothing uses them in a const context.
+
+#![crate_type = "lib"]
+
+const fn div(a: u32, b: u32) -> u32 {
+    a / b
+    //~^ ERROR evaluation of constant value failed
+    //~| ERROR any use of this value will cause an error
+}
+
+pub const fn broken() -> u32 {
+    div(1, 0)
+}
+
+pub struct S;
+
+impl S {
+    pub const BROKEN: u32 = div(2, 0);
+}
+
+// These need inputs, so they are left alone.
+pub const fn divide_by(b: u32) -> u32 {
+    div(1, b)
+}
+
+pub const fn generic<T>() -> u32 {
+    div(3, std::mem::size_of::<T>() as u32)
+}
@@ -0,0 +1,19 @@
+#![feature(const_panic)]
+#![crate_type = "lib"]
+
+const N: u32 = 42;
+
+pub const A: () = panic!("N is {}", N);
+//~^ ERROR any use of this value will cause an error
+
+pub const B: () = panic!("{:?} and {:?}", "cheese", 'c');
+//~^ ERROR any use of this value will cause an error
+
+pub const C: () = panic!("{}{} {}", -12i8, 'c', &false);
+//~^ ERROR any use of this value will cause an error
+
+pub const D: () = panic!("{:x}", N);
+//~^ ERROR any use of this value will cause an error
+
+pub const E: () = panic!("{:5}", N);
+//~^ ERROR any use of this value will cause an error
@@ -0,0 +1,16 @@
+// check-pass
+// Raw pointers that don't point into the const heap are fine in the final value of constants, even
+// when `const_allocate` is available.
+#![feature(core_intrinsics)]
+#![feature(const_heap)]
+#![allow(dead_code)]
+use std::ptr;
+
+struct Wrapper(*const u8);
+
+const P: *const u8 = ptr::null();
+const Q: *mut i32 = ptr::null_mut();
+const WRAPPED: Wrapper = Wrapper(ptr::null());
+const PAIR: (*const u8, u32) = (P, 4);
+
+fn main() {}
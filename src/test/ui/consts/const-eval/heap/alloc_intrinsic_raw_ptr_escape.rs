@@ -0,0 +1,9 @@
+#![feature(core_intrinsics)]
+#![feature(const_heap)]
+use std::intrinsics;
+
+const FOO: *mut u8 = unsafe { intrinsics::const_allocate(4, 4) };
+//~^ ERROR raw pointers into the const heap are not allowed in the final value of constants
+//~| ERROR untyped pointers to the const heap are not allowed in the final value
+
+fn main() {}
@@ -0,0 +1,43 @@
+#![feature(const_fn, const_fn_ptr_call, const_mut_refs)]
+
+const fn double(x: u32) -> u32 {
+    x * 2
+}
+
+const fn triple(x: u32) -> u32 {
+    x * 3
+}
+
+fn not_const(x: u32) -> u32 {
+    x
+}
+
+const REASSIGNED: u32 = {
+    let mut f: fn(u32) -> u32 = double;
+    f = triple;
+    f(1)
+    //~^ ERROR function pointers are not allowed in const fn
+};
+
+const NOT_CONST: u32 = {
+    let f: fn(u32) -> u32 = not_const;
+    f(1)
+    //~^ ERROR calls in constants are limited to constant functions
+};
+
+// The caller decides what an argument points to.
+const fn apply(f: fn(u32) -> u32) -> u32 {
+    f(1)
+    //~^ ERROR function pointers are not allowed in const fn
+}
+
+// A pointer that is borrowed mutably can be changed through the borrow.
+const fn overwritten() -> u32 {
+    let mut f: fn(u32) -> u32 = double;
+    let r = &mut f;
+    *r = triple;
+    f(1)
+    //~^ ERROR function pointers are not allowed in const fn
+}
+
+fn main() {}
@@ -0,0 +1,33 @@
+// run-pass
+
+#![feature(const_fn, const_fn_ptr_call)]
+
+const fn double(x: u32) -> u32 {
+    x * 2
+}
+
+// A pointer may be called more than once, and in a `const fn` as well.
+const fn quadruple(x: u32) -> u32 {
+    let f: fn(u32) -> u32 = double;
+    f(f(x))
+}
+
+const DOUBLED: u32 = {
+    let f: fn(u32) -> u32 = double;
+    let g = f;
+    g(21)
+};
+
+static TRIPLED: u32 = {
+    let f = double as fn(u32) -> u32;
+    f(7) + 7
+};
+
+const QUADRUPLED: u32 = quadruple(5);
+
+fn main() {
+    assert_eq!(DOUBLED, 42);
+    assert_eq!(TRIPLED, 21);
+    assert_eq!(QUADRUPLED, 20);
+    assert_eq!(quadruple(5), 20);
+}
@@ -0,0 +1,11 @@
+const fn double(x: u32) -> u32 {
+    x * 2
+}
+
+const DOUBLED: u32 = {
+    let f: fn(u32) -> u32 = double;
+    f(21)
+    //~^ ERROR function pointers are not allowed in const fn
+};
+
+fn main() {}
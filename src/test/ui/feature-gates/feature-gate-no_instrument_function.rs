@@ -0,0 +1,5 @@
+#![crate_type = "lib"]
+
+#[no_instrument_function]
+//~^ ERROR the `#[no_instrument_function]` attribute is an experimental feature
+pub fn foo() {}
@@ -0,0 +1,25 @@
+#![feature(core_intrinsics, rustc_attrs)]
+
+use std::intrinsics::rustc_peek;
+
+#[rustc_mir(rustc_peek_maybe_borrowed, stop_after_dataflow)]
+fn foo() -> i32 {
+    let x: i32 = 0;
+    let y: i32 = 1;
+
+    rustc_peek(x); //~ ERROR rustc_peek: bit not set
+
+    let p = &x;
+
+    // `x` is considered borrowed from here on, even after the last use of `p`...
+    rustc_peek(x);
+    let val = *p;
+    rustc_peek(x);
+
+    // ... while `y` was only ever copied.
+    rustc_peek(y); //~ ERROR rustc_peek: bit not set
+
+    val + y
+}
+
+fn main() {}
@@ -0,0 +1,29 @@
+#![feature(core_intrinsics, rustc_attrs)]
+
+use std::cell::Cell;
+use std::intrinsics::rustc_peek;
+
+#[rustc_mir(rustc_peek_indirectly_mutable, stop_after_dataflow)]
+fn foo() -> i32 {
+    let x: i32 = 0;
+    let mut y: i32 = 1;
+    let c = Cell::new(2);
+
+    // A shared borrow of a `Freeze` type does not allow mutation...
+    let shared = &x;
+    rustc_peek(x); //~ ERROR rustc_peek: bit not set
+
+    // ... but a mutable borrow does...
+    let unique = &mut y;
+    *unique += *shared;
+    rustc_peek(y);
+
+    // ... and so does a shared borrow of a type with interior mutability.
+    let cell = &c;
+    cell.set(3);
+    rustc_peek(c);
+
+    y
+}
+
+fn main() {}
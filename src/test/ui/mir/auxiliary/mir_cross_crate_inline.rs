@@ -0,0 +1,42 @@
+pub struct Size {
+    width: u32,
+    height: u32,
+}
+
+static SCALE: u32 = 3;
+
+impl Size {
+    pub fn new(width: u32, height: u32) -> Size {
+        Size { width, height }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn area(&self) -> u32 {
+        self.width * self.height
+    }
+
+    // Refers to a static that isn't exported.
+    pub fn scaled_width(&self) -> u32 {
+        self.width * SCALE
+    }
+
+    pub fn kind(&self) -> &'static str {
+        if self.width == self.height { "square" } else { "rectangle" }
+    }
+}
+
+pub fn add_one(value: u8) -> u8 {
+    value + 1
+}
+
+// Calls a function that isn't exported.
+pub fn doubled(value: u32) -> u32 {
+    double(value)
+}
+
+fn double(value: u32) -> u32 {
+    value * 2
+}
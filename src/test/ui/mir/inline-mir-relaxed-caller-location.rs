@@ -0,0 +1,24 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z inline-mir-relaxed
+// Check that the relaxed mode of MIR inlining still doesn't inline calls of `caller_location` into
+// `#[track_caller]` functions, where they would report the location of the outer caller.
+
+#![feature(core_intrinsics)]
+
+use std::intrinsics::caller_location;
+use std::panic::Location;
+
+#[inline]
+fn here() -> &'static Location<'static> {
+    caller_location()
+}
+
+#[track_caller]
+fn tracked() -> &'static Location<'static> {
+    here()
+}
+
+fn main() {
+    assert_eq!(here().line(), 13);
+    assert_eq!(tracked().line(), 13);
+}
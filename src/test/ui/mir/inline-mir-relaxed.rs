@@ -0,0 +1,25 @@
+// Tests that MIR inlining leaves callees with inline assembly or calls of `caller_location` alone
+// without `-Z inline-mir-relaxed`.
+
+// build-pass
+// compile-flags: -Z mir-opt-level=2 -Z remark-mir=Inline
+
+#![feature(core_intrinsics, llvm_asm)]
+
+use std::intrinsics::caller_location;
+use std::panic::Location;
+
+fn main() {
+    nop();
+    let _location = here();
+}
+
+#[inline]
+fn nop() {
+    unsafe { llvm_asm!("NOP") }
+}
+
+#[inline]
+fn here() -> &'static Location<'static> {
+    caller_location()
+}
@@ -0,0 +1,14 @@
+// Tests that `-Z inline-mir-hint-threshold` replaces the threshold of MIR inlining for functions
+// marked `#[inline]`.
+
+// build-pass
+// compile-flags: -Z mir-opt-level=2 -Z remark-mir=Inline -Z inline-mir-hint-threshold=4
+
+fn main() {
+    let _x = one();
+}
+
+#[inline]
+fn one() -> usize {
+    1
+}
@@ -0,0 +1,19 @@
+// run-pass
+// compile-flags: -Z lower-128bit-ops=yes -C overflow-checks=on
+// Check that `const fn`s whose 128-bit operators are lowered to calls still evaluate at compile
+// time, to the same values as at run time.
+
+#![feature(test)]
+
+const fn mix(a: u128, b: i128) -> (u128, i128, bool) {
+    let x = a * 3 + (a >> 7) - a % 5;
+    let y = b / -3 << 2;
+    (x, y, b < -1)
+}
+
+const MIXED: (u128, i128, bool) = mix(1 << 100, -1 << 90);
+
+fn main() {
+    let (a, b) = (std::hint::black_box(1 << 100), std::hint::black_box(-1 << 90));
+    assert_eq!(mix(a, b), MIXED);
+}
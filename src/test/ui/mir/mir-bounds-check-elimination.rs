@@ -0,0 +1,53 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// ignore-wasm32-bare compiled with panic=abort by default
+// Check that removing bounds checks implied by earlier comparisons keeps the ones that can fail.
+
+use std::panic;
+
+fn guarded(values: [u32; 4], i: usize) -> u32 {
+    if i < 4 { values[i] } else { 0 }
+}
+
+fn smaller_bound(values: [u32; 4], i: usize) -> u32 {
+    if i <= 2 { values[i] + values[i + 1] } else { 0 }
+}
+
+fn repeated(values: &[u32], i: usize) -> u32 {
+    let first = values[i];
+    first + values[i] * 2
+}
+
+fn counted(values: [u32; 4]) -> u32 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < 4 {
+        total += values[i];
+        i += 1;
+    }
+    total
+}
+
+fn changed_index(values: &[u32], mut i: usize) -> u32 {
+    let first = values[i];
+    i += 1;
+    first + values[i]
+}
+
+fn wrong_bound(values: [u32; 4], i: usize) -> u32 {
+    if i < 5 { values[i] } else { 0 }
+}
+
+fn main() {
+    assert_eq!(guarded([1, 2, 3, 4], 3), 4);
+    assert_eq!(guarded([1, 2, 3, 4], 4), 0);
+    assert_eq!(smaller_bound([1, 2, 3, 4], 2), 7);
+    assert_eq!(smaller_bound([1, 2, 3, 4], 3), 0);
+    assert_eq!(repeated(&[1, 2, 3], 1), 6);
+    assert_eq!(counted([1, 2, 3, 4]), 10);
+    assert_eq!(changed_index(&[1, 2, 3], 1), 5);
+
+    assert!(panic::catch_unwind(|| repeated(&[1, 2, 3], 3)).is_err());
+    assert!(panic::catch_unwind(|| changed_index(&[1, 2, 3], 2)).is_err());
+    assert!(panic::catch_unwind(|| wrong_bound([1, 2, 3, 4], 4)).is_err());
+}
@@ -0,0 +1,58 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that splitting critical edges and inserting loop preheaders keeps the paths through
+// matches and loops.
+
+fn shared_target(x: u32, flag: bool) -> u32 {
+    let mut y = x;
+    if flag {
+        y += 1;
+    }
+    match y {
+        0 => 10,
+        1 | 2 => 20,
+        _ => y * 2,
+    }
+}
+
+fn entered_twice(n: u32, skip: bool) -> u32 {
+    let mut i = if skip { 5 } else { 0 };
+    let mut total = 0;
+    while i < n {
+        total += i;
+        i += 1;
+    }
+    total
+}
+
+fn nested(rows: u32, columns: u32) -> u32 {
+    let mut total = 0;
+    let mut row = 0;
+    while row < rows {
+        let mut column = 0;
+        while column < columns {
+            if column == row {
+                total += 100;
+            } else {
+                total += 1;
+            }
+            column += 1;
+        }
+        row += 1;
+    }
+    total
+}
+
+fn main() {
+    assert_eq!(shared_target(0, false), 10);
+    assert_eq!(shared_target(0, true), 20);
+    assert_eq!(shared_target(2, false), 20);
+    assert_eq!(shared_target(4, true), 10);
+
+    assert_eq!(entered_twice(8, false), 28);
+    assert_eq!(entered_twice(8, true), 18);
+    assert_eq!(entered_twice(3, true), 0);
+
+    assert_eq!(nested(3, 4), 309);
+    assert_eq!(nested(0, 4), 0);
+}
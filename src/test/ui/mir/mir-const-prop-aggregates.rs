@@ -0,0 +1,73 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that propagating constants through the fields of aggregates reads back the value each
+// field was last assigned.
+
+#[derive(Clone, Copy)]
+struct Config {
+    width: u32,
+    height: u32,
+    depth: u8,
+}
+
+impl Config {
+    fn new() -> Config {
+        Config { width: 1, height: 1, depth: 8 }
+    }
+
+    fn width(mut self, width: u32) -> Config {
+        self.width = width;
+        self
+    }
+
+    fn height(mut self, height: u32) -> Config {
+        self.height = height;
+        self
+    }
+}
+
+fn built() -> u32 {
+    let config = Config::new().width(640).height(480);
+    config.width * config.height + config.depth as u32
+}
+
+fn field_by_field() -> u32 {
+    let mut config = Config::new();
+    config.width = 3;
+    config.height = 4;
+    config.width = config.width * config.height;
+    config.width + config.depth as u32
+}
+
+fn tuple(flag: bool) -> (u32, i64) {
+    let mut pair = (1, -1);
+    pair.0 = 10;
+    if flag {
+        pair.1 = 20;
+    }
+    (pair.0 + 1, pair.1 * 2)
+}
+
+fn nested() -> u64 {
+    let mut outer = ((1u64, 2u64), 3u64);
+    (outer.0).1 = 5;
+    outer.1 = (outer.0).0 + (outer.0).1;
+    let inner = outer.0;
+    inner.0 * 100 + inner.1 * 10 + outer.1
+}
+
+fn borrowed_field() -> u32 {
+    let mut pair = (1u32, 2u32);
+    let second = &mut pair.1;
+    *second = 7;
+    pair.0 + pair.1
+}
+
+fn main() {
+    assert_eq!(built(), 640 * 480 + 8);
+    assert_eq!(field_by_field(), 20);
+    assert_eq!(tuple(true), (11, 40));
+    assert_eq!(tuple(false), (11, -2));
+    assert_eq!(nested(), 156);
+    assert_eq!(borrowed_field(), 8);
+}
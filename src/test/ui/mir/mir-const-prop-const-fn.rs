@@ -0,0 +1,75 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// ignore-wasm32-bare compiled with panic=abort by default
+// Check that calls of `const fn`s with constant arguments evaluate to the same results when they
+// are folded during optimization, and still panic at runtime when their evaluation fails.
+
+use std::panic;
+
+fn scaled() -> u32 {
+    scale(6, 7) + scale(1, 0)
+}
+
+fn branches() -> (i32, i32, i32) {
+    (clamp(-5, 0, 10), clamp(5, 0, 10), clamp(15, 0, 10))
+}
+
+fn short_and_long_loops() -> (u64, u64) {
+    (sum_to(5), sum_to(10_000))
+}
+
+fn in_caller_flow(flag: bool) -> u32 {
+    let factor = if flag { 3 } else { 3 };
+    scale(factor, 5)
+}
+
+fn overflowing() -> u8 {
+    add(200, 100)
+}
+
+fn dividing(divisor: u32) -> u32 {
+    div(10, divisor)
+}
+
+const fn scale(a: u32, b: u32) -> u32 {
+    a * b + 1
+}
+
+const fn clamp(value: i32, min: i32, max: i32) -> i32 {
+    if value < min {
+        min
+    } else if value > max {
+        max
+    } else {
+        value
+    }
+}
+
+const fn sum_to(n: u64) -> u64 {
+    let mut sum = 0;
+    let mut i = 0;
+    while i <= n {
+        sum += i;
+        i += 1;
+    }
+    sum
+}
+
+const fn add(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+const fn div(a: u32, b: u32) -> u32 {
+    a / b
+}
+
+fn main() {
+    assert_eq!(scaled(), 44);
+    assert_eq!(branches(), (0, 5, 10));
+    assert_eq!(short_and_long_loops(), (15, 50_005_000));
+    assert_eq!(in_caller_flow(true), 16);
+    assert_eq!(in_caller_flow(false), 16);
+    assert_eq!(dividing(5), 2);
+    assert!(panic::catch_unwind(overflowing).is_err());
+    assert!(panic::catch_unwind(|| dividing(0)).is_err());
+}
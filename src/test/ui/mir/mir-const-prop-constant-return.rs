@@ -0,0 +1,85 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// ignore-wasm32-bare compiled with panic=abort by default
+// Check that calls of functions that always return the same constant evaluate to it when they are
+// folded during optimization, and that functions doing anything else are still called.
+
+use std::panic;
+
+trait Shape {
+    fn sides(&self) -> u32;
+    fn name(&self) -> &'static str;
+}
+
+struct Triangle;
+struct Square(u8);
+
+fn tags() -> (u32, &'static str, u32, &'static str) {
+    let (triangle, square) = (Triangle, Square(4));
+    (triangle.sides(), triangle.name(), square.sides(), square.name())
+}
+
+fn dynamic(shape: &dyn Shape) -> u32 {
+    shape.sides()
+}
+
+fn branches(value: i32) -> u64 {
+    same_on_both_sides(value) + u64::default()
+}
+
+fn side_effects() -> (u32, u32) {
+    let mut written = 0;
+    let returned = writes(&mut written);
+    (returned, written)
+}
+
+fn panics(flag: bool) -> u32 {
+    panics_if(flag)
+}
+
+impl Shape for Triangle {
+    fn sides(&self) -> u32 {
+        3
+    }
+
+    fn name(&self) -> &'static str {
+        "triangle"
+    }
+}
+
+impl Shape for Square {
+    fn sides(&self) -> u32 {
+        self.0 as u32
+    }
+
+    fn name(&self) -> &'static str {
+        "square"
+    }
+}
+
+fn same_on_both_sides(value: i32) -> u64 {
+    if value < 0 { 7 } else { 7 }
+}
+
+fn writes(place: &mut u32) -> u32 {
+    *place = 9;
+    1
+}
+
+fn panics_if(flag: bool) -> u32 {
+    if flag {
+        panic!("flag was set");
+    }
+    2
+}
+
+fn main() {
+    assert_eq!(tags(), (3, "triangle", 4, "square"));
+    assert_eq!(dynamic(&Triangle), 3);
+    assert_eq!(dynamic(&Square(5)), 5);
+    assert_eq!(branches(-1), 7);
+    assert_eq!(branches(1), 7);
+    assert_eq!(side_effects(), (1, 9));
+    assert_eq!(panics(false), 2);
+    assert!(panic::catch_unwind(|| panics(true)).is_err());
+}
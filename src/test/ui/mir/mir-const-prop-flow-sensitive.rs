@@ -0,0 +1,91 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that propagating the values of variables along edges joins the values of every path, and
+// that it doesn't lint operations with values from other blocks.
+
+fn same_on_both_paths(flag: bool) -> u32 {
+    let x;
+    if flag {
+        x = 2;
+    } else {
+        x = 2;
+    }
+    x * 3
+}
+
+fn different_paths(flag: bool) -> u32 {
+    let mut x = 2;
+    if flag {
+        x = 5;
+    }
+    x * 3
+}
+
+fn into_branch(flag: bool) -> u32 {
+    let x = 2;
+    let mut y = 1;
+    if flag {
+        y = x * 3;
+    }
+    y
+}
+
+fn changed_in_loop(n: u32) -> u32 {
+    let mut x = 1;
+    let mut i = 0;
+    while i < n {
+        x *= 2;
+        i += 1;
+    }
+    x
+}
+
+fn known_switch() -> u32 {
+    let x = 3;
+    match x {
+        1 => 10,
+        3 => 30,
+        _ => 0,
+    }
+}
+
+fn not_taken(flag: bool) -> u32 {
+    let mut x = 1;
+    let y = 0;
+    if y == 0 {
+        x = 4;
+    }
+    if flag { x } else { x + 1 }
+}
+
+#[allow(unused_assignments)]
+fn overwritten_by_call(flag: bool) -> u32 {
+    let mut x = 7;
+    if flag {
+        x = std::convert::identity(8);
+    }
+    x
+}
+
+// The subtraction would overflow, but only runs when `flag` is set.
+fn guarded(flag: bool) -> u8 {
+    let x = 0u8;
+    if flag { x - 1 } else { x }
+}
+
+fn main() {
+    assert_eq!(same_on_both_paths(true), 6);
+    assert_eq!(same_on_both_paths(false), 6);
+    assert_eq!(different_paths(true), 15);
+    assert_eq!(different_paths(false), 6);
+    assert_eq!(into_branch(true), 6);
+    assert_eq!(into_branch(false), 1);
+    assert_eq!(changed_in_loop(0), 1);
+    assert_eq!(changed_in_loop(4), 16);
+    assert_eq!(known_switch(), 30);
+    assert_eq!(not_taken(true), 4);
+    assert_eq!(not_taken(false), 5);
+    assert_eq!(overwritten_by_call(true), 8);
+    assert_eq!(overwritten_by_call(false), 7);
+    assert_eq!(guarded(false), 0);
+}
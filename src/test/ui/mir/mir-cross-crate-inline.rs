@@ -0,0 +1,22 @@
+// run-pass
+// aux-build:mir_cross_crate_inline.rs
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// ignore-wasm32-bare compiled with panic=abort by default
+// Check that small functions of other crates that aren't generic or `#[inline]` behave the same
+// when their MIR is exported and inlined, including the ones referring to private items.
+
+extern crate mir_cross_crate_inline as aux;
+
+use std::panic;
+
+fn main() {
+    let size = aux::Size::new(4, 5);
+    assert_eq!(size.width(), 4);
+    assert_eq!(size.area(), 20);
+    assert_eq!(size.scaled_width(), 12);
+    assert_eq!(size.kind(), "rectangle");
+    assert_eq!(aux::Size::new(2, 2).kind(), "square");
+    assert_eq!(aux::add_one(41), 42);
+    assert_eq!(aux::doubled(21), 42);
+    assert!(panic::catch_unwind(|| aux::add_one(u8::MAX)).is_err());
+}
@@ -0,0 +1,44 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that removing dead stores keeps the stores that are read, including through references
+// and by destructors.
+
+#![allow(unused_assignments)]
+
+use std::cell::Cell;
+
+struct Noisy<'a>(&'a Cell<u32>, u32);
+
+impl Drop for Noisy<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + self.1);
+    }
+}
+
+fn overwritten(x: u32) -> u32 {
+    let mut y = x * 2;
+    y = x + 1;
+    y
+}
+
+fn through_reference(x: u32) -> u32 {
+    let mut y = 0;
+    let r = &mut y;
+    *r = x;
+    y = y + 1;
+    y
+}
+
+fn dropped(counter: &Cell<u32>) {
+    let mut noisy = Noisy(counter, 1);
+    noisy = Noisy(counter, 10);
+    noisy.1 = 100;
+}
+
+fn main() {
+    assert_eq!(overwritten(4), 5);
+    assert_eq!(through_reference(4), 5);
+    let counter = Cell::new(0);
+    dropped(&counter);
+    assert_eq!(counter.get(), 101);
+}
@@ -0,0 +1,40 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that merging identical blocks keeps the paths through them apart where they differ.
+
+struct Noisy<'a>(&'a mut u32, u32);
+
+impl Drop for Noisy<'_> {
+    fn drop(&mut self) {
+        *self.0 += self.1;
+    }
+}
+
+fn drops(x: u8, counter: &mut u32) -> u8 {
+    let _guard = Noisy(counter, 1);
+    match x {
+        0 => 10,
+        1 => 20,
+        2 => 10,
+        _ => return x,
+    }
+}
+
+fn arms(x: u8) -> u32 {
+    match x {
+        0 | 3 => 7,
+        1 => 7,
+        2 => 9,
+        _ => 7,
+    }
+}
+
+fn main() {
+    let mut counter = 0;
+    assert_eq!(drops(0, &mut counter), 10);
+    assert_eq!(drops(1, &mut counter), 20);
+    assert_eq!(drops(2, &mut counter), 10);
+    assert_eq!(drops(9, &mut counter), 9);
+    assert_eq!(counter, 4);
+    assert_eq!((arms(0), arms(1), arms(2), arms(3), arms(4)), (7, 7, 9, 7, 7));
+}
@@ -0,0 +1,36 @@
+// run-pass
+// compile-flags: -Z validate-mir
+// Check that merging identical promoted constants keeps the ones that differ apart.
+
+macro_rules! table {
+    () => {
+        &[1u32, 2, 3]
+    };
+}
+
+fn first_two() -> (&'static [u32; 3], &'static [u32; 3]) {
+    (table!(), table!())
+}
+
+fn mixed(i: usize) -> u32 {
+    let a: &'static [u32; 3] = table!();
+    let b: &'static [u32; 3] = &[4, 5, 6];
+    let c: &'static [u32; 3] = table!();
+    a[i] + b[i] * 10 + c[i] * 100
+}
+
+fn generic<T: Default + PartialEq>() -> bool {
+    let a: &'static Option<u8> = &None;
+    let b: &'static Option<u8> = &Some(0);
+    let c: &'static Option<u8> = &None;
+    a.is_none() && b.is_some() && c == a && T::default() == T::default()
+}
+
+fn main() {
+    let (a, b) = first_two();
+    assert_eq!(a, &[1, 2, 3]);
+    assert_eq!(b, &[1, 2, 3]);
+    assert_eq!(mixed(0), 1 + 40 + 100);
+    assert_eq!(mixed(2), 3 + 60 + 300);
+    assert!(generic::<u64>());
+}
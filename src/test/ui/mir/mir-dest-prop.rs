@@ -0,0 +1,37 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that merging the source and the destination of copies keeps the values that are still
+// needed apart.
+
+#[inline(never)]
+fn make(seed: u8) -> [u8; 64] {
+    [seed; 64]
+}
+
+fn returned(seed: u8) -> [u8; 64] {
+    let buf = make(seed);
+    buf
+}
+
+fn swapped(a: [u8; 64], b: [u8; 64]) -> ([u8; 64], [u8; 64]) {
+    let mut x = a;
+    let mut y = b;
+    let tmp = x;
+    x = y;
+    y = tmp;
+    (x, y)
+}
+
+fn overlapping(seed: u8) -> u8 {
+    let first = make(seed);
+    let second = first;
+    let third = make(seed + 1);
+    second[0] + third[0] + first[1]
+}
+
+fn main() {
+    assert_eq!(returned(3)[63], 3);
+    let (x, y) = swapped([1; 64], [2; 64]);
+    assert_eq!((x[0], y[0]), (2, 1));
+    assert_eq!(overlapping(5), 5 + 6 + 5);
+}
@@ -0,0 +1,61 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that calling methods of trait objects whose type is known calls the right methods.
+
+trait Shape {
+    fn area(&self) -> u32;
+    fn scale(&mut self, factor: u32);
+}
+
+struct Square(u32);
+struct Rect(u32, u32);
+
+impl Shape for Square {
+    fn area(&self) -> u32 {
+        self.0 * self.0
+    }
+    fn scale(&mut self, factor: u32) {
+        self.0 *= factor;
+    }
+}
+
+impl Shape for Rect {
+    fn area(&self) -> u32 {
+        self.0 * self.1
+    }
+    fn scale(&mut self, factor: u32) {
+        self.0 *= factor;
+    }
+}
+
+#[inline]
+fn area_of(shape: &dyn Shape) -> u32 {
+    shape.area()
+}
+
+fn known(side: u32) -> u32 {
+    let square = Square(side);
+    let shape: &dyn Shape = &square;
+    shape.area() + area_of(&Rect(side, 2))
+}
+
+fn scaled(side: u32) -> u32 {
+    let mut rect = Rect(side, 3);
+    let shape: &mut dyn Shape = &mut rect;
+    shape.scale(2);
+    rect.area()
+}
+
+fn unknown(flag: bool) -> u32 {
+    let square = Square(3);
+    let rect = Rect(2, 5);
+    let shape: &dyn Shape = if flag { &square } else { &rect };
+    shape.area()
+}
+
+fn main() {
+    assert_eq!(known(4), 16 + 8);
+    assert_eq!(scaled(5), 30);
+    assert_eq!(unknown(true), 9);
+    assert_eq!(unknown(false), 10);
+}
@@ -0,0 +1,67 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that folding switches on discriminants that were just set picks the right arms.
+
+#[derive(Clone, Copy)]
+enum Shape {
+    Circle(u32),
+    Square(u32),
+    Empty,
+}
+
+#[repr(i8)]
+enum Signed {
+    Low = -3,
+    High = 7,
+}
+
+fn area(radius: u32) -> u32 {
+    let shape = Shape::Circle(radius);
+    match shape {
+        Shape::Circle(r) => 3 * r * r,
+        Shape::Square(side) => side * side,
+        Shape::Empty => 0,
+    }
+}
+
+fn reassigned(side: u32, flag: bool) -> u32 {
+    let mut shape = Shape::Square(side);
+    if flag {
+        shape = Shape::Empty;
+    }
+    match shape {
+        Shape::Circle(r) => r,
+        Shape::Square(side) => side * 4,
+        Shape::Empty => 1,
+    }
+}
+
+fn option(x: u32) -> u32 {
+    match Some(x).map(|x| x + 1) {
+        Some(y) => y,
+        None => 0,
+    }
+}
+
+fn signed(high: bool) -> i8 {
+    let value = if high { Signed::High } else { Signed::Low };
+    match value {
+        Signed::Low => -1,
+        Signed::High => 1,
+    }
+}
+
+fn explicit() -> i32 {
+    match Signed::Low {
+        Signed::Low => 10,
+        Signed::High => 20,
+    }
+}
+
+fn main() {
+    assert_eq!(area(2), 12);
+    assert_eq!((reassigned(3, false), reassigned(3, true)), (12, 1));
+    assert_eq!(option(4), 5);
+    assert_eq!((signed(true), signed(false)), (1, -1));
+    assert_eq!(explicit(), 10);
+}
@@ -0,0 +1,53 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that merging the switches on the discriminants of two enums keeps every arm reachable.
+
+enum Shape {
+    Dot,
+    Line(u8),
+    Square(u8, u8),
+}
+
+fn both_some(a: Option<u8>, b: Option<u8>) -> u8 {
+    match (a, b) {
+        (Some(x), Some(y)) => x + y,
+        (None, None) => 1,
+        _ => 0,
+    }
+}
+
+fn same_shape(a: &Shape, b: &Shape) -> bool {
+    match (a, b) {
+        (Shape::Dot, Shape::Dot) => true,
+        (Shape::Line(x), Shape::Line(y)) => x == y,
+        (Shape::Square(x, _), Shape::Square(y, _)) => x == y,
+        _ => false,
+    }
+}
+
+fn results(a: Result<u8, u8>, b: Result<u8, u8>) -> u8 {
+    match (a, b) {
+        (Ok(x), Ok(y)) => x * y,
+        (Err(_), Ok(_)) => 2,
+        _ => 3,
+    }
+}
+
+fn main() {
+    assert_eq!(both_some(Some(2), Some(3)), 5);
+    assert_eq!(both_some(None, None), 1);
+    assert_eq!(both_some(Some(2), None), 0);
+    assert_eq!(both_some(None, Some(3)), 0);
+
+    assert!(same_shape(&Shape::Dot, &Shape::Dot));
+    assert!(same_shape(&Shape::Line(4), &Shape::Line(4)));
+    assert!(!same_shape(&Shape::Line(4), &Shape::Line(5)));
+    assert!(same_shape(&Shape::Square(1, 2), &Shape::Square(1, 3)));
+    assert!(!same_shape(&Shape::Dot, &Shape::Line(0)));
+    assert!(!same_shape(&Shape::Square(0, 0), &Shape::Dot));
+
+    assert_eq!(results(Ok(2), Ok(4)), 8);
+    assert_eq!(results(Err(0), Ok(4)), 2);
+    assert_eq!(results(Err(0), Err(4)), 3);
+    assert_eq!(results(Ok(1), Err(4)), 3);
+}
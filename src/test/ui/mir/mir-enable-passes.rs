@@ -0,0 +1,13 @@
+// build-pass
+// compile-flags: -Z mir-enable-passes=+ConstProp,-ConstProp
+// Check that `-Z mir-enable-passes` can turn passes off, and that the last mention of a pass wins.
+
+// `ConstProp` would report the overflow below as an error.
+fn overflow() -> u8 {
+    let x: u8 = 255;
+    x + 1
+}
+
+fn main() {
+    overflow();
+}
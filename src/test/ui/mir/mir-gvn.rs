@@ -0,0 +1,50 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that reusing values computed earlier keeps the results of the program.
+
+#[derive(Clone, Copy)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+enum Shape {
+    Circle(Point, i32),
+    Segment(Point, Point),
+}
+
+fn sum_ends(values: &mut [u32]) -> u32 {
+    let first = values[0];
+    values[0] = 10;
+    let last = values[values.len() - 1];
+    first + last + values[0] + values[values.len() - 1]
+}
+
+// The lowest and the highest corner of the bounding box.
+fn bounds(shape: &Shape) -> (i32, i32, i32, i32) {
+    match *shape {
+        Shape::Circle(center, radius) => {
+            (center.x - radius, center.y - radius, center.x + radius, center.y + radius)
+        }
+        Shape::Segment(from, to) => {
+            (from.x.min(to.x), from.y.min(to.y), from.x.max(to.x), from.y.max(to.y))
+        }
+    }
+}
+
+fn mixed(a: u8, b: u64) -> u64 {
+    let mut total = a as u64 + b;
+    for i in 0..3 {
+        total += (a as u64) * i + (a as u64 + b);
+    }
+    total
+}
+
+fn main() {
+    let mut values = [1, 2, 3, 4];
+    assert_eq!(sum_ends(&mut values), 1 + 4 + 10 + 4);
+    assert_eq!(bounds(&Shape::Circle(Point { x: 3, y: 0 }, 2)), (1, -2, 5, 2));
+    let segment = Shape::Segment(Point { x: 7, y: 1 }, Point { x: -2, y: 4 });
+    assert_eq!(bounds(&segment), (-2, 1, 7, 4));
+    assert_eq!(mixed(2, 5), 7 + (0 + 7) + (2 + 7) + (4 + 7));
+}
@@ -0,0 +1,57 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=0 -Z validate-mir
+// ignore-wasm32-bare compiled with panic=abort by default
+// Check that inlining `#[inline(always)]` callees with trivial bodies without optimizations keeps
+// the behavior of the calls, including their overflow checks, and leaves the other callees alone.
+
+#![feature(core_intrinsics)]
+
+use std::intrinsics;
+use std::panic;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Vector {
+    x: i32,
+    y: i32,
+}
+
+fn sum(a: Vector, b: Vector) -> Vector {
+    add(a, b)
+}
+
+fn wrapped(a: u32, b: u32) -> (u32, u32, u32) {
+    (minimum(a, b), wrapping_double(a), sum_to(b))
+}
+
+#[inline(always)]
+fn add(a: Vector, b: Vector) -> Vector {
+    Vector { x: a.x + b.x, y: a.y + b.y }
+}
+
+#[inline(always)]
+fn minimum(a: u32, b: u32) -> u32 {
+    if a < b { a } else { b }
+}
+
+#[inline(always)]
+fn wrapping_double(a: u32) -> u32 {
+    intrinsics::wrapping_mul(a, 2)
+}
+
+// Not trivial, because of the loop.
+#[inline(always)]
+fn sum_to(n: u32) -> u32 {
+    let mut sum = 0;
+    for i in 0..=n {
+        sum += i;
+    }
+    sum
+}
+
+fn main() {
+    let a = Vector { x: 1, y: -2 };
+    assert_eq!(sum(a, Vector { x: 3, y: 4 }), Vector { x: 4, y: 2 });
+    assert_eq!(wrapped(7, 4), (4, 14, 10));
+    assert_eq!(wrapped(u32::MAX, 0), (0, u32::MAX - 1, 0));
+    assert!(panic::catch_unwind(|| sum(a, Vector { x: i32::MAX, y: 0 })).is_err());
+}
@@ -0,0 +1,66 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z mir-insert-assumes -Z validate-mir
+// ignore-wasm32-bare compiled with panic=abort by default
+// Check that assuming the facts proven by assertions and discriminant reads keeps the behavior
+// of the checks and matches they come from.
+
+use std::panic;
+
+#[allow(dead_code)]
+enum Shape {
+    Point,
+    Circle(u32),
+    Rect(u16, u16),
+}
+
+#[repr(i8)]
+#[derive(Clone, Copy)]
+enum Sign {
+    Negative = -1,
+    Zero = 0,
+    Positive = 1,
+}
+
+fn area(shape: &Shape) -> u32 {
+    match *shape {
+        Shape::Point => 0,
+        Shape::Circle(r) => 3 * r * r,
+        Shape::Rect(w, h) => w as u32 * h as u32,
+    }
+}
+
+fn flip(sign: Sign) -> Sign {
+    match sign {
+        Sign::Negative => Sign::Positive,
+        Sign::Zero => Sign::Zero,
+        Sign::Positive => Sign::Negative,
+    }
+}
+
+fn sum_pairs(values: &[u32], indices: &[usize]) -> u32 {
+    let mut sum = 0;
+    for &i in indices {
+        sum += values[i] + values[i / 2];
+    }
+    sum
+}
+
+fn divide(a: i32, b: i32) -> i32 {
+    a / b
+}
+
+fn main() {
+    let shapes = [Shape::Point, Shape::Circle(2), Shape::Rect(3, 4)];
+    assert_eq!(shapes.iter().map(area).sum::<u32>(), 24);
+    assert_eq!(flip(Sign::Negative) as i8, 1);
+    assert_eq!(flip(Sign::Zero) as i8, 0);
+    assert_eq!(flip(Sign::Positive) as i8, -1);
+    assert_eq!(Some(3).map_or(0, |x| x + 1), 4);
+
+    let values = [1, 2, 3, 4, 5];
+    assert_eq!(sum_pairs(&values, &[0, 2, 4]), 15);
+    assert!(panic::catch_unwind(|| sum_pairs(&values, &[1, 5])).is_err());
+    assert_eq!(divide(7, 2), 3);
+    assert!(panic::catch_unwind(|| divide(1, 0)).is_err());
+    assert!(panic::catch_unwind(|| divide(i32::MIN, -1)).is_err());
+}
@@ -0,0 +1,44 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that branching straight to the targets known on each edge keeps the results.
+
+#[inline]
+fn is_small(x: u32) -> bool {
+    let small = x < 10;
+    if small { true } else { false }
+}
+
+fn classify(x: u32) -> &'static str {
+    let flag = if x % 2 == 0 { true } else { false };
+    if flag {
+        if is_small(x) { "small even" } else { "even" }
+    } else if flag || is_small(x) {
+        "small odd"
+    } else {
+        "odd"
+    }
+}
+
+fn count(limit: u8) -> u32 {
+    let mut total = 0;
+    let mut state = 0u8;
+    for i in 0..limit {
+        match state {
+            0 => state = 1,
+            1 => state = 2,
+            _ => state = 0,
+        }
+        if state == 2 {
+            total += i as u32;
+        }
+    }
+    total
+}
+
+fn main() {
+    assert_eq!(classify(4), "small even");
+    assert_eq!(classify(40), "even");
+    assert_eq!(classify(5), "small odd");
+    assert_eq!(classify(51), "odd");
+    assert_eq!(count(7), 1 + 4);
+}
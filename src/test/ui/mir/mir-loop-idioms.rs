@@ -0,0 +1,71 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// ignore-wasm32-bare compiled with panic=abort by default
+// Check that fill and copy loops replaced with intrinsic calls write the same elements, and still
+// panic at the first element out of bounds.
+
+use std::panic;
+
+fn fill(dst: &mut [u32], n: usize) {
+    let mut i = 0;
+    while i < n {
+        dst[i] = 0xabababab;
+        i += 1;
+    }
+}
+
+fn copy(dst: &mut [u16], src: &[u16], n: usize) {
+    let mut i = 0;
+    while i < n {
+        dst[i] = src[i];
+        i += 1;
+    }
+}
+
+fn copy_array(src: [u8; 4]) -> [u8; 4] {
+    let mut dst = [0; 4];
+    let mut i = 0;
+    while i < 4 {
+        dst[i] = src[i];
+        i += 1;
+    }
+    dst
+}
+
+fn fill_returning_counter(dst: &mut [bool], n: usize) -> usize {
+    let mut i = 0;
+    while i < n {
+        dst[i] = true;
+        i += 1;
+    }
+    i
+}
+
+fn main() {
+    let mut words = [1; 5];
+    fill(&mut words, 3);
+    assert_eq!(words, [0xabababab, 0xabababab, 0xabababab, 1, 1]);
+    fill(&mut words, 0);
+    assert_eq!(words[3], 1);
+
+    let mut halves = [0; 4];
+    copy(&mut halves, &[1, 2, 3, 4], 3);
+    assert_eq!(halves, [1, 2, 3, 0]);
+
+    assert_eq!(copy_array([5, 6, 7, 8]), [5, 6, 7, 8]);
+
+    let mut flags = [false; 3];
+    assert_eq!(fill_returning_counter(&mut flags, 2), 2);
+    assert_eq!(flags, [true, true, false]);
+
+    // Too many elements: the loop fills what it can before panicking.
+    let mut short = [1; 2];
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| fill(&mut short, 3)));
+    assert!(result.is_err());
+    assert_eq!(short, [0xabababab, 0xabababab]);
+
+    let mut short = [0; 2];
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| copy(&mut short, &[9], 2)));
+    assert!(result.is_err());
+    assert_eq!(short, [9, 0]);
+}
@@ -0,0 +1,105 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// ignore-wasm32-bare compiled with panic=abort by default
+// Check that moving loop-invariant computations out of loops keeps the values they compute, and
+// doesn't move reads out of the checks guarding them.
+
+use std::cell::Cell;
+use std::panic;
+
+fn scaled_sum(values: &[u32], a: u32, b: u32) -> u32 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < values.len() {
+        let scale = a * b + 1;
+        total += values[i] * scale;
+        i += 1;
+    }
+    total
+}
+
+fn nested(rows: u32, columns: u32, offset: u32) -> u32 {
+    let mut total = 0;
+    let mut row = 0;
+    while row < rows {
+        let mut column = 0;
+        while column < columns {
+            let base = offset * 3;
+            let row_base = base + row;
+            total += row_base + column;
+            column += 1;
+        }
+        row += 1;
+    }
+    total
+}
+
+fn conditional(n: u32, a: u32) -> u32 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < n {
+        if i % 2 == 0 {
+            let double = a + a;
+            total += double;
+        }
+        i += 1;
+    }
+    total
+}
+
+fn changing(mut a: u32, n: u32) -> u32 {
+    let mut total = 0;
+    for _ in 0..n {
+        let next = a + 1;
+        total += next;
+        a = next;
+    }
+    total
+}
+
+fn shared_cell(cell: &Cell<u32>, n: u32) -> u32 {
+    let mut total = 0;
+    for _ in 0..n {
+        let value = cell.get();
+        total += value;
+        cell.set(value + 1);
+    }
+    total
+}
+
+fn indexed(values: &[u32], index: usize, n: u32) -> u32 {
+    let array = [1, 2, 3];
+    let mut total = 0;
+    let mut i = 0;
+    while i < n {
+        total += values[index] + array[index];
+        i += 1;
+    }
+    total
+}
+
+fn variant_field(value: &Option<u32>, n: u32) -> u32 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < n {
+        if let Some(x) = *value {
+            total += x;
+        }
+        i += 1;
+    }
+    total
+}
+
+fn main() {
+    assert_eq!(scaled_sum(&[1, 2, 3], 2, 3), 6 * 7);
+    assert_eq!(scaled_sum(&[], 2, 3), 0);
+    assert_eq!(nested(2, 3, 1), (3 + 4 + 5) + (4 + 5 + 6));
+    assert_eq!(conditional(5, 4), 3 * 8);
+    assert_eq!(changing(0, 4), 1 + 2 + 3 + 4);
+    assert_eq!(shared_cell(&Cell::new(1), 3), 1 + 2 + 3);
+    assert_eq!(indexed(&[5, 6], 1, 2), 2 * (6 + 2));
+    assert_eq!(indexed(&[5, 6], 1_000_000, 0), 0);
+    assert!(panic::catch_unwind(|| indexed(&[5, 6], 1_000_000, 1)).is_err());
+    assert_eq!(variant_field(&Some(3), 2), 6);
+    assert_eq!(variant_field(&None, 2), 0);
+}
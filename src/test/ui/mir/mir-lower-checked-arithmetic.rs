@@ -0,0 +1,40 @@
+// run-pass
+// compile-flags: -C overflow-checks=off -Z mir-opt-level=2 -Z validate-mir
+// Check that the inlined operators of the standard library wrap without overflow checks.
+
+use std::ops::{Add, Mul, Neg, Shl, Sub};
+
+fn add<T: Add<Output = T>>(a: T, b: T) -> T {
+    a + b
+}
+
+fn sub<T: Sub<Output = T>>(a: T, b: T) -> T {
+    a - b
+}
+
+fn mul<T: Mul<Output = T>>(a: T, b: T) -> T {
+    a * b
+}
+
+fn neg<T: Neg<Output = T>>(a: T) -> T {
+    -a
+}
+
+fn shl<T: Shl<u32, Output = T>>(a: T, b: u32) -> T {
+    a << b
+}
+
+fn checked_add(a: u8, b: u8) -> (u8, bool) {
+    a.overflowing_add(b)
+}
+
+fn main() {
+    assert_eq!(add(u8::MAX, 2), 1);
+    assert_eq!(add(i32::MAX, 1), i32::MIN);
+    assert_eq!(sub(0u16, 1), u16::MAX);
+    assert_eq!(mul(i64::MAX, 2), -2);
+    assert_eq!(neg(i8::MIN), i8::MIN);
+    assert_eq!(shl(1u32, 33), 2);
+    assert_eq!(checked_add(200, 100), (44, true));
+    assert_eq!(checked_add(1, 2), (3, false));
+}
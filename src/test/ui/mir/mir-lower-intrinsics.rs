@@ -0,0 +1,60 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that lowering calls to simple intrinsics keeps their results.
+
+#![feature(variant_count)]
+
+use std::any::{self, TypeId};
+use std::mem;
+
+#[allow(dead_code)]
+enum Shape {
+    Point,
+    Circle(u32),
+    Rect(u16, u16),
+}
+
+struct Counted<'a>(&'a mut u32);
+
+impl Drop for Counted<'_> {
+    fn drop(&mut self) {
+        *self.0 += 1;
+    }
+}
+
+fn sizes<T>() -> (usize, usize) {
+    (mem::size_of::<T>(), mem::align_of::<T>())
+}
+
+fn describe<T: 'static>() -> (bool, &'static str, TypeId, bool) {
+    let zero_sized = if mem::size_of::<T>() == 0 { true } else { false };
+    (mem::needs_drop::<T>(), any::type_name::<T>(), TypeId::of::<T>(), zero_sized)
+}
+
+fn same_variant<T>(a: &T, b: &T) -> bool {
+    mem::discriminant(a) == mem::discriminant(b)
+}
+
+fn main() {
+    assert_eq!((mem::size_of::<u64>(), mem::align_of::<u64>()), sizes::<u64>());
+    assert_eq!(sizes::<[u16; 3]>(), (6, 2));
+    assert_eq!(sizes::<()>(), (0, 1));
+
+    assert_eq!(describe::<u32>(), (false, "u32", TypeId::of::<u32>(), false));
+    assert_eq!(describe::<()>(), (false, "()", TypeId::of::<()>(), true));
+    let (needs_drop, name, _, zero_sized) = describe::<String>();
+    assert!(needs_drop && !zero_sized);
+    assert_eq!(name, "alloc::string::String");
+    assert_eq!(mem::variant_count::<Shape>(), 3);
+    assert_eq!(mem::variant_count::<Option<u8>>(), 2);
+
+    assert!(same_variant(&Shape::Circle(1), &Shape::Circle(2)));
+    assert!(!same_variant(&Shape::Circle(1), &Shape::Rect(1, 2)));
+    assert!(mem::discriminant(&Shape::Point) != mem::discriminant(&Shape::Rect(3, 4)));
+    assert!(same_variant(&1u8, &2u8));
+
+    let mut drops = 0;
+    mem::forget(Counted(&mut drops));
+    drop(Counted(&mut drops));
+    assert_eq!(drops, 1);
+}
@@ -0,0 +1,46 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that replacing switches that only pick between constants computes the same values.
+
+#[derive(Clone, Copy)]
+enum Level {
+    Low,
+    Mid,
+    High,
+}
+
+fn is_zero(x: u8) -> bool {
+    if x == 0 { true } else { false }
+}
+
+fn not(x: bool) -> bool {
+    if x { false } else { true }
+}
+
+fn same(x: bool) -> bool {
+    if x { true } else { false }
+}
+
+fn rank(level: Level) -> u32 {
+    match level {
+        Level::Low => 0,
+        Level::Mid => 1,
+        Level::High => 2,
+    }
+}
+
+fn shifted(level: Level) -> u8 {
+    match level {
+        Level::Low => 1,
+        Level::Mid => 2,
+        Level::High => 3,
+    }
+}
+
+fn main() {
+    assert_eq!((is_zero(0), is_zero(7)), (true, false));
+    assert_eq!((not(true), not(false)), (false, true));
+    assert_eq!((same(true), same(false)), (true, false));
+    assert_eq!((rank(Level::Low), rank(Level::Mid), rank(Level::High)), (0, 1, 2));
+    assert_eq!((shifted(Level::Low), shifted(Level::Mid), shifted(Level::High)), (1, 2, 3));
+}
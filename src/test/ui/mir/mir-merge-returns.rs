@@ -0,0 +1,51 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that merging the returns of a body keeps the value every path returns.
+
+struct Pair {
+    first: u64,
+    second: u64,
+}
+
+fn early(x: u32) -> u32 {
+    if x == 0 {
+        return 1;
+    }
+    if x > 10 {
+        return x - 10;
+    }
+    x * 2
+}
+
+#[inline]
+fn classify(x: i32) -> &'static str {
+    match x {
+        0 => "zero",
+        x if x < 0 => "negative",
+        _ => "positive",
+    }
+}
+
+fn inlined(x: i32, y: i32) -> usize {
+    classify(x).len() + classify(y).len()
+}
+
+fn named(flag: bool, value: u64) -> Pair {
+    let mut pair = Pair { first: value, second: 0 };
+    if flag {
+        pair.second = value + 1;
+        return pair;
+    }
+    pair.first += 2;
+    pair
+}
+
+fn main() {
+    assert_eq!((early(0), early(15), early(4)), (1, 5, 8));
+    assert_eq!(inlined(-3, 7), "negative".len() + "positive".len());
+    assert_eq!(inlined(0, 0), 8);
+    let pair = named(true, 4);
+    assert_eq!((pair.first, pair.second), (4, 5));
+    let pair = named(false, 4);
+    assert_eq!((pair.first, pair.second), (6, 0));
+}
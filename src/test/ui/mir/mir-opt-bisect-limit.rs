@@ -0,0 +1,6 @@
+// build-pass
+// compile-flags: -Z mir-opt-bisect-limit=1 -Z mir-opt-level=1 -Z mir-strip-debuginfo=yes
+// Check that `-Z mir-opt-bisect-limit` numbers the runs of MIR optimization passes, and prints
+// the last one within the limit.
+
+fn main() {}
@@ -0,0 +1,23 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=3 -Z mir-opt-stop-after=ConstProp -Z validate-mir
+// Check that cutting the optimizations short still leaves MIR that codegen can handle.
+
+#[derive(Debug, PartialEq)]
+enum Shape {
+    Square(u32),
+    Rect(u32, u32),
+}
+
+fn area(shape: &Shape) -> u32 {
+    match *shape {
+        Shape::Square(side) => side * side,
+        Shape::Rect(width, height) => width * height,
+    }
+}
+
+fn main() {
+    let shapes = vec![Shape::Square(3), Shape::Rect(2, 5)];
+    let total: u32 = shapes.iter().map(area).sum();
+    assert_eq!(total, 19);
+    assert_eq!(shapes[0], Shape::Square(3));
+}
@@ -0,0 +1,75 @@
+// run-pass
+// compile-flags: -Z mir-outline-cold-blocks -Z validate-mir
+// ignore-wasm32-bare compiled with panic=abort by default
+// Check that outlined panicking blocks see and change the locals of the function they were
+// moved out of, and that unwinding out of them still drops what it has to.
+
+use std::cell::Cell;
+use std::panic;
+
+thread_local!(static DROPS: Cell<u32> = Cell::new(0));
+
+struct Guard(u32);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        DROPS.with(|drops| drops.set(drops.get() + self.0));
+    }
+}
+
+fn drops() -> u32 {
+    DROPS.with(|drops| drops.replace(0))
+}
+
+// A `Copy` local that the panicking blocks only read is passed by value.
+fn checked_half(x: u32) -> u32 {
+    if x % 2 != 0 {
+        panic!("{} is odd, {} and {} are not", x, x - 1, x + 1);
+    }
+    x / 2
+}
+
+// The names are changed and borrowed by the panicking blocks, so they get a pointer to them.
+fn lookup(names: &mut Vec<String>, index: usize) -> usize {
+    let _guard = Guard(1);
+    if index >= names.len() {
+        names.push(format!("missing {}", index));
+        panic!("{} names, {:?}", names.len(), names);
+    }
+    names[index].len()
+}
+
+// Two separate panicking paths become two outlined functions.
+fn pick(values: &[u32], first: usize, second: usize) -> u32 {
+    let guard = Guard(10);
+    if first >= values.len() {
+        panic!("first index {} out of {}", first, values.len());
+    }
+    let total = values[first] + guard.0;
+    if second >= values.len() {
+        drop(guard);
+        panic!("second index {} out of {}", second, values.len());
+    }
+    total + values[second]
+}
+
+fn main() {
+    assert_eq!(checked_half(8), 4);
+    assert!(panic::catch_unwind(|| checked_half(7)).is_err());
+
+    let mut names = vec![String::from("a"), String::from("bcd")];
+    assert_eq!(lookup(&mut names, 1), 3);
+    assert_eq!(drops(), 1);
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| lookup(&mut names, 5)));
+    assert!(result.is_err());
+    assert_eq!(names.last().map(String::as_str), Some("missing 5"));
+    assert_eq!(drops(), 1);
+
+    let values = [1, 2, 3];
+    assert_eq!(pick(&values, 0, 2), 14);
+    assert_eq!(drops(), 10);
+    assert!(panic::catch_unwind(|| pick(&values, 3, 0)).is_err());
+    assert_eq!(drops(), 10);
+    assert!(panic::catch_unwind(|| pick(&values, 0, 3)).is_err());
+    assert_eq!(drops(), 10);
+}
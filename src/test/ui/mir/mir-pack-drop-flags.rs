@@ -0,0 +1,94 @@
+// run-pass
+// compile-flags: -Z validate-mir
+// Check that packing the drop flags of functions with many of them drops every value once.
+
+use std::cell::Cell;
+
+struct Counted<'a>(&'a Cell<u32>, u32);
+
+impl Drop for Counted<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + self.1);
+    }
+}
+
+macro_rules! maybe_moved {
+    ($drops:ident, $mask:ident, $sink:ident; $($bit:literal)*) => {
+        $(
+            let value = Counted($drops, 1 << ($bit % 16));
+            if $mask & (1 << ($bit % 64)) != 0 {
+                $sink.push(value);
+            }
+        )*
+    };
+}
+
+fn few_words(drops: &Cell<u32>, mask: u64) -> usize {
+    let mut sink = Vec::new();
+    maybe_moved!(drops, mask, sink; 0 1 2 3 4 5 6 7 8 9 10 11);
+    let kept = sink.len();
+    std::mem::forget(sink);
+    kept
+}
+
+fn two_words(drops: &Cell<u32>, mask: u64) -> usize {
+    let mut sink = Vec::new();
+    maybe_moved!(
+        drops, mask, sink;
+        0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31
+        32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59
+        60 61 62 63 64 65 66 67 68 69
+    );
+    let kept = sink.len();
+    std::mem::forget(sink);
+    kept
+}
+
+fn moved_in_loop(drops: &Cell<u32>, rounds: u32) {
+    let mut a = Some(Counted(drops, 1));
+    let mut b = Some(Counted(drops, 2));
+    let c = Counted(drops, 4);
+    let d = Counted(drops, 8);
+    let e = Counted(drops, 16);
+    let f = Counted(drops, 32);
+    let g = Counted(drops, 64);
+    let h = Counted(drops, 128);
+    let i = Counted(drops, 256);
+    let j = Counted(drops, 512);
+    for round in 0..rounds {
+        if round == 1 {
+            drop(a.take());
+        } else if round == 2 {
+            b = None;
+        }
+    }
+    if rounds > 3 {
+        drop((c, e, g, i, j));
+    } else {
+        drop((d, f, h));
+    }
+    drop(a);
+    drop(b);
+}
+
+fn main() {
+    let drops = Cell::new(0);
+    assert_eq!(few_words(&drops, 0), 0);
+    assert_eq!(drops.replace(0), (1 << 12) - 1);
+    assert_eq!(few_words(&drops, 0b1010_0101_0011), 6);
+    assert_eq!(drops.replace(0), !0b1010_0101_0011 & ((1 << 12) - 1));
+
+    let expected_all: u32 = (0..70).map(|bit| 1u32 << (bit % 16)).sum();
+    assert_eq!(two_words(&drops, 0), 0);
+    assert_eq!(drops.replace(0), expected_all);
+    let mask = 0x8000_0000_0000_00f1;
+    let kept: u32 =
+        (0..70).filter(|bit| mask & (1 << (bit % 64)) != 0).map(|bit| 1 << (bit % 16)).sum();
+    assert_eq!(two_words(&drops, mask), 9);
+    assert_eq!(drops.replace(0), expected_all - kept);
+
+    moved_in_loop(&drops, 2);
+    assert_eq!(drops.replace(0), 1023);
+    moved_in_loop(&drops, 5);
+    assert_eq!(drops.replace(0), 1023);
+}
@@ -0,0 +1,7 @@
+// build-pass
+// compile-flags: -Z fuel=foo=0 -Z mir-opt-level=1 -Z mir-strip-debuginfo=yes
+// Check that MIR optimization passes use up `-Z fuel`, and that the first one to run out says so.
+
+#![crate_name = "foo"]
+
+fn main() {}
@@ -0,0 +1,40 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that removing storage markers keeps the values of locals that are used across them.
+
+fn scalars(n: u32) -> u32 {
+    let mut total = 0;
+    for i in 0..n {
+        let square = i * i;
+        total += square;
+    }
+    total
+}
+
+fn borrowed(n: u32) -> u32 {
+    let mut total = 0;
+    for i in 0..n {
+        let value = i + 1;
+        let reference = &value;
+        total += *reference;
+    }
+    total
+}
+
+fn scoped(x: u64) -> u64 {
+    let first = {
+        let temp = [x, x + 1];
+        temp[0] + temp[1]
+    };
+    let second = {
+        let temp = [x * 2, x * 3];
+        temp[0] * temp[1]
+    };
+    first + second
+}
+
+fn main() {
+    assert_eq!(scalars(4), 0 + 1 + 4 + 9);
+    assert_eq!(borrowed(4), 1 + 2 + 3 + 4);
+    assert_eq!(scoped(2), 5 + 24);
+}
@@ -0,0 +1,41 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that removing drops of values without drop glue keeps the drops that are needed.
+
+use std::cell::Cell;
+
+struct Counted<'a>(&'a Cell<u32>);
+
+impl Drop for Counted<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+#[inline]
+fn replace<T>(slot: &mut T, value: T) {
+    *slot = value;
+}
+
+#[inline]
+fn discard<T>(_value: T) {}
+
+fn copies(x: u32) -> u32 {
+    let mut slot = x;
+    replace(&mut slot, x + 1);
+    discard(slot);
+    slot
+}
+
+fn counted(count: &Cell<u32>) {
+    let mut slot = Counted(count);
+    replace(&mut slot, Counted(count));
+    discard(slot);
+}
+
+fn main() {
+    assert_eq!(copies(4), 5);
+    let count = Cell::new(0);
+    counted(&count);
+    assert_eq!(count.get(), 2);
+}
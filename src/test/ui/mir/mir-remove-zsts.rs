@@ -0,0 +1,62 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that removing zero-sized assignments and reads keeps the effects of the code around them.
+
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+struct Tagged<T> {
+    value: u32,
+    tag: PhantomData<T>,
+}
+
+struct Token;
+
+struct Bell;
+
+static RINGS: AtomicU32 = AtomicU32::new(0);
+
+impl Drop for Bell {
+    fn drop(&mut self) {
+        RINGS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+const UNIT: () = ();
+
+fn tagged<T>(value: u32) -> Tagged<T> {
+    Tagged { value, tag: PhantomData }
+}
+
+fn touch(counter: &Cell<u32>) {
+    counter.set(counter.get() * 2);
+}
+
+fn consume(token: Token, value: u32) -> u32 {
+    let Token = token;
+    value + 1
+}
+
+fn main() {
+    let counter = Cell::new(1);
+    let units: Vec<()> = (0..4).map(|_| touch(&counter)).collect();
+    assert_eq!(units.len(), 4);
+    assert_eq!(counter.get(), 16);
+
+    let tagged = tagged::<String>(7);
+    assert_eq!(tagged.value, 7);
+    let token = Token;
+    assert_eq!(consume(token, tagged.value), 8);
+
+    let bell = Bell;
+    let moved = bell;
+    drop(moved);
+    assert_eq!(RINGS.load(Ordering::Relaxed), 1);
+    {
+        let bells = [Bell, Bell];
+        let _kept = (bells, [(); 3], UNIT);
+        assert_eq!(RINGS.load(Ordering::Relaxed), 1);
+    }
+    assert_eq!(RINGS.load(Ordering::Relaxed), 3);
+}
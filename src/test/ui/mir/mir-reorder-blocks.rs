@@ -0,0 +1,56 @@
+// run-pass
+// compile-flags: -Z mir-reorder-blocks -Z mir-branch-weights -Z validate-mir
+// ignore-wasm32-bare compiled with panic=abort by default
+// Check that laying out the blocks along their likely successors keeps every path working.
+
+#![feature(core_intrinsics)]
+
+use std::intrinsics::{likely, unlikely};
+use std::panic;
+
+#[cold]
+fn report(code: u32) -> u32 {
+    code + 1000
+}
+
+fn expected(x: u32) -> u32 {
+    if unsafe { likely(x < 10) } { x * 2 } else { x + 1 }
+}
+
+fn unexpected(x: u32) -> u32 {
+    if unsafe { unlikely(x < 10) } { x * 2 } else { x + 1 }
+}
+
+fn with_cold_call(x: u32) -> u32 {
+    if x > 10 { report(x) } else { x * 3 }
+}
+
+fn looping(values: &[u32]) -> u32 {
+    let mut total = 0;
+    for &value in values {
+        if value == 0 {
+            break;
+        }
+        total += if value % 2 == 0 { value } else { report(value) };
+    }
+    total
+}
+
+fn with_drops(x: usize) -> usize {
+    let names = vec![String::from("a"), String::from("bb")];
+    if x == 0 {
+        panic!("no names");
+    }
+    names[x - 1].len()
+}
+
+fn main() {
+    assert_eq!((expected(4), expected(12)), (8, 13));
+    assert_eq!((unexpected(4), unexpected(12)), (8, 13));
+    assert_eq!((with_cold_call(4), with_cold_call(11)), (12, 1011));
+    assert_eq!(looping(&[2, 3, 4, 0, 6]), 2 + 1003 + 4);
+    assert_eq!(with_drops(2), 2);
+
+    assert!(panic::catch_unwind(|| with_drops(0)).is_err());
+    assert!(panic::catch_unwind(|| with_drops(3)).is_err());
+}
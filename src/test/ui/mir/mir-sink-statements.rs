@@ -0,0 +1,40 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that moving computations into the branch that uses them keeps the values they read.
+
+fn one_side(a: u32, b: u32, flag: bool) -> u32 {
+    let product = a * b;
+    let sum = product + a;
+    if flag { sum } else { b }
+}
+
+fn overwritten(mut a: u32, flag: bool) -> u32 {
+    let before = a + 1;
+    a = 10;
+    if flag { before + a } else { a }
+}
+
+fn both_sides(a: u32, flag: bool) -> u32 {
+    let double = a * 2;
+    if flag { double + 1 } else { double - 1 }
+}
+
+fn in_loop(n: u32) -> u32 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < n {
+        let square = i * i;
+        if i % 2 == 0 {
+            total += square;
+        }
+        i += 1;
+    }
+    total
+}
+
+fn main() {
+    assert_eq!((one_side(3, 4, true), one_side(3, 4, false)), (15, 4));
+    assert_eq!((overwritten(5, true), overwritten(5, false)), (16, 10));
+    assert_eq!((both_sides(5, true), both_sides(5, false)), (11, 9));
+    assert_eq!(in_loop(5), 0 + 4 + 16);
+}
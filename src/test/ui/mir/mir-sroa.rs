@@ -0,0 +1,36 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that splitting aggregates into their fields keeps the values of the fields apart.
+
+#[derive(Clone, Copy)]
+struct Pair {
+    a: u32,
+    b: (u8, bool),
+}
+
+fn fields(x: u32) -> u32 {
+    let mut pair = Pair { a: x, b: (1, true) };
+    pair.a += 2;
+    (pair.b).0 += 3;
+    if (pair.b).1 { pair.a + (pair.b).0 as u32 } else { 0 }
+}
+
+fn partly_borrowed(x: u32) -> u32 {
+    let mut pair = (x, x + 1);
+    let first = &mut pair.0;
+    *first *= 10;
+    pair.0 + pair.1
+}
+
+fn moved_whole(x: u32) -> Pair {
+    let mut pair = Pair { a: x, b: (0, false) };
+    pair.b.1 = true;
+    pair
+}
+
+fn main() {
+    assert_eq!(fields(5), 7 + 4);
+    assert_eq!(partly_borrowed(2), 20 + 3);
+    let pair = moved_whole(8);
+    assert_eq!((pair.a, pair.b.0, pair.b.1), (8, 0, true));
+}
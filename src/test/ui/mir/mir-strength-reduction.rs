@@ -0,0 +1,43 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that replacing divisions by powers of two with shifts and masks keeps their results.
+
+const BLOCK: u64 = 4096;
+
+fn through_local(x: u32) -> (u32, u32) {
+    let divisor = 8;
+    (x / divisor, x % divisor)
+}
+
+fn through_const(offset: u64) -> (u64, u64) {
+    (offset / BLOCK, offset % BLOCK)
+}
+
+fn one(x: u8) -> (u8, u8) {
+    let divisor = 1;
+    (x / divisor, x % divisor)
+}
+
+fn top_bit(x: u8) -> (u8, u8) {
+    let divisor = 128;
+    (x / divisor, x % divisor)
+}
+
+fn signed(x: i32) -> (i32, i32) {
+    let divisor = 4;
+    (x / divisor, x % divisor)
+}
+
+fn not_power(x: usize) -> (usize, usize) {
+    let divisor = 6;
+    (x / divisor, x % divisor)
+}
+
+fn main() {
+    assert_eq!(through_local(29), (3, 5));
+    assert_eq!(through_const(10_000), (2, 1808));
+    assert_eq!(one(200), (200, 0));
+    assert_eq!(top_bit(200), (1, 72));
+    assert_eq!(signed(-7), (-1, -3));
+    assert_eq!(not_power(20), (3, 2));
+}
@@ -0,0 +1,90 @@
+// run-pass
+// compile-flags: -Z mir-opt-level=2 -Z validate-mir
+// Check that unrolling loops with a constant trip count runs every iteration exactly once.
+
+fn counted(values: [u32; 3]) -> u32 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < 3 {
+        total = total * 10 + values[i];
+        i += 1;
+    }
+    total
+}
+
+fn counting_down(start: i32) -> i32 {
+    let mut total = start;
+    let mut i = 2;
+    while i >= -1 {
+        total = total * 2 + i;
+        i -= 1;
+    }
+    total
+}
+
+fn never_runs(x: u32) -> u32 {
+    let mut total = x;
+    let mut i = 5;
+    while i < 5 {
+        total += i;
+        i += 1;
+    }
+    total
+}
+
+fn early_exit(values: &[u32]) -> Option<usize> {
+    let mut i = 0;
+    while i < 4 {
+        if i < values.len() && values[i] == 0 {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+fn with_continue(x: u32) -> u32 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < 4 {
+        i += 1;
+        if i == 2 {
+            continue;
+        }
+        total += x * i;
+    }
+    total
+}
+
+fn ranges(x: u64) -> u64 {
+    let mut total = 0;
+    for i in 0..2 {
+        total += x << i;
+    }
+    total
+}
+
+fn nested(x: u32) -> u32 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < 2 {
+        let mut j = 0;
+        while j < 3 {
+            total += x + i * 3 + j;
+            j += 1;
+        }
+        i += 1;
+    }
+    total
+}
+
+fn main() {
+    assert_eq!(counted([1, 2, 3]), 123);
+    assert_eq!(counting_down(1), ((((1 * 2 + 2) * 2 + 1) * 2 + 0) * 2) - 1);
+    assert_eq!(never_runs(7), 7);
+    assert_eq!(early_exit(&[1, 0, 2]), Some(1));
+    assert_eq!(early_exit(&[1, 2]), None);
+    assert_eq!(with_continue(2), 2 + 6 + 8);
+    assert_eq!(ranges(3), 3 + 6);
+    assert_eq!(nested(1), (0..6).map(|k| 1 + k).sum());
+}
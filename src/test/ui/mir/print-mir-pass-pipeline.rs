@@ -0,0 +1,9 @@
+// check-pass
+// compile-flags: -Z print-mir-pass-pipeline -Z mir-opt-level=2 -Z mir-strip-debuginfo=yes
+// compile-flags: -Z mir-enable-passes=+UnreachablePropagation,-ConstProp
+// compile-flags: -Z lower-128bit-ops=no -Z mir-reaggregate=no -Z mir-max-block-statements=0
+// compile-flags: -C overflow-checks=on
+// Check the pipeline `-Z print-mir-pass-pipeline` prints. The target-dependent passes are turned
+// off, so that the output is the same everywhere.
+
+fn main() {}
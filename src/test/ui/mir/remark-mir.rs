@@ -0,0 +1,14 @@
+// Tests the remarks of `-Z remark-mir`.
+
+// build-pass
+// compile-flags: -Z mir-opt-level=2 -Z remark-mir=all
+
+fn main() {
+    let a = [1, 2, 3];
+    let _x = a[one()];
+}
+
+#[inline]
+fn one() -> usize {
+    1
+}
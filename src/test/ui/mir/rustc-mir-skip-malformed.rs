@@ -0,0 +1,8 @@
+#![feature(rustc_attrs)]
+
+#[rustc_mir(skip)] //~ ERROR `skip` expects a comma-separated list of MIR pass names
+fn foo() {}
+
+fn main() {
+    foo();
+}
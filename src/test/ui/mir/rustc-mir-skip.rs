@@ -0,0 +1,15 @@
+// build-pass
+// Check that `#[rustc_mir(skip = "...")]` keeps the named passes from running on a body.
+
+#![feature(rustc_attrs)]
+
+// `ConstProp` would report the overflow below as an error.
+#[rustc_mir(skip = "ConstProp")]
+fn overflow() -> u8 {
+    let x: u8 = 255;
+    x + 1
+}
+
+fn main() {
+    overflow();
+}
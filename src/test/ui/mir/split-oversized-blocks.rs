@@ -0,0 +1,18 @@
+// run-pass
+// compile-flags: -Z mir-max-block-statements=1 -Z validate-mir
+// Check that splitting MIR blocks down to single statements preserves behavior.
+
+#[derive(Clone, Debug, PartialEq)]
+struct Big {
+    a: u32,
+    b: [u8; 4],
+    c: (u16, bool),
+    d: Option<String>,
+}
+
+fn main() {
+    let big = Big { a: 1, b: [2, 3, 4, 5], c: (6, true), d: Some(String::from("seven")) };
+    let copy = big.clone();
+    assert_eq!(big, copy);
+    assert_eq!(copy.b.iter().map(|&x| x as u32).sum::<u32>() + copy.a + copy.c.0 as u32, 21);
+}
@@ -0,0 +1,26 @@
+// run-pass
+// compile-flags: -Z mir-stack-slot-hints
+
+// Check that computing stack slot hints copes with borrows, moves and disjoint scopes.
+
+fn consume(v: Vec<u32>) -> usize {
+    v.len()
+}
+
+fn main() {
+    let mut total = 0;
+    {
+        let a = vec![1, 2, 3];
+        let r = &a;
+        total += r.len();
+    }
+    {
+        let b = vec![4, 5];
+        total += consume(b);
+    }
+    for i in 0..3 {
+        let c = [i; 4];
+        total += c.iter().sum::<usize>();
+    }
+    assert_eq!(total, 17);
+}
@@ -4,7 +4,8 @@
 
 use std::mem::size_of;
 
-// compile-flags: -Z fuel=foo=0
+// MIR passes use fuel as well, so they are turned off.
+// compile-flags: -Z fuel=foo=0 -Z mir-opt-level=0
 
 struct S1(u8, u16, u8);
 struct S2(u8, u16, u8);
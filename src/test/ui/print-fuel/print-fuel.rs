@@ -2,7 +2,7 @@
 #![allow(dead_code)]
 
 // (#55495: The --error-format is to sidestep an issue in our test harness)
-// compile-flags: --error-format human -Z print-fuel=foo
+// compile-flags: --error-format human -Z print-fuel=foo -Z mir-opt-level=0
 // build-pass (FIXME(62277): could be check-pass?)
 
 struct S1(u8, u16, u8);